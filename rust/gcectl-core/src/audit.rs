@@ -0,0 +1,282 @@
+//! Local append-only log of the changes gcectl asked the API to make.
+//!
+//! Each write request adds a `request` entry and each operation gcectl
+//! waited on adds an `operation` entry with its outcome, one JSON object per
+//! line in `audit.jsonl` under the config directory.
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::config::Config;
+use crate::logging::REDACTED;
+use crate::output::{Details, Render};
+
+const AUDIT_FILE_NAME: &str = "audit.jsonl";
+
+// flags whose KEY=VALUE values may hold secrets; the keys are kept
+const SECRET_FLAGS: &[&str] = &["--metadata"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Event {
+    // a write was sent
+    Request,
+    // an operation gcectl waited on finished
+    Operation,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    pub time: DateTime<Utc>,
+    pub event: Event,
+    pub user: String,
+    // the gcectl arguments that led to the change, secrets redacted
+    pub command: String,
+    // HTTP method for requests, operation type for operations
+    pub action: String,
+    // resource path below the API root, e.g. `projects/p/zones/z/instances/web/stop`
+    pub target: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    // zone or region
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation: Option<String>,
+    pub result: String,
+}
+
+impl Entry {
+    /// An entry stamped with the current time, user, and command line.
+    pub fn new(event: Event, action: &str, target: &str, result: String) -> Self {
+        let (project, location) = scope_of(target);
+        Self {
+            time: Utc::now(),
+            event,
+            user: env::var("USER")
+                .or_else(|_| env::var("USERNAME"))
+                .unwrap_or_default(),
+            command: redact_args(env::args().skip(1)),
+            action: action.to_string(),
+            target: target.to_string(),
+            project,
+            location,
+            operation: None,
+            result,
+        }
+    }
+
+    pub fn operation(mut self, name: Option<&str>) -> Self {
+        self.operation = name.map(str::to_string);
+        self
+    }
+
+    /// Target without the `projects/{project}/{zones|regions}/{location}/`
+    /// prefix the Project and Location fields already carry.
+    fn short_target(&self) -> &str {
+        let mut rest = self.target.as_str();
+        for key in ["projects/", "zones/", "regions/"] {
+            if let Some(tail) = rest.strip_prefix(key) {
+                rest = tail.split_once('/').map_or(tail, |(_, after)| after);
+            }
+        }
+        rest.strip_prefix("global/").unwrap_or(rest)
+    }
+}
+
+/// `args` joined with the values of [`SECRET_FLAGS`] and everything after a
+/// bare `--`, such as a remote command, redacted.
+fn redact_args(args: impl Iterator<Item = String>) -> String {
+    let mut redacted = Vec::new();
+    let mut secret_next = false;
+    let mut passthrough = false;
+    for arg in args {
+        let arg = if passthrough {
+            REDACTED.to_string()
+        } else if std::mem::take(&mut secret_next) {
+            redact_value(&arg)
+        } else if arg == "--" {
+            passthrough = true;
+            arg
+        } else if let Some((flag, value)) = arg.split_once('=')
+            && SECRET_FLAGS.contains(&flag)
+        {
+            format!("{flag}={}", redact_value(value))
+        } else {
+            secret_next = SECRET_FLAGS.contains(&arg.as_str());
+            arg
+        };
+        redacted.push(arg);
+    }
+    redacted.join(" ")
+}
+
+// `KEY=VALUE` with the value replaced
+fn redact_value(value: &str) -> String {
+    match value.split_once('=') {
+        Some((key, _)) => format!("{key}={REDACTED}"),
+        None => REDACTED.to_string(),
+    }
+}
+
+/// Project and zone or region named in a resource path or URL.
+fn scope_of(target: &str) -> (Option<String>, Option<String>) {
+    let segments: Vec<&str> = target.split(['/', '?']).collect();
+    let after = |key: &str| {
+        segments
+            .windows(2)
+            .find(|w| w[0] == key)
+            .map(|w| w[1].to_string())
+    };
+    (
+        after("projects"),
+        after("zones").or_else(|| after("regions")),
+    )
+}
+
+pub fn path() -> Result<PathBuf> {
+    Ok(Config::dir()?.join(AUDIT_FILE_NAME))
+}
+
+/// Appends `entry` to the log. A failure to write must not fail the change
+/// it describes, so it is only warned about.
+pub fn record(entry: &Entry) {
+    if let Err(err) = path().and_then(|path| append(&path, entry)) {
+        warn!("cannot write the audit log: {err:#}");
+    }
+}
+
+fn append(path: &Path, entry: &Entry) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Every entry in the log, oldest first; a missing log is empty.
+pub fn read() -> Result<Vec<Entry>> {
+    read_from(&path()?)
+}
+
+fn read_from(path: &Path) -> Result<Vec<Entry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let body =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    // a line torn by a crash mid-write should not hide the rest
+    Ok(body
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+impl Render for Entry {
+    fn headers() -> Vec<&'static str> {
+        vec!["Time", "User", "Action", "Target", "Result", "Command"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.time.with_timezone(&Local).format("%F %T").to_string(),
+            self.user.clone(),
+            self.action.clone(),
+            self.short_target().to_string(),
+            self.result.clone(),
+            self.command.clone(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Time", self.time.to_rfc3339())
+            .field("User", &self.user)
+            .field("Command", &self.command)
+            .field("Action", &self.action)
+            .field("Target", &self.target)
+            .field_opt("Project", self.project.as_deref())
+            .field_opt("Location", self.location.as_deref())
+            .field_opt("Operation", self.operation.as_deref())
+            .field("Result", &self.result);
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scope_from_paths() {
+        assert_eq!(
+            scope_of("projects/p/zones/us-central1-a/instances/web/stop"),
+            (Some("p".into()), Some("us-central1-a".into()))
+        );
+        assert_eq!(
+            scope_of("projects/p/global/firewalls/allow-ssh"),
+            (Some("p".into()), None)
+        );
+    }
+
+    #[test]
+    fn redacts_metadata_values_and_passthrough_args() {
+        let args = [
+            "instances",
+            "add-metadata",
+            "web",
+            "--metadata",
+            "db-password=hunter2",
+            "--metadata=api-key=abc",
+            "--zone",
+            "z",
+            "--",
+            "mysql",
+            "-phunter2",
+        ];
+        assert_eq!(
+            redact_args(args.into_iter().map(String::from)),
+            "instances add-metadata web --metadata db-password=[REDACTED] \
+             --metadata=api-key=[REDACTED] --zone z -- [REDACTED] [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn appends_and_skips_torn_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(AUDIT_FILE_NAME);
+        let entry = Entry::new(
+            Event::Request,
+            "POST",
+            "projects/p/zones/z/instances/web/stop",
+            "requested".into(),
+        )
+        .operation(Some("operation-1"));
+        append(&path, &entry).unwrap();
+        fs::write(
+            &path,
+            format!("{}{{\"time\":\n", fs::read_to_string(&path).unwrap()),
+        )
+        .unwrap();
+        append(&path, &entry).unwrap();
+
+        let entries = read_from(&path).unwrap();
+        assert_eq!(entries, [entry.clone(), entry.clone()]);
+        assert_eq!(entry.short_target(), "instances/web/stop");
+        assert_eq!(entry.location.as_deref(), Some("z"));
+    }
+}
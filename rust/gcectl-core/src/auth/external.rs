@@ -0,0 +1,438 @@
+//! External account credentials, for workload identity federation.
+//!
+//! A federated workload holds no Google key. It proves who it is with a
+//! token from its own platform, either an OIDC ID token read from a file or
+//! a URL, or a signed AWS `GetCallerIdentity` request, and Google's Security
+//! Token Service exchanges that subject token for an access token. The
+//! credential file, as written by
+//! `gcloud iam workload-identity-pools create-cred-config`, says where the
+//! subject token comes from and which service account, if any, the
+//! federated identity impersonates.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use super::CLOUD_PLATFORM_SCOPE;
+use crate::transport::Transport;
+use crate::util::hex;
+
+const STS_TOKEN_URL: &str = "https://sts.googleapis.com/v1/token";
+const TOKEN_EXCHANGE_GRANT: &str = "urn:ietf:params:oauth:grant-type:token-exchange";
+const ACCESS_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:access_token";
+// the impersonation URL ends in `serviceAccounts/{email}:generateAccessToken`
+const IMPERSONATION_SUFFIX: &str = ":generateAccessToken";
+
+/// The `external_account` credential file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExternalAccount {
+    // the workload identity pool provider
+    pub audience: String,
+    pub subject_token_type: String,
+    #[serde(default = "default_token_url")]
+    pub token_url: String,
+    #[serde(default)]
+    pub service_account_impersonation_url: Option<String>,
+    pub credential_source: CredentialSource,
+}
+
+/// Where the subject token comes from: a file, a URL, or AWS.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CredentialSource {
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    // sent with `url`, e.g. an Azure metadata header
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub format: Option<SourceFormat>,
+    // `aws1` for AWS-sourced credentials
+    #[serde(default)]
+    pub environment_id: Option<String>,
+    #[serde(default)]
+    pub region_url: Option<String>,
+    #[serde(default)]
+    pub regional_cred_verification_url: Option<String>,
+    #[serde(default)]
+    pub imdsv2_session_token_url: Option<String>,
+}
+
+/// How the token sits in the file or response: the whole text, or one
+/// field of a JSON object.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SourceFormat {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub subject_token_field_name: Option<String>,
+}
+
+/// AWS keys the caller-identity request is signed with.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AwsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    #[serde(default)]
+    token: Option<String>,
+}
+
+fn default_token_url() -> String {
+    STS_TOKEN_URL.to_string()
+}
+
+impl ExternalAccount {
+    /// Service account the federated identity impersonates, if any.
+    pub fn impersonated_account(&self) -> Option<&str> {
+        let url = self.service_account_impersonation_url.as_deref()?;
+        let (_, account) = url.strip_suffix(IMPERSONATION_SUFFIX)?.rsplit_once('/')?;
+        Some(account)
+    }
+
+    fn is_aws(&self) -> bool {
+        self.credential_source
+            .environment_id
+            .as_deref()
+            .is_some_and(|id| id.starts_with("aws"))
+    }
+
+    /// The form posted to the Security Token Service to swap `subject_token`
+    /// for an access token.
+    pub fn exchange_form(&self, subject_token: &str) -> Vec<(&'static str, String)> {
+        vec![
+            ("grant_type", TOKEN_EXCHANGE_GRANT.to_string()),
+            ("audience", self.audience.clone()),
+            ("scope", CLOUD_PLATFORM_SCOPE.to_string()),
+            ("requested_token_type", ACCESS_TOKEN_TYPE.to_string()),
+            ("subject_token_type", self.subject_token_type.clone()),
+            ("subject_token", subject_token.to_string()),
+        ]
+    }
+
+    /// Reads the subject token from the credential source.
+    pub async fn subject_token(&self, http: &Transport) -> Result<String> {
+        if self.is_aws() {
+            return self.aws_subject_token(http).await;
+        }
+        let source = &self.credential_source;
+        let raw = match (&source.file, &source.url) {
+            (Some(path), _) => {
+                debug!("reading the subject token from {path}");
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("failed to read the subject token file {path}"))?
+            }
+            (None, Some(url)) => {
+                debug!("fetching the subject token from {url}");
+                let request = source
+                    .headers
+                    .iter()
+                    .fold(http.get(url), |request, (name, value)| {
+                        request.header(name, value)
+                    });
+                fetch_text(http, request, "the subject token URL").await?
+            }
+            (None, None) => bail!(
+                "the external account credentials name no file, url, or AWS environment \
+                 to read the subject token from"
+            ),
+        };
+        source.extract(&raw)
+    }
+
+    /// A signed `GetCallerIdentity` request, which STS replays to AWS to
+    /// learn the caller's role.
+    async fn aws_subject_token(&self, http: &Transport) -> Result<String> {
+        let source = &self.credential_source;
+        let session = match &source.imdsv2_session_token_url {
+            Some(url) => {
+                let request = http
+                    .put(url)
+                    .header("X-aws-ec2-metadata-token-ttl-seconds", "300");
+                Some(fetch_text(http, request, "the AWS IMDSv2 session endpoint").await?)
+            }
+            None => None,
+        };
+        let imds = |url: &str| {
+            let request = http.get(url);
+            match &session {
+                Some(token) => request.header("X-aws-ec2-metadata-token", token),
+                None => request,
+            }
+        };
+
+        let region = match env_var("AWS_REGION").or_else(|| env_var("AWS_DEFAULT_REGION")) {
+            Some(region) => region,
+            None => {
+                let url = source.region_url.as_deref().context(
+                    "the AWS credential source has no region_url and AWS_REGION is unset",
+                )?;
+                // the availability zone, e.g. us-east-1b
+                let zone = fetch_text(http, imds(url), "the AWS region endpoint").await?;
+                let zone = zone.trim();
+                zone[..zone.len().saturating_sub(1)].to_string()
+            }
+        };
+
+        let credentials = match (
+            env_var("AWS_ACCESS_KEY_ID"),
+            env_var("AWS_SECRET_ACCESS_KEY"),
+        ) {
+            (Some(access_key_id), Some(secret_access_key)) => AwsCredentials {
+                access_key_id,
+                secret_access_key,
+                token: env_var("AWS_SESSION_TOKEN"),
+            },
+            _ => {
+                let url = source.url.as_deref().context(
+                    "the AWS credential source has no url and AWS_ACCESS_KEY_ID is unset",
+                )?;
+                let role = fetch_text(http, imds(url), "the AWS role endpoint").await?;
+                let role_url = format!("{}/{}", url.trim_end_matches('/'), role.trim());
+                let body =
+                    fetch_text(http, imds(&role_url), "the AWS credentials endpoint").await?;
+                serde_json::from_str(&body).context("failed to decode the AWS role credentials")?
+            }
+        };
+
+        let url = source
+            .regional_cred_verification_url
+            .as_deref()
+            .context("the AWS credential source has no regional_cred_verification_url")?
+            .replace("{region}", &region);
+        signed_caller_identity(&url, &region, &credentials, &self.audience, Utc::now())
+    }
+}
+
+impl CredentialSource {
+    /// The token in `raw`, the contents of the file or URL response.
+    fn extract(&self, raw: &str) -> Result<String> {
+        let token = match &self.format {
+            Some(format) if format.kind == "json" => {
+                let field = format
+                    .subject_token_field_name
+                    .as_deref()
+                    .context("a json credential source needs subject_token_field_name")?;
+                let value: Value =
+                    serde_json::from_str(raw).context("the subject token source is not JSON")?;
+                value[field]
+                    .as_str()
+                    .with_context(|| format!("the subject token source has no `{field}` field"))?
+                    .to_string()
+            }
+            _ => raw.trim().to_string(),
+        };
+        if token.is_empty() {
+            bail!("the subject token source is empty");
+        }
+        Ok(token)
+    }
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+async fn fetch_text(
+    http: &Transport,
+    request: reqwest::RequestBuilder,
+    what: &str,
+) -> Result<String> {
+    let resp = http
+        .send(request)
+        .await
+        .with_context(|| format!("request to {what} failed"))?;
+    let status = resp.status();
+    let body = resp.text().await?;
+    if !status.is_success() {
+        bail!("{what} returned {status}: {body}");
+    }
+    Ok(body)
+}
+
+/// Signs `POST url` with AWS Signature Version 4 and serializes it the way
+/// STS expects an AWS subject token: URL-encoded JSON of the URL, method,
+/// and headers.
+fn signed_caller_identity(
+    url: &str,
+    region: &str,
+    credentials: &AwsCredentials,
+    audience: &str,
+    now: DateTime<Utc>,
+) -> Result<String> {
+    let parsed = Url::parse(url).with_context(|| format!("invalid AWS verification URL {url}"))?;
+    let host = parsed
+        .host_str()
+        .context("the AWS verification URL has no host")?;
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    let mut headers = BTreeMap::from([
+        ("host".to_string(), host.to_string()),
+        ("x-amz-date".to_string(), amz_date.clone()),
+        (
+            "x-goog-cloud-target-resource".to_string(),
+            audience.to_string(),
+        ),
+    ]);
+    if let Some(token) = &credentials.token {
+        headers.insert("x-amz-security-token".to_string(), token.clone());
+    }
+    let canonical_headers: String = headers.iter().map(|(k, v)| format!("{k}:{v}\n")).collect();
+    let signed_headers = headers.keys().cloned().collect::<Vec<_>>().join(";");
+    let mut query: Vec<(String, String)> = parsed.query_pairs().into_owned().collect();
+    query.sort();
+    let canonical_query = query
+        .iter()
+        .map(|(k, v)| format!("{}={}", encode(k), encode(v)))
+        .collect::<Vec<_>>()
+        .join("&");
+    let path = match parsed.path() {
+        "" => "/",
+        path => path,
+    };
+    let canonical_request = format!(
+        "POST\n{path}\n{canonical_query}\n{canonical_headers}\n{signed_headers}\n{}",
+        hex(&Sha256::digest(b""))
+    );
+
+    let scope = format!("{date}/{region}/sts/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = [date.as_str(), region, "sts", "aws4_request"].iter().fold(
+        format!("AWS4{}", credentials.secret_access_key).into_bytes(),
+        |key, part| hmac(&key, part.as_bytes()),
+    );
+    let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+    headers.insert(
+        "Authorization".to_string(),
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, \
+             Signature={signature}",
+            credentials.access_key_id
+        ),
+    );
+
+    // BTreeMap order puts `Authorization` first, as the client libraries do
+    let request = json!({
+        "url": url,
+        "method": "POST",
+        "headers": headers
+            .iter()
+            .map(|(key, value)| json!({"key": key, "value": value}))
+            .collect::<Vec<_>>(),
+    });
+    Ok(encode(&request.to_string()))
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// RFC 3986 percent-encoding of everything but the unreserved characters
+fn encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_oidc_file_sources() {
+        let body = r#"{
+            "type": "external_account",
+            "audience": "//iam.googleapis.com/projects/1/locations/global/workloadIdentityPools/ci/providers/gh",
+            "subject_token_type": "urn:ietf:params:oauth:token-type:jwt",
+            "token_url": "https://sts.googleapis.com/v1/token",
+            "service_account_impersonation_url": "https://iamcredentials.googleapis.com/v1/projects/-/serviceAccounts/deploy@p.iam.gserviceaccount.com:generateAccessToken",
+            "credential_source": {"file": "/var/run/token", "format": {"type": "json", "subject_token_field_name": "id_token"}}
+        }"#;
+        let account: ExternalAccount = serde_json::from_str(body).unwrap();
+        assert_eq!(
+            account.impersonated_account(),
+            Some("deploy@p.iam.gserviceaccount.com")
+        );
+        assert!(!account.is_aws());
+        let source = &account.credential_source;
+        assert_eq!(source.extract(r#"{"id_token": "abc"}"#).unwrap(), "abc");
+        assert!(source.extract(r#"{"other": "abc"}"#).is_err());
+        assert_eq!(CredentialSource::default().extract("tok\n").unwrap(), "tok");
+    }
+
+    #[test]
+    fn signs_the_aws_caller_identity_request() {
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".into(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into(),
+            token: Some("session".into()),
+        };
+        let url = "https://sts.us-east-1.amazonaws.com?Action=GetCallerIdentity&Version=2011-06-15";
+        let now = "2026-01-02T03:04:05Z".parse().unwrap();
+        let token =
+            signed_caller_identity(url, "us-east-1", &credentials, "//iam/pool", now).unwrap();
+        assert!(!token.contains('{'));
+
+        let decoded = percent_decode(&token);
+        let request: Value = serde_json::from_str(&decoded).unwrap();
+        assert_eq!(request["method"], "POST");
+        let headers = request["headers"].as_array().unwrap();
+        let keys: Vec<&str> = headers.iter().map(|h| h["key"].as_str().unwrap()).collect();
+        assert_eq!(
+            keys,
+            [
+                "Authorization",
+                "host",
+                "x-amz-date",
+                "x-amz-security-token",
+                "x-goog-cloud-target-resource"
+            ]
+        );
+        let authorization = headers[0]["value"].as_str().unwrap();
+        assert!(authorization.starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20260102/us-east-1/sts/aws4_request, \
+             SignedHeaders=host;x-amz-date;x-amz-security-token;x-goog-cloud-target-resource, \
+             Signature="
+        ));
+        // the same inputs always sign the same way
+        assert_eq!(
+            token,
+            signed_caller_identity(url, "us-east-1", &credentials, "//iam/pool", now).unwrap()
+        );
+    }
+
+    fn percent_decode(s: &str) -> String {
+        let bytes = s.as_bytes();
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'%' {
+                out.push(u8::from_str_radix(&s[i + 1..i + 3], 16).unwrap());
+                i += 3;
+            } else {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+        String::from_utf8(out).unwrap()
+    }
+}
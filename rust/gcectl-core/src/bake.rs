@@ -0,0 +1,242 @@
+//! Bake files for `gcectl bake`, which turn a stock image into a versioned
+//! one of an image family:
+//!
+//! ```yaml
+//! zone: asia-northeast1-a
+//! builds:
+//!   - family: web
+//!     sourceImage: projects/debian-cloud/global/images/family/debian-12
+//!     steps:
+//!       - run: sudo apt-get update && sudo apt-get install -y nginx
+//!       - script: scripts/harden.sh
+//! ```
+//!
+//! Each build boots a builder instance, runs its steps over ssh in order,
+//! stops it, and images its boot disk as `{family}-{version}`. Scripts are
+//! read relative to the bake file and sent as the argument of `sh -c`.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::output::Render;
+use crate::resources::instance::builder::{DEFAULT_MACHINE_TYPE, ImageSource, InstanceBuilder};
+
+/// Label on builder instances, holding the family they bake.
+pub const BAKE_LABEL: &str = "gcectl-bake";
+
+// leaves room in 63-character names for `bake-` and the version
+const MAX_FAMILY_LEN: usize = 40;
+const MAX_VERSION_LEN: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BakeFile {
+    #[serde(default)]
+    pub project: Option<String>,
+    // where builds without their own zone run
+    #[serde(default)]
+    pub zone: Option<String>,
+    pub builds: Vec<Build>,
+}
+
+/// One image to bake.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Build {
+    pub family: String,
+    #[serde(default)]
+    pub zone: Option<String>,
+    // image path such as `projects/debian-cloud/global/images/family/debian-12`
+    #[serde(default)]
+    pub source_image: Option<String>,
+    #[serde(default = "default_machine_type")]
+    pub machine_type: String,
+    #[serde(default)]
+    pub boot_disk_size_gb: Option<u64>,
+    #[serde(default)]
+    pub description: Option<String>,
+    // applied to the image
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    // each a `run:` or `script:` map
+    #[serde(default, with = "serde_yaml::with::singleton_map_recursive")]
+    pub steps: Vec<Step>,
+}
+
+/// A provisioning step, run on the builder as its login user.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Step {
+    /// A command line for the remote shell.
+    Run(String),
+    /// A local script, relative to the bake file.
+    Script(PathBuf),
+}
+
+fn default_machine_type() -> String {
+    DEFAULT_MACHINE_TYPE.to_string()
+}
+
+impl BakeFile {
+    /// Parses a bake file, rejecting families baked twice.
+    pub fn parse(yaml: &str) -> Result<Self> {
+        let file: Self = serde_yaml::from_str(yaml).context("invalid bake file")?;
+        if file.builds.is_empty() {
+            bail!("the bake file has no builds");
+        }
+        let mut seen = HashSet::new();
+        for build in &file.builds {
+            check_name("family", &build.family, MAX_FAMILY_LEN)?;
+            if !seen.insert(build.family.as_str()) {
+                bail!("family {} is baked twice", build.family);
+            }
+        }
+        Ok(file)
+    }
+}
+
+impl Build {
+    /// Builder instance for version `version`, in `zone`.
+    pub fn builder(&self, version: &str, zone: &str) -> Result<InstanceBuilder> {
+        let mut builder = InstanceBuilder::new(self.builder_name(version), zone)
+            .machine_type(&self.machine_type)
+            .boot_disk_size_gb(self.boot_disk_size_gb)
+            .labels([(BAKE_LABEL.to_string(), self.family.clone())])
+            // to reach it with the caller's key without editing project metadata
+            .metadata([("enable-oslogin".to_string(), "TRUE".to_string())]);
+        if let Some(image) = &self.source_image {
+            let source = ImageSource::from_path(image).with_context(|| {
+                format!(
+                    "build {}: image `{image}` is not a path like \
+                     projects/debian-cloud/global/images/family/debian-12",
+                    self.family
+                )
+            })?;
+            builder = builder.image(source);
+        }
+        Ok(builder)
+    }
+
+    pub fn builder_name(&self, version: &str) -> String {
+        format!("bake-{}-{version}", self.family)
+    }
+
+    pub fn image_name(&self, version: &str) -> String {
+        format!("{}-{version}", self.family)
+    }
+
+    /// The command line of every step, with scripts under `dir` read in.
+    pub fn commands(&self, dir: &Path) -> Result<Vec<String>> {
+        self.steps
+            .iter()
+            .map(|step| match step {
+                Step::Run(command) => Ok(command.clone()),
+                Step::Script(path) => {
+                    let path = dir.join(path);
+                    let script = std::fs::read_to_string(&path)
+                        .with_context(|| format!("failed to read {}", path.display()))?;
+                    Ok(format!("sh -c {}", crate::ssh::shell_quote(&script)))
+                }
+            })
+            .collect()
+    }
+}
+
+/// The version images baked at `now` get unless `--version` is given.
+pub fn default_version(now: DateTime<Utc>) -> String {
+    now.format("v%Y%m%d%H%M%S").to_string()
+}
+
+/// Checks a `--version`, which ends image and instance names.
+pub fn check_version(version: &str) -> Result<()> {
+    check_name("version", version, MAX_VERSION_LEN)
+}
+
+// lowercase letters, digits, and inner hyphens, as resource names allow
+fn check_name(what: &str, name: &str, max: usize) -> Result<()> {
+    let valid = name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && !name.ends_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid || name.len() > max {
+        bail!("{what} `{name}` must be at most {max} lowercase letters, digits, and hyphens");
+    }
+    Ok(())
+}
+
+/// What a build produced.
+#[derive(Debug, Clone, Serialize)]
+pub struct Baked {
+    pub family: String,
+    pub image: String,
+    // the first error, if the build failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Render for Baked {
+    fn headers() -> Vec<&'static str> {
+        vec!["Family", "Image", "Result"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.family.clone(),
+            self.image.clone(),
+            self.error.clone().unwrap_or_else(|| "baked".to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_builds_and_their_steps() {
+        let file = BakeFile::parse(
+            "zone: z\nbuilds:\n  - family: web\n    steps:\n      - run: echo hi\n      - script: setup.sh\n",
+        )
+        .unwrap();
+        let build = &file.builds[0];
+        assert_eq!(
+            build.steps,
+            [Step::Run("echo hi".into()), Step::Script("setup.sh".into())]
+        );
+        assert_eq!(build.image_name("v3"), "web-v3");
+        assert_eq!(build.builder_name("v3"), "bake-web-v3");
+        let body = build.builder("v3", "z").unwrap().build();
+        assert_eq!(body["labels"][BAKE_LABEL], "web");
+
+        assert!(BakeFile::parse("builds: []").is_err());
+        assert!(BakeFile::parse("builds:\n  - family: Web\n").is_err());
+        assert!(BakeFile::parse("builds:\n  - family: a\n  - family: a\n").is_err());
+    }
+
+    #[test]
+    fn scripts_run_through_sh() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("setup.sh"), "echo 'it''s'\n").unwrap();
+        let build = Build {
+            steps: vec![Step::Script("setup.sh".into())],
+            ..BakeFile::parse("builds:\n  - family: web\n")
+                .unwrap()
+                .builds[0]
+                .clone()
+        };
+        assert_eq!(
+            build.commands(dir.path()).unwrap(),
+            [r"sh -c 'echo '\''it'\'''\''s'\''
+'"]
+        );
+        assert_eq!(
+            default_version(DateTime::from_timestamp(1_791_979_200, 0).unwrap()),
+            "v20261014120000"
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Picking sets of instances by name, shell-style glob, or listing,
+//! summarizing what happened to each, and remembering the ones a batch
+//! left undone.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::output::Render;
+use crate::util::{is_taken, write_private};
+
+/// Whether `pattern` uses glob syntax rather than naming one instance.
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+/// Shell-style match of `name` against `pattern`: `*` is any run of
+/// characters, `?` any one, and `[a-z]` or `[!0-9]` a class.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    matches_from(&pattern, &name)
+}
+
+fn matches_from(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skip| matches_from(&pattern[1..], &name[skip..])),
+        Some('?') => !name.is_empty() && matches_from(&pattern[1..], &name[1..]),
+        Some('[') => match (class_end(pattern), name.first()) {
+            (Some(end), Some(&c)) => {
+                class_matches(&pattern[1..end], c) && matches_from(&pattern[end + 1..], &name[1..])
+            }
+            // an unclosed `[` is an ordinary character
+            (None, Some('[')) => matches_from(&pattern[1..], &name[1..]),
+            _ => false,
+        },
+        Some(&c) => name.first() == Some(&c) && matches_from(&pattern[1..], &name[1..]),
+    }
+}
+
+// index of the `]` closing the class that opens `pattern`
+fn class_end(pattern: &[char]) -> Option<usize> {
+    let first = match pattern.get(1) {
+        Some('!') => 3,
+        _ => 2,
+    };
+    (first..pattern.len()).find(|&i| pattern[i] == ']')
+}
+
+fn class_matches(class: &[char], c: char) -> bool {
+    let (negated, class) = match class.first() {
+        Some('!') => (true, &class[1..]),
+        _ => (false, class),
+    };
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            found |= (class[i]..=class[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            found |= class[i] == c;
+            i += 1;
+        }
+    }
+    found != negated
+}
+
+/// The names in `listed` that `patterns` select, in listing order; no
+/// patterns select everything. Each pattern must match at least once, so a
+/// typo fails instead of silently shrinking the set.
+pub fn select(patterns: &[String], listed: &[String]) -> Result<Vec<String>> {
+    let unmatched: Vec<&str> = patterns
+        .iter()
+        .filter(|p| !listed.iter().any(|name| glob_match(p, name)))
+        .map(String::as_str)
+        .collect();
+    if !unmatched.is_empty() {
+        bail!("no instance matches {}", unmatched.join(", "));
+    }
+    Ok(listed
+        .iter()
+        .filter(|name| patterns.is_empty() || patterns.iter().any(|p| glob_match(p, name)))
+        .cloned()
+        .collect())
+}
+
+/// What happened to one member of a batch.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Outcome {
+    pub name: String,
+    pub ok: bool,
+    // the past-tense verb on success, the error otherwise
+    pub result: String,
+}
+
+impl Outcome {
+    pub fn new(name: &str, past: &str, result: Result<()>) -> Self {
+        let (ok, result) = match result {
+            Ok(()) => (true, past.to_string()),
+            Err(err) => (false, format!("{err:#}")),
+        };
+        Self {
+            name: name.to_string(),
+            ok,
+            result,
+        }
+    }
+}
+
+impl Render for Outcome {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Result"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let result = match self.ok {
+            true => self.result.clone(),
+            false => format!("failed: {}", self.result),
+        };
+        vec![self.name.clone(), result]
+    }
+}
+
+/// The instances a partially failed batch still has to act on, saved so
+/// `gcectl resume FILE` retries only those.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumeFile {
+    // the verb of the batch: start, stop, delete, ...
+    pub action: String,
+    pub project: String,
+    pub zone: String,
+    pub pending: Vec<String>,
+    // RFC 3339
+    pub created: String,
+    // seconds a stop drained the instances for, to drain them again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drain: Option<u64>,
+}
+
+impl ResumeFile {
+    pub fn new(action: &str, project: &str, zone: &str, pending: Vec<String>) -> Self {
+        Self {
+            action: action.to_string(),
+            project: project.to_string(),
+            zone: zone.to_string(),
+            pending,
+            created: Utc::now().to_rfc3339(),
+            drain: None,
+        }
+    }
+
+    /// `resume/` under the config directory, which outlives `cache clear`.
+    pub fn dir() -> Result<PathBuf> {
+        Ok(Config::dir()?.join("resume"))
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&text)
+            .with_context(|| format!("{} is not a resume file", path.display()))
+    }
+
+    /// Writes the file over `path`, or to a new file in [`ResumeFile::dir`]
+    /// named after the batch, and returns where it went. Only the user can
+    /// read it.
+    pub fn save(&self, path: Option<&Path>) -> Result<PathBuf> {
+        let body = serde_json::to_string_pretty(self)? + "\n";
+        if let Some(path) = path {
+            write_private(path, body.as_bytes(), false)?;
+            return Ok(path.to_path_buf());
+        }
+        let stamp = Utc::now().format("%Y%m%dT%H%M%S%.3f");
+        let dir = Self::dir()?;
+        // batches finishing in the same millisecond get a number each
+        for n in 1.. {
+            let name = match n {
+                1 => format!("{}-{}-{stamp}.json", self.action, self.zone),
+                n => format!("{}-{}-{stamp}-{n}.json", self.action, self.zone),
+            };
+            let path = dir.join(name);
+            match write_private(&path, body.as_bytes(), true) {
+                Err(err) if is_taken(&err) => continue,
+                result => return result.map(|()| path),
+            }
+        }
+        unreachable!("some file name is free")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("training-*", "training-7"));
+        assert!(glob_match("training-*", "training-"));
+        assert!(!glob_match("training-*", "web-1"));
+        assert!(glob_match("web-?", "web-1"));
+        assert!(!glob_match("web-?", "web-10"));
+        assert!(glob_match("web-[0-3]", "web-2"));
+        assert!(!glob_match("web-[!0-3]", "web-2"));
+        assert!(glob_match("a[b", "a[b"));
+        assert!(glob_match("*-worker-*", "gpu-worker-3"));
+    }
+
+    #[test]
+    fn selects_in_listing_order_and_rejects_unmatched() {
+        let listed: Vec<String> = ["db", "web-1", "web-2"].map(String::from).into();
+        let selected = select(&["web-*".into(), "db".into()], &listed).unwrap();
+        assert_eq!(selected, ["db", "web-1", "web-2"]);
+        assert_eq!(select(&[], &listed).unwrap(), listed);
+        let err = select(&["cache-*".into()], &listed).unwrap_err();
+        assert_eq!(err.to_string(), "no instance matches cache-*");
+    }
+
+    #[test]
+    fn resume_files_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = ResumeFile::new("stop", "demo", "us-central1-a", vec!["web-2".into()]);
+        let path = file.save(Some(&dir.path().join("stop.json"))).unwrap();
+        assert_eq!(ResumeFile::read(&path).unwrap(), file);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
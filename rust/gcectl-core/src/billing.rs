@@ -0,0 +1,384 @@
+//! Billed spend from a Cloud Billing export to BigQuery, through a minimal
+//! client for the BigQuery v2 `jobs.query` API.
+//!
+//! Per-instance attribution needs the detailed (resource-level) export,
+//! where each row names the resource it bills; the standard export only
+//! carries labels. The query job runs in the project that holds the export
+//! table, so the caller needs `bigquery.jobs.create` there and read access
+//! to the dataset.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::debug;
+
+use crate::auth::Authenticator;
+use crate::compute::parse_response;
+use crate::output::{Details, Render};
+use crate::transport::Transport;
+
+const BIGQUERY_ENDPOINT: &str = "https://bigquery.googleapis.com/bigquery/v2";
+// how long one request waits for the job before polling again
+const WAIT_MS: u64 = 10_000;
+
+pub struct Billing {
+    http: Transport,
+    auth: Arc<Authenticator>,
+    endpoint: String,
+}
+
+/// What spend is attributed to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Grouping {
+    /// Each Compute Engine instance, by name.
+    Instance,
+    /// Each value of a resource label, over every Compute Engine charge.
+    Label(String),
+}
+
+/// Spend attributed to one instance or label value over the window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Spend {
+    // instance name or label value; `None` for charges without the label
+    pub key: Option<String>,
+    pub cost: f64,
+    // credits such as sustained-use discounts, as negative amounts
+    pub credits: f64,
+    pub currency: String,
+}
+
+impl Spend {
+    /// Cost after credits.
+    pub fn net(&self) -> f64 {
+        self.cost + self.credits
+    }
+}
+
+impl Render for Spend {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Cost", "Credits", "Net", "Currency"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.key.clone().unwrap_or_else(|| "(none)".to_string()),
+            format!("{:.2}", self.cost),
+            format!("{:.2}", self.credits),
+            format!("{:.2}", self.net()),
+            self.currency.clone(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        for (header, value) in Self::headers().into_iter().zip(self.row()) {
+            details.field(header, value);
+        }
+        details
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryResponse {
+    #[serde(default)]
+    job_complete: bool,
+    job_reference: Option<JobReference>,
+    schema: Option<Schema>,
+    #[serde(default)]
+    rows: Vec<Row>,
+    page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JobReference {
+    job_id: String,
+    location: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Schema {
+    #[serde(default)]
+    fields: Vec<Field>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Field {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    #[serde(default)]
+    f: Vec<Cell>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Cell {
+    // scalars arrive as strings, NULL as null
+    v: Value,
+}
+
+/// One result row, by column name; NULL columns are absent.
+pub type Record = HashMap<String, String>;
+
+/// A named query parameter and its BigQuery type, e.g. `STRING`.
+#[derive(Debug, Clone)]
+pub struct Param<'a> {
+    pub name: &'a str,
+    pub kind: &'a str,
+    pub value: String,
+}
+
+impl Billing {
+    pub fn new(http: Transport, auth: Arc<Authenticator>) -> Self {
+        Self {
+            http,
+            auth,
+            endpoint: BIGQUERY_ENDPOINT.to_string(),
+        }
+    }
+
+    /// Sends requests to `endpoint` instead of the public API, if given.
+    pub fn endpoint(mut self, endpoint: Option<&str>) -> Self {
+        if let Some(endpoint) = endpoint {
+            self.endpoint = endpoint.trim_end_matches('/').to_string();
+        }
+        self
+    }
+
+    /// Compute Engine spend of `project` over the last `window`, read from
+    /// export `table` (`project.dataset.table`), most expensive first.
+    pub async fn spend(
+        &self,
+        table: &str,
+        project: &str,
+        window: Duration,
+        grouping: &Grouping,
+    ) -> Result<Vec<Spend>> {
+        let (job_project, table) = export_table(table)?;
+        let (key, scope) = match grouping {
+            Grouping::Instance => (
+                "resource.name",
+                "AND resource.global_name LIKE '%/instances/%'",
+            ),
+            Grouping::Label(_) => (
+                "(SELECT l.value FROM UNNEST(labels) AS l WHERE l.key = @label)",
+                "",
+            ),
+        };
+        let sql = format!(
+            "SELECT {key} AS key, SUM(cost) AS cost, \
+             SUM(IFNULL((SELECT SUM(c.amount) FROM UNNEST(credits) AS c), 0)) AS credits, \
+             ANY_VALUE(currency) AS currency \
+             FROM `{table}` \
+             WHERE service.description = 'Compute Engine' AND project.id = @project \
+             AND usage_start_time >= TIMESTAMP_SUB(CURRENT_TIMESTAMP(), INTERVAL @hours HOUR) \
+             {scope} GROUP BY key ORDER BY cost DESC"
+        );
+        let mut params = vec![
+            Param {
+                name: "project",
+                kind: "STRING",
+                value: project.to_string(),
+            },
+            Param {
+                name: "hours",
+                kind: "INT64",
+                value: window.as_secs().div_ceil(3600).max(1).to_string(),
+            },
+        ];
+        if let Grouping::Label(label) = grouping {
+            params.push(Param {
+                name: "label",
+                kind: "STRING",
+                value: label.clone(),
+            });
+        }
+        let records = self.query(job_project, &sql, &params).await?;
+        records.iter().map(spend_of).collect()
+    }
+
+    /// Runs standard SQL `sql` as a query job in `project` and reads every
+    /// page of its result.
+    ///
+    /// `POST projects/{project}/queries`, then
+    /// `GET projects/{project}/queries/{jobId}` until the job completes.
+    pub async fn query(
+        &self,
+        project: &str,
+        sql: &str,
+        params: &[Param<'_>],
+    ) -> Result<Vec<Record>> {
+        let url = format!("{}/projects/{project}/queries", self.endpoint);
+        let parameters: Vec<Value> = params
+            .iter()
+            .map(|p| {
+                json!({
+                    "name": p.name,
+                    "parameterType": {"type": p.kind},
+                    "parameterValue": {"value": p.value},
+                })
+            })
+            .collect();
+        let body = json!({
+            "query": sql,
+            "useLegacySql": false,
+            "parameterMode": "NAMED",
+            "queryParameters": parameters,
+            "timeoutMs": WAIT_MS,
+        });
+        debug!("POST {url} {sql}");
+        let request = self
+            .http
+            .post(&url)
+            .json(&body)
+            .bearer_auth(self.auth.token().await?);
+        let resp = self
+            .http
+            .send(request)
+            .await
+            .context("request to BigQuery API failed")?;
+        let mut page: QueryResponse = parse_response(resp, "BigQuery API").await?;
+
+        let mut fields: Vec<String> = Vec::new();
+        let mut records = Vec::new();
+        loop {
+            if let Some(schema) = page.schema.take() {
+                fields = schema.fields.into_iter().map(|f| f.name).collect();
+            }
+            if page.job_complete {
+                records.extend(page.rows.drain(..).map(|row| record(&fields, row)));
+            }
+            let token = page.page_token.take().filter(|t| !t.is_empty());
+            if page.job_complete && token.is_none() {
+                return Ok(records);
+            }
+            let Some(job) = page.job_reference.as_ref() else {
+                bail!("BigQuery API returned an unfinished query without a job reference");
+            };
+            let url = format!(
+                "{}/projects/{project}/queries/{}",
+                self.endpoint, job.job_id
+            );
+            let mut query = vec![("timeoutMs", WAIT_MS.to_string())];
+            if let Some(location) = &job.location {
+                query.push(("location", location.clone()));
+            }
+            if let Some(token) = token {
+                query.push(("pageToken", token));
+            }
+            debug!("GET {url} {query:?}");
+            let request = self
+                .http
+                .get(&url)
+                .query(&query)
+                .bearer_auth(self.auth.token().await?);
+            let resp = self
+                .http
+                .send(request)
+                .await
+                .context("request to BigQuery API failed")?;
+            let next: QueryResponse = parse_response(resp, "BigQuery API").await?;
+            page = QueryResponse {
+                job_reference: next.job_reference.or(page.job_reference),
+                ..next
+            };
+        }
+    }
+}
+
+/// The project of export `table` and the table itself, checked to be a
+/// `project.dataset.table` path since it cannot be a query parameter.
+fn export_table(table: &str) -> Result<(&str, &str)> {
+    let table = table.trim_matches('`');
+    let parts: Vec<&str> = table.split('.').collect();
+    let valid = parts.len() == 3
+        && parts.iter().all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | ':'))
+        });
+    if !valid {
+        bail!("billing export table must be `project.dataset.table`, got `{table}`");
+    }
+    Ok((parts[0], table))
+}
+
+fn record(fields: &[String], row: Row) -> Record {
+    fields
+        .iter()
+        .zip(row.f)
+        .filter_map(|(name, cell)| match cell.v {
+            Value::String(value) => Some((name.clone(), value)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn spend_of(record: &Record) -> Result<Spend> {
+    let amount = |column: &str| -> Result<f64> {
+        record.get(column).map_or(Ok(0.0), |value| {
+            value
+                .parse()
+                .with_context(|| format!("BigQuery returned a non-numeric {column} `{value}`"))
+        })
+    };
+    Ok(Spend {
+        key: record.get("key").cloned(),
+        cost: amount("cost")?,
+        credits: amount("credits")?,
+        currency: record.get("currency").cloned().unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_decode_by_schema_and_nulls_drop_out() {
+        let page: QueryResponse = serde_json::from_value(json!({
+            "jobComplete": true,
+            "schema": {"fields": [
+                {"name": "key", "type": "STRING"},
+                {"name": "cost", "type": "FLOAT"},
+                {"name": "credits", "type": "FLOAT"},
+                {"name": "currency", "type": "STRING"},
+            ]},
+            "rows": [{"f": [{"v": null}, {"v": "12.5"}, {"v": "-2.25"}, {"v": "USD"}]}],
+        }))
+        .unwrap();
+        let fields: Vec<String> = page
+            .schema
+            .unwrap()
+            .fields
+            .into_iter()
+            .map(|f| f.name)
+            .collect();
+        let rows: Vec<Record> = page.rows.into_iter().map(|r| record(&fields, r)).collect();
+        let spend = spend_of(&rows[0]).unwrap();
+        assert_eq!(spend.key, None);
+        assert_eq!(spend.net(), 10.25);
+        assert_eq!(spend.row()[0], "(none)");
+    }
+
+    #[test]
+    fn export_tables_are_checked_before_they_reach_sql() {
+        assert_eq!(
+            export_table("`billing-admin.exports.gcp_billing_export_resource_v1_01`").unwrap(),
+            (
+                "billing-admin",
+                "billing-admin.exports.gcp_billing_export_resource_v1_01"
+            )
+        );
+        assert!(export_table("exports.table").is_err());
+        assert!(export_table("p.d.t` WHERE 1=1 --").is_err());
+    }
+}
@@ -0,0 +1,285 @@
+//! On-disk cache of API reads, so back-to-back commands skip the network.
+//!
+//! Entries live in one JSON file each, named after a namespace such as
+//! `instances-my-project` plus a hash of the full key, so everything about a
+//! project can be dropped at once when a command changes it.
+//!
+//! Catalogs that rarely change (zones, machine types, public images) are
+//! kept for a day instead, along with the ETag they were served with, and
+//! once stale are revalidated with `If-None-Match` rather than fetched again.
+//!
+//! Listings can name private resources, so the directory and its files are
+//! readable by the user alone.
+
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::util::write_private;
+
+/// How long entries stay fresh unless `--cache-ttl` says otherwise.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(30);
+/// How long a catalog stays fresh before it is revalidated.
+pub const CATALOG_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+// namespaces of the catalogs
+pub const ZONES_CATALOG: &str = "catalog-zones";
+pub const MACHINE_TYPES_CATALOG: &str = "catalog-machine-types";
+pub const IMAGES_CATALOG: &str = "catalog-images";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry<T> {
+    // unix seconds
+    stored_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    value: T,
+}
+
+/// A stored catalog, however old, and what it takes to revalidate it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stored<T> {
+    pub value: T,
+    pub etag: Option<String>,
+    // younger than CATALOG_TTL
+    pub fresh: bool,
+}
+
+/// A cache rooted at [`Cache::dir`]; a disabled cache misses every lookup
+/// and stores nothing.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: Option<PathBuf>,
+    ttl: Duration,
+}
+
+impl Cache {
+    /// `GCECTL_CACHE_DIR` overrides the default of `$XDG_CACHE_HOME/gcectl`
+    /// (or `~/.cache/gcectl`).
+    pub fn dir() -> Result<PathBuf> {
+        if let Some(dir) = env::var_os("GCECTL_CACHE_DIR") {
+            return Ok(PathBuf::from(dir));
+        }
+        let base = match env::var_os("XDG_CACHE_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".cache"))
+                .context("cannot locate the cache directory: HOME is not set")?,
+        };
+        Ok(base.join("gcectl"))
+    }
+
+    pub fn new(enabled: bool, ttl: Duration) -> Self {
+        let dir = match enabled {
+            true => Self::dir().ok(),
+            false => None,
+        };
+        Self { dir, ttl }
+    }
+
+    fn path(&self, namespace: &str, key: &str) -> Option<PathBuf> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let name = format!("{}-{:016x}.json", sanitize(namespace), hasher.finish());
+        Some(self.dir.as_ref()?.join(name))
+    }
+
+    fn read<T: DeserializeOwned>(&self, namespace: &str, key: &str) -> Option<Entry<T>> {
+        let path = self.path(namespace, key)?;
+        serde_json::from_slice(&fs::read(&path).ok()?).ok()
+    }
+
+    /// The value stored under `key`, if it is younger than the TTL.
+    pub fn get<T: DeserializeOwned>(&self, namespace: &str, key: &str) -> Option<T> {
+        let entry = self.read::<T>(namespace, key)?;
+        let age = Utc::now().timestamp() - entry.stored_at;
+        if !is_fresh(age, self.ttl) {
+            return None;
+        }
+        debug!("cache hit for {key}");
+        Some(entry.value)
+    }
+
+    /// The catalog stored under `key`, judged fresh against [`CATALOG_TTL`].
+    pub fn catalog<T: DeserializeOwned>(&self, namespace: &str, key: &str) -> Option<Stored<T>> {
+        let entry = self.read::<T>(namespace, key)?;
+        let age = Utc::now().timestamp() - entry.stored_at;
+        Some(Stored {
+            value: entry.value,
+            etag: entry.etag,
+            fresh: is_fresh(age, CATALOG_TTL),
+        })
+    }
+
+    /// Stores `value` under `key`. Failures only cost a later cache miss, so
+    /// they are logged rather than returned.
+    pub fn put<T: Serialize>(&self, namespace: &str, key: &str, value: &T) {
+        self.write(namespace, key, value, None);
+    }
+
+    /// Stores a catalog under `key` with the ETag it was served with,
+    /// restarting its [`CATALOG_TTL`].
+    pub fn put_catalog<T: Serialize>(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &T,
+        etag: Option<&str>,
+    ) {
+        self.write(namespace, key, value, etag.map(str::to_string));
+    }
+
+    fn write<T: Serialize>(&self, namespace: &str, key: &str, value: &T, etag: Option<String>) {
+        let Some(path) = self.path(namespace, key) else {
+            return;
+        };
+        let entry = Entry {
+            stored_at: Utc::now().timestamp(),
+            etag,
+            value,
+        };
+        let result = serde_json::to_vec(&entry)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| write_private(&path, &bytes, false));
+        if let Err(err) = result {
+            debug!("not caching {key}: {err:#}");
+        }
+    }
+
+    /// Drops every entry in `namespace`.
+    pub fn invalidate(&self, namespace: &str) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let prefix = format!("{}-", sanitize(namespace));
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+
+    /// Every value stored in `namespace`, however old; for completions,
+    /// which must not wait on the API.
+    pub fn stored<T: DeserializeOwned>(namespace: &str) -> Vec<T> {
+        let Ok(dir) = Self::dir() else {
+            return Vec::new();
+        };
+        let prefix = format!("{}-", sanitize(namespace));
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+            .filter_map(|entry| {
+                serde_json::from_slice::<Entry<T>>(&fs::read(entry.path()).ok()?).ok()
+            })
+            .map(|entry| entry.value)
+            .collect()
+    }
+
+    /// Removes the whole cache directory, returning how many files it held.
+    pub fn clear() -> Result<usize> {
+        let dir = Self::dir()?;
+        if !dir.exists() {
+            return Ok(0);
+        }
+        let count = fs::read_dir(&dir)?.count();
+        fs::remove_dir_all(&dir).with_context(|| format!("failed to remove {}", dir.display()))?;
+        Ok(count)
+    }
+}
+
+fn is_fresh(age_secs: i64, ttl: Duration) -> bool {
+    (0..ttl.as_secs() as i64).contains(&age_secs)
+}
+
+// keeps namespaces readable as file name prefixes
+fn sanitize(namespace: &str) -> String {
+    namespace
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() || c == '-' {
+            true => c,
+            false => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(ttl: u64) -> (tempfile::TempDir, Cache) {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache {
+            dir: Some(dir.path().to_path_buf()),
+            ttl: Duration::from_secs(ttl),
+        };
+        (dir, cache)
+    }
+
+    #[test]
+    fn stores_and_invalidates_by_namespace() {
+        let (_dir, cache) = cache(60);
+        cache.put("instances-p", "a", &vec!["web"]);
+        cache.put("instances-q", "a", &vec!["db"]);
+        assert_eq!(
+            cache.get::<Vec<String>>("instances-p", "a").unwrap(),
+            ["web"]
+        );
+        assert!(cache.get::<Vec<String>>("instances-p", "b").is_none());
+
+        cache.invalidate("instances-p");
+        assert!(cache.get::<Vec<String>>("instances-p", "a").is_none());
+        assert!(cache.get::<Vec<String>>("instances-q", "a").is_some());
+    }
+
+    #[test]
+    fn expired_and_disabled_caches_miss() {
+        let (_dir, cache) = cache(0);
+        cache.put("n", "k", &1);
+        assert!(cache.get::<i32>("n", "k").is_none());
+
+        let disabled = Cache {
+            dir: None,
+            ttl: DEFAULT_TTL,
+        };
+        disabled.put("n", "k", &1);
+        assert!(disabled.get::<i32>("n", "k").is_none());
+    }
+
+    #[test]
+    fn catalogs_outlive_the_ttl_with_their_etag() {
+        let (_dir, cache) = cache(0);
+        cache.put_catalog(
+            "catalog-zones",
+            "p",
+            &vec!["us-central1-a"],
+            Some("\"abc\""),
+        );
+        assert!(cache.get::<Vec<String>>("catalog-zones", "p").is_none());
+        let stored = cache.catalog::<Vec<String>>("catalog-zones", "p").unwrap();
+        assert_eq!(stored.value, ["us-central1-a"]);
+        assert_eq!(stored.etag.as_deref(), Some("\"abc\""));
+        assert!(stored.fresh);
+        assert!(cache.catalog::<Vec<String>>("catalog-zones", "q").is_none());
+    }
+
+    #[test]
+    fn freshness_window() {
+        let ttl = Duration::from_secs(30);
+        assert!(is_fresh(0, ttl));
+        assert!(is_fresh(29, ttl));
+        assert!(!is_fresh(30, ttl));
+        // clock moved backwards
+        assert!(!is_fresh(-5, ttl));
+    }
+}
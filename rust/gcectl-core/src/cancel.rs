@@ -0,0 +1,67 @@
+//! Ending a command early on Ctrl-C or when `--timeout` runs out.
+//!
+//! Waits on long-running operations race against [`cancelled`] so they can
+//! report which operation was still running; everything else is cut short
+//! by the same race around the whole command.
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::error::GcectlError;
+
+/// When the command has to be done by, from `--timeout`.
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    pub at: Instant,
+    pub timeout: Duration,
+}
+
+impl Deadline {
+    pub fn after(timeout: Duration) -> Self {
+        Self {
+            at: Instant::now() + timeout,
+            timeout,
+        }
+    }
+}
+
+/// Resolves with the error to fail with once Ctrl-C is pressed or
+/// `deadline` passes; `operation` is the one being waited on, if any.
+pub async fn cancelled(deadline: Option<Deadline>, operation: Option<&str>) -> GcectlError {
+    let operation = operation.map(str::to_string);
+    let expired = async {
+        match deadline {
+            Some(deadline) => tokio::time::sleep_until(deadline.at).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        // if the handler cannot be installed, only the deadline applies
+        Ok(()) = tokio::signal::ctrl_c() => GcectlError::Interrupted { operation },
+        () = expired => GcectlError::TimedOut {
+            after: deadline.map(|d| d.timeout).unwrap_or_default(),
+            operation,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn deadline_names_the_pending_operation() {
+        let deadline = Deadline::after(Duration::from_millis(20));
+        let err = cancelled(Some(deadline), Some("operation-123")).await;
+        assert!(matches!(
+            &err,
+            GcectlError::TimedOut { operation: Some(op), .. } if op == "operation-123"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "timed out after 20ms waiting for operation operation-123"
+        );
+        assert_eq!(err.exit_code(), 124);
+    }
+}
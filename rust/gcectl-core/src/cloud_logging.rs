@@ -0,0 +1,225 @@
+//! Thin client for the Cloud Logging v2 `entries.list` API, used to read the
+//! logs of an instance.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use tracing::debug;
+
+use crate::auth::Authenticator;
+use crate::compute::parse_response;
+use crate::transport::Transport;
+
+const LOGGING_ENDPOINT: &str = "https://logging.googleapis.com/v2";
+// the API's largest page
+const MAX_PAGE_SIZE: usize = 1000;
+
+pub struct CloudLogging {
+    http: Transport,
+    auth: Arc<Authenticator>,
+    endpoint: String,
+}
+
+/// One log entry; only the fields gcectl prints are typed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    #[serde(default)]
+    pub log_name: String,
+    // RFC 3339
+    #[serde(default)]
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insert_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_payload: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_payload: Option<Map<String, Value>>,
+    // audit logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proto_payload: Option<Map<String, Value>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EntriesPage {
+    #[serde(default)]
+    entries: Vec<LogEntry>,
+    next_page_token: Option<String>,
+}
+
+/// Which end of the matching entries [`CloudLogging::entries`] reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    Oldest,
+    Newest,
+}
+
+impl LogEntry {
+    /// Short log name, e.g. `syslog` for `projects/p/logs/syslog`, with
+    /// URL-escaped slashes restored.
+    pub fn log(&self) -> String {
+        let name = self.log_name.rsplit("/logs/").next().unwrap_or_default();
+        name.replace("%2F", "/")
+    }
+
+    /// The payload as one line of text: the text payload, a JSON payload's
+    /// `message`, an audit log's method, or the JSON itself.
+    pub fn message(&self) -> String {
+        if let Some(text) = &self.text_payload {
+            return text.trim_end().to_string();
+        }
+        if let Some(payload) = &self.json_payload {
+            return match payload.get("message") {
+                Some(Value::String(message)) => message.trim_end().to_string(),
+                _ => Value::Object(payload.clone()).to_string(),
+            };
+        }
+        if let Some(payload) = &self.proto_payload {
+            let field = |key: &str| payload.get(key).and_then(Value::as_str).unwrap_or("?");
+            return format!(
+                "{} by {}",
+                field("methodName"),
+                payload
+                    .get("authenticationInfo")
+                    .and_then(|a| a.get("principalEmail"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("?")
+            );
+        }
+        String::new()
+    }
+}
+
+impl CloudLogging {
+    pub fn new(http: Transport, auth: Arc<Authenticator>) -> Self {
+        Self {
+            http,
+            auth,
+            endpoint: LOGGING_ENDPOINT.to_string(),
+        }
+    }
+
+    /// Sends requests to `endpoint` instead of the public API, if given.
+    pub fn endpoint(mut self, endpoint: Option<&str>) -> Self {
+        if let Some(endpoint) = endpoint {
+            self.endpoint = endpoint.trim_end_matches('/').to_string();
+        }
+        self
+    }
+
+    /// Up to `limit` entries of `project` matching `filter`, from the
+    /// `order` end, returned oldest first.
+    ///
+    /// `POST entries:list`
+    pub async fn entries(
+        &self,
+        project: &str,
+        filter: &str,
+        order: Order,
+        limit: usize,
+    ) -> Result<Vec<LogEntry>> {
+        let url = format!("{}/entries:list", self.endpoint);
+        let order_by = match order {
+            Order::Oldest => "timestamp asc",
+            Order::Newest => "timestamp desc",
+        };
+        let mut entries = Vec::new();
+        let mut page_token: Option<String> = None;
+        while entries.len() < limit {
+            let mut body = json!({
+                "resourceNames": [format!("projects/{project}")],
+                "filter": filter,
+                "orderBy": order_by,
+                "pageSize": (limit - entries.len()).min(MAX_PAGE_SIZE),
+            });
+            if let Some(token) = &page_token {
+                body["pageToken"] = json!(token);
+            }
+            debug!("POST {url} {filter}");
+            let request = self
+                .http
+                .post(&url)
+                .json(&body)
+                .bearer_auth(self.auth.token().await?);
+            let resp = self
+                .http
+                .send(request)
+                .await
+                .context("request to Cloud Logging API failed")?;
+            let page: EntriesPage = parse_response(resp, "Cloud Logging API").await?;
+            entries.extend(page.entries);
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => break,
+            }
+        }
+        entries.truncate(limit);
+        if order == Order::Newest {
+            entries.reverse();
+        }
+        Ok(entries)
+    }
+}
+
+/// The filter selecting the logs of instance `instance_id` since `since`,
+/// narrowed by `extra` if given.
+pub fn instance_filter(instance_id: &str, since: DateTime<Utc>, extra: Option<&str>) -> String {
+    let mut filter = format!(
+        "resource.type=\"gce_instance\" AND resource.labels.instance_id=\"{instance_id}\" \
+         AND timestamp>=\"{}\"",
+        since.to_rfc3339_opts(SecondsFormat::Nanos, true)
+    );
+    if let Some(extra) = extra {
+        filter.push_str(&format!(" AND ({extra})"));
+    }
+    filter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_print_as_one_line_whatever_the_payload() {
+        let entry: LogEntry = serde_json::from_value(json!({
+            "logName": "projects/p/logs/cloudaudit.googleapis.com%2Factivity",
+            "timestamp": "2026-10-14T12:00:00Z",
+            "protoPayload": {
+                "methodName": "v1.compute.instances.stop",
+                "authenticationInfo": {"principalEmail": "me@example.com"},
+            },
+        }))
+        .unwrap();
+        assert_eq!(entry.log(), "cloudaudit.googleapis.com/activity");
+        assert_eq!(
+            entry.message(),
+            "v1.compute.instances.stop by me@example.com"
+        );
+
+        let entry: LogEntry = serde_json::from_value(json!({
+            "logName": "projects/p/logs/syslog",
+            "jsonPayload": {"message": "started\n", "pid": 1},
+        }))
+        .unwrap();
+        assert_eq!(entry.message(), "started");
+    }
+
+    #[test]
+    fn extra_filters_are_parenthesized() {
+        let since = DateTime::parse_from_rfc3339("2026-10-14T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let filter = instance_filter("42", since, Some("severity>=ERROR OR textPayload:oom"));
+        assert!(filter.contains("resource.labels.instance_id=\"42\""));
+        assert!(filter.contains("timestamp>=\"2026-10-14T12:00:00.000000000Z\""));
+        assert!(filter.ends_with(" AND (severity>=ERROR OR textPayload:oom)"));
+    }
+}
@@ -0,0 +1,24 @@
+use anyhow::Result;
+
+use super::Compute;
+use crate::filter::Filter;
+use crate::resources::AcceleratorType;
+
+impl Compute {
+    /// `GET projects/{project}/aggregated/acceleratorTypes`, sorted by zone
+    pub async fn list_accelerator_types_all_zones(
+        &self,
+        project: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<AcceleratorType>> {
+        let mut types: Vec<AcceleratorType> = self
+            .aggregated_all(
+                &format!("projects/{project}/aggregated/acceleratorTypes"),
+                "acceleratorTypes",
+                filter,
+            )
+            .await?;
+        types.sort_by(|a, b| (a.zone_name(), &a.name).cmp(&(b.zone_name(), &b.name)));
+        Ok(types)
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use super::Compute;
+use crate::filter::Filter;
+use crate::resources::{Address, Operation};
+
+/// Address methods take the region the address lives in, or `None` for a
+/// global address.
+impl Compute {
+    /// `GET projects/{project}/regions/{region}/addresses` or
+    /// `GET projects/{project}/global/addresses`
+    pub async fn list_addresses(
+        &self,
+        project: &str,
+        region: Option<&str>,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Address>> {
+        self.list_all(&addresses_path(project, region), filter)
+            .await
+    }
+
+    /// `GET projects/{project}/aggregated/addresses`, covering every region
+    /// and global addresses, sorted by scope and name.
+    pub async fn list_addresses_everywhere(
+        &self,
+        project: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Address>> {
+        let mut addresses: Vec<Address> = self
+            .aggregated_all(
+                &format!("projects/{project}/aggregated/addresses"),
+                "addresses",
+                filter,
+            )
+            .await?;
+        addresses.sort_by(|a, b| (a.scope(), &a.name).cmp(&(b.scope(), &b.name)));
+        Ok(addresses)
+    }
+
+    /// `GET .../addresses/{name}`
+    pub async fn get_address(
+        &self,
+        project: &str,
+        region: Option<&str>,
+        name: &str,
+    ) -> Result<Address> {
+        self.get(&format!("{}/{name}", addresses_path(project, region)), &[])
+            .await
+    }
+
+    /// `POST .../addresses`
+    pub async fn insert_address(
+        &self,
+        project: &str,
+        region: Option<&str>,
+        body: &Value,
+    ) -> Result<Operation> {
+        self.post(&addresses_path(project, region), body).await
+    }
+
+    /// `DELETE .../addresses/{name}`
+    pub async fn delete_address(
+        &self,
+        project: &str,
+        region: Option<&str>,
+        name: &str,
+    ) -> Result<Operation> {
+        self.delete(&format!("{}/{name}", addresses_path(project, region)))
+            .await
+    }
+}
+
+fn addresses_path(project: &str, region: Option<&str>) -> String {
+    match region {
+        Some(region) => format!("projects/{project}/regions/{region}/addresses"),
+        None => format!("projects/{project}/global/addresses"),
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use super::{Compute, Paging};
+use crate::filter::Filter;
+use crate::labels::LabelEdit;
+use crate::resources::{Disk, Operation};
+
+impl Compute {
+    /// `GET projects/{project}/aggregated/disks`
+    pub async fn list_disks_all_zones(
+        &self,
+        project: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Disk>> {
+        self.aggregated_all(
+            &format!("projects/{project}/aggregated/disks"),
+            "disks",
+            filter,
+        )
+        .await
+    }
+
+    /// Disks in `zone` a page at a time, or in every zone as one page once
+    /// all of it is listed, sorted by zone and name.
+    pub async fn list_disk_pages(
+        &self,
+        project: &str,
+        zone: Option<&str>,
+        filter: Option<&Filter>,
+        paging: Paging,
+        mut on_page: impl FnMut(Vec<Disk>) -> Result<()>,
+    ) -> Result<()> {
+        match zone {
+            Some(zone) => {
+                self.list_pages(&disks_path(project, zone), filter, paging, on_page)
+                    .await
+            }
+            // pages of every zone come zone by zone, so the listing is
+            // held to be sorted as a whole
+            None => {
+                let mut all = Vec::new();
+                self.aggregated_pages(
+                    &format!("projects/{project}/aggregated/disks"),
+                    "disks",
+                    filter,
+                    paging,
+                    |page: Vec<Disk>| {
+                        all.extend(page);
+                        Ok(())
+                    },
+                )
+                .await?;
+                all.sort_by(|a, b| (a.zone_name(), &a.name).cmp(&(b.zone_name(), &b.name)));
+                on_page(all)
+            }
+        }
+    }
+
+    /// `GET projects/{project}/zones/{zone}/disks/{name}`
+    pub async fn get_disk(&self, project: &str, zone: &str, name: &str) -> Result<Disk> {
+        self.get(&format!("{}/{name}", disks_path(project, zone)), &[])
+            .await
+    }
+
+    /// `POST projects/{project}/zones/{zone}/disks`
+    pub async fn insert_disk(&self, project: &str, zone: &str, body: &Value) -> Result<Operation> {
+        self.post(&disks_path(project, zone), body).await
+    }
+
+    /// `DELETE .../disks/{name}`
+    pub async fn delete_disk(&self, project: &str, zone: &str, name: &str) -> Result<Operation> {
+        self.delete(&format!("{}/{name}", disks_path(project, zone)))
+            .await
+    }
+
+    /// `POST .../disks/{name}/resize`
+    pub async fn resize_disk(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        size_gb: u64,
+    ) -> Result<Operation> {
+        self.post(
+            &format!("{}/{name}/resize", disks_path(project, zone)),
+            &json!({ "sizeGb": size_gb.to_string() }),
+        )
+        .await
+    }
+
+    /// `POST .../instances/{instance}/attachDisk`
+    pub async fn attach_disk(
+        &self,
+        project: &str,
+        zone: &str,
+        instance: &str,
+        body: &Value,
+    ) -> Result<Operation> {
+        self.post(
+            &format!("projects/{project}/zones/{zone}/instances/{instance}/attachDisk"),
+            body,
+        )
+        .await
+    }
+
+    /// `POST .../instances/{instance}/detachDisk?deviceName=...`
+    pub async fn detach_disk(
+        &self,
+        project: &str,
+        zone: &str,
+        instance: &str,
+        device_name: &str,
+    ) -> Result<Operation> {
+        self.post_with_query(
+            &format!("projects/{project}/zones/{zone}/instances/{instance}/detachDisk"),
+            &[("deviceName", device_name)],
+            &json!({}),
+        )
+        .await
+    }
+
+    /// Edits a disk's labels under its label fingerprint
+    pub async fn update_disk_labels(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        edit: &LabelEdit,
+    ) -> Result<Operation> {
+        self.update_labels(&format!("{}/{name}", disks_path(project, zone)), edit)
+            .await
+    }
+}
+
+fn disks_path(project: &str, zone: &str) -> String {
+    format!("projects/{project}/zones/{zone}/disks")
+}
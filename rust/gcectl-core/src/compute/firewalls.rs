@@ -0,0 +1,49 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use super::Compute;
+use crate::filter::Filter;
+use crate::resources::{Firewall, Operation};
+
+impl Compute {
+    /// `GET projects/{project}/global/firewalls`
+    pub async fn list_firewalls(
+        &self,
+        project: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Firewall>> {
+        self.list_all(&firewalls_path(project), filter).await
+    }
+
+    /// `GET projects/{project}/global/firewalls/{name}`
+    pub async fn get_firewall(&self, project: &str, name: &str) -> Result<Firewall> {
+        self.get(&format!("{}/{name}", firewalls_path(project)), &[])
+            .await
+    }
+
+    /// `POST projects/{project}/global/firewalls`
+    pub async fn insert_firewall(&self, project: &str, body: &Value) -> Result<Operation> {
+        self.post(&firewalls_path(project), body).await
+    }
+
+    /// `PATCH projects/{project}/global/firewalls/{name}`
+    pub async fn patch_firewall(
+        &self,
+        project: &str,
+        name: &str,
+        body: &Value,
+    ) -> Result<Operation> {
+        self.patch(&format!("{}/{name}", firewalls_path(project)), body)
+            .await
+    }
+
+    /// `DELETE projects/{project}/global/firewalls/{name}`
+    pub async fn delete_firewall(&self, project: &str, name: &str) -> Result<Operation> {
+        self.delete(&format!("{}/{name}", firewalls_path(project)))
+            .await
+    }
+}
+
+fn firewalls_path(project: &str) -> String {
+    format!("projects/{project}/global/firewalls")
+}
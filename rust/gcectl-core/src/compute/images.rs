@@ -0,0 +1,61 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use super::{Compute, Revalidated};
+use crate::filter::Filter;
+use crate::resources::{Image, Operation};
+
+impl Compute {
+    /// `GET projects/{project}/global/images`
+    pub async fn list_images(&self, project: &str, filter: Option<&Filter>) -> Result<Vec<Image>> {
+        self.list_all(&images_path(project), filter).await
+    }
+
+    /// `GET projects/{project}/global/images`, unless the listing still
+    /// matches `etag`
+    pub async fn list_images_if_modified(
+        &self,
+        project: &str,
+        etag: Option<&str>,
+    ) -> Result<Revalidated<Image>> {
+        self.list_all_if_modified(&images_path(project), etag).await
+    }
+
+    /// `GET projects/{project}/global/images/{name}`
+    pub async fn get_image(&self, project: &str, name: &str) -> Result<Image> {
+        self.get(&format!("{}/{name}", images_path(project)), &[])
+            .await
+    }
+
+    /// `GET projects/{project}/global/images/family/{family}`
+    pub async fn get_image_from_family(&self, project: &str, family: &str) -> Result<Image> {
+        self.get(&format!("{}/family/{family}", images_path(project)), &[])
+            .await
+    }
+
+    /// `POST projects/{project}/global/images`
+    pub async fn insert_image(&self, project: &str, body: &Value) -> Result<Operation> {
+        self.post(&images_path(project), body).await
+    }
+
+    /// `DELETE projects/{project}/global/images/{name}`
+    pub async fn delete_image(&self, project: &str, name: &str) -> Result<Operation> {
+        self.delete(&format!("{}/{name}", images_path(project)))
+            .await
+    }
+
+    /// `POST projects/{project}/global/images/{name}/deprecate`
+    pub async fn deprecate_image(
+        &self,
+        project: &str,
+        name: &str,
+        body: &Value,
+    ) -> Result<Operation> {
+        self.post(&format!("{}/{name}/deprecate", images_path(project)), body)
+            .await
+    }
+}
+
+fn images_path(project: &str) -> String {
+    format!("projects/{project}/global/images")
+}
@@ -0,0 +1,90 @@
+use anyhow::Result;
+use serde_json::json;
+
+use super::Compute;
+use crate::resources::{BackendService, GroupMember, InstanceGroup, ListPage, Operation};
+
+impl Compute {
+    /// `GET projects/{project}/zones/{zone}/instanceGroups`, managed and
+    /// unmanaged alike.
+    pub async fn list_instance_groups(
+        &self,
+        project: &str,
+        zone: &str,
+    ) -> Result<Vec<InstanceGroup>> {
+        self.list_all(&groups_path(project, zone), None).await
+    }
+
+    /// `POST .../instanceGroups/{group}/listInstances`, every page.
+    pub async fn list_group_members(
+        &self,
+        project: &str,
+        zone: &str,
+        group: &str,
+    ) -> Result<Vec<GroupMember>> {
+        let path = format!("{}/{group}/listInstances", groups_path(project, zone));
+        let body = json!({ "instanceState": "ALL" });
+        let mut members = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let query: Vec<_> = page_token
+                .iter()
+                .map(|t| ("pageToken", t.as_str()))
+                .collect();
+            let page: ListPage<GroupMember> = self.post_unchecked(&path, &query, &body).await?;
+            members.extend(page.items);
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => break,
+            }
+        }
+        Ok(members)
+    }
+
+    /// `POST .../instanceGroups/{group}/addInstances`
+    pub async fn add_instances_to_group(
+        &self,
+        project: &str,
+        zone: &str,
+        group: &str,
+        names: &[String],
+    ) -> Result<Operation> {
+        let path = format!("{}/{group}/addInstances", groups_path(project, zone));
+        self.post(&path, &members_body(project, zone, names)).await
+    }
+
+    /// `POST .../instanceGroups/{group}/removeInstances`
+    pub async fn remove_instances_from_group(
+        &self,
+        project: &str,
+        zone: &str,
+        group: &str,
+        names: &[String],
+    ) -> Result<Operation> {
+        let path = format!("{}/{group}/removeInstances", groups_path(project, zone));
+        self.post(&path, &members_body(project, zone, names)).await
+    }
+
+    /// `GET projects/{project}/aggregated/backendServices`, global and
+    /// regional services together.
+    pub async fn list_backend_services_all(&self, project: &str) -> Result<Vec<BackendService>> {
+        self.aggregated_all(
+            &format!("projects/{project}/aggregated/backendServices"),
+            "backendServices",
+            None,
+        )
+        .await
+    }
+}
+
+fn groups_path(project: &str, zone: &str) -> String {
+    format!("projects/{project}/zones/{zone}/instanceGroups")
+}
+
+fn members_body(project: &str, zone: &str, names: &[String]) -> serde_json::Value {
+    let instances: Vec<_> = names
+        .iter()
+        .map(|name| json!({ "instance": format!("projects/{project}/zones/{zone}/instances/{name}") }))
+        .collect();
+    json!({ "instances": instances })
+}
@@ -0,0 +1,390 @@
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use super::{Compute, Paging, retry_on_conflict};
+use crate::filter::Filter;
+use crate::labels::LabelEdit;
+use crate::resources::instance::{Metadata, Scheduling, Screenshot, SerialPortOutput, Tags};
+use crate::resources::{Instance, Operation};
+
+impl Compute {
+    /// `GET projects/{project}/zones/{zone}/instances`
+    pub async fn list_instances(
+        &self,
+        project: &str,
+        zone: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Instance>> {
+        self.list_all(&instances_path(project, zone), filter).await
+    }
+
+    /// `GET projects/{project}/aggregated/instances`, covering every zone in
+    /// a single paged request.
+    pub async fn list_instances_all_zones(
+        &self,
+        project: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Instance>> {
+        self.aggregated_all(
+            &format!("projects/{project}/aggregated/instances"),
+            "instances",
+            filter,
+        )
+        .await
+    }
+
+    /// Instances in `zone`, or in every zone sorted by zone and name.
+    pub async fn list_instances_in(
+        &self,
+        project: &str,
+        zone: Option<&str>,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Instance>> {
+        match zone {
+            Some(zone) => self.list_instances(project, zone, filter).await,
+            None => {
+                let mut instances = self.list_instances_all_zones(project, filter).await?;
+                instances.sort_by(|a, b| (a.zone_name(), &a.name).cmp(&(b.zone_name(), &b.name)));
+                Ok(instances)
+            }
+        }
+    }
+
+    /// [`Compute::list_instances_in`] a page at a time. Every zone comes as
+    /// one page once all of it is listed, sorted by zone and name.
+    pub async fn list_instance_pages(
+        &self,
+        project: &str,
+        zone: Option<&str>,
+        filter: Option<&Filter>,
+        paging: Paging,
+        mut on_page: impl FnMut(Vec<Instance>) -> Result<()>,
+    ) -> Result<()> {
+        match zone {
+            Some(zone) => {
+                self.list_pages(&instances_path(project, zone), filter, paging, on_page)
+                    .await
+            }
+            // pages of every zone come zone by zone, so the listing is
+            // held to be sorted as a whole
+            None => {
+                let mut all = Vec::new();
+                self.aggregated_pages(
+                    &format!("projects/{project}/aggregated/instances"),
+                    "instances",
+                    filter,
+                    paging,
+                    |page: Vec<Instance>| {
+                        all.extend(page);
+                        Ok(())
+                    },
+                )
+                .await?;
+                all.sort_by(|a, b| (a.zone_name(), &a.name).cmp(&(b.zone_name(), &b.name)));
+                on_page(all)
+            }
+        }
+    }
+
+    /// `GET projects/{project}/zones/{zone}/instances/{name}`
+    pub async fn get_instance(&self, project: &str, zone: &str, name: &str) -> Result<Instance> {
+        self.get(&format!("{}/{name}", instances_path(project, zone)), &[])
+            .await
+    }
+
+    /// `POST projects/{project}/zones/{zone}/instances`
+    pub async fn insert_instance(
+        &self,
+        project: &str,
+        zone: &str,
+        body: &Value,
+    ) -> Result<Operation> {
+        self.post(&instances_path(project, zone), body).await
+    }
+
+    /// `DELETE .../instances/{name}`
+    pub async fn delete_instance(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+    ) -> Result<Operation> {
+        self.delete(&format!("{}/{name}", instances_path(project, zone)))
+            .await
+    }
+
+    /// `POST .../instances/{name}/setMetadata`
+    pub async fn set_instance_metadata(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        metadata: &Metadata,
+    ) -> Result<Operation> {
+        self.post(
+            &format!("{}/{name}/setMetadata", instances_path(project, zone)),
+            metadata,
+        )
+        .await
+    }
+
+    /// `POST .../instances/{name}/deleteAccessConfig`
+    pub async fn delete_access_config(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        interface: &str,
+        config: &str,
+    ) -> Result<Operation> {
+        self.post_with_query(
+            &format!(
+                "{}/{name}/deleteAccessConfig",
+                instances_path(project, zone)
+            ),
+            &[("networkInterface", interface), ("accessConfig", config)],
+            &json!({}),
+        )
+        .await
+    }
+
+    /// `POST .../instances/{name}/addAccessConfig`
+    pub async fn add_access_config(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        interface: &str,
+        config: &Value,
+    ) -> Result<Operation> {
+        self.post_with_query(
+            &format!("{}/{name}/addAccessConfig", instances_path(project, zone)),
+            &[("networkInterface", interface)],
+            config,
+        )
+        .await
+    }
+
+    /// Applies `edit` to the instance's current metadata and writes it back
+    /// under the fingerprint that was read, re-reading on conflicting writes.
+    pub async fn update_instance_metadata(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        edit: impl Fn(&mut Metadata) -> Result<()>,
+    ) -> Result<Operation> {
+        let edit = &edit;
+        retry_on_conflict(|| async move {
+            let instance = self.get_instance(project, zone, name).await?;
+            let mut metadata = instance.metadata.unwrap_or_default();
+            edit(&mut metadata)?;
+            self.set_instance_metadata(project, zone, name, &metadata)
+                .await
+        })
+        .await
+    }
+
+    /// `POST .../instances/{name}/setTags`; `tags.fingerprint` must be
+    /// current.
+    pub async fn set_instance_tags(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        tags: &Tags,
+    ) -> Result<Operation> {
+        self.post(
+            &format!("{}/{name}/setTags", instances_path(project, zone)),
+            tags,
+        )
+        .await
+    }
+
+    /// Applies `edit` to the instance's network tags and writes them back
+    /// under the fingerprint that was read, re-reading on conflicting writes.
+    pub async fn update_instance_tags(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        edit: impl Fn(&mut Tags) -> Result<()>,
+    ) -> Result<Operation> {
+        let edit = &edit;
+        retry_on_conflict(|| async move {
+            let instance = self.get_instance(project, zone, name).await?;
+            let mut tags = instance.tags.unwrap_or_default();
+            edit(&mut tags)?;
+            self.set_instance_tags(project, zone, name, &tags).await
+        })
+        .await
+    }
+
+    /// `GET .../instances/{name}/screenshot`
+    pub async fn get_screenshot(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+    ) -> Result<Screenshot> {
+        self.get(
+            &format!("{}/{name}/screenshot", instances_path(project, zone)),
+            &[],
+        )
+        .await
+    }
+
+    /// `GET .../instances/{name}/serialPort`, from byte offset `start`
+    pub async fn get_serial_port_output(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        port: u8,
+        start: u64,
+    ) -> Result<SerialPortOutput> {
+        let (port, start) = (port.to_string(), start.to_string());
+        self.get(
+            &format!("{}/{name}/serialPort", instances_path(project, zone)),
+            &[("port", port.as_str()), ("start", start.as_str())],
+        )
+        .await
+    }
+
+    /// `PUT .../instances/{name}`, replacing the instance's settings with
+    /// `instance`, whose fingerprint must be current. Shielded VM and
+    /// Confidential VM settings can only be changed while stopped.
+    pub async fn update_instance(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        instance: &Instance,
+    ) -> Result<Operation> {
+        self.put(
+            &format!("{}/{name}", instances_path(project, zone)),
+            instance,
+        )
+        .await
+    }
+
+    /// `POST .../instances/{name}/setMachineType`; the instance must be
+    /// stopped.
+    pub async fn set_machine_type(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        machine_type: &str,
+    ) -> Result<Operation> {
+        self.post(
+            &format!("{}/{name}/setMachineType", instances_path(project, zone)),
+            &json!({ "machineType": format!("zones/{zone}/machineTypes/{machine_type}") }),
+        )
+        .await
+    }
+
+    /// `POST .../instances/{name}/setScheduling`, replacing every
+    /// scheduling setting with those in `scheduling`.
+    pub async fn set_scheduling(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        scheduling: &Scheduling,
+    ) -> Result<Operation> {
+        self.post(
+            &format!("{}/{name}/setScheduling", instances_path(project, zone)),
+            &serde_json::to_value(scheduling)?,
+        )
+        .await
+    }
+
+    /// `POST .../instances/{name}/setServiceAccount`; the instance must be
+    /// stopped.
+    pub async fn set_service_account(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        email: &str,
+        scopes: &[String],
+    ) -> Result<Operation> {
+        self.post(
+            &format!("{}/{name}/setServiceAccount", instances_path(project, zone)),
+            &json!({ "email": email, "scopes": scopes }),
+        )
+        .await
+    }
+
+    /// `POST .../instances/{name}/start`
+    pub async fn start_instance(&self, project: &str, zone: &str, name: &str) -> Result<Operation> {
+        self.post(
+            &format!("{}/{name}/start", instances_path(project, zone)),
+            &json!({}),
+        )
+        .await
+    }
+
+    /// `POST .../instances/{name}/stop`
+    pub async fn stop_instance(&self, project: &str, zone: &str, name: &str) -> Result<Operation> {
+        self.post(
+            &format!("{}/{name}/stop", instances_path(project, zone)),
+            &json!({}),
+        )
+        .await
+    }
+
+    /// `POST .../instances/{name}/reset`, a hard power cycle
+    pub async fn reset_instance(&self, project: &str, zone: &str, name: &str) -> Result<Operation> {
+        self.post(
+            &format!("{}/{name}/reset", instances_path(project, zone)),
+            &json!({}),
+        )
+        .await
+    }
+
+    /// `POST .../instances/{name}/suspend`
+    pub async fn suspend_instance(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+    ) -> Result<Operation> {
+        self.post(
+            &format!("{}/{name}/suspend", instances_path(project, zone)),
+            &json!({}),
+        )
+        .await
+    }
+
+    /// `POST .../instances/{name}/resume`
+    pub async fn resume_instance(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+    ) -> Result<Operation> {
+        self.post(
+            &format!("{}/{name}/resume", instances_path(project, zone)),
+            &json!({}),
+        )
+        .await
+    }
+
+    /// Edits an instance's labels under its label fingerprint
+    pub async fn update_instance_labels(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        edit: &LabelEdit,
+    ) -> Result<Operation> {
+        self.update_labels(&format!("{}/{name}", instances_path(project, zone)), edit)
+            .await
+    }
+}
+
+fn instances_path(project: &str, zone: &str) -> String {
+    format!("projects/{project}/zones/{zone}/instances")
+}
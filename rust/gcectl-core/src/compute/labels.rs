@@ -0,0 +1,19 @@
+use anyhow::Result;
+
+use super::{Compute, retry_on_conflict};
+use crate::labels::{LabelEdit, Labels};
+use crate::resources::Operation;
+
+impl Compute {
+    /// Applies `edit` to the labels of the resource at `path` (an instance,
+    /// disk, or snapshot) and writes them back through `{path}/setLabels`
+    /// under the fingerprint that was read, re-reading on conflicting writes.
+    pub(super) async fn update_labels(&self, path: &str, edit: &LabelEdit) -> Result<Operation> {
+        retry_on_conflict(|| async move {
+            let mut labels: Labels = self.get(path, &[]).await?;
+            edit.apply(&mut labels.labels)?;
+            self.post(&format!("{path}/setLabels"), &labels).await
+        })
+        .await
+    }
+}
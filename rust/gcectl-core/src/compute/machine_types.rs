@@ -0,0 +1,67 @@
+use anyhow::Result;
+
+use super::{Compute, Revalidated};
+use crate::filter::Filter;
+use crate::resources::MachineType;
+
+impl Compute {
+    /// `GET projects/{project}/zones/{zone}/machineTypes`
+    pub async fn list_machine_types(
+        &self,
+        project: &str,
+        zone: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<MachineType>> {
+        self.list_all(
+            &format!("projects/{project}/zones/{zone}/machineTypes"),
+            filter,
+        )
+        .await
+    }
+
+    /// `GET projects/{project}/zones/{zone}/machineTypes`, unless the listing
+    /// still matches `etag`
+    pub async fn list_machine_types_if_modified(
+        &self,
+        project: &str,
+        zone: &str,
+        etag: Option<&str>,
+    ) -> Result<Revalidated<MachineType>> {
+        self.list_all_if_modified(
+            &format!("projects/{project}/zones/{zone}/machineTypes"),
+            etag,
+        )
+        .await
+    }
+
+    /// `GET projects/{project}/aggregated/machineTypes`, sorted by zone
+    pub async fn list_machine_types_all_zones(
+        &self,
+        project: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<MachineType>> {
+        let mut types: Vec<MachineType> = self
+            .aggregated_all(
+                &format!("projects/{project}/aggregated/machineTypes"),
+                "machineTypes",
+                filter,
+            )
+            .await?;
+        types.sort_by(|a, b| (a.zone_name(), &a.name).cmp(&(b.zone_name(), &b.name)));
+        Ok(types)
+    }
+
+    /// `GET projects/{project}/zones/{zone}/machineTypes/{name}`
+    pub async fn get_machine_type(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+    ) -> Result<MachineType> {
+        self.get(
+            &format!("projects/{project}/zones/{zone}/machineTypes/{name}"),
+            &[],
+        )
+        .await
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use super::Compute;
+use crate::filter::Filter;
+use crate::resources::{InstanceGroupManager, Operation};
+
+impl Compute {
+    /// `GET projects/{project}/zones/{zone}/instanceGroupManagers`
+    pub async fn list_migs(
+        &self,
+        project: &str,
+        zone: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<InstanceGroupManager>> {
+        self.list_all(&migs_path(project, zone), filter).await
+    }
+
+    /// `GET projects/{project}/aggregated/instanceGroupManagers`
+    pub async fn list_migs_all_zones(
+        &self,
+        project: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<InstanceGroupManager>> {
+        self.aggregated_all(
+            &format!("projects/{project}/aggregated/instanceGroupManagers"),
+            "instanceGroupManagers",
+            filter,
+        )
+        .await
+    }
+
+    /// `GET .../instanceGroupManagers/{name}`
+    pub async fn get_mig(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+    ) -> Result<InstanceGroupManager> {
+        self.get(&format!("{}/{name}", migs_path(project, zone)), &[])
+            .await
+    }
+
+    /// `POST projects/{project}/zones/{zone}/instanceGroupManagers`
+    pub async fn insert_mig(&self, project: &str, zone: &str, body: &Value) -> Result<Operation> {
+        self.post(&migs_path(project, zone), body).await
+    }
+
+    /// `PATCH .../instanceGroupManagers/{name}`
+    pub async fn patch_mig(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        body: &Value,
+    ) -> Result<Operation> {
+        self.patch(&format!("{}/{name}", migs_path(project, zone)), body)
+            .await
+    }
+
+    /// `POST .../instanceGroupManagers/{name}/resize?size={size}`
+    pub async fn resize_mig(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        size: u32,
+    ) -> Result<Operation> {
+        self.post_with_query(
+            &format!("{}/{name}/resize", migs_path(project, zone)),
+            &[("size", size.to_string().as_str())],
+            &json!({}),
+        )
+        .await
+    }
+
+    /// `DELETE .../instanceGroupManagers/{name}`
+    pub async fn delete_mig(&self, project: &str, zone: &str, name: &str) -> Result<Operation> {
+        self.delete(&format!("{}/{name}", migs_path(project, zone)))
+            .await
+    }
+}
+
+fn migs_path(project: &str, zone: &str) -> String {
+    format!("projects/{project}/zones/{zone}/instanceGroupManagers")
+}
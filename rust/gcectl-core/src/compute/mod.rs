@@ -0,0 +1,467 @@
+//! Thin client for the Compute Engine v1 REST API.
+//!
+//! Every write goes through `post`, `put`, `patch`, or `delete`; in dry-run mode
+//! those print the request instead of sending it, while reads still reach
+//! the API so commands can resolve what they would change. Writes that are
+//! sent are recorded in the audit log.
+
+mod accelerators;
+mod addresses;
+mod disks;
+mod firewalls;
+mod images;
+mod instance_groups;
+mod instances;
+mod labels;
+mod machine_types;
+mod migs;
+mod networks;
+mod nodes;
+mod operations;
+mod projects;
+mod reservations;
+mod resource_policies;
+mod snapshots;
+mod templates;
+mod zones;
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use reqwest::StatusCode;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use tracing::{debug, info, trace};
+use uuid::Uuid;
+
+use crate::audit::{self, Event};
+use crate::auth::Authenticator;
+use crate::cancel::Deadline;
+use crate::error::{self, GcectlError};
+use crate::events;
+use crate::filter::Filter;
+use crate::logging::{self, HTTP_TARGET};
+use crate::resources::{AggregatedPage, ListPage};
+use crate::transport::Transport;
+
+pub const COMPUTE_ENDPOINT: &str = "https://compute.googleapis.com/compute/v1";
+
+/// The query parameter the API deduplicates writes by.
+pub const REQUEST_ID: &str = "requestId";
+
+/// Compute Engine API client; every request is authorized through [`Authenticator`].
+pub struct Compute {
+    http: Transport,
+    auth: Arc<Authenticator>,
+    endpoint: String,
+    dry_run: bool,
+    deadline: Option<Deadline>,
+}
+
+impl Compute {
+    pub fn new(http: Transport, auth: Arc<Authenticator>) -> Self {
+        Self {
+            http,
+            auth,
+            endpoint: COMPUTE_ENDPOINT.to_string(),
+            dry_run: false,
+            deadline: None,
+        }
+    }
+
+    /// Sends requests to `endpoint` instead of the public API, if given.
+    pub fn endpoint(mut self, endpoint: Option<&str>) -> Self {
+        if let Some(endpoint) = endpoint {
+            self.endpoint = endpoint.trim_end_matches('/').to_string();
+        }
+        self
+    }
+
+    /// Prints writes instead of sending them; each answers with a finished
+    /// placeholder operation so callers carry on as if it succeeded.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Operation waits give up at `deadline`, as they do on Ctrl-C.
+    pub fn deadline(mut self, deadline: Option<Deadline>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.endpoint, path.trim_start_matches('/'))
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, &str)]) -> Result<T> {
+        let url = self.url(path);
+        info!("GET {url} {query:?}");
+        self.send(self.http.get(&url).query(query)).await
+    }
+
+    async fn post<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> Result<T> {
+        self.post_with_query(path, &[], body).await
+    }
+
+    async fn post_with_query<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        body: &impl Serialize,
+    ) -> Result<T> {
+        let url = self.url(path);
+        if self.dry_run {
+            return print_request("POST", &url, query, Some(json!(body)));
+        }
+        info!("POST {url} {query:?}");
+        self.send_write("POST", path, self.http.post(&url).query(query).json(body))
+            .await
+    }
+
+    /// `post_with_query` for POSTs that only read, such as `operations.wait`,
+    /// which dry-run mode lets through.
+    async fn post_unchecked<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        body: &impl Serialize,
+    ) -> Result<T> {
+        let url = self.url(path);
+        info!("POST {url} {query:?}");
+        self.send(self.http.post(&url).query(query).json(body))
+            .await
+    }
+
+    async fn patch<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> Result<T> {
+        let url = self.url(path);
+        if self.dry_run {
+            return print_request("PATCH", &url, &[], Some(json!(body)));
+        }
+        info!("PATCH {url}");
+        self.send_write("PATCH", path, self.http.patch(&url).json(body))
+            .await
+    }
+
+    async fn put<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> Result<T> {
+        let url = self.url(path);
+        if self.dry_run {
+            return print_request("PUT", &url, &[], Some(json!(body)));
+        }
+        info!("PUT {url}");
+        self.send_write("PUT", path, self.http.put(&url).json(body))
+            .await
+    }
+
+    async fn delete<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let url = self.url(path);
+        if self.dry_run {
+            return print_request("DELETE", &url, &[], None);
+        }
+        info!("DELETE {url}");
+        self.send_write("DELETE", path, self.http.delete(&url))
+            .await
+    }
+
+    /// Sends a write and records it, with the operation it started, in the
+    /// audit log. The write carries a `requestId`, the same across the
+    /// transport's retries, so the API carries out a retried write once.
+    async fn send_write<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T> {
+        let request = request.query(&[(REQUEST_ID, Uuid::new_v4().to_string())]);
+        let result: Result<Value> = self.send(request).await;
+        let entry = match &result {
+            Ok(op) => audit::Entry::new(Event::Request, method, path, "requested".into())
+                .operation(op["name"].as_str()),
+            Err(err) => audit::Entry::new(Event::Request, method, path, format!("failed: {err:#}")),
+        };
+        audit::record(&entry);
+        Ok(serde_json::from_value(result?)?)
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+        let resp = self
+            .http
+            .send(request.bearer_auth(self.auth.token().await?))
+            .await
+            .context("request to Compute API failed")?;
+        parse_response(resp, "Compute API").await
+    }
+
+    /// Fetches every page of a `*.list` endpoint, optionally narrowed
+    /// server-side by `filter`.
+    async fn list_all<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        self.list_pages(path, filter, Paging::default(), |page| {
+            items.extend(page);
+            Ok(())
+        })
+        .await?;
+        Ok(items)
+    }
+
+    /// Hands each page of a `*.list` endpoint to `on_page` as it arrives,
+    /// stopping once `paging.limit` items have been seen.
+    async fn list_pages<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        filter: Option<&Filter>,
+        paging: Paging,
+        mut on_page: impl FnMut(Vec<T>) -> Result<()>,
+    ) -> Result<()> {
+        let filter = filter.map(Filter::to_api);
+        let mut remaining = paging.limit.map(|limit| limit as usize);
+        let mut page_token: Option<String> = None;
+        loop {
+            let max_results = paging.max_results(remaining);
+            let mut query = Vec::new();
+            if let Some(filter) = filter.as_deref() {
+                query.push(("filter", filter));
+            }
+            if let Some(max_results) = max_results.as_deref() {
+                query.push(("maxResults", max_results));
+            }
+            if let Some(token) = page_token.as_deref() {
+                query.push(("pageToken", token));
+            }
+            let mut page: ListPage<T> = self.get(path, &query).await?;
+            take(&mut page.items, &mut remaining);
+            on_page(page.items)?;
+            match page.next_page_token {
+                Some(token) if !token.is_empty() && remaining != Some(0) => {
+                    page_token = Some(token)
+                }
+                _ => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// [`Compute::list_all`] for a catalog the caller may hold a copy of,
+    /// asking the API to answer `304 Not Modified` if `etag` still matches.
+    /// Only single-page listings come back with an ETag, since it covers
+    /// no more than the page it was sent with.
+    async fn list_all_if_modified<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        etag: Option<&str>,
+    ) -> Result<Revalidated<T>> {
+        let url = self.url(path);
+        info!("GET {url} (If-None-Match: {etag:?})");
+        let mut request = self.http.get(&url);
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        let resp = self
+            .http
+            .send(request.bearer_auth(self.auth.token().await?))
+            .await
+            .context("request to Compute API failed")?;
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            debug!("{path} not modified");
+            return Ok(Revalidated::NotModified);
+        }
+        let mut etag = resp
+            .headers()
+            .get(ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let mut page: ListPage<T> = parse_response(resp, "Compute API").await?;
+        let mut items = page.items;
+        while let Some(token) = page.next_page_token.filter(|t| !t.is_empty()) {
+            etag = None;
+            page = self.get(path, &[("pageToken", &token)]).await?;
+            items.append(&mut page.items);
+        }
+        Ok(Revalidated::Modified { items, etag })
+    }
+
+    /// Fetches every page of an `*.aggregatedList` endpoint, flattening all
+    /// scopes into one list. `key` names the resource array in each scope.
+    async fn aggregated_all<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        key: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        self.aggregated_pages(path, key, filter, Paging::default(), |page| {
+            items.extend(page);
+            Ok(())
+        })
+        .await?;
+        Ok(items)
+    }
+
+    /// [`Compute::list_pages`] for an `*.aggregatedList` endpoint; each page
+    /// arrives with its scopes flattened.
+    async fn aggregated_pages<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        key: &str,
+        filter: Option<&Filter>,
+        paging: Paging,
+        mut on_page: impl FnMut(Vec<T>) -> Result<()>,
+    ) -> Result<()> {
+        let filter = filter.map(Filter::to_api);
+        let mut remaining = paging.limit.map(|limit| limit as usize);
+        let mut page_token: Option<String> = None;
+        loop {
+            let max_results = paging.max_results(remaining);
+            let mut query = vec![("returnPartialSuccess", "true")];
+            if let Some(filter) = filter.as_deref() {
+                query.push(("filter", filter));
+            }
+            if let Some(max_results) = max_results.as_deref() {
+                query.push(("maxResults", max_results));
+            }
+            if let Some(token) = page_token.as_deref() {
+                query.push(("pageToken", token));
+            }
+            let page: AggregatedPage = self.get(path, &query).await?;
+            let mut items = Vec::new();
+            for (scope, mut list) in page.items {
+                if let Some(resources) = list.remove(key) {
+                    let resources: Vec<T> = serde_json::from_value(resources)
+                        .with_context(|| format!("failed to decode {key} in {scope}"))?;
+                    items.extend(resources);
+                }
+            }
+            take(&mut items, &mut remaining);
+            on_page(items)?;
+            match page.next_page_token {
+                Some(token) if !token.is_empty() && remaining != Some(0) => {
+                    page_token = Some(token)
+                }
+                _ => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A catalog listing fetched against the ETag of a copy the caller holds.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Revalidated<T> {
+    /// The held copy is still current.
+    NotModified,
+    /// What the listing holds now, and the ETag to revalidate it with next
+    /// time, if it has one.
+    Modified { items: Vec<T>, etag: Option<String> },
+}
+
+/// How much of a listing to fetch at a time, and in all.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Paging {
+    // `maxResults` per request; the API's own default is its maximum of 500
+    pub page_size: Option<u32>,
+    // items after which listing stops
+    pub limit: Option<u32>,
+}
+
+impl Paging {
+    // `maxResults` for the next request while `remaining` items are wanted,
+    // so a small limit is met by one short page
+    fn max_results(&self, remaining: Option<usize>) -> Option<String> {
+        let remaining = remaining.map(|r| u32::try_from(r).unwrap_or(u32::MAX));
+        match (self.page_size, remaining) {
+            (Some(size), Some(remaining)) => Some(size.min(remaining)),
+            (size, remaining) => size.or(remaining.filter(|&r| r < MAX_PAGE_SIZE)),
+        }
+        .map(|n| n.to_string())
+    }
+}
+
+const MAX_PAGE_SIZE: u32 = 500;
+
+// drops what `remaining` has no room for and counts the rest against it
+fn take<T>(items: &mut Vec<T>, remaining: &mut Option<usize>) {
+    if let Some(remaining) = remaining {
+        items.truncate(*remaining);
+        *remaining -= items.len();
+    }
+}
+
+/// Prints a write dry-run mode held back and returns the placeholder
+/// operation standing in for its response.
+fn print_request<T: DeserializeOwned>(
+    method: &str,
+    url: &str,
+    query: &[(&str, &str)],
+    body: Option<Value>,
+) -> Result<T> {
+    let mut url = reqwest::Url::parse_with_params(url, query).context("invalid request URL")?;
+    if query.is_empty() {
+        url.set_query(None);
+    }
+    print_dry_run(method, url.as_str(), body.as_ref())?;
+    serde_json::from_value(json!({ "name": "dry-run", "status": "DONE" }))
+        .context("this command cannot run in dry-run mode")
+}
+
+/// Prints a write held back as dry-run mode does, for callers previewing
+/// one without a client.
+pub fn print_dry_run(method: &str, url: &str, body: Option<&Value>) -> Result<()> {
+    let body = body.filter(|b| b.as_object().is_none_or(|o| !o.is_empty()));
+    if events::enabled() {
+        events::emit(&events::Event::DryRun { method, url, body });
+        return Ok(());
+    }
+    println!("[DRY-RUN] | {method} {url}");
+    if let Some(body) = body {
+        println!("{}", serde_json::to_string_pretty(body)?);
+    }
+    Ok(())
+}
+
+/// Attempts at a read-modify-write before a fingerprint conflict is reported.
+const FINGERPRINT_ATTEMPTS: u32 = 3;
+
+/// Runs the read-modify-write `update`, replaying it when the API rejects the
+/// write's fingerprint because the resource changed after it was read.
+async fn retry_on_conflict<T, F, Fut>(update: F) -> Result<T>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match update().await {
+            Err(err)
+                if attempt < FINGERPRINT_ATTEMPTS
+                    && has_status(&err, StatusCode::PRECONDITION_FAILED) =>
+            {
+                debug!("fingerprint conflict, retrying: {err:#}");
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether `err` is an API error with `status`.
+pub fn has_status(err: &anyhow::Error, status: StatusCode) -> bool {
+    error::find(err).is_some_and(|e| e.status() == Some(status))
+}
+
+/// Decodes a Google API JSON response, turning error statuses into a
+/// [`GcectlError`] classified from the standard `{"error": {...}}` body.
+pub async fn parse_response<T: DeserializeOwned>(resp: reqwest::Response, api: &str) -> Result<T> {
+    let status = resp.status();
+    let body = resp.text().await.context("failed to read response body")?;
+    trace!(target: HTTP_TARGET, "< body: {}", logging::redact_body(&body));
+    if !status.is_success() {
+        return Err(GcectlError::from_response(api, status, body).into());
+    }
+    serde_json::from_str(&body).with_context(|| format!("failed to decode {api} response"))
+}
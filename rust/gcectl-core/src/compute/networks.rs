@@ -0,0 +1,66 @@
+use anyhow::Result;
+
+use super::Compute;
+use crate::filter::Filter;
+use crate::resources::{Network, Subnetwork};
+
+impl Compute {
+    /// `GET projects/{project}/global/networks`
+    pub async fn list_networks(
+        &self,
+        project: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Network>> {
+        self.list_all(&format!("projects/{project}/global/networks"), filter)
+            .await
+    }
+
+    /// `GET projects/{project}/global/networks/{name}`
+    pub async fn get_network(&self, project: &str, name: &str) -> Result<Network> {
+        self.get(&format!("projects/{project}/global/networks/{name}"), &[])
+            .await
+    }
+
+    /// `GET projects/{project}/regions/{region}/subnetworks`
+    pub async fn list_subnets(
+        &self,
+        project: &str,
+        region: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Subnetwork>> {
+        self.list_all(&subnets_path(project, region), filter).await
+    }
+
+    /// Subnets in `region`, or in every region sorted by region and name.
+    pub async fn list_subnets_in(
+        &self,
+        project: &str,
+        region: Option<&str>,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Subnetwork>> {
+        match region {
+            Some(region) => self.list_subnets(project, region, filter).await,
+            None => {
+                let mut subnets: Vec<Subnetwork> = self
+                    .aggregated_all(
+                        &format!("projects/{project}/aggregated/subnetworks"),
+                        "subnetworks",
+                        filter,
+                    )
+                    .await?;
+                subnets.sort_by(|a, b| (a.region_name(), &a.name).cmp(&(b.region_name(), &b.name)));
+                Ok(subnets)
+            }
+        }
+    }
+
+    /// `GET projects/{project}/regions/{region}/subnetworks/{name}`
+    pub async fn get_subnet(&self, project: &str, region: &str, name: &str) -> Result<Subnetwork> {
+        self.get(&format!("{}/{name}", subnets_path(project, region)), &[])
+            .await
+    }
+}
+
+fn subnets_path(project: &str, region: &str) -> String {
+    format!("projects/{project}/regions/{region}/subnetworks")
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use super::Compute;
+use crate::filter::Filter;
+use crate::resources::{NodeGroup, NodeTemplate, Operation};
+
+impl Compute {
+    /// `GET projects/{project}/regions/{region}/nodeTemplates`, or the
+    /// aggregated list sorted by region and name when `region` is `None`.
+    pub async fn list_node_templates(
+        &self,
+        project: &str,
+        region: Option<&str>,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<NodeTemplate>> {
+        match region {
+            Some(region) => {
+                self.list_all(&templates_path(project, region), filter)
+                    .await
+            }
+            None => {
+                let mut templates: Vec<NodeTemplate> = self
+                    .aggregated_all(
+                        &format!("projects/{project}/aggregated/nodeTemplates"),
+                        "nodeTemplates",
+                        filter,
+                    )
+                    .await?;
+                templates
+                    .sort_by(|a, b| (a.region_name(), &a.name).cmp(&(b.region_name(), &b.name)));
+                Ok(templates)
+            }
+        }
+    }
+
+    /// `GET .../nodeTemplates/{name}`
+    pub async fn get_node_template(
+        &self,
+        project: &str,
+        region: &str,
+        name: &str,
+    ) -> Result<NodeTemplate> {
+        self.get(&format!("{}/{name}", templates_path(project, region)), &[])
+            .await
+    }
+
+    /// `POST .../nodeTemplates`
+    pub async fn insert_node_template(
+        &self,
+        project: &str,
+        region: &str,
+        body: &Value,
+    ) -> Result<Operation> {
+        self.post(&templates_path(project, region), body).await
+    }
+
+    /// `DELETE .../nodeTemplates/{name}`; fails while a node group uses it.
+    pub async fn delete_node_template(
+        &self,
+        project: &str,
+        region: &str,
+        name: &str,
+    ) -> Result<Operation> {
+        self.delete(&format!("{}/{name}", templates_path(project, region)))
+            .await
+    }
+
+    /// `GET projects/{project}/zones/{zone}/nodeGroups`, or the aggregated
+    /// list sorted by zone and name when `zone` is `None`.
+    pub async fn list_node_groups(
+        &self,
+        project: &str,
+        zone: Option<&str>,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<NodeGroup>> {
+        match zone {
+            Some(zone) => self.list_all(&groups_path(project, zone), filter).await,
+            None => {
+                let mut groups: Vec<NodeGroup> = self
+                    .aggregated_all(
+                        &format!("projects/{project}/aggregated/nodeGroups"),
+                        "nodeGroups",
+                        filter,
+                    )
+                    .await?;
+                groups.sort_by(|a, b| (a.zone_name(), &a.name).cmp(&(b.zone_name(), &b.name)));
+                Ok(groups)
+            }
+        }
+    }
+
+    /// `GET .../nodeGroups/{name}`
+    pub async fn get_node_group(&self, project: &str, zone: &str, name: &str) -> Result<NodeGroup> {
+        self.get(&format!("{}/{name}", groups_path(project, zone)), &[])
+            .await
+    }
+
+    /// `POST .../nodeGroups?initialNodeCount=N`; nodes are billed from
+    /// creation whether or not instances run on them.
+    pub async fn insert_node_group(
+        &self,
+        project: &str,
+        zone: &str,
+        body: &Value,
+        nodes: u32,
+    ) -> Result<Operation> {
+        let nodes = nodes.to_string();
+        self.post_with_query(
+            &groups_path(project, zone),
+            &[("initialNodeCount", nodes.as_str())],
+            body,
+        )
+        .await
+    }
+
+    /// `DELETE .../nodeGroups/{name}`; fails while instances run on it.
+    pub async fn delete_node_group(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+    ) -> Result<Operation> {
+        self.delete(&format!("{}/{name}", groups_path(project, zone)))
+            .await
+    }
+}
+
+fn templates_path(project: &str, region: &str) -> String {
+    format!("projects/{project}/regions/{region}/nodeTemplates")
+}
+
+fn groups_path(project: &str, zone: &str) -> String {
+    format!("projects/{project}/zones/{zone}/nodeGroups")
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use serde_json::json;
+use tracing::debug;
+
+use super::Compute;
+use crate::audit::{self, Event};
+use crate::cancel;
+use crate::error::GcectlError;
+use crate::events;
+use crate::filter::Filter;
+use crate::resources::Operation;
+use crate::resources::operation::OperationScope;
+
+// pause between server-side waits, which return after at most two minutes
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+impl Compute {
+    /// Operations in one scope.
+    pub async fn list_operations(
+        &self,
+        project: &str,
+        scope: &OperationScope,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Operation>> {
+        self.list_all(&scope.path(project), filter).await
+    }
+
+    /// `GET projects/{project}/aggregated/operations`, covering every zone,
+    /// region, and the global scope.
+    pub async fn list_operations_all_scopes(
+        &self,
+        project: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Operation>> {
+        self.aggregated_all(
+            &format!("projects/{project}/aggregated/operations"),
+            "operations",
+            filter,
+        )
+        .await
+    }
+
+    /// Fetches an operation by name, searching every scope when `scope` is
+    /// not known.
+    pub async fn get_operation(
+        &self,
+        project: &str,
+        scope: Option<&OperationScope>,
+        name: &str,
+    ) -> Result<Operation> {
+        if let Some(scope) = scope {
+            return self
+                .get(&format!("{}/{name}", scope.path(project)), &[])
+                .await;
+        }
+        let filter: Filter = format!("name={name}").parse()?;
+        self.list_operations_all_scopes(project, Some(&filter))
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("operation {name} not found in project {project}"))
+    }
+
+    /// Blocks server-side until the operation is DONE or about two minutes
+    /// have passed, whichever comes first.
+    async fn await_operation(&self, op: &Operation) -> Result<Operation> {
+        self.post_unchecked(&format!("{}/wait", op.path()), &[], &json!({}))
+            .await
+    }
+
+    /// Waits until `op` is DONE, failing if the operation reports errors or
+    /// the wait is cancelled by Ctrl-C or the deadline.
+    pub async fn wait_operation(&self, mut op: Operation) -> Result<Operation> {
+        let target = op
+            .target_link
+            .strip_prefix(&format!("{}/", self.endpoint))
+            .unwrap_or(&op.target_link)
+            .to_string();
+        events::emit(&events::Event::OperationStarted {
+            operation: &op.name,
+            operation_type: &op.operation_type,
+            target: &target,
+        });
+        while !op.is_done() {
+            let name = op.name.clone();
+            let poll = async {
+                let op = self.await_operation(&op).await?;
+                debug!("operation {} is {}", op.name, op.status);
+                if !op.is_done() {
+                    events::emit(&events::Event::Polling {
+                        operation: &op.name,
+                        status: &op.status,
+                        progress: op.progress,
+                    });
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+                anyhow::Ok(op)
+            };
+            op = tokio::select! {
+                biased;
+                err = cancel::cancelled(self.deadline, Some(&name)) => return Err(err.into()),
+                op = poll => op?,
+            };
+        }
+        let error = op.error_message();
+        events::emit(&events::Event::OperationDone {
+            operation: &op.name,
+            operation_type: &op.operation_type,
+            target: &target,
+            error: error.as_deref(),
+        });
+        if !self.dry_run {
+            let result = match error {
+                Some(message) => format!("failed: {message}"),
+                None => "done".to_string(),
+            };
+            let entry = audit::Entry::new(Event::Operation, &op.operation_type, &target, result)
+                .operation(Some(&op.name));
+            audit::record(&entry);
+        }
+        if let Some(error) = op.error.as_ref().filter(|e| !e.errors.is_empty()) {
+            return Err(GcectlError::from_operation(&op.name, &error.errors).into());
+        }
+        Ok(op)
+    }
+}
@@ -0,0 +1,74 @@
+use anyhow::Result;
+
+use super::{Compute, retry_on_conflict};
+use crate::resources::instance::Metadata;
+use crate::resources::project::UsageExportLocation;
+use crate::resources::{Operation, Project};
+
+impl Compute {
+    /// `GET projects/{project}`
+    pub async fn get_project(&self, project: &str) -> Result<Project> {
+        self.get(&format!("projects/{project}"), &[]).await
+    }
+
+    /// `POST projects/{project}/setCommonInstanceMetadata`
+    pub async fn set_common_instance_metadata(
+        &self,
+        project: &str,
+        metadata: &Metadata,
+    ) -> Result<Operation> {
+        self.post(
+            &format!("projects/{project}/setCommonInstanceMetadata"),
+            metadata,
+        )
+        .await
+    }
+
+    /// `POST projects/{project}/setUsageExportBucket`
+    pub async fn set_usage_export_bucket(
+        &self,
+        project: &str,
+        location: &UsageExportLocation,
+    ) -> Result<Operation> {
+        self.post(
+            &format!("projects/{project}/setUsageExportBucket"),
+            location,
+        )
+        .await
+    }
+
+    /// `POST projects/{project}/setDefaultServiceAccount`, which only the
+    /// beta API offers; the path steps from `compute/v1` over to it.
+    pub async fn set_default_service_account(
+        &self,
+        project: &str,
+        email: &str,
+    ) -> Result<Operation> {
+        self.post(
+            &format!("../beta/projects/{project}/setDefaultServiceAccount"),
+            &serde_json::json!({ "email": email }),
+        )
+        .await
+    }
+
+    /// Applies `edit` to the project's common instance metadata and writes it
+    /// back under the fingerprint that was read, re-reading on conflicting
+    /// writes.
+    pub async fn update_common_instance_metadata(
+        &self,
+        project: &str,
+        edit: impl Fn(&mut Metadata) -> Result<()>,
+    ) -> Result<Operation> {
+        let edit = &edit;
+        retry_on_conflict(|| async move {
+            let mut metadata = self
+                .get_project(project)
+                .await?
+                .common_instance_metadata
+                .unwrap_or_default();
+            edit(&mut metadata)?;
+            self.set_common_instance_metadata(project, &metadata).await
+        })
+        .await
+    }
+}
@@ -0,0 +1,61 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use super::Compute;
+use crate::filter::Filter;
+use crate::resources::{Operation, Reservation};
+
+impl Compute {
+    /// `GET projects/{project}/zones/{zone}/reservations`, or the aggregated
+    /// list sorted by zone and name when `zone` is `None`.
+    pub async fn list_reservations(
+        &self,
+        project: &str,
+        zone: Option<&str>,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Reservation>> {
+        match zone {
+            Some(zone) => {
+                self.list_all(&reservations_path(project, zone), filter)
+                    .await
+            }
+            None => {
+                let mut reservations: Vec<Reservation> = self
+                    .aggregated_all(
+                        &format!("projects/{project}/aggregated/reservations"),
+                        "reservations",
+                        filter,
+                    )
+                    .await?;
+                reservations
+                    .sort_by(|a, b| (a.zone_name(), &a.name).cmp(&(b.zone_name(), &b.name)));
+                Ok(reservations)
+            }
+        }
+    }
+
+    /// `GET .../reservations/{name}`
+    pub async fn get_reservation(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+    ) -> Result<Reservation> {
+        self.get(&format!("{}/{name}", reservations_path(project, zone)), &[])
+            .await
+    }
+
+    /// `POST .../reservations`; the capacity is billed from creation.
+    pub async fn insert_reservation(
+        &self,
+        project: &str,
+        zone: &str,
+        body: &Value,
+    ) -> Result<Operation> {
+        self.post(&reservations_path(project, zone), body).await
+    }
+}
+
+fn reservations_path(project: &str, zone: &str) -> String {
+    format!("projects/{project}/zones/{zone}/reservations")
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use super::Compute;
+use crate::filter::Filter;
+use crate::resources::{Operation, ResourcePolicy};
+
+impl Compute {
+    /// `GET projects/{project}/regions/{region}/resourcePolicies`, or the
+    /// aggregated list sorted by region and name when `region` is `None`.
+    pub async fn list_resource_policies(
+        &self,
+        project: &str,
+        region: Option<&str>,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<ResourcePolicy>> {
+        match region {
+            Some(region) => self.list_all(&policies_path(project, region), filter).await,
+            None => {
+                let mut policies: Vec<ResourcePolicy> = self
+                    .aggregated_all(
+                        &format!("projects/{project}/aggregated/resourcePolicies"),
+                        "resourcePolicies",
+                        filter,
+                    )
+                    .await?;
+                policies
+                    .sort_by(|a, b| (a.region_name(), &a.name).cmp(&(b.region_name(), &b.name)));
+                Ok(policies)
+            }
+        }
+    }
+
+    /// `GET .../resourcePolicies/{name}`
+    pub async fn get_resource_policy(
+        &self,
+        project: &str,
+        region: &str,
+        name: &str,
+    ) -> Result<ResourcePolicy> {
+        self.get(&format!("{}/{name}", policies_path(project, region)), &[])
+            .await
+    }
+
+    /// `POST .../resourcePolicies`
+    pub async fn insert_resource_policy(
+        &self,
+        project: &str,
+        region: &str,
+        body: &Value,
+    ) -> Result<Operation> {
+        self.post(&policies_path(project, region), body).await
+    }
+
+    /// `DELETE .../resourcePolicies/{name}`; fails while the policy is
+    /// attached to anything.
+    pub async fn delete_resource_policy(
+        &self,
+        project: &str,
+        region: &str,
+        name: &str,
+    ) -> Result<Operation> {
+        self.delete(&format!("{}/{name}", policies_path(project, region)))
+            .await
+    }
+
+    /// `POST projects/{project}/zones/{zone}/{collection}/{name}/addResourcePolicies`
+    /// or `removeResourcePolicies`, for `disks` or `instances`.
+    pub async fn set_resource_policy(
+        &self,
+        project: &str,
+        zone: &str,
+        collection: &str,
+        name: &str,
+        policy: &str,
+        attach: bool,
+    ) -> Result<Operation> {
+        let method = match attach {
+            true => "addResourcePolicies",
+            false => "removeResourcePolicies",
+        };
+        self.post(
+            &format!("projects/{project}/zones/{zone}/{collection}/{name}/{method}"),
+            &json!({ "resourcePolicies": [policy] }),
+        )
+        .await
+    }
+}
+
+fn policies_path(project: &str, region: &str) -> String {
+    format!("projects/{project}/regions/{region}/resourcePolicies")
+}
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use super::{Compute, Paging};
+use crate::filter::Filter;
+use crate::labels::LabelEdit;
+use crate::resources::{Operation, Snapshot};
+
+impl Compute {
+    /// `GET projects/{project}/global/snapshots`, a page at a time.
+    pub async fn list_snapshot_pages(
+        &self,
+        project: &str,
+        filter: Option<&Filter>,
+        paging: Paging,
+        on_page: impl FnMut(Vec<Snapshot>) -> Result<()>,
+    ) -> Result<()> {
+        self.list_pages(&snapshots_path(project), filter, paging, on_page)
+            .await
+    }
+
+    /// `GET projects/{project}/global/snapshots/{name}`
+    pub async fn get_snapshot(&self, project: &str, name: &str) -> Result<Snapshot> {
+        self.get(&format!("{}/{name}", snapshots_path(project)), &[])
+            .await
+    }
+
+    /// `POST projects/{project}/zones/{zone}/disks/{disk}/createSnapshot`
+    pub async fn create_snapshot(
+        &self,
+        project: &str,
+        zone: &str,
+        disk: &str,
+        body: &Value,
+    ) -> Result<Operation> {
+        self.post(
+            &format!("projects/{project}/zones/{zone}/disks/{disk}/createSnapshot"),
+            body,
+        )
+        .await
+    }
+
+    /// `DELETE projects/{project}/global/snapshots/{name}`
+    pub async fn delete_snapshot(&self, project: &str, name: &str) -> Result<Operation> {
+        self.delete(&format!("{}/{name}", snapshots_path(project)))
+            .await
+    }
+
+    /// Edits a snapshot's labels under its label fingerprint
+    pub async fn update_snapshot_labels(
+        &self,
+        project: &str,
+        name: &str,
+        edit: &LabelEdit,
+    ) -> Result<Operation> {
+        self.update_labels(&format!("{}/{name}", snapshots_path(project)), edit)
+            .await
+    }
+}
+
+fn snapshots_path(project: &str) -> String {
+    format!("projects/{project}/global/snapshots")
+}
@@ -0,0 +1,42 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use super::Compute;
+use crate::filter::Filter;
+use crate::resources::{InstanceTemplate, Operation};
+
+impl Compute {
+    /// `GET projects/{project}/global/instanceTemplates`
+    pub async fn list_instance_templates(
+        &self,
+        project: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<InstanceTemplate>> {
+        self.list_all(&templates_path(project), filter).await
+    }
+
+    /// `GET projects/{project}/global/instanceTemplates/{name}`
+    pub async fn get_instance_template(
+        &self,
+        project: &str,
+        name: &str,
+    ) -> Result<InstanceTemplate> {
+        self.get(&format!("{}/{name}", templates_path(project)), &[])
+            .await
+    }
+
+    /// `POST projects/{project}/global/instanceTemplates`
+    pub async fn insert_instance_template(&self, project: &str, body: &Value) -> Result<Operation> {
+        self.post(&templates_path(project), body).await
+    }
+
+    /// `DELETE projects/{project}/global/instanceTemplates/{name}`
+    pub async fn delete_instance_template(&self, project: &str, name: &str) -> Result<Operation> {
+        self.delete(&format!("{}/{name}", templates_path(project)))
+            .await
+    }
+}
+
+fn templates_path(project: &str) -> String {
+    format!("projects/{project}/global/instanceTemplates")
+}
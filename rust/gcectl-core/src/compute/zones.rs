@@ -0,0 +1,39 @@
+use anyhow::Result;
+
+use super::{Compute, Revalidated};
+use crate::filter::Filter;
+use crate::resources::{Region, Zone};
+
+impl Compute {
+    /// `GET projects/{project}/zones`
+    pub async fn list_zones(&self, project: &str, filter: Option<&Filter>) -> Result<Vec<Zone>> {
+        self.list_all(&format!("projects/{project}/zones"), filter)
+            .await
+    }
+
+    /// `GET projects/{project}/zones`, unless the listing still matches `etag`
+    pub async fn list_zones_if_modified(
+        &self,
+        project: &str,
+        etag: Option<&str>,
+    ) -> Result<Revalidated<Zone>> {
+        self.list_all_if_modified(&format!("projects/{project}/zones"), etag)
+            .await
+    }
+
+    /// `GET projects/{project}/regions/{region}`
+    pub async fn get_region(&self, project: &str, region: &str) -> Result<Region> {
+        self.get(&format!("projects/{project}/regions/{region}"), &[])
+            .await
+    }
+
+    /// `GET projects/{project}/regions`, each with its quotas
+    pub async fn list_regions(
+        &self,
+        project: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Region>> {
+        self.list_all(&format!("projects/{project}/regions"), filter)
+            .await
+    }
+}
@@ -0,0 +1,375 @@
+//! User configuration stored in `~/.config/gcectl/config.toml`.
+//!
+//! The file holds named profiles with per-profile defaults:
+//!
+//! ```toml
+//! active_profile = "work"
+//!
+//! [profiles.work]
+//! project = "my-project"
+//! zone = "asia-northeast1-a"
+//! region = "asia-northeast1"
+//! output = "table"
+//! retries = "3"
+//! qps = "20"
+//! google_apis = "private"
+//! impersonate_service_account = "deploy@my-project.iam.gserviceaccount.com"
+//! billing_export_table = "billing-admin.exports.gcp_billing_export_resource_v1_0123AB"
+//! dns_domain = "dev.example.com"
+//! columns = "name,zone,status,internal-ip"
+//!
+//! [alias]
+//! up = "instances start --filter labels.owner=$USER"
+//!
+//! [notify]
+//! command = "say gcectl is done"
+//! slack_webhook = "https://hooks.slack.com/services/T000/B000/XXXX"
+//! desktop = true
+//! ```
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::endpoints::GoogleApis;
+use crate::error::GcectlError;
+use crate::output::OutputFormat;
+
+pub const DEFAULT_PROFILE: &str = "default";
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Config {
+    // profile used when `--profile` is not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    // command aliases, expanded before the command line is parsed
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub alias: BTreeMap<String, String>,
+    // hooks `--notify` fires when an operation ends
+    #[serde(default, skip_serializing_if = "NotifyHooks::is_empty")]
+    pub notify: NotifyHooks,
+}
+
+/// Where `--notify` reports a finished operation. With no hook set, it
+/// shows a desktop notification.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotifyHooks {
+    // run with `sh -c`, with `GCECTL_NOTIFY_*` describing the outcome
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    // incoming webhook the outcome is posted to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slack_webhook: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desktop: Option<bool>,
+}
+
+impl NotifyHooks {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Whether to show a desktop notification: when asked to, or when no
+    /// other hook is set and it is not turned off.
+    pub fn desktop(&self) -> bool {
+        let others = self.command.is_some() || self.slack_webhook.is_some();
+        self.desktop.unwrap_or(!others)
+    }
+}
+
+/// Defaults applied to every command run under a profile.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    // comma-separated projects `--all-projects` covers instead of every
+    // project the account can see
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projects: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    // retries for transient API failures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<String>,
+    // requests per second across all API calls; 0 for no limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qps: Option<String>,
+    // Compute Engine endpoint, e.g. a proxy or private service connect address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_endpoint: Option<String>,
+    // `private` or `restricted` to reach Google APIs through their VIP
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub google_apis: Option<String>,
+    // service account every request acts as, through impersonation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonate_service_account: Option<String>,
+    // BigQuery table of the Cloud Billing export `cost actual` reads
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub billing_export_table: Option<String>,
+    // domain `dns register` and `instances create --auto-dns` name instances under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns_domain: Option<String>,
+    // table and CSV columns when `--columns` is not given, e.g. name,zone,status
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub columns: Option<String>,
+}
+
+/// Settable profile keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum ProfileKey {
+    Project,
+    Projects,
+    Zone,
+    Region,
+    Output,
+    Retries,
+    Qps,
+    ApiEndpoint,
+    GoogleApis,
+    ImpersonateServiceAccount,
+    BillingExportTable,
+    DnsDomain,
+    Columns,
+}
+
+impl fmt::Display for ProfileKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Project => "project",
+            Self::Projects => "projects",
+            Self::Zone => "zone",
+            Self::Region => "region",
+            Self::Output => "output",
+            Self::Retries => "retries",
+            Self::Qps => "qps",
+            Self::ApiEndpoint => "api-endpoint",
+            Self::GoogleApis => "google-apis",
+            Self::ImpersonateServiceAccount => "impersonate-service-account",
+            Self::BillingExportTable => "billing-export-table",
+            Self::DnsDomain => "dns-domain",
+            Self::Columns => "columns",
+        })
+    }
+}
+
+impl Profile {
+    /// The projects listed under `projects`, if any.
+    pub fn project_list(&self) -> Vec<String> {
+        self.projects
+            .iter()
+            .flat_map(|p| p.split(','))
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    pub fn get(&self, key: ProfileKey) -> Option<&str> {
+        match key {
+            ProfileKey::Project => self.project.as_deref(),
+            ProfileKey::Projects => self.projects.as_deref(),
+            ProfileKey::Zone => self.zone.as_deref(),
+            ProfileKey::Region => self.region.as_deref(),
+            ProfileKey::Output => self.output.as_deref(),
+            ProfileKey::Retries => self.retries.as_deref(),
+            ProfileKey::Qps => self.qps.as_deref(),
+            ProfileKey::ApiEndpoint => self.api_endpoint.as_deref(),
+            ProfileKey::GoogleApis => self.google_apis.as_deref(),
+            ProfileKey::ImpersonateServiceAccount => self.impersonate_service_account.as_deref(),
+            ProfileKey::BillingExportTable => self.billing_export_table.as_deref(),
+            ProfileKey::DnsDomain => self.dns_domain.as_deref(),
+            ProfileKey::Columns => self.columns.as_deref(),
+        }
+    }
+
+    /// Sets `key` to `value`, parsing typed keys first so that a bad value
+    /// is refused here rather than failing every later run.
+    pub fn set(&mut self, key: ProfileKey, value: String) -> Result<()> {
+        check(key, &value)?;
+        let slot = match key {
+            ProfileKey::Project => &mut self.project,
+            ProfileKey::Projects => &mut self.projects,
+            ProfileKey::Zone => &mut self.zone,
+            ProfileKey::Region => &mut self.region,
+            ProfileKey::Output => &mut self.output,
+            ProfileKey::Retries => &mut self.retries,
+            ProfileKey::Qps => &mut self.qps,
+            ProfileKey::ApiEndpoint => &mut self.api_endpoint,
+            ProfileKey::GoogleApis => &mut self.google_apis,
+            ProfileKey::ImpersonateServiceAccount => &mut self.impersonate_service_account,
+            ProfileKey::BillingExportTable => &mut self.billing_export_table,
+            ProfileKey::DnsDomain => &mut self.dns_domain,
+            ProfileKey::Columns => &mut self.columns,
+        };
+        *slot = Some(value);
+        Ok(())
+    }
+}
+
+fn check(key: ProfileKey, value: &str) -> Result<()> {
+    let parsed = match key {
+        ProfileKey::Output => value
+            .parse::<OutputFormat>()
+            .map(drop)
+            .map_err(|e| e.to_string()),
+        ProfileKey::Retries => value.parse::<u32>().map(drop).map_err(|e| e.to_string()),
+        ProfileKey::Qps => match value.parse::<f64>() {
+            Ok(qps) if qps.is_finite() && qps >= 0.0 => Ok(()),
+            Ok(_) => Err("expected 0 or more".to_string()),
+            Err(err) => Err(err.to_string()),
+        },
+        ProfileKey::GoogleApis => value
+            .parse::<GoogleApis>()
+            .map(drop)
+            .map_err(|e| e.to_string()),
+        _ => Ok(()),
+    };
+    parsed.map_err(|err| GcectlError::Usage(format!("invalid {key} {value:?}: {err}")).into())
+}
+
+impl Config {
+    /// Directory holding gcectl's configuration and state.
+    ///
+    /// `GCECTL_CONFIG_DIR` overrides the default of `$XDG_CONFIG_HOME/gcectl`
+    /// (or `~/.config/gcectl`).
+    pub fn dir() -> Result<PathBuf> {
+        if let Some(dir) = env::var_os("GCECTL_CONFIG_DIR") {
+            return Ok(PathBuf::from(dir));
+        }
+        let base = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".config"))
+                .context("cannot locate the config directory: HOME is not set")?,
+        };
+        Ok(base.join("gcectl"))
+    }
+
+    pub fn path() -> Result<PathBuf> {
+        Ok(Self::dir()?.join(CONFIG_FILE_NAME))
+    }
+
+    /// Loads the config file, returning an empty config if it does not exist.
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let body = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        toml::from_str(&body).with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        fs::write(&path, toml::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Name of the profile to use: `--profile` if given, else `active_profile`,
+    /// else `default`.
+    pub fn profile_name(&self, flag: Option<&str>) -> String {
+        flag.or(self.active_profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE)
+            .to_string()
+    }
+
+    /// Looks up a profile, treating an unknown `default` profile as empty.
+    ///
+    /// With `create`, any unknown profile is returned empty so that it can be
+    /// populated and saved.
+    pub fn profile(&self, name: &str, create: bool) -> Result<Profile> {
+        match self.profiles.get(name) {
+            Some(profile) => Ok(profile.clone()),
+            None if create || name == DEFAULT_PROFILE => Ok(Profile::default()),
+            None => bail!("profile `{name}` does not exist"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_profiles() {
+        let config: Config = toml::from_str(
+            r#"
+            active_profile = "work"
+
+            [profiles.work]
+            project = "work-project"
+            projects = "work-project, work-data,"
+            zone = "asia-northeast1-a"
+
+            [profiles.default]
+            project = "home-project"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.profile_name(None), "work");
+        assert_eq!(config.profile_name(Some("default")), "default");
+        let work = config.profile("work", false).unwrap();
+        assert_eq!(work.get(ProfileKey::Zone), Some("asia-northeast1-a"));
+        assert_eq!(work.get(ProfileKey::Region), None);
+        assert_eq!(work.project_list(), ["work-project", "work-data"]);
+        assert!(config.notify.desktop());
+    }
+
+    #[test]
+    fn missing_profiles() {
+        let config = Config::default();
+        assert_eq!(config.profile_name(None), DEFAULT_PROFILE);
+        assert_eq!(
+            config.profile(DEFAULT_PROFILE, false).unwrap(),
+            Profile::default()
+        );
+        assert!(config.profile("nope", false).is_err());
+        assert!(config.profile("nope", true).is_ok());
+    }
+
+    #[test]
+    fn refuses_values_that_do_not_parse() {
+        let mut profile = Profile::default();
+        for (key, value) in [
+            (ProfileKey::Retries, "many"),
+            (ProfileKey::Output, "bogus"),
+            (ProfileKey::Qps, "-1"),
+            (ProfileKey::GoogleApis, "x"),
+        ] {
+            assert!(profile.set(key, value.into()).is_err(), "{key} {value}");
+        }
+        assert_eq!(profile, Profile::default());
+        profile.set(ProfileKey::Qps, "2.5".into()).unwrap();
+        assert_eq!(profile.get(ProfileKey::Qps), Some("2.5"));
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let mut config = Config::default();
+        let mut profile = Profile::default();
+        profile.set(ProfileKey::Output, "json".into()).unwrap();
+        config.profiles.insert("ci".into(), profile.clone());
+        let parsed: Config = toml::from_str(&toml::to_string_pretty(&config).unwrap()).unwrap();
+        assert_eq!(parsed.profiles["ci"], profile);
+        assert_eq!(parsed.active_profile, None);
+    }
+}
@@ -0,0 +1,93 @@
+//! Cloud Console links to resources, for the `open` subcommands.
+//!
+//! gcectl only builds the URL; whether the resource exists is for the
+//! console page to say.
+
+use std::env;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+
+const CONSOLE: &str = "https://console.cloud.google.com";
+const SSH_IN_BROWSER: &str = "https://ssh.cloud.google.com/v2/ssh";
+
+/// A resource with a detail page in the Cloud Console.
+#[derive(Debug, Clone, Copy)]
+pub enum Resource<'a> {
+    Instance { zone: &'a str, name: &'a str },
+    Disk { zone: &'a str, name: &'a str },
+    Snapshot { name: &'a str },
+    Image { name: &'a str },
+    Template { name: &'a str },
+    Firewall { name: &'a str },
+}
+
+impl Resource<'_> {
+    /// Detail page of the resource in `project`.
+    pub fn url(&self, project: &str) -> String {
+        let page = match *self {
+            Self::Instance { zone, name } => {
+                format!("compute/instancesDetail/zones/{zone}/instances/{name}")
+            }
+            Self::Disk { zone, name } => format!("compute/disksDetail/zones/{zone}/disks/{name}"),
+            Self::Snapshot { name } => {
+                format!("compute/snapshotsDetail/projects/{project}/global/snapshots/{name}")
+            }
+            Self::Image { name } => {
+                format!("compute/imagesDetail/projects/{project}/global/images/{name}")
+            }
+            Self::Template { name } => format!("compute/instanceTemplates/details/{name}"),
+            Self::Firewall { name } => format!("networking/firewalls/details/{name}"),
+        };
+        format!("{CONSOLE}/{page}?project={project}")
+    }
+}
+
+/// SSH-in-browser session to `name`, which signs in through the console
+/// rather than gcectl's keys.
+pub fn ssh_in_browser_url(project: &str, zone: &str, name: &str) -> String {
+    format!("{SSH_IN_BROWSER}/projects/{project}/zones/{zone}/instances/{name}")
+}
+
+/// Opens `url` with `$BROWSER`, else the platform's opener.
+pub fn open_in_browser(url: &str) -> Result<()> {
+    let (program, args): (String, Vec<&str>) = match env::var("BROWSER") {
+        Ok(browser) if !browser.is_empty() => (browser, vec![url]),
+        _ if cfg!(target_os = "macos") => ("open".into(), vec![url]),
+        _ if cfg!(windows) => ("cmd".into(), vec!["/C", "start", "", url]),
+        _ => ("xdg-open".into(), vec![url]),
+    };
+    Command::new(&program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to run {program} to open the browser"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_console_urls() {
+        let instance = Resource::Instance {
+            zone: "asia-northeast1-a",
+            name: "web-1",
+        };
+        assert_eq!(
+            instance.url("my-proj"),
+            "https://console.cloud.google.com/compute/instancesDetail/zones/asia-northeast1-a/instances/web-1?project=my-proj"
+        );
+        assert_eq!(
+            Resource::Snapshot { name: "nightly" }.url("my-proj"),
+            "https://console.cloud.google.com/compute/snapshotsDetail/projects/my-proj/global/snapshots/nightly?project=my-proj"
+        );
+        assert_eq!(
+            ssh_in_browser_url("my-proj", "asia-northeast1-a", "web-1"),
+            "https://ssh.cloud.google.com/v2/ssh/projects/my-proj/zones/asia-northeast1-a/instances/web-1"
+        );
+    }
+}
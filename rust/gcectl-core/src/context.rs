@@ -0,0 +1,418 @@
+//! Resolution of the project, zone, and region a command runs against.
+//!
+//! Each setting comes from the first of these that has it:
+//!
+//! 1. the command's `--project`, `--zone`, or `--region` flag, then the
+//!    same flag given before the subcommand
+//! 2. `GCECTL_PROJECT`/`GCECTL_ZONE`/`GCECTL_REGION`, then gcloud's
+//!    `CLOUDSDK_CORE_PROJECT`/`CLOUDSDK_COMPUTE_ZONE`/`CLOUDSDK_COMPUTE_REGION`
+//! 3. the active gcectl profile
+//! 4. the active gcloud configuration
+//!
+//! Within each layer a region not set explicitly is taken from the zone.
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::auth::gcloud_config_dir;
+use crate::config::Profile;
+use crate::output::Render;
+use crate::resources::region_of;
+
+const PROJECT_VARS: &[&str] = &["GCECTL_PROJECT", "CLOUDSDK_CORE_PROJECT"];
+const ZONE_VARS: &[&str] = &["GCECTL_ZONE", "CLOUDSDK_COMPUTE_ZONE"];
+const REGION_VARS: &[&str] = &["GCECTL_REGION", "CLOUDSDK_COMPUTE_REGION"];
+
+/// Where a resolved setting came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Flag,
+    Env(String),
+    Profile(String),
+    Gcloud(String),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Flag => f.write_str("flag"),
+            Self::Env(var) => write!(f, "env {var}"),
+            Self::Profile(name) => write!(f, "gcectl profile {name}"),
+            Self::Gcloud(name) => write!(f, "gcloud configuration {name}"),
+        }
+    }
+}
+
+/// A setting's value and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolved {
+    pub value: String,
+    pub source: Source,
+    // a region taken from the zone rather than set itself
+    pub from_zone: bool,
+}
+
+/// The values each layer below the flags offers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Layer {
+    source: Option<Source>,
+    project: Option<String>,
+    zone: Option<String>,
+    region: Option<String>,
+}
+
+/// Everything settings are resolved from apart from the command's own flags.
+#[derive(Debug, Clone, Default)]
+pub struct Resolver {
+    // flags before the subcommand, environment, then profile, then gcloud
+    layers: Vec<Layer>,
+}
+
+impl Resolver {
+    /// Reads the environment and gcloud's active configuration.
+    pub fn load(profile_name: &str, profile: &Profile) -> Self {
+        let vars: HashMap<String, String> = PROJECT_VARS
+            .iter()
+            .chain(ZONE_VARS)
+            .chain(REGION_VARS)
+            .filter_map(|var| Some((var.to_string(), env::var(var).ok()?)))
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+        Self::from_layers(&vars, profile_name, profile, GcloudConfig::load())
+    }
+
+    fn from_layers(
+        vars: &HashMap<String, String>,
+        profile_name: &str,
+        profile: &Profile,
+        gcloud: Option<GcloudConfig>,
+    ) -> Self {
+        let mut layers = Vec::new();
+        // each variable is its own layer so the source names it
+        for var in PROJECT_VARS.iter().chain(ZONE_VARS).chain(REGION_VARS) {
+            let Some(value) = vars.get(*var) else {
+                continue;
+            };
+            let mut layer = Layer {
+                source: Some(Source::Env(var.to_string())),
+                ..Layer::default()
+            };
+            match var {
+                _ if PROJECT_VARS.contains(var) => layer.project = Some(value.clone()),
+                _ if ZONE_VARS.contains(var) => layer.zone = Some(value.clone()),
+                _ => layer.region = Some(value.clone()),
+            }
+            layers.push(layer);
+        }
+        layers.push(Layer {
+            source: Some(Source::Profile(profile_name.to_string())),
+            project: profile.project.clone(),
+            zone: profile.zone.clone(),
+            region: profile.region.clone(),
+        });
+        if let Some(gcloud) = gcloud {
+            layers.push(Layer {
+                source: Some(Source::Gcloud(gcloud.name)),
+                project: gcloud.project,
+                zone: gcloud.zone,
+                region: gcloud.region,
+            });
+        }
+        Self { layers }
+    }
+
+    /// Puts `--project`, `--zone`, and `--region` given before the
+    /// subcommand ahead of every other layer.
+    pub fn with_flags(
+        mut self,
+        project: Option<String>,
+        zone: Option<String>,
+        region: Option<String>,
+    ) -> Self {
+        if project.is_some() || zone.is_some() || region.is_some() {
+            let layer = Layer {
+                source: Some(Source::Flag),
+                project,
+                zone,
+                region,
+            };
+            self.layers.insert(0, layer);
+        }
+        self
+    }
+
+    /// Name of gcloud's active configuration and the project it sets, when
+    /// gcloud is configured.
+    pub fn gcloud(&self) -> Option<(&str, Option<&str>)> {
+        self.layers.iter().find_map(|layer| match &layer.source {
+            Some(Source::Gcloud(name)) => Some((name.as_str(), layer.project.as_deref())),
+            _ => None,
+        })
+    }
+
+    pub fn project(&self, flag: Option<&str>) -> Option<Resolved> {
+        self.resolve(flag, |layer| layer.project.clone())
+    }
+
+    pub fn zone(&self, flag: Option<&str>) -> Option<Resolved> {
+        self.resolve(flag, |layer| layer.zone.clone())
+    }
+
+    pub fn region(&self, flag: Option<&str>) -> Option<Resolved> {
+        if let Some(value) = flag {
+            return Some(flag_value(value));
+        }
+        // region-only layers (the env vars) win over zones lower down
+        self.layers.iter().find_map(|layer| {
+            let (value, from_zone) = match (&layer.region, &layer.zone) {
+                (Some(region), _) => (region.clone(), false),
+                (None, Some(zone)) => (region_of(zone).to_string(), true),
+                (None, None) => return None,
+            };
+            Some(Resolved {
+                value,
+                source: layer.source.clone()?,
+                from_zone,
+            })
+        })
+    }
+
+    fn resolve(
+        &self,
+        flag: Option<&str>,
+        pick: impl Fn(&Layer) -> Option<String>,
+    ) -> Option<Resolved> {
+        if let Some(value) = flag {
+            return Some(flag_value(value));
+        }
+        self.layers.iter().find_map(|layer| {
+            Some(Resolved {
+                value: pick(layer)?,
+                source: layer.source.clone()?,
+                from_zone: false,
+            })
+        })
+    }
+}
+
+fn flag_value(value: &str) -> Resolved {
+    Resolved {
+        value: value.to_string(),
+        source: Source::Flag,
+        from_zone: false,
+    }
+}
+
+/// The settings gcectl reads from a gcloud configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct GcloudConfig {
+    name: String,
+    project: Option<String>,
+    zone: Option<String>,
+    region: Option<String>,
+}
+
+impl GcloudConfig {
+    /// The active configuration, named by `CLOUDSDK_ACTIVE_CONFIG_NAME` or
+    /// gcloud's `active_config` file.
+    fn load() -> Option<Self> {
+        let dir = gcloud_config_dir()?;
+        let name = env::var("CLOUDSDK_ACTIVE_CONFIG_NAME")
+            .ok()
+            .or_else(|| fs::read_to_string(dir.join("active_config")).ok())
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "default".to_string());
+        let path: PathBuf = dir.join("configurations").join(format!("config_{name}"));
+        Some(Self::parse(name, &fs::read_to_string(path).ok()?))
+    }
+
+    /// Parses the INI-style properties file.
+    fn parse(name: String, ini: &str) -> Self {
+        let mut config = Self {
+            name,
+            ..Self::default()
+        };
+        let mut section = "";
+        for line in ini.lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim();
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+            match (section, key.trim()) {
+                ("core", "project") => config.project = value,
+                ("compute", "zone") => config.zone = value,
+                ("compute", "region") => config.region = value,
+                _ => {}
+            }
+        }
+        config
+    }
+}
+
+/// One row of `config doctor`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Setting {
+    pub setting: &'static str,
+    pub value: Option<String>,
+    pub source: Option<String>,
+}
+
+impl Setting {
+    pub fn new(setting: &'static str, resolved: Option<Resolved>) -> Self {
+        Self {
+            setting,
+            source: resolved.as_ref().map(|r| match r.from_zone {
+                true => format!("zone from {}", r.source),
+                false => r.source.to_string(),
+            }),
+            value: resolved.map(|r| r.value),
+        }
+    }
+}
+
+impl Render for Setting {
+    fn headers() -> Vec<&'static str> {
+        vec!["Setting", "Value", "Source"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.setting.to_string(),
+            self.value
+                .clone()
+                .unwrap_or_else(|| "(not set)".to_string()),
+            self.source.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(project: Option<&str>, zone: Option<&str>) -> Profile {
+        Profile {
+            project: project.map(str::to_string),
+            zone: zone.map(str::to_string),
+            ..Profile::default()
+        }
+    }
+
+    fn gcloud() -> GcloudConfig {
+        GcloudConfig::parse(
+            "work".to_string(),
+            "[core]\naccount = me@example.com\nproject = gcloud-project\n\n\
+             [compute]\nzone = europe-west1-b\nregion = europe-west4\n",
+        )
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn flag_overrides_profile_default() {
+        let resolver = Resolver::from_layers(
+            &vars(&[]),
+            "default",
+            &profile(Some("from-profile"), None),
+            None,
+        );
+        assert_eq!(
+            resolver.project(Some("from-flag")).unwrap().value,
+            "from-flag"
+        );
+        let resolved = resolver.project(None).unwrap();
+        assert_eq!(resolved.value, "from-profile");
+        assert_eq!(resolved.source, Source::Profile("default".to_string()));
+        assert_eq!(resolver.zone(None), None);
+    }
+
+    #[test]
+    fn leading_flags_beat_env_but_not_the_commands_own() {
+        let resolver = Resolver::from_layers(
+            &vars(&[("GCECTL_PROJECT", "env-project")]),
+            "default",
+            &profile(None, Some("us-central1-a")),
+            None,
+        )
+        .with_flags(
+            Some("leading".to_string()),
+            Some("asia-northeast1-a".to_string()),
+            None,
+        );
+        let project = resolver.project(None).unwrap();
+        assert_eq!(project.value, "leading");
+        assert_eq!(project.source, Source::Flag);
+        assert_eq!(resolver.project(Some("own")).unwrap().value, "own");
+        assert_eq!(resolver.region(None).unwrap().value, "asia-northeast1");
+    }
+
+    #[test]
+    fn env_beats_profile_and_gcloud_comes_last() {
+        let resolver = Resolver::from_layers(
+            &vars(&[("CLOUDSDK_CORE_PROJECT", "env-project")]),
+            "work",
+            &profile(Some("profile-project"), None),
+            Some(gcloud()),
+        );
+        let project = resolver.project(None).unwrap();
+        assert_eq!(project.value, "env-project");
+        assert_eq!(project.source.to_string(), "env CLOUDSDK_CORE_PROJECT");
+        let zone = resolver.zone(None).unwrap();
+        assert_eq!(zone.value, "europe-west1-b");
+        assert_eq!(zone.source.to_string(), "gcloud configuration work");
+    }
+
+    #[test]
+    fn gcectl_variables_beat_gcloud_ones() {
+        let resolver = Resolver::from_layers(
+            &vars(&[("GCECTL_PROJECT", "a"), ("CLOUDSDK_CORE_PROJECT", "b")]),
+            "default",
+            &Profile::default(),
+            None,
+        );
+        assert_eq!(resolver.project(None).unwrap().value, "a");
+    }
+
+    #[test]
+    fn region_falls_back_to_the_zone_of_the_same_layer() {
+        let resolver = Resolver::from_layers(
+            &vars(&[]),
+            "default",
+            &profile(None, Some("asia-northeast1-a")),
+            Some(gcloud()),
+        );
+        let region = resolver.region(None).unwrap();
+        assert_eq!(region.value, "asia-northeast1");
+        assert!(region.from_zone);
+        assert_eq!(
+            Setting::new("region", Some(region)).source.as_deref(),
+            Some("zone from gcectl profile default")
+        );
+    }
+
+    #[test]
+    fn parses_gcloud_properties() {
+        let config = gcloud();
+        assert_eq!(config.project.as_deref(), Some("gcloud-project"));
+        assert_eq!(config.region.as_deref(), Some("europe-west4"));
+        assert_eq!(
+            GcloudConfig::parse("x".into(), "[core]\nproject =\n").project,
+            None
+        );
+    }
+}
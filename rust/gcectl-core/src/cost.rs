@@ -0,0 +1,410 @@
+//! Monthly cost estimates from a bundled on-demand price table.
+//!
+//! Prices are approximate us-central1 list prices in USD, scaled per region.
+//! They ignore sustained-use and committed-use discounts, licenses, and
+//! network egress, so treat the result as an order of magnitude for finding
+//! forgotten machines rather than a bill.
+
+use serde::Serialize;
+
+use crate::output::{Details, Render};
+
+/// Hours in the average month, as Google Cloud bills them.
+pub const HOURS_PER_MONTH: f64 = 730.0;
+
+// boot and data disks whose type cannot be looked up are priced as the default type
+pub const DEFAULT_DISK_TYPE: &str = "pd-balanced";
+
+/// Hourly price of one vCPU and one GB of memory, and the fraction of it
+/// Spot capacity costs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FamilyRates {
+    vcpu: f64,
+    memory_gb: f64,
+    spot: f64,
+}
+
+const FAMILY_RATES: &[(&str, FamilyRates)] = &[
+    ("e2", rates(0.021811, 0.002923, 0.30)),
+    ("n1", rates(0.031611, 0.004237, 0.20)),
+    ("f1", rates(0.031611, 0.004237, 0.20)),
+    ("g1", rates(0.031611, 0.004237, 0.20)),
+    ("n2", rates(0.031611, 0.004237, 0.30)),
+    ("n2d", rates(0.027502, 0.003686, 0.25)),
+    ("n4", rates(0.030170, 0.003710, 0.35)),
+    ("t2d", rates(0.027502, 0.003686, 0.25)),
+    ("t2a", rates(0.024200, 0.003300, 0.35)),
+    ("c2", rates(0.033982, 0.004552, 0.30)),
+    ("c2d", rates(0.029563, 0.003959, 0.25)),
+    ("c3", rates(0.034650, 0.003938, 0.35)),
+    ("c3d", rates(0.030300, 0.003420, 0.35)),
+    ("c4", rates(0.034830, 0.004090, 0.35)),
+    ("m1", rates(0.034806, 0.005101, 0.30)),
+    ("m3", rates(0.035850, 0.004790, 0.30)),
+    ("a2", rates(0.031611, 0.004237, 0.30)),
+    ("g2", rates(0.024988, 0.002927, 0.35)),
+];
+
+const fn rates(vcpu: f64, memory_gb: f64, spot: f64) -> FamilyRates {
+    FamilyRates {
+        vcpu,
+        memory_gb,
+        spot,
+    }
+}
+
+// fractional vCPUs approximate the burst billing of shared-core types
+const SHARED_CORE: &[(&str, MachineShape)] = &[
+    ("e2-micro", shape(0.25, 1.0)),
+    ("e2-small", shape(0.5, 2.0)),
+    ("e2-medium", shape(1.0, 4.0)),
+    ("f1-micro", shape(0.2, 0.6)),
+    ("g1-small", shape(0.5, 1.7)),
+];
+
+/// Price relative to us-central1, matched by the longest region prefix.
+const REGION_MULTIPLIERS: &[(&str, f64)] = &[
+    ("us-", 1.0),
+    ("northamerica-", 1.1),
+    ("southamerica-", 1.5),
+    ("europe-west1", 1.1),
+    ("europe-", 1.2),
+    ("asia-northeast", 1.3),
+    ("asia-", 1.2),
+    ("australia-", 1.4),
+    ("me-", 1.3),
+    ("africa-", 1.35),
+];
+
+/// Hourly on-demand price of one GPU.
+const GPU_HOURLY: &[(&str, f64)] = &[
+    ("nvidia-tesla-k80", 0.45),
+    ("nvidia-tesla-p4", 0.60),
+    ("nvidia-tesla-t4", 0.35),
+    ("nvidia-tesla-p100", 1.46),
+    ("nvidia-tesla-v100", 2.48),
+    ("nvidia-l4", 0.60),
+    ("nvidia-tesla-a100", 2.93),
+    ("nvidia-a100-80gb", 3.93),
+    ("nvidia-h100-80gb", 11.06),
+];
+
+// Spot GPUs are billed at roughly this fraction of on-demand
+const GPU_SPOT: f64 = 0.4;
+
+/// Monthly price of one GB of disk.
+const DISK_MONTHLY_GB: &[(&str, f64)] = &[
+    ("pd-standard", 0.04),
+    ("pd-balanced", 0.10),
+    ("pd-ssd", 0.17),
+    ("pd-extreme", 0.125),
+    ("hyperdisk-balanced", 0.06),
+    ("hyperdisk-throughput", 0.03),
+    ("hyperdisk-extreme", 0.125),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MachineShape {
+    pub vcpus: f64,
+    pub memory_gb: f64,
+}
+
+const fn shape(vcpus: f64, memory_gb: f64) -> MachineShape {
+    MachineShape { vcpus, memory_gb }
+}
+
+/// vCPUs and memory of a predefined (`n2-standard-8`) or custom
+/// (`n2-custom-4-8192`) machine type.
+pub fn machine_shape(machine_type: &str) -> Option<MachineShape> {
+    if let Some((_, shape)) = SHARED_CORE.iter().find(|(name, _)| *name == machine_type) {
+        return Some(*shape);
+    }
+    let parts: Vec<&str> = machine_type.split('-').collect();
+    match parts.as_slice() {
+        // custom memory is given in MB; `custom` may be preceded by the family
+        [.., "custom", vcpus, memory_mb] | [.., "custom", vcpus, memory_mb, "ext"] => {
+            let vcpus: f64 = vcpus.parse().ok()?;
+            let memory_mb: f64 = memory_mb.parse().ok()?;
+            Some(shape(vcpus, memory_mb / 1024.0))
+        }
+        [family, class, vcpus, ..] => {
+            let vcpus: f64 = vcpus.parse().ok()?;
+            let per_vcpu = memory_per_vcpu(family, class)?;
+            Some(shape(vcpus, vcpus * per_vcpu))
+        }
+        _ => None,
+    }
+}
+
+fn memory_per_vcpu(family: &str, class: &str) -> Option<f64> {
+    let n1 = family == "n1";
+    Some(match class {
+        "standard" if n1 => 3.75,
+        "standard" => 4.0,
+        "highmem" if n1 => 6.5,
+        "highmem" => 8.0,
+        "highcpu" if n1 => 0.9,
+        "highcpu" => 1.0,
+        "megamem" => 14.9,
+        "ultramem" => 24.0,
+        "highgpu" => 7.0,
+        _ => return None,
+    })
+}
+
+fn family_of(machine_type: &str) -> &str {
+    machine_type.split('-').next().unwrap_or(machine_type)
+}
+
+fn family_rates(machine_type: &str) -> Option<FamilyRates> {
+    let family = family_of(machine_type);
+    FAMILY_RATES
+        .iter()
+        .find(|(name, _)| *name == family)
+        .map(|(_, rates)| *rates)
+}
+
+pub fn region_multiplier(region: &str) -> f64 {
+    REGION_MULTIPLIERS
+        .iter()
+        .filter(|(prefix, _)| region.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map_or(1.0, |(_, multiplier)| *multiplier)
+}
+
+/// What an instance is made of, as far as pricing is concerned.
+#[derive(Debug, Clone, Default)]
+pub struct Usage {
+    pub name: String,
+    pub zone: String,
+    pub region: String,
+    pub machine_type: String,
+    pub status: String,
+    pub spot: bool,
+    // accelerator type names and counts
+    pub gpus: Vec<(String, u32)>,
+    // disk type names and sizes in GB
+    pub disks: Vec<(String, u64)>,
+}
+
+/// Estimated monthly cost of one instance in USD. Compute and GPUs are only
+/// charged while the instance is `RUNNING`; `None` means the price is
+/// unknown.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Estimate {
+    pub name: String,
+    pub zone: String,
+    pub machine_type: String,
+    pub status: String,
+    pub model: &'static str,
+    pub compute: Option<f64>,
+    pub gpus: Option<f64>,
+    pub disks: Option<f64>,
+}
+
+impl Estimate {
+    /// Sum of the known parts.
+    pub fn total(&self) -> f64 {
+        [self.compute, self.gpus, self.disks]
+            .into_iter()
+            .flatten()
+            .sum()
+    }
+
+    /// Whether part of the cost could not be priced.
+    pub fn is_partial(&self) -> bool {
+        self.compute.is_none() || self.gpus.is_none() || self.disks.is_none()
+    }
+}
+
+pub fn estimate(usage: &Usage) -> Estimate {
+    let multiplier = region_multiplier(&usage.region);
+    // instances that are not running only pay for their disks
+    let running = usage.status == "RUNNING";
+    let billed = |hourly: Option<f64>| match running {
+        true => hourly.map(|h| h * HOURS_PER_MONTH * multiplier),
+        false => Some(0.0),
+    };
+
+    let compute = billed(machine_hourly(&usage.machine_type, usage.spot));
+    let gpus = billed(
+        usage
+            .gpus
+            .iter()
+            .map(|(kind, count)| gpu_hourly(kind, usage.spot).map(|h| h * f64::from(*count)))
+            .sum(),
+    );
+    let disks = usage
+        .disks
+        .iter()
+        .map(|(kind, size)| disk_monthly_gb(kind).map(|p| p * *size as f64 * multiplier))
+        .sum();
+    Estimate {
+        name: usage.name.clone(),
+        zone: usage.zone.clone(),
+        machine_type: usage.machine_type.clone(),
+        status: usage.status.clone(),
+        model: if usage.spot { "spot" } else { "on-demand" },
+        compute,
+        gpus,
+        disks,
+    }
+}
+
+/// On-demand monthly price of `machine_type` in `region`.
+pub fn machine_monthly(machine_type: &str, region: &str) -> Option<f64> {
+    Some(machine_hourly(machine_type, false)? * HOURS_PER_MONTH * region_multiplier(region))
+}
+
+fn machine_hourly(machine_type: &str, spot: bool) -> Option<f64> {
+    let shape = machine_shape(machine_type)?;
+    let rates = family_rates(machine_type)?;
+    let hourly = shape.vcpus * rates.vcpu + shape.memory_gb * rates.memory_gb;
+    Some(if spot { hourly * rates.spot } else { hourly })
+}
+
+fn gpu_hourly(kind: &str, spot: bool) -> Option<f64> {
+    let (_, hourly) = GPU_HOURLY.iter().find(|(name, _)| *name == kind)?;
+    Some(if spot { hourly * GPU_SPOT } else { *hourly })
+}
+
+fn disk_monthly_gb(kind: &str) -> Option<f64> {
+    DISK_MONTHLY_GB
+        .iter()
+        .find(|(name, _)| *name == kind)
+        .map(|(_, price)| *price)
+}
+
+fn dollars(amount: Option<f64>) -> String {
+    amount.map_or_else(|| "?".to_string(), |a| format!("${a:.2}"))
+}
+
+impl Render for Estimate {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Name",
+            "Zone",
+            "Machine-Type",
+            "Status",
+            "Model",
+            "Compute",
+            "GPUs",
+            "Disks",
+            "Monthly",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let total = dollars(Some(self.total()));
+        vec![
+            self.name.clone(),
+            self.zone.clone(),
+            self.machine_type.clone(),
+            self.status.clone(),
+            self.model.to_string(),
+            dollars(self.compute),
+            dollars(self.gpus),
+            dollars(self.disks),
+            if self.is_partial() {
+                format!("{total}+")
+            } else {
+                total
+            },
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        for (header, value) in Self::headers().into_iter().zip(self.row()) {
+            details.field(header, value);
+        }
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn parses_machine_shapes() {
+        assert_eq!(machine_shape("n2-standard-8"), Some(shape(8.0, 32.0)));
+        assert_eq!(machine_shape("n1-highmem-4"), Some(shape(4.0, 26.0)));
+        assert_eq!(machine_shape("e2-medium"), Some(shape(1.0, 4.0)));
+        assert_eq!(machine_shape("n2-custom-4-8192"), Some(shape(4.0, 8.0)));
+        assert_eq!(machine_shape("custom-2-4096"), Some(shape(2.0, 4.0)));
+        assert_eq!(machine_shape("n2-mystery-4"), None);
+    }
+
+    #[test]
+    fn prices_machine_types_per_region() {
+        let central = machine_monthly("n2-standard-8", "us-central1").unwrap();
+        assert!(close(
+            central,
+            (8.0 * 0.031611 + 32.0 * 0.004237) * HOURS_PER_MONTH
+        ));
+        let tokyo = machine_monthly("n2-standard-8", "asia-northeast1").unwrap();
+        assert!(close(tokyo, central * 1.3));
+        assert_eq!(machine_monthly("x9-standard-8", "us-central1"), None);
+    }
+
+    #[test]
+    fn picks_longest_region_prefix() {
+        assert_eq!(region_multiplier("us-central1"), 1.0);
+        assert_eq!(region_multiplier("europe-west1"), 1.1);
+        assert_eq!(region_multiplier("europe-west4"), 1.2);
+        assert_eq!(region_multiplier("mars-north1"), 1.0);
+    }
+
+    #[test]
+    fn estimates_running_instance() {
+        let usage = Usage {
+            name: "trainer".into(),
+            region: "us-central1".into(),
+            machine_type: "n1-standard-4".into(),
+            status: "RUNNING".into(),
+            gpus: vec![("nvidia-tesla-t4".into(), 1)],
+            disks: vec![("pd-balanced".into(), 100)],
+            ..Default::default()
+        };
+        let estimate = estimate(&usage);
+        assert!(close(estimate.compute.unwrap(), 138.70));
+        assert!(close(estimate.gpus.unwrap(), 255.50));
+        assert!(close(estimate.disks.unwrap(), 10.0));
+        assert!(!estimate.is_partial());
+    }
+
+    #[test]
+    fn stopped_instances_pay_for_disks_only() {
+        for status in ["TERMINATED", "STOPPING", "SUSPENDED", "STAGING"] {
+            let usage = Usage {
+                machine_type: "e2-standard-4".into(),
+                status: status.into(),
+                disks: vec![("pd-ssd".into(), 10)],
+                ..Default::default()
+            };
+            let estimate = estimate(&usage);
+            assert_eq!(estimate.compute, Some(0.0), "{status}");
+            assert!(close(estimate.total(), 1.7), "{status}");
+        }
+    }
+
+    #[test]
+    fn spot_and_unknown_prices() {
+        let usage = Usage {
+            machine_type: "zz-standard-2".into(),
+            status: "RUNNING".into(),
+            spot: true,
+            ..Default::default()
+        };
+        let estimate = estimate(&usage);
+        assert_eq!(estimate.model, "spot");
+        assert_eq!(estimate.compute, None);
+        assert!(estimate.row()[8].ends_with('+'));
+    }
+}
@@ -0,0 +1,143 @@
+//! The checks `gcectl diagnose` runs and how they are reported; `instances
+//! check-port` reports its findings the same way.
+//!
+//! Each check says whether one thing gcectl depends on works (credentials,
+//! a token, the Compute API, the network, gcloud's configuration) and, when
+//! it does not, what to do about it. A failed check explains later ones,
+//! which are skipped rather than failing for the same reason again.
+
+use std::fmt;
+
+use anyhow::Error;
+use serde::Serialize;
+
+use crate::error::GcectlError;
+use crate::output::Render;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Warn,
+    Fail,
+    Skipped,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ok => "ok",
+            Self::Warn => "warn",
+            Self::Fail => "FAIL",
+            Self::Skipped => "skipped",
+        })
+    }
+}
+
+/// One row of `gcectl diagnose` or `instances check-port`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub check: &'static str,
+    pub status: Status,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl Check {
+    pub fn ok(check: &'static str, detail: impl Into<String>) -> Self {
+        Self::new(check, Status::Ok, detail.into(), None)
+    }
+
+    pub fn warn(check: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self::new(check, Status::Warn, detail.into(), Some(fix.into()))
+    }
+
+    pub fn fail(check: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self::new(check, Status::Fail, detail.into(), Some(fix.into()))
+    }
+
+    /// A check that cannot run because `failed` did not pass.
+    pub fn skipped(check: &'static str, failed: &str) -> Self {
+        Self::new(check, Status::Skipped, format!("needs {failed}"), None)
+    }
+
+    /// A failed check from `err`, suggesting the error's own hint when it
+    /// has one and `fix` otherwise.
+    pub fn failed(check: &'static str, err: &Error, fix: &str) -> Self {
+        let hint = err
+            .downcast_ref::<GcectlError>()
+            .and_then(GcectlError::hint);
+        Self::fail(check, format!("{err:#}"), hint.unwrap_or(fix))
+    }
+
+    fn new(check: &'static str, status: Status, detail: String, fix: Option<String>) -> Self {
+        Self {
+            check,
+            status,
+            detail,
+            fix,
+        }
+    }
+}
+
+/// The line printed under the checks, e.g. `5 ok, 1 warning, 1 failed`.
+pub fn summary(checks: &[Check]) -> String {
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    let mut parts = vec![format!("{} ok", count(Status::Ok))];
+    match count(Status::Warn) {
+        0 => {}
+        1 => parts.push("1 warning".to_string()),
+        n => parts.push(format!("{n} warnings")),
+    }
+    for (status, label) in [(Status::Fail, "failed"), (Status::Skipped, "skipped")] {
+        if count(status) > 0 {
+            parts.push(format!("{} {label}", count(status)));
+        }
+    }
+    parts.join(", ")
+}
+
+impl Render for Check {
+    fn headers() -> Vec<&'static str> {
+        vec!["Check", "Status", "Detail", "Fix"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.check.to_string(),
+            self.status.to_string(),
+            self.detail.clone(),
+            self.fix.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ApiFailure;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn failures_suggest_the_errors_hint() {
+        let disabled = Error::from(GcectlError::ApiDisabled(ApiFailure {
+            api: "Compute Engine API".to_string(),
+            status: StatusCode::FORBIDDEN,
+            message: "Compute Engine API has not been used in project demo".to_string(),
+        }));
+        let check = Check::failed("compute api", &disabled, "check the project");
+        assert!(check.fix.unwrap().contains("gcloud services enable"));
+
+        let other = Check::failed("network", &anyhow::anyhow!("timed out"), "check the proxy");
+        assert_eq!(other.fix.as_deref(), Some("check the proxy"));
+
+        let checks = [
+            Check::ok("a", ""),
+            Check::warn("b", "", ""),
+            other,
+            Check::skipped("c", "a"),
+        ];
+        assert_eq!(summary(&checks), "1 ok, 1 warning, 1 failed, 1 skipped");
+    }
+}
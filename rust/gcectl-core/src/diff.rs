@@ -0,0 +1,271 @@
+//! Structural comparison of two instances for `instances diff`.
+//!
+//! Both instances are reduced to the settings that can legitimately differ
+//! between otherwise similar VMs — identifiers, IPs, fingerprints, and disk
+//! sources are dropped — and flattened into dotted paths such as
+//! `labels.env` or `disks[0].diskSizeGb` before comparing.
+
+use std::collections::BTreeMap;
+
+use comfy_table::presets::UTF8_FULL;
+use comfy_table::{Cell, Color, Table};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+use crate::output::Render;
+use crate::resources::{Instance, short_name};
+
+// longer values are cut short in the table; JSON and YAML keep them whole
+const MAX_CELL: usize = 60;
+
+/// One setting that differs; `None` means the instance does not have it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Difference {
+    pub field: String,
+    pub first: Option<String>,
+    pub second: Option<String>,
+}
+
+impl Render for Difference {
+    fn headers() -> Vec<&'static str> {
+        vec!["Field", "First", "Second"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let value = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+        vec![self.field.clone(), value(&self.first), value(&self.second)]
+    }
+}
+
+/// Settings of `instance` worth comparing, keyed by dotted path.
+pub fn normalize(instance: &Instance) -> BTreeMap<String, String> {
+    let disks: Vec<Value> = instance
+        .disks
+        .iter()
+        .map(|disk| {
+            let mut value = json!({
+                "boot": disk.boot,
+                "autoDelete": disk.auto_delete,
+                "mode": disk.mode,
+                "diskSizeGb": disk.disk_size_gb,
+            });
+            for key in ["type", "interface"] {
+                if let Some(extra) = disk.extra.get(key) {
+                    value[key] = extra.clone();
+                }
+            }
+            value
+        })
+        .collect();
+    let nics: Vec<Value> = instance
+        .network_interfaces
+        .iter()
+        .map(|nic| {
+            let mut value = json!({
+                "network": nic.network.as_deref().map(short_name),
+                "subnetwork": nic.subnetwork.as_deref().map(short_name),
+                "externalIp": !nic.access_configs.is_empty(),
+            });
+            for key in ["nicType", "stackType"] {
+                if let Some(extra) = nic.extra.get(key) {
+                    value[key] = extra.clone();
+                }
+            }
+            value
+        })
+        .collect();
+    let metadata: Map<String, Value> = instance
+        .metadata
+        .iter()
+        .flat_map(|m| &m.items)
+        .map(|item| (item.key.clone(), json!(item.value)))
+        .collect();
+    let accelerators: Vec<Value> = instance
+        .guest_accelerators
+        .iter()
+        .map(|a| json!({ "type": short_name(&a.accelerator_type), "count": a.accelerator_count }))
+        .collect();
+    let accounts: Vec<Value> = instance
+        .service_accounts
+        .iter()
+        .map(|a| json!({ "email": a.email, "scopes": a.scopes.join(",") }))
+        .collect();
+
+    let normalized = json!({
+        "machineType": instance.machine_type_name(),
+        "disks": disks,
+        "metadata": metadata,
+        "labels": instance.labels,
+        "networkInterfaces": nics,
+        "scheduling": instance.scheduling,
+        "guestAccelerators": accelerators,
+        "serviceAccounts": accounts,
+    });
+    let mut fields = BTreeMap::new();
+    flatten("", &normalized, &mut fields);
+    fields
+}
+
+fn flatten(path: &str, value: &Value, out: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Null => {}
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = match path.is_empty() {
+                    true => key.clone(),
+                    false => format!("{path}.{key}"),
+                };
+                flatten(&path, value, out);
+            }
+        }
+        Value::Array(items) => {
+            for (i, value) in items.iter().enumerate() {
+                flatten(&format!("{path}[{i}]"), value, out);
+            }
+        }
+        Value::String(s) => {
+            out.insert(path.to_string(), s.clone());
+        }
+        other => {
+            out.insert(path.to_string(), other.to_string());
+        }
+    }
+}
+
+/// Fields whose values differ between `first` and `second`, in path order.
+pub fn compare(
+    first: &BTreeMap<String, String>,
+    second: &BTreeMap<String, String>,
+) -> Vec<Difference> {
+    let mut fields: Vec<&String> = first.keys().chain(second.keys()).collect();
+    fields.sort();
+    fields.dedup();
+    fields
+        .into_iter()
+        .filter(|field| first.get(*field) != second.get(*field))
+        .map(|field| Difference {
+            field: field.clone(),
+            first: first.get(field).cloned(),
+            second: second.get(field).cloned(),
+        })
+        .collect()
+}
+
+/// Side-by-side table headed by the two instance names. Values only one
+/// instance has are green; values both have but disagree on are yellow.
+pub fn render(first: &str, second: &str, differences: &[Difference]) -> String {
+    let mut table = Table::new();
+    table
+        .load_style(UTF8_FULL)
+        .set_header(vec!["Field", first, second]);
+    for difference in differences {
+        let color = match (&difference.first, &difference.second) {
+            (Some(_), Some(_)) => Color::Yellow,
+            _ => Color::Green,
+        };
+        let cell = |value: &Option<String>| match value {
+            Some(value) => Cell::new(truncate(value)).fg(color),
+            None => Cell::new("-").fg(Color::DarkGrey),
+        };
+        table.add_row(vec![
+            Cell::new(&difference.field),
+            cell(&difference.first),
+            cell(&difference.second),
+        ]);
+    }
+    table.to_string()
+}
+
+fn truncate(value: &str) -> String {
+    let first_line = value.lines().next().unwrap_or_default();
+    match first_line.chars().count() > MAX_CELL || first_line.len() < value.len() {
+        true => {
+            let cut: String = first_line.chars().take(MAX_CELL).collect();
+            format!("{cut}… ({} bytes)", value.len())
+        }
+        false => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::instance::{AttachedDisk, Metadata, MetadataItem, NetworkInterface};
+
+    fn instance(name: &str, machine_type: &str) -> Instance {
+        Instance {
+            name: name.to_string(),
+            id: Some(format!("{name}-id")),
+            machine_type: format!("zones/z/machineTypes/{machine_type}"),
+            disks: vec![AttachedDisk {
+                source: Some(format!("zones/z/disks/{name}")),
+                device_name: Some(name.to_string()),
+                boot: true,
+                disk_size_gb: Some("10".to_string()),
+                ..Default::default()
+            }],
+            network_interfaces: vec![NetworkInterface {
+                network: Some("global/networks/default".to_string()),
+                network_ip: Some(format!("10.0.0.{}", name.len())),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn identical_setups_have_no_differences() {
+        // names, ids, IPs, and disk sources are expected to differ
+        let (a, b) = (
+            instance("web-1", "e2-medium"),
+            instance("web-10", "e2-medium"),
+        );
+        assert_eq!(compare(&normalize(&a), &normalize(&b)), vec![]);
+    }
+
+    #[test]
+    fn reports_changed_and_one_sided_fields() {
+        let mut a = instance("a", "e2-medium");
+        let mut b = instance("b", "e2-standard-4");
+        a.labels.insert("env".to_string(), "dev".to_string());
+        b.labels.insert("env".to_string(), "prod".to_string());
+        b.disks[0].disk_size_gb = Some("50".to_string());
+        b.metadata = Some(Metadata {
+            items: vec![MetadataItem {
+                key: "startup-script".to_string(),
+                value: "#!/bin/sh".to_string(),
+            }],
+            ..Default::default()
+        });
+        let fields: Vec<(String, Option<String>, Option<String>)> =
+            compare(&normalize(&a), &normalize(&b))
+                .into_iter()
+                .map(|d| (d.field, d.first, d.second))
+                .collect();
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(
+            fields,
+            vec![
+                ("disks[0].diskSizeGb".to_string(), some("10"), some("50")),
+                ("labels.env".to_string(), some("dev"), some("prod")),
+                (
+                    "machineType".to_string(),
+                    some("e2-medium"),
+                    some("e2-standard-4")
+                ),
+                (
+                    "metadata.startup-script".to_string(),
+                    None,
+                    some("#!/bin/sh")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn long_values_are_truncated_in_the_table() {
+        assert_eq!(truncate("short"), "short");
+        assert_eq!(truncate("line one\nline two"), "line one… (17 bytes)");
+        assert!(truncate(&"x".repeat(100)).ends_with("… (100 bytes)"));
+    }
+}
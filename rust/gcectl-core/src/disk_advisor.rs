@@ -0,0 +1,347 @@
+//! Whether a persistent disk is held back by its provisioned performance.
+//!
+//! Persistent disk limits grow with size up to a per-type cap, while
+//! Extreme and Hyperdisk volumes get what was provisioned. Measured peaks
+//! are compared against those limits; the VM's own caps, which are lower
+//! on small machine types, are not modelled.
+
+use serde::Serialize;
+
+use crate::output::Render;
+
+// utilization at which a disk counts as throttled, and as close to it
+const THROTTLED: f64 = 0.9;
+const NEAR_LIMIT: f64 = 0.7;
+// utilization a suggested resize or type aims the peak at
+const TARGET: f64 = 0.6;
+const MAX_SIZE_GB: f64 = 65_536.0;
+
+/// How a persistent disk type's performance scales with size.
+struct Rule {
+    disk_type: &'static str,
+    iops_per_gb: f64,
+    baseline_iops: f64,
+    max_iops: f64,
+    // MiB/s
+    throughput_per_gb: f64,
+    baseline_throughput: f64,
+    max_throughput: f64,
+}
+
+// read limits of the zonal types, cheapest first; writes come close enough
+const RULES: &[Rule] = &[
+    Rule {
+        disk_type: "pd-standard",
+        iops_per_gb: 0.75,
+        baseline_iops: 0.0,
+        max_iops: 7_500.0,
+        throughput_per_gb: 0.12,
+        baseline_throughput: 0.0,
+        max_throughput: 1_200.0,
+    },
+    Rule {
+        disk_type: "pd-balanced",
+        iops_per_gb: 6.0,
+        baseline_iops: 3_000.0,
+        max_iops: 80_000.0,
+        throughput_per_gb: 0.28,
+        baseline_throughput: 140.0,
+        max_throughput: 1_200.0,
+    },
+    Rule {
+        disk_type: "pd-ssd",
+        iops_per_gb: 30.0,
+        baseline_iops: 6_000.0,
+        max_iops: 100_000.0,
+        throughput_per_gb: 0.48,
+        baseline_throughput: 240.0,
+        max_throughput: 1_200.0,
+    },
+];
+
+impl Rule {
+    fn find(disk_type: &str) -> Option<&'static Rule> {
+        RULES.iter().find(|rule| rule.disk_type == disk_type)
+    }
+
+    fn limit(&self, dimension: Dimension, size_gb: f64) -> f64 {
+        match dimension {
+            Dimension::Iops => (self.baseline_iops + self.iops_per_gb * size_gb).min(self.max_iops),
+            Dimension::Throughput => (self.baseline_throughput + self.throughput_per_gb * size_gb)
+                .min(self.max_throughput),
+        }
+    }
+
+    /// Smallest size whose limit reaches `want`, if the type's cap allows it.
+    fn size_for(&self, dimension: Dimension, want: f64) -> Option<f64> {
+        let (per_gb, baseline, max) = match dimension {
+            Dimension::Iops => (self.iops_per_gb, self.baseline_iops, self.max_iops),
+            Dimension::Throughput => (
+                self.throughput_per_gb,
+                self.baseline_throughput,
+                self.max_throughput,
+            ),
+        };
+        if want > max {
+            return None;
+        }
+        let size = ((want - baseline) / per_gb).max(0.0).ceil();
+        (size <= MAX_SIZE_GB).then_some(size)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Dimension {
+    #[serde(rename = "IOPS")]
+    Iops,
+    // MiB/s
+    #[serde(rename = "THROUGHPUT")]
+    Throughput,
+}
+
+impl Dimension {
+    fn format(self, value: f64) -> String {
+        match self {
+            Self::Iops => format!("{value:.0}"),
+            Self::Throughput => format!("{value:.1} MiB/s"),
+        }
+    }
+}
+
+/// The disk as the rules see it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskSpec {
+    pub disk_type: String,
+    pub size_gb: f64,
+    // what Extreme and Hyperdisk volumes were provisioned with
+    pub provisioned_iops: Option<f64>,
+    pub provisioned_throughput: Option<f64>,
+}
+
+impl DiskSpec {
+    /// The limit of `dimension`, or `None` for types the rules don't know.
+    pub fn limit(&self, dimension: Dimension) -> Option<f64> {
+        if let Some(rule) = Rule::find(&self.disk_type) {
+            return Some(rule.limit(dimension, self.size_gb));
+        }
+        match dimension {
+            Dimension::Iops => self.provisioned_iops,
+            Dimension::Throughput => self.provisioned_throughput,
+        }
+    }
+}
+
+/// Read and write I/O combined over the lookback window.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Usage {
+    pub mean: f64,
+    pub peak: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Verdict {
+    Throttled,
+    NearLimit,
+    Ok,
+    // no limit known for the type, or no I/O measured
+    Unknown,
+}
+
+impl Verdict {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Throttled => "THROTTLED",
+            Self::NearLimit => "NEAR_LIMIT",
+            Self::Ok => "OK",
+            Self::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+/// One dimension of a disk measured against its limit.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    pub dimension: Dimension,
+    pub mean: Option<f64>,
+    pub peak: Option<f64>,
+    pub limit: Option<f64>,
+    pub verdict: Verdict,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl Finding {
+    fn utilization(&self) -> Option<f64> {
+        Some(self.peak? / self.limit.filter(|l| *l > 0.0)?)
+    }
+}
+
+/// Measures `usage` of each dimension against the limits of `disk`.
+pub fn analyze(disk: &DiskSpec, iops: Option<Usage>, throughput: Option<Usage>) -> Vec<Finding> {
+    [(Dimension::Iops, iops), (Dimension::Throughput, throughput)]
+        .into_iter()
+        .map(|(dimension, usage)| {
+            let limit = disk.limit(dimension);
+            let verdict = match (usage, limit) {
+                (Some(usage), Some(limit)) if limit > 0.0 => match usage.peak / limit {
+                    u if u >= THROTTLED => Verdict::Throttled,
+                    u if u >= NEAR_LIMIT => Verdict::NearLimit,
+                    _ => Verdict::Ok,
+                },
+                _ => Verdict::Unknown,
+            };
+            let suggestion = match (verdict, usage) {
+                (Verdict::Throttled | Verdict::NearLimit, Some(usage)) => {
+                    suggest(disk, dimension, usage.peak / TARGET)
+                }
+                _ => None,
+            };
+            Finding {
+                dimension,
+                mean: usage.map(|u| u.mean),
+                peak: usage.map(|u| u.peak),
+                limit,
+                verdict,
+                suggestion,
+            }
+        })
+        .collect()
+}
+
+/// A resize or type change that gives `dimension` at least `want`.
+fn suggest(disk: &DiskSpec, dimension: Dimension, want: f64) -> Option<String> {
+    let Some(rule) = Rule::find(&disk.disk_type) else {
+        let what = match dimension {
+            Dimension::Iops => "IOPS",
+            Dimension::Throughput => "MiB/s of throughput",
+        };
+        return Some(format!("provision at least {want:.0} {what}"));
+    };
+    if let Some(size) = rule.size_for(dimension, want) {
+        return Some(format!(
+            "resize to {size:.0} GB (limit {})",
+            dimension.format(rule.limit(dimension, size))
+        ));
+    }
+    // the type's cap is too low at any size; the next type up at the
+    // current size, or grown, may do
+    let faster = |r: &&Rule| r.max_iops > rule.max_iops;
+    for next in RULES.iter().filter(faster) {
+        if next.limit(dimension, disk.size_gb) >= want {
+            return Some(format!("change the type to {}", next.disk_type));
+        }
+        if let Some(size) = next.size_for(dimension, want) {
+            return Some(format!(
+                "change the type to {} and resize to {size:.0} GB",
+                next.disk_type
+            ));
+        }
+    }
+    Some(format!(
+        "move to hyperdisk-balanced provisioned with {}",
+        dimension.format(want)
+    ))
+}
+
+impl Render for Finding {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Dimension",
+            "Mean",
+            "Peak",
+            "Limit",
+            "Used",
+            "Verdict",
+            "Suggestion",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let format =
+            |value: Option<f64>| value.map(|v| self.dimension.format(v)).unwrap_or_default();
+        vec![
+            match self.dimension {
+                Dimension::Iops => "IOPS".to_string(),
+                Dimension::Throughput => "Throughput".to_string(),
+            },
+            format(self.mean),
+            format(self.peak),
+            format(self.limit),
+            self.utilization()
+                .map(|u| format!("{:.0}%", u * 100.0))
+                .unwrap_or_default(),
+            self.verdict.as_str().to_string(),
+            self.suggestion.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn disk(disk_type: &str, size_gb: f64) -> DiskSpec {
+        DiskSpec {
+            disk_type: disk_type.into(),
+            size_gb,
+            provisioned_iops: None,
+            provisioned_throughput: None,
+        }
+    }
+
+    fn usage(peak: f64) -> Option<Usage> {
+        Some(Usage {
+            mean: peak / 2.0,
+            peak,
+        })
+    }
+
+    #[test]
+    fn limits_grow_with_size_up_to_the_cap() {
+        assert_eq!(
+            disk("pd-balanced", 100.0).limit(Dimension::Iops),
+            Some(3_600.0)
+        );
+        assert_eq!(
+            disk("pd-ssd", 10_000.0).limit(Dimension::Iops),
+            Some(100_000.0)
+        );
+        let hyperdisk = DiskSpec {
+            provisioned_iops: Some(5_000.0),
+            ..disk("hyperdisk-balanced", 100.0)
+        };
+        assert_eq!(hyperdisk.limit(Dimension::Iops), Some(5_000.0));
+        assert_eq!(hyperdisk.limit(Dimension::Throughput), None);
+    }
+
+    #[test]
+    fn throttled_disks_get_a_resize_within_the_type_cap() {
+        // 0.12 MiB/s per GB: 12 MiB/s at 100 GB, peaking at 11.5
+        let findings = analyze(&disk("pd-standard", 100.0), usage(10.0), usage(11.5));
+        assert_eq!(findings[0].verdict, Verdict::Ok);
+        assert_eq!(findings[0].suggestion, None);
+        assert_eq!(findings[1].verdict, Verdict::Throttled);
+        assert_eq!(
+            findings[1].suggestion.as_deref(),
+            Some("resize to 160 GB (limit 19.2 MiB/s)")
+        );
+    }
+
+    #[test]
+    fn capped_types_are_pointed_at_a_faster_one() {
+        let findings = analyze(&disk("pd-standard", 10_000.0), usage(7_400.0), None);
+        assert_eq!(findings[0].verdict, Verdict::Throttled);
+        assert_eq!(
+            findings[0].suggestion.as_deref(),
+            Some("change the type to pd-balanced")
+        );
+        assert_eq!(findings[1].verdict, Verdict::Unknown);
+        let findings = analyze(&disk("pd-ssd", 5_000.0), usage(99_000.0), None);
+        assert_eq!(
+            findings[0].suggestion.as_deref(),
+            Some("move to hyperdisk-balanced provisioned with 165000")
+        );
+    }
+}
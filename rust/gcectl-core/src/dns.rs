@@ -0,0 +1,264 @@
+//! Thin client for the Cloud DNS v1 API: managed zones and the record sets
+//! that give instances stable hostnames.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::auth::Authenticator;
+use crate::compute::parse_response;
+use crate::error::{self, GcectlError};
+use crate::transport::Transport;
+
+const DNS_ENDPOINT: &str = "https://dns.googleapis.com/dns/v1";
+
+pub struct Dns {
+    http: Transport,
+    auth: Arc<Authenticator>,
+    endpoint: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManagedZone {
+    pub name: String,
+    // with the trailing dot, e.g. `dev.example.com.`
+    pub dns_name: String,
+    // `public` or `private`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordSet {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub record_type: String,
+    pub ttl: u32,
+    #[serde(default)]
+    pub rrdatas: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ZonesPage {
+    #[serde(default)]
+    managed_zones: Vec<ManagedZone>,
+    next_page_token: Option<String>,
+}
+
+/// What [`Dns::upsert`] did to a record set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Upserted {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+impl Dns {
+    pub fn new(http: Transport, auth: Arc<Authenticator>) -> Self {
+        Self {
+            http,
+            auth,
+            endpoint: DNS_ENDPOINT.to_string(),
+        }
+    }
+
+    /// Sends requests to `endpoint` instead of the public API, if given.
+    pub fn endpoint(mut self, endpoint: Option<&str>) -> Self {
+        if let Some(endpoint) = endpoint {
+            self.endpoint = endpoint.trim_end_matches('/').to_string();
+        }
+        self
+    }
+
+    /// `GET projects/{project}/managedZones`
+    pub async fn list_managed_zones(&self, project: &str) -> Result<Vec<ManagedZone>> {
+        let url = format!("{}/projects/{project}/managedZones", self.endpoint);
+        let mut zones = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let query: Vec<(&str, &str)> = page_token
+                .as_deref()
+                .map(|token| ("pageToken", token))
+                .into_iter()
+                .collect();
+            let page: ZonesPage = self.get(&url, &query).await?;
+            zones.extend(page.managed_zones);
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => break,
+            }
+        }
+        Ok(zones)
+    }
+
+    /// `GET projects/{project}/managedZones/{zone}/rrsets/{name}/{type}`,
+    /// or `None` if there is no such record set.
+    pub async fn get_record_set(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        record_type: &str,
+    ) -> Result<Option<RecordSet>> {
+        let url = self.record_url(project, zone, name, record_type);
+        match self.get(&url, &[]).await {
+            Ok(record) => Ok(Some(record)),
+            Err(err) if matches!(error::find(&err), Some(GcectlError::NotFound(_))) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Creates `record`, or replaces the record set of its name and type.
+    ///
+    /// `POST projects/{project}/managedZones/{zone}/rrsets` or
+    /// `PATCH projects/{project}/managedZones/{zone}/rrsets/{name}/{type}`
+    pub async fn upsert(&self, project: &str, zone: &str, record: &RecordSet) -> Result<Upserted> {
+        let existing = self
+            .get_record_set(project, zone, &record.name, &record.record_type)
+            .await?;
+        let (request, upserted) = match existing {
+            Some(existing) if &existing == record => return Ok(Upserted::Unchanged),
+            Some(_) => {
+                let url = self.record_url(project, zone, &record.name, &record.record_type);
+                debug!("PATCH {url}");
+                (self.http.patch(&url), Upserted::Updated)
+            }
+            None => {
+                let url = format!(
+                    "{}/projects/{project}/managedZones/{zone}/rrsets",
+                    self.endpoint
+                );
+                debug!("POST {url}");
+                (self.http.post(&url), Upserted::Created)
+            }
+        };
+        let request = request.json(record).bearer_auth(self.auth.token().await?);
+        let resp = self
+            .http
+            .send(request)
+            .await
+            .context("request to Cloud DNS API failed")?;
+        let _: RecordSet = parse_response(resp, "Cloud DNS API").await?;
+        Ok(upserted)
+    }
+
+    /// `DELETE projects/{project}/managedZones/{zone}/rrsets/{name}/{type}`
+    pub async fn delete_record_set(
+        &self,
+        project: &str,
+        zone: &str,
+        name: &str,
+        record_type: &str,
+    ) -> Result<()> {
+        let url = self.record_url(project, zone, name, record_type);
+        debug!("DELETE {url}");
+        let request = self.http.delete(&url).bearer_auth(self.auth.token().await?);
+        let resp = self
+            .http
+            .send(request)
+            .await
+            .context("request to Cloud DNS API failed")?;
+        // the response body is empty
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(GcectlError::from_response("Cloud DNS API", status, body).into());
+        }
+        Ok(())
+    }
+
+    fn record_url(&self, project: &str, zone: &str, name: &str, record_type: &str) -> String {
+        format!(
+            "{}/projects/{project}/managedZones/{zone}/rrsets/{name}/{record_type}",
+            self.endpoint
+        )
+    }
+
+    async fn get<T: DeserializeOwned>(&self, url: &str, query: &[(&str, &str)]) -> Result<T> {
+        debug!("GET {url} {query:?}");
+        let request = self
+            .http
+            .get(url)
+            .query(query)
+            .bearer_auth(self.auth.token().await?);
+        let resp = self
+            .http
+            .send(request)
+            .await
+            .context("request to Cloud DNS API failed")?;
+        parse_response(resp, "Cloud DNS API").await
+    }
+}
+
+/// Fully qualified hostname of instance `name` under `domain`.
+pub fn hostname(name: &str, domain: &str) -> String {
+    format!("{name}.{}.", domain.trim_matches('.'))
+}
+
+/// The zone `fqdn` belongs in: the one with the longest name it ends in,
+/// preferring `visibility` (`public` or `private`) when zones of both kinds
+/// serve the same name, as split-horizon setups do.
+pub fn zone_for<'a>(
+    zones: &'a [ManagedZone],
+    fqdn: &str,
+    visibility: &str,
+) -> Result<&'a ManagedZone> {
+    let serves = |zone: &&ManagedZone| {
+        fqdn.strip_suffix(zone.dns_name.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.ends_with('.'))
+    };
+    let rank = |zone: &&ManagedZone| {
+        let matches = zone.visibility.as_deref().unwrap_or("public") == visibility;
+        (zone.dns_name.len(), matches)
+    };
+    match zones.iter().filter(serves).max_by_key(rank) {
+        Some(zone) => Ok(zone),
+        None => bail!("no managed zone serves {fqdn}; pass --managed-zone"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zone(name: &str, dns_name: &str, visibility: &str) -> ManagedZone {
+        ManagedZone {
+            name: name.into(),
+            dns_name: dns_name.into(),
+            visibility: Some(visibility.into()),
+        }
+    }
+
+    #[test]
+    fn the_most_specific_zone_of_the_right_kind_wins() {
+        let zones = [
+            zone("example", "example.com.", "public"),
+            zone("dev-public", "dev.example.com.", "public"),
+            zone("dev-private", "dev.example.com.", "private"),
+            zone("other", "myexample.com.", "public"),
+        ];
+        let fqdn = hostname("web-1", "dev.example.com.");
+        assert_eq!(fqdn, "web-1.dev.example.com.");
+        assert_eq!(
+            zone_for(&zones, &fqdn, "private").unwrap().name,
+            "dev-private"
+        );
+        assert_eq!(
+            zone_for(&zones, &fqdn, "public").unwrap().name,
+            "dev-public"
+        );
+        assert_eq!(
+            zone_for(&zones, "web-1.example.com.", "private")
+                .unwrap()
+                .name,
+            "example"
+        );
+        assert!(zone_for(&zones, "web-1.example.org.", "public").is_err());
+    }
+}
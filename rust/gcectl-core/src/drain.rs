@@ -0,0 +1,128 @@
+//! Instance group memberships that `instances stop --drain` took away,
+//! stored in `drained.toml` next to the config file so `instances start`
+//! can put the instances back.
+//!
+//! ```toml
+//! [instances]
+//! "my-project/us-central1-a/web-1" = ["web-ig"]
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+const DRAINED_FILE_NAME: &str = "drained.toml";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Drained {
+    // `project/zone/instance` to the names of the groups it left
+    #[serde(default)]
+    pub instances: BTreeMap<String, Vec<String>>,
+}
+
+impl Drained {
+    pub fn path() -> Result<PathBuf> {
+        Ok(Config::dir()?.join(DRAINED_FILE_NAME))
+    }
+
+    /// Loads the state file, returning nothing drained if it does not exist.
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let body = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        toml::from_str(&body).with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        fs::write(&path, toml::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Remembers that `name` left `groups`, on top of any earlier drain.
+    pub fn record(&mut self, project: &str, zone: &str, name: &str, groups: &[String]) {
+        let entry = self.instances.entry(key(project, zone, name)).or_default();
+        for group in groups {
+            if !entry.contains(group) {
+                entry.push(group.clone());
+            }
+        }
+    }
+
+    /// Forgets and returns the groups each of `names` left, skipping
+    /// instances that were never drained.
+    pub fn take(
+        &mut self,
+        project: &str,
+        zone: &str,
+        names: &[String],
+    ) -> Vec<(String, Vec<String>)> {
+        names
+            .iter()
+            .filter_map(|name| {
+                let groups = self.instances.remove(&key(project, zone, name))?;
+                Some((name.clone(), groups))
+            })
+            .collect()
+    }
+}
+
+/// Turns instance-to-groups `memberships` around into group-to-instances,
+/// so each group gets one add or remove request.
+pub fn by_group(memberships: &[(String, Vec<String>)]) -> BTreeMap<String, Vec<String>> {
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, member_of) in memberships {
+        for group in member_of {
+            groups.entry(group.clone()).or_default().push(name.clone());
+        }
+    }
+    groups
+}
+
+fn key(project: &str, zone: &str, name: &str) -> String {
+    format!("{project}/{zone}/{name}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_and_takes_memberships() {
+        let mut drained = Drained::default();
+        drained.record("p", "z", "web-1", &["web-ig".into()]);
+        drained.record("p", "z", "web-1", &["web-ig".into(), "api-ig".into()]);
+        drained.record("p", "z", "web-2", &["web-ig".into()]);
+
+        let mut drained: Drained =
+            toml::from_str(&toml::to_string_pretty(&drained).unwrap()).unwrap();
+        let taken = drained.take("p", "z", &["web-1".into(), "web-2".into(), "db-1".into()]);
+        assert_eq!(
+            taken,
+            [
+                (
+                    "web-1".to_string(),
+                    vec!["web-ig".to_string(), "api-ig".to_string()]
+                ),
+                ("web-2".to_string(), vec!["web-ig".to_string()]),
+            ]
+        );
+        assert!(drained.instances.is_empty());
+
+        let groups = by_group(&taken);
+        assert_eq!(groups["web-ig"], ["web-1", "web-2"]);
+        assert_eq!(groups["api-ig"], ["web-1"]);
+    }
+}
@@ -0,0 +1,180 @@
+//! Where API requests go: overridden endpoints for proxies and emulators,
+//! and the Private Google Access VIPs for networks without internet egress.
+//!
+//! Overrides follow gcloud's `CLOUDSDK_API_ENDPOINT_OVERRIDES_<API>`
+//! variables, so a shell already set up for gcloud works unchanged. An
+//! override that names only a host keeps the API's usual path.
+
+use std::env;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+
+use anyhow::{Result, bail};
+use reqwest::ClientBuilder;
+
+// hosts gcectl talks to, including the token endpoint
+const GOOGLE_API_HOSTS: &[&str] = &[
+    "compute.googleapis.com",
+    "monitoring.googleapis.com",
+    "logging.googleapis.com",
+    "dns.googleapis.com",
+    "bigquery.googleapis.com",
+    "iam.googleapis.com",
+    "iamcredentials.googleapis.com",
+    "sts.googleapis.com",
+    "oslogin.googleapis.com",
+    "osconfig.googleapis.com",
+    "cloudresourcemanager.googleapis.com",
+    "oauth2.googleapis.com",
+    "www.googleapis.com",
+];
+
+/// The Private Google Access virtual IPs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum GoogleApis {
+    /// private.googleapis.com, 199.36.153.8/30
+    Private,
+    /// restricted.googleapis.com, 199.36.153.4/30, for VPC Service Controls
+    Restricted,
+}
+
+impl FromStr for GoogleApis {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "private" => Ok(Self::Private),
+            "restricted" => Ok(Self::Restricted),
+            _ => bail!("expected private or restricted, got `{s}`"),
+        }
+    }
+}
+
+impl GoogleApis {
+    fn addrs(self) -> Vec<SocketAddr> {
+        let first = match self {
+            Self::Private => 8,
+            Self::Restricted => 4,
+        };
+        (first..first + 4)
+            .map(|last| SocketAddr::new(IpAddr::V4(Ipv4Addr::new(199, 36, 153, last)), 443))
+            .collect()
+    }
+
+    /// Resolves every Google API host to the VIP instead of public DNS. TLS
+    /// still names the real host, which the VIP serves certificates for.
+    pub fn pin(self, builder: ClientBuilder) -> ClientBuilder {
+        let addrs = self.addrs();
+        GOOGLE_API_HOSTS.iter().fold(builder, |builder, host| {
+            builder.resolve_to_addrs(host, &addrs)
+        })
+    }
+}
+
+/// Endpoint overrides for each API client; `None` keeps the public default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Endpoints {
+    pub compute: Option<String>,
+    pub monitoring: Option<String>,
+    pub logging: Option<String>,
+    pub bigquery: Option<String>,
+    pub dns: Option<String>,
+    pub iam: Option<String>,
+    pub iam_credentials: Option<String>,
+    pub oslogin: Option<String>,
+    pub osconfig: Option<String>,
+    pub resource_manager: Option<String>,
+}
+
+impl Endpoints {
+    /// Compute from `--api-endpoint`, then the profile, then gcloud's
+    /// override variable; the other APIs from their override variables.
+    pub fn resolve(compute: Option<&str>) -> Self {
+        Self::resolve_with(compute, |name| env::var(name).ok())
+    }
+
+    fn resolve_with(compute: Option<&str>, var: impl Fn(&str) -> Option<String>) -> Self {
+        let gcloud = |api: &str, path: &str| {
+            var(&format!("CLOUDSDK_API_ENDPOINT_OVERRIDES_{api}"))
+                .filter(|url| !url.is_empty())
+                .map(|url| with_default_path(&url, path))
+        };
+        Self {
+            compute: compute
+                .map(|url| with_default_path(url, "compute/v1"))
+                .or_else(|| gcloud("COMPUTE", "compute/v1")),
+            monitoring: gcloud("MONITORING", "v3"),
+            logging: gcloud("LOGGING", "v2"),
+            bigquery: gcloud("BIGQUERY", "bigquery/v2"),
+            dns: gcloud("DNS", "dns/v1"),
+            iam: gcloud("IAM", "v1"),
+            iam_credentials: gcloud("IAMCREDENTIALS", "v1"),
+            oslogin: gcloud("OSLOGIN", "v1"),
+            osconfig: gcloud("OSCONFIG", "v1"),
+            resource_manager: gcloud("CLOUDRESOURCEMANAGER", "v1"),
+        }
+    }
+}
+
+/// `url` without a trailing slash, with `path` appended when it names only
+/// a scheme and host.
+fn with_default_path(url: &str, path: &str) -> String {
+    let url = url.trim_end_matches('/');
+    let host_only = url
+        .split_once("://")
+        .is_some_and(|(_, rest)| !rest.contains('/'));
+    match host_only {
+        true => format!("{url}/{path}"),
+        false => url.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_only_overrides_keep_the_api_path() {
+        assert_eq!(
+            with_default_path("https://compute-proxy.corp:8443/", "compute/v1"),
+            "https://compute-proxy.corp:8443/compute/v1"
+        );
+        assert_eq!(
+            with_default_path("https://www.googleapis.com/compute/beta/", "compute/v1"),
+            "https://www.googleapis.com/compute/beta"
+        );
+    }
+
+    #[test]
+    fn flag_wins_over_gcloud_overrides() {
+        let var = |name: &str| match name {
+            "CLOUDSDK_API_ENDPOINT_OVERRIDES_COMPUTE" => Some("http://gcloud:1".to_string()),
+            "CLOUDSDK_API_ENDPOINT_OVERRIDES_MONITORING" => Some("http://mon:2/".to_string()),
+            _ => None,
+        };
+        let endpoints = Endpoints::resolve_with(Some("http://flag:3"), var);
+        assert_eq!(
+            endpoints.compute.as_deref(),
+            Some("http://flag:3/compute/v1")
+        );
+        assert_eq!(endpoints.monitoring.as_deref(), Some("http://mon:2/v3"));
+        assert_eq!(endpoints.iam, None);
+        let endpoints = Endpoints::resolve_with(None, var);
+        assert_eq!(
+            endpoints.compute.as_deref(),
+            Some("http://gcloud:1/compute/v1")
+        );
+    }
+
+    #[test]
+    fn vips_cover_four_addresses() {
+        let addrs = GoogleApis::Restricted.addrs();
+        assert_eq!(addrs.len(), 4);
+        assert_eq!(addrs[0].to_string(), "199.36.153.4:443");
+        assert_eq!(
+            GoogleApis::Private.addrs()[3].to_string(),
+            "199.36.153.11:443"
+        );
+    }
+}
@@ -0,0 +1,385 @@
+//! Failures gcectl can explain, each with a hint at what to do next.
+//!
+//! Google API error bodies and failed operations are classified here so
+//! callers can react to a kind of failure (a stock-out, a fingerprint
+//! conflict) and `main` can print a suggestion under the message.
+//!
+//! The kind of failure also decides the exit status, which scripts can rely
+//! on:
+//!
+//! | code | meaning |
+//! |------|---------|
+//! | 0    | success |
+//! | 1    | any other failure |
+//! | 2    | usage: bad flags, or a required setting is missing |
+//! | 3    | authentication or authorization |
+//! | 4    | a resource was not found |
+//! | 5    | the API or an operation reported an error |
+//! | 6    | some resources of a batch failed |
+//! | 124  | `--timeout` ran out |
+//! | 130  | interrupted by Ctrl-C |
+
+use std::fmt;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::resources::operation::OperationErrorItem;
+
+// operation error codes meaning the zone has no capacity left
+const STOCKOUT_CODES: &[&str] = &[
+    "ZONE_RESOURCE_POOL_EXHAUSTED",
+    "ZONE_RESOURCE_POOL_EXHAUSTED_WITH_DETAILS",
+];
+
+#[derive(Debug, Error)]
+pub enum GcectlError {
+    // clap reports its own parse errors with the same code
+    #[error("{0}")]
+    Usage(String),
+    #[error("{0}")]
+    NoCredentials(String),
+    // 401, or a token endpoint refusing the credentials
+    #[error("{0}")]
+    Unauthenticated(ApiFailure),
+    #[error("{0}")]
+    PermissionDenied(ApiFailure),
+    // 403 because the API is not enabled on the project
+    #[error("{0}")]
+    ApiDisabled(ApiFailure),
+    #[error("{0}")]
+    NotFound(ApiFailure),
+    // 409
+    #[error("{0}")]
+    AlreadyExists(ApiFailure),
+    // 412: a fingerprint no longer matches
+    #[error("{0}")]
+    Conflict(ApiFailure),
+    #[error("{0}")]
+    RateLimited(ApiFailure),
+    // either an API response or a failed operation; the message says which
+    #[error("{0}")]
+    QuotaExceeded(String),
+    #[error("{0}")]
+    StockOut(String),
+    #[error("{0}")]
+    Api(ApiFailure),
+    #[error("operation {operation} failed: {message}")]
+    OperationFailed { operation: String, message: String },
+    // the per-resource results were already reported
+    #[error("failed to {action} {failed} of {total} {kind}(s)")]
+    BatchFailed {
+        action: String,
+        kind: String,
+        failed: usize,
+        total: usize,
+    },
+    // Ctrl-C; 130 like a shell reports SIGINT
+    #[error("interrupted{}", waiting_for(operation))]
+    Interrupted { operation: Option<String> },
+    // --timeout; 124 like timeout(1)
+    #[error("timed out after {after:?}{}", waiting_for(operation))]
+    TimedOut {
+        after: Duration,
+        operation: Option<String>,
+    },
+}
+
+fn waiting_for(operation: &Option<String>) -> String {
+    operation
+        .as_ref()
+        .map(|op| format!(" waiting for operation {op}"))
+        .unwrap_or_default()
+}
+
+/// Error status returned by a Google API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiFailure {
+    pub api: String,
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl fmt::Display for ApiFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} returned {}: {}", self.api, self.status, self.message)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    error: ErrorDetail,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ErrorDetail {
+    message: String,
+    // canonical code, e.g. PERMISSION_DENIED
+    #[serde(default)]
+    status: String,
+    // legacy per-error reasons, e.g. quotaExceeded
+    #[serde(default)]
+    errors: Vec<ErrorReason>,
+    // google.rpc.ErrorInfo and friends, e.g. reason SERVICE_DISABLED
+    #[serde(default)]
+    details: Vec<ErrorReason>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorReason {
+    #[serde(default)]
+    reason: String,
+}
+
+impl ErrorDetail {
+    fn has_reason(&self, reasons: &[&str]) -> bool {
+        self.errors
+            .iter()
+            .chain(&self.details)
+            .any(|e| reasons.contains(&e.reason.as_str()))
+    }
+}
+
+impl GcectlError {
+    /// Classifies an error response from `api` by its status and the
+    /// reasons in the standard `{"error": {...}}` body.
+    pub fn from_response(api: &str, status: StatusCode, body: String) -> Self {
+        let detail = serde_json::from_str::<ErrorBody>(&body).map(|b| b.error);
+        let (detail, message) = match detail {
+            Ok(detail) => {
+                let message = detail.message.clone();
+                (detail, message)
+            }
+            Err(_) => (ErrorDetail::default(), body),
+        };
+        let failure = ApiFailure {
+            api: api.to_string(),
+            status,
+            message,
+        };
+        let quota = detail.has_reason(&["quotaExceeded", "QUOTA_EXCEEDED"])
+            || detail.status == "RESOURCE_EXHAUSTED" && status == StatusCode::FORBIDDEN;
+        match status {
+            _ if quota => Self::QuotaExceeded(failure.to_string()),
+            StatusCode::UNAUTHORIZED => Self::Unauthenticated(failure),
+            StatusCode::FORBIDDEN
+                if detail.has_reason(&["accessNotConfigured", "SERVICE_DISABLED"]) =>
+            {
+                Self::ApiDisabled(failure)
+            }
+            StatusCode::FORBIDDEN if detail.has_reason(&["rateLimitExceeded"]) => {
+                Self::RateLimited(failure)
+            }
+            StatusCode::FORBIDDEN => Self::PermissionDenied(failure),
+            StatusCode::NOT_FOUND => Self::NotFound(failure),
+            StatusCode::CONFLICT => Self::AlreadyExists(failure),
+            StatusCode::PRECONDITION_FAILED => Self::Conflict(failure),
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited(failure),
+            _ => Self::Api(failure),
+        }
+    }
+
+    /// Classifies the errors a finished operation reported.
+    pub fn from_operation(operation: &str, errors: &[OperationErrorItem]) -> Self {
+        let message = errors
+            .iter()
+            .map(|e| format!("{}: {}", e.code, e.message))
+            .collect::<Vec<_>>()
+            .join("; ");
+        let has_code = |codes: &[&str]| errors.iter().any(|e| codes.contains(&e.code.as_str()));
+        let text = format!("operation {operation} failed: {message}");
+        if has_code(STOCKOUT_CODES) {
+            Self::StockOut(text)
+        } else if has_code(&["QUOTA_EXCEEDED"]) {
+            Self::QuotaExceeded(text)
+        } else {
+            Self::OperationFailed {
+                operation: operation.to_string(),
+                message,
+            }
+        }
+    }
+
+    /// HTTP status of an API failure.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Unauthenticated(f)
+            | Self::PermissionDenied(f)
+            | Self::ApiDisabled(f)
+            | Self::NotFound(f)
+            | Self::AlreadyExists(f)
+            | Self::Conflict(f)
+            | Self::RateLimited(f)
+            | Self::Api(f) => Some(f.status),
+            _ => None,
+        }
+    }
+
+    /// What the user can do about it.
+    pub fn hint(&self) -> Option<&'static str> {
+        Some(match self {
+            Self::Usage(_) | Self::NoCredentials(_) | Self::BatchFailed { .. } => return None,
+            Self::Unauthenticated(_) => {
+                "the credentials were rejected or have expired; run \
+                 `gcloud auth application-default login` or check GOOGLE_APPLICATION_CREDENTIALS"
+            }
+            Self::PermissionDenied(_) => {
+                "the account lacks an IAM role on this project, e.g. roles/compute.instanceAdmin.v1; \
+                 check --project and the profiles in `gcectl config list-profiles`"
+            }
+            Self::ApiDisabled(_) => {
+                "enable the API on the project, e.g. `gcloud services enable compute.googleapis.com`"
+            }
+            Self::NotFound(_) => {
+                "check the name, --project, and --zone; `gcectl instances list --all-zones` \
+                 finds instances in other zones"
+            }
+            Self::AlreadyExists(_) => "pick another name or delete the existing resource first",
+            Self::Conflict(_) => {
+                "the resource changed while gcectl was updating it; run the command again"
+            }
+            Self::RateLimited(_) => "wait a moment and retry, or raise --retries",
+            Self::QuotaExceeded(_) => {
+                "`gcectl regions list` shows quota usage; free some capacity, pick another \
+                 region, or request a quota increase in the console"
+            }
+            Self::StockOut(_) => {
+                "the zone has no capacity for this machine right now; try another zone or \
+                 machine type, or let `gcectl fleet create` spread over several zones"
+            }
+            Self::Interrupted { operation: Some(_) }
+            | Self::TimedOut {
+                operation: Some(_), ..
+            } => {
+                "the operation carries on without gcectl; `gcectl operations wait NAME` \
+                 picks it back up"
+            }
+            Self::Api(_)
+            | Self::OperationFailed { .. }
+            | Self::Interrupted { .. }
+            | Self::TimedOut { .. } => return None,
+        })
+    }
+
+    /// Process exit status for a command failing with this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Usage(_) => 2,
+            Self::NoCredentials(_) | Self::Unauthenticated(_) | Self::PermissionDenied(_) => 3,
+            Self::NotFound(_) => 4,
+            Self::ApiDisabled(_)
+            | Self::AlreadyExists(_)
+            | Self::Conflict(_)
+            | Self::RateLimited(_)
+            | Self::QuotaExceeded(_)
+            | Self::StockOut(_)
+            | Self::Api(_)
+            | Self::OperationFailed { .. } => 5,
+            Self::BatchFailed { .. } => 6,
+            Self::TimedOut { .. } => 124,
+            Self::Interrupted { .. } => 130,
+        }
+    }
+}
+
+/// The classified error somewhere in `err`'s chain.
+pub fn find(err: &anyhow::Error) -> Option<&GcectlError> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<GcectlError>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(status: u16, body: &str) -> GcectlError {
+        GcectlError::from_response(
+            "Compute Engine API",
+            StatusCode::from_u16(status).unwrap(),
+            body.to_string(),
+        )
+    }
+
+    #[test]
+    fn classifies_api_error_bodies() {
+        let err = classify(
+            403,
+            r#"{"error": {"code": 403, "message": "Required 'compute.instances.stop' permission",
+                "errors": [{"reason": "forbidden"}]}}"#,
+        );
+        assert!(matches!(err, GcectlError::PermissionDenied(_)));
+        assert_eq!(
+            err.to_string(),
+            "Compute Engine API returned 403 Forbidden: Required 'compute.instances.stop' permission"
+        );
+        let err = classify(
+            403,
+            r#"{"error": {"message": "Compute Engine API has not been used",
+                "status": "PERMISSION_DENIED",
+                "details": [{"@type": "type.googleapis.com/google.rpc.ErrorInfo", "reason": "SERVICE_DISABLED"}]}}"#,
+        );
+        assert!(matches!(err, GcectlError::ApiDisabled(_)));
+        let err = classify(
+            403,
+            r#"{"error": {"message": "Quota 'CPUS' exceeded", "errors": [{"reason": "quotaExceeded"}]}}"#,
+        );
+        assert!(matches!(err, GcectlError::QuotaExceeded(_)));
+        assert!(matches!(
+            classify(401, "{}"),
+            GcectlError::Unauthenticated(_)
+        ));
+        assert!(matches!(
+            classify(404, "not json"),
+            GcectlError::NotFound(_)
+        ));
+        assert_eq!(
+            classify(412, r#"{"error": {"message": "stale"}}"#).status(),
+            Some(StatusCode::PRECONDITION_FAILED)
+        );
+        assert!(classify(500, "boom").hint().is_none());
+    }
+
+    #[test]
+    fn classifies_operation_errors() {
+        let item = |code: &str| OperationErrorItem {
+            code: code.into(),
+            message: "no capacity".into(),
+        };
+        let err = GcectlError::from_operation("op-1", &[item("ZONE_RESOURCE_POOL_EXHAUSTED")]);
+        assert!(matches!(err, GcectlError::StockOut(_)));
+        assert_eq!(
+            err.to_string(),
+            "operation op-1 failed: ZONE_RESOURCE_POOL_EXHAUSTED: no capacity"
+        );
+        let err = GcectlError::from_operation("op-2", &[item("RESOURCE_NOT_READY")]);
+        assert!(matches!(err, GcectlError::OperationFailed { .. }));
+        assert!(err.hint().is_none());
+    }
+
+    #[test]
+    fn finds_classified_errors_behind_context() {
+        let err = anyhow::Error::new(classify(401, "{}")).context("failed to list instances");
+        assert!(find(&err).and_then(GcectlError::hint).is_some());
+        assert!(find(&anyhow::anyhow!("plain")).is_none());
+    }
+
+    #[test]
+    fn exit_codes_follow_the_kind_of_failure() {
+        assert_eq!(classify(401, "{}").exit_code(), 3);
+        assert_eq!(classify(403, "{}").exit_code(), 3);
+        assert_eq!(classify(404, "{}").exit_code(), 4);
+        assert_eq!(classify(409, "{}").exit_code(), 5);
+        assert_eq!(classify(503, "{}").exit_code(), 5);
+        assert_eq!(GcectlError::Usage("no zone".into()).exit_code(), 2);
+        let batch = GcectlError::BatchFailed {
+            action: "stop".into(),
+            kind: "instance".into(),
+            failed: 1,
+            total: 3,
+        };
+        assert_eq!(batch.to_string(), "failed to stop 1 of 3 instance(s)");
+        assert_eq!(batch.exit_code(), 6);
+    }
+}
@@ -0,0 +1,152 @@
+//! `--events jsonl`: progress of long-running commands as one JSON object
+//! per line on stdout, for tools and UIs that drive gcectl.
+//!
+//! Each line carries an `event` kind and a `time`. Spinners and the
+//! `[SUCCESS]`-style progress lines become events, results come as
+//! `output` events holding what `--output json` would print, other text
+//! comes as `message` and `dry_run` events, and a failing command ends with
+//! an `error` event before it exits.
+
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum EventFormat {
+    Jsonl,
+}
+
+// set once from `--events` before any command runs
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_format(format: Option<EventFormat>) {
+    ENABLED.store(format == Some(EventFormat::Jsonl), Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// A step that would show a spinner began.
+    Started {
+        message: &'a str,
+    },
+    /// That step ended, successfully or not.
+    Finished {
+        message: &'a str,
+        elapsed_ms: u64,
+    },
+    /// An operation is being waited on.
+    OperationStarted {
+        operation: &'a str,
+        operation_type: &'a str,
+        target: &'a str,
+    },
+    /// A poll found the operation still going.
+    Polling {
+        operation: &'a str,
+        status: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        progress: Option<u32>,
+    },
+    OperationDone {
+        operation: &'a str,
+        operation_type: &'a str,
+        target: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<&'a str>,
+    },
+    /// A write held back by `--dry-run`.
+    DryRun {
+        method: &'a str,
+        url: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        body: Option<&'a Value>,
+    },
+    /// A write sent with `--no-wait`.
+    Requested {
+        what: &'a str,
+        operation: &'a str,
+    },
+    Success {
+        message: &'a str,
+    },
+    Warning {
+        message: &'a str,
+    },
+    Error {
+        message: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        hint: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        exit_code: Option<i32>,
+    },
+    /// A command's result.
+    Output {
+        data: Value,
+    },
+    /// A line besides the result, such as a plan.
+    Message {
+        message: &'a str,
+    },
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    time: String,
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+}
+
+/// Prints `event` as a line of JSON when `--events jsonl` is on.
+pub fn emit(event: &Event) {
+    if !enabled() {
+        return;
+    }
+    let line = Line {
+        time: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        event,
+    };
+    let Ok(json) = serde_json::to_string(&line) else {
+        return;
+    };
+    // one write per event keeps concurrent tasks from interleaving lines
+    let mut out = io::stdout().lock();
+    let _ = writeln!(out, "{json}");
+    let _ = out.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_tagged_objects() {
+        let event = Event::Polling {
+            operation: "operation-1",
+            status: "RUNNING",
+            progress: None,
+        };
+        let line = serde_json::to_value(Line {
+            time: "2026-01-01T00:00:00.000Z".into(),
+            event: &event,
+        })
+        .unwrap();
+        assert_eq!(
+            line,
+            serde_json::json!({
+                "time": "2026-01-01T00:00:00.000Z",
+                "event": "polling",
+                "operation": "operation-1",
+                "status": "RUNNING",
+            })
+        );
+    }
+}
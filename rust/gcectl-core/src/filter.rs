@@ -0,0 +1,217 @@
+//! Filter expressions for list commands.
+//!
+//! Accepts a small language of `FIELD=VALUE` / `FIELD!=VALUE` terms joined
+//! with `AND` or `OR`, e.g. `labels.env=prod AND status=RUNNING`, and
+//! translates it into the Compute API `filter` query parameter so filtering
+//! happens server-side.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Error, Result, anyhow, bail};
+
+/// A parsed `--filter` expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    terms: Vec<Term>,
+    join: Join,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Term {
+    field: String,
+    op: Op,
+    value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Join {
+    And,
+    Or,
+}
+
+impl Filter {
+    /// Renders the expression in Compute API filter syntax.
+    pub fn to_api(&self) -> String {
+        let join = match self.join {
+            Join::And => " AND ",
+            Join::Or => " OR ",
+        };
+        self.terms
+            .iter()
+            .map(|t| {
+                let op = match t.op {
+                    Op::Eq => "=",
+                    Op::Ne => "!=",
+                };
+                format!("({} {op} {})", t.field, quote(&t.value))
+            })
+            .collect::<Vec<_>>()
+            .join(join)
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_api())
+    }
+}
+
+impl FromStr for Filter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let tokens = tokenize(s)?;
+        let mut terms = Vec::new();
+        let mut join = None;
+        let mut expect_term = true;
+        for token in tokens {
+            let keyword = match token.as_str() {
+                t if t.eq_ignore_ascii_case("AND") => Some(Join::And),
+                t if t.eq_ignore_ascii_case("OR") => Some(Join::Or),
+                _ => None,
+            };
+            match (keyword, expect_term) {
+                (None, true) => {
+                    terms.push(parse_term(&token)?);
+                    expect_term = false;
+                }
+                (Some(j), false) => {
+                    if join.is_some_and(|prev| prev != j) {
+                        bail!("cannot mix AND and OR in one filter");
+                    }
+                    join = Some(j);
+                    expect_term = true;
+                }
+                // adjacent terms are implicitly ANDed, as in the API syntax
+                (None, false) => {
+                    if join == Some(Join::Or) {
+                        bail!("cannot mix AND and OR in one filter");
+                    }
+                    join = Some(Join::And);
+                    terms.push(parse_term(&token)?);
+                }
+                (Some(_), true) => bail!("expected FIELD=VALUE, got `{token}`"),
+            }
+        }
+        if terms.is_empty() || expect_term {
+            bail!("filter is empty or ends with a connective");
+        }
+        Ok(Self {
+            terms,
+            join: join.unwrap_or(Join::And),
+        })
+    }
+}
+
+/// Splits on whitespace outside double quotes, dropping the quotes.
+fn tokenize(s: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in s.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if in_quotes {
+        bail!("unterminated quote in filter");
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+fn parse_term(token: &str) -> Result<Term> {
+    let (field, op, value) = if let Some((field, value)) = token.split_once("!=") {
+        (field, Op::Ne, value)
+    } else if let Some((field, value)) = token.split_once('=') {
+        (field, Op::Eq, value)
+    } else {
+        return Err(anyhow!(
+            "expected FIELD=VALUE or FIELD!=VALUE, got `{token}`"
+        ));
+    };
+    let valid_field = !field.is_empty()
+        && field
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if !valid_field {
+        bail!("invalid field name `{field}` in filter");
+    }
+    Ok(Term {
+        field: field.to_string(),
+        op,
+        value: value.to_string(),
+    })
+}
+
+fn quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '*'));
+    match plain {
+        true => value.to_string(),
+        false => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api(s: &str) -> String {
+        s.parse::<Filter>().unwrap().to_api()
+    }
+
+    #[test]
+    fn translates_and_terms() {
+        assert_eq!(
+            api("labels.env=prod AND status=RUNNING"),
+            "(labels.env = prod) AND (status = RUNNING)"
+        );
+    }
+
+    #[test]
+    fn adjacent_terms_default_to_and() {
+        assert_eq!(api("a=1 b!=2"), "(a = 1) AND (b != 2)");
+    }
+
+    #[test]
+    fn or_and_quoting() {
+        assert_eq!(
+            api(r#"name=web-1 or description="my vm""#),
+            r#"(name = web-1) OR (description = "my vm")"#
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_filters() {
+        for bad in [
+            "",
+            "status",
+            "a=1 AND",
+            "AND a=1",
+            "a=1 AND b=2 OR c=3",
+            "=x",
+            "a b=1",
+            "name=\"open",
+        ] {
+            assert!(bad.parse::<Filter>().is_err(), "{bad:?} should not parse");
+        }
+    }
+}
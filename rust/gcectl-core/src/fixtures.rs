@@ -0,0 +1,372 @@
+//! `--record` and `--replay`: API exchanges saved as fixture files, and
+//! played back later without network or credentials.
+//!
+//! Each exchange is one JSON file under the fixture directory, with the
+//! request and the response it got. Credentials are redacted as in the
+//! `-vvv` dumps and never written: token exchanges are not recorded at all,
+//! and replaying needs none.
+//!
+//! A replayed request gets the recorded responses to the same method, path,
+//! and query in the order they were recorded, and the last one again once
+//! they run out, so an operation polled more often than it was when
+//! recording still finishes. One directory holds one run's exchanges;
+//! record each scenario into a directory of its own.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result, bail};
+use reqwest::{Request, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::compute::REQUEST_ID;
+use crate::logging;
+use crate::util::hex;
+
+/// The access token API clients send while replaying.
+pub const REPLAY_TOKEN: &str = "replayed-token";
+
+// response headers worth keeping; the others vary from run to run
+const KEPT_HEADERS: &[&str] = &["content-type", "etag"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Record,
+    Replay,
+}
+
+/// A fixture directory being recorded into or replayed from. Clones share
+/// the position in each exchange sequence.
+#[derive(Debug, Clone)]
+pub struct Fixtures {
+    dir: PathBuf,
+    mode: Mode,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    // exchanges recorded or replayed so far, by key
+    seen: HashMap<String, usize>,
+    // what replaying has to offer, by key, in recorded order
+    recorded: HashMap<String, Vec<Exchange>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Exchange {
+    key: String,
+    // position among the exchanges of the same key, from 1
+    index: usize,
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+/// What is kept of a request, taken before it is sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    method: String,
+    // path and query, without the host
+    url: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    body: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    body: Value,
+}
+
+impl RecordedRequest {
+    pub fn of(request: &Request) -> Self {
+        let url = request.url();
+        // request IDs are new on every run, so they cannot tell requests apart
+        let query = url.query().map(|query| {
+            let kept: Vec<&str> = query
+                .split('&')
+                .filter(|pair| pair.split('=').next() != Some(REQUEST_ID))
+                .collect();
+            logging::redact_query(&kept.join("&"))
+        });
+        let query = query.filter(|q| !q.is_empty());
+        let body = request
+            .body()
+            .and_then(|b| b.as_bytes())
+            .map(|b| stored_body(&String::from_utf8_lossy(b)))
+            .unwrap_or_default();
+        Self {
+            method: request.method().to_string(),
+            url: match query {
+                Some(query) => format!("{}?{query}", url.path()),
+                None => url.path().to_string(),
+            },
+            body,
+        }
+    }
+
+    fn key(&self) -> String {
+        format!("{} {}", self.method, self.url)
+    }
+
+    // tokens are minted afresh when recording and not needed to replay
+    fn is_token_exchange(&self) -> bool {
+        let path = self.url.split('?').next().unwrap_or_default();
+        path.ends_with("/token") || path.ends_with(":generateAccessToken")
+    }
+}
+
+impl Fixtures {
+    /// Records into `dir`, creating it if needed.
+    pub fn record(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create fixture directory {}", dir.display()))?;
+        Ok(Self::new(dir, Mode::Record, State::default()))
+    }
+
+    /// Replays the exchanges recorded in `dir`.
+    pub fn replay(dir: &Path) -> Result<Self> {
+        let entries = fs::read_dir(dir)
+            .with_context(|| format!("failed to read fixture directory {}", dir.display()))?;
+        let mut state = State::default();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let body = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read fixture {}", path.display()))?;
+            let exchange: Exchange = serde_json::from_str(&body)
+                .with_context(|| format!("invalid fixture {}", path.display()))?;
+            state
+                .recorded
+                .entry(exchange.key.clone())
+                .or_default()
+                .push(exchange);
+        }
+        for exchanges in state.recorded.values_mut() {
+            exchanges.sort_by_key(|e| e.index);
+        }
+        Ok(Self::new(dir, Mode::Replay, state))
+    }
+
+    fn new(dir: &Path, mode: Mode, state: State) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            mode,
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// The recorded response to `request`.
+    pub fn replayed(&self, request: &RecordedRequest) -> Result<Response> {
+        let key = request.key();
+        let mut state = self.state.lock().expect("fixtures lock poisoned");
+        let seen = state.seen.entry(key.clone()).or_default();
+        *seen += 1;
+        let index = *seen;
+        let Some(exchanges) = state.recorded.get(&key) else {
+            bail!(
+                "no recorded response to {key} in {}; record one with --record",
+                self.dir.display()
+            );
+        };
+        let exchange = exchanges
+            .get(index - 1)
+            .or(exchanges.last())
+            .expect("recorded keys have exchanges");
+        debug!("replaying {key} from fixture {}", exchange.index);
+        exchange.response.to_response()
+    }
+
+    /// Saves the exchange of `request` and `response`, and hands back a
+    /// response with the same status, headers, and body.
+    pub async fn recorded(&self, request: RecordedRequest, response: Response) -> Result<Response> {
+        if request.is_token_exchange() {
+            return Ok(response);
+        }
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = response
+            .bytes()
+            .await
+            .context("failed to read the response to record")?;
+        let key = request.key();
+        let index = {
+            let mut state = self.state.lock().expect("fixtures lock poisoned");
+            let seen = state.seen.entry(key.clone()).or_default();
+            *seen += 1;
+            *seen
+        };
+        let exchange = Exchange {
+            response: RecordedResponse {
+                status: status.as_u16(),
+                headers: headers
+                    .iter()
+                    .filter(|(name, _)| KEPT_HEADERS.contains(&name.as_str()))
+                    .filter_map(|(name, value)| {
+                        Some((name.to_string(), value.to_str().ok()?.to_string()))
+                    })
+                    .collect(),
+                body: stored_body(&String::from_utf8_lossy(&bytes)),
+            },
+            key,
+            index,
+            request,
+        };
+        let path = self.dir.join(exchange.file_name());
+        fs::write(&path, serde_json::to_string_pretty(&exchange)? + "\n")
+            .with_context(|| format!("failed to write fixture {}", path.display()))?;
+
+        let mut rebuilt = http::Response::builder().status(status);
+        if let Some(all) = rebuilt.headers_mut() {
+            *all = headers;
+        }
+        Ok(rebuilt.body(bytes)?.into())
+    }
+}
+
+impl Exchange {
+    // `GET-instances-web-1-3f2a9c1e-1.json`: the last segments of the path
+    // to tell files apart at a glance, and a digest of the key to keep
+    // different queries apart
+    fn file_name(&self) -> String {
+        let path = self.request.url.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let tail = segments[segments.len().saturating_sub(2)..].join("-");
+        let tail: String = tail
+            .chars()
+            .map(|c| match c.is_ascii_alphanumeric() {
+                true => c,
+                false => '-',
+            })
+            .collect();
+        let digest = Sha256::digest(self.key.as_bytes());
+        let digest = hex(&digest[..4]);
+        format!(
+            "{}-{tail}-{digest}-{}.json",
+            self.request.method, self.index
+        )
+    }
+}
+
+impl RecordedResponse {
+    fn to_response(&self) -> Result<Response> {
+        let mut response = http::Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            response = response.header(name, value);
+        }
+        let body = match &self.body {
+            Value::String(text) => text.clone(),
+            Value::Null => String::new(),
+            json => json.to_string(),
+        };
+        Ok(response.body(body)?.into())
+    }
+}
+
+// JSON kept as JSON, so fixtures read and diff well, and anything else as
+// text; credentials redacted either way
+fn stored_body(body: &str) -> Value {
+    let redacted = logging::redact_body(body);
+    serde_json::from_str(&redacted).unwrap_or(Value::String(redacted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, url: &str, body: &str) -> RecordedRequest {
+        let client = reqwest::Client::new();
+        let builder = match method {
+            "POST" => client.post(url).body(body.to_string()),
+            _ => client.get(url),
+        };
+        RecordedRequest::of(&builder.build().unwrap())
+    }
+
+    fn response(body: &str) -> Response {
+        http::Response::builder()
+            .status(200)
+            .header("content-type", "application/json")
+            .header("date", "Wed, 14 Oct 2026 00:00:00 GMT")
+            .body(body.to_string())
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn recordings_leave_out_credentials() {
+        let info = request(
+            "GET",
+            "https://oauth2.googleapis.com/tokeninfo?access_token=ya29.secret",
+            "",
+        );
+        assert_eq!(info.key(), "GET /tokeninfo?access_token=[REDACTED]");
+        assert!(!info.is_token_exchange());
+        let exchange = request(
+            "POST",
+            "https://oauth2.googleapis.com/token",
+            "grant_type=refresh_token&refresh_token=1//secret",
+        );
+        assert!(exchange.is_token_exchange());
+        let write = request(
+            "POST",
+            "https://compute.googleapis.com/compute/v1/projects/p/zones/z/instances?requestId=3f2a",
+            "{}",
+        );
+        assert_eq!(write.key(), "POST /compute/v1/projects/p/zones/z/instances");
+        assert_eq!(
+            exchange.body,
+            Value::String("grant_type=refresh_token&refresh_token=[REDACTED]".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn replays_in_recorded_order_then_repeats_the_last() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let url = "https://compute.googleapis.com/compute/v1/projects/p/zones/z/operations/op-1";
+        let recording = Fixtures::record(dir.path())?;
+        for status in ["RUNNING", "DONE"] {
+            let body = format!(r#"{{"name":"op-1","status":"{status}"}}"#);
+            recording
+                .recorded(request("GET", url, ""), response(&body))
+                .await?;
+        }
+        let token = r#"{"access_token":"ya29.secret","expires_in":3600}"#;
+        recording
+            .recorded(
+                request("POST", "https://oauth2.googleapis.com/token", ""),
+                response(token),
+            )
+            .await?;
+        assert_eq!(fs::read_dir(dir.path())?.count(), 2);
+
+        let replay = Fixtures::replay(dir.path())?;
+        let mut statuses = Vec::new();
+        for _ in 0..3 {
+            let resp = replay.replayed(&request("GET", url, ""))?;
+            assert_eq!(resp.headers().len(), 1, "only content-type was kept");
+            let op: Value = resp.json().await?;
+            statuses.push(op["status"].as_str().unwrap_or_default().to_string());
+        }
+        assert_eq!(statuses, ["RUNNING", "DONE", "DONE"]);
+        assert!(
+            replay
+                .replayed(&request("GET", "https://x/other", ""))
+                .is_err()
+        );
+        Ok(())
+    }
+}
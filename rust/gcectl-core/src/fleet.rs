@@ -0,0 +1,198 @@
+//! Spreading a fleet of identical instances over several zones.
+
+use serde::Serialize;
+
+use crate::error::{self, GcectlError};
+use crate::output::Render;
+
+// operation error codes and insert-time messages meaning a zone has no capacity
+const STOCKOUT_MARKERS: &[&str] = &[
+    "ZONE_RESOURCE_POOL_EXHAUSTED",
+    "does not have enough resources available",
+];
+
+/// Whether `err` means the zone ran out of capacity, so another zone may
+/// still succeed.
+pub fn is_stockout(err: &anyhow::Error) -> bool {
+    if matches!(error::find(err), Some(GcectlError::StockOut(_))) {
+        return true;
+    }
+    is_stockout_message(&format!("{err:#}"))
+}
+
+/// Whether an error message, such as an audit log result, reports a
+/// stock-out.
+pub fn is_stockout_message(message: &str) -> bool {
+    STOCKOUT_MARKERS
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+/// Member names `{prefix}-1` through `{prefix}-{count}`.
+pub fn member_names(prefix: &str, count: usize) -> Vec<String> {
+    (1..=count).map(|i| format!("{prefix}-{i}")).collect()
+}
+
+/// Zones to try for the `index`th member: its round-robin zone first, then
+/// every other zone in order.
+pub fn zone_order(zones: &[String], index: usize) -> Vec<&str> {
+    let start = index % zones.len().max(1);
+    zones[start..]
+        .iter()
+        .chain(&zones[..start])
+        .map(String::as_str)
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Attempt {
+    Created,
+    Stockout,
+    Failed(String),
+}
+
+/// Every zone tried for one member and how each attempt went.
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub name: String,
+    pub attempts: Vec<(String, Attempt)>,
+}
+
+impl Outcome {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            attempts: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, zone: &str, attempt: Attempt) {
+        self.attempts.push((zone.to_string(), attempt));
+    }
+
+    /// Zone the member was created in.
+    pub fn zone(&self) -> Option<&str> {
+        self.attempts
+            .iter()
+            .find(|(_, attempt)| *attempt == Attempt::Created)
+            .map(|(zone, _)| zone.as_str())
+    }
+
+    /// Why the member could not be created, if it was not.
+    pub fn error(&self) -> Option<String> {
+        match self.attempts.last() {
+            Some((_, Attempt::Created)) => None,
+            Some((zone, Attempt::Failed(message))) => Some(format!("{zone}: {message}")),
+            _ => Some("every zone is out of capacity".to_string()),
+        }
+    }
+}
+
+/// Per-zone tally of a fleet creation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ZoneSummary {
+    pub zone: String,
+    pub created: usize,
+    pub stockouts: usize,
+    pub failed: usize,
+}
+
+impl Render for ZoneSummary {
+    fn headers() -> Vec<&'static str> {
+        vec!["Zone", "Created", "Stock-Outs", "Failed"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.zone.clone(),
+            self.created.to_string(),
+            self.stockouts.to_string(),
+            self.failed.to_string(),
+        ]
+    }
+}
+
+/// Tallies `outcomes` per zone, in the order `zones` were given.
+pub fn summarize(zones: &[String], outcomes: &[Outcome]) -> Vec<ZoneSummary> {
+    zones
+        .iter()
+        .map(|zone| {
+            let mut summary = ZoneSummary {
+                zone: zone.clone(),
+                ..Default::default()
+            };
+            for (_, attempt) in outcomes
+                .iter()
+                .flat_map(|o| &o.attempts)
+                .filter(|(z, _)| z == zone)
+            {
+                match attempt {
+                    Attempt::Created => summary.created += 1,
+                    Attempt::Stockout => summary.stockouts += 1,
+                    Attempt::Failed(_) => summary.failed += 1,
+                }
+            }
+            summary
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    fn zones() -> Vec<String> {
+        vec!["a".into(), "b".into(), "c".into()]
+    }
+
+    #[test]
+    fn members_rotate_through_zones() {
+        assert_eq!(member_names("worker", 2), ["worker-1", "worker-2"]);
+        assert_eq!(zone_order(&zones(), 0), ["a", "b", "c"]);
+        assert_eq!(zone_order(&zones(), 1), ["b", "c", "a"]);
+        assert_eq!(zone_order(&zones(), 5), ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn recognizes_stockouts() {
+        let err = anyhow!("operation op-1 failed: ZONE_RESOURCE_POOL_EXHAUSTED: no capacity");
+        assert!(is_stockout(&err));
+        assert!(!is_stockout(&anyhow!("QUOTA_EXCEEDED: CPUS")));
+    }
+
+    #[test]
+    fn summarizes_attempts_per_zone() {
+        let mut moved = Outcome::new("worker-1");
+        moved.record("a", Attempt::Stockout);
+        moved.record("b", Attempt::Created);
+        let mut failed = Outcome::new("worker-2");
+        failed.record("b", Attempt::Failed("QUOTA_EXCEEDED".into()));
+
+        assert_eq!(moved.zone(), Some("b"));
+        assert_eq!(moved.error(), None);
+        assert_eq!(failed.error().as_deref(), Some("b: QUOTA_EXCEEDED"));
+
+        let summary = summarize(&zones(), &[moved, failed]);
+        assert_eq!(summary[0].stockouts, 1);
+        assert_eq!((summary[1].created, summary[1].failed), (1, 1));
+        assert_eq!(
+            summary[2],
+            ZoneSummary {
+                zone: "c".into(),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn exhausting_every_zone_is_an_error() {
+        let mut outcome = Outcome::new("worker-1");
+        outcome.record("a", Attempt::Stockout);
+        assert_eq!(
+            outcome.error().as_deref(),
+            Some("every zone is out of capacity")
+        );
+    }
+}
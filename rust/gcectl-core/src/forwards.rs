@@ -0,0 +1,605 @@
+//! `tunnel up`: several port forwards kept open by one process, as listed
+//! in a tunnels file:
+//!
+//! ```yaml
+//! project: my-project
+//! zone: us-central1-a
+//! tunnels:
+//!   - name: db
+//!     instance: db-1
+//!     port: 5432
+//!     localPort: 15432
+//!   # a port bound to the instance's localhost needs ssh -L instead of IAP
+//!   - name: jupyter
+//!     instance: gpu-1
+//!     port: 8888
+//!     via: ssh
+//! ```
+//!
+//! IAP forwards open a fresh tunnel per local connection and probe the
+//! instance port to report whether it is reachable; ssh forwards are
+//! restarted with backoff whenever ssh exits. The running process keeps
+//! [`State::path`] current for `tunnel status` and `tunnel down`.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::auth::Authenticator;
+use crate::config::Config;
+use crate::output::{self, Render};
+use crate::ssh;
+use crate::tunnel::{self, IapTarget};
+
+const STATE_FILE_NAME: &str = "tunnels.json";
+// before binding a busy local port again
+const REBIND_DELAY: Duration = Duration::from_secs(5);
+// an ssh forward still running after this long counts as up
+const SSH_SETTLE: Duration = Duration::from_secs(3);
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A tunnels file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TunnelsFile {
+    // defaults for every tunnel, themselves defaulting to the profile
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub zone: Option<String>,
+    pub tunnels: Vec<Forward>,
+}
+
+/// One forward from a local port to a port on an instance.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Forward {
+    pub name: String,
+    pub instance: String,
+    // port on the instance
+    pub port: u16,
+    // defaults to `port`; 0 picks a free one
+    #[serde(default)]
+    pub local_port: Option<u16>,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub zone: Option<String>,
+    #[serde(default = "default_interface")]
+    pub interface: String,
+    #[serde(default)]
+    pub via: Via,
+    // ssh forwards only: reach sshd itself through IAP
+    #[serde(default)]
+    pub tunnel_through_iap: bool,
+}
+
+fn default_interface() -> String {
+    "nic0".to_string()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Via {
+    #[default]
+    Iap,
+    Ssh,
+}
+
+impl Forward {
+    pub fn local_port(&self) -> u16 {
+        self.local_port.unwrap_or(self.port)
+    }
+}
+
+impl TunnelsFile {
+    pub fn read(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let file: Self = serde_yaml::from_str(&text)
+            .with_context(|| format!("{} is not a valid tunnels file", path.display()))?;
+        file.validate()
+            .with_context(|| format!("invalid tunnels file {}", path.display()))?;
+        Ok(file)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.tunnels.is_empty() {
+            bail!("no tunnels are listed");
+        }
+        let mut names = HashSet::new();
+        let mut ports = HashSet::new();
+        for forward in &self.tunnels {
+            if !names.insert(forward.name.as_str()) {
+                bail!("tunnel name {} is used twice", forward.name);
+            }
+            let port = forward.local_port();
+            if port != 0 && !ports.insert(port) {
+                bail!("local port {port} is used by more than one tunnel");
+            }
+            if forward.tunnel_through_iap && forward.via != Via::Ssh {
+                bail!(
+                    "tunnel {}: tunnelThroughIap only applies to `via: ssh`",
+                    forward.name
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A forward with its project and zone settled.
+#[derive(Debug, Clone)]
+pub struct Resolved {
+    pub forward: Forward,
+    pub project: String,
+    pub zone: String,
+}
+
+/// What the `tunnel up` process is running, as kept in [`State::path`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct State {
+    pub pid: u32,
+    pub file: String,
+    pub started: String,
+    pub tunnels: Vec<Status>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Status {
+    pub name: String,
+    pub instance: String,
+    pub via: Via,
+    pub local_port: u16,
+    pub port: u16,
+    pub health: Health,
+    // local connections accepted, or ssh restarts for ssh forwards
+    pub connections: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // when the health last changed
+    pub since: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Health {
+    Connecting,
+    Up,
+    Down,
+}
+
+impl Health {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Connecting => "connecting",
+            Self::Up => "up",
+            Self::Down => "down",
+        }
+    }
+}
+
+impl State {
+    pub fn path() -> Result<PathBuf> {
+        Ok(Config::dir()?.join(STATE_FILE_NAME))
+    }
+
+    /// The state left by a `tunnel up` process, if one wrote any.
+    pub fn read() -> Result<Option<Self>> {
+        let path = Self::path()?;
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()));
+            }
+        };
+        let state = serde_json::from_str(&text)
+            .with_context(|| format!("{} is not a tunnels state file", path.display()))?;
+        Ok(Some(state))
+    }
+
+    fn write(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    pub fn remove() {
+        if let Ok(path) = Self::path() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl Render for Status {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Name",
+            "Instance",
+            "Local",
+            "Remote",
+            "Via",
+            "Health",
+            "Connections",
+            "Since",
+            "Error",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.instance.clone(),
+            format!("localhost:{}", self.local_port),
+            self.port.to_string(),
+            match self.via {
+                Via::Iap => "iap".to_string(),
+                Via::Ssh => "ssh".to_string(),
+            },
+            self.health.as_str().to_string(),
+            self.connections.to_string(),
+            self.since.clone(),
+            self.error.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+/// Whether process `pid` is still running.
+#[cfg(unix)]
+pub fn alive(pid: u32) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Whether process `pid` is still running.
+#[cfg(not(unix))]
+pub fn alive(pid: u32) -> bool {
+    std::process::Command::new("tasklist")
+        .args(["/NH", "/FI", &format!("PID eq {pid}")])
+        .output()
+        .is_ok_and(|out| String::from_utf8_lossy(&out.stdout).contains(&pid.to_string()))
+}
+
+/// Asks process `pid` to shut its tunnels down.
+pub fn terminate(pid: u32) -> Result<()> {
+    #[cfg(unix)]
+    let status = std::process::Command::new("kill")
+        .args(["-TERM", &pid.to_string()])
+        .status();
+    #[cfg(not(unix))]
+    let status = std::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string()])
+        .status();
+    match status
+        .context("failed to signal the tunnel process")?
+        .success()
+    {
+        true => Ok(()),
+        false => bail!("cannot stop tunnel process {pid}"),
+    }
+}
+
+/// Health of every forward, shared by their tasks and written to
+/// [`State::path`] on each change; dropping it removes the file.
+struct Tracker {
+    state: Mutex<State>,
+}
+
+impl Tracker {
+    fn update(&self, name: &str, change: impl FnOnce(&mut Status)) {
+        let mut state = self.state.lock().expect("tracker lock");
+        let Some(status) = state.tunnels.iter_mut().find(|s| s.name == name) else {
+            return;
+        };
+        let (health, error) = (status.health, status.error.clone());
+        change(status);
+        if status.health != health {
+            status.since = Utc::now().to_rfc3339();
+        }
+        if status.health != health || status.error != error {
+            match (&status.error, status.health) {
+                (Some(error), Health::Down) => {
+                    output::print_note(&format!("[{name}] down: {error}"))
+                }
+                _ => output::print_note(&format!(
+                    "[{name}] {}: localhost:{} -> {}:{}",
+                    status.health.as_str(),
+                    status.local_port,
+                    status.instance,
+                    status.port
+                )),
+            }
+        }
+        if let Err(err) = state.write() {
+            warn!("cannot write the tunnels state: {err:#}");
+        }
+    }
+
+    fn health(&self, name: &str, health: Health, error: Option<String>) {
+        self.update(name, |status| {
+            status.health = health;
+            status.error = error;
+        });
+    }
+}
+
+impl Drop for Tracker {
+    fn drop(&mut self) {
+        State::remove();
+    }
+}
+
+/// Runs every forward in `forwards` until the process is interrupted or
+/// asked to stop by `tunnel down`, probing IAP forwards every
+/// `probe_every`.
+pub async fn serve(
+    auth: Arc<Authenticator>,
+    forwards: Vec<Resolved>,
+    file: &Path,
+    probe_every: Duration,
+) -> Result<()> {
+    let now = Utc::now().to_rfc3339();
+    let state = State {
+        pid: std::process::id(),
+        file: file.display().to_string(),
+        started: now.clone(),
+        tunnels: forwards
+            .iter()
+            .map(|r| Status {
+                name: r.forward.name.clone(),
+                instance: r.forward.instance.clone(),
+                via: r.forward.via,
+                local_port: r.forward.local_port(),
+                port: r.forward.port,
+                health: Health::Connecting,
+                connections: 0,
+                error: None,
+                since: now.clone(),
+            })
+            .collect(),
+    };
+    state.write()?;
+    let tracker = Arc::new(Tracker {
+        state: Mutex::new(state),
+    });
+    let exe = std::env::current_exe().context("cannot locate the gcectl executable")?;
+    let exe = exe.display().to_string();
+
+    let tasks = forwards.iter().map(|resolved| {
+        let tracker = tracker.clone();
+        let auth = auth.clone();
+        let exe = exe.as_str();
+        async move {
+            match resolved.forward.via {
+                Via::Iap => run_iap(auth, resolved, &tracker, probe_every).await,
+                Via::Ssh => run_ssh(exe, resolved, &tracker).await,
+            }
+        }
+    });
+    tokio::select! {
+        _ = join_all(tasks) => Ok(()),
+        () = terminated() => Ok(()),
+    }
+}
+
+// SIGTERM is what `tunnel down` sends; Ctrl-C ends the whole command
+async fn terminated() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        if let Ok(mut term) = signal(SignalKind::terminate()) {
+            term.recv().await;
+            return;
+        }
+    }
+    std::future::pending::<()>().await
+}
+
+/// Listens on the forward's local port, tunnelling each connection through
+/// IAP, while probing whether the instance port can be reached.
+async fn run_iap(
+    auth: Arc<Authenticator>,
+    resolved: &Resolved,
+    tracker: &Arc<Tracker>,
+    probe_every: Duration,
+) {
+    let forward = &resolved.forward;
+    let name = forward.name.as_str();
+    let target = Arc::new(IapTarget {
+        project: resolved.project.clone(),
+        zone: resolved.zone.clone(),
+        instance: forward.instance.clone(),
+        interface: forward.interface.clone(),
+        port: forward.port,
+    });
+    loop {
+        let listener = match TcpListener::bind(("127.0.0.1", forward.local_port())).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                let error = format!("cannot listen on port {}: {err}", forward.local_port());
+                tracker.health(name, Health::Down, Some(error));
+                tokio::time::sleep(REBIND_DELAY).await;
+                continue;
+            }
+        };
+        if let Ok(addr) = listener.local_addr() {
+            tracker.update(name, |status| status.local_port = addr.port());
+        }
+        let accept = async {
+            loop {
+                let (socket, peer) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(err) => return err,
+                };
+                tracker.update(name, |status| status.connections += 1);
+                // only the forward's own tasks keep the tracker, and so the
+                // state file, alive
+                let tracker = Arc::downgrade(tracker);
+                let (auth, target) = (auth.clone(), target.clone());
+                let name = name.to_string();
+                tokio::spawn(async move {
+                    let (reader, writer) = socket.into_split();
+                    if let Err(err) = tunnel::relay(&auth, &target, reader, writer).await {
+                        eprintln!("[{name}] connection from {peer}: {err:#}");
+                        if let Some(tracker) = tracker.upgrade() {
+                            tracker.update(&name, |s| s.error = Some(format!("{err:#}")));
+                        }
+                    }
+                });
+            }
+        };
+        let probe = async {
+            loop {
+                match tunnel::probe(&auth, &target).await {
+                    Ok(()) => tracker.health(name, Health::Up, None),
+                    Err(err) => tracker.health(name, Health::Down, Some(format!("{err:#}"))),
+                }
+                tokio::time::sleep(probe_every).await;
+            }
+        };
+        tokio::select! {
+            err = accept => {
+                tracker.health(name, Health::Down, Some(format!("stopped listening: {err}")));
+            }
+            () = probe => {}
+        }
+        tokio::time::sleep(REBIND_DELAY).await;
+    }
+}
+
+/// Keeps `gcectl ssh -- -N -L ...` running, restarting it with backoff
+/// each time it exits.
+async fn run_ssh(exe: &str, resolved: &Resolved, tracker: &Arc<Tracker>) {
+    let forward = &resolved.forward;
+    let name = forward.name.as_str();
+    let args = ssh_args(resolved);
+    let prefix = format!("[{name}] ");
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        tracker.health(name, Health::Connecting, None);
+        let started = Instant::now();
+        let child = ssh::run_prefixed(exe, &args, &prefix);
+        tokio::pin!(child);
+        let result = tokio::select! {
+            result = &mut child => result,
+            () = tokio::time::sleep(SSH_SETTLE) => {
+                tracker.health(name, Health::Up, None);
+                child.await
+            }
+        };
+        // a forward that held for a while starts over from a quick retry
+        if started.elapsed() > MAX_BACKOFF * 2 {
+            backoff = MIN_BACKOFF;
+        }
+        let reason = match result {
+            Ok(()) => "ssh exited".to_string(),
+            Err(err) => format!("ssh failed: {err:#}"),
+        };
+        let error = format!("{reason}; reconnecting in {}s", backoff.as_secs());
+        tracker.update(name, |status| {
+            status.health = Health::Down;
+            status.error = Some(error);
+            status.connections += 1;
+        });
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+fn ssh_args(resolved: &Resolved) -> Vec<String> {
+    let forward = &resolved.forward;
+    let mut args = vec![
+        "ssh".to_string(),
+        forward.instance.clone(),
+        "--project".to_string(),
+        resolved.project.clone(),
+        "--zone".to_string(),
+        resolved.zone.clone(),
+    ];
+    if forward.tunnel_through_iap {
+        args.push("--tunnel-through-iap".to_string());
+    }
+    args.extend(
+        [
+            "--",
+            "-N",
+            "-o",
+            "ExitOnForwardFailure=yes",
+            "-o",
+            "ServerAliveInterval=15",
+            "-L",
+        ]
+        .map(String::from),
+    );
+    args.push(format!(
+        "{}:localhost:{}",
+        forward.local_port(),
+        forward.port
+    ));
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(yaml: &str) -> Result<TunnelsFile> {
+        let file: TunnelsFile = serde_yaml::from_str(yaml)?;
+        file.validate()?;
+        Ok(file)
+    }
+
+    #[test]
+    fn tunnels_default_to_iap_on_the_same_port() {
+        let file = parse(
+            "zone: us-central1-a\ntunnels:\n  - {name: db, instance: db-1, port: 5432}\n  \
+             - {name: nb, instance: gpu-1, port: 8888, localPort: 18888, via: ssh}\n",
+        )
+        .unwrap();
+        assert_eq!(file.tunnels[0].via, Via::Iap);
+        assert_eq!(file.tunnels[0].local_port(), 5432);
+        let resolved = Resolved {
+            forward: file.tunnels[1].clone(),
+            project: "demo".into(),
+            zone: "us-central1-a".into(),
+        };
+        let args = ssh_args(&resolved);
+        assert_eq!(args[..2], ["ssh", "gpu-1"]);
+        assert_eq!(args.last().unwrap(), "18888:localhost:8888");
+    }
+
+    #[test]
+    fn rejects_shared_local_ports_and_names() {
+        let err = parse(
+            "tunnels:\n  - {name: a, instance: vm, port: 80}\n  \
+             - {name: b, instance: vm, port: 8080, localPort: 80}\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "local port 80 is used by more than one tunnel"
+        );
+        assert!(parse("tunnels:\n  - {name: a, instance: vm, port: 80}\n  - {name: a, instance: vm, port: 81}\n").is_err());
+        assert!(parse("tunnels: []\n").is_err());
+    }
+}
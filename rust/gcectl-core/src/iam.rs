@@ -0,0 +1,121 @@
+//! Thin client for the IAM v1 `serviceAccounts` API.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::debug;
+
+use crate::auth::Authenticator;
+use crate::compute::parse_response;
+use crate::output::{Details, Render};
+use crate::transport::Transport;
+
+const IAM_ENDPOINT: &str = "https://iam.googleapis.com/v1";
+
+pub struct Iam {
+    http: Transport,
+    auth: Arc<Authenticator>,
+    endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountsPage {
+    #[serde(default)]
+    accounts: Vec<ServiceAccount>,
+    next_page_token: Option<String>,
+}
+
+/// A service account an instance can run as.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceAccount {
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unique_id: Option<String>,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Render for ServiceAccount {
+    fn headers() -> Vec<&'static str> {
+        vec!["Email", "Display-Name", "Disabled"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.email.clone(),
+            self.display_name.clone().unwrap_or_default(),
+            self.disabled.to_string(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Email", &self.email)
+            .field_opt("Display-Name", self.display_name.as_deref())
+            .field_opt("Description", self.description.as_deref())
+            .field_opt("Unique-Id", self.unique_id.as_deref())
+            .field("Disabled", self.disabled.to_string());
+        details
+    }
+}
+
+impl Iam {
+    pub fn new(http: Transport, auth: Arc<Authenticator>) -> Self {
+        Self {
+            http,
+            auth,
+            endpoint: IAM_ENDPOINT.to_string(),
+        }
+    }
+
+    /// Sends requests to `endpoint` instead of the public API, if given.
+    pub fn endpoint(mut self, endpoint: Option<&str>) -> Self {
+        if let Some(endpoint) = endpoint {
+            self.endpoint = endpoint.trim_end_matches('/').to_string();
+        }
+        self
+    }
+
+    /// `GET projects/{project}/serviceAccounts`, every page
+    pub async fn list_service_accounts(&self, project: &str) -> Result<Vec<ServiceAccount>> {
+        let url = format!("{}/projects/{project}/serviceAccounts", self.endpoint);
+        let mut accounts = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut query = Vec::new();
+            if let Some(token) = page_token.as_deref() {
+                query.push(("pageToken", token));
+            }
+            debug!("GET {url} {query:?}");
+            let request = self
+                .http
+                .get(&url)
+                .query(&query)
+                .bearer_auth(self.auth.token().await?);
+            let resp = self
+                .http
+                .send(request)
+                .await
+                .context("request to IAM API failed")?;
+            let page: AccountsPage = parse_response(resp, "IAM API").await?;
+            accounts.extend(page.accounts);
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => break,
+            }
+        }
+        accounts.sort_by(|a, b| a.email.cmp(&b.email));
+        Ok(accounts)
+    }
+}
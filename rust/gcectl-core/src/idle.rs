@@ -0,0 +1,99 @@
+//! Spotting instances that run without doing any work.
+
+use serde::Serialize;
+
+use crate::output::Render;
+
+/// Utilization below which an instance counts as idle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    // fraction of allocated CPU, 0.0–1.0
+    pub cpu: f64,
+    // bytes per second, sent and received combined
+    pub network: f64,
+}
+
+/// Mean utilization of one instance over the lookback window.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Utilization {
+    pub name: String,
+    pub zone: String,
+    pub machine_type: String,
+    pub cpu: Option<f64>,
+    pub network_bytes_per_sec: Option<f64>,
+}
+
+impl Utilization {
+    /// Idle when CPU and network both stay under `thresholds`. Instances
+    /// without CPU data, such as ones started moments ago, never count.
+    pub fn is_idle(&self, thresholds: Thresholds) -> bool {
+        let Some(cpu) = self.cpu else {
+            return false;
+        };
+        cpu < thresholds.cpu && self.network_bytes_per_sec.unwrap_or(0.0) < thresholds.network
+    }
+}
+
+fn rate(bytes_per_sec: f64) -> String {
+    match bytes_per_sec {
+        b if b >= 1024.0 * 1024.0 => format!("{:.1} MB/s", b / (1024.0 * 1024.0)),
+        b if b >= 1024.0 => format!("{:.1} KB/s", b / 1024.0),
+        b => format!("{b:.0} B/s"),
+    }
+}
+
+impl Render for Utilization {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Zone", "Machine-Type", "CPU-Mean", "Network-Mean"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.zone.clone(),
+            self.machine_type.clone(),
+            self.cpu
+                .map(|c| format!("{:.1}%", c * 100.0))
+                .unwrap_or_default(),
+            self.network_bytes_per_sec.map(rate).unwrap_or_default(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLDS: Thresholds = Thresholds {
+        cpu: 0.05,
+        network: 10.0 * 1024.0,
+    };
+
+    fn usage(cpu: Option<f64>, network: Option<f64>) -> Utilization {
+        Utilization {
+            cpu,
+            network_bytes_per_sec: network,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn idle_needs_low_cpu_and_network() {
+        assert!(usage(Some(0.01), Some(200.0)).is_idle(THRESHOLDS));
+        assert!(usage(Some(0.01), None).is_idle(THRESHOLDS));
+        assert!(!usage(Some(0.20), Some(200.0)).is_idle(THRESHOLDS));
+        assert!(!usage(Some(0.01), Some(50.0 * 1024.0)).is_idle(THRESHOLDS));
+        assert!(!usage(None, Some(0.0)).is_idle(THRESHOLDS));
+    }
+
+    #[test]
+    fn formats_rates() {
+        assert_eq!(
+            usage(Some(0.012), Some(2048.0)).row()[3..],
+            ["1.2%", "2.0 KB/s"]
+        );
+        assert_eq!(rate(12.0), "12 B/s");
+        assert_eq!(rate(3.5 * 1024.0 * 1024.0), "3.5 MB/s");
+    }
+}
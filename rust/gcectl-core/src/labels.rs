@@ -0,0 +1,124 @@
+//! Label edits shared by every labelled resource.
+//!
+//! Labels are replaced as a whole by `setLabels`, guarded by the
+//! `labelFingerprint` read with them, so an edit is applied to the current
+//! set and written back rather than sent on its own.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+const MAX_LABEL_LEN: usize = 63;
+
+/// The labels of a resource and the fingerprint guarding them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Labels {
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    // must be sent back unchanged, or the write is rejected with a 412
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_fingerprint: Option<String>,
+}
+
+/// Labels to set and keys to remove.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LabelEdit {
+    pub set: Vec<(String, String)>,
+    pub remove: Vec<String>,
+}
+
+impl LabelEdit {
+    pub fn set(labels: Vec<(String, String)>) -> Result<Self> {
+        for (key, value) in &labels {
+            validate(key, value)?;
+        }
+        Ok(Self {
+            set: labels,
+            remove: Vec::new(),
+        })
+    }
+
+    pub fn remove(keys: Vec<String>) -> Self {
+        Self {
+            set: Vec::new(),
+            remove: keys,
+        }
+    }
+
+    /// Applies the edit to `labels`, failing if a key to remove is not set.
+    pub fn apply(&self, labels: &mut BTreeMap<String, String>) -> Result<()> {
+        let missing: Vec<&str> = self
+            .remove
+            .iter()
+            .filter(|key| labels.remove(key.as_str()).is_none())
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            bail!("no label {}", missing.join(", "));
+        }
+        labels.extend(self.set.iter().cloned());
+        Ok(())
+    }
+
+    /// What the edit did, e.g. `Set 2 label(s)` or `Removed env, team`.
+    pub fn summary(&self) -> String {
+        match self.remove.is_empty() {
+            true => format!("Set {} label(s)", self.set.len()),
+            false => format!("Removed label(s) {}", self.remove.join(", ")),
+        }
+    }
+}
+
+/// Checks a label against Compute Engine's rules: lowercase letters,
+/// digits, `_` and `-`, at most 63 characters, and keys start with a letter.
+fn validate(key: &str, value: &str) -> Result<()> {
+    let allowed = |c: char| c.is_lowercase() || c.is_ascii_digit() || c == '_' || c == '-';
+    if !key.chars().next().is_some_and(char::is_lowercase) {
+        bail!("label key `{key}` must start with a lowercase letter");
+    }
+    for (what, text) in [("key", key), ("value", value)] {
+        if text.chars().count() > MAX_LABEL_LEN {
+            bail!("label {what} `{text}` is longer than {MAX_LABEL_LEN} characters");
+        }
+        if !text.chars().all(allowed) {
+            bail!("label {what} `{text}` may only contain lowercase letters, digits, `_`, and `-`");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_labels() {
+        assert!(LabelEdit::set(vec![("env".into(), "prod-1".into())]).is_ok());
+        assert!(LabelEdit::set(vec![("team".into(), String::new())]).is_ok());
+        assert!(LabelEdit::set(vec![("Env".into(), "prod".into())]).is_err());
+        assert!(LabelEdit::set(vec![("1st".into(), "x".into())]).is_err());
+        assert!(LabelEdit::set(vec![("env".into(), "Prod".into())]).is_err());
+        assert!(LabelEdit::set(vec![("env".into(), "x".repeat(64))]).is_err());
+    }
+
+    #[test]
+    fn applies_edits() {
+        let mut labels: BTreeMap<String, String> =
+            [("env".into(), "dev".into()), ("team".into(), "ml".into())].into();
+        let edit = LabelEdit::set(vec![("env".into(), "prod".into())]).unwrap();
+        edit.apply(&mut labels).unwrap();
+        assert_eq!(labels["env"], "prod");
+        assert_eq!(edit.summary(), "Set 1 label(s)");
+
+        LabelEdit::remove(vec!["team".into()])
+            .apply(&mut labels)
+            .unwrap();
+        assert!(!labels.contains_key("team"));
+        let err = LabelEdit::remove(vec!["team".into()])
+            .apply(&mut labels)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "no label team");
+    }
+}
@@ -0,0 +1,96 @@
+//! API clients, resource types, and operations behind gcectl.
+//!
+//! Everything the `gcectl` binary does against Google Cloud goes through
+//! this crate, so other tools can do the same without shelling out. The
+//! usual entry point is [`compute::Compute`], built from a
+//! [`transport::Transport`] and an [`auth::Authenticator`] resolved from
+//! Application Default Credentials; its methods are async and return the
+//! typed resources in [`resources`].
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use gcectl_core::auth::Authenticator;
+//! use gcectl_core::compute::Compute;
+//! use gcectl_core::transport::{RetryPolicy, Transport};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let http = Transport::new(reqwest::Client::new(), RetryPolicy::default());
+//! let auth = Arc::new(Authenticator::discover(http.clone()).await?);
+//! let compute = Compute::new(http, auth);
+//! for instance in compute.list_instances("my-project", "us-central1-a", None).await? {
+//!     println!("{} {}", instance.name, instance.status);
+//! }
+//! let op = compute
+//!     .start_instance("my-project", "us-central1-a", "web-1")
+//!     .await?;
+//! compute.wait_operation(op).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Writes return the [`resources::Operation`] the API started, which
+//! [`compute::Compute::wait_operation`] polls until it is done. Errors are [`anyhow::Error`]s, and
+//! [`error::find`] digs the classified [`error::GcectlError`] out of one.
+//! Tables and details for the command line come from [`output::Render`],
+//! which every resource implements.
+//!
+//! What the commands do beyond single API calls lives here too: placement
+//! and fleets in [`placement`] and [`fleet`], moving instances in
+//! [`relocate`], draining in [`drain`], schedules in [`schedule`], SSH in
+//! [`ssh`], and image builds in [`bake`], among others. The binary itself
+//! only parses flags, prompts, and prints.
+
+pub mod audit;
+pub mod auth;
+pub mod bake;
+pub mod batch;
+pub mod billing;
+pub mod cache;
+pub mod cancel;
+pub mod cloud_logging;
+pub mod compute;
+pub mod config;
+pub mod console;
+pub mod context;
+pub mod cost;
+pub mod diagnose;
+pub mod diff;
+pub mod disk_advisor;
+pub mod dns;
+pub mod drain;
+pub mod endpoints;
+pub mod error;
+pub mod events;
+pub mod filter;
+pub mod fixtures;
+pub mod fleet;
+pub mod forwards;
+pub mod iam;
+pub mod idle;
+pub mod labels;
+pub mod logging;
+pub mod manifest;
+pub mod metrics;
+pub mod monitoring;
+pub mod naming;
+pub mod notify;
+pub mod osconfig;
+pub mod oslogin;
+pub mod output;
+pub mod placement;
+pub mod relocate;
+pub mod resource_manager;
+pub mod resources;
+pub mod schedule;
+pub mod secrets;
+pub mod security;
+pub mod ssh;
+pub mod ssh_keys;
+pub mod terraform;
+pub mod transport;
+pub mod tunnel;
+pub mod util;
+pub mod validate;
+pub mod watch;
+pub mod windows;
@@ -0,0 +1,146 @@
+//! Redaction for diagnostic logs.
+//!
+//! `-vvv` dumps every HTTP request and response under the `gcectl::http`
+//! target with credentials redacted from headers, bodies, and query
+//! strings, as are the URLs in warnings. The subscriber itself is installed
+//! by the binary.
+
+use reqwest::Url;
+use reqwest::header::HeaderMap;
+
+/// Target of the request and response dumps shown by `-vvv`.
+pub const HTTP_TARGET: &str = "gcectl::http";
+
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+// headers that carry credentials
+const SECRET_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-goog-iam-authorization-token",
+];
+
+// JSON and form fields that carry credentials, e.g. in token exchanges
+const SECRET_FIELDS: &[&str] = &[
+    "access_token",
+    "accessToken",
+    "id_token",
+    "refresh_token",
+    "client_secret",
+    "private_key",
+    "assertion",
+    "subject_token",
+    "password",
+];
+
+// query parameters that carry credentials: the fields above, API keys, and
+// bare tokens
+const SECRET_PARAMS: &[&str] = &["key", "token"];
+
+/// Header names and values with credentials replaced.
+pub fn redact_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = match SECRET_HEADERS.contains(&name.as_str()) {
+                true => REDACTED.to_string(),
+                false => value.to_str().unwrap_or("<binary>").to_string(),
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+/// A JSON or form-encoded body with credential fields replaced; anything
+/// else is returned as is.
+pub fn redact_body(body: &str) -> String {
+    if let Ok(mut json) = serde_json::from_str::<serde_json::Value>(body) {
+        redact_json(&mut json);
+        return json.to_string();
+    }
+    let looks_like_form = !body.contains(char::is_whitespace) && body.contains('=');
+    if !looks_like_form {
+        return body.to_string();
+    }
+    redact_pairs(body, |_| false)
+}
+
+/// `url` with the values of credential query parameters replaced.
+pub fn redact_url(url: &Url) -> String {
+    let mut redacted = url.clone();
+    if let Some(query) = url.query() {
+        redacted.set_query(Some(&redact_query(query)));
+    }
+    redacted.to_string()
+}
+
+/// A query string with the values of credential parameters replaced.
+pub fn redact_query(query: &str) -> String {
+    redact_pairs(query, |key| SECRET_PARAMS.contains(&key))
+}
+
+fn redact_pairs(pairs: &str, also: impl Fn(&str) -> bool) -> String {
+    pairs
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _)) if SECRET_FIELDS.contains(&key) || also(key) => {
+                format!("{key}={REDACTED}")
+            }
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn redact_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match SECRET_FIELDS.contains(&key.as_str()) {
+                    true => *value = REDACTED.into(),
+                    false => redact_json(value),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderValue};
+
+    use super::*;
+
+    #[test]
+    fn redacts_credential_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_static("Bearer ya29.secret"),
+        );
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        let redacted = redact_headers(&headers);
+        assert!(redacted.contains(&("authorization".to_string(), REDACTED.to_string())));
+        assert!(redacted.contains(&("content-type".to_string(), "application/json".to_string())));
+    }
+
+    #[test]
+    fn redacts_credential_fields_in_bodies() {
+        let json = r#"{"access_token":"ya29.x","expires_in":3599,"nested":[{"private_key":"k"}]}"#;
+        let redacted = redact_body(json);
+        assert!(!redacted.contains("ya29.x"));
+        assert!(!redacted.contains("\"k\""));
+        assert!(redacted.contains("3599"));
+
+        let form = "grant_type=refresh_token&refresh_token=1//abc&client_id=id";
+        assert_eq!(
+            redact_body(form),
+            "grant_type=refresh_token&refresh_token=[REDACTED]&client_id=id"
+        );
+        assert_eq!(redact_body("plain text body"), "plain text body");
+    }
+}
@@ -0,0 +1,548 @@
+//! Declarative instance definitions for `export instances` and `apply`.
+//!
+//! A manifest lists the instances a project should have. [`plan`] compares
+//! it with the instances that exist and works out what to create, update,
+//! and (with `--prune`) delete. Only the machine type, labels, metadata, and
+//! power state of an existing instance are reconciled, and only those the
+//! manifest declares; the image, boot disk, network, and service account are
+//! used when an instance is created.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::labels::LabelEdit;
+use crate::resources::instance::builder::{
+    DEFAULT_MACHINE_TYPE, ImageSource, InstanceBuilder, Provisioning,
+};
+use crate::resources::{Disk, Instance, short_name};
+
+/// The instances a project should have.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub instances: Vec<InstanceSpec>,
+}
+
+/// One declared instance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct InstanceSpec {
+    pub name: String,
+    pub zone: String,
+    #[serde(default = "default_machine_type")]
+    pub machine_type: String,
+    // left alone when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<PowerState>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub spot: bool,
+    // left alone when unset; `{}` removes them all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labels: Option<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<BTreeMap<String, String>>,
+    // image path such as `projects/debian-cloud/global/images/family/debian-12`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub boot_disk_size_gb: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub boot_disk_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subnet: Option<String>,
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub external_ip: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_account: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
+}
+
+/// Power state an instance should be left in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PowerState {
+    Running,
+    Terminated,
+}
+
+impl PowerState {
+    pub fn api_name(self) -> &'static str {
+        match self {
+            Self::Running => "RUNNING",
+            Self::Terminated => "TERMINATED",
+        }
+    }
+}
+
+fn default_machine_type() -> String {
+    DEFAULT_MACHINE_TYPE.to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl Manifest {
+    /// Parses a manifest, rejecting instances declared twice.
+    pub fn parse(yaml: &str) -> Result<Self> {
+        let manifest: Self = serde_yaml::from_str(yaml).context("invalid manifest")?;
+        let mut seen = HashSet::new();
+        for spec in &manifest.instances {
+            if !seen.insert((spec.zone.as_str(), spec.name.as_str())) {
+                bail!("instance {} in {} is declared twice", spec.name, spec.zone);
+            }
+            if let Some(labels) = &spec.labels {
+                LabelEdit::set(labels.clone().into_iter().collect())
+                    .with_context(|| format!("instance {}", spec.name))?;
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+impl InstanceSpec {
+    /// Declaration reproducing `instance`; `disks` supplies the boot disk's
+    /// image, size, and type, which the instance itself does not carry.
+    pub fn from_instance(instance: &Instance, disks: &HashMap<&str, &Disk>) -> Self {
+        let boot = instance
+            .disks
+            .iter()
+            .find(|d| d.boot)
+            .and_then(|d| disks.get(d.source.as_deref()?));
+        let nic = instance.network_interfaces.first();
+        let account = instance.service_accounts.first();
+        Self {
+            name: instance.name.clone(),
+            zone: instance.zone_name().to_string(),
+            machine_type: instance.machine_type_name().to_string(),
+            status: match instance.status.as_str() {
+                "RUNNING" => Some(PowerState::Running),
+                "TERMINATED" => Some(PowerState::Terminated),
+                _ => None,
+            },
+            spot: instance.is_spot(),
+            labels: Some(instance.labels.clone()).filter(|labels| !labels.is_empty()),
+            metadata: Some(metadata_map(instance)).filter(|metadata| !metadata.is_empty()),
+            image: boot
+                .and_then(|d| d.source_image.as_deref())
+                .and_then(ImageSource::from_path)
+                .map(|image| image.url()),
+            boot_disk_size_gb: boot
+                .and_then(|d| d.size_gb.as_deref())
+                .and_then(|s| s.parse().ok()),
+            boot_disk_type: boot.map(|d| d.type_name().to_string()),
+            network: nic
+                .and_then(|n| n.network.as_deref())
+                .map(|n| short_name(n).to_string()),
+            subnet: nic
+                .and_then(|n| n.subnetwork.as_deref())
+                .map(|s| short_name(s).to_string()),
+            external_ip: nic.is_some_and(|n| !n.access_configs.is_empty()),
+            service_account: account.map(|a| a.email.clone()),
+            scopes: account.map(|a| a.scopes.clone()).unwrap_or_default(),
+        }
+    }
+
+    /// Builder for creating the declared instance.
+    pub fn builder(&self) -> Result<InstanceBuilder> {
+        let mut builder = InstanceBuilder::new(&self.name, &self.zone)
+            .machine_type(&self.machine_type)
+            .boot_disk_size_gb(self.boot_disk_size_gb)
+            .boot_disk_type(self.boot_disk_type.clone())
+            .network(self.network.clone())
+            .subnet(self.subnet.clone())
+            .external_ip(self.external_ip)
+            .labels(self.labels.clone().unwrap_or_default())
+            .metadata(self.metadata.clone().unwrap_or_default())
+            .provisioning(match self.spot {
+                true => Provisioning::Spot,
+                false => Provisioning::Standard,
+            })
+            .service_account(self.service_account.clone());
+        if let Some(image) = &self.image {
+            let source = ImageSource::from_path(image).with_context(|| {
+                format!(
+                    "instance {}: image `{image}` is not a path like \
+                     projects/debian-cloud/global/images/family/debian-12",
+                    self.name
+                )
+            })?;
+            builder = builder.image(source);
+        }
+        if !self.scopes.is_empty() {
+            builder = builder.scopes(self.scopes.clone());
+        }
+        Ok(builder)
+    }
+}
+
+fn metadata_map(instance: &Instance) -> BTreeMap<String, String> {
+    instance
+        .metadata
+        .iter()
+        .flat_map(|m| &m.items)
+        .map(|item| (item.key.clone(), item.value.clone()))
+        .collect()
+}
+
+/// One step towards the declared state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Create(Box<InstanceSpec>),
+    Update {
+        name: String,
+        zone: String,
+        // status before the update, which decides whether it needs a stop
+        status: String,
+        updates: Vec<Update>,
+    },
+    Delete {
+        name: String,
+        zone: String,
+    },
+}
+
+/// A difference between a declared and an existing instance.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Update {
+    MachineType { from: String, to: String },
+    Labels(LabelEdit),
+    Metadata(MetadataEdit),
+    Power { from: String, to: PowerState },
+}
+
+/// Metadata keys to set and remove.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetadataEdit {
+    pub set: Vec<(String, String)>,
+    pub remove: Vec<String>,
+}
+
+/// Works out the changes that turn `actual` into what `manifest` declares.
+/// Undeclared instances are only deleted when `prune` is set, and only in
+/// zones the manifest mentions.
+pub fn plan(manifest: &Manifest, actual: &[Instance], prune: bool) -> Vec<Change> {
+    let existing: HashMap<(&str, &str), &Instance> = actual
+        .iter()
+        .map(|i| ((i.zone_name(), i.name.as_str()), i))
+        .collect();
+    let mut changes = Vec::new();
+    for spec in &manifest.instances {
+        match existing.get(&(spec.zone.as_str(), spec.name.as_str())) {
+            None => changes.push(Change::Create(Box::new(spec.clone()))),
+            Some(instance) => {
+                let updates = updates(spec, instance);
+                if !updates.is_empty() {
+                    changes.push(Change::Update {
+                        name: spec.name.clone(),
+                        zone: spec.zone.clone(),
+                        status: instance.status.clone(),
+                        updates,
+                    });
+                }
+            }
+        }
+    }
+    if prune {
+        let declared: Vec<(&str, &str)> = manifest
+            .instances
+            .iter()
+            .map(|s| (s.zone.as_str(), s.name.as_str()))
+            .collect();
+        for instance in actual {
+            let key = (instance.zone_name(), instance.name.as_str());
+            let in_scope = declared.iter().any(|(zone, _)| *zone == key.0);
+            if in_scope && !declared.contains(&key) {
+                changes.push(Change::Delete {
+                    name: instance.name.clone(),
+                    zone: instance.zone_name().to_string(),
+                });
+            }
+        }
+    }
+    changes
+}
+
+fn updates(spec: &InstanceSpec, instance: &Instance) -> Vec<Update> {
+    let mut updates = Vec::new();
+    if instance.machine_type_name() != spec.machine_type {
+        updates.push(Update::MachineType {
+            from: instance.machine_type_name().to_string(),
+            to: spec.machine_type.clone(),
+        });
+    }
+    if let Some(labels) = &spec.labels {
+        let (set, remove) = map_diff(&instance.labels, labels);
+        if !set.is_empty() || !remove.is_empty() {
+            updates.push(Update::Labels(LabelEdit { set, remove }));
+        }
+    }
+    if let Some(metadata) = &spec.metadata {
+        let (set, remove) = map_diff(&metadata_map(instance), metadata);
+        if !set.is_empty() || !remove.is_empty() {
+            updates.push(Update::Metadata(MetadataEdit { set, remove }));
+        }
+    }
+    if let Some(to) = spec.status
+        && instance.status != to.api_name()
+    {
+        updates.push(Update::Power {
+            from: instance.status.clone(),
+            to,
+        });
+    }
+    updates
+}
+
+/// Entries of `want` that `have` lacks or differs on, and keys only `have` has.
+fn map_diff(
+    have: &BTreeMap<String, String>,
+    want: &BTreeMap<String, String>,
+) -> (Vec<(String, String)>, Vec<String>) {
+    let set = want
+        .iter()
+        .filter(|(key, value)| have.get(*key) != Some(*value))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    let remove = have
+        .keys()
+        .filter(|key| !want.contains_key(*key))
+        .cloned()
+        .collect();
+    (set, remove)
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Create(spec) => write!(
+                f,
+                "+ create {} ({}, {})",
+                spec.name, spec.zone, spec.machine_type
+            ),
+            Self::Delete { name, zone } => write!(f, "- delete {name} ({zone})"),
+            Self::Update {
+                name,
+                zone,
+                status,
+                updates,
+            } => {
+                write!(f, "~ update {name} ({zone})")?;
+                for update in updates {
+                    match update {
+                        Update::MachineType { from, to } if status == "RUNNING" => {
+                            write!(f, "\n    machine type: {from} -> {to} (stops the instance)")?
+                        }
+                        Update::MachineType { from, to } => {
+                            write!(f, "\n    machine type: {from} -> {to}")?
+                        }
+                        Update::Labels(edit) => {
+                            for (key, value) in &edit.set {
+                                write!(f, "\n    label {key}={value}")?;
+                            }
+                            for key in &edit.remove {
+                                write!(f, "\n    remove label {key}")?;
+                            }
+                        }
+                        Update::Metadata(edit) => {
+                            for (key, _) in &edit.set {
+                                write!(f, "\n    metadata {key}")?;
+                            }
+                            for key in &edit.remove {
+                                write!(f, "\n    remove metadata {key}")?;
+                            }
+                        }
+                        Update::Power { from, to } => {
+                            write!(f, "\n    status: {from} -> {}", to.api_name())?
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// One-line count of `changes`, e.g. `Plan: 1 to create, 2 to update, 0 to delete.`
+pub fn summary(changes: &[Change]) -> String {
+    let count = |f: fn(&Change) -> bool| changes.iter().filter(|c| f(c)).count();
+    format!(
+        "Plan: {} to create, {} to update, {} to delete.",
+        count(|c| matches!(c, Change::Create(_))),
+        count(|c| matches!(c, Change::Update { .. })),
+        count(|c| matches!(c, Change::Delete { .. })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::instance::{Metadata, MetadataItem};
+
+    fn existing(name: &str, machine_type: &str, status: &str) -> Instance {
+        Instance {
+            name: name.to_string(),
+            zone: "https://www.googleapis.com/compute/v1/projects/p/zones/us-central1-a"
+                .to_string(),
+            machine_type: format!("zones/us-central1-a/machineTypes/{machine_type}"),
+            status: status.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn manifest(yaml: &str) -> Manifest {
+        Manifest::parse(yaml).unwrap()
+    }
+
+    #[test]
+    fn parses_with_defaults() {
+        let manifest = manifest("instances:\n  - name: web\n    zone: us-central1-a\n");
+        let spec = &manifest.instances[0];
+        assert_eq!(spec.machine_type, DEFAULT_MACHINE_TYPE);
+        assert!(spec.external_ip);
+        assert_eq!(spec.status, None);
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_duplicates() {
+        assert!(
+            Manifest::parse("instances:\n  - name: a\n    zone: z\n    colour: red\n").is_err()
+        );
+        let twice = "instances:\n  - {name: a, zone: z}\n  - {name: a, zone: z}\n";
+        let err = Manifest::parse(twice).unwrap_err();
+        assert!(err.to_string().contains("declared twice"));
+        assert!(Manifest::parse("instances:\n  - {name: a, zone: z, labels: {Env: x}}\n").is_err());
+    }
+
+    #[test]
+    fn plans_creates_updates_and_prunes() {
+        let mut web = existing("web", "e2-small", "RUNNING");
+        web.labels.insert("env".to_string(), "dev".to_string());
+        web.labels.insert("old".to_string(), "x".to_string());
+        web.metadata = Some(Metadata {
+            items: vec![MetadataItem {
+                key: "startup-script".to_string(),
+                value: "echo hi".to_string(),
+            }],
+            ..Default::default()
+        });
+        let actual = [web, existing("stray", "e2-medium", "RUNNING")];
+        let manifest = manifest(
+            "instances:\n\
+             - name: web\n  zone: us-central1-a\n  machineType: e2-medium\n  \
+               status: TERMINATED\n  labels: {env: prod}\n  metadata: {}\n\
+             - name: api\n  zone: us-central1-a\n",
+        );
+
+        let changes = plan(&manifest, &actual, false);
+        assert_eq!(changes.len(), 2);
+        let Change::Update { updates, .. } = &changes[0] else {
+            panic!("expected an update, got {:?}", changes[0]);
+        };
+        assert_eq!(
+            updates,
+            &[
+                Update::MachineType {
+                    from: "e2-small".to_string(),
+                    to: "e2-medium".to_string(),
+                },
+                Update::Labels(LabelEdit {
+                    set: vec![("env".to_string(), "prod".to_string())],
+                    remove: vec!["old".to_string()],
+                }),
+                Update::Metadata(MetadataEdit {
+                    set: vec![],
+                    remove: vec!["startup-script".to_string()],
+                }),
+                Update::Power {
+                    from: "RUNNING".to_string(),
+                    to: PowerState::Terminated,
+                },
+            ]
+        );
+        assert!(matches!(&changes[1], Change::Create(spec) if spec.name == "api"));
+
+        let pruned = plan(&manifest, &actual, true);
+        assert_eq!(
+            pruned.last(),
+            Some(&Change::Delete {
+                name: "stray".to_string(),
+                zone: "us-central1-a".to_string(),
+            })
+        );
+        assert_eq!(
+            summary(&pruned),
+            "Plan: 1 to create, 1 to update, 1 to delete."
+        );
+    }
+
+    #[test]
+    fn leaves_undeclared_labels_and_metadata_alone() {
+        let mut web = existing("web", DEFAULT_MACHINE_TYPE, "RUNNING");
+        web.labels.insert("env".to_string(), "dev".to_string());
+        web.metadata = Some(Metadata {
+            items: vec![MetadataItem {
+                key: "startup-script".to_string(),
+                value: "echo hi".to_string(),
+            }],
+            ..Default::default()
+        });
+        let manifest = manifest("instances:\n  - {name: web, zone: us-central1-a}\n");
+        assert!(plan(&manifest, &[web], false).is_empty());
+    }
+
+    #[test]
+    fn prune_leaves_zones_outside_the_manifest_alone() {
+        let mut elsewhere = existing("other", "e2-medium", "RUNNING");
+        elsewhere.zone = "projects/p/zones/europe-west1-b".to_string();
+        let manifest = manifest("instances:\n  - {name: web, zone: us-central1-a}\n");
+        let changes = plan(&manifest, &[elsewhere], true);
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(&changes[0], Change::Create(_)));
+    }
+
+    #[test]
+    fn exported_instances_plan_no_changes() {
+        let mut instance = existing("web", "n2-standard-4", "RUNNING");
+        instance.labels.insert("team".to_string(), "ml".to_string());
+        let spec = InstanceSpec::from_instance(&instance, &HashMap::new());
+        let exported = Manifest {
+            project: Some("p".to_string()),
+            instances: vec![spec],
+        };
+        let yaml = serde_yaml::to_string(&exported).unwrap();
+        let reparsed = Manifest::parse(&yaml).unwrap();
+        assert_eq!(reparsed, exported);
+        assert!(plan(&reparsed, &[instance], true).is_empty());
+    }
+
+    #[test]
+    fn create_uses_the_declared_image() {
+        let yaml = "instances:\n  - name: web\n    zone: us-central1-a\n    \
+                    image: projects/ubuntu-os-cloud/global/images/family/ubuntu-2404-lts-amd64\n    \
+                    spot: true\n";
+        let body = manifest(yaml).instances[0].builder().unwrap().build();
+        assert_eq!(
+            body["disks"][0]["initializeParams"]["sourceImage"],
+            "projects/ubuntu-os-cloud/global/images/family/ubuntu-2404-lts-amd64"
+        );
+        assert_eq!(body["scheduling"]["provisioningModel"], "SPOT");
+    }
+}
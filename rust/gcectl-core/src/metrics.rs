@@ -0,0 +1,267 @@
+//! Prometheus text exposition of fleet state for `metrics serve`.
+//!
+//! Only enough HTTP is spoken to answer `GET /metrics`; the exposition is
+//! rebuilt on each refresh and served from memory between them.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+use crate::resources::Instance;
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// One refresh worth of fleet state.
+#[derive(Debug, Default)]
+pub struct Sample<'a> {
+    pub project: &'a str,
+    pub instances: &'a [Instance],
+    // estimated monthly cost of each instance, in the same order
+    pub costs: &'a [f64],
+    // label keys broken out into their own gauge
+    pub label_keys: &'a [String],
+    // unix time of the refresh
+    pub timestamp: i64,
+}
+
+/// Renders `sample` in the Prometheus text format.
+pub fn render(sample: &Sample) -> String {
+    let project = sample.project;
+    let mut by_status: BTreeMap<(&str, &str), u64> = BTreeMap::new();
+    let mut by_label: BTreeMap<(&str, &str, &str), u64> = BTreeMap::new();
+    let mut cost_by_zone: BTreeMap<&str, f64> = BTreeMap::new();
+    for (i, instance) in sample.instances.iter().enumerate() {
+        let zone = instance.zone_name();
+        *by_status.entry((zone, &instance.status)).or_default() += 1;
+        for key in sample.label_keys {
+            let value = instance.labels.get(key).map_or("", String::as_str);
+            *by_label.entry((key, value, &instance.status)).or_default() += 1;
+        }
+        *cost_by_zone.entry(zone).or_default() += sample.costs.get(i).copied().unwrap_or(0.0);
+    }
+
+    let mut out = String::new();
+    header(
+        &mut out,
+        "gcectl_instances",
+        "Instances by zone and status.",
+    );
+    for ((zone, status), count) in &by_status {
+        let labels = [("project", project), ("zone", zone), ("status", status)];
+        sample_line(&mut out, "gcectl_instances", &labels, *count as f64);
+    }
+    if !sample.label_keys.is_empty() {
+        header(
+            &mut out,
+            "gcectl_instances_by_label",
+            "Instances by label value and status; an empty value means the label is unset.",
+        );
+        for ((key, value, status), count) in &by_label {
+            let labels = [
+                ("project", project),
+                ("label", key),
+                ("value", value),
+                ("status", status),
+            ];
+            sample_line(
+                &mut out,
+                "gcectl_instances_by_label",
+                &labels,
+                *count as f64,
+            );
+        }
+    }
+    header(
+        &mut out,
+        "gcectl_estimated_monthly_cost_usd",
+        "Estimated monthly cost of instances by zone, at list prices.",
+    );
+    for (zone, cost) in &cost_by_zone {
+        let labels = [("project", project), ("zone", zone)];
+        sample_line(
+            &mut out,
+            "gcectl_estimated_monthly_cost_usd",
+            &labels,
+            *cost,
+        );
+    }
+    header(
+        &mut out,
+        "gcectl_last_refresh_timestamp_seconds",
+        "Unix time of the last successful refresh.",
+    );
+    sample_line(
+        &mut out,
+        "gcectl_last_refresh_timestamp_seconds",
+        &[("project", project)],
+        sample.timestamp as f64,
+    );
+    out
+}
+
+fn header(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} gauge");
+}
+
+fn sample_line(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| format!("{key}=\"{}\"", escape(value)))
+        .collect();
+    let _ = writeln!(out, "{name}{{{}}} {value}", labels.join(","));
+}
+
+/// Escapes a label value as the text format requires.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+/// Answers scrapes on `listener` with whatever `body` holds at the time.
+pub async fn serve(listener: TcpListener, body: Arc<RwLock<String>>) -> Result<()> {
+    loop {
+        let (socket, peer) = listener.accept().await?;
+        let body = body.clone();
+        tokio::spawn(async move {
+            if let Err(err) = respond(socket, &body).await {
+                warn!("metrics request from {peer}: {err:#}");
+            }
+        });
+    }
+}
+
+async fn respond(socket: TcpStream, body: &RwLock<String>) -> Result<()> {
+    let mut reader = BufReader::new(socket);
+    let mut request_line = String::new();
+    reader
+        .read_line(&mut request_line)
+        .await
+        .context("failed to read request")?;
+    debug!("metrics request: {}", request_line.trim_end());
+    // drain headers so clients are not reset before reading the response
+    let mut line = String::new();
+    while reader.read_line(&mut line).await? > 2 {
+        line.clear();
+    }
+
+    let (status, content_type, payload) = match route(&request_line) {
+        Route::Metrics => ("200 OK", CONTENT_TYPE, body.read().await.clone()),
+        Route::NotFound => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        Route::MethodNotAllowed => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n".to_string(),
+        ),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{payload}",
+        payload.len()
+    );
+    let mut socket = reader.into_inner();
+    socket.write_all(response.as_bytes()).await?;
+    socket.shutdown().await?;
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Route {
+    Metrics,
+    NotFound,
+    MethodNotAllowed,
+}
+
+fn route(request_line: &str) -> Route {
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Route::NotFound;
+    };
+    let path = target.split('?').next().unwrap_or(target);
+    match (method, path) {
+        ("GET", "/metrics") => Route::Metrics,
+        (_, "/metrics") => Route::MethodNotAllowed,
+        _ => Route::NotFound,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(name: &str, zone: &str, status: &str, team: Option<&str>) -> Instance {
+        let mut instance = Instance {
+            name: name.to_string(),
+            zone: format!("projects/p/zones/{zone}"),
+            status: status.to_string(),
+            ..Default::default()
+        };
+        if let Some(team) = team {
+            instance.labels.insert("team".to_string(), team.to_string());
+        }
+        instance
+    }
+
+    #[test]
+    fn counts_instances_by_zone_status_and_label() {
+        let instances = [
+            instance("a", "us-central1-a", "RUNNING", Some("ml")),
+            instance("b", "us-central1-a", "RUNNING", Some("ml")),
+            instance("c", "us-central1-b", "TERMINATED", None),
+        ];
+        let text = render(&Sample {
+            project: "p",
+            instances: &instances,
+            costs: &[10.0, 2.5, 1.0],
+            label_keys: &["team".to_string()],
+            timestamp: 1700000000,
+        });
+        assert!(text.contains(
+            "gcectl_instances{project=\"p\",zone=\"us-central1-a\",status=\"RUNNING\"} 2\n"
+        ));
+        assert!(text.contains(
+            "gcectl_instances{project=\"p\",zone=\"us-central1-b\",status=\"TERMINATED\"} 1\n"
+        ));
+        assert!(text.contains(
+            "gcectl_instances_by_label{project=\"p\",label=\"team\",value=\"ml\",status=\"RUNNING\"} 2\n"
+        ));
+        assert!(text.contains(
+            "gcectl_instances_by_label{project=\"p\",label=\"team\",value=\"\",status=\"TERMINATED\"} 1\n"
+        ));
+        assert!(text.contains(
+            "gcectl_estimated_monthly_cost_usd{project=\"p\",zone=\"us-central1-a\"} 12.5\n"
+        ));
+        assert!(text.contains("gcectl_last_refresh_timestamp_seconds{project=\"p\"} 1700000000\n"));
+    }
+
+    #[test]
+    fn label_gauge_is_omitted_without_label_keys() {
+        let text = render(&Sample {
+            project: "p",
+            ..Default::default()
+        });
+        assert!(!text.contains("gcectl_instances_by_label"));
+        assert!(text.contains("# TYPE gcectl_instances gauge\n"));
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape("a\"b\\c\nd"), r#"a\"b\\c\nd"#);
+    }
+
+    #[test]
+    fn only_metrics_path_is_served() {
+        assert_eq!(route("GET /metrics HTTP/1.1\r\n"), Route::Metrics);
+        assert_eq!(route("GET /metrics?x=1 HTTP/1.1\r\n"), Route::Metrics);
+        assert_eq!(route("POST /metrics HTTP/1.1\r\n"), Route::MethodNotAllowed);
+        assert_eq!(route("GET / HTTP/1.1\r\n"), Route::NotFound);
+        assert_eq!(route(""), Route::NotFound);
+    }
+}
@@ -0,0 +1,281 @@
+//! Thin client for the Cloud Monitoring v3 `timeSeries.list` API.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{SecondsFormat, Utc};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::auth::Authenticator;
+use crate::compute::parse_response;
+use crate::transport::Transport;
+
+const MONITORING_ENDPOINT: &str = "https://monitoring.googleapis.com/v3";
+pub const CPU_UTILIZATION: &str = "compute.googleapis.com/instance/cpu/utilization";
+pub const NETWORK_RECEIVED: &str = "compute.googleapis.com/instance/network/received_bytes_count";
+pub const NETWORK_SENT: &str = "compute.googleapis.com/instance/network/sent_bytes_count";
+pub const DISK_READ_OPS: &str = "compute.googleapis.com/instance/disk/read_ops_count";
+pub const DISK_WRITE_OPS: &str = "compute.googleapis.com/instance/disk/write_ops_count";
+pub const DISK_READ_BYTES: &str = "compute.googleapis.com/instance/disk/read_bytes_count";
+pub const DISK_WRITE_BYTES: &str = "compute.googleapis.com/instance/disk/write_bytes_count";
+
+pub struct Monitoring {
+    http: Transport,
+    auth: Arc<Authenticator>,
+    endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TimeSeriesPage {
+    #[serde(default)]
+    time_series: Vec<TimeSeries>,
+    next_page_token: Option<String>,
+}
+
+/// One monitored resource's series of aligned points, newest first.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TimeSeries {
+    #[serde(default)]
+    pub resource: Labeled,
+    #[serde(default)]
+    pub points: Vec<Point>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Labeled {
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Point {
+    pub value: TypedValue,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedValue {
+    pub double_value: Option<f64>,
+    // int64 encoded as a string
+    pub int64_value: Option<String>,
+}
+
+impl TypedValue {
+    pub fn as_f64(&self) -> Option<f64> {
+        self.double_value
+            .or_else(|| self.int64_value.as_deref()?.parse().ok())
+    }
+}
+
+impl TimeSeries {
+    /// `instance_id` label of a `gce_instance` resource.
+    pub fn instance_id(&self) -> Option<&str> {
+        self.resource.labels.get("instance_id").map(String::as_str)
+    }
+
+    pub fn latest(&self) -> Option<f64> {
+        self.points.first()?.value.as_f64()
+    }
+
+    /// Average of every point in the series.
+    pub fn mean(&self) -> Option<f64> {
+        let values: Vec<f64> = self
+            .points
+            .iter()
+            .filter_map(|p| p.value.as_f64())
+            .collect();
+        match values.is_empty() {
+            true => None,
+            false => Some(values.iter().sum::<f64>() / values.len() as f64),
+        }
+    }
+}
+
+impl Monitoring {
+    pub fn new(http: Transport, auth: Arc<Authenticator>) -> Self {
+        Self {
+            http,
+            auth,
+            endpoint: MONITORING_ENDPOINT.to_string(),
+        }
+    }
+
+    /// Sends requests to `endpoint` instead of the public API, if given.
+    pub fn endpoint(mut self, endpoint: Option<&str>) -> Self {
+        if let Some(endpoint) = endpoint {
+            self.endpoint = endpoint.trim_end_matches('/').to_string();
+        }
+        self
+    }
+
+    /// Every series of `metric_type` over the last `window`, reduced into
+    /// `period`-long points by `aligner` (`ALIGN_MEAN`, `ALIGN_RATE`, ...).
+    pub async fn time_series(
+        &self,
+        project: &str,
+        metric_type: &str,
+        window: Duration,
+        period: Duration,
+        aligner: &str,
+    ) -> Result<Vec<TimeSeries>> {
+        let filter = format!("metric.type = \"{metric_type}\"");
+        self.series_matching(project, &filter, window, period, aligner)
+            .await
+    }
+
+    /// `time_series` of one disk attached to instance `instance_id` as
+    /// `device_name`, in per-second rates.
+    pub async fn disk_series(
+        &self,
+        project: &str,
+        metric_type: &str,
+        instance_id: &str,
+        device_name: &str,
+        window: Duration,
+        period: Duration,
+    ) -> Result<Vec<TimeSeries>> {
+        let filter = format!(
+            "metric.type = \"{metric_type}\" AND resource.labels.instance_id = \"{instance_id}\" \
+             AND metric.labels.device_name = \"{device_name}\""
+        );
+        self.series_matching(project, &filter, window, period, "ALIGN_RATE")
+            .await
+    }
+
+    async fn series_matching(
+        &self,
+        project: &str,
+        filter: &str,
+        window: Duration,
+        period: Duration,
+        aligner: &str,
+    ) -> Result<Vec<TimeSeries>> {
+        let end = Utc::now();
+        let start = end - chrono::Duration::from_std(window)?;
+        let start = start.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let end = end.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let period = format!("{}s", period.as_secs().max(60));
+        let url = format!("{}/projects/{project}/timeSeries", self.endpoint);
+
+        let mut series = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("filter", filter),
+                ("interval.startTime", start.as_str()),
+                ("interval.endTime", end.as_str()),
+                ("aggregation.alignmentPeriod", period.as_str()),
+                ("aggregation.perSeriesAligner", aligner),
+            ];
+            if let Some(token) = page_token.as_deref() {
+                query.push(("pageToken", token));
+            }
+            debug!("GET {url} {query:?}");
+            let request = self
+                .http
+                .get(&url)
+                .query(&query)
+                .bearer_auth(self.auth.token().await?);
+            let resp = self
+                .http
+                .send(request)
+                .await
+                .context("request to Monitoring API failed")?;
+            let page: TimeSeriesPage = parse_response(resp, "Monitoring API").await?;
+            series.extend(page.time_series);
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => break,
+            }
+        }
+        Ok(series)
+    }
+
+    /// Latest CPU utilization (0.0–1.0) per instance ID.
+    pub async fn cpu_utilization(&self, project: &str) -> Result<HashMap<String, f64>> {
+        let series = self
+            .time_series(
+                project,
+                CPU_UTILIZATION,
+                Duration::from_secs(5 * 60),
+                Duration::from_secs(60),
+                "ALIGN_MEAN",
+            )
+            .await?;
+        Ok(series
+            .iter()
+            .filter_map(|s| Some((s.instance_id()?.to_string(), s.latest()?)))
+            .collect())
+    }
+
+    /// Mean of `metric_type` per instance ID over the last `window`, with
+    /// `aligner` reducing hourly points first.
+    pub async fn mean_per_instance(
+        &self,
+        project: &str,
+        metric_type: &str,
+        window: Duration,
+        aligner: &str,
+    ) -> Result<HashMap<String, f64>> {
+        let period = window.min(Duration::from_secs(60 * 60));
+        let series = self
+            .time_series(project, metric_type, window, period, aligner)
+            .await?;
+        // one series per network interface, so add interfaces up
+        let mut means = HashMap::new();
+        for s in &series {
+            if let (Some(id), Some(mean)) = (s.instance_id(), s.mean()) {
+                *means.entry(id.to_string()).or_insert(0.0) += mean;
+            }
+        }
+        Ok(means)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_latest_point() {
+        let series: TimeSeries = serde_json::from_str(
+            r#"{
+                "resource": {"type": "gce_instance", "labels": {"instance_id": "123", "zone": "z"}},
+                "points": [
+                    {"interval": {"endTime": "2024-01-01T00:02:00Z"}, "value": {"doubleValue": 0.5}},
+                    {"interval": {"endTime": "2024-01-01T00:01:00Z"}, "value": {"doubleValue": 0.1}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(series.instance_id(), Some("123"));
+        assert_eq!(series.latest(), Some(0.5));
+    }
+
+    #[test]
+    fn averages_points() {
+        let series: TimeSeries = serde_json::from_str(
+            r#"{"points": [{"value": {"doubleValue": 0.5}}, {"value": {"int64Value": "1"}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(series.mean(), Some(0.75));
+        assert_eq!(
+            TimeSeries {
+                points: vec![],
+                ..series
+            }
+            .mean(),
+            None
+        );
+    }
+
+    #[test]
+    fn int64_values_are_numeric() {
+        let value: TypedValue = serde_json::from_str(r#"{"int64Value": "42"}"#).unwrap();
+        assert_eq!(value.as_f64(), Some(42.0));
+    }
+}
@@ -0,0 +1,140 @@
+//! Names for batches of new instances, such as `worker-{index:03}`.
+
+use std::str::FromStr;
+
+use anyhow::{Error, Result, bail};
+use chrono::NaiveDate;
+
+use crate::error::GcectlError;
+use crate::validate;
+
+/// A name with `{index}`, `{index:0N}`, `{zone}`, and `{date}` placeholders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameTemplate {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    // zero-padded to the width
+    Index(usize),
+    Zone,
+    // YYYYMMDD, as names cannot hold other separators
+    Date,
+}
+
+impl FromStr for NameTemplate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+        while let Some(open) = rest.find('{') {
+            if open > 0 {
+                parts.push(Part::Text(rest[..open].to_string()));
+            }
+            let Some(close) = rest[open..].find('}') else {
+                bail!("unclosed `{{` in name template `{s}`");
+            };
+            let placeholder = &rest[open + 1..open + close];
+            parts.push(match placeholder.split_once(':') {
+                None if placeholder == "index" => Part::Index(0),
+                None if placeholder == "zone" => Part::Zone,
+                None if placeholder == "date" => Part::Date,
+                Some(("index", width)) if width.starts_with('0') => match width.parse() {
+                    Ok(width) => Part::Index(width),
+                    Err(_) => bail!("expected a width like {{index:03}}, got `{{{placeholder}}}`"),
+                },
+                _ => bail!(
+                    "unknown placeholder `{{{placeholder}}}` in name template `{s}`; \
+                     use {{index}}, {{index:03}}, {{zone}}, or {{date}}"
+                ),
+            });
+            rest = &rest[open + close + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+        if !parts.iter().any(|p| matches!(p, Part::Index(_))) {
+            bail!("name template `{s}` needs an {{index}} placeholder to tell instances apart");
+        }
+        Ok(Self { parts })
+    }
+}
+
+impl NameTemplate {
+    /// The name of the instance numbered `index`, checked to be one the API
+    /// accepts.
+    pub fn render(&self, index: u32, zone: &str, date: NaiveDate) -> Result<String> {
+        let mut name = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => name.push_str(text),
+                Part::Index(width) => name.push_str(&format!("{index:0width$}")),
+                Part::Zone => name.push_str(zone),
+                Part::Date => name.push_str(&date.format("%Y%m%d").to_string()),
+            }
+        }
+        if let Some(problem) = validate::name_problem(&name) {
+            bail!(problem);
+        }
+        Ok(name)
+    }
+
+    /// Names of `count` instances numbered from `start`.
+    pub fn names(
+        &self,
+        start: u32,
+        count: u32,
+        zone: &str,
+        date: NaiveDate,
+    ) -> Result<Vec<String>> {
+        let Some(end) = start.checked_add(count) else {
+            return Err(GcectlError::Usage(format!(
+                "--start-index {start} with --count {count} runs past the largest index"
+            ))
+            .into());
+        };
+        (start..end)
+            .map(|index| self.render(index, zone, date))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_padded_indexes_zone_and_date() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        let template: NameTemplate = "worker-{index:03}".parse().unwrap();
+        assert_eq!(
+            template.names(9, 2, "us-central1-a", date).unwrap(),
+            ["worker-009", "worker-010"]
+        );
+        let template: NameTemplate = "ci-{date}-{zone}-{index}".parse().unwrap();
+        assert_eq!(
+            template.render(1, "us-central1-a", date).unwrap(),
+            "ci-20260309-us-central1-a-1"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_placeholders_and_invalid_names() {
+        assert!("worker".parse::<NameTemplate>().is_err());
+        assert!("worker-{idx}".parse::<NameTemplate>().is_err());
+        assert!("worker-{index".parse::<NameTemplate>().is_err());
+        let template: NameTemplate = "Worker-{index}".parse().unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        assert!(template.render(1, "us-central1-a", date).is_err());
+    }
+
+    #[test]
+    fn rejects_indexes_past_the_largest() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        let template: NameTemplate = "worker-{index}".parse().unwrap();
+        assert!(template.names(u32::MAX, 2, "us-central1-a", date).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! `--notify`: telling someone away from the terminal that a long operation
+//! finished or failed, through the hooks of the config file's `[notify]`
+//! table.
+//!
+//! A `command` hook runs with `sh -c` and these in its environment:
+//!
+//! - `GCECTL_NOTIFY_STATUS`: `succeeded` or `failed`
+//! - `GCECTL_NOTIFY_COMMAND`: the gcectl command line
+//! - `GCECTL_NOTIFY_MESSAGE`: the line every hook sends
+//! - `GCECTL_NOTIFY_ELAPSED`: seconds the operation took
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{Context, Error, Result, bail};
+use serde_json::json;
+use tokio::process::Command;
+
+use crate::config::NotifyHooks;
+
+const TITLE: &str = "gcectl";
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How an operation ended.
+#[derive(Debug, Clone)]
+pub struct Finished {
+    pub command: String,
+    // the error it failed with, if it did
+    pub error: Option<String>,
+    pub elapsed: Duration,
+}
+
+impl Finished {
+    /// The operation run by the gcectl arguments `args`, ending with
+    /// `error`, if any.
+    pub fn new(args: &str, error: Option<String>, elapsed: Duration) -> Self {
+        Self {
+            command: format!("{TITLE} {args}"),
+            error,
+            elapsed,
+        }
+    }
+
+    fn status(&self) -> &'static str {
+        match self.error {
+            Some(_) => "failed",
+            None => "succeeded",
+        }
+    }
+
+    /// One line saying how the operation ended.
+    pub fn message(&self) -> String {
+        let elapsed = elapsed(self.elapsed);
+        match &self.error {
+            Some(err) => format!("`{}` failed after {elapsed}: {err}", self.command),
+            None => format!("`{}` finished in {elapsed}", self.command),
+        }
+    }
+}
+
+/// Fires every hook set in `hooks`, returning what went wrong with each
+/// one that failed. A failed hook never stops the others.
+pub async fn send(hooks: &NotifyHooks, finished: &Finished) -> Vec<Error> {
+    let mut errors = Vec::new();
+    if let Some(command) = &hooks.command {
+        errors.extend(run_command(command, finished).await.err());
+    }
+    if let Some(url) = &hooks.slack_webhook {
+        errors.extend(post_webhook(url, finished).await.err());
+    }
+    if hooks.desktop() {
+        errors.extend(desktop(finished).await.err());
+    }
+    errors
+}
+
+async fn run_command(command: &str, finished: &Finished) -> Result<()> {
+    let status = Command::new("sh")
+        .args(["-c", command])
+        .env("GCECTL_NOTIFY_STATUS", finished.status())
+        .env("GCECTL_NOTIFY_COMMAND", &finished.command)
+        .env("GCECTL_NOTIFY_MESSAGE", finished.message())
+        .env(
+            "GCECTL_NOTIFY_ELAPSED",
+            finished.elapsed.as_secs().to_string(),
+        )
+        .stdin(Stdio::null())
+        .status()
+        .await
+        .context("failed to run the notify command")?;
+    if !status.success() {
+        bail!("the notify command exited with {status}");
+    }
+    Ok(())
+}
+
+async fn post_webhook(url: &str, finished: &Finished) -> Result<()> {
+    let http = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()?;
+    http.post(url)
+        .json(&json!({ "text": finished.message() }))
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .context("failed to post to the Slack webhook")?;
+    Ok(())
+}
+
+async fn desktop(finished: &Finished) -> Result<()> {
+    let message = finished.message();
+    let (program, args) = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {} with title {}",
+            apple_script_string(&message),
+            apple_script_string(TITLE)
+        );
+        ("osascript", vec!["-e".to_string(), script])
+    } else if cfg!(windows) {
+        bail!("desktop notifications are not supported on Windows; set a notify command instead");
+    } else {
+        let urgency = match finished.error {
+            Some(_) => "critical",
+            None => "normal",
+        };
+        let args = ["-u", urgency, TITLE, &message];
+        ("notify-send", args.map(String::from).to_vec())
+    };
+    let status = Command::new(program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .status()
+        .await
+        .with_context(|| format!("failed to run {program} for a desktop notification"))?;
+    if !status.success() {
+        bail!("{program} exited with {status}");
+    }
+    Ok(())
+}
+
+fn apple_script_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// `42s`, `9m41s`, or `1h05m`
+fn elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    match seconds {
+        0..60 => format!("{seconds}s"),
+        60..3600 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_say_how_long_the_operation_took() {
+        let mut finished = Finished {
+            command: "gcectl instances create gpu-1".into(),
+            error: None,
+            elapsed: Duration::from_secs(581),
+        };
+        assert_eq!(
+            finished.message(),
+            "`gcectl instances create gpu-1` finished in 9m41s"
+        );
+        finished.error = Some("quota exceeded".into());
+        finished.elapsed = Duration::from_secs(3900);
+        assert_eq!(
+            finished.message(),
+            "`gcectl instances create gpu-1` failed after 1h05m: quota exceeded"
+        );
+        assert_eq!(elapsed(Duration::from_secs(42)), "42s");
+    }
+}
@@ -0,0 +1,514 @@
+//! Thin client for the OS Config v1 API: the package inventory the guest
+//! agent reports and the results of patch jobs.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::debug;
+
+use crate::auth::Authenticator;
+use crate::compute::parse_response;
+use crate::output::{Details, Render};
+use crate::resources::short_name;
+use crate::transport::Transport;
+
+const OSCONFIG_ENDPOINT: &str = "https://osconfig.googleapis.com/v1";
+
+pub struct OsConfig {
+    http: Transport,
+    auth: Arc<Authenticator>,
+    endpoint: String,
+}
+
+/// What the guest agent last reported about an instance's OS and packages.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Inventory {
+    // `projects/{number}/locations/{zone}/instances/{id}/inventory`
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub os_info: OsInfo,
+    // keyed by an opaque item ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub items: BTreeMap<String, InventoryItem>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_time: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OsInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    // e.g. `Debian GNU/Linux 12 (bookworm)`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub architecture: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kernel_release: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub osconfig_agent_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryItem {
+    // `INSTALLED_PACKAGE` or `AVAILABLE_PACKAGE`
+    #[serde(default, rename = "type")]
+    pub kind: String,
+    // one key naming the package manager, e.g. `aptPackage`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed_package: Option<Map<String, Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub available_package: Option<Map<String, Value>>,
+}
+
+/// One installed package or pending update, flattened across the package
+/// managers the agent knows.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    pub architecture: String,
+    // e.g. `apt`, `yum`, `wua` for Windows Update
+    pub source: String,
+}
+
+/// A patch job run against a set of instances.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchJob {
+    // `projects/{project}/patchJobs/{id}`
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub create_time: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// How one instance fared in a patch job.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchJobInstance {
+    // `projects/{project}/zones/{zone}/instances/{name}`
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub instance_system_id: String,
+    // e.g. `SUCCEEDED`, `FAILED`, `SUCCEEDED_REBOOT_REQUIRED`
+    #[serde(default)]
+    pub state: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<String>,
+}
+
+/// An instance's pending updates and its result in the newest patch job
+/// that covered it, for `os patch-status`.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchStatus {
+    pub instance: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os: Option<String>,
+    // unknown without an inventory, e.g. when the agent is not installed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_updates: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_patch_job: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch_state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<String>,
+}
+
+impl PatchStatus {
+    /// Joins `instances`, as `(name, id)` pairs, with the inventories the
+    /// agents reported and the per-instance results of `jobs`, newest first.
+    pub fn collect(
+        instances: &[(String, String)],
+        inventories: &[Inventory],
+        jobs: &[(PatchJob, Vec<PatchJobInstance>)],
+    ) -> Vec<Self> {
+        instances
+            .iter()
+            .map(|(name, id)| {
+                let inventory = inventories.iter().find(|i| i.instance_id() == id);
+                let result = jobs.iter().find_map(|(job, results)| {
+                    let result = results
+                        .iter()
+                        .find(|r| r.instance_system_id == *id || short_name(&r.name) == name)?;
+                    Some((job, result))
+                });
+                Self {
+                    instance: name.clone(),
+                    os: inventory
+                        .map(Inventory::os_name)
+                        .filter(|os| !os.is_empty()),
+                    pending_updates: inventory.map(|i| i.updates().len()),
+                    last_patch_job: result.map(|(job, _)| job.label().to_string()),
+                    patch_state: result.map(|(_, r)| r.state.clone()),
+                    failure_reason: result.and_then(|(_, r)| r.failure_reason.clone()),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Render for PatchStatus {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Instance",
+            "OS",
+            "Updates",
+            "Last-Patch-Job",
+            "State",
+            "Failure",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let text = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+        vec![
+            self.instance.clone(),
+            text(&self.os),
+            self.pending_updates
+                .map_or_else(|| "-".to_string(), |n| n.to_string()),
+            text(&self.last_patch_job),
+            text(&self.patch_state),
+            self.failure_reason.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+impl PatchJob {
+    pub fn id(&self) -> &str {
+        short_name(&self.name)
+    }
+
+    /// The display name, else the job ID.
+    pub fn label(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(self.id())
+    }
+}
+
+impl Inventory {
+    /// Numeric ID of the instance the inventory belongs to.
+    pub fn instance_id(&self) -> &str {
+        self.name
+            .trim_end_matches("/inventory")
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+    }
+
+    /// Installed packages, sorted by name.
+    pub fn installed(&self) -> Vec<Package> {
+        self.packages(|item| item.installed_package.as_ref())
+    }
+
+    /// Updates the package managers offer, sorted by name.
+    pub fn updates(&self) -> Vec<Package> {
+        self.packages(|item| item.available_package.as_ref())
+    }
+
+    fn packages(
+        &self,
+        pick: impl Fn(&InventoryItem) -> Option<&Map<String, Value>>,
+    ) -> Vec<Package> {
+        let mut packages: Vec<Package> = self
+            .items
+            .values()
+            .filter_map(&pick)
+            .filter_map(Package::from_item)
+            .collect();
+        packages.sort();
+        packages
+    }
+
+    /// `Debian GNU/Linux 12 (bookworm)`, or the short name and version.
+    pub fn os_name(&self) -> String {
+        let os = &self.os_info;
+        match (&os.long_name, &os.short_name) {
+            (Some(long), _) => long.clone(),
+            (None, Some(short)) => format!("{short} {}", os.version.as_deref().unwrap_or_default())
+                .trim_end()
+                .to_string(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+impl Package {
+    // field names differ per manager: yum/apt/zypper/goo/cos packages have
+    // `packageName`, Windows updates a `title`, hotfixes a `hotFixId`
+    fn from_item(item: &Map<String, Value>) -> Option<Self> {
+        let (manager, details) = item.iter().next()?;
+        let text = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| details.get(*key).and_then(Value::as_str))
+                .unwrap_or_default()
+                .to_string()
+        };
+        let source = match manager.as_str() {
+            "googetPackage" => "googet".to_string(),
+            "windowsApplication" => "windows".to_string(),
+            other => other
+                .trim_end_matches("Package")
+                .trim_end_matches("Patch")
+                .to_string(),
+        };
+        Some(Self {
+            name: text(&[
+                "packageName",
+                "patchName",
+                "title",
+                "hotFixId",
+                "displayName",
+            ]),
+            version: text(&["version", "displayVersion", "lastDeploymentChangeTime"]),
+            architecture: text(&["architecture"]),
+            source,
+        })
+    }
+}
+
+impl Render for Package {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Version", "Arch", "Source"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.version.clone(),
+            self.architecture.clone(),
+            self.source.clone(),
+        ]
+    }
+}
+
+impl Render for Inventory {
+    fn headers() -> Vec<&'static str> {
+        vec!["Hostname", "OS", "Installed", "Updates"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.os_info.hostname.clone().unwrap_or_default(),
+            self.os_name(),
+            self.installed().len().to_string(),
+            self.updates().len().to_string(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let os = &self.os_info;
+        let mut details = Details::default();
+        details
+            .field_opt("Hostname", os.hostname.as_deref())
+            .field("OS", self.os_name())
+            .field_opt("Kernel", os.kernel_release.as_deref())
+            .field_opt("Architecture", os.architecture.as_deref())
+            .field_opt("Agent-Version", os.osconfig_agent_version.as_deref())
+            .field_opt("Reported", self.update_time.as_deref())
+            .field("Installed-Packages", self.installed().len().to_string());
+        let updates = self.updates();
+        details.group("Pending-Updates", |group| {
+            for package in &updates {
+                group.field(&package.name, &package.version);
+            }
+        });
+        details
+    }
+}
+
+impl OsConfig {
+    pub fn new(http: Transport, auth: Arc<Authenticator>) -> Self {
+        Self {
+            http,
+            auth,
+            endpoint: OSCONFIG_ENDPOINT.to_string(),
+        }
+    }
+
+    /// Sends requests to `endpoint` instead of the public API, if given.
+    pub fn endpoint(mut self, endpoint: Option<&str>) -> Self {
+        if let Some(endpoint) = endpoint {
+            self.endpoint = endpoint.trim_end_matches('/').to_string();
+        }
+        self
+    }
+
+    /// `GET projects/{project}/locations/{zone}/instances/{instance}/inventory`,
+    /// with every package
+    pub async fn get_inventory(
+        &self,
+        project: &str,
+        zone: &str,
+        instance: &str,
+    ) -> Result<Inventory> {
+        let url = format!(
+            "{}/projects/{project}/locations/{zone}/instances/{instance}/inventory",
+            self.endpoint
+        );
+        self.get(&url, &[("view", "FULL")]).await
+    }
+
+    /// `GET projects/{project}/locations/{zone}/instances/-/inventories`,
+    /// every page, with every package
+    pub async fn list_inventories(&self, project: &str, zone: &str) -> Result<Vec<Inventory>> {
+        let url = format!(
+            "{}/projects/{project}/locations/{zone}/instances/-/inventories",
+            self.endpoint
+        );
+        self.list_all(&url, "inventories", &[("view", "FULL")], None)
+            .await
+    }
+
+    /// `GET projects/{project}/patchJobs`: the newest `limit` jobs, newest
+    /// first
+    pub async fn list_patch_jobs(&self, project: &str, limit: usize) -> Result<Vec<PatchJob>> {
+        let url = format!("{}/projects/{project}/patchJobs", self.endpoint);
+        let mut jobs: Vec<PatchJob> = self.list_all(&url, "patchJobs", &[], Some(limit)).await?;
+        jobs.sort_by(|a, b| b.create_time.cmp(&a.create_time));
+        jobs.truncate(limit);
+        Ok(jobs)
+    }
+
+    /// `GET projects/{project}/patchJobs/{id}/instanceDetails`, every page
+    pub async fn list_patch_job_instances(&self, job: &PatchJob) -> Result<Vec<PatchJobInstance>> {
+        let url = format!("{}/{}/instanceDetails", self.endpoint, job.name);
+        self.list_all(&url, "patchJobInstanceDetails", &[], None)
+            .await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, url: &str, query: &[(&str, &str)]) -> Result<T> {
+        debug!("GET {url} {query:?}");
+        let request = self
+            .http
+            .get(url)
+            .query(query)
+            .bearer_auth(self.auth.token().await?);
+        let resp = self
+            .http
+            .send(request)
+            .await
+            .context("request to OS Config API failed")?;
+        parse_response(resp, "OS Config API").await
+    }
+
+    /// Fetches pages of a list endpoint until there are no more or `limit`
+    /// items arrived. `key` names the array in each page.
+    async fn list_all<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        key: &str,
+        query: &[(&str, &str)],
+        limit: Option<usize>,
+    ) -> Result<Vec<T>> {
+        let page_size = limit.map(|n| n.to_string());
+        let mut items = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut page_query = query.to_vec();
+            if let Some(size) = page_size.as_deref() {
+                page_query.push(("pageSize", size));
+            }
+            if let Some(token) = page_token.as_deref() {
+                page_query.push(("pageToken", token));
+            }
+            let mut page: Map<String, Value> = self.get(url, &page_query).await?;
+            if let Some(list) = page.remove(key) {
+                let list: Vec<T> = serde_json::from_value(list)
+                    .with_context(|| format!("failed to decode {key}"))?;
+                items.extend(list);
+            }
+            if limit.is_some_and(|limit| items.len() >= limit) {
+                break;
+            }
+            match page.remove("nextPageToken") {
+                Some(Value::String(token)) if !token.is_empty() => page_token = Some(token),
+                _ => break,
+            }
+        }
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn flattens_packages_across_managers() {
+        let inventory: Inventory = serde_json::from_value(json!({
+            "osInfo": {"shortName": "debian", "version": "12"},
+            "items": {
+                "a": {"type": "INSTALLED_PACKAGE", "installedPackage": {
+                    "aptPackage": {"packageName": "openssl", "version": "3.0.11", "architecture": "amd64"}
+                }},
+                "b": {"type": "AVAILABLE_PACKAGE", "availablePackage": {
+                    "aptPackage": {"packageName": "openssl", "version": "3.0.13", "architecture": "amd64"}
+                }},
+                "c": {"type": "AVAILABLE_PACKAGE", "availablePackage": {
+                    "wuaPackage": {"title": "2024-05 Cumulative Update"}
+                }},
+            }
+        }))
+        .unwrap();
+        assert_eq!(inventory.os_name(), "debian 12");
+        assert_eq!(
+            inventory.installed()[0].row(),
+            ["openssl", "3.0.11", "amd64", "apt"]
+        );
+        let updates: Vec<_> = inventory.updates().into_iter().map(|p| p.name).collect();
+        assert_eq!(updates, ["2024-05 Cumulative Update", "openssl"]);
+        assert_eq!(inventory.row()[2..], ["1", "2"]);
+    }
+
+    #[test]
+    fn patch_status_takes_the_newest_job_per_instance() {
+        let inventory = Inventory {
+            name: "projects/1/locations/z/instances/111/inventory".into(),
+            ..Default::default()
+        };
+        let job = |id: &str, state: &str, instance: &str| {
+            let job = PatchJob {
+                name: format!("projects/p/patchJobs/{id}"),
+                ..Default::default()
+            };
+            let result = PatchJobInstance {
+                name: format!("projects/p/zones/z/instances/{instance}"),
+                state: state.into(),
+                ..Default::default()
+            };
+            (job, vec![result])
+        };
+        let jobs = [
+            job("new", "FAILED", "web-1"),
+            job("old", "SUCCEEDED", "web-1"),
+        ];
+        let instances = [
+            ("web-1".to_string(), "111".to_string()),
+            ("db".to_string(), "222".to_string()),
+        ];
+        let status = PatchStatus::collect(&instances, &[inventory], &jobs);
+        assert_eq!(status[0].row(), ["web-1", "-", "0", "new", "FAILED", ""]);
+        assert_eq!(status[1].row(), ["db", "-", "-", "-", "-", ""]);
+    }
+}
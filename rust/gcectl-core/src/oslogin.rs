@@ -0,0 +1,203 @@
+//! Thin client for the OS Login v1 API, and detection of instances that
+//! authenticate ssh through it instead of metadata `ssh-keys`.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::debug;
+
+use crate::auth::Authenticator;
+use crate::compute::parse_response;
+use crate::resources::instance::Metadata;
+use crate::transport::Transport;
+
+const OSLOGIN_ENDPOINT: &str = "https://oslogin.googleapis.com/v1";
+pub const ENABLE_OSLOGIN: &str = "enable-oslogin";
+
+/// Whether OS Login governs ssh to an instance: its own `enable-oslogin`
+/// entry wins over the project's.
+pub fn enabled(instance: Option<&Metadata>, project: Option<&Metadata>) -> bool {
+    instance
+        .and_then(|m| m.get(ENABLE_OSLOGIN))
+        .or_else(|| project.and_then(|m| m.get(ENABLE_OSLOGIN)))
+        .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+}
+
+pub struct OsLogin {
+    http: Transport,
+    auth: Arc<Authenticator>,
+    endpoint: String,
+}
+
+/// The POSIX accounts and ssh keys OS Login holds for a Google account.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginProfile {
+    #[serde(default)]
+    pub posix_accounts: Vec<PosixAccount>,
+    // keyed by fingerprint
+    #[serde(default)]
+    pub ssh_public_keys: BTreeMap<String, SshPublicKey>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PosixAccount {
+    #[serde(default)]
+    pub primary: bool,
+    pub username: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SshPublicKey {
+    #[serde(default)]
+    pub key: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportResponse {
+    login_profile: LoginProfile,
+}
+
+impl LoginProfile {
+    /// Login name on instances: the primary POSIX account, else the first.
+    pub fn username(&self) -> Option<&str> {
+        self.posix_accounts
+            .iter()
+            .find(|a| a.primary)
+            .or_else(|| self.posix_accounts.first())
+            .map(|a| a.username.as_str())
+    }
+
+    /// Whether `public_key` is already registered, ignoring its comment.
+    pub fn has_key(&self, public_key: &str) -> bool {
+        let blob = key_blob(public_key);
+        self.ssh_public_keys
+            .values()
+            .any(|k| blob.is_some() && key_blob(&k.key) == blob)
+    }
+}
+
+// `TYPE BASE64` without the trailing comment
+fn key_blob(key: &str) -> Option<(&str, &str)> {
+    let mut parts = key.split_whitespace();
+    Some((parts.next()?, parts.next()?))
+}
+
+impl OsLogin {
+    pub fn new(http: Transport, auth: Arc<Authenticator>) -> Self {
+        Self {
+            http,
+            auth,
+            endpoint: OSLOGIN_ENDPOINT.to_string(),
+        }
+    }
+
+    /// Sends requests to `endpoint` instead of the public API, if given.
+    pub fn endpoint(mut self, endpoint: Option<&str>) -> Self {
+        if let Some(endpoint) = endpoint {
+            self.endpoint = endpoint.trim_end_matches('/').to_string();
+        }
+        self
+    }
+
+    /// `GET users/{account}/loginProfile`
+    pub async fn login_profile(&self, account: &str, project: &str) -> Result<LoginProfile> {
+        let url = format!("{}/users/{account}/loginProfile", self.endpoint);
+        debug!("GET {url}");
+        let request = self
+            .http
+            .get(&url)
+            .query(&[("projectId", project)])
+            .bearer_auth(self.auth.token().await?);
+        let resp = self
+            .http
+            .send(request)
+            .await
+            .context("request to OS Login API failed")?;
+        parse_response(resp, "OS Login API").await
+    }
+
+    /// `POST users/{account}:importSshPublicKey`, returning the updated profile
+    pub async fn import_ssh_public_key(
+        &self,
+        account: &str,
+        project: &str,
+        public_key: &str,
+    ) -> Result<LoginProfile> {
+        let url = format!("{}/users/{account}:importSshPublicKey", self.endpoint);
+        debug!("POST {url}");
+        let request = self
+            .http
+            .post(&url)
+            .query(&[("projectId", project)])
+            .json(&json!({"key": public_key}))
+            .bearer_auth(self.auth.token().await?);
+        let resp = self
+            .http
+            .send(request)
+            .await
+            .context("request to OS Login API failed")?;
+        let imported: ImportResponse = parse_response(resp, "OS Login API").await?;
+        Ok(imported.login_profile)
+    }
+
+    /// The login profile for `account` with `public_key` registered,
+    /// importing the key first if OS Login does not have it yet.
+    pub async fn ensure_key(
+        &self,
+        account: &str,
+        project: &str,
+        public_key: &str,
+    ) -> Result<LoginProfile> {
+        let profile = self.login_profile(account, project).await?;
+        if profile.has_key(public_key) {
+            return Ok(profile);
+        }
+        debug!("importing ssh key into the OS Login profile of {account}");
+        self.import_ssh_public_key(account, project, public_key)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(value: &str) -> Metadata {
+        let mut metadata = Metadata::default();
+        metadata.set(ENABLE_OSLOGIN, value);
+        metadata
+    }
+
+    #[test]
+    fn instance_setting_overrides_project() {
+        assert!(enabled(None, Some(&metadata("TRUE"))));
+        assert!(!enabled(Some(&metadata("FALSE")), Some(&metadata("TRUE"))));
+        assert!(enabled(Some(&metadata("true")), None));
+        assert!(!enabled(Some(&Metadata::default()), None));
+    }
+
+    #[test]
+    fn picks_primary_username_and_matches_keys_without_comments() {
+        let profile: LoginProfile = serde_json::from_str(
+            r#"{
+                "name": "users/alice@example.com",
+                "posixAccounts": [
+                    {"username": "alice_corp"},
+                    {"primary": true, "username": "alice_example_com"}
+                ],
+                "sshPublicKeys": {"ab12": {"key": "ssh-ed25519 AAAAC3Nza alice@laptop"}}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(profile.username(), Some("alice_example_com"));
+        assert!(profile.has_key("ssh-ed25519 AAAAC3Nza other-comment\n"));
+        assert!(!profile.has_key("ssh-ed25519 AAAAC3Nzb"));
+        assert_eq!(LoginProfile::default().username(), None);
+    }
+}
@@ -0,0 +1,203 @@
+//! `--columns` and `--sort-by`: which columns of a table or CSV listing are
+//! printed, and in what order its rows come.
+//!
+//! Columns are named by their headers, case-insensitively and with `_` for
+//! `-`, or by a prefix only one header starts with, so `machine` picks
+//! `Machine-Type`. Columns a profile lists by default that a listing lacks
+//! are skipped, since one profile setting covers every kind of resource.
+
+use std::cmp::Ordering;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use anyhow::{Error, Result, bail};
+
+// set once per run, after the profile is known
+static LAYOUT: OnceLock<Layout> = OnceLock::new();
+
+/// Makes listings print `layout`'s columns in its order.
+pub fn set_layout(layout: Layout) {
+    // set once per run, so the cell is still empty
+    let _ = LAYOUT.set(layout);
+}
+
+pub(super) fn layout() -> Option<&'static Layout> {
+    LAYOUT.get()
+}
+
+/// Chosen columns and sort key of listings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Layout {
+    columns: Vec<String>,
+    // from `--columns` rather than the profile, so unknown names are errors
+    strict: bool,
+    sort: Option<SortKey>,
+}
+
+/// A `--sort-by` column; a leading `~` sorts it descending.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortKey {
+    column: String,
+    descending: bool,
+}
+
+impl FromStr for SortKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (column, descending) = match s.strip_prefix('~') {
+            Some(column) => (column, true),
+            None => (s, false),
+        };
+        if column.trim().is_empty() {
+            bail!("expected a column to sort by, e.g. zone or ~name");
+        }
+        Ok(Self {
+            column: column.trim().to_string(),
+            descending,
+        })
+    }
+}
+
+impl Layout {
+    /// `--columns` if given, else the profile's `columns`, both
+    /// comma-separated.
+    pub fn new(flag: Option<&str>, profile: Option<&str>, sort: Option<SortKey>) -> Self {
+        let split = |list: &str| -> Vec<String> {
+            list.split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(String::from)
+                .collect()
+        };
+        Self {
+            columns: flag.or(profile).map(split).unwrap_or_default(),
+            strict: flag.is_some(),
+            sort,
+        }
+    }
+
+    /// Positions in `headers` of the columns to print.
+    pub(super) fn columns(&self, headers: &[&str]) -> Result<Vec<usize>> {
+        let all = (0..headers.len()).collect();
+        if self.columns.is_empty() {
+            return Ok(all);
+        }
+        let mut chosen = Vec::new();
+        for name in &self.columns {
+            match find(headers, name)? {
+                Some(i) => chosen.push(i),
+                None if self.strict => bail!(
+                    "unknown column `{name}`; this listing has {}",
+                    headers.join(", ")
+                ),
+                None => {}
+            }
+        }
+        Ok(if chosen.is_empty() { all } else { chosen })
+    }
+
+    /// The order to print `rows` in, as positions in `rows`.
+    pub(super) fn order(&self, headers: &[&str], rows: &[Vec<String>]) -> Result<Vec<usize>> {
+        let mut order: Vec<usize> = (0..rows.len()).collect();
+        let Some(sort) = &self.sort else {
+            return Ok(order);
+        };
+        let Some(column) = find(headers, &sort.column)? else {
+            bail!(
+                "cannot sort by `{}`; this listing has {}",
+                sort.column,
+                headers.join(", ")
+            );
+        };
+        let cell = |i: usize| rows[i].get(column).map_or("", String::as_str);
+        // stable, so rows that tie keep the API's order
+        order.sort_by(|&a, &b| {
+            let ordering = compare(cell(a), cell(b));
+            match sort.descending {
+                true => ordering.reverse(),
+                false => ordering,
+            }
+        });
+        Ok(order)
+    }
+
+    pub(super) fn sorts(&self) -> bool {
+        self.sort.is_some()
+    }
+}
+
+// the header `name` picks: an exact match, else the only one it prefixes
+fn find(headers: &[&str], name: &str) -> Result<Option<usize>> {
+    let normalize = |s: &str| s.to_ascii_lowercase().replace('_', "-");
+    let name = normalize(name);
+    if let Some(i) = headers.iter().position(|h| normalize(h) == name) {
+        return Ok(Some(i));
+    }
+    let prefixed: Vec<usize> = (0..headers.len())
+        .filter(|&i| normalize(headers[i]).starts_with(&name))
+        .collect();
+    match prefixed[..] {
+        [] => Ok(None),
+        [i] => Ok(Some(i)),
+        _ => {
+            let names: Vec<&str> = prefixed.iter().map(|&i| headers[i]).collect();
+            bail!("column `{name}` could be any of {}", names.join(", "))
+        }
+    }
+}
+
+// numbers, sizes, and prices by value; everything else as text
+fn compare(a: &str, b: &str) -> Ordering {
+    let number = |s: &str| {
+        s.trim_start_matches('$')
+            .trim_end_matches('%')
+            .trim_end_matches(" GB")
+            .parse::<f64>()
+            .ok()
+    };
+    match (number(a), number(b)) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        _ => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADERS: &[&str] = &["Name", "Zone", "Machine-Type", "Internal-IP", "External-IP"];
+
+    #[test]
+    fn columns_match_headers_loosely() {
+        let layout = Layout::new(Some("name, machine_type,ext"), None, None);
+        assert_eq!(layout.columns(HEADERS).unwrap(), [0, 2, 4]);
+        let err = Layout::new(Some("ip"), None, None).columns(HEADERS);
+        assert!(err.is_err());
+        assert!(
+            Layout::new(Some("name,owner"), None, None)
+                .columns(HEADERS)
+                .is_err()
+        );
+        // a profile's columns skip what a listing lacks
+        let profile = Layout::new(None, Some("name,owner"), None);
+        assert_eq!(profile.columns(HEADERS).unwrap(), [0]);
+        let profile = Layout::new(None, Some("owner"), None);
+        assert_eq!(profile.columns(HEADERS).unwrap(), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn rows_sort_by_value_and_keep_ties_in_order() {
+        let rows: Vec<Vec<String>> = [("a", "$10.00"), ("b", "$9.50"), ("c", "$10.00")]
+            .iter()
+            .map(|(name, cost)| vec![name.to_string(), cost.to_string()])
+            .collect();
+        let headers = ["Name", "Monthly"];
+        let ascending = Layout::new(None, None, Some("monthly".parse().unwrap()));
+        assert_eq!(ascending.order(&headers, &rows).unwrap(), [1, 0, 2]);
+        let descending = Layout::new(None, None, Some("~monthly".parse().unwrap()));
+        assert_eq!(descending.order(&headers, &rows).unwrap(), [0, 2, 1]);
+        let unknown = Layout::new(None, None, Some("zone".parse().unwrap()));
+        assert!(unknown.order(&headers, &rows).is_err());
+    }
+}
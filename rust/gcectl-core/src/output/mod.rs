@@ -0,0 +1,821 @@
+//! Rendering of resources as tables, JSON, YAML, or CSV, or as chosen
+//! fields through a `--format` projection.
+
+mod layout;
+mod projection;
+
+pub use layout::{Layout, SortKey, set_layout};
+pub use projection::Projection;
+
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Result, bail};
+use comfy_table::{ColumnConstraint, Table, Width, presets::UTF8_FULL};
+use serde::Serialize;
+
+use crate::events::{self, Event};
+
+// set once from `--quiet` before any command runs
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Turns on `--quiet`: no spinners or progress lines, and table output
+/// reduced to what a script can consume.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+// set once from `--format`; takes over from the output format when present
+static PROJECTION: OnceLock<Projection> = OnceLock::new();
+
+/// Makes list and describe output print `projection` instead.
+pub fn set_projection(projection: Projection) {
+    // main calls this once, so the cell is still empty
+    let _ = PROJECTION.set(projection);
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Yaml,
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            "yaml" => Ok(Self::Yaml),
+            "csv" => Ok(Self::Csv),
+            _ => bail!("expected table, json, yaml or csv, got `{s}`"),
+        }
+    }
+}
+
+/// Implemented by every resource type that list/describe commands print.
+///
+/// JSON and YAML come straight from `Serialize`; table and CSV output use
+/// the column layout declared here.
+pub trait Render: Serialize {
+    /// Column headers for table and CSV output.
+    fn headers() -> Vec<&'static str>;
+
+    /// Plain cell values matching [`Render::headers`], used for CSV.
+    fn row(&self) -> Vec<String>;
+
+    /// Cell values for the human-readable table; defaults to [`Render::row`].
+    fn table_row(&self) -> Vec<String> {
+        self.row()
+    }
+
+    /// Hierarchical view used by describe commands in table mode; defaults
+    /// to one field per column.
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        for (header, value) in Self::headers().into_iter().zip(self.table_row()) {
+            details.field(header, value);
+        }
+        details
+    }
+}
+
+/// A resource listed across projects, with a Project column after its
+/// name; JSON and YAML gain a `project` field.
+#[derive(Debug, Serialize)]
+pub struct InProject<'a, T> {
+    pub project: &'a str,
+    #[serde(flatten)]
+    pub item: &'a T,
+}
+
+impl<T: Render> Render for InProject<'_, T> {
+    fn headers() -> Vec<&'static str> {
+        let mut headers = T::headers();
+        headers.insert(1.min(headers.len()), "Project");
+        headers
+    }
+
+    fn row(&self) -> Vec<String> {
+        self.with_project(self.item.row())
+    }
+
+    fn table_row(&self) -> Vec<String> {
+        self.with_project(self.item.table_row())
+    }
+}
+
+// lets listings print items they have put in another order
+impl<T: Render> Render for &T {
+    fn headers() -> Vec<&'static str> {
+        T::headers()
+    }
+
+    fn row(&self) -> Vec<String> {
+        (*self).row()
+    }
+
+    fn table_row(&self) -> Vec<String> {
+        (*self).table_row()
+    }
+
+    fn details(&self) -> Details {
+        (*self).details()
+    }
+}
+
+impl<T> InProject<'_, T> {
+    fn with_project(&self, mut row: Vec<String>) -> Vec<String> {
+        row.insert(1.min(row.len()), self.project.to_string());
+        row
+    }
+}
+
+/// Nested key/value listing for a single resource.
+#[derive(Debug, Default)]
+pub struct Details {
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+enum Entry {
+    Field(String, String),
+    Group(String, Details),
+}
+
+impl Details {
+    pub fn field(&mut self, key: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.entries.push(Entry::Field(key.into(), value.into()));
+        self
+    }
+
+    /// Adds a field only when `value` is present.
+    pub fn field_opt(
+        &mut self,
+        key: impl Into<String>,
+        value: Option<impl Into<String>>,
+    ) -> &mut Self {
+        if let Some(value) = value {
+            self.field(key, value);
+        }
+        self
+    }
+
+    /// Adds a titled sub-section; sections left empty by `build` are dropped.
+    pub fn group(
+        &mut self,
+        title: impl Into<String>,
+        build: impl FnOnce(&mut Details),
+    ) -> &mut Self {
+        let mut child = Details::default();
+        build(&mut child);
+        if !child.entries.is_empty() {
+            self.entries.push(Entry::Group(title.into(), child));
+        }
+        self
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.render_into(&mut out, 0);
+        out
+    }
+
+    fn render_into(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let width = self
+            .entries
+            .iter()
+            .filter_map(|e| match e {
+                Entry::Field(key, _) => Some(key.chars().count()),
+                Entry::Group(..) => None,
+            })
+            .max()
+            .unwrap_or(0);
+        for entry in &self.entries {
+            match entry {
+                Entry::Field(key, value) => {
+                    out.push_str(&format!(
+                        "{indent}• {key:<width$} : {}\n",
+                        first_line(value)
+                    ));
+                }
+                Entry::Group(title, child) => {
+                    out.push_str(&format!("{indent}{title}:\n"));
+                    child.render_into(out, depth + 1);
+                }
+            }
+        }
+    }
+}
+
+// multi-line values such as startup scripts are abbreviated in listings
+fn first_line(value: &str) -> String {
+    match value.split_once('\n') {
+        Some((first, _)) => format!("{first} …"),
+        None => value.to_string(),
+    }
+}
+
+/// Prints `items` to stdout in `format`, in `--sort-by` order; a quiet
+/// table is just the first column, usually the name, one per line.
+pub fn print_list<T: Render>(format: OutputFormat, items: &[T]) -> Result<()> {
+    if events::enabled() {
+        let data = serde_json::to_value(items)?;
+        events::emit(&Event::Output { data });
+        return Ok(());
+    }
+    let rows: Vec<Vec<String>> = items.iter().map(Render::row).collect();
+    let items: Vec<&T> = order(&T::headers(), &rows)?
+        .into_iter()
+        .map(|i| &items[i])
+        .collect();
+    let items = &items[..];
+    let mut out = io::stdout().lock();
+    if let Some(projection) = PROJECTION.get() {
+        let items = items
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        write!(out, "{}", projection.render(&items, false)?)?;
+        return Ok(());
+    }
+    match format {
+        OutputFormat::Table if quiet() => write!(out, "{}", first_column(items))?,
+        OutputFormat::Table => writeln!(out, "{}", table(items)?)?,
+        OutputFormat::Json => writeln!(out, "{}", serde_json::to_string_pretty(items)?)?,
+        OutputFormat::Yaml => write!(out, "{}", serde_yaml::to_string(items)?)?,
+        OutputFormat::Csv => write_csv(&mut out, items)?,
+    }
+    Ok(())
+}
+
+/// Prints a single resource to stdout in `format`; a quiet table is JSON.
+pub fn print_one<T: Render>(format: OutputFormat, item: &T) -> Result<()> {
+    if events::enabled() {
+        let data = serde_json::to_value(item)?;
+        events::emit(&Event::Output { data });
+        return Ok(());
+    }
+    if let Some(projection) = PROJECTION.get() {
+        let item = serde_json::to_value(item)?;
+        print!("{}", projection.render(&[item], true)?);
+        return Ok(());
+    }
+    match format {
+        OutputFormat::Table if quiet() => println!("{}", serde_json::to_string_pretty(item)?),
+        OutputFormat::Table => print!("{}", item.details().render()),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(item)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(item)?),
+        OutputFormat::Csv => write_csv(io::stdout().lock(), std::slice::from_ref(item))?,
+    }
+    Ok(())
+}
+
+/// Prints a JSON value, such as a request body a dry run previews, or
+/// emits it as an `output` event.
+pub fn print_json(value: &serde_json::Value) -> Result<()> {
+    if events::enabled() {
+        events::emit(&Event::Output {
+            data: value.clone(),
+        });
+        return Ok(());
+    }
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Prints `text`, a command's result, exactly as given, or emits it as the
+/// string of an `output` event.
+pub fn print_text(text: &str) {
+    if events::enabled() {
+        return events::emit(&Event::Output {
+            data: text.trim_end_matches('\n').into(),
+        });
+    }
+    print!("{text}");
+}
+
+/// Prints a line that is not the command's result, such as a plan or what a
+/// server is listening on, or emits it as a `message` event.
+pub fn print_note(line: &str) {
+    if events::enabled() {
+        return events::emit(&Event::Message { message: line });
+    }
+    println!("{line}");
+}
+
+/// Prints a listing page by page as the pages arrive, so that projects
+/// with tens of thousands of resources never sit in memory at once.
+///
+/// The output is what [`print_list`] prints for all the pages together,
+/// except that table columns keep the widths of the first page and wrap
+/// longer cells. A `--format` projection sizes its columns from every row,
+/// and `--sort-by` needs every row, so both still wait for the last page.
+///
+/// Every page and [`ListPrinter::finish`] name the same item type. A listing
+/// that fails part way is ended with [`ListPrinter::close`] instead.
+pub struct ListPrinter<W = io::Stdout> {
+    format: OutputFormat,
+    out: W,
+    // items printed so far
+    printed: usize,
+    // column widths of the first table page, padding included
+    widths: Vec<u16>,
+    // bottom border of the table so far, printed by `finish`
+    bottom: Option<String>,
+    // items held for the last page, when the listing waits for it
+    held: Vec<Held>,
+}
+
+// an item held back by a listing, ready to print in any format
+struct Held {
+    value: serde_json::Value,
+    row: Vec<String>,
+    table_row: Vec<String>,
+}
+
+impl ListPrinter {
+    pub fn stdout(format: OutputFormat) -> Self {
+        Self::new(format, io::stdout())
+    }
+}
+
+impl<W: Write> ListPrinter<W> {
+    pub fn new(format: OutputFormat, out: W) -> Self {
+        Self {
+            format,
+            out,
+            printed: 0,
+            widths: Vec::new(),
+            bottom: None,
+            held: Vec::new(),
+        }
+    }
+
+    /// Prints the next page of the listing.
+    pub fn page<T: Render>(&mut self, items: &[T]) -> Result<()> {
+        if events::enabled() {
+            let data = serde_json::to_value(items)?;
+            events::emit(&Event::Output { data });
+            return Ok(());
+        }
+        if waits() {
+            for item in items {
+                self.held.push(Held {
+                    value: serde_json::to_value(item)?,
+                    row: item.row(),
+                    table_row: item.table_row(),
+                });
+            }
+            return Ok(());
+        }
+        match self.format {
+            OutputFormat::Table if quiet() => write!(self.out, "{}", first_column(items))?,
+            OutputFormat::Table => self.table_page(items)?,
+            OutputFormat::Json => {
+                for (i, item) in items.iter().enumerate() {
+                    let open = if self.printed + i == 0 { "[" } else { "," };
+                    writeln!(self.out, "{open}")?;
+                    let item = serde_json::to_string_pretty(item)?;
+                    write!(self.out, "{}", indent(&item).trim_end_matches('\n'))?;
+                }
+            }
+            OutputFormat::Yaml => {
+                for item in items {
+                    write!(
+                        self.out,
+                        "- {}",
+                        &indent(&serde_yaml::to_string(item)?)[2..]
+                    )?;
+                }
+            }
+            OutputFormat::Csv => write_rows(
+                &mut self.out,
+                &T::headers(),
+                self.printed == 0,
+                items.iter().map(Render::row),
+            )?,
+        }
+        self.printed += items.len();
+        self.out.flush()?;
+        Ok(())
+    }
+
+    /// Closes the listing after its last page.
+    pub fn finish<T: Render>(mut self) -> Result<()> {
+        if events::enabled() {
+            return Ok(());
+        }
+        if waits() {
+            return self.finish_held::<T>();
+        }
+        let empty = self.printed == 0;
+        match self.format {
+            OutputFormat::Table if quiet() => {}
+            OutputFormat::Table => match &self.bottom {
+                Some(bottom) => writeln!(self.out, "{bottom}")?,
+                None => writeln!(self.out, "{}", table::<T>(&[])?)?,
+            },
+            OutputFormat::Json if empty => writeln!(self.out, "[]")?,
+            OutputFormat::Json => writeln!(self.out, "\n]")?,
+            OutputFormat::Yaml if empty => writeln!(self.out, "[]")?,
+            OutputFormat::Yaml => {}
+            OutputFormat::Csv if empty => write_csv(&mut self.out, &[] as &[T])?,
+            OutputFormat::Csv => {}
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+
+    /// Ends the listing the way `listed`, the listing feeding it, ended. A
+    /// failed listing closes what it printed, so a JSON array or a table is
+    /// never left open above the error, and prints nothing otherwise.
+    pub fn close<T: Render, R>(self, listed: Result<R>) -> Result<R> {
+        match listed {
+            Ok(listed) => {
+                self.finish::<T>()?;
+                Ok(listed)
+            }
+            Err(err) => {
+                if !events::enabled() && !waits() && self.printed > 0 {
+                    self.finish::<T>()?;
+                }
+                Err(err)
+            }
+        }
+    }
+
+    // prints a page as rows of one table: every line but the bottom border,
+    // with later pages standing in a row separator for their top border
+    // and header
+    fn table_page<T: Render>(&mut self, items: &[T]) -> Result<()> {
+        if items.is_empty() {
+            return Ok(());
+        }
+        let mut table = table(items)?;
+        let first = self.bottom.is_none();
+        if first {
+            self.widths = table
+                .column_max_content_widths()
+                .into_iter()
+                .map(|width| width + 2)
+                .collect();
+        } else {
+            table.set_constraints(
+                self.widths
+                    .iter()
+                    .map(|&width| ColumnConstraint::Absolute(Width::Fixed(width))),
+            );
+        }
+        let mut lines: Vec<String> = table.lines().collect();
+        self.bottom = lines.pop();
+        let rows = if first {
+            &lines[..]
+        } else {
+            // the top border, header, and header separator
+            writeln!(self.out, "{}", row_separator(&table, &lines[0]))?;
+            &lines[3..]
+        };
+        for line in rows {
+            writeln!(self.out, "{line}")?;
+        }
+        Ok(())
+    }
+
+    // prints the held items, in `--sort-by` order
+    fn finish_held<T: Render>(mut self) -> Result<()> {
+        let headers = T::headers();
+        let rows: Vec<Vec<String>> = self.held.iter().map(|h| h.row.clone()).collect();
+        let held: Vec<&Held> = order(&headers, &rows)?
+            .into_iter()
+            .map(|i| &self.held[i])
+            .collect();
+        let values: Vec<&serde_json::Value> = held.iter().map(|h| &h.value).collect();
+        if let Some(projection) = PROJECTION.get() {
+            let values: Vec<serde_json::Value> = values.into_iter().cloned().collect();
+            write!(self.out, "{}", projection.render(&values, false)?)?;
+            return Ok(());
+        }
+        match self.format {
+            OutputFormat::Table if quiet() => {
+                for h in &held {
+                    writeln!(self.out, "{}", h.row.first().map_or("", String::as_str))?;
+                }
+            }
+            OutputFormat::Table => {
+                let table = grid(&headers, held.iter().map(|h| h.table_row.clone()))?;
+                writeln!(self.out, "{table}")?;
+            }
+            OutputFormat::Json => writeln!(self.out, "{}", serde_json::to_string_pretty(&values)?)?,
+            OutputFormat::Yaml => write!(self.out, "{}", serde_yaml::to_string(&values)?)?,
+            OutputFormat::Csv => write_rows(
+                &mut self.out,
+                &headers,
+                true,
+                held.iter().map(|h| h.row.clone()),
+            )?,
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+// whether a streamed listing must wait for its last page
+fn waits() -> bool {
+    PROJECTION.get().is_some() || layout::layout().is_some_and(Layout::sorts)
+}
+
+// the `--sort-by` order of `rows`, as positions in it
+fn order(headers: &[&str], rows: &[Vec<String>]) -> Result<Vec<usize>> {
+    match layout::layout() {
+        Some(layout) => layout.order(headers, rows),
+        None => Ok((0..rows.len()).collect()),
+    }
+}
+
+// the cells of `row` in the `--columns` chosen from `headers`
+fn pick(columns: &[usize], row: &[String]) -> Vec<String> {
+    columns
+        .iter()
+        .map(|&i| row.get(i).cloned().unwrap_or_default())
+        .collect()
+}
+
+fn columns(headers: &[&str]) -> Result<Vec<usize>> {
+    match layout::layout() {
+        Some(layout) => layout.columns(headers),
+        None => Ok((0..headers.len()).collect()),
+    }
+}
+
+// indents every line but blank ones two spaces, as items of a JSON or YAML
+// sequence are
+fn indent(text: &str) -> String {
+    text.split_inclusive('\n')
+        .map(|line| match line {
+            "\n" => line.to_string(),
+            line => format!("  {line}"),
+        })
+        .collect()
+}
+
+// a line between two rows, drawn from the table's top border
+fn row_separator(table: &Table, top: &str) -> String {
+    let style = table.style();
+    let (from, to) = (style.top_border, style.row_separator);
+    let swaps = [
+        (from.left, to.left),
+        (from.fill, to.fill),
+        (from.junction, to.junction),
+        (from.right, to.right),
+    ];
+    top.chars()
+        .map(|c| {
+            swaps
+                .iter()
+                .find(|(from, _)| *from == Some(c))
+                .map_or(c, |(_, to)| to.unwrap_or(' '))
+        })
+        .collect()
+}
+
+fn table<T: Render>(items: &[T]) -> Result<Table> {
+    grid(&T::headers(), items.iter().map(Render::table_row))
+}
+
+fn grid(headers: &[&str], rows: impl IntoIterator<Item = Vec<String>>) -> Result<Table> {
+    let columns = columns(headers)?;
+    let mut table = Table::new();
+    table
+        .load_style(UTF8_FULL)
+        .set_header(columns.iter().map(|&i| headers[i]));
+    for row in rows {
+        table.add_row(pick(&columns, &row));
+    }
+    Ok(table)
+}
+
+fn first_column<T: Render>(items: &[T]) -> String {
+    items
+        .iter()
+        .filter_map(|item| item.row().into_iter().next())
+        .map(|name| name + "\n")
+        .collect()
+}
+
+fn write_csv<T: Render>(out: impl Write, items: &[T]) -> Result<()> {
+    write_rows(out, &T::headers(), true, items.iter().map(Render::row))
+}
+
+fn write_rows(
+    out: impl Write,
+    headers: &[&str],
+    header: bool,
+    rows: impl IntoIterator<Item = Vec<String>>,
+) -> Result<()> {
+    let columns = columns(headers)?;
+    let mut writer = csv::Writer::from_writer(out);
+    if header {
+        writer.write_record(columns.iter().map(|&i| headers[i]))?;
+    }
+    for row in rows {
+        writer.write_record(pick(&columns, &row))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Emoji prefix for instance status in tables.
+pub fn status_emoji(status: &str) -> &'static str {
+    match status {
+        "RUNNING" => "🟢",
+        "STOPPED" | "TERMINATED" => "🔴",
+        "SUSPENDED" => "🟡",
+        _ => "⚪",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Serialize)]
+    struct Item {
+        name: String,
+        note: String,
+    }
+
+    impl Render for Item {
+        fn headers() -> Vec<&'static str> {
+            vec!["Name", "Note"]
+        }
+
+        fn row(&self) -> Vec<String> {
+            vec![self.name.clone(), self.note.clone()]
+        }
+    }
+
+    #[test]
+    fn csv_quotes_fields() {
+        let items = [Item {
+            name: "vm".into(),
+            note: "a, b".into(),
+        }];
+        let mut buf = Vec::new();
+        write_csv(&mut buf, &items).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "Name,Note\nvm,\"a, b\"\n");
+    }
+
+    #[test]
+    fn details_align_and_nest() {
+        let mut details = Details::default();
+        details.field("Name", "vm").field("Zone-Name", "z");
+        details.group("Labels", |g| {
+            g.field("env", "dev");
+        });
+        details.group("Empty", |_| {});
+        assert_eq!(
+            details.render(),
+            "• Name      : vm\n• Zone-Name : z\nLabels:\n  • env : dev\n"
+        );
+    }
+
+    #[test]
+    fn details_abbreviate_multiline_values() {
+        let mut details = Details::default();
+        details.field("startup-script", "#!/bin/bash\necho hi");
+        assert_eq!(details.render(), "• startup-script : #!/bin/bash …\n");
+    }
+
+    #[test]
+    fn project_column_follows_the_name() {
+        let item = Item {
+            name: "vm".into(),
+            note: "ok".into(),
+        };
+        let listed = InProject {
+            project: "p1",
+            item: &item,
+        };
+        assert_eq!(InProject::<Item>::headers(), ["Name", "Project", "Note"]);
+        assert_eq!(listed.row(), ["vm", "p1", "ok"]);
+        assert_eq!(
+            serde_json::to_value(&listed).unwrap(),
+            serde_json::json!({"project": "p1", "name": "vm", "note": "ok"})
+        );
+    }
+
+    fn streamed(format: OutputFormat, pages: &[&[Item]]) -> String {
+        let mut buf = Vec::new();
+        let mut printer = ListPrinter::new(format, &mut buf);
+        for page in pages {
+            printer.page(page).unwrap();
+        }
+        printer.finish::<Item>().unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn streamed_pages_print_as_one_listing() {
+        let item = |name: &str, note: &str| Item {
+            name: name.into(),
+            note: note.into(),
+        };
+        let first = [item("instance-1", "first page"), item("instance-2", "")];
+        let second = [item("vm-3", "a, b")];
+        let all = [&first[..], &second[..]].concat();
+        let pages: [&[Item]; 3] = [&first, &[], &second];
+
+        assert_eq!(
+            streamed(OutputFormat::Json, &pages),
+            serde_json::to_string_pretty(&all).unwrap() + "\n"
+        );
+        assert_eq!(
+            streamed(OutputFormat::Yaml, &pages),
+            serde_yaml::to_string(&all).unwrap()
+        );
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &all).unwrap();
+        assert_eq!(
+            streamed(OutputFormat::Csv, &pages),
+            String::from_utf8(csv).unwrap()
+        );
+        // the first page is the widest, so its widths fit every row
+        assert_eq!(
+            streamed(OutputFormat::Table, &pages),
+            table(&all).unwrap().to_string() + "\n"
+        );
+        for format in [OutputFormat::Table, OutputFormat::Json, OutputFormat::Yaml] {
+            assert_eq!(
+                streamed(format, &[]),
+                streamed(format, &[&[]]),
+                "{format:?}"
+            );
+        }
+        assert_eq!(streamed(OutputFormat::Json, &[]), "[]\n");
+        assert_eq!(streamed(OutputFormat::Csv, &[]), "Name,Note\n");
+    }
+
+    #[test]
+    fn failed_listings_close_what_they_printed() {
+        let close = |pages: &[&[Item]]| {
+            let mut buf = Vec::new();
+            let mut printer = ListPrinter::new(OutputFormat::Json, &mut buf);
+            for page in pages {
+                printer.page(page).unwrap();
+            }
+            let failed: Result<()> = Err(anyhow::anyhow!("page 2 failed"));
+            assert!(printer.close::<Item, _>(failed).is_err());
+            String::from_utf8(buf).unwrap()
+        };
+        let first = [Item {
+            name: "instance-1".into(),
+            note: String::new(),
+        }];
+        let printed = close(&[&first]);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&printed).unwrap(),
+            serde_json::to_value(&first).unwrap()
+        );
+        assert_eq!(close(&[]), "");
+    }
+
+    #[test]
+    fn streamed_tables_keep_the_first_page_widths() {
+        let first = [Item {
+            name: "vm".into(),
+            note: "ok".into(),
+        }];
+        let second = [Item {
+            name: "longer".into(),
+            note: "ok".into(),
+        }];
+        let rendered = streamed(OutputFormat::Table, &[&first, &second]);
+        let widths: Vec<usize> = rendered.lines().map(|l| l.chars().count()).collect();
+        assert!(widths.iter().all(|&w| w == widths[0]), "{rendered}");
+        assert!(rendered.contains("│ lo"), "{rendered}");
+    }
+
+    #[test]
+    fn table_has_headers_and_rows() {
+        let items = [Item {
+            name: "vm-1".into(),
+            note: "ok".into(),
+        }];
+        let rendered = table(&items).unwrap().to_string();
+        assert!(rendered.contains("Name"));
+        assert!(rendered.contains("vm-1"));
+    }
+}
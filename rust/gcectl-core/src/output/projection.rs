@@ -0,0 +1,245 @@
+//! `--format` projections, which print chosen fields of each resource
+//! instead of the whole thing.
+//!
+//! A projection is a printer applied to a list of field paths, e.g.
+//! `value(name,networkInterfaces[0].accessConfigs[0].natIP)`. Paths follow
+//! the API's JSON field names; `[N]` picks an array element, and a key
+//! applied to an array collects it from every element, so `disks.deviceName`
+//! names all disks. Printers:
+//!
+//! - `value(...)`: tab-separated values, one line per resource, no header
+//! - `csv(...)`: CSV with the paths as the header row
+//! - `table(...)`: a table with the paths as headers
+//! - `json(...)`: JSON objects keyed by path
+
+use std::str::FromStr;
+
+use anyhow::{Error, Result, anyhow, bail};
+use comfy_table::{Table, presets::UTF8_FULL};
+use serde_json::{Map, Value};
+
+/// A parsed `--format` expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Projection {
+    printer: Printer,
+    fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Printer {
+    Value,
+    Csv,
+    Table,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Field {
+    // as written, for headers and JSON keys
+    path: String,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+impl Projection {
+    /// Renders the projected fields of `items`, each a serialized resource.
+    /// `one` is set for describe output, where JSON is an object rather
+    /// than an array.
+    pub fn render(&self, items: &[Value], one: bool) -> Result<String> {
+        let rows: Vec<Vec<Value>> = items
+            .iter()
+            .map(|item| self.fields.iter().map(|f| f.select(item)).collect())
+            .collect();
+        let headers = self.fields.iter().map(|f| f.path.as_str());
+        Ok(match self.printer {
+            Printer::Value => rows
+                .iter()
+                .map(|row| row.iter().map(cell).collect::<Vec<_>>().join("\t") + "\n")
+                .collect(),
+            Printer::Csv => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                writer.write_record(headers)?;
+                for row in &rows {
+                    writer.write_record(row.iter().map(cell))?;
+                }
+                String::from_utf8(writer.into_inner()?)?
+            }
+            Printer::Table => {
+                let mut table = Table::new();
+                table.load_style(UTF8_FULL).set_header(headers);
+                for row in &rows {
+                    table.add_row(row.iter().map(cell));
+                }
+                format!("{table}\n")
+            }
+            Printer::Json => {
+                let objects: Vec<Value> = rows
+                    .into_iter()
+                    .map(|row| {
+                        let object: Map<String, Value> = self
+                            .fields
+                            .iter()
+                            .map(|f| f.path.clone())
+                            .zip(row)
+                            .collect();
+                        Value::Object(object)
+                    })
+                    .collect();
+                let json = match (one, objects.as_slice()) {
+                    (true, [object]) => serde_json::to_string_pretty(object)?,
+                    _ => serde_json::to_string_pretty(&objects)?,
+                };
+                json + "\n"
+            }
+        })
+    }
+}
+
+impl Field {
+    /// Value at the path in `item`, `null` where it leads nowhere.
+    fn select(&self, item: &Value) -> Value {
+        self.steps
+            .iter()
+            .fold(item.clone(), |value, step| match (step, value) {
+                (Step::Index(i), Value::Array(mut items)) if *i < items.len() => {
+                    items.swap_remove(*i)
+                }
+                (Step::Key(key), Value::Array(items)) => Value::Array(
+                    items
+                        .into_iter()
+                        .map(|item| item.get(key).cloned().unwrap_or(Value::Null))
+                        .filter(|v| !v.is_null())
+                        .collect(),
+                ),
+                (Step::Key(key), Value::Object(mut object)) => {
+                    object.remove(key).unwrap_or(Value::Null)
+                }
+                _ => Value::Null,
+            })
+    }
+}
+
+// scalars as plain text and lists joined with `;`, like gcloud's value()
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(cell).collect::<Vec<_>>().join(";"),
+        other => other.to_string(),
+    }
+}
+
+impl FromStr for Projection {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (name, rest) = s
+            .split_once('(')
+            .ok_or_else(|| anyhow!("expected PRINTER(FIELD,...), e.g. 'value(name,status)'"))?;
+        let printer = match name.trim() {
+            "value" => Printer::Value,
+            "csv" => Printer::Csv,
+            "table" => Printer::Table,
+            "json" => Printer::Json,
+            other => bail!("unknown printer `{other}`; expected value, csv, table, or json"),
+        };
+        let list = rest
+            .strip_suffix(')')
+            .ok_or_else(|| anyhow!("missing `)` after the field list"))?;
+        let fields = list
+            .split(',')
+            .map(|path| parse_field(path.trim()))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { printer, fields })
+    }
+}
+
+fn parse_field(path: &str) -> Result<Field> {
+    if path.is_empty() {
+        bail!("empty field in the field list");
+    }
+    let mut steps = Vec::new();
+    for segment in path.split('.') {
+        let (key, mut indexes) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+        let valid = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+        if key.is_empty() || !key.chars().all(valid) {
+            bail!("invalid field `{path}`");
+        }
+        steps.push(Step::Key(key.to_string()));
+        while !indexes.is_empty() {
+            let (index, rest) = indexes
+                .strip_prefix('[')
+                .and_then(|i| i.split_once(']'))
+                .ok_or_else(|| anyhow!("invalid index in `{path}`"))?;
+            let index = index
+                .parse()
+                .map_err(|_| anyhow!("index `{index}` in `{path}` is not a number"))?;
+            steps.push(Step::Index(index));
+            indexes = rest;
+        }
+    }
+    Ok(Field {
+        path: path.to_string(),
+        steps,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn web() -> Value {
+        json!({
+            "name": "web-1",
+            "networkInterfaces": [{"accessConfigs": [{"natIP": "34.1.2.3"}]}],
+            "disks": [{"deviceName": "boot"}, {"deviceName": "data"}],
+            "labels": {"env": "prod"},
+        })
+    }
+
+    #[test]
+    fn projects_values_by_path() {
+        let projection: Projection =
+            "value(name, networkInterfaces[0].accessConfigs[0].natIP, disks.deviceName, labels.env, missing)"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            projection.render(&[web()], false).unwrap(),
+            "web-1\t34.1.2.3\tboot;data\tprod\t\n"
+        );
+    }
+
+    #[test]
+    fn csv_and_json_are_keyed_by_path() {
+        let csv: Projection = "csv(name,labels.env)".parse().unwrap();
+        assert_eq!(
+            csv.render(&[web()], false).unwrap(),
+            "name,labels.env\nweb-1,prod\n"
+        );
+        let json: Projection = "json(name,disks[1].deviceName)".parse().unwrap();
+        let one: Value = serde_json::from_str(&json.render(&[web()], true).unwrap()).unwrap();
+        assert_eq!(one, json!({"name": "web-1", "disks[1].deviceName": "data"}));
+    }
+
+    #[test]
+    fn rejects_malformed_projections() {
+        for bad in [
+            "name",
+            "yaml(name)",
+            "value(name",
+            "value(name,)",
+            "value(disks[x])",
+            "value(disks[0)",
+            "value(a..b)",
+        ] {
+            assert!(bad.parse::<Projection>().is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,237 @@
+//! Ranking a region's zones for new capacity, for `placement suggest` and
+//! `fleet create --region`.
+//!
+//! A zone that does not offer the machine type or an accelerator asked for
+//! is out. The rest are ordered by the stock-outs gcectl recorded there in
+//! the audit log, of any machine type, fewest first and then the longest
+//! ago. Quota is regional, so the headroom it leaves is the same for every
+//! zone and only says whether the capacity fits at all.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+
+use crate::audit::Entry;
+use crate::fleet;
+use crate::output::Render;
+use crate::resources::{Quota, Region};
+
+/// How far back stock-outs count unless told otherwise.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// One zone's standing for the capacity asked for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Placement {
+    // 1 for the best zone; none for zones that do not offer the shape
+    pub rank: Option<usize>,
+    pub zone: String,
+    pub offered: bool,
+    pub stockouts: usize,
+    pub last_stockout: Option<DateTime<Utc>>,
+    // instances of the shape the region's quota still has room for
+    pub headroom: Option<u64>,
+}
+
+impl Render for Placement {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Rank",
+            "Zone",
+            "Offered",
+            "Stock-Outs",
+            "Last-Stock-Out",
+            "Headroom",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.rank.map(|r| r.to_string()).unwrap_or_default(),
+            self.zone.clone(),
+            self.offered.to_string(),
+            self.stockouts.to_string(),
+            self.last_stockout
+                .map(|t| t.with_timezone(&Local).format("%F %T").to_string())
+                .unwrap_or_default(),
+            self.headroom.map(|h| h.to_string()).unwrap_or_default(),
+        ]
+    }
+}
+
+/// What one instance of the capacity takes from the region's quota.
+#[derive(Debug, Clone, Default)]
+pub struct Shape {
+    pub machine_type: String,
+    pub cpus: u32,
+    // accelerator type and count, bundled with the machine type or attached
+    pub gpus: Vec<(String, u32)>,
+    pub spot: bool,
+}
+
+impl Shape {
+    /// How many instances fit in what `region`'s quotas have left, or
+    /// `None` if the region reports none of the quotas the shape uses.
+    pub fn headroom(&self, region: &Region) -> Option<u64> {
+        let mut needs = vec![(self.cpu_quota(region), self.cpus)];
+        for (accelerator, count) in &self.gpus {
+            needs.push((self.quota(region, &gpu_metric(accelerator)), *count));
+        }
+        needs
+            .into_iter()
+            .filter_map(|(quota, need)| quota.filter(|_| need > 0).map(|q| (q, need)))
+            .map(|(quota, need)| ((quota.limit - quota.usage).max(0.0) / f64::from(need)) as u64)
+            .min()
+    }
+
+    // accelerator-optimized and newer families have a CPU quota of their
+    // own, E2 and N1 count against plain CPUS, and Spot CPUs of any family
+    // against the preemptible quota
+    fn cpu_quota<'a>(&self, region: &'a Region) -> Option<&'a Quota> {
+        let family = self.machine_type.split('-').next().unwrap_or_default();
+        let metric = format!("{}_CPUS", family.to_ascii_uppercase());
+        let preemptible = region
+            .quota("PREEMPTIBLE_CPUS")
+            .filter(|q| self.spot && q.limit > 0.0);
+        preemptible
+            .or_else(|| region.quota(&metric))
+            .or_else(|| region.quota("CPUS"))
+    }
+
+    // Spot capacity counts against the preemptible quota where the project
+    // has one
+    fn quota<'a>(&self, region: &'a Region, metric: &str) -> Option<&'a Quota> {
+        let preemptible = region
+            .quota(&format!("PREEMPTIBLE_{metric}"))
+            .filter(|q| self.spot && q.limit > 0.0);
+        preemptible.or_else(|| region.quota(metric))
+    }
+}
+
+// `nvidia-tesla-t4` counts against NVIDIA_T4_GPUS
+fn gpu_metric(accelerator: &str) -> String {
+    let metric = accelerator.to_ascii_uppercase().replace('-', "_");
+    format!("{}_GPUS", metric.replace("_TESLA_", "_"))
+}
+
+/// Stock-outs recorded in `zone` since `since`, and when the last was.
+pub fn stockouts(
+    entries: &[Entry],
+    zone: &str,
+    since: DateTime<Utc>,
+) -> (usize, Option<DateTime<Utc>>) {
+    let times: Vec<DateTime<Utc>> = entries
+        .iter()
+        .filter(|e| e.time >= since && e.location.as_deref() == Some(zone))
+        .filter(|e| e.result.starts_with("failed:") && fleet::is_stockout_message(&e.result))
+        .map(|e| e.time)
+        .collect();
+    (times.len(), times.into_iter().max())
+}
+
+/// `placements` best first, numbered; zones not offering the shape trail
+/// unnumbered.
+pub fn rank(mut placements: Vec<Placement>) -> Vec<Placement> {
+    placements.sort_by(|a, b| {
+        (!a.offered, a.stockouts, a.last_stockout, &a.zone).cmp(&(
+            !b.offered,
+            b.stockouts,
+            b.last_stockout,
+            &b.zone,
+        ))
+    });
+    for (i, placement) in placements.iter_mut().enumerate() {
+        placement.rank = placement.offered.then_some(i + 1);
+    }
+    placements
+}
+
+/// The zones to place capacity in, best first.
+pub fn ranked_zones(placements: &[Placement]) -> Vec<String> {
+    placements
+        .iter()
+        .filter(|p| p.offered)
+        .map(|p| p.zone.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::Event;
+
+    fn quota(metric: &str, limit: f64, usage: f64) -> Quota {
+        Quota {
+            metric: metric.into(),
+            limit,
+            usage,
+        }
+    }
+
+    #[test]
+    fn headroom_is_the_scarcest_quota() {
+        let region = Region {
+            quotas: vec![
+                quota("CPUS", 100.0, 0.0),
+                quota("A2_CPUS", 48.0, 12.0),
+                quota("NVIDIA_A100_GPUS", 4.0, 2.0),
+                quota("PREEMPTIBLE_CPUS", 0.0, 0.0),
+            ],
+            ..Default::default()
+        };
+        let mut shape = Shape {
+            machine_type: "a2-highgpu-1g".into(),
+            cpus: 12,
+            gpus: vec![("nvidia-tesla-a100".into(), 1)],
+            spot: false,
+        };
+        assert_eq!(shape.headroom(&region), Some(2));
+        shape.gpus.clear();
+        assert_eq!(shape.headroom(&region), Some(3));
+        // no preemptible quota to speak of, so Spot uses the regular one
+        shape.spot = true;
+        assert_eq!(shape.headroom(&region), Some(3));
+        shape.machine_type = "e2-standard-8".into();
+        shape.cpus = 8;
+        assert_eq!(shape.headroom(&region), Some(12));
+        assert_eq!(gpu_metric("nvidia-l4"), "NVIDIA_L4_GPUS");
+    }
+
+    #[test]
+    fn ranks_offered_zones_by_recent_stockouts() {
+        let now = Utc::now();
+        let stockout = |zone: &str, hours: i64| Entry {
+            time: now - chrono::Duration::hours(hours),
+            location: Some(zone.into()),
+            ..Entry::new(
+                Event::Operation,
+                "insert",
+                "projects/p/zones/z/instances/gpu-1",
+                "failed: ZONE_RESOURCE_POOL_EXHAUSTED: no capacity".into(),
+            )
+        };
+        let entries = [stockout("a", 1), stockout("a", 400), stockout("c", 2)];
+        let since = now - chrono::Duration::days(7);
+        assert_eq!(stockouts(&entries, "a", since).0, 1);
+        let placement = |zone: &str, offered: bool| {
+            let (stockouts, last_stockout) = stockouts(&entries, zone, since);
+            Placement {
+                rank: None,
+                zone: zone.into(),
+                offered,
+                stockouts,
+                last_stockout,
+                headroom: None,
+            }
+        };
+        let ranked = rank(vec![
+            placement("a", true),
+            placement("b", false),
+            placement("c", true),
+            placement("d", true),
+        ]);
+        assert_eq!(ranked_zones(&ranked), ["d", "c", "a"]);
+        assert_eq!(ranked[0].rank, Some(1));
+        assert_eq!((ranked[3].zone.as_str(), ranked[3].rank), ("b", None));
+    }
+}
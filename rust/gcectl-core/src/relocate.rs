@@ -0,0 +1,321 @@
+//! `instances move`: recreating an instance in another zone from snapshots
+//! of its disks.
+//!
+//! The source is stopped and every persistent disk snapshotted, then the
+//! instance is created again in the destination with the same settings and
+//! disk names. Reserved external addresses follow it when the destination is
+//! in the same region; everything else tied to the source zone, such as
+//! internal and ephemeral IPs, local SSDs, and regional policies elsewhere,
+//! is left behind and listed in the plan.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use anyhow::{Result, bail};
+use serde_json::{Value, json};
+
+use crate::resources::instance::clone::{self, Overrides};
+use crate::resources::{Address, Disk, Instance, region_of, short_name};
+
+const MAX_NAME: usize = 63;
+
+/// A snapshot the move takes of one disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskSnapshot {
+    pub disk: String,
+    pub snapshot: String,
+}
+
+/// A reserved external address handed from the source to its replacement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovedAddress {
+    pub address: String,
+    pub ip: String,
+    // network interface, e.g. `nic0`, and access config of the source
+    pub interface: String,
+    pub config: String,
+}
+
+/// The steps of a move, printed before it starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    pub name: String,
+    pub from: String,
+    pub to: String,
+    pub stop: bool,
+    pub snapshots: Vec<DiskSnapshot>,
+    pub addresses: Vec<MovedAddress>,
+    pub delete_source: bool,
+    // what the destination will not get from the source
+    pub left_behind: Vec<String>,
+}
+
+/// Plans moving `source` to `to`. `reserved` holds the static addresses of
+/// the source's region and `stamp` suffixes the snapshot names.
+pub fn plan(
+    source: &Instance,
+    reserved: &[Address],
+    to: &str,
+    delete_source: bool,
+    stamp: &str,
+) -> Result<Plan> {
+    let from = source.zone_name();
+    if from == to {
+        bail!("instance {} is already in {to}", source.name);
+    }
+    let same_region = region_of(from) == region_of(to);
+    let mut left_behind = Vec::new();
+    let mut snapshots = Vec::new();
+    for attached in &source.disks {
+        let Some(url) = attached.source.as_deref() else {
+            left_behind.push("a local SSD and its data".to_string());
+            continue;
+        };
+        if !url.contains("/zones/") {
+            bail!(
+                "instance {} uses regional disk {}, which cannot be moved",
+                source.name,
+                short_name(url)
+            );
+        }
+        let disk = short_name(url);
+        snapshots.push(DiskSnapshot {
+            disk: disk.to_string(),
+            snapshot: snapshot_name(disk, stamp),
+        });
+    }
+
+    let mut addresses = Vec::new();
+    for (i, nic) in source.network_interfaces.iter().enumerate() {
+        let interface = nic.name.clone().unwrap_or_else(|| format!("nic{i}"));
+        if let Some(ip) = &nic.network_ip {
+            left_behind.push(format!("internal IP {ip} of {interface}"));
+        }
+        for config in &nic.access_configs {
+            let Some(ip) = &config.nat_ip else {
+                continue;
+            };
+            let address = reserved.iter().find(|a| &a.address == ip);
+            match address {
+                Some(address) if same_region => addresses.push(MovedAddress {
+                    address: address.name.clone(),
+                    ip: ip.clone(),
+                    interface: interface.clone(),
+                    config: config
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| "External NAT".to_string()),
+                }),
+                Some(address) => left_behind.push(format!(
+                    "static IP {ip} ({}), which stays in {}",
+                    address.name,
+                    region_of(from)
+                )),
+                None => left_behind.push(format!("ephemeral external IP {ip}")),
+            }
+        }
+    }
+    if !same_region && !source.resource_policies.is_empty() {
+        left_behind.push(format!("resource policies of {}", region_of(from)));
+    }
+    Ok(Plan {
+        name: source.name.clone(),
+        from: from.to_string(),
+        to: to.to_string(),
+        stop: source.status == "RUNNING",
+        snapshots,
+        addresses,
+        delete_source,
+        left_behind,
+    })
+}
+
+/// Insert body for the instance in the destination, its disks restored
+/// from the plan's snapshots and its reserved addresses carried over.
+pub fn target_body(source: &Instance, disks: &[Disk], plan: &Plan, project: &str) -> Result<Value> {
+    let restored: Vec<(String, Disk)> = disks
+        .iter()
+        .filter_map(|disk| {
+            let url = disk.self_link.clone()?;
+            let snapshot = plan.snapshots.iter().find(|s| s.disk == disk.name)?;
+            let restored = Disk {
+                source_image: None,
+                source_snapshot: Some(format!(
+                    "projects/{project}/global/snapshots/{}",
+                    snapshot.snapshot
+                )),
+                ..disk.clone()
+            };
+            Some((url, restored))
+        })
+        .collect();
+    let by_url: HashMap<&str, &Disk> = restored.iter().map(|(u, d)| (u.as_str(), d)).collect();
+    let overrides = Overrides {
+        name: &source.name,
+        zone: &plan.to,
+        machine_type: None,
+        keep_disk_names: true,
+    };
+    let mut body = clone::clone_body(source, &by_url, &overrides)?;
+    for (i, nic) in source.network_interfaces.iter().enumerate() {
+        let interface = nic.name.clone().unwrap_or_else(|| format!("nic{i}"));
+        for (j, config) in nic.access_configs.iter().enumerate() {
+            let moved = plan
+                .addresses
+                .iter()
+                .find(|a| a.interface == interface && Some(&a.ip) == config.nat_ip.as_ref());
+            if let Some(moved) = moved {
+                body["networkInterfaces"][i]["accessConfigs"][j]["natIP"] = json!(moved.ip);
+            }
+        }
+    }
+    Ok(body)
+}
+
+// a snapshot name for `disk` that fits the 63 characters names allow
+fn snapshot_name(disk: &str, stamp: &str) -> String {
+    let room = MAX_NAME - stamp.len() - 1;
+    let prefix = disk[..disk.len().min(room)].trim_end_matches('-');
+    format!("{prefix}-{stamp}")
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut steps = Vec::new();
+        if self.stop {
+            steps.push(format!("stop {} in {}", self.name, self.from));
+        }
+        for s in &self.snapshots {
+            steps.push(format!("snapshot disk {} as {}", s.disk, s.snapshot));
+        }
+        for a in &self.addresses {
+            steps.push(format!(
+                "release static IP {} ({}) from {}",
+                a.ip, a.address, self.name
+            ));
+        }
+        steps.push(format!(
+            "create {} in {} from the snapshots{}",
+            self.name,
+            self.to,
+            match self.addresses.is_empty() {
+                true => "",
+                false => " with the static IPs",
+            }
+        ));
+        if self.delete_source {
+            steps.push(format!("delete {} in {}", self.name, self.from));
+        }
+        writeln!(f, "Move {} from {} to {}:", self.name, self.from, self.to)?;
+        for (i, step) in steps.iter().enumerate() {
+            writeln!(f, "  {}. {step}", i + 1)?;
+        }
+        for lost in &self.left_behind {
+            writeln!(f, "  left behind: {lost}")?;
+        }
+        write!(f, "  the snapshots are kept afterwards")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::instance::{AccessConfig, AttachedDisk, NetworkInterface};
+
+    const DISK_URL: &str =
+        "https://compute.googleapis.com/compute/v1/projects/p/zones/us-central1-a/disks/web";
+
+    fn source() -> Instance {
+        Instance {
+            name: "web".into(),
+            zone: "https://x/projects/p/zones/us-central1-a".into(),
+            machine_type: "zones/us-central1-a/machineTypes/e2-medium".into(),
+            status: "RUNNING".into(),
+            disks: vec![AttachedDisk {
+                source: Some(DISK_URL.into()),
+                boot: true,
+                auto_delete: true,
+                ..Default::default()
+            }],
+            network_interfaces: vec![NetworkInterface {
+                name: Some("nic0".into()),
+                network_ip: Some("10.128.0.2".into()),
+                access_configs: vec![AccessConfig {
+                    name: Some("External NAT".into()),
+                    nat_ip: Some("34.1.2.3".into()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn reserved() -> Vec<Address> {
+        vec![Address {
+            name: "web-ip".into(),
+            address: "34.1.2.3".into(),
+            ..Default::default()
+        }]
+    }
+
+    #[test]
+    fn static_ips_follow_within_the_region() {
+        let plan = plan(
+            &source(),
+            &reserved(),
+            "us-central1-b",
+            true,
+            "20260101120000",
+        )
+        .unwrap();
+        assert!(plan.stop);
+        assert_eq!(plan.snapshots[0].snapshot, "web-20260101120000");
+        assert_eq!(plan.addresses[0].address, "web-ip");
+        assert_eq!(plan.addresses[0].config, "External NAT");
+        assert_eq!(plan.left_behind, ["internal IP 10.128.0.2 of nic0"]);
+        let steps = plan.to_string();
+        assert!(steps.contains("1. stop web in us-central1-a"));
+        assert!(steps.contains("5. delete web in us-central1-a"));
+
+        let disk = Disk {
+            name: "web".into(),
+            disk_type: "zones/us-central1-a/diskTypes/pd-balanced".into(),
+            source_image: Some("projects/debian-cloud/global/images/debian-12".into()),
+            self_link: Some(DISK_URL.into()),
+            ..Default::default()
+        };
+        let body = target_body(&source(), &[disk], &plan, "p").unwrap();
+        let params = &body["disks"][0]["initializeParams"];
+        assert_eq!(params["diskName"], "web");
+        assert_eq!(
+            params["sourceSnapshot"],
+            "projects/p/global/snapshots/web-20260101120000"
+        );
+        assert!(params.get("sourceImage").is_none());
+        assert_eq!(
+            body["networkInterfaces"][0]["accessConfigs"][0]["natIP"],
+            "34.1.2.3"
+        );
+    }
+
+    #[test]
+    fn other_regions_keep_the_ip_and_long_names_are_cut() {
+        let plan = plan(
+            &source(),
+            &reserved(),
+            "europe-west1-b",
+            false,
+            "20260101120000",
+        )
+        .unwrap();
+        assert!(plan.addresses.is_empty());
+        assert!(plan.left_behind[1].contains("stays in us-central1"));
+        assert!(!plan.to_string().contains("delete web"));
+        assert!(super::plan(&source(), &[], "us-central1-a", false, "x").is_err());
+
+        let name = snapshot_name(&"d".repeat(63), "20260101120000");
+        assert_eq!(name.len(), 63);
+        assert!(name.ends_with("-20260101120000"));
+    }
+}
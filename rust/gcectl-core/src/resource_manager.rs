@@ -0,0 +1,85 @@
+//! Thin client for the Cloud Resource Manager v1 `projects` API, used to
+//! discover the projects `--all-projects` fans out to.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::auth::Authenticator;
+use crate::compute::parse_response;
+use crate::transport::Transport;
+
+const RESOURCE_MANAGER_ENDPOINT: &str = "https://cloudresourcemanager.googleapis.com/v1";
+
+pub struct ResourceManager {
+    http: Transport,
+    auth: Arc<Authenticator>,
+    endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectsPage {
+    #[serde(default)]
+    projects: Vec<Project>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Project {
+    project_id: String,
+}
+
+impl ResourceManager {
+    pub fn new(http: Transport, auth: Arc<Authenticator>) -> Self {
+        Self {
+            http,
+            auth,
+            endpoint: RESOURCE_MANAGER_ENDPOINT.to_string(),
+        }
+    }
+
+    /// Sends requests to `endpoint` instead of the public API, if given.
+    pub fn endpoint(mut self, endpoint: Option<&str>) -> Self {
+        if let Some(endpoint) = endpoint {
+            self.endpoint = endpoint.trim_end_matches('/').to_string();
+        }
+        self
+    }
+
+    /// `GET projects`, every page: the IDs of the active projects the
+    /// account can see, sorted.
+    pub async fn list_project_ids(&self) -> Result<Vec<String>> {
+        let url = format!("{}/projects", self.endpoint);
+        let mut ids = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut query = vec![("filter", "lifecycleState:ACTIVE")];
+            if let Some(token) = page_token.as_deref() {
+                query.push(("pageToken", token));
+            }
+            debug!("GET {url} {query:?}");
+            let request = self
+                .http
+                .get(&url)
+                .query(&query)
+                .bearer_auth(self.auth.token().await?);
+            let resp = self
+                .http
+                .send(request)
+                .await
+                .context("request to Cloud Resource Manager API failed")?;
+            let page: ProjectsPage = parse_response(resp, "Cloud Resource Manager API").await?;
+            ids.extend(page.projects.into_iter().map(|p| p.project_id));
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => break,
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+}
@@ -0,0 +1,115 @@
+use std::str::FromStr;
+
+use anyhow::{Error, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::short_name;
+use crate::output::{Details, Render};
+
+/// A GPU model offered in one zone.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AcceleratorType {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    // full URL of the zone
+    #[serde(default)]
+    pub zone: String,
+    #[serde(default)]
+    pub maximum_cards_per_instance: u32,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl AcceleratorType {
+    pub fn zone_name(&self) -> &str {
+        short_name(&self.zone)
+    }
+}
+
+impl Render for AcceleratorType {
+    fn headers() -> Vec<&'static str> {
+        vec!["Zone", "Name", "Description", "Max-Per-Instance"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.zone_name().to_string(),
+            self.name.clone(),
+            self.description.clone(),
+            self.maximum_cards_per_instance.to_string(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field("Description", &self.description)
+            .field("Zone", self.zone_name())
+            .field(
+                "Max-Per-Instance",
+                self.maximum_cards_per_instance.to_string(),
+            );
+        details
+    }
+}
+
+/// GPUs to attach to a new instance, parsed from
+/// `type=nvidia-tesla-t4,count=1`; the count defaults to one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Accelerator {
+    pub accelerator_type: String,
+    pub count: u32,
+}
+
+impl FromStr for Accelerator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut accelerator_type = None;
+        let mut count = 1;
+        for part in s.split(',') {
+            match part.split_once('=') {
+                Some(("type", value)) if !value.is_empty() => {
+                    accelerator_type = Some(value.to_string())
+                }
+                Some(("count", value)) => match value.parse() {
+                    Ok(n) if n > 0 => count = n,
+                    _ => bail!("accelerator count must be a positive number, got `{value}`"),
+                },
+                _ => bail!("expected type=TYPE[,count=N], got `{s}`"),
+            }
+        }
+        let Some(accelerator_type) = accelerator_type else {
+            bail!("accelerator `{s}` has no type=TYPE");
+        };
+        Ok(Self {
+            accelerator_type,
+            count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_type_and_count() {
+        let gpu: Accelerator = "type=nvidia-tesla-t4,count=2".parse().unwrap();
+        assert_eq!(gpu.accelerator_type, "nvidia-tesla-t4");
+        assert_eq!(gpu.count, 2);
+        let gpu: Accelerator = "type=nvidia-l4".parse().unwrap();
+        assert_eq!(gpu.count, 1);
+    }
+
+    #[test]
+    fn rejects_malformed_specs() {
+        assert!("count=1".parse::<Accelerator>().is_err());
+        assert!("type=nvidia-l4,count=0".parse::<Accelerator>().is_err());
+        assert!("nvidia-l4".parse::<Accelerator>().is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::short_name;
+use crate::output::{Details, Render};
+
+/// A reserved static IP address, regional or global.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Address {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub address: String,
+    // EXTERNAL or INTERNAL
+    #[serde(default)]
+    pub address_type: String,
+    // RESERVING, RESERVED, or IN_USE
+    #[serde(default)]
+    pub status: String,
+    // full URL of the region; absent for global addresses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_tier: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subnetwork: Option<String>,
+    // full URLs of the resources using the address
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_timestamp: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Address {
+    /// Region name, or `global`.
+    pub fn scope(&self) -> &str {
+        self.region.as_deref().map_or("global", short_name)
+    }
+
+    pub fn is_internal(&self) -> bool {
+        self.address_type == "INTERNAL"
+    }
+
+    pub fn user_names(&self) -> Vec<&str> {
+        self.users.iter().map(|u| short_name(u)).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum NetworkTier {
+    Premium,
+    Standard,
+}
+
+impl NetworkTier {
+    pub fn api_name(self) -> &'static str {
+        match self {
+            Self::Premium => "PREMIUM",
+            Self::Standard => "STANDARD",
+        }
+    }
+}
+
+/// Fields of an address to reserve.
+#[derive(Debug, Clone, Default)]
+pub struct AddressSpec {
+    pub name: String,
+    pub description: Option<String>,
+    // a specific IP to reserve instead of letting the API pick one
+    pub address: Option<String>,
+    pub network_tier: Option<NetworkTier>,
+    // `regions/{region}/subnetworks/{name}`; makes the address internal
+    pub subnetwork: Option<String>,
+}
+
+impl AddressSpec {
+    /// Request body for `addresses.insert` or `globalAddresses.insert`.
+    pub fn to_body(&self) -> Value {
+        let mut body = json!({ "name": self.name });
+        if let Some(description) = &self.description {
+            body["description"] = json!(description);
+        }
+        if let Some(address) = &self.address {
+            body["address"] = json!(address);
+        }
+        if let Some(tier) = self.network_tier {
+            body["networkTier"] = json!(tier.api_name());
+        }
+        if let Some(subnetwork) = &self.subnetwork {
+            body["addressType"] = json!("INTERNAL");
+            body["subnetwork"] = json!(subnetwork);
+        }
+        body
+    }
+}
+
+impl Render for Address {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Address", "Type", "Scope", "Status", "Users"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.address.clone(),
+            self.address_type.clone(),
+            self.scope().to_string(),
+            self.status.clone(),
+            self.user_names().join(","),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Id", self.id.as_deref())
+            .field_opt("Description", self.description.as_deref())
+            .field("Address", &self.address)
+            .field("Type", &self.address_type)
+            .field("Scope", self.scope())
+            .field("Status", &self.status)
+            .field_opt("Network-Tier", self.network_tier.as_deref())
+            .field_opt("Subnet", self.subnetwork.as_deref().map(short_name))
+            .field("Users", self.user_names().join(", "))
+            .field_opt("Created", self.creation_timestamp.as_deref());
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_spec_sets_subnet_and_type() {
+        let spec = AddressSpec {
+            name: "db".into(),
+            address: Some("10.0.0.5".into()),
+            subnetwork: Some("regions/us-central1/subnetworks/default".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            spec.to_body(),
+            json!({
+                "name": "db",
+                "address": "10.0.0.5",
+                "addressType": "INTERNAL",
+                "subnetwork": "regions/us-central1/subnetworks/default",
+            })
+        );
+    }
+
+    #[test]
+    fn row_shows_scope_and_users() {
+        let address: Address = serde_json::from_str(
+            r#"{
+                "name": "web-ip",
+                "address": "34.1.2.3",
+                "addressType": "EXTERNAL",
+                "status": "IN_USE",
+                "region": "https://x/regions/us-central1",
+                "users": ["https://x/zones/us-central1-a/instances/web"]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            address.row(),
+            [
+                "web-ip",
+                "34.1.2.3",
+                "EXTERNAL",
+                "us-central1",
+                "IN_USE",
+                "web"
+            ]
+        );
+        assert_eq!(Address::default().scope(), "global");
+    }
+}
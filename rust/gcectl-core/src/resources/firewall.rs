@@ -0,0 +1,532 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::short_name;
+use crate::output::{Details, Render};
+
+/// Addresses IAP TCP forwarding connects to instances from.
+pub const IAP_SOURCE_RANGE: &str = "35.235.240.0/20";
+
+/// A VPC firewall rule.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Firewall {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // full URL of the network
+    #[serde(default)]
+    pub network: String,
+    #[serde(default)]
+    pub priority: u32,
+    #[serde(default)]
+    pub direction: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_ranges: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub destination_ranges: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub target_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub target_service_accounts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed: Vec<Allowed>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied: Vec<Allowed>,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_timestamp: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A protocol and, for TCP/UDP-like protocols, the ports it applies to,
+/// written `tcp:22`, `tcp:8000-8080`, or just `icmp`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Allowed {
+    #[serde(rename = "IPProtocol")]
+    pub protocol: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<String>,
+}
+
+impl FromStr for Allowed {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (protocol, ports) = match s.split_once(':') {
+            Some((protocol, ports)) => (protocol, Some(ports)),
+            None => (s, None),
+        };
+        if protocol.is_empty() {
+            bail!("expected PROTOCOL[:PORTS], got `{s}`");
+        }
+        let ports = match ports {
+            Some(ports) => {
+                if !is_port_range(ports) {
+                    bail!("invalid port or range `{ports}` in `{s}`");
+                }
+                vec![ports.to_string()]
+            }
+            None => Vec::new(),
+        };
+        Ok(Self {
+            protocol: protocol.to_ascii_lowercase(),
+            ports,
+        })
+    }
+}
+
+impl Allowed {
+    /// Whether the entry covers `port` of `protocol`.
+    fn covers(&self, protocol: &str, port: u16) -> bool {
+        let in_range = |ports: &str| match ports.split_once('-') {
+            Some((low, high)) => {
+                low.parse().is_ok_and(|low: u16| low <= port)
+                    && high.parse().is_ok_and(|high: u16| port <= high)
+            }
+            None => ports.parse() == Ok(port),
+        };
+        (self.protocol == protocol || self.protocol == "all")
+            && (self.ports.is_empty() || self.ports.iter().any(|p| in_range(p)))
+    }
+}
+
+fn is_port_range(ports: &str) -> bool {
+    let valid = |port: &str| port.parse::<u16>().is_ok_and(|p| p > 0);
+    match ports.split_once('-') {
+        Some((low, high)) => {
+            valid(low) && valid(high) && low.parse::<u16>().ok() <= high.parse::<u16>().ok()
+        }
+        None => valid(ports),
+    }
+}
+
+impl fmt::Display for Allowed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ports.is_empty() {
+            true => write!(f, "{}", self.protocol),
+            false => write!(f, "{}:{}", self.protocol, self.ports.join(",")),
+        }
+    }
+}
+
+/// Merges entries for the same protocol, as the API expects one entry per
+/// protocol. A protocol given without ports covers every port.
+pub fn group_allowed(entries: &[Allowed]) -> Vec<Allowed> {
+    let mut grouped: Vec<Allowed> = Vec::new();
+    for entry in entries {
+        match grouped.iter_mut().find(|g| g.protocol == entry.protocol) {
+            Some(existing) if existing.ports.is_empty() || entry.ports.is_empty() => {
+                existing.ports.clear();
+            }
+            Some(existing) => existing.ports.extend(entry.ports.iter().cloned()),
+            None => grouped.push(entry.clone()),
+        }
+    }
+    grouped
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum Direction {
+    Ingress,
+    Egress,
+}
+
+impl Direction {
+    pub fn api_name(self) -> &'static str {
+        match self {
+            Self::Ingress => "INGRESS",
+            Self::Egress => "EGRESS",
+        }
+    }
+}
+
+/// Fields of a firewall rule to set; unset fields are left out of the
+/// request, so the same shape serves inserts and patches.
+#[derive(Debug, Clone, Default)]
+pub struct FirewallSpec {
+    pub description: Option<String>,
+    pub network: Option<String>,
+    pub direction: Option<Direction>,
+    pub priority: Option<u32>,
+    pub source_ranges: Vec<String>,
+    pub destination_ranges: Vec<String>,
+    pub source_tags: Vec<String>,
+    pub target_tags: Vec<String>,
+    pub allow: Vec<Allowed>,
+}
+
+impl FirewallSpec {
+    pub fn is_empty(&self) -> bool {
+        self.to_body().as_object().is_none_or(Map::is_empty)
+    }
+
+    /// Request body for `firewalls.insert` (with `name` added) or
+    /// `firewalls.patch`.
+    pub fn to_body(&self) -> Value {
+        let mut body = json!({});
+        if let Some(description) = &self.description {
+            body["description"] = json!(description);
+        }
+        if let Some(network) = &self.network {
+            body["network"] = json!(format!("global/networks/{network}"));
+        }
+        if let Some(direction) = self.direction {
+            body["direction"] = json!(direction.api_name());
+        }
+        if let Some(priority) = self.priority {
+            body["priority"] = json!(priority);
+        }
+        let lists = [
+            ("sourceRanges", &self.source_ranges),
+            ("destinationRanges", &self.destination_ranges),
+            ("sourceTags", &self.source_tags),
+            ("targetTags", &self.target_tags),
+        ];
+        for (key, values) in lists {
+            if !values.is_empty() {
+                body[key] = json!(values);
+            }
+        }
+        if !self.allow.is_empty() {
+            body["allowed"] = json!(group_allowed(&self.allow));
+        }
+        body
+    }
+}
+
+impl Firewall {
+    pub fn network_name(&self) -> &str {
+        short_name(&self.network)
+    }
+
+    /// `allow tcp:22` or `deny all`, summarizing the rule's action.
+    pub fn action(&self) -> String {
+        let (verb, entries) = match self.denied.is_empty() {
+            true => ("allow", &self.allowed),
+            false => ("deny", &self.denied),
+        };
+        let entries: Vec<String> = entries.iter().map(Allowed::to_string).collect();
+        format!("{verb} {}", entries.join(" "))
+    }
+
+    pub fn is_deny(&self) -> bool {
+        !self.denied.is_empty()
+    }
+
+    /// Whether the rule applies to an instance with network `tags` running
+    /// as one of `service_accounts`; a rule without targets applies to all.
+    pub fn targets(&self, tags: &[String], service_accounts: &[String]) -> bool {
+        if self.target_tags.is_empty() && self.target_service_accounts.is_empty() {
+            return true;
+        }
+        self.target_tags.iter().any(|t| tags.contains(t))
+            || self
+                .target_service_accounts
+                .iter()
+                .any(|a| service_accounts.contains(a))
+    }
+
+    /// Whether every address of `range` is among the rule's source ranges.
+    pub fn admits(&self, range: &str) -> bool {
+        self.source_ranges.iter().any(|r| cidr_contains(r, range))
+    }
+
+    fn covers_tcp(&self, port: u16) -> bool {
+        self.allowed
+            .iter()
+            .chain(&self.denied)
+            .any(|entry| entry.covers("tcp", port))
+    }
+
+    /// Source ranges or tags for ingress rules, destinations for egress.
+    fn peers(&self) -> Vec<String> {
+        match self.direction.as_str() {
+            "EGRESS" => self.destination_ranges.clone(),
+            _ => self
+                .source_ranges
+                .iter()
+                .chain(&self.source_tags)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// Incoming TCP traffic to one port of an instance, as the firewall rules
+/// of its network see it.
+#[derive(Debug, Clone, Copy)]
+pub struct Ingress<'a> {
+    // full URL of the instance's network
+    pub network: &'a str,
+    pub tags: &'a [String],
+    pub service_accounts: &'a [String],
+    pub port: u16,
+}
+
+impl Ingress<'_> {
+    /// The rule deciding whether traffic from `source`, or from any source
+    /// when `None`, reaches the port: the enabled rule with the lowest
+    /// priority number that covers it, deny before allow on a tie. `None`
+    /// means the implied rule denying all ingress applies.
+    pub fn deciding<'r>(
+        &self,
+        rules: &'r [Firewall],
+        source: Option<&str>,
+    ) -> Option<&'r Firewall> {
+        self.candidates(rules)
+            .filter(|rule| !rule.disabled && rule.targets(self.tags, self.service_accounts))
+            .filter(|rule| source.is_none_or(|source| rule.admits(source)))
+            .min_by_key(|rule| (rule.priority, !rule.is_deny()))
+    }
+
+    /// Allow rules for the port that would help if they applied to the
+    /// instance, each saying why they do not.
+    pub fn near_misses(&self, rules: &[Firewall]) -> Vec<String> {
+        self.candidates(rules)
+            .filter(|rule| !rule.is_deny())
+            .filter_map(|rule| {
+                let targeted = rule.targets(self.tags, self.service_accounts);
+                match (rule.disabled, targeted) {
+                    (true, true) => Some(format!("{} would allow it but is disabled", rule.name)),
+                    (_, false) => {
+                        let targets: Vec<&str> = rule
+                            .target_tags
+                            .iter()
+                            .chain(&rule.target_service_accounts)
+                            .map(String::as_str)
+                            .collect();
+                        Some(format!(
+                            "{} only applies to {}, which the instance lacks",
+                            rule.name,
+                            targets.join(", ")
+                        ))
+                    }
+                    (false, true) => None,
+                }
+            })
+            .collect()
+    }
+
+    // ingress rules of the network covering the port
+    fn candidates<'r>(&self, rules: &'r [Firewall]) -> impl Iterator<Item = &'r Firewall> {
+        rules.iter().filter(move |rule| {
+            rule.direction != "EGRESS"
+                && same_network(&rule.network, self.network)
+                && rule.covers_tcp(self.port)
+        })
+    }
+}
+
+// network URLs may come with or without the API prefix
+fn same_network(a: &str, b: &str) -> bool {
+    let path = |url: &str| url.find("projects/").map_or(url, |i| &url[i..]).to_string();
+    path(a) == path(b)
+}
+
+/// Whether the IPv4 range `outer` contains all of `inner`; both are CIDR
+/// blocks or single addresses.
+fn cidr_contains(outer: &str, inner: &str) -> bool {
+    let parse = |cidr: &str| -> Option<(u32, u32)> {
+        let (ip, len) = cidr.split_once('/').unwrap_or((cidr, "32"));
+        let ip: std::net::Ipv4Addr = ip.parse().ok()?;
+        let len: u32 = len.parse().ok().filter(|&len| len <= 32)?;
+        Some((u32::from(ip), len))
+    };
+    let (Some((outer, outer_len)), Some((inner, inner_len))) = (parse(outer), parse(inner)) else {
+        return false;
+    };
+    let mask = u32::MAX.checked_shl(32 - outer_len).unwrap_or(0);
+    outer_len <= inner_len && outer & mask == inner & mask
+}
+
+impl Render for Firewall {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Name",
+            "Network",
+            "Direction",
+            "Priority",
+            "Action",
+            "Peers",
+            "Target-Tags",
+            "Disabled",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.network_name().to_string(),
+            self.direction.clone(),
+            self.priority.to_string(),
+            self.action(),
+            self.peers().join(","),
+            self.target_tags.join(","),
+            self.disabled.to_string(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Id", self.id.as_deref())
+            .field_opt("Description", self.description.as_deref())
+            .field("Network", self.network_name())
+            .field("Direction", &self.direction)
+            .field("Priority", self.priority.to_string())
+            .field("Action", self.action())
+            .field("Source-Ranges", self.source_ranges.join(", "))
+            .field("Destination-Ranges", self.destination_ranges.join(", "))
+            .field("Source-Tags", self.source_tags.join(", "))
+            .field("Target-Tags", self.target_tags.join(", "))
+            .field("Disabled", self.disabled.to_string())
+            .field_opt("Created", self.creation_timestamp.as_deref());
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(specs: &[&str]) -> Vec<Allowed> {
+        specs.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn parses_protocols_and_ports() {
+        assert_eq!(
+            "TCP:8000-8080".parse::<Allowed>().unwrap(),
+            Allowed {
+                protocol: "tcp".into(),
+                ports: vec!["8000-8080".into()],
+            }
+        );
+        assert!("icmp".parse::<Allowed>().unwrap().ports.is_empty());
+        for bad in ["tcp:", "tcp:0", "tcp:70000", "tcp:90-80", ":22"] {
+            assert!(bad.parse::<Allowed>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn groups_ports_by_protocol() {
+        let grouped = group_allowed(&allowed(&["tcp:22", "udp:53", "tcp:443", "icmp"]));
+        assert_eq!(grouped.len(), 3);
+        assert_eq!(grouped[0].to_string(), "tcp:22,443");
+        // a bare protocol opens every port
+        let grouped = group_allowed(&allowed(&["tcp:22", "tcp"]));
+        assert_eq!(grouped[0].to_string(), "tcp");
+    }
+
+    #[test]
+    fn finds_the_rule_deciding_ingress() {
+        let rule = |body: Value| -> Firewall { serde_json::from_value(body).unwrap() };
+        let network = "https://x/compute/v1/projects/p/global/networks/default";
+        let rules = [
+            rule(json!({
+                "name": "allow-ssh-iap", "network": network, "priority": 1000,
+                "sourceRanges": ["35.235.240.0/20"],
+                "allowed": [{"IPProtocol": "tcp", "ports": ["22"]}],
+            })),
+            rule(json!({
+                "name": "allow-db", "network": network, "priority": 1000,
+                "sourceRanges": ["0.0.0.0/0"], "targetTags": ["db"],
+                "allowed": [{"IPProtocol": "tcp", "ports": ["5000-6000"]}],
+            })),
+            rule(json!({
+                "name": "deny-db", "network": network, "priority": 900,
+                "sourceRanges": ["203.0.113.0/24"], "targetTags": ["db"],
+                "denied": [{"IPProtocol": "all"}],
+            })),
+            rule(json!({
+                "name": "other-network", "network": "projects/p/global/networks/lab",
+                "priority": 0, "sourceRanges": ["0.0.0.0/0"],
+                "allowed": [{"IPProtocol": "tcp"}],
+            })),
+        ];
+        let web = ["web".to_string()];
+        let ingress = Ingress {
+            network: "projects/p/global/networks/default",
+            tags: &web,
+            service_accounts: &[],
+            port: 5432,
+        };
+        assert!(ingress.deciding(&rules, Some(IAP_SOURCE_RANGE)).is_none());
+        assert_eq!(
+            ingress.near_misses(&rules),
+            ["allow-db only applies to db, which the instance lacks"]
+        );
+
+        let db = ["db".to_string()];
+        let ingress = Ingress {
+            tags: &db,
+            ..ingress
+        };
+        let decide = |source| ingress.deciding(&rules, source).map(|r| r.name.as_str());
+        assert_eq!(decide(Some(IAP_SOURCE_RANGE)), Some("allow-db"));
+        assert_eq!(decide(Some("203.0.113.7")), Some("deny-db"));
+        assert_eq!(decide(None), Some("deny-db"));
+        let ssh = Ingress {
+            port: 22,
+            ..ingress
+        };
+        assert_eq!(
+            ssh.deciding(&rules, Some(IAP_SOURCE_RANGE))
+                .map(|r| r.name.as_str()),
+            Some("allow-ssh-iap")
+        );
+        assert!(ssh.deciding(&rules, Some("198.51.100.1")).is_none());
+    }
+
+    #[test]
+    fn spec_only_sends_given_fields() {
+        let spec = FirewallSpec {
+            priority: Some(900),
+            target_tags: vec!["web".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            spec.to_body(),
+            json!({"priority": 900, "targetTags": ["web"]})
+        );
+        assert!(FirewallSpec::default().is_empty());
+    }
+
+    #[test]
+    fn row_summarizes_rule() {
+        let rule: Firewall = serde_json::from_str(
+            r#"{
+                "name": "allow-ssh",
+                "network": "https://x/global/networks/default",
+                "direction": "INGRESS",
+                "priority": 1000,
+                "sourceRanges": ["35.235.240.0/20"],
+                "allowed": [{"IPProtocol": "tcp", "ports": ["22"]}]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            rule.row()[1..6],
+            [
+                "default",
+                "INGRESS",
+                "1000",
+                "allow tcp:22",
+                "35.235.240.0/20"
+            ]
+        );
+    }
+}
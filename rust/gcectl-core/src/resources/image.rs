@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::short_name;
+use crate::output::{Details, Render};
+
+/// A boot disk image.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Image {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub family: Option<String>,
+    #[serde(default)]
+    pub status: String,
+    // int64 fields are strings in the REST API
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_size_gb: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_size_bytes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_disk: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<Deprecation>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_timestamp: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deprecation {
+    #[serde(default)]
+    pub state: String,
+    // full URL of the suggested replacement image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Deprecation state that `images deprecate` can set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum DeprecationState {
+    /// Clear the deprecation
+    Active,
+    /// Still usable, with a warning
+    Deprecated,
+    /// New uses are rejected
+    Obsolete,
+    /// Marked as deleted; new uses are rejected
+    Deleted,
+}
+
+impl DeprecationState {
+    pub fn api_name(self) -> &'static str {
+        match self {
+            Self::Active => "ACTIVE",
+            Self::Deprecated => "DEPRECATED",
+            Self::Obsolete => "OBSOLETE",
+            Self::Deleted => "DELETED",
+        }
+    }
+}
+
+impl Image {
+    /// Deprecation state, or `None` for active images.
+    pub fn deprecation_state(&self) -> Option<&str> {
+        self.deprecated
+            .as_ref()
+            .map(|d| d.state.as_str())
+            .filter(|state| !state.is_empty() && *state != "ACTIVE")
+    }
+
+    /// Request body for `images.insert` from a zonal disk.
+    pub fn create_request(
+        name: &str,
+        source_disk: &str,
+        family: Option<&str>,
+        description: Option<&str>,
+        labels: &[(String, String)],
+    ) -> Value {
+        let mut body = json!({ "name": name, "sourceDisk": source_disk });
+        if let Some(family) = family {
+            body["family"] = json!(family);
+        }
+        if let Some(description) = description {
+            body["description"] = json!(description);
+        }
+        if !labels.is_empty() {
+            let labels: BTreeMap<_, _> = labels.iter().cloned().collect();
+            body["labels"] = json!(labels);
+        }
+        body
+    }
+
+    /// Request body for `images.deprecate`.
+    pub fn deprecate_request(
+        project: &str,
+        state: DeprecationState,
+        replacement: Option<&str>,
+    ) -> Value {
+        let mut body = json!({ "state": state.api_name() });
+        if let Some(replacement) = replacement {
+            body["replacement"] = json!(format!("projects/{project}/global/images/{replacement}"));
+        }
+        body
+    }
+}
+
+impl Render for Image {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Family", "Disk-Size-GB", "Status", "Deprecated"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.family.clone().unwrap_or_else(|| "-".to_string()),
+            self.disk_size_gb.clone().unwrap_or_else(|| "-".to_string()),
+            self.status.clone(),
+            self.deprecation_state().unwrap_or("-").to_string(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Id", self.id.as_deref())
+            .field_opt("Description", self.description.as_deref())
+            .field_opt("Family", self.family.as_deref())
+            .field("Status", &self.status)
+            .field_opt("Disk-Size-GB", self.disk_size_gb.as_deref())
+            .field_opt("Archive-Size-Bytes", self.archive_size_bytes.as_deref())
+            .field_opt("Source-Disk", self.source_disk.as_deref().map(short_name))
+            .field_opt("Created", self.creation_timestamp.as_deref());
+        if let Some(deprecated) = &self.deprecated {
+            details.group("Deprecation", |group| {
+                group.field("State", &deprecated.state).field_opt(
+                    "Replacement",
+                    deprecated.replacement.as_deref().map(short_name),
+                );
+            });
+        }
+        details.group("Labels", |group| {
+            for (key, value) in &self.labels {
+                group.field(key, value);
+            }
+        });
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_images_are_not_deprecated() {
+        let image: Image = serde_json::from_str(
+            r#"{"name": "debian-12-v1", "family": "debian-12", "status": "READY",
+                "deprecated": {"state": "ACTIVE"}}"#,
+        )
+        .unwrap();
+        assert_eq!(image.deprecation_state(), None);
+        assert_eq!(image.row()[4], "-");
+
+        let old: Image = serde_json::from_str(
+            r#"{"name": "debian-11-v1", "deprecated": {"state": "DEPRECATED"}}"#,
+        )
+        .unwrap();
+        assert_eq!(old.deprecation_state(), Some("DEPRECATED"));
+    }
+
+    #[test]
+    fn deprecate_request_points_at_replacement() {
+        assert_eq!(
+            Image::deprecate_request("p", DeprecationState::Deprecated, Some("golden-v2")),
+            json!({
+                "state": "DEPRECATED",
+                "replacement": "projects/p/global/images/golden-v2"
+            })
+        );
+        assert_eq!(
+            Image::deprecate_request("p", DeprecationState::Active, None),
+            json!({"state": "ACTIVE"})
+        );
+    }
+}
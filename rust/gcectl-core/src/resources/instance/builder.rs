@@ -0,0 +1,610 @@
+//! Assembles the request body for `instances.insert`.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+
+use serde_json::{Value, json};
+
+use super::{LINUX_STARTUP_SCRIPT, ShieldedInstanceConfig};
+use crate::resources::node::NODE_GROUP_AFFINITY_KEY;
+use crate::resources::{Accelerator, EncryptionKey, region_of};
+
+pub const DEFAULT_MACHINE_TYPE: &str = "e2-medium";
+pub const DEFAULT_IMAGE_PROJECT: &str = "debian-cloud";
+pub const DEFAULT_IMAGE_FAMILY: &str = "debian-12";
+pub const DEFAULT_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+/// Metadata key recording the `--max-lifetime` an instance was created with.
+pub const MAX_LIFETIME_KEY: &str = "gcectl-max-lifetime";
+
+/// Where the boot disk image comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageSource {
+    /// Latest non-deprecated image of a family.
+    Family { project: String, family: String },
+    /// One specific image.
+    Image { project: String, image: String },
+}
+
+impl ImageSource {
+    /// Source selected by `--image`/`--image-family` style flags; neither
+    /// means the default family.
+    pub fn from_flags(project: String, image: Option<String>, family: Option<String>) -> Self {
+        match image {
+            Some(image) => Self::Image { project, image },
+            None => Self::Family {
+                project,
+                family: family.unwrap_or_else(|| DEFAULT_IMAGE_FAMILY.to_string()),
+            },
+        }
+    }
+
+    /// Parses an image path such as `projects/P/global/images/family/F` or a
+    /// full image URL; the inverse of [`ImageSource::url`].
+    pub fn from_path(path: &str) -> Option<Self> {
+        let path = &path[path.find("projects/")?..];
+        let rest = path.strip_prefix("projects/")?;
+        let (project, rest) = rest.split_once("/global/images/")?;
+        let project = project.to_string();
+        Some(match rest.strip_prefix("family/") {
+            Some(family) => Self::Family {
+                project,
+                family: family.to_string(),
+            },
+            None => Self::Image {
+                project,
+                image: rest.to_string(),
+            },
+        })
+    }
+
+    /// Project-relative resource path of the image.
+    pub fn url(&self) -> String {
+        match self {
+            Self::Family { project, family } => {
+                format!("projects/{project}/global/images/family/{family}")
+            }
+            Self::Image { project, image } => format!("projects/{project}/global/images/{image}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Provisioning {
+    #[default]
+    Standard,
+    Preemptible,
+    Spot,
+}
+
+/// Builder for a new instance; unset options fall back to the API defaults.
+#[derive(Debug, Clone)]
+pub struct InstanceBuilder {
+    name: String,
+    zone: String,
+    machine_type: String,
+    image: ImageSource,
+    boot_disk_size_gb: Option<u64>,
+    boot_disk_type: Option<String>,
+    boot_disk_kms_key: Option<String>,
+    network: Option<String>,
+    subnet: Option<String>,
+    external_ip: bool,
+    labels: BTreeMap<String, String>,
+    metadata: Vec<(String, String)>,
+    provisioning: Provisioning,
+    service_account: Option<String>,
+    scopes: Vec<String>,
+    accelerators: Vec<Accelerator>,
+    shielded: ShieldedInstanceConfig,
+    confidential_compute: bool,
+    node_group: Option<String>,
+}
+
+impl InstanceBuilder {
+    pub fn new(name: impl Into<String>, zone: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            zone: zone.into(),
+            machine_type: DEFAULT_MACHINE_TYPE.to_string(),
+            image: ImageSource::Family {
+                project: DEFAULT_IMAGE_PROJECT.to_string(),
+                family: DEFAULT_IMAGE_FAMILY.to_string(),
+            },
+            boot_disk_size_gb: None,
+            boot_disk_type: None,
+            boot_disk_kms_key: None,
+            network: None,
+            subnet: None,
+            external_ip: true,
+            labels: BTreeMap::new(),
+            metadata: Vec::new(),
+            provisioning: Provisioning::Standard,
+            service_account: None,
+            scopes: vec![DEFAULT_SCOPE.to_string()],
+            accelerators: Vec::new(),
+            shielded: ShieldedInstanceConfig::default(),
+            confidential_compute: false,
+            node_group: None,
+        }
+    }
+
+    pub fn machine_type(mut self, machine_type: impl Into<String>) -> Self {
+        self.machine_type = machine_type.into();
+        self
+    }
+
+    pub fn image(mut self, image: ImageSource) -> Self {
+        self.image = image;
+        self
+    }
+
+    pub fn boot_disk_size_gb(mut self, size: Option<u64>) -> Self {
+        self.boot_disk_size_gb = size;
+        self
+    }
+
+    pub fn boot_disk_type(mut self, disk_type: Option<String>) -> Self {
+        self.boot_disk_type = disk_type;
+        self
+    }
+
+    /// Encrypts the boot disk with Cloud KMS key `key` instead of a
+    /// Google-managed one.
+    pub fn boot_disk_kms_key(mut self, key: Option<String>) -> Self {
+        self.boot_disk_kms_key = key;
+        self
+    }
+
+    pub fn network(mut self, network: Option<String>) -> Self {
+        self.network = network;
+        self
+    }
+
+    pub fn subnet(mut self, subnet: Option<String>) -> Self {
+        self.subnet = subnet;
+        self
+    }
+
+    pub fn external_ip(mut self, external_ip: bool) -> Self {
+        self.external_ip = external_ip;
+        self
+    }
+
+    pub fn labels(mut self, labels: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.labels.extend(labels);
+        self
+    }
+
+    pub fn metadata(mut self, items: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.metadata.extend(items);
+        self
+    }
+
+    pub fn provisioning(mut self, provisioning: Provisioning) -> Self {
+        self.provisioning = provisioning;
+        self
+    }
+
+    pub fn service_account(mut self, email: Option<String>) -> Self {
+        self.service_account = email;
+        self
+    }
+
+    pub fn scopes(mut self, scopes: Vec<String>) -> Self {
+        self.scopes = scopes;
+        self
+    }
+
+    pub fn accelerators(mut self, accelerators: Vec<Accelerator>) -> Self {
+        self.accelerators = accelerators;
+        self
+    }
+
+    pub fn shielded(mut self, shielded: ShieldedInstanceConfig) -> Self {
+        self.shielded = shielded;
+        self
+    }
+
+    pub fn confidential_compute(mut self, enabled: bool) -> Self {
+        self.confidential_compute = enabled;
+        self
+    }
+
+    /// Places the instance on the sole-tenant nodes of `group`.
+    pub fn node_group(mut self, group: Option<String>) -> Self {
+        self.node_group = group;
+        self
+    }
+
+    /// Has the instance halt itself, and so stop, `lifetime` after every
+    /// boot. The timer is prepended to the startup script, which therefore
+    /// has to be a shell script given inline.
+    pub fn max_lifetime(mut self, lifetime: Duration) -> Result<Self> {
+        let keys = LINUX_STARTUP_SCRIPT;
+        if self.metadata.iter().any(|(key, _)| key == keys.url) {
+            bail!(
+                "--max-lifetime cannot be combined with a {} script",
+                keys.url
+            );
+        }
+        let minutes = lifetime.as_secs().div_ceil(60).max(1);
+        let timer =
+            format!("shutdown -h +{minutes} 'gcectl: max lifetime of {minutes} minutes reached'\n");
+        let script = match self.metadata.iter().position(|(key, _)| key == keys.inline) {
+            Some(i) => with_timer(&self.metadata.remove(i).1, &timer)?,
+            None => format!("#!/bin/sh\n{timer}"),
+        };
+        self.metadata.push((keys.inline.to_string(), script));
+        self.metadata
+            .push((MAX_LIFETIME_KEY.to_string(), format!("{minutes}m")));
+        Ok(self)
+    }
+
+    /// The JSON body for `POST projects/{project}/zones/{zone}/instances`.
+    pub fn build(&self) -> Value {
+        let zone = &self.zone;
+        let mut body = self.properties(
+            format!("zones/{zone}/machineTypes/{}", self.machine_type),
+            self.boot_disk_type
+                .as_ref()
+                .map(|disk_type| format!("zones/{zone}/diskTypes/{disk_type}")),
+            region_of(zone),
+        );
+        body["name"] = json!(self.name);
+        if let Some(accelerators) = body["guestAccelerators"].as_array_mut() {
+            for accelerator in accelerators {
+                let name = accelerator["acceleratorType"].as_str().unwrap_or_default();
+                accelerator["acceleratorType"] =
+                    json!(format!("zones/{zone}/acceleratorTypes/{name}"));
+            }
+        }
+        body
+    }
+
+    /// The JSON body for `POST projects/{project}/global/instanceTemplates`.
+    ///
+    /// Templates are not tied to a zone, so machine and disk types are given
+    /// by name and the builder's zone is not used; a subnet needs `region`.
+    pub fn build_template(&self, region: Option<&str>) -> Value {
+        json!({
+            "name": self.name,
+            "properties": self.properties(
+                self.machine_type.clone(),
+                self.boot_disk_type.clone(),
+                region.unwrap_or_default(),
+            ),
+        })
+    }
+
+    /// Instance properties shared by instances and templates. Accelerator
+    /// types are given by name; `build` qualifies them with the zone.
+    fn properties(&self, machine_type: String, disk_type: Option<String>, region: &str) -> Value {
+        let mut initialize_params = json!({ "sourceImage": self.image.url() });
+        if let Some(size) = self.boot_disk_size_gb {
+            initialize_params["diskSizeGb"] = json!(size.to_string());
+        }
+        if let Some(disk_type) = disk_type {
+            initialize_params["diskType"] = json!(disk_type);
+        }
+
+        let mut nic = json!({});
+        if let Some(network) = &self.network {
+            nic["network"] = json!(format!("global/networks/{network}"));
+        }
+        if let Some(subnet) = &self.subnet {
+            nic["subnetwork"] = json!(format!("regions/{region}/subnetworks/{subnet}"));
+        }
+        if self.external_ip {
+            nic["accessConfigs"] = json!([{ "name": "External NAT", "type": "ONE_TO_ONE_NAT" }]);
+        }
+
+        let mut scheduling = match self.provisioning {
+            Provisioning::Standard => json!({}),
+            Provisioning::Preemptible => json!({
+                "preemptible": true,
+                "automaticRestart": false,
+                "onHostMaintenance": "TERMINATE",
+            }),
+            Provisioning::Spot => json!({
+                "provisioningModel": "SPOT",
+                "instanceTerminationAction": "STOP",
+                "automaticRestart": false,
+                "onHostMaintenance": "TERMINATE",
+            }),
+        };
+
+        // GPU and confidential hosts cannot live-migrate during maintenance
+        if !self.accelerators.is_empty() || self.confidential_compute {
+            scheduling["onHostMaintenance"] = json!("TERMINATE");
+        }
+        if let Some(group) = &self.node_group {
+            scheduling["nodeAffinities"] = json!([{
+                "key": NODE_GROUP_AFFINITY_KEY,
+                "operator": "IN",
+                "values": [group],
+            }]);
+        }
+
+        let mut body = json!({
+            "machineType": machine_type,
+            "disks": [{
+                "boot": true,
+                "autoDelete": true,
+                "initializeParams": initialize_params,
+            }],
+            "networkInterfaces": [nic],
+            "scheduling": scheduling,
+        });
+        if let Some(key) = &self.boot_disk_kms_key {
+            body["disks"][0]["diskEncryptionKey"] = EncryptionKey::kms(key);
+        }
+        if !self.labels.is_empty() {
+            body["labels"] = json!(self.labels);
+        }
+        if !self.metadata.is_empty() {
+            let items: Vec<Value> = self
+                .metadata
+                .iter()
+                .map(|(key, value)| json!({ "key": key, "value": value }))
+                .collect();
+            body["metadata"] = json!({ "items": items });
+        }
+        if let Some(email) = &self.service_account {
+            body["serviceAccounts"] = json!([{ "email": email, "scopes": self.scopes }]);
+        }
+        if self.shielded != ShieldedInstanceConfig::default() {
+            body["shieldedInstanceConfig"] = json!(self.shielded);
+        }
+        if self.confidential_compute {
+            body["confidentialInstanceConfig"] = json!({ "enableConfidentialCompute": true });
+        }
+        if !self.accelerators.is_empty() {
+            let accelerators: Vec<Value> = self
+                .accelerators
+                .iter()
+                .map(|a| {
+                    json!({
+                        "acceleratorType": a.accelerator_type,
+                        "acceleratorCount": a.count,
+                    })
+                })
+                .collect();
+            body["guestAccelerators"] = json!(accelerators);
+        }
+        body
+    }
+}
+
+/// `script` with `timer` run first, after any shebang line.
+fn with_timer(script: &str, timer: &str) -> Result<String> {
+    let Some(rest) = script.strip_prefix("#!") else {
+        return Ok(format!("{timer}{script}"));
+    };
+    let (shebang, body) = rest.split_once('\n').unwrap_or((rest, ""));
+    let interpreter = shebang.split_whitespace().last().unwrap_or_default();
+    if !interpreter.ends_with("sh") {
+        bail!("--max-lifetime needs a shell startup script, not `#!{shebang}`");
+    }
+    Ok(format!("#!{shebang}\n{timer}{body}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_paths_round_trip() {
+        let family = ImageSource::Family {
+            project: "debian-cloud".to_string(),
+            family: "debian-12".to_string(),
+        };
+        assert_eq!(ImageSource::from_path(&family.url()), Some(family));
+        assert_eq!(
+            ImageSource::from_path(
+                "https://www.googleapis.com/compute/v1/projects/p/global/images/img-1"
+            ),
+            Some(ImageSource::Image {
+                project: "p".to_string(),
+                image: "img-1".to_string(),
+            })
+        );
+        assert_eq!(ImageSource::from_path("debian-12"), None);
+    }
+
+    #[test]
+    fn defaults() {
+        let body = InstanceBuilder::new("vm", "asia-northeast1-a").build();
+        assert_eq!(body["name"], "vm");
+        assert_eq!(
+            body["machineType"],
+            "zones/asia-northeast1-a/machineTypes/e2-medium"
+        );
+        assert_eq!(
+            body["disks"][0]["initializeParams"]["sourceImage"],
+            "projects/debian-cloud/global/images/family/debian-12"
+        );
+        assert_eq!(
+            body["networkInterfaces"][0]["accessConfigs"][0]["type"],
+            "ONE_TO_ONE_NAT"
+        );
+        assert!(body.get("labels").is_none());
+        assert!(body.get("serviceAccounts").is_none());
+    }
+
+    #[test]
+    fn full_configuration() {
+        let body = InstanceBuilder::new("gpu-box", "us-central1-a")
+            .machine_type("n1-standard-8")
+            .image(ImageSource::Image {
+                project: "my-project".into(),
+                image: "golden-v3".into(),
+            })
+            .boot_disk_size_gb(Some(200))
+            .boot_disk_type(Some("pd-ssd".into()))
+            .network(Some("dev".into()))
+            .subnet(Some("dev-us".into()))
+            .external_ip(false)
+            .labels([("env".to_string(), "dev".to_string())])
+            .metadata([("enable-oslogin".to_string(), "TRUE".to_string())])
+            .provisioning(Provisioning::Spot)
+            .service_account(Some("sa@p.iam.gserviceaccount.com".into()))
+            .boot_disk_kms_key(Some(
+                "projects/p/locations/us/keyRings/r/cryptoKeys/k".into(),
+            ))
+            .build();
+
+        let disk = &body["disks"][0]["initializeParams"];
+        assert_eq!(
+            disk["sourceImage"],
+            "projects/my-project/global/images/golden-v3"
+        );
+        assert_eq!(disk["diskSizeGb"], "200");
+        assert_eq!(disk["diskType"], "zones/us-central1-a/diskTypes/pd-ssd");
+        assert_eq!(
+            body["disks"][0]["diskEncryptionKey"]["kmsKeyName"],
+            "projects/p/locations/us/keyRings/r/cryptoKeys/k"
+        );
+        let nic = &body["networkInterfaces"][0];
+        assert_eq!(nic["network"], "global/networks/dev");
+        assert_eq!(nic["subnetwork"], "regions/us-central1/subnetworks/dev-us");
+        assert!(nic.get("accessConfigs").is_none());
+        assert_eq!(body["labels"]["env"], "dev");
+        assert_eq!(body["metadata"]["items"][0]["key"], "enable-oslogin");
+        assert_eq!(body["scheduling"]["provisioningModel"], "SPOT");
+        assert_eq!(body["serviceAccounts"][0]["scopes"][0], DEFAULT_SCOPE);
+    }
+
+    #[test]
+    fn node_group_becomes_a_node_affinity() {
+        let body = InstanceBuilder::new("sql-1", "us-central1-a")
+            .node_group(Some("sql-nodes".into()))
+            .build();
+        assert_eq!(
+            body["scheduling"]["nodeAffinities"],
+            json!([{
+                "key": "compute.googleapis.com/node-group-name",
+                "operator": "IN",
+                "values": ["sql-nodes"],
+            }])
+        );
+    }
+
+    #[test]
+    fn template_properties_use_bare_type_names() {
+        let body = InstanceBuilder::new("web-v2", "")
+            .boot_disk_type(Some("pd-ssd".into()))
+            .subnet(Some("web".into()))
+            .build_template(Some("us-central1"));
+        assert_eq!(body["name"], "web-v2");
+        let properties = &body["properties"];
+        assert_eq!(properties["machineType"], "e2-medium");
+        assert!(properties.get("name").is_none());
+        assert_eq!(
+            properties["disks"][0]["initializeParams"]["diskType"],
+            "pd-ssd"
+        );
+        assert_eq!(
+            properties["networkInterfaces"][0]["subnetwork"],
+            "regions/us-central1/subnetworks/web"
+        );
+    }
+
+    #[test]
+    fn accelerators_force_terminate_on_maintenance() {
+        let gpu = Accelerator {
+            accelerator_type: "nvidia-tesla-t4".into(),
+            count: 2,
+        };
+        let builder = InstanceBuilder::new("gpu", "us-central1-a").accelerators(vec![gpu]);
+        let body = builder.build();
+        assert_eq!(
+            body["guestAccelerators"][0]["acceleratorType"],
+            "zones/us-central1-a/acceleratorTypes/nvidia-tesla-t4"
+        );
+        assert_eq!(body["guestAccelerators"][0]["acceleratorCount"], 2);
+        assert_eq!(body["scheduling"]["onHostMaintenance"], "TERMINATE");
+
+        let template = builder.build_template(None);
+        assert_eq!(
+            template["properties"]["guestAccelerators"][0]["acceleratorType"],
+            "nvidia-tesla-t4"
+        );
+    }
+
+    #[test]
+    fn security_options_are_only_sent_when_set() {
+        let body = InstanceBuilder::new("vm", "us-central1-a").build();
+        assert!(body.get("shieldedInstanceConfig").is_none());
+        assert!(body.get("confidentialInstanceConfig").is_none());
+
+        let body = InstanceBuilder::new("vm", "us-central1-a")
+            .shielded(ShieldedInstanceConfig {
+                enable_secure_boot: Some(true),
+                enable_vtpm: Some(false),
+                ..Default::default()
+            })
+            .confidential_compute(true)
+            .build();
+        assert_eq!(
+            body["shieldedInstanceConfig"],
+            json!({ "enableSecureBoot": true, "enableVtpm": false })
+        );
+        assert_eq!(
+            body["confidentialInstanceConfig"]["enableConfidentialCompute"],
+            true
+        );
+        assert_eq!(body["scheduling"]["onHostMaintenance"], "TERMINATE");
+    }
+
+    #[test]
+    fn max_lifetime_prepends_a_shutdown_timer() {
+        let items = |builder: InstanceBuilder| builder.build()["metadata"]["items"].clone();
+        let items = items(
+            InstanceBuilder::new("gpu", "us-central1-a")
+                .max_lifetime(Duration::from_secs(8 * 60 * 60))
+                .unwrap(),
+        );
+        assert_eq!(
+            items[0]["value"],
+            "#!/bin/sh\nshutdown -h +480 'gcectl: max lifetime of 480 minutes reached'\n"
+        );
+        assert_eq!(
+            items[1],
+            json!({ "key": MAX_LIFETIME_KEY, "value": "480m" })
+        );
+
+        let script = "#!/usr/bin/env bash\necho hi\n";
+        let body = InstanceBuilder::new("gpu", "us-central1-a")
+            .metadata([("startup-script".to_string(), script.to_string())])
+            .max_lifetime(Duration::from_secs(90))
+            .unwrap()
+            .build();
+        let value = body["metadata"]["items"][0]["value"].as_str().unwrap();
+        assert!(value.starts_with("#!/usr/bin/env bash\nshutdown -h +2 "));
+        assert!(value.ends_with("reached'\necho hi\n"));
+    }
+
+    #[test]
+    fn max_lifetime_rejects_other_startup_scripts() {
+        let builder = |key: &str, script: &str| {
+            InstanceBuilder::new("vm", "us-central1-a")
+                .metadata([(key.to_string(), script.to_string())])
+                .max_lifetime(Duration::from_secs(3600))
+        };
+        assert!(builder("startup-script", "#!/usr/bin/python3\nprint(1)").is_err());
+        assert!(builder("startup-script-url", "gs://bucket/boot.sh").is_err());
+        assert!(builder("startup-script", "echo no shebang").is_ok());
+    }
+
+    #[test]
+    fn preemptible_scheduling() {
+        let body = InstanceBuilder::new("vm", "z-a")
+            .provisioning(Provisioning::Preemptible)
+            .build();
+        assert_eq!(body["scheduling"]["preemptible"], true);
+        assert_eq!(body["scheduling"]["onHostMaintenance"], "TERMINATE");
+    }
+}
@@ -0,0 +1,324 @@
+//! Insert bodies that copy an existing instance, for `instances create-from`.
+//!
+//! The source instance's API body is reused as is apart from what is tied
+//! to the source itself: ids, timestamps, fingerprints, and IPs are dropped,
+//! and every disk is recreated from the image or snapshot it was made from,
+//! so data written since is not copied. Zonal URLs follow the new zone.
+
+use std::collections::HashMap;
+
+use anyhow::{Result, bail};
+use serde_json::{Map, Value, json};
+
+use super::Instance;
+use crate::resources::{Disk, region_of, short_name};
+
+// output-only or per-instance fields of the API resource
+const DROPPED: &[&str] = &[
+    "kind",
+    "id",
+    "zone",
+    "selfLink",
+    "selfLinkWithId",
+    "creationTimestamp",
+    "lastStartTimestamp",
+    "lastStopTimestamp",
+    "lastSuspendedTimestamp",
+    "status",
+    "statusMessage",
+    "cpuPlatform",
+    "fingerprint",
+    "labelFingerprint",
+    "startRestricted",
+    "satisfiesPzi",
+    "satisfiesPzs",
+    "resourceStatus",
+    "hostname",
+];
+
+/// What a copy may change about its source.
+#[derive(Debug, Clone)]
+pub struct Overrides<'a> {
+    pub name: &'a str,
+    pub zone: &'a str,
+    pub machine_type: Option<&'a str>,
+    // name recreated disks after the source's own, as a move does, rather
+    // than after the copy
+    pub keep_disk_names: bool,
+}
+
+/// Insert body for a copy of `source`. `disks` maps the URLs of the
+/// source's disks to the disks themselves.
+pub fn clone_body(
+    source: &Instance,
+    disks: &HashMap<&str, &Disk>,
+    overrides: &Overrides,
+) -> Result<Value> {
+    let zone = overrides.zone;
+    let same_region = region_of(source.zone_name()) == region_of(zone);
+    let Value::Object(mut body) = serde_json::to_value(source)? else {
+        bail!("instance {} is not a JSON object", source.name);
+    };
+    for key in DROPPED {
+        body.remove(*key);
+    }
+    // region-scoped policies do not exist in another region
+    if !same_region {
+        body.remove("resourcePolicies");
+    }
+    body.insert("name".to_string(), json!(overrides.name));
+    let machine_type = overrides
+        .machine_type
+        .unwrap_or_else(|| source.machine_type_name());
+    body.insert(
+        "machineType".to_string(),
+        json!(format!("zones/{zone}/machineTypes/{machine_type}")),
+    );
+    for key in ["metadata", "tags"] {
+        if let Some(Value::Object(value)) = body.get_mut(key) {
+            value.remove("fingerprint");
+            value.remove("kind");
+        }
+    }
+
+    let copied: Vec<Value> = source
+        .disks
+        .iter()
+        .enumerate()
+        .map(|(i, attached)| {
+            if attached.extra.get("type").and_then(Value::as_str) == Some("SCRATCH") {
+                return Ok(json!({
+                    "type": "SCRATCH",
+                    "autoDelete": true,
+                    "interface": attached.extra.get("interface").cloned().unwrap_or(json!("NVME")),
+                    "initializeParams": { "diskType": format!("zones/{zone}/diskTypes/local-ssd") },
+                }));
+            }
+            let url = attached.source.as_deref().unwrap_or_default();
+            let Some(disk) = disks.get(url) else {
+                bail!(
+                    "disk {} of instance {} was not found",
+                    short_name(url),
+                    source.name
+                );
+            };
+            let mut params = Map::new();
+            params.insert(
+                "diskName".to_string(),
+                json!(match (overrides.keep_disk_names, attached.boot) {
+                    (true, _) => disk.name.clone(),
+                    (false, true) => overrides.name.to_string(),
+                    (false, false) => format!("{}-{i}", overrides.name),
+                }),
+            );
+            params.insert(
+                "diskType".to_string(),
+                json!(format!("zones/{zone}/diskTypes/{}", disk.type_name())),
+            );
+            if let Some(size) = &disk.size_gb {
+                params.insert("diskSizeGb".to_string(), json!(size));
+            }
+            if let Some(image) = &disk.source_image {
+                params.insert("sourceImage".to_string(), json!(image));
+            } else if let Some(snapshot) = &disk.source_snapshot {
+                params.insert("sourceSnapshot".to_string(), json!(snapshot));
+            } else if attached.boot {
+                bail!(
+                    "boot disk {} was not made from an image or snapshot; snapshot it and \
+                     create the copy from the snapshot instead",
+                    disk.name
+                );
+            }
+            if !disk.labels.is_empty() {
+                params.insert("labels".to_string(), json!(disk.labels));
+            }
+            let mut copy = json!({
+                "boot": attached.boot,
+                "autoDelete": attached.auto_delete,
+                "initializeParams": params,
+            });
+            for (key, value) in [
+                ("mode", json!(attached.mode)),
+                ("deviceName", json!(attached.device_name)),
+            ] {
+                if !value.is_null() {
+                    copy[key] = value;
+                }
+            }
+            if let Some(interface) = attached.extra.get("interface") {
+                copy["interface"] = interface.clone();
+            }
+            Ok(copy)
+        })
+        .collect::<Result<_>>()?;
+    body.insert("disks".to_string(), Value::Array(copied));
+
+    let region = region_of(zone);
+    let nics: Vec<Value> = source
+        .network_interfaces
+        .iter()
+        .map(|nic| {
+            let mut copy = json!({});
+            if let Some(network) = &nic.network {
+                copy["network"] = json!(network);
+            }
+            if let Some(subnet) = &nic.subnetwork {
+                copy["subnetwork"] = json!(format!(
+                    "regions/{region}/subnetworks/{}",
+                    short_name(subnet)
+                ));
+            }
+            for key in ["nicType", "stackType", "queueCount"] {
+                if let Some(value) = nic.extra.get(key) {
+                    copy[key] = value.clone();
+                }
+            }
+            // fresh ephemeral addresses rather than the source's
+            let access: Vec<Value> = nic
+                .access_configs
+                .iter()
+                .map(|config| {
+                    let mut copy = json!({ "type": "ONE_TO_ONE_NAT" });
+                    if let Some(name) = &config.name {
+                        copy["name"] = json!(name);
+                    }
+                    if let Some(tier) = config.extra.get("networkTier") {
+                        copy["networkTier"] = tier.clone();
+                    }
+                    copy
+                })
+                .collect();
+            if !access.is_empty() {
+                copy["accessConfigs"] = Value::Array(access);
+            }
+            copy
+        })
+        .collect();
+    body.insert("networkInterfaces".to_string(), Value::Array(nics));
+
+    if !source.guest_accelerators.is_empty() {
+        let accelerators: Vec<Value> = source
+            .guest_accelerators
+            .iter()
+            .map(|a| {
+                json!({
+                    "acceleratorType": format!(
+                        "zones/{zone}/acceleratorTypes/{}",
+                        short_name(&a.accelerator_type)
+                    ),
+                    "acceleratorCount": a.accelerator_count,
+                })
+            })
+            .collect();
+        body.insert("guestAccelerators".to_string(), Value::Array(accelerators));
+    }
+    Ok(Value::Object(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::instance::{AccessConfig, AttachedDisk, NetworkInterface};
+
+    fn source() -> (Instance, Disk) {
+        let url =
+            "https://compute.googleapis.com/compute/v1/projects/p/zones/us-central1-a/disks/web";
+        let instance: Instance = serde_json::from_value(json!({
+            "name": "web",
+            "id": "123",
+            "zone": "https://compute.googleapis.com/compute/v1/projects/p/zones/us-central1-a",
+            "machineType": "zones/us-central1-a/machineTypes/e2-medium",
+            "status": "RUNNING",
+            "labelFingerprint": "abc",
+            "labels": { "team": "infra" },
+            "metadata": { "fingerprint": "def", "items": [{ "key": "k", "value": "v" }] },
+            "tags": { "fingerprint": "ghi", "items": ["http"] },
+            "resourcePolicies": ["regions/us-central1/resourcePolicies/daily"],
+        }))
+        .unwrap();
+        let instance = Instance {
+            disks: vec![AttachedDisk {
+                source: Some(url.to_string()),
+                device_name: Some("persistent-disk-0".to_string()),
+                boot: true,
+                auto_delete: true,
+                ..Default::default()
+            }],
+            network_interfaces: vec![NetworkInterface {
+                network: Some("global/networks/default".to_string()),
+                subnetwork: Some("regions/us-central1/subnetworks/default".to_string()),
+                network_ip: Some("10.128.0.2".to_string()),
+                access_configs: vec![AccessConfig {
+                    name: Some("External NAT".to_string()),
+                    nat_ip: Some("34.1.2.3".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..instance
+        };
+        let disk = Disk {
+            name: "web".to_string(),
+            size_gb: Some("20".to_string()),
+            disk_type: "zones/us-central1-a/diskTypes/pd-ssd".to_string(),
+            source_image: Some("projects/debian-cloud/global/images/debian-12-v1".to_string()),
+            self_link: Some(url.to_string()),
+            ..Default::default()
+        };
+        (instance, disk)
+    }
+
+    #[test]
+    fn copies_settings_and_drops_instance_specific_fields() {
+        let (instance, disk) = source();
+        let disks = HashMap::from([(disk.self_link.as_deref().unwrap(), &disk)]);
+        let overrides = Overrides {
+            name: "web-copy",
+            zone: "europe-west1-b",
+            machine_type: Some("e2-standard-4"),
+            keep_disk_names: false,
+        };
+        let body = clone_body(&instance, &disks, &overrides).unwrap();
+        for key in ["id", "status", "labelFingerprint", "resourcePolicies"] {
+            assert!(body.get(key).is_none(), "{key} copied");
+        }
+        assert!(body["metadata"].get("fingerprint").is_none());
+        assert!(body["tags"].get("fingerprint").is_none());
+        assert_eq!(body["labels"]["team"], "infra");
+        assert_eq!(
+            body["machineType"],
+            "zones/europe-west1-b/machineTypes/e2-standard-4"
+        );
+        let params = &body["disks"][0]["initializeParams"];
+        assert_eq!(params["diskName"], "web-copy");
+        assert_eq!(params["diskType"], "zones/europe-west1-b/diskTypes/pd-ssd");
+        assert_eq!(params["diskSizeGb"], "20");
+        assert_eq!(
+            params["sourceImage"],
+            "projects/debian-cloud/global/images/debian-12-v1"
+        );
+        assert!(body["disks"][0].get("source").is_none());
+        let nic = &body["networkInterfaces"][0];
+        assert_eq!(
+            nic["subnetwork"],
+            "regions/europe-west1/subnetworks/default"
+        );
+        assert!(nic.get("networkIP").is_none());
+        assert!(nic["accessConfigs"][0].get("natIP").is_none());
+    }
+
+    #[test]
+    fn boot_disks_need_an_image_or_snapshot() {
+        let (instance, mut disk) = source();
+        disk.source_image = None;
+        let disks = HashMap::from([(disk.self_link.as_deref().unwrap(), &disk)]);
+        let overrides = Overrides {
+            name: "web-copy",
+            zone: "us-central1-a",
+            machine_type: None,
+            keep_disk_names: false,
+        };
+        let err = clone_body(&instance, &disks, &overrides).unwrap_err();
+        assert!(err.to_string().contains("snapshot it"));
+    }
+}
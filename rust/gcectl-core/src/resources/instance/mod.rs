@@ -0,0 +1,1022 @@
+pub mod builder;
+pub mod clone;
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{EncryptionKey, short_name};
+use crate::output::{Details, Render, status_emoji};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// A Compute Engine VM instance as returned by the `instances` API.
+///
+/// Fields gcectl works with are typed; everything else is kept in `extra` so
+/// that serializing an instance reproduces the API body.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Instance {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // full URL of the zone, e.g. `https://.../zones/asia-northeast1-a`
+    #[serde(default)]
+    pub zone: String,
+    // full URL of the machine type
+    #[serde(default)]
+    pub machine_type: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_platform: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_timestamp: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_start_timestamp: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disks: Vec<AttachedDisk>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub network_interfaces: Vec<NetworkInterface>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    // network tags, which firewall rules and routes target
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Tags>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduling: Option<Scheduling>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reservation_affinity: Option<ReservationAffinity>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub service_accounts: Vec<ServiceAccount>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub guest_accelerators: Vec<AcceleratorConfig>,
+    // full URLs of the attached resource policies
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resource_policies: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shielded_instance_config: Option<ShieldedInstanceConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidential_instance_config: Option<ConfidentialInstanceConfig>,
+    // every other field of the API resource, kept for JSON/YAML output
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AcceleratorConfig {
+    // full URL of the accelerator type
+    #[serde(default)]
+    pub accelerator_type: String,
+    #[serde(default)]
+    pub accelerator_count: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachedDisk {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_name: Option<String>,
+    // full URL of the disk resource
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub boot: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    #[serde(default)]
+    pub auto_delete: bool,
+    // int64 encoded as a string by the API
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_size_gb: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_encryption_key: Option<EncryptionKey>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkInterface {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subnetwork: Option<String>,
+    #[serde(default, rename = "networkIP", skip_serializing_if = "Option::is_none")]
+    pub network_ip: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub access_configs: Vec<AccessConfig>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, rename = "natIP", skip_serializing_if = "Option::is_none")]
+    pub nat_ip: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Metadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<MetadataItem>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Network tags of an instance and the fingerprint guarding them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Tags {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataItem {
+    pub key: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scheduling {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preemptible: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_host_maintenance: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub automatic_restart: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provisioning_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance_termination_action: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// What the instance does while its host is under maintenance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum MaintenancePolicy {
+    /// Live-migrate to another host
+    Migrate,
+    /// Stop, and restart afterwards if automatic restart is on
+    Terminate,
+}
+
+/// The capacity an instance runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum ProvisioningModel {
+    Standard,
+    /// Discounted capacity that Compute Engine can reclaim at any time
+    Spot,
+}
+
+/// What happens to a Spot instance when its capacity is reclaimed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum TerminationAction {
+    Stop,
+    Delete,
+}
+
+impl MaintenancePolicy {
+    pub fn api_name(self) -> &'static str {
+        match self {
+            Self::Migrate => "MIGRATE",
+            Self::Terminate => "TERMINATE",
+        }
+    }
+}
+
+impl ProvisioningModel {
+    pub fn api_name(self) -> &'static str {
+        match self {
+            Self::Standard => "STANDARD",
+            Self::Spot => "SPOT",
+        }
+    }
+}
+
+impl TerminationAction {
+    pub fn api_name(self) -> &'static str {
+        match self {
+            Self::Stop => "STOP",
+            Self::Delete => "DELETE",
+        }
+    }
+}
+
+/// Scheduling settings to change; `None` keeps the current one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SchedulingChange {
+    pub maintenance_policy: Option<MaintenancePolicy>,
+    pub automatic_restart: Option<bool>,
+    pub provisioning_model: Option<ProvisioningModel>,
+    pub termination_action: Option<TerminationAction>,
+}
+
+impl Scheduling {
+    /// These settings with `change` applied. Spot and preemptible capacity
+    /// can neither live-migrate nor restart on its own, so moving to it
+    /// turns both off, and asking for either is an error.
+    pub fn with(&self, change: &SchedulingChange) -> Result<Self> {
+        let mut new = self.clone();
+        if let Some(model) = change.provisioning_model {
+            if self.preemptible == Some(true) {
+                bail!(
+                    "preemptible instances cannot change provisioning model; recreate it with --spot"
+                );
+            }
+            new.provisioning_model = Some(model.api_name().to_string());
+        }
+        if let Some(policy) = change.maintenance_policy {
+            new.on_host_maintenance = Some(policy.api_name().to_string());
+        }
+        if let Some(restart) = change.automatic_restart {
+            new.automatic_restart = Some(restart);
+        }
+        if let Some(action) = change.termination_action {
+            new.instance_termination_action = Some(action.api_name().to_string());
+        }
+        let reclaimable =
+            new.provisioning_model.as_deref() == Some("SPOT") || new.preemptible == Some(true);
+        if !reclaimable {
+            if change.termination_action.is_some() {
+                bail!("only Spot instances have a termination action");
+            }
+            new.instance_termination_action = None;
+            return Ok(new);
+        }
+        if change.maintenance_policy == Some(MaintenancePolicy::Migrate) {
+            bail!("Spot and preemptible instances cannot live-migrate; use TERMINATE");
+        }
+        if change.automatic_restart == Some(true) {
+            bail!("Spot and preemptible instances cannot restart automatically");
+        }
+        new.on_host_maintenance = Some(MaintenancePolicy::Terminate.api_name().to_string());
+        new.automatic_restart = Some(false);
+        if new.provisioning_model.as_deref() == Some("SPOT") {
+            new.instance_termination_action
+                .get_or_insert_with(|| TerminationAction::Stop.api_name().to_string());
+        }
+        Ok(new)
+    }
+
+    /// Whether going to `new` needs a stopped instance, as changing what
+    /// capacity it runs on does.
+    pub fn needs_stop(&self, new: &Self) -> bool {
+        self.provisioning_model != new.provisioning_model
+            || self.instance_termination_action != new.instance_termination_action
+    }
+
+    /// e.g. `SPOT, TERMINATE on maintenance, no automatic restart, STOP when reclaimed`
+    pub fn summary(&self) -> String {
+        let model = match self.preemptible {
+            Some(true) => "PREEMPTIBLE",
+            _ => self.provisioning_model.as_deref().unwrap_or("STANDARD"),
+        };
+        let maintenance = self.on_host_maintenance.as_deref().unwrap_or("MIGRATE");
+        // the API's default is to restart
+        let restart = match self.automatic_restart.unwrap_or(true) {
+            true => "automatic restart",
+            false => "no automatic restart",
+        };
+        let mut summary = format!("{model}, {maintenance} on maintenance, {restart}");
+        if let Some(action) = &self.instance_termination_action {
+            summary.push_str(&format!(", {action} when reclaimed"));
+        }
+        summary
+    }
+}
+
+/// Which reservations an instance may consume capacity from.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationAffinity {
+    // `ANY_RESERVATION`, `SPECIFIC_RESERVATION`, or `NO_RESERVATION`
+    #[serde(default)]
+    pub consume_reservation_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    // reservation names or URLs, for a specific reservation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+}
+
+impl ReservationAffinity {
+    /// What the instance consumes, e.g. `gpu-pool` or `any matching`.
+    pub fn describe(&self) -> String {
+        match self.consume_reservation_type.as_str() {
+            "SPECIFIC_RESERVATION" => {
+                let names: Vec<&str> = self.values.iter().map(|v| short_name(v)).collect();
+                names.join(", ")
+            }
+            "NO_RESERVATION" => "none".to_string(),
+            _ => "any matching".to_string(),
+        }
+    }
+}
+
+/// Shielded VM options; `None` leaves the API default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShieldedInstanceConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable_secure_boot: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable_vtpm: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable_integrity_monitoring: Option<bool>,
+}
+
+impl ShieldedInstanceConfig {
+    /// `self` with the options `changes` sets replaced.
+    pub fn merge(&self, changes: &Self) -> Self {
+        Self {
+            enable_secure_boot: changes.enable_secure_boot.or(self.enable_secure_boot),
+            enable_vtpm: changes.enable_vtpm.or(self.enable_vtpm),
+            enable_integrity_monitoring: changes
+                .enable_integrity_monitoring
+                .or(self.enable_integrity_monitoring),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfidentialInstanceConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable_confidential_compute: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServiceAccount {
+    pub email: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+/// What the instance's display shows, from `instances.getScreenshot`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Screenshot {
+    // a base64-encoded PNG
+    #[serde(default)]
+    pub contents: String,
+}
+
+impl Screenshot {
+    /// The decoded PNG.
+    pub fn png(&self) -> Result<Vec<u8>> {
+        let png = STANDARD
+            .decode(self.contents.trim())
+            .context("the screenshot is not valid base64")?;
+        if !png.starts_with(PNG_SIGNATURE) {
+            bail!("the screenshot is not a PNG image");
+        }
+        Ok(png)
+    }
+}
+
+/// A chunk of serial port output from `instances.getSerialPortOutput`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerialPortOutput {
+    #[serde(default)]
+    pub contents: String,
+    // byte offsets, int64 encoded as strings
+    #[serde(default)]
+    pub start: Option<String>,
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+impl SerialPortOutput {
+    /// Offset the returned contents begin at; greater than the requested
+    /// one when older output has already rotated out of the buffer.
+    pub fn start_offset(&self) -> u64 {
+        parse_offset(self.start.as_deref())
+    }
+
+    /// Offset to request next to continue where this chunk ends.
+    pub fn next_offset(&self) -> u64 {
+        parse_offset(self.next.as_deref())
+    }
+}
+
+fn parse_offset(offset: Option<&str>) -> u64 {
+    offset.and_then(|o| o.parse().ok()).unwrap_or_default()
+}
+
+/// Metadata keys of the script an image runs at boot, stored inline or
+/// fetched from Cloud Storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartupScriptKeys {
+    pub inline: &'static str,
+    pub url: &'static str,
+}
+
+pub const LINUX_STARTUP_SCRIPT: StartupScriptKeys = StartupScriptKeys {
+    inline: "startup-script",
+    url: "startup-script-url",
+};
+
+pub const WINDOWS_STARTUP_SCRIPT: StartupScriptKeys = StartupScriptKeys {
+    inline: "windows-startup-script-ps1",
+    url: "windows-startup-script-url",
+};
+
+impl Tags {
+    /// Adds the tags not already present, checking each is a valid
+    /// RFC 1035 name.
+    pub fn add(&mut self, tags: &[String]) -> Result<()> {
+        for tag in tags {
+            validate_tag(tag)?;
+            if !self.items.contains(tag) {
+                self.items.push(tag.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes `tags`, failing if any is not set.
+    pub fn remove(&mut self, tags: &[String]) -> Result<()> {
+        let missing: Vec<&str> = tags
+            .iter()
+            .filter(|tag| !self.items.contains(tag))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            bail!("no network tag {}", missing.join(", "));
+        }
+        self.items.retain(|item| !tags.contains(item));
+        Ok(())
+    }
+}
+
+/// Lowercase letters, digits, and `-`, starting with a letter and not
+/// ending with `-`, at most 63 characters.
+fn validate_tag(tag: &str) -> Result<()> {
+    let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-';
+    if tag.len() > 63
+        || !tag.starts_with(|c: char| c.is_ascii_lowercase())
+        || tag.ends_with('-')
+        || !tag.chars().all(allowed)
+    {
+        bail!(
+            "network tag `{tag}` must be 1-63 lowercase letters, digits, or `-`, \
+             starting with a letter"
+        );
+    }
+    Ok(())
+}
+
+impl Metadata {
+    /// Value stored under `key`, if any.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.items
+            .iter()
+            .find(|item| item.key == key)
+            .map(|item| item.value.as_str())
+    }
+
+    /// Sets `key` to `value`, replacing an existing entry in place.
+    pub fn set(&mut self, key: &str, value: impl Into<String>) {
+        let value = value.into();
+        match self.items.iter_mut().find(|item| item.key == key) {
+            Some(item) => item.value = value,
+            None => self.items.push(MetadataItem {
+                key: key.to_string(),
+                value,
+            }),
+        }
+    }
+
+    /// Removes `key`, returning whether it was present.
+    pub fn remove(&mut self, key: &str) -> bool {
+        let before = self.items.len();
+        self.items.retain(|item| item.key != key);
+        self.items.len() != before
+    }
+
+    /// Stores `script` inline, dropping any URL it would otherwise be
+    /// fetched from, since the URL takes precedence at boot.
+    pub fn set_startup_script(&mut self, keys: StartupScriptKeys, script: String) {
+        self.remove(keys.url);
+        self.set(keys.inline, script);
+    }
+}
+
+/// Longest value shown in a table cell before it is cut short.
+const VALUE_PREVIEW_LEN: usize = 60;
+
+impl Render for MetadataItem {
+    fn headers() -> Vec<&'static str> {
+        vec!["Key", "Value"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.key.clone(), self.value.clone()]
+    }
+
+    // startup scripts and ssh keys would otherwise swamp the table
+    fn table_row(&self) -> Vec<String> {
+        let first_line = self.value.lines().next().unwrap_or_default();
+        let mut preview: String = first_line.chars().take(VALUE_PREVIEW_LEN).collect();
+        if preview.len() < self.value.len() {
+            preview.push_str(" …");
+        }
+        vec![self.key.clone(), preview]
+    }
+}
+
+impl Instance {
+    /// Zone name without the resource URL prefix.
+    pub fn zone_name(&self) -> &str {
+        short_name(&self.zone)
+    }
+
+    /// Whether the instance runs as Spot or legacy preemptible capacity.
+    pub fn is_spot(&self) -> bool {
+        self.scheduling.as_ref().is_some_and(|s| {
+            s.provisioning_model.as_deref() == Some("SPOT") || s.preemptible == Some(true)
+        })
+    }
+
+    /// Network tags, in the order the API returned them.
+    pub fn tags(&self) -> &[String] {
+        self.tags.as_ref().map_or(&[], |t| &t.items)
+    }
+
+    /// Machine type name without the resource URL prefix.
+    pub fn machine_type_name(&self) -> &str {
+        short_name(&self.machine_type)
+    }
+
+    /// Internal IP of the primary network interface.
+    pub fn internal_ip(&self) -> Option<&str> {
+        self.network_interfaces
+            .first()
+            .and_then(|nic| nic.network_ip.as_deref())
+    }
+
+    /// External (NAT) IP of the primary network interface, if any.
+    pub fn external_ip(&self) -> Option<&str> {
+        self.network_interfaces
+            .first()
+            .and_then(|nic| nic.access_configs.first())
+            .and_then(|ac| ac.nat_ip.as_deref())
+    }
+
+    /// Device name under which the disk called `disk` is attached.
+    pub fn device_name_of(&self, disk: &str) -> Option<&str> {
+        self.disks
+            .iter()
+            .find(|d| d.source.as_deref().map(short_name) == Some(disk))
+            .and_then(|d| d.device_name.as_deref())
+    }
+}
+
+impl Render for Instance {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Name",
+            "Zone",
+            "Machine-Type",
+            "Status",
+            "Internal-IP",
+            "External-IP",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.zone_name().to_string(),
+            self.machine_type_name().to_string(),
+            self.status.clone(),
+            self.internal_ip().unwrap_or("-").to_string(),
+            self.external_ip().unwrap_or("-").to_string(),
+        ]
+    }
+
+    fn table_row(&self) -> Vec<String> {
+        let mut row = self.row();
+        row[3] = format!("{} {}", status_emoji(&self.status), self.status);
+        row
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Id", self.id.as_deref())
+            .field_opt("Description", self.description.as_deref())
+            .field("Zone", self.zone_name())
+            .field("Machine-Type", self.machine_type_name())
+            .field(
+                "Status",
+                format!("{} {}", status_emoji(&self.status), self.status),
+            )
+            .field_opt("CPU-Platform", self.cpu_platform.as_deref())
+            .field_opt(
+                "Network-Tags",
+                Some(self.tags().join(", ")).filter(|t| !t.is_empty()),
+            )
+            .field_opt("Created", self.creation_timestamp.as_deref())
+            .field_opt("Last-Started", self.last_start_timestamp.as_deref())
+            .field_opt(
+                "Reservation",
+                self.reservation_affinity
+                    .as_ref()
+                    .map(ReservationAffinity::describe),
+            );
+
+        details.group("Disks", |group| {
+            for disk in &self.disks {
+                let title = disk.device_name.as_deref().unwrap_or("(unnamed)");
+                group.group(title, |d| {
+                    d.field_opt("Source", disk.source.as_deref().map(short_name))
+                        .field("Boot", disk.boot.to_string())
+                        .field_opt("Mode", disk.mode.as_deref())
+                        .field_opt("Size-GB", disk.disk_size_gb.as_deref())
+                        .field("Auto-Delete", disk.auto_delete.to_string())
+                        .field_opt(
+                            "KMS-Key",
+                            disk.disk_encryption_key.as_ref().and_then(|k| k.kms_key()),
+                        );
+                });
+            }
+        });
+
+        details.group("Network-Interfaces", |group| {
+            for nic in &self.network_interfaces {
+                group.group(nic.name.as_deref().unwrap_or("(unnamed)"), |d| {
+                    d.field_opt("Network", nic.network.as_deref().map(short_name))
+                        .field_opt("Subnetwork", nic.subnetwork.as_deref().map(short_name))
+                        .field_opt("Internal-IP", nic.network_ip.as_deref())
+                        .field_opt(
+                            "External-IP",
+                            nic.access_configs
+                                .first()
+                                .and_then(|ac| ac.nat_ip.as_deref()),
+                        );
+                });
+            }
+        });
+
+        details.group("Metadata", |group| {
+            for item in self.metadata.iter().flat_map(|m| &m.items) {
+                group.field(&item.key, &item.value);
+            }
+        });
+
+        details.group("Labels", |group| {
+            for (key, value) in &self.labels {
+                group.field(key, value);
+            }
+        });
+
+        if let Some(scheduling) = &self.scheduling {
+            details.group("Scheduling", |d| {
+                d.field_opt(
+                    "Provisioning-Model",
+                    scheduling.provisioning_model.as_deref(),
+                )
+                .field_opt("Preemptible", scheduling.preemptible.map(|b| b.to_string()))
+                .field_opt(
+                    "On-Host-Maintenance",
+                    scheduling.on_host_maintenance.as_deref(),
+                )
+                .field_opt(
+                    "Automatic-Restart",
+                    scheduling.automatic_restart.map(|b| b.to_string()),
+                )
+                .field_opt(
+                    "Termination-Action",
+                    scheduling.instance_termination_action.as_deref(),
+                );
+            });
+        }
+
+        if let Some(shielded) = &self.shielded_instance_config {
+            let flag = |b: Option<bool>| b.map(|b| b.to_string());
+            details.group("Shielded-VM", |d| {
+                d.field_opt("Secure-Boot", flag(shielded.enable_secure_boot))
+                    .field_opt("vTPM", flag(shielded.enable_vtpm))
+                    .field_opt(
+                        "Integrity-Monitoring",
+                        flag(shielded.enable_integrity_monitoring),
+                    );
+            });
+        }
+        if let Some(confidential) = &self.confidential_instance_config {
+            details.field_opt(
+                "Confidential-Compute",
+                confidential
+                    .enable_confidential_compute
+                    .map(|b| b.to_string()),
+            );
+        }
+
+        details.group("Resource-Policies", |group| {
+            for policy in &self.resource_policies {
+                group.field("Policy", short_name(policy));
+            }
+        });
+
+        details.group("Service-Accounts", |group| {
+            for sa in &self.service_accounts {
+                group.group(&sa.email, |d| {
+                    let scopes: Vec<&str> = sa.scopes.iter().map(|s| short_name(s)).collect();
+                    d.field("Scopes", scopes.join(", "));
+                });
+            }
+        });
+
+        details
+    }
+}
+
+/// An instance listed with a column of its network tags, for
+/// `instances list --show-tags`.
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct WithTags<'a>(pub &'a Instance);
+
+impl Render for WithTags<'_> {
+    fn headers() -> Vec<&'static str> {
+        let mut headers = Instance::headers();
+        headers.push("Tags");
+        headers
+    }
+
+    fn row(&self) -> Vec<String> {
+        let mut row = self.0.row();
+        row.push(self.0.tags().join(","));
+        row
+    }
+
+    fn table_row(&self) -> Vec<String> {
+        let mut row = self.0.table_row();
+        row.push(self.0.tags().join(","));
+        row
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_network_tags() {
+        let mut tags = Tags {
+            fingerprint: Some("abc".into()),
+            items: vec!["http-server".into()],
+        };
+        tags.add(&["http-server".into(), "ssh".into()]).unwrap();
+        assert_eq!(tags.items, ["http-server", "ssh"]);
+        tags.remove(&["http-server".into()]).unwrap();
+        assert_eq!(tags.items, ["ssh"]);
+        let err = tags.remove(&["web".into()]).unwrap_err();
+        assert_eq!(err.to_string(), "no network tag web");
+        for bad in ["Web", "1st", "web-", "a_b", &"a".repeat(64)] {
+            assert!(tags.add(&[bad.to_string()]).is_err(), "{bad}");
+        }
+        assert_eq!(tags.fingerprint.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn decodes_screenshots() {
+        let png = [PNG_SIGNATURE, b"rest"].concat();
+        let shot = Screenshot {
+            contents: STANDARD.encode(&png),
+        };
+        assert_eq!(shot.png().unwrap(), png);
+        let text = Screenshot {
+            contents: STANDARD.encode("not an image"),
+        };
+        assert!(text.png().is_err());
+        assert!(Screenshot::default().png().is_err());
+    }
+
+    #[test]
+    fn parses_api_response() {
+        let body = r#"{
+            "name": "my-vm",
+            "zone": "https://www.googleapis.com/compute/v1/projects/p/zones/asia-northeast1-a",
+            "machineType": "https://www.googleapis.com/compute/v1/projects/p/zones/asia-northeast1-a/machineTypes/e2-medium",
+            "status": "RUNNING",
+            "networkInterfaces": [
+                {"networkIP": "10.0.0.2", "accessConfigs": [{"natIP": "34.1.2.3"}]}
+            ]
+        }"#;
+        let instance: Instance = serde_json::from_str(body).unwrap();
+        assert_eq!(instance.zone_name(), "asia-northeast1-a");
+        assert_eq!(instance.machine_type_name(), "e2-medium");
+        assert_eq!(instance.internal_ip(), Some("10.0.0.2"));
+        assert_eq!(instance.external_ip(), Some("34.1.2.3"));
+        assert_eq!(instance.row()[3], "RUNNING");
+        assert_eq!(instance.table_row()[3], "🟢 RUNNING");
+    }
+
+    #[test]
+    fn keeps_unknown_fields_for_serialization() {
+        let instance: Instance =
+            serde_json::from_str(r#"{"name": "vm", "id": "123", "labels": {"env": "dev"}}"#)
+                .unwrap();
+        let value = serde_json::to_value(&instance).unwrap();
+        assert_eq!(value["id"], "123");
+        assert_eq!(value["labels"]["env"], "dev");
+    }
+
+    #[test]
+    fn serializes_without_inventing_fields() {
+        let body = serde_json::json!({
+            "name": "vm",
+            "zone": "z",
+            "machineType": "m",
+            "status": "RUNNING",
+            "networkInterfaces": [{"networkIP": "10.0.0.2"}]
+        });
+        let instance: Instance = serde_json::from_value(body.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&instance).unwrap(), body);
+    }
+
+    #[test]
+    fn details_include_sections() {
+        let body = r#"{
+            "name": "vm",
+            "disks": [{"deviceName": "boot", "boot": true, "source": "https://x/disks/vm-boot"}],
+            "metadata": {"fingerprint": "abc", "items": [{"key": "enable-oslogin", "value": "TRUE"}]},
+            "labels": {"env": "dev"},
+            "serviceAccounts": [{"email": "sa@p.iam.gserviceaccount.com",
+                                 "scopes": ["https://www.googleapis.com/auth/cloud-platform"]}],
+            "reservationAffinity": {"consumeReservationType": "SPECIFIC_RESERVATION",
+                                    "key": "compute.googleapis.com/reservation-name",
+                                    "values": ["projects/p/zones/z/reservations/gpu-pool"]}
+        }"#;
+        let instance: Instance = serde_json::from_str(body).unwrap();
+        let rendered = instance.details().render();
+        assert!(rendered.contains("Disks:"));
+        assert!(rendered.contains("gpu-pool"));
+        assert!(rendered.contains("vm-boot"));
+        assert!(rendered.contains("enable-oslogin"));
+        assert!(rendered.contains("env"));
+        assert!(rendered.contains("cloud-platform"));
+        assert!(!rendered.contains("Scheduling:"));
+        assert!(!rendered.contains("Shielded-VM:"));
+    }
+
+    #[test]
+    fn scheduling_changes_keep_spot_rules() {
+        let standard = Scheduling::default();
+        let spot = standard
+            .with(&SchedulingChange {
+                provisioning_model: Some(ProvisioningModel::Spot),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            spot.summary(),
+            "SPOT, TERMINATE on maintenance, no automatic restart, STOP when reclaimed"
+        );
+        assert!(standard.needs_stop(&spot));
+        let migrate = SchedulingChange {
+            maintenance_policy: Some(MaintenancePolicy::Migrate),
+            ..Default::default()
+        };
+        assert!(spot.with(&migrate).is_err());
+        let live = standard.with(&migrate).unwrap();
+        assert!(!standard.needs_stop(&live));
+        let back = spot
+            .with(&SchedulingChange {
+                provisioning_model: Some(ProvisioningModel::Standard),
+                ..migrate
+            })
+            .unwrap();
+        assert_eq!(back.instance_termination_action, None);
+        assert!(
+            standard
+                .with(&SchedulingChange {
+                    termination_action: Some(TerminationAction::Delete),
+                    ..Default::default()
+                })
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn details_show_security_settings() {
+        let body = r#"{
+            "name": "vm",
+            "shieldedInstanceConfig": {"enableSecureBoot": true, "enableVtpm": true,
+                                       "enableIntegrityMonitoring": false},
+            "confidentialInstanceConfig": {"enableConfidentialCompute": true}
+        }"#;
+        let instance: Instance = serde_json::from_str(body).unwrap();
+        let rendered = instance.details().render();
+        assert!(rendered.contains("Shielded-VM:"));
+        assert!(rendered.contains("Secure-Boot"));
+        assert!(rendered.contains("Integrity-Monitoring"));
+        assert!(rendered.contains("Confidential-Compute"));
+    }
+
+    #[test]
+    fn missing_network_interfaces() {
+        let instance: Instance = serde_json::from_str(r#"{"name": "vm"}"#).unwrap();
+        assert_eq!(instance.internal_ip(), None);
+        assert_eq!(instance.external_ip(), None);
+    }
+
+    #[test]
+    fn metadata_edits_keep_other_entries() {
+        let mut metadata: Metadata = serde_json::from_str(
+            r#"{"fingerprint": "abc", "items": [
+                {"key": "enable-oslogin", "value": "TRUE"},
+                {"key": "startup-script", "value": "echo hi"}
+            ]}"#,
+        )
+        .unwrap();
+        metadata.set("enable-oslogin", "FALSE");
+        metadata.set("env", "dev");
+        assert!(metadata.remove("startup-script"));
+        assert!(!metadata.remove("missing"));
+
+        let keys: Vec<&str> = metadata.items.iter().map(|i| i.key.as_str()).collect();
+        assert_eq!(keys, ["enable-oslogin", "env"]);
+        assert_eq!(metadata.items[0].value, "FALSE");
+        // the fingerprint read must be sent back unchanged
+        assert_eq!(
+            serde_json::to_value(&metadata).unwrap()["fingerprint"],
+            "abc"
+        );
+    }
+
+    #[test]
+    fn inline_startup_script_replaces_its_url() {
+        let mut metadata = Metadata::default();
+        metadata.set("startup-script-url", "gs://bucket/boot.sh");
+        metadata.set_startup_script(LINUX_STARTUP_SCRIPT, "echo hi".into());
+        assert_eq!(metadata.get("startup-script"), Some("echo hi"));
+        assert_eq!(metadata.get("startup-script-url"), None);
+    }
+
+    #[test]
+    fn long_metadata_values_are_shortened_in_tables() {
+        let item = MetadataItem {
+            key: "startup-script".into(),
+            value: "#!/bin/bash\napt-get update".into(),
+        };
+        assert_eq!(item.table_row()[1], "#!/bin/bash …");
+        assert_eq!(item.row()[1], "#!/bin/bash\napt-get update");
+    }
+
+    #[test]
+    fn serial_output_offsets() {
+        let output: SerialPortOutput =
+            serde_json::from_str(r#"{"contents": "boot\n", "start": "1024", "next": "1029"}"#)
+                .unwrap();
+        assert_eq!(output.start_offset(), 1024);
+        assert_eq!(output.next_offset(), 1029);
+        assert_eq!(SerialPortOutput::default().next_offset(), 0);
+    }
+
+    #[test]
+    fn finds_device_name_of_attached_disk() {
+        let instance: Instance = serde_json::from_str(
+            r#"{"name": "vm", "disks": [
+                {"deviceName": "persistent-disk-0", "source": "https://x/zones/z/disks/vm"},
+                {"deviceName": "scratch", "source": "https://x/zones/z/disks/data"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(instance.device_name_of("data"), Some("scratch"));
+        assert_eq!(instance.device_name_of("other"), None);
+    }
+}
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::short_name;
+
+/// A zonal group of instances that load balancer backends point at.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceGroup {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // full URL of the zone
+    #[serde(default)]
+    pub zone: String,
+    #[serde(default)]
+    pub size: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub self_link: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// One entry of `instanceGroups.listInstances`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GroupMember {
+    // full URL of the instance
+    #[serde(default)]
+    pub instance: String,
+    #[serde(default)]
+    pub status: String,
+}
+
+impl GroupMember {
+    pub fn instance_name(&self) -> &str {
+        short_name(&self.instance)
+    }
+}
+
+/// A load balancer backend service, global or regional.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendService {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backends: Vec<Backend>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_draining: Option<ConnectionDraining>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Backend {
+    // full URL of the instance group or network endpoint group
+    #[serde(default)]
+    pub group: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionDraining {
+    #[serde(default)]
+    pub draining_timeout_sec: u64,
+}
+
+impl BackendService {
+    /// Whether the instance group `group` in `zone` is one of the backends.
+    pub fn serves_group(&self, zone: &str, group: &str) -> bool {
+        let suffix = format!("/zones/{zone}/instanceGroups/{group}");
+        self.backends.iter().any(|b| b.group.ends_with(&suffix))
+    }
+
+    /// Seconds the load balancer gives open connections to a leaving backend.
+    pub fn draining_timeout(&self) -> u64 {
+        self.connection_draining
+            .as_ref()
+            .map_or(0, |d| d.draining_timeout_sec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn matches_backend_groups_by_zone_and_name() {
+        let service: BackendService = serde_json::from_value(json!({
+            "name": "web",
+            "backends": [{
+                "group": "https://www.googleapis.com/compute/v1/projects/p/zones/us-central1-a/instanceGroups/web-ig"
+            }],
+            "connectionDraining": { "drainingTimeoutSec": 30 }
+        }))
+        .unwrap();
+        assert!(service.serves_group("us-central1-a", "web-ig"));
+        assert!(!service.serves_group("us-central1-b", "web-ig"));
+        assert!(!service.serves_group("us-central1-a", "ig"));
+        assert_eq!(service.draining_timeout(), 30);
+    }
+}
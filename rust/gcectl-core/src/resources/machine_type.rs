@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{region_of, short_name};
+use crate::cost;
+use crate::output::{Details, Render};
+
+/// A machine type offered in a zone.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MachineType {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub guest_cpus: u32,
+    #[serde(default)]
+    pub memory_mb: u64,
+    // full URL of the zone
+    #[serde(default)]
+    pub zone: String,
+    #[serde(default)]
+    pub is_shared_cpu: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maximum_persistent_disks: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accelerators: Vec<BundledAccelerator>,
+    // estimated from the bundled price table, not returned by the API
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_price: Option<f64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// GPUs that come attached to an accelerator-optimized machine type.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundledAccelerator {
+    pub guest_accelerator_type: String,
+    pub guest_accelerator_count: u32,
+}
+
+/// Order of a machine type listing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum SortKey {
+    #[default]
+    Name,
+    Cpus,
+    Memory,
+    Price,
+}
+
+impl MachineType {
+    pub fn zone_name(&self) -> &str {
+        short_name(&self.zone)
+    }
+
+    pub fn memory_gb(&self) -> f64 {
+        self.memory_mb as f64 / 1024.0
+    }
+
+    /// Fills in [`MachineType::monthly_price`] for the zone's region.
+    pub fn with_price(mut self) -> Self {
+        self.monthly_price = cost::machine_monthly(&self.name, region_of(self.zone_name()));
+        self
+    }
+
+    pub fn accelerator_summary(&self) -> String {
+        self.accelerators
+            .iter()
+            .map(|a| {
+                format!(
+                    "{} x{}",
+                    a.guest_accelerator_type, a.guest_accelerator_count
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Sorts `types` by `key`, breaking ties by name. Types without a known
+/// price sort last.
+pub fn sort(types: &mut [MachineType], key: SortKey) {
+    types.sort_by(|a, b| {
+        let ordering = match key {
+            SortKey::Name => std::cmp::Ordering::Equal,
+            SortKey::Cpus => a.guest_cpus.cmp(&b.guest_cpus),
+            SortKey::Memory => a.memory_mb.cmp(&b.memory_mb),
+            SortKey::Price => match (a.monthly_price, b.monthly_price) {
+                (Some(x), Some(y)) => x.total_cmp(&y),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            },
+        };
+        ordering.then_with(|| a.name.cmp(&b.name))
+    });
+}
+
+fn price(monthly: Option<f64>) -> String {
+    monthly.map_or_else(String::new, |p| format!("${p:.2}"))
+}
+
+impl Render for MachineType {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "vCPUs", "Memory-GB", "Zone", "Monthly"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.guest_cpus.to_string(),
+            format!("{:.1}", self.memory_gb()),
+            self.zone_name().to_string(),
+            price(self.monthly_price),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Description", self.description.as_deref())
+            .field("Zone", self.zone_name())
+            .field("vCPUs", self.guest_cpus.to_string())
+            .field("Memory-GB", format!("{:.1}", self.memory_gb()))
+            .field("Shared-CPU", self.is_shared_cpu.to_string())
+            .field_opt(
+                "Max-Disks",
+                self.maximum_persistent_disks.map(|n| n.to_string()),
+            );
+        if !self.accelerators.is_empty() {
+            details.field("Accelerators", self.accelerator_summary());
+        }
+        details.field_opt(
+            "Monthly (est.)",
+            self.monthly_price.map(|p| format!("${p:.2}")),
+        );
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn machine_type(name: &str, guest_cpus: u32, memory_mb: u64) -> MachineType {
+        MachineType {
+            name: name.into(),
+            guest_cpus,
+            memory_mb,
+            zone: "https://www.googleapis.com/compute/v1/projects/p/zones/us-central1-a".into(),
+            ..Default::default()
+        }
+        .with_price()
+    }
+
+    fn names(types: &[MachineType]) -> Vec<&str> {
+        types.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    #[test]
+    fn sorts_by_key_then_name() {
+        let mut types = vec![
+            machine_type("n2-standard-8", 8, 32768),
+            machine_type("n2-highcpu-8", 8, 8192),
+            machine_type("e2-standard-4", 4, 16384),
+            machine_type("x9-mystery-2", 2, 4096),
+        ];
+        sort(&mut types, SortKey::Cpus);
+        assert_eq!(
+            names(&types),
+            [
+                "x9-mystery-2",
+                "e2-standard-4",
+                "n2-highcpu-8",
+                "n2-standard-8"
+            ]
+        );
+        sort(&mut types, SortKey::Memory);
+        assert_eq!(names(&types)[..2], ["x9-mystery-2", "n2-highcpu-8"]);
+        sort(&mut types, SortKey::Price);
+        assert_eq!(names(&types)[0], "e2-standard-4");
+        assert_eq!(names(&types)[3], "x9-mystery-2");
+    }
+}
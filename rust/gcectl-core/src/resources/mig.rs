@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::short_name;
+use crate::output::{Details, Render};
+
+/// A zonal managed instance group (`instanceGroupManagers`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceGroupManager {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // full URL of the zone
+    #[serde(default)]
+    pub zone: String,
+    // full URL of the template new instances are created from
+    #[serde(default)]
+    pub instance_template: String,
+    #[serde(default)]
+    pub base_instance_name: String,
+    #[serde(default)]
+    pub target_size: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<MigStatus>,
+    // instance counts per pending action: creating, recreating, deleting, ...
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub current_actions: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_timestamp: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigStatus {
+    #[serde(default)]
+    pub is_stable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_target: Option<VersionTarget>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Whether the group's instances run the versions it asks for.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionTarget {
+    #[serde(default)]
+    pub is_reached: bool,
+}
+
+/// How far a rolling update may deviate from the target size at once.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RolloutLimits {
+    pub max_surge: Option<u32>,
+    pub max_unavailable: Option<u32>,
+}
+
+impl InstanceGroupManager {
+    pub fn zone_name(&self) -> &str {
+        short_name(&self.zone)
+    }
+
+    pub fn template_name(&self) -> &str {
+        short_name(&self.instance_template)
+    }
+
+    /// Whether every instance matches the group's intended state with no
+    /// actions in progress.
+    pub fn is_stable(&self) -> bool {
+        self.status.as_ref().is_some_and(|s| s.is_stable)
+    }
+
+    /// Whether every instance runs the group's target template; a group
+    /// that says nothing of its versions is taken to have reached them.
+    pub fn reached_version_target(&self) -> bool {
+        self.status
+            .as_ref()
+            .and_then(|s| s.version_target.as_ref())
+            .is_none_or(|v| v.is_reached)
+    }
+
+    /// Pending actions with a non-zero instance count, e.g. `creating=2`.
+    pub fn pending_actions(&self) -> Vec<String> {
+        self.current_actions
+            .iter()
+            .filter_map(|(action, count)| {
+                let count = count.as_u64().filter(|&c| c > 0)?;
+                Some(format!("{action}={count}"))
+            })
+            .collect()
+    }
+
+    /// Request body for `instanceGroupManagers.insert`.
+    pub fn create_request(
+        name: &str,
+        project: &str,
+        template: &str,
+        size: u32,
+        base_instance_name: Option<&str>,
+    ) -> Value {
+        json!({
+            "name": name,
+            "instanceTemplate": template_url(project, template),
+            "targetSize": size,
+            "baseInstanceName": base_instance_name.unwrap_or(name),
+        })
+    }
+
+    /// Patch that proactively replaces every instance with ones from
+    /// `template`.
+    pub fn rolling_update_request(project: &str, template: &str, limits: RolloutLimits) -> Value {
+        let mut policy = json!({
+            "type": "PROACTIVE",
+            "minimalAction": "REPLACE",
+        });
+        if let Some(surge) = limits.max_surge {
+            policy["maxSurge"] = json!({ "fixed": surge });
+        }
+        if let Some(unavailable) = limits.max_unavailable {
+            policy["maxUnavailable"] = json!({ "fixed": unavailable });
+        }
+        json!({
+            "versions": [{ "instanceTemplate": template_url(project, template) }],
+            "updatePolicy": policy,
+        })
+    }
+}
+
+fn template_url(project: &str, template: &str) -> String {
+    format!("projects/{project}/global/instanceTemplates/{template}")
+}
+
+impl Render for InstanceGroupManager {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Zone", "Template", "Target-Size", "Stable"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.zone_name().to_string(),
+            self.template_name().to_string(),
+            self.target_size.to_string(),
+            self.is_stable().to_string(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Id", self.id.as_deref())
+            .field_opt("Description", self.description.as_deref())
+            .field("Zone", self.zone_name())
+            .field("Template", self.template_name())
+            .field("Base-Instance-Name", &self.base_instance_name)
+            .field("Target-Size", self.target_size.to_string())
+            .field("Stable", self.is_stable().to_string())
+            .field("Pending-Actions", self.pending_actions().join(", "))
+            .field_opt("Created", self.creation_timestamp.as_deref());
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_stability_and_pending_actions() {
+        let mig: InstanceGroupManager = serde_json::from_str(
+            r#"{
+                "name": "web",
+                "zone": "https://x/zones/us-central1-a",
+                "instanceTemplate": "https://x/global/instanceTemplates/web-v2",
+                "targetSize": 3,
+                "status": {"isStable": false},
+                "currentActions": {"creating": 1, "none": 2, "deleting": 0}
+            }"#,
+        )
+        .unwrap();
+        assert!(!mig.is_stable());
+        assert!(mig.reached_version_target());
+        assert_eq!(mig.pending_actions(), ["creating=1", "none=2"]);
+        assert_eq!(mig.row(), ["web", "us-central1-a", "web-v2", "3", "false"]);
+    }
+
+    #[test]
+    fn a_stable_group_may_still_be_rolling_out() {
+        let mig: InstanceGroupManager = serde_json::from_str(
+            r#"{"name": "web", "status": {"isStable": true, "versionTarget": {"isReached": false}}}"#,
+        )
+        .unwrap();
+        assert!(mig.is_stable());
+        assert!(!mig.reached_version_target());
+    }
+
+    #[test]
+    fn rolling_update_sets_limits_only_when_given() {
+        let body = InstanceGroupManager::rolling_update_request(
+            "p",
+            "web-v2",
+            RolloutLimits {
+                max_surge: Some(2),
+                max_unavailable: None,
+            },
+        );
+        assert_eq!(
+            body["versions"][0]["instanceTemplate"],
+            "projects/p/global/instanceTemplates/web-v2"
+        );
+        assert_eq!(body["updatePolicy"]["maxSurge"]["fixed"], 2);
+        assert!(body["updatePolicy"].get("maxUnavailable").is_none());
+    }
+}
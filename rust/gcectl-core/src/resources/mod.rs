@@ -0,0 +1,113 @@
+//! Typed Compute Engine resources, deserialized from the REST API.
+
+pub mod accelerator;
+pub mod address;
+pub mod disk;
+pub mod firewall;
+pub mod image;
+pub mod instance;
+pub mod instance_group;
+pub mod machine_type;
+pub mod mig;
+pub mod network;
+pub mod node;
+pub mod operation;
+pub mod project;
+pub mod reservation;
+pub mod resource_policy;
+pub mod snapshot;
+pub mod template;
+pub mod zone;
+
+pub use accelerator::{Accelerator, AcceleratorType};
+pub use address::Address;
+pub use disk::{Disk, EncryptionKey};
+pub use firewall::Firewall;
+pub use image::Image;
+pub use instance::Instance;
+pub use instance_group::{BackendService, GroupMember, InstanceGroup};
+pub use machine_type::MachineType;
+pub use mig::InstanceGroupManager;
+pub use network::{Network, Subnetwork};
+pub use node::{NodeGroup, NodeTemplate};
+pub use operation::Operation;
+pub use project::Project;
+pub use reservation::Reservation;
+pub use resource_policy::ResourcePolicy;
+pub use snapshot::Snapshot;
+pub use template::InstanceTemplate;
+pub use zone::{Quota, Region, Zone};
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// One page of a Compute Engine `*.list` response.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListPage<T> {
+    #[serde(default = "Vec::new")]
+    pub items: Vec<T>,
+    pub next_page_token: Option<String>,
+}
+
+/// One page of a Compute Engine `*.aggregatedList` response.
+///
+/// `items` is keyed by scope (`zones/us-central1-a`); each scope holds either
+/// the resources under a type-specific key (`instances`, `disks`, ...) or a
+/// warning when the scope is empty.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregatedPage {
+    #[serde(default)]
+    pub items: BTreeMap<String, Map<String, Value>>,
+    pub next_page_token: Option<String>,
+}
+
+/// Returns the last path segment of a resource URL
+/// (`.../zones/asia-northeast1-a` → `asia-northeast1-a`).
+pub fn short_name(url: &str) -> &str {
+    url.rsplit('/').next().unwrap_or(url)
+}
+
+/// Region containing `zone` (`asia-northeast1-a` → `asia-northeast1`).
+pub fn region_of(zone: &str) -> &str {
+    zone.rsplit_once('-').map_or(zone, |(region, _)| region)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_of_zone() {
+        assert_eq!(region_of("asia-northeast1-a"), "asia-northeast1");
+        assert_eq!(region_of("us-central1-f"), "us-central1");
+    }
+
+    #[test]
+    fn short_name_strips_url_prefix() {
+        assert_eq!(
+            short_name("https://www.googleapis.com/compute/v1/projects/p/zones/us-central1-a"),
+            "us-central1-a"
+        );
+        assert_eq!(short_name("e2-medium"), "e2-medium");
+        assert_eq!(short_name(""), "");
+    }
+
+    #[test]
+    fn aggregated_page_keeps_scopes_with_warnings() {
+        let page: AggregatedPage = serde_json::from_str(
+            r#"{"items": {
+                "zones/us-central1-a": {"instances": [{"name": "vm"}]},
+                "zones/us-east1-b": {"warning": {"code": "NO_RESULTS_ON_PAGE"}}
+            }}"#,
+        )
+        .unwrap();
+        assert_eq!(page.items.len(), 2);
+        assert!(page.items["zones/us-central1-a"].contains_key("instances"));
+        assert!(!page.items["zones/us-east1-b"].contains_key("instances"));
+        assert!(page.next_page_token.is_none());
+    }
+}
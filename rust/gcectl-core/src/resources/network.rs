@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::short_name;
+use crate::output::{Details, Render};
+
+/// A VPC network.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Network {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_create_subnetworks: Option<bool>,
+    // set only on legacy networks, which have no subnets
+    #[serde(default, rename = "IPv4Range", skip_serializing_if = "Option::is_none")]
+    pub ipv4_range: Option<String>,
+    // full URLs of the network's subnets
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subnetworks: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing_config: Option<RoutingConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_timestamp: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoutingConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing_mode: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Network {
+    /// `auto`, `custom`, or `legacy` subnet mode.
+    pub fn mode(&self) -> &'static str {
+        match (self.ipv4_range.is_some(), self.auto_create_subnetworks) {
+            (true, _) => "legacy",
+            (false, Some(true)) => "auto",
+            (false, _) => "custom",
+        }
+    }
+
+    pub fn routing_mode(&self) -> &str {
+        self.routing_config
+            .as_ref()
+            .and_then(|c| c.routing_mode.as_deref())
+            .unwrap_or("")
+    }
+}
+
+impl Render for Network {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Mode", "Subnets", "Routing", "MTU"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.mode().to_string(),
+            self.subnetworks.len().to_string(),
+            self.routing_mode().to_string(),
+            self.mtu.map(|m| m.to_string()).unwrap_or_default(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Id", self.id.as_deref())
+            .field_opt("Description", self.description.as_deref())
+            .field("Mode", self.mode())
+            .field_opt("Range", self.ipv4_range.as_deref())
+            .field("Routing", self.routing_mode())
+            .field_opt("MTU", self.mtu.map(|m| m.to_string()))
+            .field_opt("Created", self.creation_timestamp.as_deref())
+            .group("Subnets", |d| {
+                for subnet in &self.subnetworks {
+                    d.field(short_name(subnet), subnet_region(subnet));
+                }
+            });
+        details
+    }
+}
+
+/// Region segment of a subnet URL
+/// (`.../regions/us-central1/subnetworks/default` → `us-central1`).
+fn subnet_region(url: &str) -> &str {
+    url.split_once("/regions/")
+        .and_then(|(_, rest)| rest.split('/').next())
+        .unwrap_or("")
+}
+
+/// A regional subnet of a VPC network.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Subnetwork {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // full URLs of the region and network
+    #[serde(default)]
+    pub region: String,
+    #[serde(default)]
+    pub network: String,
+    #[serde(default)]
+    pub ip_cidr_range: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secondary_ip_ranges: Vec<SecondaryRange>,
+    #[serde(default)]
+    pub private_ip_google_access: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub self_link: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_timestamp: Option<String>,
+    // filled in by `subnets describe` from the instances attached to the subnet
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub addresses_in_use: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecondaryRange {
+    pub range_name: String,
+    pub ip_cidr_range: String,
+}
+
+impl Subnetwork {
+    pub fn region_name(&self) -> &str {
+        short_name(&self.region)
+    }
+
+    pub fn network_name(&self) -> &str {
+        short_name(&self.network)
+    }
+
+    /// Primary-range addresses instances can use.
+    pub fn usable_addresses(&self) -> Option<u64> {
+        usable_addresses(&self.ip_cidr_range)
+    }
+
+    /// Usable addresses not taken by an instance, when usage is known.
+    pub fn available_addresses(&self) -> Option<u64> {
+        Some(
+            self.usable_addresses()?
+                .saturating_sub(self.addresses_in_use?),
+        )
+    }
+
+    fn secondary_ranges(&self) -> Vec<String> {
+        self.secondary_ip_ranges
+            .iter()
+            .map(|r| format!("{}={}", r.range_name, r.ip_cidr_range))
+            .collect()
+    }
+}
+
+/// Addresses in an IPv4 `cidr` range less the four Compute Engine reserves
+/// (network, gateway, second-to-last, and broadcast).
+pub fn usable_addresses(cidr: &str) -> Option<u64> {
+    let (_, prefix) = cidr.split_once('/')?;
+    let prefix: u32 = prefix.parse().ok().filter(|p| *p <= 32)?;
+    Some((1u64 << (32 - prefix)).saturating_sub(4))
+}
+
+impl Render for Subnetwork {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Name",
+            "Region",
+            "Network",
+            "Range",
+            "Secondary-Ranges",
+            "Usable-IPs",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.region_name().to_string(),
+            self.network_name().to_string(),
+            self.ip_cidr_range.clone(),
+            self.secondary_ranges().join(","),
+            self.usable_addresses()
+                .map(|n| n.to_string())
+                .unwrap_or_default(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Id", self.id.as_deref())
+            .field_opt("Description", self.description.as_deref())
+            .field("Region", self.region_name())
+            .field("Network", self.network_name())
+            .field("Range", &self.ip_cidr_range)
+            .field_opt("Gateway", self.gateway_address.as_deref())
+            .field_opt("Usable-IPs", self.usable_addresses().map(|n| n.to_string()))
+            .field_opt("In-Use-IPs", self.addresses_in_use.map(|n| n.to_string()))
+            .field_opt(
+                "Available-IPs",
+                self.available_addresses().map(|n| n.to_string()),
+            )
+            .field(
+                "Private-Google-Access",
+                self.private_ip_google_access.to_string(),
+            )
+            .field_opt("Purpose", self.purpose.as_deref())
+            .field_opt("Self-Link", self.self_link.as_deref())
+            .field_opt("Created", self.creation_timestamp.as_deref())
+            .group("Secondary-Ranges", |d| {
+                for range in &self.secondary_ip_ranges {
+                    d.field(&range.range_name, &range.ip_cidr_range);
+                }
+            });
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_usable_addresses() {
+        assert_eq!(usable_addresses("10.128.0.0/20"), Some(4092));
+        assert_eq!(usable_addresses("10.0.0.0/29"), Some(4));
+        assert_eq!(usable_addresses("10.0.0.0/33"), None);
+        assert_eq!(usable_addresses("10.0.0.0"), None);
+    }
+
+    #[test]
+    fn subtracts_addresses_in_use() {
+        let mut subnet = Subnetwork {
+            ip_cidr_range: "10.0.0.0/24".into(),
+            ..Default::default()
+        };
+        assert_eq!(subnet.available_addresses(), None);
+        subnet.addresses_in_use = Some(2);
+        assert_eq!(subnet.available_addresses(), Some(250));
+    }
+
+    #[test]
+    fn network_mode() {
+        let network: Network = serde_json::from_str(
+            r#"{
+                "name": "default",
+                "autoCreateSubnetworks": true,
+                "subnetworks": ["https://x/regions/us-central1/subnetworks/default"],
+                "routingConfig": {"routingMode": "REGIONAL"}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(network.row(), ["default", "auto", "1", "REGIONAL", ""]);
+        assert_eq!(subnet_region(&network.subnetworks[0]), "us-central1");
+    }
+}
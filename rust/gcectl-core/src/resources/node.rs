@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::short_name;
+use crate::output::{Details, Render};
+
+/// Node affinity key matching the nodes of one sole-tenant node group.
+pub const NODE_GROUP_AFFINITY_KEY: &str = "compute.googleapis.com/node-group-name";
+
+/// A regional template describing the sole-tenant nodes of node groups.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeTemplate {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // full URL of the region
+    #[serde(default)]
+    pub region: String,
+    // e.g. `n2-node-80-640`
+    #[serde(default)]
+    pub node_type: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub node_affinity_labels: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_binding: Option<ServerBinding>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_overcommit_type: Option<String>,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_timestamp: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerBinding {
+    // `RESTART_NODE_ON_ANY_SERVER` or `RESTART_NODE_ON_MINIMAL_SERVERS`
+    #[serde(default, rename = "type")]
+    pub kind: String,
+}
+
+/// A zonal group of sole-tenant nodes built from one node template.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeGroup {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // full URL of the zone
+    #[serde(default)]
+    pub zone: String,
+    // full URL of the node template
+    #[serde(default)]
+    pub node_template: String,
+    #[serde(default)]
+    pub size: u32,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance_policy: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autoscaling_policy: Option<AutoscalingPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_timestamp: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoscalingPolicy {
+    // `OFF`, `ON`, or `ONLY_SCALE_OUT`
+    #[serde(default)]
+    pub mode: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_nodes: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_nodes: Option<u32>,
+}
+
+impl NodeTemplate {
+    pub fn region_name(&self) -> &str {
+        short_name(&self.region)
+    }
+}
+
+impl NodeGroup {
+    pub fn zone_name(&self) -> &str {
+        short_name(&self.zone)
+    }
+
+    pub fn template_name(&self) -> &str {
+        short_name(&self.node_template)
+    }
+}
+
+/// Fields of a node template to create.
+#[derive(Debug, Clone)]
+pub struct NodeTemplateSpec {
+    pub name: String,
+    pub description: Option<String>,
+    pub node_type: String,
+    pub affinity_labels: Vec<(String, String)>,
+    // keep instances on the same physical server across maintenance, for
+    // per-core or per-socket licenses
+    pub minimal_servers: bool,
+}
+
+impl NodeTemplateSpec {
+    /// Request body for `nodeTemplates.insert`.
+    pub fn to_body(&self) -> Value {
+        let mut body = json!({ "name": self.name, "nodeType": self.node_type });
+        if let Some(description) = &self.description {
+            body["description"] = json!(description);
+        }
+        if !self.affinity_labels.is_empty() {
+            let labels: BTreeMap<_, _> = self.affinity_labels.iter().cloned().collect();
+            body["nodeAffinityLabels"] = json!(labels);
+        }
+        if self.minimal_servers {
+            body["serverBinding"] = json!({ "type": "RESTART_NODE_ON_MINIMAL_SERVERS" });
+        }
+        body
+    }
+}
+
+/// What happens to a node group's instances during host maintenance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum MaintenancePolicy {
+    /// Live-migrate instances to other hosts, leaving the group
+    Default,
+    /// Restart instances on the same physical server
+    RestartInPlace,
+    /// Live-migrate instances within the group's own nodes
+    MigrateWithinNodeGroup,
+}
+
+impl MaintenancePolicy {
+    pub fn api_name(self) -> &'static str {
+        match self {
+            Self::Default => "DEFAULT",
+            Self::RestartInPlace => "RESTART_IN_PLACE",
+            Self::MigrateWithinNodeGroup => "MIGRATE_WITHIN_NODE_GROUP",
+        }
+    }
+}
+
+/// Fields of a node group to create; the node count goes in the query.
+#[derive(Debug, Clone)]
+pub struct NodeGroupSpec {
+    pub name: String,
+    pub description: Option<String>,
+    // URL path of the node template
+    pub node_template: String,
+    pub maintenance_policy: Option<MaintenancePolicy>,
+    pub autoscale_max: Option<u32>,
+}
+
+impl NodeGroupSpec {
+    /// Request body for `nodeGroups.insert`.
+    pub fn to_body(&self) -> Value {
+        let mut body = json!({ "name": self.name, "nodeTemplate": self.node_template });
+        if let Some(description) = &self.description {
+            body["description"] = json!(description);
+        }
+        if let Some(policy) = self.maintenance_policy {
+            body["maintenancePolicy"] = json!(policy.api_name());
+        }
+        if let Some(max) = self.autoscale_max {
+            body["autoscalingPolicy"] = json!({ "mode": "ON", "maxNodes": max });
+        }
+        body
+    }
+}
+
+impl Render for NodeTemplate {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Region", "Node-Type", "Status"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.region_name().to_string(),
+            self.node_type.clone(),
+            self.status.clone(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Id", self.id.as_deref())
+            .field_opt("Description", self.description.as_deref())
+            .field("Region", self.region_name())
+            .field("Node-Type", &self.node_type)
+            .field_opt(
+                "Server-Binding",
+                self.server_binding.as_ref().map(|b| b.kind.as_str()),
+            )
+            .field_opt("CPU-Overcommit", self.cpu_overcommit_type.as_deref())
+            .field("Status", &self.status)
+            .field_opt("Created", self.creation_timestamp.as_deref());
+        details.group("Affinity-Labels", |group| {
+            for (key, value) in &self.node_affinity_labels {
+                group.field(key, value);
+            }
+        });
+        details
+    }
+}
+
+impl Render for NodeGroup {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Zone", "Template", "Nodes", "Status"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.zone_name().to_string(),
+            self.template_name().to_string(),
+            self.size.to_string(),
+            self.status.clone(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Id", self.id.as_deref())
+            .field_opt("Description", self.description.as_deref())
+            .field("Zone", self.zone_name())
+            .field("Template", self.template_name())
+            .field("Nodes", self.size.to_string())
+            .field_opt("Maintenance-Policy", self.maintenance_policy.as_deref())
+            .field("Status", &self.status)
+            .field_opt("Created", self.creation_timestamp.as_deref());
+        if let Some(policy) = &self.autoscaling_policy {
+            details.group("Autoscaling", |d| {
+                d.field("Mode", &policy.mode)
+                    .field_opt("Min-Nodes", policy.min_nodes.map(|n| n.to_string()))
+                    .field_opt("Max-Nodes", policy.max_nodes.map(|n| n.to_string()));
+            });
+        }
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_body_binds_to_minimal_servers() {
+        let spec = NodeTemplateSpec {
+            name: "licensed".into(),
+            description: None,
+            node_type: "n2-node-80-640".into(),
+            affinity_labels: vec![("workload".into(), "sql".into())],
+            minimal_servers: true,
+        };
+        let template: NodeTemplate = serde_json::from_value(spec.to_body()).unwrap();
+        assert_eq!(template.node_type, "n2-node-80-640");
+        assert_eq!(template.node_affinity_labels["workload"], "sql");
+        assert_eq!(
+            template.server_binding.unwrap().kind,
+            "RESTART_NODE_ON_MINIMAL_SERVERS"
+        );
+    }
+
+    #[test]
+    fn group_body_and_row() {
+        let spec = NodeGroupSpec {
+            name: "sql-nodes".into(),
+            description: None,
+            node_template: "projects/p/regions/us-central1/nodeTemplates/licensed".into(),
+            maintenance_policy: Some(MaintenancePolicy::MigrateWithinNodeGroup),
+            autoscale_max: Some(4),
+        };
+        let mut group: NodeGroup = serde_json::from_value(spec.to_body()).unwrap();
+        group.zone = "https://x/zones/us-central1-a".into();
+        group.size = 2;
+        assert_eq!(
+            group.row(),
+            ["sql-nodes", "us-central1-a", "licensed", "2", ""]
+        );
+        assert_eq!(group.autoscaling_policy.unwrap().max_nodes, Some(4));
+        assert_eq!(
+            group.maintenance_policy.as_deref(),
+            Some("MIGRATE_WITHIN_NODE_GROUP")
+        );
+    }
+}
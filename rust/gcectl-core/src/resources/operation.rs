@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::short_name;
+use crate::output::{Details, Render};
+
+/// A long-running Compute Engine operation (zonal, regional, or global).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Operation {
+    pub name: String,
+    #[serde(default)]
+    pub operation_type: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub target_link: String,
+    #[serde(default)]
+    pub self_link: String,
+    // set for zonal operations only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+    // set for regional operations only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insert_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<OperationError>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Where an operation lives; each scope has its own `operations` collection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OperationScope {
+    Zone(String),
+    Region(String),
+    Global,
+}
+
+impl OperationScope {
+    pub fn path(&self, project: &str) -> String {
+        match self {
+            Self::Zone(zone) => format!("projects/{project}/zones/{zone}/operations"),
+            Self::Region(region) => format!("projects/{project}/regions/{region}/operations"),
+            Self::Global => format!("projects/{project}/global/operations"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OperationError {
+    #[serde(default)]
+    pub errors: Vec<OperationErrorItem>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OperationErrorItem {
+    #[serde(default)]
+    pub code: String,
+    #[serde(default)]
+    pub message: String,
+}
+
+impl Operation {
+    pub fn is_done(&self) -> bool {
+        self.status == "DONE"
+    }
+
+    /// API path of the operation relative to the `compute/v1` root, derived
+    /// from its `selfLink` so zonal, regional, and global operations are all
+    /// polled the same way.
+    pub fn path(&self) -> &str {
+        self.self_link
+            .find("projects/")
+            .map_or(self.self_link.as_str(), |i| &self.self_link[i..])
+    }
+
+    /// `zone`, `region`, or `global`, whichever the operation belongs to.
+    pub fn scope_name(&self) -> &str {
+        match (&self.zone, &self.region) {
+            (Some(zone), _) => short_name(zone),
+            (None, Some(region)) => short_name(region),
+            (None, None) => "global",
+        }
+    }
+
+    /// Type and name of the resource the operation acts on, e.g.
+    /// `instances/my-vm`.
+    pub fn target(&self) -> String {
+        let mut parts = self.target_link.rsplit('/');
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(kind)) if !name.is_empty() => format!("{kind}/{name}"),
+            _ => "-".to_string(),
+        }
+    }
+
+    /// Joined error messages if the operation failed.
+    pub fn error_message(&self) -> Option<String> {
+        let errors = &self.error.as_ref()?.errors;
+        if errors.is_empty() {
+            return None;
+        }
+        Some(
+            errors
+                .iter()
+                .map(|e| format!("{}: {}", e.code, e.message))
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+}
+
+impl Render for Operation {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Name", "Type", "Target", "Scope", "Status", "Progress", "Started",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.operation_type.clone(),
+            self.target(),
+            self.scope_name().to_string(),
+            self.status.clone(),
+            self.progress
+                .map_or_else(|| "-".to_string(), |p| format!("{p}%")),
+            self.insert_time.clone().unwrap_or_else(|| "-".to_string()),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field("Type", &self.operation_type)
+            .field("Target", self.target())
+            .field("Scope", self.scope_name())
+            .field("Status", &self.status)
+            .field_opt("Progress", self.progress.map(|p| format!("{p}%")))
+            .field_opt("User", self.user.as_deref())
+            .field_opt("Started", self.insert_time.as_deref())
+            .field_opt("Ended", self.end_time.as_deref())
+            .field_opt("Error", self.error_message());
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_operation_errors() {
+        let body = r#"{
+            "name": "operation-123",
+            "status": "DONE",
+            "targetLink": "https://www.googleapis.com/compute/v1/projects/p/zones/z/instances/vm",
+            "selfLink": "https://www.googleapis.com/compute/v1/projects/p/zones/z/operations/operation-123",
+            "error": {"errors": [{"code": "ZONE_RESOURCE_POOL_EXHAUSTED", "message": "no capacity"}]}
+        }"#;
+        let op: Operation = serde_json::from_str(body).unwrap();
+        assert!(op.is_done());
+        assert_eq!(op.path(), "projects/p/zones/z/operations/operation-123");
+        assert_eq!(
+            op.error_message().as_deref(),
+            Some("ZONE_RESOURCE_POOL_EXHAUSTED: no capacity")
+        );
+    }
+
+    #[test]
+    fn successful_operation_has_no_error() {
+        let op: Operation =
+            serde_json::from_str(r#"{"name": "operation-1", "status": "RUNNING"}"#).unwrap();
+        assert!(!op.is_done());
+        assert_eq!(op.error_message(), None);
+    }
+
+    #[test]
+    fn describes_scope_and_target() {
+        let op: Operation = serde_json::from_str(
+            r#"{
+                "name": "operation-2",
+                "operationType": "stop",
+                "targetLink": "https://www.googleapis.com/compute/v1/projects/p/zones/z/instances/vm",
+                "zone": "https://www.googleapis.com/compute/v1/projects/p/zones/us-central1-a",
+                "progress": 40
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(op.scope_name(), "us-central1-a");
+        assert_eq!(op.target(), "instances/vm");
+        assert_eq!(op.row()[5], "40%");
+
+        let global: Operation = serde_json::from_str(r#"{"name": "operation-3"}"#).unwrap();
+        assert_eq!(global.scope_name(), "global");
+        assert_eq!(global.target(), "-");
+    }
+
+    #[test]
+    fn scope_paths() {
+        assert_eq!(
+            OperationScope::Region("us-central1".into()).path("p"),
+            "projects/p/regions/us-central1/operations"
+        );
+        assert_eq!(
+            OperationScope::Global.path("p"),
+            "projects/p/global/operations"
+        );
+    }
+}
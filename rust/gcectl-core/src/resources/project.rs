@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::Quota;
+use super::instance::Metadata;
+use crate::output::{Details, Render};
+
+/// A project's Compute Engine settings as returned by `projects.get`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_timestamp: Option<String>,
+    // service account new instances run as when none is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_service_account: Option<String>,
+    // PREMIUM or STANDARD, for external IPs created without a tier
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_network_tier: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage_export_location: Option<UsageExportLocation>,
+    // HOST_PROJECT when the project shares its VPC networks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xpn_project_status: Option<String>,
+    // metadata every instance in the project inherits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub common_instance_metadata: Option<Metadata>,
+    // project-wide quotas such as snapshots, networks, and firewall rules
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quotas: Vec<Quota>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Where daily usage reports of the project are written; the body of
+/// `projects.setUsageExportBucket`, which an empty location turns off.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageExportLocation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bucket_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report_name_prefix: Option<String>,
+}
+
+impl Project {
+    /// `gs://bucket/prefix` of the usage reports, if they are exported.
+    pub fn usage_export(&self) -> Option<String> {
+        let location = self.usage_export_location.as_ref()?;
+        let bucket = location.bucket_name.as_deref()?;
+        let bucket = bucket.strip_prefix("gs://").unwrap_or(bucket);
+        Some(match location.report_name_prefix.as_deref() {
+            Some(prefix) => format!("gs://{bucket}/{prefix}"),
+            None => format!("gs://{bucket}"),
+        })
+    }
+}
+
+impl Render for Project {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Name",
+            "Default-Service-Account",
+            "Network-Tier",
+            "Usage-Export",
+            "Metadata-Keys",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.default_service_account.clone().unwrap_or_default(),
+            self.default_network_tier.clone().unwrap_or_default(),
+            self.usage_export().unwrap_or_default(),
+            self.common_instance_metadata
+                .as_ref()
+                .map_or(0, |metadata| metadata.items.len())
+                .to_string(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Id", self.id.as_deref())
+            .field_opt("Created", self.creation_timestamp.as_deref())
+            .field_opt(
+                "Default-Service-Account",
+                self.default_service_account.as_deref(),
+            )
+            .field_opt("Network-Tier", self.default_network_tier.as_deref())
+            .field("Usage-Export", self.usage_export().unwrap_or("off".into()))
+            .field_opt("Shared-VPC", self.xpn_project_status.as_deref());
+        details.group("Common-Metadata", |group| {
+            for item in self.common_instance_metadata.iter().flat_map(|m| &m.items) {
+                group.field(&item.key, &item.value);
+            }
+        });
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usage_export_joins_bucket_and_prefix() {
+        let mut project = Project {
+            usage_export_location: Some(UsageExportLocation {
+                bucket_name: Some("gs://usage-reports".into()),
+                report_name_prefix: Some("compute".into()),
+            }),
+            ..Project::default()
+        };
+        assert_eq!(
+            project.usage_export().as_deref(),
+            Some("gs://usage-reports/compute")
+        );
+        project.usage_export_location = Some(UsageExportLocation::default());
+        assert_eq!(project.usage_export(), None);
+    }
+}
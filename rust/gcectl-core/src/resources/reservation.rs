@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::{Accelerator, short_name};
+use crate::output::{Details, Render};
+
+/// Zonal capacity held for VMs of one shape, billed whether or not
+/// instances consume it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reservation {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // full URL of the zone
+    #[serde(default)]
+    pub zone: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub specific_reservation: Option<SpecificReservation>,
+    // only instances naming the reservation may consume it
+    #[serde(default)]
+    pub specific_reservation_required: bool,
+    // full URL of the commitment the reservation is bundled with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commitment: Option<String>,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_timestamp: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpecificReservation {
+    // int64 counts, encoded as strings by the API
+    #[serde(default)]
+    pub count: String,
+    #[serde(default)]
+    pub in_use_count: String,
+    #[serde(default)]
+    pub instance_properties: ReservedProperties,
+}
+
+/// The VM shape a reservation holds capacity for.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservedProperties {
+    // a bare machine type name, e.g. `n2-standard-4`
+    #[serde(default)]
+    pub machine_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_cpu_platform: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub guest_accelerators: Vec<ReservedAccelerator>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservedAccelerator {
+    #[serde(default)]
+    pub accelerator_type: String,
+    #[serde(default)]
+    pub accelerator_count: u32,
+}
+
+impl Reservation {
+    pub fn zone_name(&self) -> &str {
+        short_name(&self.zone)
+    }
+
+    pub fn machine_type(&self) -> &str {
+        self.specific_reservation
+            .as_ref()
+            .map_or("", |s| s.instance_properties.machine_type.as_str())
+    }
+
+    /// `IN-USE/COUNT`, e.g. `3/5`.
+    pub fn usage(&self) -> String {
+        self.specific_reservation
+            .as_ref()
+            .map_or_else(String::new, |s| {
+                format!("{}/{}", or_zero(&s.in_use_count), or_zero(&s.count))
+            })
+    }
+}
+
+fn or_zero(count: &str) -> &str {
+    if count.is_empty() { "0" } else { count }
+}
+
+/// Fields of a reservation to create.
+#[derive(Debug, Clone)]
+pub struct ReservationSpec {
+    pub name: String,
+    pub description: Option<String>,
+    pub machine_type: String,
+    pub count: u32,
+    pub accelerators: Vec<Accelerator>,
+    pub min_cpu_platform: Option<String>,
+    pub specific_only: bool,
+}
+
+impl ReservationSpec {
+    /// Request body for `reservations.insert`.
+    pub fn to_body(&self) -> Value {
+        let mut properties = json!({ "machineType": self.machine_type });
+        if let Some(platform) = &self.min_cpu_platform {
+            properties["minCpuPlatform"] = json!(platform);
+        }
+        if !self.accelerators.is_empty() {
+            properties["guestAccelerators"] = self
+                .accelerators
+                .iter()
+                .map(|a| {
+                    json!({ "acceleratorType": a.accelerator_type, "acceleratorCount": a.count })
+                })
+                .collect();
+        }
+        let mut body = json!({
+            "name": self.name,
+            "specificReservation": {
+                "count": self.count.to_string(),
+                "instanceProperties": properties,
+            },
+            "specificReservationRequired": self.specific_only,
+        });
+        if let Some(description) = &self.description {
+            body["description"] = json!(description);
+        }
+        body
+    }
+}
+
+impl Render for Reservation {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Name",
+            "Zone",
+            "Machine-Type",
+            "In-Use",
+            "Specific-Only",
+            "Status",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.zone_name().to_string(),
+            self.machine_type().to_string(),
+            self.usage(),
+            self.specific_reservation_required.to_string(),
+            self.status.clone(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Id", self.id.as_deref())
+            .field_opt("Description", self.description.as_deref())
+            .field("Zone", self.zone_name())
+            .field("Machine-Type", self.machine_type())
+            .field("In-Use", self.usage())
+            .field(
+                "Specific-Only",
+                self.specific_reservation_required.to_string(),
+            )
+            .field_opt("Commitment", self.commitment.as_deref().map(short_name))
+            .field("Status", &self.status)
+            .field_opt("Created", self.creation_timestamp.as_deref());
+        if let Some(specific) = &self.specific_reservation {
+            let properties = &specific.instance_properties;
+            details.field_opt("Min-CPU-Platform", properties.min_cpu_platform.as_deref());
+            details.group("Accelerators", |group| {
+                for gpu in &properties.guest_accelerators {
+                    group.field(&gpu.accelerator_type, gpu.accelerator_count.to_string());
+                }
+            });
+        }
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_round_trips_into_a_row() {
+        let spec = ReservationSpec {
+            name: "gpu-pool".into(),
+            description: None,
+            machine_type: "n1-standard-8".into(),
+            count: 4,
+            accelerators: vec![Accelerator {
+                accelerator_type: "nvidia-tesla-t4".into(),
+                count: 2,
+            }],
+            min_cpu_platform: None,
+            specific_only: true,
+        };
+        let body = spec.to_body();
+        assert_eq!(body["specificReservation"]["count"], "4");
+        let mut reservation: Reservation = serde_json::from_value(body).unwrap();
+        reservation.zone = "https://x/zones/us-central1-a".into();
+        reservation
+            .specific_reservation
+            .as_mut()
+            .unwrap()
+            .in_use_count = "1".into();
+        assert_eq!(
+            reservation.row(),
+            [
+                "gpu-pool",
+                "us-central1-a",
+                "n1-standard-8",
+                "1/4",
+                "true",
+                ""
+            ]
+        );
+        let gpus = &reservation
+            .specific_reservation
+            .unwrap()
+            .instance_properties
+            .guest_accelerators;
+        assert_eq!(gpus[0].accelerator_count, 2);
+    }
+}
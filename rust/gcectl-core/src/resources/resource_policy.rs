@@ -0,0 +1,328 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::short_name;
+use crate::output::{Details, Render};
+
+/// A regional resource policy: a snapshot schedule attached to disks or a
+/// start/stop schedule attached to instances.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourcePolicy {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // full URL of the region
+    #[serde(default)]
+    pub region: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_schedule_policy: Option<SnapshotSchedulePolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance_schedule_policy: Option<InstanceSchedulePolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_timestamp: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotSchedulePolicy {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_policy: Option<RetentionPolicy>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPolicy {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retention_days: Option<u32>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceSchedulePolicy {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vm_start_schedule: Option<CronSchedule>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vm_stop_schedule: Option<CronSchedule>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CronSchedule {
+    #[serde(default)]
+    pub schedule: String,
+}
+
+impl ResourcePolicy {
+    pub fn region_name(&self) -> &str {
+        short_name(&self.region)
+    }
+
+    /// `snapshot-schedule`, `instance-schedule`, or `other` for policy
+    /// types gcectl does not create.
+    pub fn kind(&self) -> &'static str {
+        match (
+            &self.snapshot_schedule_policy,
+            &self.instance_schedule_policy,
+        ) {
+            (Some(_), _) => "snapshot-schedule",
+            (None, Some(_)) => "instance-schedule",
+            (None, None) => "other",
+        }
+    }
+
+    /// One-line description of when the policy acts.
+    pub fn schedule(&self) -> String {
+        if let Some(policy) = &self.snapshot_schedule_policy {
+            let mut parts = policy
+                .schedule
+                .as_ref()
+                .map(describe_snapshot_schedule)
+                .into_iter()
+                .collect::<Vec<_>>();
+            if let Some(days) = policy
+                .retention_policy
+                .as_ref()
+                .and_then(|r| r.max_retention_days)
+            {
+                parts.push(format!("keep {days}d"));
+            }
+            return parts.join(", ");
+        }
+        if let Some(policy) = &self.instance_schedule_policy {
+            let mut parts = Vec::new();
+            if let Some(start) = &policy.vm_start_schedule {
+                parts.push(format!("start `{}`", start.schedule));
+            }
+            if let Some(stop) = &policy.vm_stop_schedule {
+                parts.push(format!("stop `{}`", stop.schedule));
+            }
+            if let Some(zone) = &policy.time_zone {
+                parts.push(zone.clone());
+            }
+            return parts.join(", ");
+        }
+        String::new()
+    }
+}
+
+/// `daily at 04:00`, `every 6h from 00:00`, or `weekly on MONDAY 04:00`
+/// for a `snapshotSchedulePolicy.schedule`; times are UTC.
+fn describe_snapshot_schedule(schedule: &Value) -> String {
+    let text = |v: &Value, key: &str| v[key].as_str().unwrap_or_default().to_string();
+    if let Some(hourly) = schedule.get("hourlySchedule") {
+        return format!(
+            "every {}h from {} UTC",
+            hourly["hoursInCycle"],
+            text(hourly, "startTime")
+        );
+    }
+    if let Some(daily) = schedule.get("dailySchedule") {
+        return match daily["daysInCycle"].as_u64() {
+            Some(1) | None => format!("daily at {} UTC", text(daily, "startTime")),
+            Some(days) => format!("every {days}d at {} UTC", text(daily, "startTime")),
+        };
+    }
+    if let Some(weekly) = schedule.get("weeklySchedule") {
+        let days: Vec<String> = weekly["dayOfWeeks"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|d| format!("{} {}", text(d, "day"), text(d, "startTime")))
+            .collect();
+        return format!("weekly on {} UTC", days.join(", "));
+    }
+    String::new()
+}
+
+/// Cadence of a snapshot schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotCadence {
+    Hourly(u32),
+    Daily(u32),
+}
+
+/// Fields of a snapshot schedule to create.
+#[derive(Debug, Clone)]
+pub struct SnapshotScheduleSpec {
+    pub name: String,
+    pub description: Option<String>,
+    pub cadence: SnapshotCadence,
+    // `HH:MM` in UTC; the API rounds to the hour
+    pub start_time: String,
+    pub retention_days: u32,
+    pub storage_locations: Vec<String>,
+}
+
+impl SnapshotScheduleSpec {
+    /// Request body for `resourcePolicies.insert`.
+    pub fn to_body(&self) -> Value {
+        let schedule = match self.cadence {
+            SnapshotCadence::Hourly(hours) => json!({
+                "hourlySchedule": { "hoursInCycle": hours, "startTime": self.start_time },
+            }),
+            SnapshotCadence::Daily(days) => json!({
+                "dailySchedule": { "daysInCycle": days, "startTime": self.start_time },
+            }),
+        };
+        let mut policy = json!({
+            "schedule": schedule,
+            "retentionPolicy": {
+                "maxRetentionDays": self.retention_days,
+                "onSourceDiskDelete": "KEEP_AUTO_SNAPSHOTS",
+            },
+        });
+        if !self.storage_locations.is_empty() {
+            policy["snapshotProperties"] = json!({ "storageLocations": self.storage_locations });
+        }
+        with_description(
+            json!({ "name": self.name, "snapshotSchedulePolicy": policy }),
+            self.description.as_deref(),
+        )
+    }
+}
+
+/// Fields of an instance start/stop schedule to create.
+#[derive(Debug, Clone)]
+pub struct InstanceScheduleSpec {
+    pub name: String,
+    pub description: Option<String>,
+    // unix cron expressions, e.g. `0 9 * * 1-5`
+    pub start: Option<String>,
+    pub stop: Option<String>,
+    // IANA name, e.g. `Asia/Tokyo`
+    pub time_zone: String,
+}
+
+impl InstanceScheduleSpec {
+    /// Request body for `resourcePolicies.insert`.
+    pub fn to_body(&self) -> Value {
+        let mut policy = json!({ "timeZone": self.time_zone });
+        if let Some(start) = &self.start {
+            policy["vmStartSchedule"] = json!({ "schedule": start });
+        }
+        if let Some(stop) = &self.stop {
+            policy["vmStopSchedule"] = json!({ "schedule": stop });
+        }
+        with_description(
+            json!({ "name": self.name, "instanceSchedulePolicy": policy }),
+            self.description.as_deref(),
+        )
+    }
+}
+
+fn with_description(mut body: Value, description: Option<&str>) -> Value {
+    if let Some(description) = description {
+        body["description"] = json!(description);
+    }
+    body
+}
+
+impl Render for ResourcePolicy {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Kind", "Region", "Schedule", "Status"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.kind().to_string(),
+            self.region_name().to_string(),
+            self.schedule(),
+            self.status.clone(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Id", self.id.as_deref())
+            .field_opt("Description", self.description.as_deref())
+            .field("Kind", self.kind())
+            .field("Region", self.region_name())
+            .field("Schedule", self.schedule())
+            .field("Status", &self.status)
+            .field_opt("Created", self.creation_timestamp.as_deref());
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_schedule_body_and_summary_agree() {
+        let spec = SnapshotScheduleSpec {
+            name: "daily".into(),
+            description: None,
+            cadence: SnapshotCadence::Daily(1),
+            start_time: "04:00".into(),
+            retention_days: 14,
+            storage_locations: vec!["asia".into()],
+        };
+        let body = spec.to_body();
+        assert_eq!(
+            body["snapshotSchedulePolicy"]["schedule"],
+            json!({ "dailySchedule": { "daysInCycle": 1, "startTime": "04:00" } })
+        );
+        assert_eq!(
+            body["snapshotSchedulePolicy"]["snapshotProperties"]["storageLocations"],
+            json!(["asia"])
+        );
+        let policy: ResourcePolicy = serde_json::from_value(body).unwrap();
+        assert_eq!(policy.kind(), "snapshot-schedule");
+        assert_eq!(policy.schedule(), "daily at 04:00 UTC, keep 14d");
+    }
+
+    #[test]
+    fn instance_schedule_summary() {
+        let spec = InstanceScheduleSpec {
+            name: "office-hours".into(),
+            description: Some("dev VMs".into()),
+            start: Some("0 9 * * 1-5".into()),
+            stop: Some("0 20 * * 1-5".into()),
+            time_zone: "Asia/Tokyo".into(),
+        };
+        let policy: ResourcePolicy = serde_json::from_value(spec.to_body()).unwrap();
+        assert_eq!(policy.kind(), "instance-schedule");
+        assert_eq!(
+            policy.schedule(),
+            "start `0 9 * * 1-5`, stop `0 20 * * 1-5`, Asia/Tokyo"
+        );
+        assert_eq!(policy.description.as_deref(), Some("dev VMs"));
+    }
+
+    #[test]
+    fn describes_hourly_and_weekly_schedules() {
+        let hourly = json!({ "hourlySchedule": { "hoursInCycle": 6, "startTime": "00:00" } });
+        assert_eq!(
+            describe_snapshot_schedule(&hourly),
+            "every 6h from 00:00 UTC"
+        );
+        let weekly = json!({ "weeklySchedule": { "dayOfWeeks": [
+            { "day": "MONDAY", "startTime": "04:00" },
+        ] } });
+        assert_eq!(
+            describe_snapshot_schedule(&weekly),
+            "weekly on MONDAY 04:00 UTC"
+        );
+        assert_eq!(ResourcePolicy::default().kind(), "other");
+    }
+}
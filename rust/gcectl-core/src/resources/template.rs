@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::short_name;
+use crate::output::{Details, Render};
+
+/// A global instance template, the blueprint managed instance groups create
+/// their instances from.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceTemplate {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_timestamp: Option<String>,
+    #[serde(default)]
+    pub properties: TemplateProperties,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateProperties {
+    // a bare machine type name, since templates are not zonal
+    #[serde(default)]
+    pub machine_type: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl InstanceTemplate {
+    /// Source image of the boot disk.
+    pub fn boot_image(&self) -> Option<&str> {
+        self.properties
+            .extra
+            .get("disks")?
+            .as_array()?
+            .iter()
+            .find(|disk| disk["boot"] == true)?
+            .pointer("/initializeParams/sourceImage")?
+            .as_str()
+    }
+}
+
+impl Render for InstanceTemplate {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Machine-Type", "Boot-Image", "Created"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            short_name(&self.properties.machine_type).to_string(),
+            self.boot_image().unwrap_or("-").to_string(),
+            self.creation_timestamp.clone().unwrap_or_default(),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Id", self.id.as_deref())
+            .field_opt("Description", self.description.as_deref())
+            .field("Machine-Type", short_name(&self.properties.machine_type))
+            .field_opt("Boot-Image", self.boot_image())
+            .field_opt("Created", self.creation_timestamp.as_deref());
+        details.group("Labels", |group| {
+            for (key, value) in &self.properties.labels {
+                group.field(key, value);
+            }
+        });
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_shows_boot_image() {
+        let template: InstanceTemplate = serde_json::from_str(
+            r#"{
+                "name": "web-v1",
+                "properties": {
+                    "machineType": "e2-small",
+                    "disks": [{"boot": true, "initializeParams": {
+                        "sourceImage": "projects/debian-cloud/global/images/family/debian-12"
+                    }}]
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            template.row()[..3],
+            [
+                "web-v1",
+                "e2-small",
+                "projects/debian-cloud/global/images/family/debian-12"
+            ]
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::short_name;
+use crate::output::{Details, Render};
+
+/// A Compute Engine zone.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Zone {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // UP or DOWN
+    #[serde(default)]
+    pub status: String,
+    // full URL of the region
+    #[serde(default)]
+    pub region: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub available_cpu_platforms: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Zone {
+    pub fn region_name(&self) -> &str {
+        short_name(&self.region)
+    }
+}
+
+impl Render for Zone {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Region", "Status", "CPU-Platforms"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.region_name().to_string(),
+            self.status.clone(),
+            self.available_cpu_platforms.join(", "),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Description", self.description.as_deref())
+            .field("Region", self.region_name())
+            .field("Status", &self.status)
+            .field("CPU-Platforms", self.available_cpu_platforms.join(", "));
+        details
+    }
+}
+
+/// A Compute Engine region and its quotas.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Region {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub status: String,
+    // full URLs of the region's zones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub zones: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quotas: Vec<Quota>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Usage of one quota metric against its limit.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Quota {
+    pub metric: String,
+    #[serde(default)]
+    pub limit: f64,
+    #[serde(default)]
+    pub usage: f64,
+}
+
+impl Quota {
+    /// `usage/limit`, e.g. `12/24`.
+    pub fn summary(&self) -> String {
+        format!("{}/{}", self.usage, self.limit)
+    }
+
+    /// Usage as a percentage of the limit; `None` for a zero limit.
+    pub fn percent(&self) -> Option<f64> {
+        (self.limit > 0.0).then(|| self.usage / self.limit * 100.0)
+    }
+
+    fn percent_label(&self) -> String {
+        self.percent()
+            .map(|p| format!("{p:.0}%"))
+            .unwrap_or_default()
+    }
+}
+
+impl Render for Quota {
+    fn headers() -> Vec<&'static str> {
+        vec!["Metric", "Limit", "Usage", "Percent"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.metric.clone(),
+            self.limit.to_string(),
+            self.usage.to_string(),
+            self.percent_label(),
+        ]
+    }
+}
+
+impl Region {
+    pub fn quota(&self, metric: &str) -> Option<&Quota> {
+        self.quotas.iter().find(|q| q.metric == metric)
+    }
+
+    fn quota_summary(&self, metric: &str) -> String {
+        self.quota(metric).map(Quota::summary).unwrap_or_default()
+    }
+}
+
+impl Render for Region {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Name",
+            "Status",
+            "Zones",
+            "CPUs",
+            "Spot-CPUs",
+            "External-IPs",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.status.clone(),
+            self.zones.len().to_string(),
+            self.quota_summary("CPUS"),
+            self.quota_summary("PREEMPTIBLE_CPUS"),
+            self.quota_summary("IN_USE_ADDRESSES"),
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details
+            .field("Name", &self.name)
+            .field_opt("Description", self.description.as_deref())
+            .field("Status", &self.status)
+            .field(
+                "Zones",
+                self.zones
+                    .iter()
+                    .map(|z| short_name(z))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+            .group("Quotas", |d| {
+                for quota in &self.quotas {
+                    d.field(&quota.metric, quota.summary());
+                }
+            });
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_row_shows_quota_usage() {
+        let region: Region = serde_json::from_str(
+            r#"{
+                "name": "us-central1",
+                "status": "UP",
+                "zones": ["https://x/zones/us-central1-a", "https://x/zones/us-central1-b"],
+                "quotas": [
+                    {"metric": "CPUS", "limit": 24, "usage": 12},
+                    {"metric": "IN_USE_ADDRESSES", "limit": 8, "usage": 1}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(region.row(), ["us-central1", "UP", "2", "12/24", "", "1/8"]);
+    }
+
+    #[test]
+    fn quota_percent_ignores_zero_limits() {
+        let quota = |limit, usage| Quota {
+            metric: "CPUS".to_string(),
+            limit,
+            usage,
+        };
+        assert_eq!(quota(24.0, 18.0).percent(), Some(75.0));
+        assert_eq!(quota(0.0, 0.0).percent(), None);
+        assert_eq!(quota(8.0, 2.0).row(), ["CPUS", "8", "2", "25%"]);
+    }
+}
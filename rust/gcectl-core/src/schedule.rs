@@ -0,0 +1,399 @@
+//! Recurring start/stop rules, stored in `schedules.toml` next to the config
+//! file and executed by `gcectl schedule run`.
+//!
+//! ```toml
+//! [rules.dev-nightly-stop]
+//! action = "stop"
+//! at = "20:00"
+//! days = "mon-fri"
+//! timezone = "Asia/Tokyo"
+//! project = "my-project"
+//! filter = "labels.env=dev"
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{Context, Error, Result, anyhow, bail};
+use chrono::{DateTime, Datelike, Local, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::output::Render;
+
+const SCHEDULES_FILE_NAME: &str = "schedules.toml";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Schedules {
+    #[serde(default)]
+    pub rules: BTreeMap<String, Rule>,
+}
+
+/// Starts or stops every instance matching `filter` at `at` on `days`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub action: Action,
+    pub at: TimeOfDay,
+    pub days: Days,
+    // IANA name; the machine's local zone when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<Tz>,
+    pub project: String,
+    // every zone when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Start,
+    Stop,
+}
+
+/// Wall-clock time of day, written as `HH:MM`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay(NaiveTime);
+
+/// Set of weekdays, written as `daily`, `mon-fri`, or `sat,sun`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Days(u8);
+
+impl Schedules {
+    pub fn path() -> Result<PathBuf> {
+        Ok(Config::dir()?.join(SCHEDULES_FILE_NAME))
+    }
+
+    /// Loads the rules file, returning no rules if it does not exist.
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let body = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        toml::from_str(&body).with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        fs::write(&path, toml::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+impl Rule {
+    /// First time strictly after `after` at which the rule fires.
+    pub fn next_fire(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self.timezone {
+            Some(tz) => next_in(&tz, self.at.0, self.days, after),
+            None => next_in(&Local, self.at.0, self.days, after),
+        }
+    }
+
+    /// Whether the rule fired in the interval `(since, now]`.
+    pub fn is_due(&self, since: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        self.next_fire(since).is_some_and(|t| t <= now)
+    }
+
+    /// Instance status the action applies to; others are left alone.
+    pub fn applies_to_status(&self) -> &'static str {
+        match self.action {
+            Action::Start => "TERMINATED",
+            Action::Stop => "RUNNING",
+        }
+    }
+}
+
+fn next_in<Z: TimeZone>(
+    tz: &Z,
+    at: NaiveTime,
+    days: Days,
+    after: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let start = after.with_timezone(tz).date_naive();
+    // a week and a day covers every weekday even when today's slot has passed
+    (0..=7)
+        .filter_map(|offset| start.checked_add_days(chrono::Days::new(offset)))
+        .filter(|date| days.contains(date.weekday()))
+        // `earliest` skips times that fall into a DST gap
+        .filter_map(|date| tz.from_local_datetime(&date.and_time(at)).earliest())
+        .map(|t| t.with_timezone(&Utc))
+        .find(|t| *t > after)
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Start => "start",
+            Self::Stop => "stop",
+        })
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        NaiveTime::parse_from_str(s, "%H:%M")
+            .map(Self)
+            .map_err(|_| anyhow!("expected a time as HH:MM, got `{s}`"))
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(t: TimeOfDay) -> Self {
+        t.to_string()
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.format("%H:%M"))
+    }
+}
+
+const WEEK: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+impl Days {
+    const ALL: u8 = 0b111_1111;
+
+    pub fn contains(self, day: Weekday) -> bool {
+        self.0 & (1 << day.num_days_from_monday()) != 0
+    }
+}
+
+fn parse_weekday(s: &str) -> Result<Weekday> {
+    s.parse::<Weekday>()
+        .map_err(|_| anyhow!("unknown weekday `{s}`"))
+}
+
+impl FromStr for Days {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim().to_ascii_lowercase();
+        match s.as_str() {
+            "daily" | "*" => return Ok(Self(Self::ALL)),
+            "weekdays" => return "mon-fri".parse(),
+            "weekends" => return "sat,sun".parse(),
+            _ => {}
+        }
+        let mut mask = 0u8;
+        for part in s.split(',').map(str::trim) {
+            match part.split_once('-') {
+                Some((from, to)) => {
+                    let (from, to) = (parse_weekday(from)?, parse_weekday(to)?);
+                    let mut day = from;
+                    loop {
+                        mask |= 1 << day.num_days_from_monday();
+                        if day == to {
+                            break;
+                        }
+                        day = day.succ();
+                    }
+                }
+                None => mask |= 1 << parse_weekday(part)?.num_days_from_monday(),
+            }
+        }
+        if mask == 0 {
+            bail!("no days given");
+        }
+        Ok(Self(mask))
+    }
+}
+
+impl TryFrom<String> for Days {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<Days> for String {
+    fn from(d: Days) -> Self {
+        d.to_string()
+    }
+}
+
+impl fmt::Display for Days {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 == Self::ALL {
+            return f.write_str("daily");
+        }
+        // collapse runs of consecutive days into ranges: `mon-wed,sat`
+        let mut parts = Vec::new();
+        let mut i = 0;
+        while i < WEEK.len() {
+            if !self.contains(WEEK[i]) {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i + 1 < WEEK.len() && self.contains(WEEK[i + 1]) {
+                i += 1;
+            }
+            let name = |d: Weekday| d.to_string().to_ascii_lowercase();
+            parts.push(match i - start {
+                0 => name(WEEK[start]),
+                1 => format!("{},{}", name(WEEK[start]), name(WEEK[i])),
+                _ => format!("{}-{}", name(WEEK[start]), name(WEEK[i])),
+            });
+            i += 1;
+        }
+        f.write_str(&parts.join(","))
+    }
+}
+
+/// A rule with its name and next run time, as shown by `schedule list`.
+#[derive(Debug, Serialize)]
+pub struct ScheduledRule {
+    pub name: String,
+    #[serde(flatten)]
+    pub rule: Rule,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_run: Option<DateTime<Utc>>,
+}
+
+impl Render for ScheduledRule {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Name", "Action", "At", "Days", "Timezone", "Target", "Next-Run",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let scope = self.rule.zone.as_deref().unwrap_or("all zones");
+        let target = match &self.rule.filter {
+            Some(filter) => format!("{}/{scope} [{filter}]", self.rule.project),
+            None => format!("{}/{scope}", self.rule.project),
+        };
+        vec![
+            self.name.clone(),
+            self.rule.action.to_string(),
+            self.rule.at.to_string(),
+            self.rule.days.to_string(),
+            self.rule
+                .timezone
+                .map_or_else(|| "local".to_string(), |tz| tz.name().to_string()),
+            target,
+            self.next_run.map_or_else(
+                || "-".to_string(),
+                |t| {
+                    t.with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M %Z")
+                        .to_string()
+                },
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(at: &str, days: &str) -> Rule {
+        Rule {
+            action: Action::Stop,
+            at: at.parse().unwrap(),
+            days: days.parse().unwrap(),
+            timezone: Some(chrono_tz::Asia::Tokyo),
+            project: "p".into(),
+            zone: None,
+            filter: Some("labels.env=dev".into()),
+        }
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn parses_and_formats_days() {
+        for (input, canonical) in [
+            ("mon-fri", "mon-fri"),
+            ("weekdays", "mon-fri"),
+            ("Sat,Sun", "sat,sun"),
+            ("daily", "daily"),
+            ("mon,tue,wed,fri", "mon-wed,fri"),
+            ("fri-mon", "mon,fri-sun"),
+        ] {
+            assert_eq!(input.parse::<Days>().unwrap().to_string(), canonical);
+        }
+        assert!("".parse::<Days>().is_err());
+        assert!("funday".parse::<Days>().is_err());
+    }
+
+    #[test]
+    fn rejects_bad_times() {
+        assert!("20:00".parse::<TimeOfDay>().is_ok());
+        assert!("8pm".parse::<TimeOfDay>().is_err());
+        assert!("25:00".parse::<TimeOfDay>().is_err());
+    }
+
+    #[test]
+    fn next_fire_respects_timezone_and_weekdays() {
+        let rule = rule("20:00", "mon-fri");
+        // Friday 2024-03-01 10:00 UTC is 19:00 JST, an hour before the slot
+        assert_eq!(
+            rule.next_fire(utc("2024-03-01T10:00:00Z")),
+            Some(utc("2024-03-01T11:00:00Z"))
+        );
+        // once Friday's slot has passed the next one is Monday
+        assert_eq!(
+            rule.next_fire(utc("2024-03-01T11:00:00Z")),
+            Some(utc("2024-03-04T11:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn due_only_within_interval() {
+        let rule = rule("20:00", "daily");
+        assert!(rule.is_due(utc("2024-03-01T10:59:30Z"), utc("2024-03-01T11:00:10Z")));
+        assert!(!rule.is_due(utc("2024-03-01T11:00:10Z"), utc("2024-03-01T11:01:10Z")));
+    }
+
+    #[test]
+    fn rules_round_trip_through_toml() {
+        let mut schedules = Schedules::default();
+        schedules
+            .rules
+            .insert("nightly".into(), rule("20:00", "mon-fri"));
+        let body = toml::to_string_pretty(&schedules).unwrap();
+        assert!(body.contains("days = \"mon-fri\""));
+        assert!(body.contains("timezone = \"Asia/Tokyo\""));
+        let parsed: Schedules = toml::from_str(&body).unwrap();
+        assert_eq!(parsed.rules["nightly"], schedules.rules["nightly"]);
+    }
+}
@@ -0,0 +1,196 @@
+//! Secrets kept in the OS keyring: the macOS Keychain, the Secret Service
+//! (GNOME Keyring, KWallet) through `secret-tool`, or the Windows Credential
+//! Manager through PowerShell.
+//!
+//! Each secret is stored under the `gcectl` service and an account name of
+//! the caller's choosing. Secrets never appear on a command line; they are
+//! written to the keyring tool's stdin.
+
+use std::process::Stdio;
+
+use anyhow::{Context, Result, bail};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+const SERVICE: &str = "gcectl";
+// the account, for the PowerShell scripts to read without quoting
+const ACCOUNT_VAR: &str = "GCECTL_KEYRING_ACCOUNT";
+
+const WINDOWS_VAULT: &str = "$v = New-Object Windows.Security.Credentials.PasswordVault;";
+
+/// The secret stored as `account`, or `None` if there is none.
+pub async fn get(account: &str) -> Result<Option<String>> {
+    let mut command = match Backend::current() {
+        Backend::Keychain => {
+            let mut command = Command::new("security");
+            command.args(["find-generic-password", "-s", SERVICE, "-a", account, "-w"]);
+            command
+        }
+        Backend::SecretService => {
+            let mut command = Command::new("secret-tool");
+            command.args(["lookup", "service", SERVICE, "account", account]);
+            command
+        }
+        Backend::CredentialManager => powershell(
+            account,
+            &format!(
+                "{WINDOWS_VAULT} $c = $v.Retrieve('{SERVICE}', $env:{ACCOUNT_VAR}); \
+                 $c.RetrievePassword(); [Console]::Out.Write($c.Password)"
+            ),
+        ),
+    };
+    let output = command
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .context(Backend::current().missing())?;
+    // every tool fails the lookup of a secret it does not have
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let secret =
+        String::from_utf8(output.stdout).context("the keyring returned a non-UTF-8 secret")?;
+    Ok(Some(secret.trim_end_matches(['\n', '\r']).to_string()))
+}
+
+/// Stores `secret` as `account`, replacing what was there.
+pub async fn set(account: &str, secret: &str) -> Result<()> {
+    let (mut command, input) = match Backend::current() {
+        // `security -i` reads its commands from stdin, keeping the secret
+        // out of the process list
+        Backend::Keychain => {
+            let mut command = Command::new("security");
+            command.arg("-i");
+            let input = format!(
+                "add-generic-password -U -s {SERVICE} -a {} -w {}\n",
+                quote(account),
+                quote(secret)
+            );
+            (command, input)
+        }
+        Backend::SecretService => {
+            let mut command = Command::new("secret-tool");
+            command.args([
+                "store",
+                &format!("--label={SERVICE} {account}"),
+                "service",
+                SERVICE,
+                "account",
+                account,
+            ]);
+            (command, secret.to_string())
+        }
+        Backend::CredentialManager => {
+            let script = format!(
+                "{WINDOWS_VAULT} $s = [Console]::In.ReadToEnd(); \
+                 $v.Add((New-Object Windows.Security.Credentials.PasswordCredential(\
+                 '{SERVICE}', $env:{ACCOUNT_VAR}, $s)))"
+            );
+            (powershell(account, &script), secret.to_string())
+        }
+    };
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context(Backend::current().missing())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes()).await?;
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        bail!(
+            "the keyring refused the secret: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Removes the secret stored as `account`, if any.
+pub async fn delete(account: &str) -> Result<()> {
+    let mut command = match Backend::current() {
+        Backend::Keychain => {
+            let mut command = Command::new("security");
+            command.args(["delete-generic-password", "-s", SERVICE, "-a", account]);
+            command
+        }
+        Backend::SecretService => {
+            let mut command = Command::new("secret-tool");
+            command.args(["clear", "service", SERVICE, "account", account]);
+            command
+        }
+        Backend::CredentialManager => powershell(
+            account,
+            &format!("{WINDOWS_VAULT} $v.Remove($v.Retrieve('{SERVICE}', $env:{ACCOUNT_VAR}))"),
+        ),
+    };
+    // a secret that was never stored is as good as deleted
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .context(Backend::current().missing())?;
+    Ok(())
+}
+
+enum Backend {
+    Keychain,
+    SecretService,
+    CredentialManager,
+}
+
+impl Backend {
+    fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Self::Keychain
+        } else if cfg!(windows) {
+            Self::CredentialManager
+        } else {
+            Self::SecretService
+        }
+    }
+
+    fn missing(&self) -> &'static str {
+        match self {
+            Self::Keychain => "failed to run security for the Keychain",
+            Self::SecretService => {
+                "failed to run secret-tool for the Secret Service; install libsecret-tools \
+                 or pass --no-keyring"
+            }
+            Self::CredentialManager => "failed to run PowerShell for the Credential Manager",
+        }
+    }
+}
+
+fn powershell(account: &str, script: &str) -> Command {
+    let mut command = Command::new("powershell");
+    command
+        .args(["-NoProfile", "-NonInteractive", "-Command"])
+        .arg(format!(
+            "[void][Windows.Security.Credentials.PasswordVault,Windows.Security.Credentials,\
+             ContentType=WindowsRuntime]; {script}"
+        ))
+        .env(ACCOUNT_VAR, account);
+    command
+}
+
+// a word for `security -i`, which splits its lines as a shell does
+fn quote(word: &str) -> String {
+    format!("\"{}\"", word.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_words_for_the_keychain() {
+        assert_eq!(quote("ya29.a0"), "\"ya29.a0\"");
+        assert_eq!(quote(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+    }
+}
@@ -0,0 +1,215 @@
+//! `security audit`: a scored report of risky instance settings.
+//!
+//! Every instance starts at 100 and loses the weight of each check it
+//! fails. Settings an instance inherits from project metadata, such as OS
+//! Login, are judged on the value that takes effect.
+
+use serde::Serialize;
+
+use crate::oslogin;
+use crate::output::{Details, Render};
+use crate::resources::Instance;
+use crate::resources::instance::Metadata;
+
+const CLOUD_PLATFORM_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+const DEFAULT_ACCOUNT_SUFFIX: &str = "-compute@developer.gserviceaccount.com";
+const DISABLE_LEGACY_ENDPOINTS: &str = "disable-legacy-endpoints";
+
+/// One check an instance failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Check {
+    ExternalIp,
+    LegacyMetadataEndpoints,
+    CloudPlatformScope,
+    OsLoginDisabled,
+    DefaultServiceAccount,
+}
+
+impl Check {
+    /// Points the check costs.
+    pub fn weight(self) -> u32 {
+        match self {
+            // any process on the VM can act with every role the account has
+            Self::CloudPlatformScope => 25,
+            Self::ExternalIp => 20,
+            Self::DefaultServiceAccount => 20,
+            Self::OsLoginDisabled => 20,
+            Self::LegacyMetadataEndpoints => 15,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub check: Check,
+    pub detail: String,
+}
+
+/// The audit of one instance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub name: String,
+    pub zone: String,
+    pub score: u32,
+    pub findings: Vec<Finding>,
+}
+
+/// Audits `instance`, whose project's metadata is `project`.
+pub fn audit(instance: &Instance, project: Option<&Metadata>) -> Report {
+    let mut findings = Vec::new();
+    let mut add = |check, detail: String| findings.push(Finding { check, detail });
+
+    for nic in &instance.network_interfaces {
+        for config in &nic.access_configs {
+            let ip = config.nat_ip.as_deref().unwrap_or("ephemeral");
+            add(Check::ExternalIp, format!("external IP {ip}"));
+        }
+    }
+
+    let instance_metadata = instance.metadata.as_ref();
+    let legacy = instance_metadata
+        .and_then(|m| m.get(DISABLE_LEGACY_ENDPOINTS))
+        .or_else(|| project.and_then(|m| m.get(DISABLE_LEGACY_ENDPOINTS)));
+    if legacy.is_some_and(|value| value.eq_ignore_ascii_case("false")) {
+        add(
+            Check::LegacyMetadataEndpoints,
+            format!("{DISABLE_LEGACY_ENDPOINTS} is FALSE"),
+        );
+    }
+
+    for account in &instance.service_accounts {
+        if account.scopes.iter().any(|s| s == CLOUD_PLATFORM_SCOPE) {
+            add(
+                Check::CloudPlatformScope,
+                format!("{} has the cloud-platform scope", account.email),
+            );
+        }
+        if account.email.ends_with(DEFAULT_ACCOUNT_SUFFIX) {
+            add(
+                Check::DefaultServiceAccount,
+                format!("runs as the default service account {}", account.email),
+            );
+        }
+    }
+
+    if !oslogin::enabled(instance_metadata, project) {
+        add(
+            Check::OsLoginDisabled,
+            format!("{} is not TRUE", oslogin::ENABLE_OSLOGIN),
+        );
+    }
+
+    let lost: u32 = findings.iter().map(|f| f.check.weight()).sum();
+    Report {
+        name: instance.name.clone(),
+        zone: instance.zone_name().to_string(),
+        score: 100u32.saturating_sub(lost),
+        findings,
+    }
+}
+
+impl Render for Report {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Zone", "Score", "Findings"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let findings = match self.findings.is_empty() {
+            true => "-".to_string(),
+            false => self
+                .findings
+                .iter()
+                .map(|f| f.detail.as_str())
+                .collect::<Vec<_>>()
+                .join("; "),
+        };
+        vec![
+            self.name.clone(),
+            self.zone.clone(),
+            self.score.to_string(),
+            findings,
+        ]
+    }
+
+    fn details(&self) -> Details {
+        let mut details = Details::default();
+        details.field("Name", self.name.clone());
+        details.field("Zone", self.zone.clone());
+        details.field("Score", self.score.to_string());
+        for finding in &self.findings {
+            details.field(
+                "Finding",
+                format!("{} (-{})", finding.detail, finding.check.weight()),
+            );
+        }
+        details
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::instance::{
+        AccessConfig, MetadataItem, NetworkInterface, ServiceAccount,
+    };
+
+    fn metadata(key: &str, value: &str) -> Metadata {
+        Metadata {
+            items: vec![MetadataItem {
+                key: key.into(),
+                value: value.into(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn every_failed_check_costs_its_weight() {
+        let instance = Instance {
+            name: "web".into(),
+            zone: "https://x/projects/p/zones/us-central1-a".into(),
+            network_interfaces: vec![NetworkInterface {
+                access_configs: vec![AccessConfig {
+                    nat_ip: Some("34.1.2.3".into()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            service_accounts: vec![ServiceAccount {
+                email: "123-compute@developer.gserviceaccount.com".into(),
+                scopes: vec![CLOUD_PLATFORM_SCOPE.into()],
+            }],
+            metadata: Some(metadata(DISABLE_LEGACY_ENDPOINTS, "FALSE")),
+            ..Default::default()
+        };
+        let report = audit(&instance, None);
+        assert_eq!(report.score, 0);
+        let checks: Vec<Check> = report.findings.iter().map(|f| f.check).collect();
+        assert_eq!(
+            checks,
+            [
+                Check::ExternalIp,
+                Check::LegacyMetadataEndpoints,
+                Check::CloudPlatformScope,
+                Check::DefaultServiceAccount,
+                Check::OsLoginDisabled,
+            ]
+        );
+        assert!(report.row()[3].starts_with("external IP 34.1.2.3; "));
+    }
+
+    #[test]
+    fn project_metadata_counts_when_the_instance_sets_nothing() {
+        let instance = Instance {
+            name: "db".into(),
+            zone: "https://x/projects/p/zones/us-central1-a".into(),
+            ..Default::default()
+        };
+        let project = metadata(oslogin::ENABLE_OSLOGIN, "TRUE");
+        let report = audit(&instance, Some(&project));
+        assert_eq!(report.score, 100);
+        assert_eq!(report.row()[3], "-");
+        assert_eq!(audit(&instance, None).score, 80);
+    }
+}
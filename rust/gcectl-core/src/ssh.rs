@@ -0,0 +1,483 @@
+//! Building and launching `ssh` invocations against instances.
+
+use std::env;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, bail};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tracing::debug;
+
+use crate::output;
+use crate::resources::Instance;
+
+// key gcloud generates for `gcloud compute ssh`; reused so both tools share keys
+const GCLOUD_KEY_FILE: &str = "google_compute_engine";
+
+/// How the ssh connection reaches the instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    ExternalIp,
+    InternalIp,
+    /// Through an IAP tunnel started by `ssh` via `ProxyCommand`.
+    Iap {
+        proxy_command: String,
+    },
+}
+
+impl Route {
+    pub fn new(internal_ip: bool) -> Self {
+        if internal_ip {
+            Self::InternalIp
+        } else {
+            Self::ExternalIp
+        }
+    }
+}
+
+/// Where and as whom to connect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshTarget {
+    pub user: String,
+    pub host: String,
+    pub key_file: Option<PathBuf>,
+    pub proxy_command: Option<String>,
+}
+
+impl SshTarget {
+    /// Resolves the target for `instance` along `route`.
+    pub fn for_instance(
+        instance: &Instance,
+        user: String,
+        key_file: Option<PathBuf>,
+        route: Route,
+    ) -> Result<Self> {
+        let (host, proxy_command) = match route {
+            Route::ExternalIp => (external_ip(instance)?, None),
+            Route::InternalIp => (internal_ip(instance)?, None),
+            // ssh only needs a placeholder host; the proxy knows the real target
+            Route::Iap { proxy_command } => (instance.name.clone(), Some(proxy_command)),
+        };
+        Ok(Self {
+            user,
+            host,
+            key_file,
+            proxy_command,
+        })
+    }
+
+    /// Arguments for the system `ssh`, with `extra` passed through verbatim.
+    pub fn ssh_args(&self, extra: &[String]) -> Vec<String> {
+        let mut args = self.options();
+        args.push(format!("{}@{}", self.user, self.host));
+        args.extend(extra.iter().cloned());
+        args
+    }
+
+    /// Arguments for running `command` without a terminal, failing rather
+    /// than prompting for a password or host key.
+    pub fn command_args(&self, command: &[String]) -> Vec<String> {
+        let mut args = self.options();
+        args.extend(["-T", "-o", "BatchMode=yes", "-o", "ConnectTimeout=15"].map(String::from));
+        args.push("--".to_string());
+        args.push(format!("{}@{}", self.user, self.host));
+        args.extend(command.iter().cloned());
+        args
+    }
+
+    /// Arguments for `scp`, rewriting remote `operands` to this target.
+    pub fn scp_args(&self, operands: &[String], recurse: bool, extra: &[String]) -> Vec<String> {
+        let mut args = self.options();
+        if recurse {
+            args.push("-r".to_string());
+        }
+        args.extend(extra.iter().cloned());
+        args.extend(self.rewrite(operands));
+        args
+    }
+
+    /// Arguments for `rsync`, which gets the ssh options through `-e`.
+    pub fn rsync_args(&self, operands: &[String], extra: &[String]) -> Vec<String> {
+        let shell: Vec<String> = std::iter::once("ssh".to_string())
+            .chain(self.options())
+            .map(|arg| shell_quote(&arg))
+            .collect();
+        let mut args = vec!["-e".to_string(), shell.join(" ")];
+        args.extend(extra.iter().cloned());
+        args.extend(self.rewrite(operands));
+        args
+    }
+
+    // key and proxy options shared by ssh, scp, and rsync's remote shell
+    fn options(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(key) = &self.key_file {
+            args.push("-i".to_string());
+            args.push(key.display().to_string());
+        }
+        if let Some(proxy) = &self.proxy_command {
+            args.push("-o".to_string());
+            args.push(format!("ProxyCommand={proxy}"));
+        }
+        args
+    }
+
+    fn rewrite(&self, operands: &[String]) -> Vec<String> {
+        operands
+            .iter()
+            .map(|operand| match RemotePath::parse(operand) {
+                Some(remote) => format!("{}@{}:{}", self.user, self.host, remote.path),
+                None => operand.clone(),
+            })
+            .collect()
+    }
+}
+
+/// A `[USER@]INSTANCE:PATH` operand of scp or rsync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemotePath<'a> {
+    pub user: Option<&'a str>,
+    pub instance: &'a str,
+    pub path: &'a str,
+}
+
+impl<'a> RemotePath<'a> {
+    /// Parses `operand` like scp does: it is remote when a colon comes
+    /// before any slash, so `./a:b` stays a local path.
+    pub fn parse(operand: &'a str) -> Option<Self> {
+        let (target, path) = operand.split_once(':')?;
+        if target.is_empty() || target.contains('/') {
+            return None;
+        }
+        let (user, instance) = split_user(target);
+        Some(Self {
+            user,
+            instance,
+            path,
+        })
+    }
+}
+
+/// The single remote operand's user and instance among `operands`.
+pub fn transfer_target(operands: &[String]) -> Result<(Option<&str>, &str)> {
+    let remotes: Vec<RemotePath> = operands
+        .iter()
+        .filter_map(|o| RemotePath::parse(o))
+        .collect();
+    let Some(first) = remotes.first() else {
+        bail!("no remote path given; prefix one with INSTANCE:");
+    };
+    if remotes.iter().any(|r| r.instance != first.instance) {
+        bail!("copying between two instances is not supported");
+    }
+    let user = remotes.iter().find_map(|r| r.user);
+    Ok((user, first.instance))
+}
+
+fn internal_ip(instance: &Instance) -> Result<String> {
+    instance
+        .internal_ip()
+        .map(str::to_string)
+        .with_context(|| format!("instance {} has no internal IP", instance.name))
+}
+
+fn external_ip(instance: &Instance) -> Result<String> {
+    match instance.external_ip() {
+        Some(ip) => Ok(ip.to_string()),
+        None => bail!(
+            "instance {} has no external IP; retry with --internal-ip or --tunnel-through-iap",
+            instance.name
+        ),
+    }
+}
+
+/// `ProxyCommand` that re-invokes this binary as a stdin/stdout IAP tunnel.
+pub fn iap_proxy_command(project: &str, zone: &str, instance: &str, port: u16) -> Result<String> {
+    let exe = env::current_exe().context("cannot locate the gcectl executable")?;
+    Ok(format!(
+        "{} tunnel {instance} --remote-port {port} --listen-on-stdin --project {project} --zone {zone}",
+        shell_quote(&exe.display().to_string())
+    ))
+}
+
+/// Quotes `s` for a POSIX shell, leaving plain words as they are.
+pub fn shell_quote(s: &str) -> String {
+    if s.chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/._-".contains(c))
+    {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', r"'\''"))
+    }
+}
+
+/// Splits gcloud-style `[USER@]INSTANCE` into its parts.
+pub fn split_user(spec: &str) -> (Option<&str>, &str) {
+    match spec.split_once('@') {
+        Some((user, name)) if !user.is_empty() => (Some(user), name),
+        Some((_, name)) => (None, name),
+        None => (None, spec),
+    }
+}
+
+/// The local login name, used when no user is given.
+pub fn default_user() -> Result<String> {
+    env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .context("cannot determine the ssh user; pass USER@INSTANCE")
+}
+
+/// gcloud's `~/.ssh/google_compute_engine` key, if it exists.
+pub fn default_key_file() -> Option<PathBuf> {
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+    let path = PathBuf::from(home).join(".ssh").join(GCLOUD_KEY_FILE);
+    path.exists().then_some(path)
+}
+
+/// Public half of [`default_key_file`], if it exists.
+pub fn default_public_key_file() -> Option<PathBuf> {
+    let mut path = default_key_file()?.into_os_string();
+    path.push(".pub");
+    let path = PathBuf::from(path);
+    path.exists().then_some(path)
+}
+
+/// Runs `program` with `args` and no stdin, prefixing every line it prints
+/// to stdout or stderr with `prefix`. Fails unless it exits 0.
+pub async fn run_prefixed(program: &str, args: &[String], prefix: &str) -> Result<()> {
+    debug!("running {program} {args:?}");
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("failed to run {program}"))?;
+    let mut stdout = BufReader::new(child.stdout.take().expect("piped")).lines();
+    let mut stderr = BufReader::new(child.stderr.take().expect("piped")).lines();
+    let out = async {
+        while let Some(line) = stdout.next_line().await? {
+            output::print_text(&format!("{prefix}{line}\n"));
+        }
+        io::Result::Ok(())
+    };
+    let err = async {
+        while let Some(line) = stderr.next_line().await? {
+            eprintln!("{prefix}{line}");
+        }
+        io::Result::Ok(())
+    };
+    let (status, out, err) = tokio::join!(child.wait(), out, err);
+    out?;
+    err?;
+    match status?.code() {
+        Some(0) => Ok(()),
+        // ssh's own failures, such as refused connections, exit 255
+        Some(255) if program == "ssh" => bail!("exit 255; ssh could not connect or log in"),
+        Some(code) => bail!("exit {code}"),
+        None => bail!("killed by a signal"),
+    }
+}
+
+/// Runs `program` with `args`, replacing the current process on Unix so the
+/// user's terminal is handed over directly.
+pub fn exec(program: &str, args: &[String]) -> Result<()> {
+    debug!("exec {program} {args:?}");
+    let mut command = Command::new(program);
+    command.args(args);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let err = command.exec();
+        Err(err).with_context(|| format!("failed to run {program}"))
+    }
+
+    #[cfg(not(unix))]
+    {
+        let status = command
+            .status()
+            .with_context(|| format!("failed to run {program}"))?;
+        std::process::exit(status.code().unwrap_or(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(external: Option<&str>) -> Instance {
+        let mut body = serde_json::json!({
+            "name": "vm",
+            "networkInterfaces": [{"networkIP": "10.0.0.5", "accessConfigs": []}]
+        });
+        if let Some(ip) = external {
+            body["networkInterfaces"][0]["accessConfigs"] = serde_json::json!([{"natIP": ip}]);
+        }
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn prefers_external_ip() {
+        let target = SshTarget::for_instance(
+            &instance(Some("34.0.0.1")),
+            "me".into(),
+            None,
+            Route::ExternalIp,
+        )
+        .unwrap();
+        assert_eq!(target.host, "34.0.0.1");
+    }
+
+    #[test]
+    fn internal_ip_on_request() {
+        let target = SshTarget::for_instance(
+            &instance(Some("34.0.0.1")),
+            "me".into(),
+            None,
+            Route::InternalIp,
+        )
+        .unwrap();
+        assert_eq!(target.host, "10.0.0.5");
+    }
+
+    #[test]
+    fn missing_external_ip_suggests_internal() {
+        let err = SshTarget::for_instance(&instance(None), "me".into(), None, Route::ExternalIp)
+            .unwrap_err();
+        assert!(err.to_string().contains("--internal-ip"));
+    }
+
+    #[test]
+    fn builds_command_args() {
+        let target = SshTarget {
+            user: "alice".into(),
+            host: "vm".into(),
+            key_file: None,
+            proxy_command: Some("gcloud start-iap-tunnel".into()),
+        };
+        assert_eq!(
+            target.command_args(&["uptime".into(), "-p".into()]),
+            [
+                "-o",
+                "ProxyCommand=gcloud start-iap-tunnel",
+                "-T",
+                "-o",
+                "BatchMode=yes",
+                "-o",
+                "ConnectTimeout=15",
+                "--",
+                "alice@vm",
+                "uptime",
+                "-p"
+            ]
+        );
+    }
+
+    #[test]
+    fn builds_ssh_args() {
+        let target = SshTarget {
+            user: "alice".into(),
+            host: "34.0.0.1".into(),
+            key_file: Some(PathBuf::from("/k")),
+            proxy_command: None,
+        };
+        assert_eq!(
+            target.ssh_args(&["-L".into(), "8080:localhost:8080".into()]),
+            ["-i", "/k", "alice@34.0.0.1", "-L", "8080:localhost:8080"]
+        );
+    }
+
+    #[test]
+    fn iap_route_uses_proxy_command() {
+        let target = SshTarget::for_instance(
+            &instance(None),
+            "me".into(),
+            None,
+            Route::Iap {
+                proxy_command: "gcectl tunnel vm".into(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            target.ssh_args(&[]),
+            ["-o", "ProxyCommand=gcectl tunnel vm", "me@vm"]
+        );
+    }
+
+    #[test]
+    fn quotes_paths_for_the_shell() {
+        assert_eq!(shell_quote("/usr/bin/gcectl"), "/usr/bin/gcectl");
+        assert_eq!(shell_quote("/my apps/gcectl"), "'/my apps/gcectl'");
+    }
+
+    fn operands(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_remote_operands() {
+        assert_eq!(
+            RemotePath::parse("alice@vm:/tmp/x"),
+            Some(RemotePath {
+                user: Some("alice"),
+                instance: "vm",
+                path: "/tmp/x",
+            })
+        );
+        assert_eq!(RemotePath::parse("vm:").map(|r| r.path), Some(""));
+        for local in ["./a:b", "/abs/a:b", "plain", ":x"] {
+            assert_eq!(RemotePath::parse(local), None, "{local}");
+        }
+    }
+
+    #[test]
+    fn transfers_need_one_instance() {
+        let args = operands(&["a.txt", "b.txt", "alice@vm:~/"]);
+        assert_eq!(transfer_target(&args).unwrap(), (Some("alice"), "vm"));
+        assert!(transfer_target(&operands(&["a", "b"])).is_err());
+        assert!(transfer_target(&operands(&["vm1:a", "vm2:b"])).is_err());
+    }
+
+    #[test]
+    fn builds_scp_and_rsync_args() {
+        let target = SshTarget {
+            user: "me".into(),
+            host: "vm".into(),
+            key_file: Some(PathBuf::from("/k")),
+            proxy_command: Some("gcectl tunnel vm".into()),
+        };
+        let paths = operands(&["vm:/var/log", "."]);
+        assert_eq!(
+            target.scp_args(&paths, true, &[]),
+            [
+                "-i",
+                "/k",
+                "-o",
+                "ProxyCommand=gcectl tunnel vm",
+                "-r",
+                "me@vm:/var/log",
+                "."
+            ]
+        );
+        assert_eq!(
+            target.rsync_args(&paths, &operands(&["-avz"])),
+            [
+                "-e",
+                "ssh -i /k -o 'ProxyCommand=gcectl tunnel vm'",
+                "-avz",
+                "me@vm:/var/log",
+                "."
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_user_prefix() {
+        assert_eq!(split_user("alice@vm"), (Some("alice"), "vm"));
+        assert_eq!(split_user("vm"), (None, "vm"));
+        assert_eq!(split_user("@vm"), (None, "vm"));
+    }
+}
@@ -0,0 +1,367 @@
+//! `export terraform`: a `google_compute_instance` resource for an existing
+//! instance, with the `terraform import` command that adopts it.
+//!
+//! Only settings the provider would otherwise plan to change are written;
+//! computed values such as ephemeral IPs are left for the import to fill in.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use crate::resources::{Disk, Instance, short_name};
+
+/// HCL for `instance`, whose persistent disks by URL are `disks`, as
+/// resource `google_compute_instance.{resource}`.
+pub fn instance_block(
+    instance: &Instance,
+    disks: &BTreeMap<&str, &Disk>,
+    project: &str,
+    resource: &str,
+) -> String {
+    let zone = instance.zone_name();
+    let mut hcl = Hcl::default();
+    hcl.line(&format!(
+        "# terraform import google_compute_instance.{resource} \
+         projects/{project}/zones/{zone}/instances/{}",
+        instance.name
+    ));
+    hcl.open(&format!(
+        "resource \"google_compute_instance\" \"{resource}\""
+    ));
+    hcl.string("name", &instance.name);
+    hcl.string("project", project);
+    hcl.string("zone", zone);
+    hcl.string("machine_type", instance.machine_type_name());
+    if let Some(description) = &instance.description {
+        hcl.string("description", description);
+    }
+    let flag = |key: &str| instance.extra.get(key).and_then(Value::as_bool);
+    if let Some(true) = flag("canIpForward") {
+        hcl.raw("can_ip_forward", "true");
+    }
+    if let Some(true) = flag("deletionProtection") {
+        hcl.raw("deletion_protection", "true");
+    }
+    if let Some(platform) = instance.extra.get("minCpuPlatform").and_then(Value::as_str) {
+        hcl.string("min_cpu_platform", platform);
+    }
+    if let Some(tags) = instance.tags.as_ref().filter(|t| !t.items.is_empty()) {
+        hcl.list("tags", &tags.items);
+    }
+    if !instance.labels.is_empty() {
+        hcl.map("labels", &instance.labels);
+    }
+    let metadata: BTreeMap<String, String> = instance
+        .metadata
+        .iter()
+        .flat_map(|m| &m.items)
+        .map(|item| (item.key.clone(), item.value.clone()))
+        .collect();
+    if !metadata.is_empty() {
+        hcl.map("metadata", &metadata);
+    }
+
+    for attached in &instance.disks {
+        let attached_type = attached.extra.get("type").and_then(Value::as_str);
+        if attached_type == Some("SCRATCH") {
+            hcl.open("scratch_disk");
+            let interface = attached.extra.get("interface").and_then(Value::as_str);
+            hcl.string("interface", interface.unwrap_or("NVME"));
+            hcl.close();
+            continue;
+        }
+        let Some(url) = attached.source.as_deref() else {
+            continue;
+        };
+        let kms_key = attached
+            .disk_encryption_key
+            .as_ref()
+            .and_then(|k| k.kms_key());
+        if attached.boot {
+            hcl.open("boot_disk");
+            if !attached.auto_delete {
+                hcl.raw("auto_delete", "false");
+            }
+            if let Some(device) = &attached.device_name {
+                hcl.string("device_name", device);
+            }
+            if let Some(key) = kms_key {
+                hcl.string("kms_key_self_link", key);
+            }
+            hcl.open("initialize_params");
+            if let Some(disk) = disks.get(url) {
+                if let Some(image) = &disk.source_image {
+                    hcl.string("image", relative(image));
+                }
+                if let Some(size) = &disk.size_gb {
+                    hcl.raw("size", size);
+                }
+                hcl.string("type", disk.type_name());
+                if !disk.labels.is_empty() {
+                    hcl.map("labels", &disk.labels);
+                }
+            }
+            hcl.close();
+            hcl.close();
+        } else {
+            hcl.open("attached_disk");
+            hcl.string("source", relative(url));
+            if let Some(device) = &attached.device_name {
+                hcl.string("device_name", device);
+            }
+            if attached.mode.as_deref() == Some("READ_ONLY") {
+                hcl.string("mode", "READ_ONLY");
+            }
+            if let Some(key) = kms_key {
+                hcl.string("kms_key_self_link", key);
+            }
+            hcl.close();
+        }
+    }
+
+    for nic in &instance.network_interfaces {
+        hcl.open("network_interface");
+        if let Some(network) = &nic.network {
+            hcl.string("network", relative(network));
+        }
+        if let Some(subnetwork) = &nic.subnetwork {
+            hcl.string("subnetwork", relative(subnetwork));
+        }
+        for config in &nic.access_configs {
+            hcl.open("access_config");
+            let tier = config.extra.get("networkTier").and_then(Value::as_str);
+            if let Some(tier) = tier.filter(|t| *t != "PREMIUM") {
+                hcl.string("network_tier", tier);
+            }
+            hcl.close();
+        }
+        hcl.close();
+    }
+
+    for gpu in &instance.guest_accelerators {
+        hcl.open("guest_accelerator");
+        hcl.string("type", short_name(&gpu.accelerator_type));
+        hcl.raw("count", &gpu.accelerator_count.to_string());
+        hcl.close();
+    }
+
+    for account in &instance.service_accounts {
+        hcl.open("service_account");
+        hcl.string("email", &account.email);
+        hcl.list("scopes", &account.scopes);
+        hcl.close();
+    }
+
+    if let Some(scheduling) = &instance.scheduling {
+        hcl.open("scheduling");
+        if let Some(preemptible) = scheduling.preemptible {
+            hcl.raw("preemptible", &preemptible.to_string());
+        }
+        if let Some(restart) = scheduling.automatic_restart {
+            hcl.raw("automatic_restart", &restart.to_string());
+        }
+        if let Some(maintenance) = &scheduling.on_host_maintenance {
+            hcl.string("on_host_maintenance", maintenance);
+        }
+        if let Some(model) = &scheduling.provisioning_model {
+            hcl.string("provisioning_model", model);
+        }
+        if let Some(action) = &scheduling.instance_termination_action {
+            hcl.string("instance_termination_action", action);
+        }
+        hcl.close();
+    }
+
+    if let Some(shielded) = &instance.shielded_instance_config {
+        hcl.open("shielded_instance_config");
+        let options = [
+            ("enable_secure_boot", shielded.enable_secure_boot),
+            ("enable_vtpm", shielded.enable_vtpm),
+            (
+                "enable_integrity_monitoring",
+                shielded.enable_integrity_monitoring,
+            ),
+        ];
+        for (key, value) in options {
+            if let Some(value) = value {
+                hcl.raw(key, &value.to_string());
+            }
+        }
+        hcl.close();
+    }
+
+    if !instance.resource_policies.is_empty() {
+        let policies: Vec<String> = instance
+            .resource_policies
+            .iter()
+            .map(|p| relative(p).to_string())
+            .collect();
+        hcl.list("resource_policies", &policies);
+    }
+    hcl.close();
+    hcl.out
+}
+
+/// A Terraform resource name for instance `name`.
+pub fn resource_name(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+// `projects/...` from a full API URL, which the provider accepts either way
+fn relative(url: &str) -> &str {
+    url.find("projects/").map_or(url, |start| &url[start..])
+}
+
+/// Indented HCL, written one attribute or block at a time.
+#[derive(Default)]
+struct Hcl {
+    out: String,
+    depth: usize,
+}
+
+impl Hcl {
+    fn line(&mut self, line: &str) {
+        let _ = writeln!(self.out, "{:width$}{line}", "", width = self.depth * 2);
+    }
+
+    fn open(&mut self, header: &str) {
+        self.line(&format!("{header} {{"));
+        self.depth += 1;
+    }
+
+    fn close(&mut self) {
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn raw(&mut self, key: &str, value: &str) {
+        self.line(&format!("{key} = {value}"));
+    }
+
+    fn string(&mut self, key: &str, value: &str) {
+        if value.contains('\n') {
+            return self.heredoc(key, value);
+        }
+        self.raw(key, &quote(value));
+    }
+
+    fn list(&mut self, key: &str, values: &[String]) {
+        let items: Vec<String> = values.iter().map(|v| quote(v)).collect();
+        self.raw(key, &format!("[{}]", items.join(", ")));
+    }
+
+    fn map(&mut self, key: &str, entries: &BTreeMap<String, String>) {
+        self.open(&format!("{key} ="));
+        for (key, value) in entries {
+            let identifier = key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !key.starts_with(|c: char| c.is_ascii_digit());
+            let key = match identifier {
+                true => key.clone(),
+                false => quote(key),
+            };
+            self.string(&key, value);
+        }
+        self.close();
+    }
+
+    // multi-line values, such as startup scripts, kept readable
+    fn heredoc(&mut self, key: &str, value: &str) {
+        let mut marker = "EOT".to_string();
+        while value.lines().any(|line| line.trim() == marker) {
+            marker.push('_');
+        }
+        self.line(&format!("{key} = <<{marker}"));
+        let body = escape_templates(value);
+        self.out.push_str(&body);
+        if !body.ends_with('\n') {
+            self.out.push('\n');
+        }
+        self.out.push_str(&marker);
+        self.out.push('\n');
+    }
+}
+
+fn quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escape_templates(&escaped))
+}
+
+// `${` and `%{` start template sequences in every HCL string
+fn escape_templates(value: &str) -> String {
+    value.replace("${", "$${").replace("%{", "%%{")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::instance::{AttachedDisk, Metadata, MetadataItem, NetworkInterface};
+
+    #[test]
+    fn writes_the_import_command_and_the_settings_that_matter() {
+        const DISK: &str =
+            "https://compute.googleapis.com/compute/v1/projects/p/zones/us-central1-a/disks/web-1";
+        let instance = Instance {
+            name: "web-1".into(),
+            zone: "https://x/projects/p/zones/us-central1-a".into(),
+            machine_type: "zones/us-central1-a/machineTypes/e2-medium".into(),
+            labels: BTreeMap::from([("env".to_string(), "prod".to_string())]),
+            metadata: Some(Metadata {
+                items: vec![MetadataItem {
+                    key: "startup-script".into(),
+                    value: "#!/bin/bash\necho ${HOME}\n".into(),
+                }],
+                ..Default::default()
+            }),
+            disks: vec![AttachedDisk {
+                source: Some(DISK.into()),
+                boot: true,
+                auto_delete: true,
+                ..Default::default()
+            }],
+            network_interfaces: vec![NetworkInterface {
+                network: Some(
+                    "https://www.googleapis.com/compute/v1/projects/p/global/networks/default"
+                        .into(),
+                ),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let disk = Disk {
+            name: "web-1".into(),
+            size_gb: Some("20".into()),
+            disk_type: "zones/us-central1-a/diskTypes/pd-balanced".into(),
+            source_image: Some(
+                "https://www.googleapis.com/compute/v1/projects/debian-cloud/global/images/debian-12-bookworm-v20260101".into(),
+            ),
+            ..Default::default()
+        };
+        let disks = BTreeMap::from([(DISK, &disk)]);
+        let hcl = instance_block(&instance, &disks, "p", &resource_name("web-1"));
+        assert!(hcl.starts_with(
+            "# terraform import google_compute_instance.web_1 \
+             projects/p/zones/us-central1-a/instances/web-1\n\
+             resource \"google_compute_instance\" \"web_1\" {\n  name = \"web-1\"\n"
+        ));
+        assert!(hcl.contains("  machine_type = \"e2-medium\"\n"));
+        assert!(hcl.contains("  labels = {\n    env = \"prod\"\n  }\n"));
+        assert!(hcl.contains("    \"startup-script\" = <<EOT\n#!/bin/bash\necho $${HOME}\nEOT\n"));
+        assert!(hcl.contains(
+            "      image = \"projects/debian-cloud/global/images/debian-12-bookworm-v20260101\"\n\
+             \x20     size = 20\n      type = \"pd-balanced\"\n"
+        ));
+        assert!(hcl.contains("    network = \"projects/p/global/networks/default\"\n"));
+        assert!(hcl.ends_with("}\n"));
+    }
+
+    #[test]
+    fn strings_escape_quotes_and_templates() {
+        assert_eq!(quote(r#"say "hi" %{x}"#), r#""say \"hi\" %%{x}""#);
+        assert_eq!(quote(r"C:\tmp"), r#""C:\\tmp""#);
+    }
+}
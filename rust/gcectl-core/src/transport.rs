@@ -0,0 +1,373 @@
+//! HTTP transport shared by every API client.
+//!
+//! Requests that fail with 429 or 5xx, or that never reach the server, are
+//! retried with exponential backoff plus jitter. A `Retry-After` header from
+//! the server takes precedence over the computed delay.
+//!
+//! Every attempt first takes a token from a bucket shared by all clones of
+//! the transport, so a batch over hundreds of instances stays under the
+//! configured rate instead of tripping the per-project API quota.
+//!
+//! With [`Fixtures`] attached, exchanges are recorded to files as they
+//! happen, or answered from such files without reaching the network.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use tracing::{debug, trace, warn};
+
+use crate::fixtures::{Fixtures, Mode, RecordedRequest};
+use crate::logging::{self, HTTP_TARGET};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{IntoUrl, RequestBuilder, Response, StatusCode};
+
+pub const DEFAULT_RETRIES: u32 = 3;
+// well under the Compute API's default per-project read and write quotas
+pub const DEFAULT_QPS: f64 = 20.0;
+
+/// How many times and how patiently to retry a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    // retries after the first attempt; 0 disables retrying
+    pub retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::with_retries(DEFAULT_RETRIES)
+    }
+}
+
+impl RetryPolicy {
+    pub fn with_retries(retries: u32) -> Self {
+        Self {
+            retries,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+
+    /// Delay before retry number `attempt` (starting at 1): a random point
+    /// in `[0, min(max_delay, base_delay * 2^(attempt-1))]`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(1 << (attempt - 1).min(16))
+            .min(self.max_delay);
+        ceiling.mul_f64(jitter())
+    }
+}
+
+/// Token bucket holding up to `burst` requests and refilling at `qps`.
+/// Clones share the bucket.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    qps: f64,
+    burst: f64,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    // negative while callers are queued for tokens not yet refilled
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    /// A limiter allowing `qps` requests per second on average and bursts
+    /// of as many at once; `None` when `qps` is 0, which means no limit.
+    pub fn new(qps: f64) -> Option<Self> {
+        if qps <= 0.0 {
+            return None;
+        }
+        let burst = qps.max(1.0);
+        Some(Self {
+            qps,
+            burst,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: burst,
+                refilled: Instant::now(),
+            })),
+        })
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self) {
+        let wait = self.reserve(Instant::now());
+        if !wait.is_zero() {
+            debug!("rate limited to {} requests/s; waiting {wait:?}", self.qps);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    // takes a token, possibly one not refilled yet, and returns how long
+    // until it is; queued callers thus go out in order, `1/qps` apart
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock().expect("rate limiter lock poisoned");
+        let elapsed = now.saturating_duration_since(bucket.refilled);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.qps).min(self.burst);
+        bucket.refilled = now;
+        bucket.tokens -= 1.0;
+        match bucket.tokens {
+            tokens if tokens >= 0.0 => Duration::ZERO,
+            tokens => Duration::from_secs_f64(-tokens / self.qps),
+        }
+    }
+}
+
+/// Cheaply cloneable HTTP client with retries and an optional rate limit.
+#[derive(Debug, Clone, Default)]
+pub struct Transport {
+    client: reqwest::Client,
+    policy: RetryPolicy,
+    limiter: Option<RateLimiter>,
+    fixtures: Option<Fixtures>,
+}
+
+impl Transport {
+    pub fn new(client: reqwest::Client, policy: RetryPolicy) -> Self {
+        Self {
+            client,
+            policy,
+            limiter: None,
+            fixtures: None,
+        }
+    }
+
+    /// Records every exchange to `fixtures`, or replays them from it.
+    pub fn fixtures(mut self, fixtures: Option<Fixtures>) -> Self {
+        self.fixtures = fixtures;
+        self
+    }
+
+    /// Holds every attempt to `limiter`'s rate, if given.
+    pub fn rate_limit(mut self, limiter: Option<RateLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    pub fn get(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    pub fn post(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    pub fn patch(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.client.patch(url)
+    }
+
+    pub fn put(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.client.put(url)
+    }
+
+    pub fn delete(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.client.delete(url)
+    }
+
+    /// Sends `request`, retrying transient failures per the policy. Only the
+    /// final response is returned, whatever its status.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let request = request.build().context("failed to build request")?;
+        trace!(target: HTTP_TARGET, "{}", request_dump(&request));
+        let Some(fixtures) = &self.fixtures else {
+            return self.execute(request).await;
+        };
+        let recorded = RecordedRequest::of(&request);
+        match fixtures.mode() {
+            Mode::Replay => fixtures.replayed(&recorded),
+            Mode::Record => {
+                let resp = self.execute(request).await?;
+                fixtures.recorded(recorded, resp).await
+            }
+        }
+    }
+
+    // the attempts at `request`, per the policy
+    async fn execute(&self, request: reqwest::Request) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            if let Some(limiter) = &self.limiter {
+                limiter.acquire().await;
+            }
+            // streaming bodies cannot be replayed, so they get one attempt
+            let Some(this_try) = request.try_clone() else {
+                return Ok(self
+                    .client
+                    .execute(request)
+                    .await
+                    .map_err(|e| e.without_url())?);
+            };
+            attempt += 1;
+            let last = attempt > self.policy.retries;
+            let delay = match self.client.execute(this_try).await {
+                Ok(resp) if !last && is_retryable(resp.status()) => {
+                    let delay = retry_after(&resp)
+                        .map(|d| d.min(self.policy.max_delay))
+                        .unwrap_or_else(|| self.policy.backoff(attempt));
+                    warn!(
+                        "{} {} returned {}; retrying in {delay:?}",
+                        request.method(),
+                        logging::redact_url(request.url()),
+                        resp.status()
+                    );
+                    delay
+                }
+                Err(err) if !last && (err.is_connect() || err.is_timeout()) => {
+                    let delay = self.policy.backoff(attempt);
+                    warn!(
+                        "{} {} failed: {}; retrying in {delay:?}",
+                        request.method(),
+                        logging::redact_url(request.url()),
+                        err.without_url()
+                    );
+                    delay
+                }
+                result => {
+                    // reqwest's errors show the URL unredacted
+                    let resp = result.map_err(|err| {
+                        anyhow::Error::new(err.without_url()).context(format!(
+                            "{} {} failed",
+                            request.method(),
+                            logging::redact_url(request.url())
+                        ))
+                    })?;
+                    trace!(
+                        target: HTTP_TARGET,
+                        "< {} {}\n< headers: {:?}",
+                        resp.status(),
+                        logging::redact_url(resp.url()),
+                        logging::redact_headers(resp.headers())
+                    );
+                    return Ok(resp);
+                }
+            };
+            debug!("attempt {attempt} of {}", self.policy.retries + 1);
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// The `-vvv` dump of `request`, credentials redacted.
+fn request_dump(request: &reqwest::Request) -> String {
+    format!(
+        "> {} {}\n> headers: {:?}\n> body: {}",
+        request.method(),
+        logging::redact_url(request.url()),
+        logging::redact_headers(request.headers()),
+        request
+            .body()
+            .and_then(|b| b.as_bytes())
+            .map(|b| logging::redact_body(&String::from_utf8_lossy(b)))
+            .unwrap_or_default()
+    )
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Parses `Retry-After` as delay-seconds or an HTTP date.
+fn retry_after(resp: &Response) -> Option<Duration> {
+    parse_retry_after(resp.headers().get(RETRY_AFTER)?.to_str().ok()?, Utc::now())
+}
+
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    if let Ok(secs) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    Some((at.with_timezone(&Utc) - now).to_std().unwrap_or_default())
+}
+
+/// Pseudo-random factor in `[0, 1)`; good enough to spread out retries.
+fn jitter() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    // scramble the low bits so consecutive calls differ
+    let mixed = nanos.wrapping_mul(2_654_435_761);
+    f64::from(mixed) / (f64::from(u32::MAX) + 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_and_caps() {
+        let policy = RetryPolicy::with_retries(10);
+        for attempt in 1..=10 {
+            let ceiling = (policy.base_delay * 2u32.pow(attempt - 1)).min(policy.max_delay);
+            assert!(policy.backoff(attempt) <= ceiling);
+        }
+    }
+
+    #[test]
+    fn rate_limiter_spends_the_burst_then_spaces_requests() {
+        let limiter = RateLimiter::new(2.0).unwrap();
+        let start = Instant::now();
+        assert_eq!(limiter.reserve(start), Duration::ZERO);
+        assert_eq!(limiter.reserve(start), Duration::ZERO);
+        assert_eq!(limiter.reserve(start), Duration::from_millis(500));
+        assert_eq!(limiter.reserve(start), Duration::from_secs(1));
+        // two tokens refilled in a second pay off the two queued requests
+        let later = start + Duration::from_secs(3);
+        assert_eq!(limiter.reserve(later), Duration::ZERO);
+        assert!(RateLimiter::new(0.0).is_none());
+    }
+
+    #[test]
+    fn dumps_tokeninfo_requests_without_the_token() {
+        let client = reqwest::Client::new();
+        let lookup = client
+            .get("https://oauth2.googleapis.com/tokeninfo")
+            .query(&[("access_token", "ya29.secret"), ("key", "AIza-secret")])
+            .bearer_auth("ya29.secret")
+            .build()
+            .unwrap();
+        let dump = request_dump(&lookup);
+        assert!(!dump.contains("secret"), "{dump}");
+        assert!(dump.contains("tokeninfo?access_token=[REDACTED]&key=[REDACTED]"));
+        let posted = client
+            .post("https://oauth2.googleapis.com/tokeninfo")
+            .form(&[("access_token", "ya29.secret")])
+            .build()
+            .unwrap();
+        assert!(!request_dump(&posted).contains("secret"));
+    }
+
+    #[test]
+    fn retries_throttling_and_server_errors_only() {
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable(StatusCode::NOT_FOUND));
+        assert!(!is_retryable(StatusCode::FORBIDDEN));
+    }
+
+    #[test]
+    fn parses_retry_after_forms() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
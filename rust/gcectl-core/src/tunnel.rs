@@ -0,0 +1,318 @@
+//! Identity-Aware Proxy (IAP) TCP forwarding over WebSocket.
+//!
+//! Each TCP connection is relayed through its own WebSocket to
+//! `tunnel.cloudproxy.app` using the same framing as
+//! `gcloud compute start-iap-tunnel`: every binary message starts with a
+//! big-endian `u16` tag, followed by a tag-specific payload.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result, anyhow, bail};
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::http::header::{AUTHORIZATION, ORIGIN, SEC_WEBSOCKET_PROTOCOL};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tracing::{debug, info, warn};
+
+use crate::auth::Authenticator;
+use crate::output;
+
+const TUNNEL_URL: &str = "wss://tunnel.cloudproxy.app/v4/connect";
+const SUBPROTOCOL: &str = "relay.tunnel.cloudproxy.app";
+const TUNNEL_ORIGIN: &str = "bot:iap-tunneler";
+
+const TAG_CONNECT_SUCCESS_SID: u16 = 0x0001;
+const TAG_RECONNECT_SUCCESS_ACK: u16 = 0x0002;
+const TAG_DATA: u16 = 0x0004;
+const TAG_ACK: u16 = 0x0007;
+
+// largest payload IAP accepts in a single DATA frame
+const MAX_DATA_FRAME: usize = 16 * 1024;
+
+type TunnelSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// The instance endpoint a tunnel connects to.
+#[derive(Debug, Clone)]
+pub struct IapTarget {
+    pub project: String,
+    pub zone: String,
+    pub instance: String,
+    pub interface: String,
+    pub port: u16,
+}
+
+impl IapTarget {
+    fn url(&self) -> String {
+        format!(
+            "{TUNNEL_URL}?project={}&zone={}&instance={}&interface={}&port={}&newWebsocket=true",
+            self.project, self.zone, self.instance, self.interface, self.port
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Frame {
+    ConnectSuccess(Vec<u8>),
+    ReconnectSuccess(u64),
+    Data(Vec<u8>),
+    Ack(u64),
+    Unknown(u16),
+}
+
+fn decode(buf: &[u8]) -> Result<Frame> {
+    let (tag, rest) = split_u16(buf)?;
+    Ok(match tag {
+        TAG_CONNECT_SUCCESS_SID => Frame::ConnectSuccess(length_prefixed(rest)?.to_vec()),
+        TAG_RECONNECT_SUCCESS_ACK => Frame::ReconnectSuccess(read_u64(rest)?),
+        TAG_DATA => Frame::Data(length_prefixed(rest)?.to_vec()),
+        TAG_ACK => Frame::Ack(read_u64(rest)?),
+        other => Frame::Unknown(other),
+    })
+}
+
+fn encode_data(data: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(6 + data.len());
+    frame.extend_from_slice(&TAG_DATA.to_be_bytes());
+    frame.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    frame.extend_from_slice(data);
+    frame
+}
+
+fn encode_ack(bytes_received: u64) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(10);
+    frame.extend_from_slice(&TAG_ACK.to_be_bytes());
+    frame.extend_from_slice(&bytes_received.to_be_bytes());
+    frame
+}
+
+fn split_u16(buf: &[u8]) -> Result<(u16, &[u8])> {
+    let (head, rest) = buf
+        .split_first_chunk::<2>()
+        .ok_or_else(|| anyhow!("truncated tunnel frame"))?;
+    Ok((u16::from_be_bytes(*head), rest))
+}
+
+fn read_u64(buf: &[u8]) -> Result<u64> {
+    let (head, _) = buf
+        .split_first_chunk::<8>()
+        .ok_or_else(|| anyhow!("truncated tunnel frame"))?;
+    Ok(u64::from_be_bytes(*head))
+}
+
+fn length_prefixed(buf: &[u8]) -> Result<&[u8]> {
+    let (head, rest) = buf
+        .split_first_chunk::<4>()
+        .ok_or_else(|| anyhow!("truncated tunnel frame"))?;
+    let len = u32::from_be_bytes(*head) as usize;
+    rest.get(..len)
+        .ok_or_else(|| anyhow!("truncated tunnel frame"))
+}
+
+/// Opens the WebSocket for `target` and waits until the relay has
+/// connected to the instance port.
+async fn connect(auth: &Authenticator, target: &IapTarget) -> Result<TunnelSocket> {
+    let mut request = target.url().into_client_request()?;
+    let headers = request.headers_mut();
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {}", auth.token().await?))?,
+    );
+    headers.insert(ORIGIN, HeaderValue::from_static(TUNNEL_ORIGIN));
+    headers.insert(
+        SEC_WEBSOCKET_PROTOCOL,
+        HeaderValue::from_static(SUBPROTOCOL),
+    );
+
+    debug!(
+        "connecting IAP tunnel to {}:{}",
+        target.instance, target.port
+    );
+    let (mut ws, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .context("failed to open IAP tunnel")?;
+
+    // nothing may be sent before the relay confirms the backend connection
+    loop {
+        match ws.next().await {
+            Some(Ok(Message::Binary(buf))) => match decode(&buf)? {
+                Frame::ConnectSuccess(_) => break,
+                other => bail!("unexpected tunnel frame before connect: {other:?}"),
+            },
+            Some(Ok(Message::Close(frame))) => bail!(
+                "IAP closed the tunnel: {}",
+                frame.map_or_else(|| "no reason given".to_string(), |f| f.to_string())
+            ),
+            Some(Ok(_)) => continue,
+            Some(Err(err)) => return Err(err).context("IAP tunnel handshake failed"),
+            None => bail!("IAP closed the tunnel during connect"),
+        }
+    }
+    debug!("IAP tunnel established");
+    Ok(ws)
+}
+
+/// Opens a tunnel to `target` and closes it again, to learn whether IAP can
+/// reach the port.
+pub async fn probe(auth: &Authenticator, target: &IapTarget) -> Result<()> {
+    let mut ws = connect(auth, target).await?;
+    // the backend connection is all that was asked about
+    let _ = ws.close(None).await;
+    Ok(())
+}
+
+/// Relays bytes between `reader`/`writer` and the instance port until either
+/// side closes.
+pub async fn relay<R, W>(
+    auth: &Authenticator,
+    target: &IapTarget,
+    mut reader: R,
+    mut writer: W,
+) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let (mut sink, mut stream) = connect(auth, target).await?.split();
+
+    // both directions send frames, so funnel them through one writer task
+    let (frames_tx, mut frames_rx) = mpsc::channel::<Vec<u8>>(32);
+
+    let send = async {
+        while let Some(frame) = frames_rx.recv().await {
+            sink.send(Message::Binary(frame.into())).await?;
+        }
+        sink.close().await?;
+        Ok::<_, anyhow::Error>(())
+    };
+
+    let upstream_tx = frames_tx.clone();
+    let upstream = async move {
+        let mut buf = vec![0u8; MAX_DATA_FRAME];
+        loop {
+            let n = reader.read(&mut buf).await?;
+            if n == 0 {
+                return Ok::<_, anyhow::Error>(());
+            }
+            if upstream_tx.send(encode_data(&buf[..n])).await.is_err() {
+                return Ok(());
+            }
+        }
+    };
+
+    let downstream = async move {
+        let mut received: u64 = 0;
+        let mut acked: u64 = 0;
+        while let Some(msg) = stream.next().await {
+            match msg? {
+                Message::Binary(buf) => match decode(&buf)? {
+                    Frame::Data(data) => {
+                        writer.write_all(&data).await?;
+                        writer.flush().await?;
+                        received += data.len() as u64;
+                        if received - acked >= MAX_DATA_FRAME as u64 {
+                            if frames_tx.send(encode_ack(received)).await.is_err() {
+                                break;
+                            }
+                            acked = received;
+                        }
+                    }
+                    Frame::Ack(_) | Frame::ReconnectSuccess(_) => {}
+                    Frame::ConnectSuccess(_) => warn!("duplicate tunnel connect frame"),
+                    Frame::Unknown(tag) => debug!("ignoring tunnel frame with tag {tag:#06x}"),
+                },
+                Message::Close(frame) => {
+                    debug!("IAP closed the tunnel: {frame:?}");
+                    break;
+                }
+                _ => {}
+            }
+        }
+        Ok::<_, anyhow::Error>(())
+    };
+
+    tokio::select! {
+        result = send => result,
+        result = upstream => result,
+        result = downstream => result,
+    }
+}
+
+/// Accepts local TCP connections on `local_port` and tunnels each one to
+/// `target` until the process is interrupted.
+pub async fn listen(auth: Arc<Authenticator>, target: IapTarget, local_port: u16) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", local_port))
+        .await
+        .with_context(|| format!("failed to listen on port {local_port}"))?;
+    let addr = listener.local_addr()?;
+    output::print_note(&format!("Listening on port [{}].", addr.port()));
+
+    let target = Arc::new(target);
+    loop {
+        let (socket, peer) = listener.accept().await?;
+        info!("new tunnel connection from {peer}");
+        let auth = auth.clone();
+        let target = target.clone();
+        tokio::spawn(async move {
+            let (reader, writer) = socket.into_split();
+            if let Err(err) = relay(&auth, &target, reader, writer).await {
+                eprintln!("Error: tunnel connection from {peer}: {err:#}");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_frames_round_trip() {
+        let frame = encode_data(b"hello");
+        assert_eq!(&frame[..6], &[0x00, 0x04, 0, 0, 0, 5]);
+        assert_eq!(decode(&frame).unwrap(), Frame::Data(b"hello".to_vec()));
+    }
+
+    #[test]
+    fn ack_frames_round_trip() {
+        let frame = encode_ack(70_000);
+        assert_eq!(frame.len(), 10);
+        assert_eq!(decode(&frame).unwrap(), Frame::Ack(70_000));
+    }
+
+    #[test]
+    fn decodes_connect_success() {
+        let mut frame = vec![0x00, 0x01, 0, 0, 0, 3];
+        frame.extend_from_slice(b"sid");
+        assert_eq!(
+            decode(&frame).unwrap(),
+            Frame::ConnectSuccess(b"sid".to_vec())
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_frames() {
+        assert!(decode(&[0x00]).is_err());
+        assert!(decode(&[0x00, 0x04, 0, 0, 0, 9, 1]).is_err());
+        assert_eq!(decode(&[0x12, 0x34]).unwrap(), Frame::Unknown(0x1234));
+    }
+
+    #[test]
+    fn builds_connect_url() {
+        let target = IapTarget {
+            project: "p".into(),
+            zone: "z".into(),
+            instance: "vm".into(),
+            interface: "nic0".into(),
+            port: 22,
+        };
+        assert_eq!(
+            target.url(),
+            "wss://tunnel.cloudproxy.app/v4/connect?project=p&zone=z&instance=vm&interface=nic0&port=22&newWebsocket=true"
+        );
+    }
+}
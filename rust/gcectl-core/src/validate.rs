@@ -0,0 +1,221 @@
+//! Checks of a create or update request against what the API would accept,
+//! run before anything is sent so every problem shows at once instead of
+//! one failed request at a time.
+//!
+//! The machine type and image catalogs come from [`crate::cache`]; when one
+//! could not be read, the checks that need it are left to the API.
+
+use crate::resources::instance::builder::ImageSource;
+use crate::resources::{Accelerator, Image, MachineType};
+
+// families whose GPUs are part of the machine type rather than attached
+const GPU_FAMILIES: &str = "A100, H100, and L4 GPUs come with the a2, a3, and g2 machine types";
+
+/// What a request asks for.
+#[derive(Debug, Clone, Default)]
+pub struct Request<'a> {
+    pub names: &'a [String],
+    pub zone: &'a str,
+    pub machine_type: &'a str,
+    pub image: Option<&'a ImageSource>,
+    pub boot_disk_size_gb: Option<u64>,
+    pub accelerators: &'a [Accelerator],
+}
+
+/// The catalogs a request is checked against, each `None` if unreadable.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Catalogs<'a> {
+    pub machine_types: Option<&'a [MachineType]>,
+    pub images: Option<&'a [Image]>,
+}
+
+/// Everything wrong with `request`, in the order of its flags.
+pub fn check(request: &Request, catalogs: Catalogs) -> Vec<String> {
+    let mut problems: Vec<String> = request
+        .names
+        .iter()
+        .filter_map(|name| name_problem(name))
+        .collect();
+
+    let machine_type = request.machine_type;
+    // custom shapes and full URLs are not in the catalog
+    let listed = !machine_type.contains("custom") && !machine_type.contains('/');
+    let found = catalogs
+        .machine_types
+        .filter(|_| listed)
+        .map(|types| types.iter().find(|t| t.name == machine_type));
+    if let Some(None) = found {
+        problems.push(format!(
+            "machine type {machine_type} is not offered in {}; \
+             `gcectl machine-types list --zone {}` shows those that are",
+            request.zone, request.zone
+        ));
+    }
+
+    if let (Some(source), Some(images)) = (request.image, catalogs.images) {
+        match resolve(source, images) {
+            Err(problem) => problems.push(problem),
+            Ok(image) => {
+                let minimum = image.disk_size_gb.as_deref().and_then(|s| s.parse().ok());
+                if let (Some(size), Some(minimum)) = (request.boot_disk_size_gb, minimum)
+                    && size < minimum
+                {
+                    problems.push(format!(
+                        "--boot-disk-size {size} is smaller than the {minimum} GB \
+                         image {} needs",
+                        image.name
+                    ));
+                }
+            }
+        }
+    }
+
+    if !request.accelerators.is_empty() {
+        let bundled = found.flatten().filter(|t| !t.accelerators.is_empty());
+        if let Some(bundled) = bundled {
+            problems.push(format!(
+                "{machine_type} comes with {} already; drop --accelerator",
+                bundled.accelerator_summary()
+            ));
+        } else if !is_n1(machine_type) {
+            problems.push(format!(
+                "GPUs attach only to N1 machine types, not {machine_type}; {GPU_FAMILIES}"
+            ));
+        }
+    }
+    problems
+}
+
+/// Why the API would reject `name` as a resource name, if it would.
+pub fn name_problem(name: &str) -> Option<String> {
+    let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-';
+    let valid = name.len() <= 63
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && !name.ends_with('-')
+        && name.chars().all(allowed);
+    (!valid).then(|| {
+        format!(
+            "instance name `{name}` must be 1-63 lowercase letters, digits, or `-`, \
+             starting with a letter"
+        )
+    })
+}
+
+/// The image `source` names, or for a family the newest image in it that is
+/// not deprecated.
+fn resolve<'a>(source: &ImageSource, images: &'a [Image]) -> Result<&'a Image, String> {
+    match source {
+        ImageSource::Image { project, image } => images
+            .iter()
+            .find(|i| &i.name == image)
+            .ok_or_else(|| format!("image {image} not found in {project}")),
+        ImageSource::Family { project, family } => images
+            .iter()
+            .filter(|i| i.family.as_ref() == Some(family))
+            .filter(|i| i.deprecated.as_ref().is_none_or(|d| d.state.is_empty()))
+            .max_by(|a, b| a.creation_timestamp.cmp(&b.creation_timestamp))
+            .ok_or_else(|| format!("image family {family} not found in {project}")),
+    }
+}
+
+// N1, and the custom shapes without a family prefix, are the only types
+// that take attached GPUs
+fn is_n1(machine_type: &str) -> bool {
+    machine_type.starts_with("n1-") || machine_type.starts_with("custom-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::machine_type::BundledAccelerator;
+
+    fn machine_type(name: &str) -> MachineType {
+        MachineType {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    fn image(name: &str, family: &str, size: &str, created: &str) -> Image {
+        Image {
+            name: name.into(),
+            family: Some(family.into()),
+            disk_size_gb: Some(size.into()),
+            creation_timestamp: Some(created.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reports_every_problem_at_once() {
+        let names = ["Web_1".to_string()];
+        let family = ImageSource::Family {
+            project: "debian-cloud".into(),
+            family: "debian-12".into(),
+        };
+        let gpu = [Accelerator {
+            accelerator_type: "nvidia-tesla-t4".into(),
+            count: 1,
+        }];
+        let request = Request {
+            names: &names,
+            zone: "us-central1-a",
+            machine_type: "e2-standard-4",
+            image: Some(&family),
+            boot_disk_size_gb: Some(8),
+            accelerators: &gpu,
+        };
+        let types = [machine_type("n1-standard-4")];
+        let images = [
+            image("debian-12-old", "debian-12", "10", "2025-01-01"),
+            image("debian-12-new", "debian-12", "20", "2026-01-01"),
+        ];
+        let catalogs = Catalogs {
+            machine_types: Some(&types),
+            images: Some(&images),
+        };
+        let problems = check(&request, catalogs);
+        assert_eq!(problems.len(), 4, "{problems:?}");
+        assert!(problems[0].contains("Web_1"));
+        assert!(problems[1].contains("e2-standard-4 is not offered in us-central1-a"));
+        assert!(problems[2].contains("20 GB image debian-12-new"));
+        assert!(problems[3].starts_with("GPUs attach only to N1"));
+    }
+
+    #[test]
+    fn bundled_gpus_and_unreadable_catalogs() {
+        let mut a2 = machine_type("a2-highgpu-1g");
+        a2.accelerators = vec![BundledAccelerator {
+            guest_accelerator_type: "nvidia-tesla-a100".into(),
+            guest_accelerator_count: 1,
+        }];
+        let gpu = [Accelerator {
+            accelerator_type: "nvidia-tesla-a100".into(),
+            count: 1,
+        }];
+        let request = Request {
+            zone: "us-central1-a",
+            machine_type: "a2-highgpu-1g",
+            accelerators: &gpu,
+            ..Default::default()
+        };
+        let types = [a2];
+        let problems = check(
+            &request,
+            Catalogs {
+                machine_types: Some(&types),
+                images: None,
+            },
+        );
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("drop --accelerator"), "{problems:?}");
+
+        // without a catalog only the flags themselves are judged
+        let request = Request {
+            machine_type: "n1-standard-4",
+            accelerators: &gpu,
+            ..request
+        };
+        assert!(check(&request, Catalogs::default()).is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+//! Frame rendering for `instances watch`.
+
+use std::collections::HashMap;
+
+use comfy_table::presets::UTF8_FULL;
+use comfy_table::{Attribute, Cell, Color, Table};
+
+use crate::output::{Render, status_emoji};
+use crate::resources::Instance;
+
+/// Remembers each instance's status between polls to spot transitions.
+#[derive(Debug, Default)]
+pub struct StatusTracker {
+    // keyed by (zone, name); names are only unique within a zone
+    last: HashMap<(String, String), String>,
+    polls: usize,
+}
+
+/// How an instance's status compares with the previous poll.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Unchanged,
+    New,
+    From(String),
+}
+
+impl StatusTracker {
+    /// Records `instances` and returns one [`Change`] per instance. Nothing
+    /// is considered new on the first poll.
+    pub fn update(&mut self, instances: &[Instance]) -> Vec<Change> {
+        let first = self.polls == 0;
+        self.polls += 1;
+        let mut next = HashMap::with_capacity(instances.len());
+        let changes = instances
+            .iter()
+            .map(|i| {
+                let key = (i.zone_name().to_string(), i.name.clone());
+                let change = match self.last.get(&key) {
+                    Some(prev) if *prev != i.status => Change::From(prev.clone()),
+                    Some(_) => Change::Unchanged,
+                    None if first => Change::Unchanged,
+                    None => Change::New,
+                };
+                next.insert(key, i.status.clone());
+                change
+            })
+            .collect();
+        self.last = next;
+        changes
+    }
+}
+
+/// Instance table with an extra column flagging status transitions.
+pub fn render(instances: &[Instance], changes: &[Change]) -> String {
+    let mut headers = Instance::headers();
+    headers.push("Change");
+    let mut table = Table::new();
+    table.load_style(UTF8_FULL).set_header(headers);
+    for (instance, change) in instances.iter().zip(changes) {
+        let mut cells: Vec<Cell> = instance.table_row().into_iter().map(Cell::new).collect();
+        cells.push(match change {
+            Change::Unchanged => Cell::new(""),
+            Change::New => Cell::new("new").fg(Color::Cyan),
+            Change::From(prev) => Cell::new(format!(
+                "{} {prev} → {}",
+                status_emoji(prev),
+                instance.status
+            ))
+            .fg(Color::Yellow)
+            .add_attribute(Attribute::Bold),
+        });
+        table.add_row(cells);
+    }
+    table.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(name: &str, status: &str) -> Instance {
+        Instance {
+            name: name.into(),
+            zone: "zones/z".into(),
+            status: status.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tracks_transitions_between_polls() {
+        let mut tracker = StatusTracker::default();
+        let first = tracker.update(&[instance("a", "STAGING")]);
+        assert_eq!(first, vec![Change::Unchanged]);
+
+        let second = tracker.update(&[instance("a", "RUNNING"), instance("b", "PROVISIONING")]);
+        assert_eq!(second, vec![Change::From("STAGING".into()), Change::New]);
+
+        let third = tracker.update(&[instance("a", "RUNNING")]);
+        assert_eq!(third, vec![Change::Unchanged]);
+    }
+
+    #[test]
+    fn render_marks_changes() {
+        let rendered = render(
+            &[instance("a", "RUNNING")],
+            &[Change::From("STAGING".into())],
+        );
+        assert!(rendered.contains("Change"));
+        assert!(rendered.contains("STAGING → RUNNING"));
+    }
+}
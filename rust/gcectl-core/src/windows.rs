@@ -0,0 +1,241 @@
+//! The `windows-keys` handshake that resets a Windows account's password.
+//!
+//! gcectl puts a fresh RSA public key in the instance's `windows-keys`
+//! metadata entry, one JSON object per line. The guest agent creates or
+//! resets the named account with a random password and writes it, encrypted
+//! to that key with RSA-OAEP, as a JSON line on serial port 4. Only the
+//! holder of the private key, which never leaves this process, can read it.
+
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{DateTime, Duration, Utc};
+use rsa::rand_core::OsRng;
+use rsa::traits::PublicKeyParts;
+use rsa::{Oaep, RsaPrivateKey};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha1::Sha1;
+
+use crate::output::Render;
+
+/// Metadata key the Windows guest agent reads password requests from.
+pub const WINDOWS_KEYS: &str = "windows-keys";
+
+/// Serial port the guest agent answers on.
+pub const AGENT_PORT: u8 = 4;
+
+const KEY_BITS: usize = 2048;
+// how long the agent may still act on a key; later requests drop it
+const KEY_LIFETIME_MINUTES: i64 = 5;
+// characters Windows forbids in account names
+const FORBIDDEN: &str = "\"/\\[]:;|=,+*?<>";
+
+/// A one-time key for one password reset.
+pub struct WindowsKey {
+    private: RsaPrivateKey,
+    user: String,
+    email: String,
+    expire_on: DateTime<Utc>,
+}
+
+/// The account the agent reset, as printed by `reset-windows-password`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowsCredentials {
+    pub username: String,
+    pub password: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip_address: Option<String>,
+}
+
+impl Render for WindowsCredentials {
+    fn headers() -> Vec<&'static str> {
+        vec!["Username", "Password", "IP-Address"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.username.clone(),
+            self.password.clone(),
+            self.ip_address.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+// one line the agent writes to the serial port
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AgentResponse {
+    #[serde(default)]
+    modulus: String,
+    #[serde(default)]
+    encrypted_password: Option<String>,
+    #[serde(default)]
+    error_message: Option<String>,
+}
+
+// one line of `windows-keys`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyEntry {
+    expire_on: DateTime<Utc>,
+}
+
+/// Checks `user` against Windows' rules for account names.
+pub fn validate_user(user: &str) -> Result<()> {
+    if user.is_empty() || user.chars().count() > 20 {
+        bail!("Windows user `{user}` must be 1-20 characters");
+    }
+    if user.contains(|c: char| FORBIDDEN.contains(c) || c.is_control()) {
+        bail!("Windows user `{user}` must not contain any of {FORBIDDEN}");
+    }
+    Ok(())
+}
+
+impl WindowsKey {
+    /// Generates a key asking the agent to reset `user`'s password, noting
+    /// `email` as the requester.
+    pub fn generate(user: &str, email: &str) -> Result<Self> {
+        let private =
+            RsaPrivateKey::new(&mut OsRng, KEY_BITS).context("failed to generate an RSA key")?;
+        Ok(Self::new(private, user, email, Utc::now()))
+    }
+
+    fn new(private: RsaPrivateKey, user: &str, email: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            private,
+            user: user.to_string(),
+            email: email.to_string(),
+            expire_on: now + Duration::minutes(KEY_LIFETIME_MINUTES),
+        }
+    }
+
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    fn modulus(&self) -> String {
+        STANDARD.encode(self.private.n().to_bytes_be())
+    }
+
+    /// The `windows-keys` line for this key.
+    pub fn entry(&self) -> String {
+        json!({
+            "userName": self.user,
+            "modulus": self.modulus(),
+            "exponent": STANDARD.encode(self.private.e().to_bytes_be()),
+            "email": self.email,
+            "expireOn": self.expire_on.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        })
+        .to_string()
+    }
+
+    /// New value of `windows-keys`: the `existing` lines that have not
+    /// expired by `now`, then this key. Lines that do not parse are kept.
+    pub fn append_to(&self, existing: Option<&str>, now: DateTime<Utc>) -> String {
+        let mut lines: Vec<&str> = existing
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter(|line| {
+                serde_json::from_str::<KeyEntry>(line).map_or(true, |entry| entry.expire_on > now)
+            })
+            .collect();
+        let entry = self.entry();
+        lines.push(&entry);
+        lines.join("\n")
+    }
+
+    /// Password the agent wrote for this key in serial port `output`, if it
+    /// has answered yet.
+    pub fn find_password(&self, output: &str) -> Result<Option<String>> {
+        let modulus = self.modulus();
+        for line in output.lines() {
+            let Ok(response) = serde_json::from_str::<AgentResponse>(line.trim()) else {
+                continue;
+            };
+            if response.modulus != modulus {
+                continue;
+            }
+            if let Some(message) = response.error_message.filter(|m| !m.is_empty()) {
+                bail!("the Windows guest agent could not reset the password: {message}");
+            }
+            let Some(encrypted) = response.encrypted_password else {
+                continue;
+            };
+            let encrypted = STANDARD
+                .decode(encrypted)
+                .context("the guest agent's password is not valid base64")?;
+            let password = self
+                .private
+                .decrypt(Oaep::new::<Sha1>(), &encrypted)
+                .context("failed to decrypt the password from the guest agent")?;
+            return Ok(Some(
+                String::from_utf8(password).context("the decrypted password is not UTF-8")?,
+            ));
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsa::RsaPublicKey;
+
+    fn key(now: DateTime<Utc>) -> WindowsKey {
+        // small enough to generate quickly in debug builds
+        let private = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        WindowsKey::new(private, "admin", "me@example.com", now)
+    }
+
+    #[test]
+    fn decrypts_the_agents_answer() {
+        let key = key(Utc::now());
+        let entry: serde_json::Value = serde_json::from_str(&key.entry()).unwrap();
+        assert_eq!(entry["userName"], "admin");
+        assert_eq!(entry["exponent"], "AQAB");
+
+        let public = RsaPublicKey::from(&key.private);
+        let encrypted = public
+            .encrypt(&mut OsRng, Oaep::new::<Sha1>(), b"s3cret!")
+            .unwrap();
+        let answer = json!({
+            "ready": true,
+            "passwordFound": true,
+            "modulus": entry["modulus"],
+            "encryptedPassword": STANDARD.encode(encrypted),
+        });
+        let output = format!("booting\n{{\"modulus\":\"other\"}}\n{answer}\n");
+        assert_eq!(key.find_password("booting\n").unwrap(), None);
+        assert_eq!(
+            key.find_password(&output).unwrap().as_deref(),
+            Some("s3cret!")
+        );
+    }
+
+    #[test]
+    fn drops_expired_keys_and_checks_users() {
+        let now = "2026-01-01T12:00:00Z".parse().unwrap();
+        let key = key(now);
+        let existing = concat!(
+            r#"{"userName":"old","expireOn":"2026-01-01T11:00:00Z"}"#,
+            "\n",
+            r#"{"userName":"recent","expireOn":"2026-01-01T12:03:00Z"}"#,
+        );
+        let value = key.append_to(Some(existing), now);
+        let users: Vec<String> = value
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<serde_json::Value>(line).unwrap()["userName"].to_string()
+            })
+            .collect();
+        assert_eq!(users, [r#""recent""#, r#""admin""#]);
+
+        assert!(validate_user("Administrator").is_ok());
+        assert!(validate_user("a:b").is_err());
+        assert!(validate_user("").is_err());
+        assert!(validate_user("a-very-long-user-name-x").is_err());
+    }
+}
@@ -0,0 +1,269 @@
+//! User-defined aliases from the config file's `[alias]` table, expanded
+//! before clap parses the command line:
+//!
+//! ```toml
+//! [alias]
+//! up = "instances start --filter labels.owner=$USER"
+//! sh = "ssh $1 --zone asia-northeast1-a"
+//! ```
+//!
+//! An alias is split into words as a shell would split it. Within it, `$1`
+//! to `$9` are the arguments given after the alias, `$@` is all of them,
+//! and `$NAME` or `${NAME}` is an environment variable. Arguments no `$N`
+//! or `$@` takes are appended, so `gcectl up --zone z` narrows the alias.
+//! Aliases may use other aliases, but never shadow a built-in command.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::ffi::OsString;
+
+use anyhow::{Context, Result, bail};
+use clap::CommandFactory;
+use gcectl_core::config::Config;
+use gcectl_core::output::Render;
+use serde::Serialize;
+
+use crate::cli::Cli;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Alias {
+    pub name: String,
+    pub expansion: String,
+}
+
+impl Render for Alias {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Expansion"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.name.clone(), self.expansion.clone()]
+    }
+}
+
+/// The process arguments with the configured aliases expanded.
+pub fn args() -> Result<Vec<OsString>> {
+    let args: Vec<OsString> = env::args_os().collect();
+    let config = match Config::load() {
+        Ok(config) => config,
+        // the command itself reports a broken config if it reads one
+        Err(_) => return Ok(args),
+    };
+    expand(args, &config.alias)
+}
+
+/// `args` with the alias in the command's place, if any, expanded.
+pub fn expand(
+    mut args: Vec<OsString>,
+    aliases: &BTreeMap<String, String>,
+) -> Result<Vec<OsString>> {
+    if aliases.is_empty() {
+        return Ok(args);
+    }
+    let command = Cli::command();
+    let mut seen = BTreeSet::new();
+    while let Some(at) = command_position(&command, &args) {
+        let Some(name) = args[at].to_str().map(String::from) else {
+            break;
+        };
+        let Some(expansion) = aliases.get(&name).filter(|_| !builtin(&name)) else {
+            break;
+        };
+        if !seen.insert(name.clone()) {
+            bail!("alias `{name}` expands to itself");
+        }
+        let rest: Vec<String> = args[at + 1..]
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        let words =
+            substitute(expansion, &rest).with_context(|| format!("invalid alias `{name}`"))?;
+        args.truncate(at);
+        args.extend(words.into_iter().map(OsString::from));
+    }
+    Ok(args)
+}
+
+/// The words of `line` as an alias's are split, with environment
+/// variables expanded.
+pub fn split(line: &str) -> Result<Vec<String>> {
+    substitute(line, &[])
+}
+
+/// Whether `name` is a built-in command, which no alias may replace.
+pub fn builtin(name: &str) -> bool {
+    name == "help" || Cli::command().find_subcommand(name).is_some()
+}
+
+// index of the first argument that is not a top-level flag or its value
+fn command_position(command: &clap::Command, args: &[OsString]) -> Option<usize> {
+    let takes_value = |arg: Option<&clap::Arg>| arg.is_some_and(|a| a.get_action().takes_values());
+    let mut i = 1;
+    while i < args.len() {
+        let arg = args[i].to_str()?;
+        if arg == "--" {
+            return None;
+        }
+        if let Some(long) = arg.strip_prefix("--") {
+            let flag = command.get_arguments().find(|a| a.get_long() == Some(long));
+            if !long.contains('=') && takes_value(flag) {
+                i += 1;
+            }
+        } else if let Some(shorts) = arg.strip_prefix('-').filter(|s| !s.is_empty()) {
+            // in `-vo json` only the last flag can take the next argument
+            for (at, short) in shorts.char_indices() {
+                let flag = command
+                    .get_arguments()
+                    .find(|a| a.get_short() == Some(short));
+                if takes_value(flag) {
+                    if at + short.len_utf8() == shorts.len() {
+                        i += 1;
+                    }
+                    break;
+                }
+            }
+        } else {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+// the words of `expansion` with `args` substituted and the unused ones appended
+fn substitute(expansion: &str, args: &[String]) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut used = vec![false; args.len()];
+    // `Some` once a word has begun, so that `''` is an empty word
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = expansion.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_default();
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None | Some('"'), '\\') => match chars.next() {
+                Some(next) => word.get_or_insert_default().push(next),
+                None => bail!("trailing backslash"),
+            },
+            (None | Some('"'), '$') => match chars.peek().copied() {
+                Some('@') => {
+                    chars.next();
+                    used.fill(true);
+                    let alone = quote.is_none()
+                        && word.is_none()
+                        && chars.peek().is_none_or(|c| c.is_whitespace());
+                    match alone {
+                        true => words.extend(args.iter().cloned()),
+                        false => word.get_or_insert_default().push_str(&args.join(" ")),
+                    }
+                }
+                Some(digit) if digit.is_ascii_digit() => {
+                    chars.next();
+                    let n = digit.to_digit(10).unwrap_or_default() as usize;
+                    let Some(arg) = n.checked_sub(1).and_then(|i| args.get(i)) else {
+                        bail!("${n} needs at least {n} argument(s) after the alias");
+                    };
+                    used[n - 1] = true;
+                    word.get_or_insert_default().push_str(arg);
+                }
+                Some('{') => {
+                    chars.next();
+                    let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                    word.get_or_insert_default().push_str(&variable(&name)?);
+                }
+                Some(first) if first == '_' || first.is_ascii_alphabetic() => {
+                    let mut name = String::new();
+                    while let Some(c) = chars.next_if(|c| *c == '_' || c.is_ascii_alphanumeric()) {
+                        name.push(c);
+                    }
+                    word.get_or_insert_default().push_str(&variable(&name)?);
+                }
+                _ => word.get_or_insert_default().push('$'),
+            },
+            (_, c) => word.get_or_insert_default().push(c),
+        }
+    }
+    if let Some(quote) = quote {
+        bail!("unterminated {quote}");
+    }
+    words.extend(word);
+    let unused = args.iter().zip(&used).filter(|(_, used)| !**used);
+    words.extend(unused.map(|(arg, _)| arg.clone()));
+    Ok(words)
+}
+
+fn variable(name: &str) -> Result<String> {
+    env::var(name).with_context(|| format!("${name} is not set"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expanded(line: &[&str], aliases: &[(&str, &str)]) -> Result<Vec<String>> {
+        let aliases = aliases
+            .iter()
+            .map(|(name, expansion)| (name.to_string(), expansion.to_string()))
+            .collect();
+        let args = line.iter().map(OsString::from).collect();
+        Ok(expand(args, &aliases)?
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn aliases_expand_in_the_command_position() {
+        let aliases = [
+            ("sh", "ssh $1 --zone 'asia northeast'"),
+            ("web", "sh web-$1"),
+            ("all", "instances list $@ --all-zones"),
+            ("instances", "disks"),
+        ];
+        assert_eq!(
+            expanded(
+                &["gcectl", "-o", "json", "web", "1", "--", "uptime"],
+                &aliases
+            )
+            .unwrap(),
+            [
+                "gcectl",
+                "-o",
+                "json",
+                "ssh",
+                "web-1",
+                "--zone",
+                "asia northeast",
+                "--",
+                "uptime"
+            ]
+        );
+        assert_eq!(
+            expanded(&["gcectl", "all", "--limit", "5"], &aliases).unwrap(),
+            ["gcectl", "instances", "list", "--limit", "5", "--all-zones"]
+        );
+        // built-in commands win, and flag values are not commands
+        assert_eq!(
+            expanded(
+                &["gcectl", "--profile", "sh", "instances", "list"],
+                &aliases
+            )
+            .unwrap(),
+            ["gcectl", "--profile", "sh", "instances", "list"]
+        );
+        assert!(expanded(&["gcectl", "sh"], &aliases).is_err());
+        assert!(expanded(&["gcectl", "a"], &[("a", "b"), ("b", "a")]).is_err());
+    }
+
+    #[test]
+    fn single_quotes_keep_dollars() {
+        let words = substitute(r#"run '$1' "$1" \$1 ${HOME}x"#, &["web".into()]).unwrap();
+        assert_eq!(words[1..4], ["$1", "web", "$1"]);
+        assert_eq!(words[4], format!("{}x", env::var("HOME").unwrap()));
+    }
+}
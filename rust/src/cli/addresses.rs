@@ -0,0 +1,127 @@
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+use gcectl_core::resources::address::NetworkTier;
+
+#[derive(Debug, Subcommand)]
+pub enum AddressesCommand {
+    /// List reserved addresses
+    List(AddressListArgs),
+    /// Show a reserved address and what uses it
+    Describe(AddressArgs),
+    /// Reserve a static IP address
+    Reserve(AddressReserveArgs),
+    /// Release one or more reserved addresses
+    Release(AddressReleaseArgs),
+}
+
+/// Project and region selection for addresses, which may also be global.
+#[derive(Debug, Args)]
+pub struct AddressScopeArgs {
+    // project that owns the addresses
+    #[arg(long, help = "Google Cloud project ID [default: from profile]")]
+    pub project: Option<String>,
+
+    // region the addresses live in
+    #[arg(
+        long,
+        help = "Compute Engine region, e.g. asia-northeast1 [default: from profile or its zone]"
+    )]
+    pub region: Option<String>,
+
+    // global addresses serve global load balancers
+    #[arg(
+        long,
+        help = "Use global instead of regional addresses",
+        conflicts_with = "region",
+        default_value_t = false
+    )]
+    pub global: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct AddressListArgs {
+    #[command(flatten)]
+    pub scope: AddressScopeArgs,
+
+    #[arg(
+        long = "all-regions",
+        help = "List addresses in every region, plus global ones",
+        conflicts_with_all = ["region", "global"],
+        default_value_t = false
+    )]
+    pub all_regions: bool,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching addresses, e.g. 'status=RESERVED'"
+    )]
+    pub filter: Option<Filter>,
+}
+
+#[derive(Debug, Args)]
+pub struct AddressArgs {
+    #[arg(value_name = "NAME", help = "Address name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub scope: AddressScopeArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct AddressReserveArgs {
+    #[arg(value_name = "NAME", help = "Name of the new address")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub scope: AddressScopeArgs,
+
+    // otherwise the API picks a free address
+    #[arg(long, value_name = "IP", help = "Specific IP address to reserve")]
+    pub address: Option<String>,
+
+    #[arg(long, help = "Address description")]
+    pub description: Option<String>,
+
+    #[arg(
+        long = "network-tier",
+        value_enum,
+        help = "Network tier of an external address [default: premium]"
+    )]
+    pub network_tier: Option<NetworkTier>,
+
+    // reserving in a subnet makes the address internal
+    #[arg(
+        long,
+        value_name = "SUBNET",
+        help = "Reserve an internal address in this subnet",
+        conflicts_with_all = ["global", "network_tier"]
+    )]
+    pub subnet: Option<String>,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct AddressReleaseArgs {
+    #[arg(value_name = "NAME", required = true, help = "Addresses to release")]
+    pub names: Vec<String>,
+
+    #[command(flatten)]
+    pub scope: AddressScopeArgs,
+
+    // skip the confirmation prompt, for scripts
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Release without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
@@ -0,0 +1,24 @@
+use clap::Subcommand;
+
+#[derive(Debug, Subcommand)]
+pub enum AliasCommand {
+    /// List the aliases in the config file
+    List,
+    /// Add or replace an alias, e.g. `gcectl alias add up 'instances start --filter labels.owner=$USER'`
+    Add {
+        #[arg(value_name = "NAME", help = "Command name the alias adds")]
+        name: String,
+        // one argument, quoted, so $1 and $USER reach the config file unexpanded
+        #[arg(
+            value_name = "EXPANSION",
+            allow_hyphen_values = true,
+            help = "Arguments it stands for; $1..$9 and $@ take the alias's own"
+        )]
+        expansion: String,
+    },
+    /// Remove an alias
+    Remove {
+        #[arg(value_name = "NAME", help = "Alias to remove")]
+        name: String,
+    },
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use super::SshConnectArgs;
+
+#[derive(Debug, Args)]
+pub struct BakeArgs {
+    #[arg(
+        long,
+        short = 'f',
+        value_name = "PATH",
+        help = "Bake file listing the images to build"
+    )]
+    pub file: PathBuf,
+
+    // --project and --zone override the bake file's
+    #[command(flatten)]
+    pub connect: SshConnectArgs,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 4,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Images to bake at once"
+    )]
+    pub parallel: u32,
+
+    // the same for every build, so images baked together match
+    #[arg(
+        long,
+        value_name = "VERSION",
+        help = "Suffix of the image names, after the family [default: v and the UTC time]"
+    )]
+    pub version: Option<String>,
+
+    // for debugging a failed step over ssh; delete it with `instances delete`
+    #[arg(
+        long = "keep-on-failure",
+        help = "Leave the builder instance of a failed build running",
+        default_value_t = false
+    )]
+    pub keep_on_failure: bool,
+
+    // hooks come from the config file's [notify] table
+    #[arg(
+        long,
+        help = "Notify through the configured hooks when the operation finishes or fails",
+        default_value_t = false
+    )]
+    pub notify: bool,
+}
@@ -0,0 +1,7 @@
+use clap::Subcommand;
+
+#[derive(Debug, Subcommand)]
+pub enum CacheCommand {
+    /// Delete every cached API response and completion candidate
+    Clear,
+}
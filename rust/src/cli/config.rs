@@ -0,0 +1,34 @@
+use clap::{Args, Subcommand};
+use gcectl_core::config::ProfileKey;
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Set a default in the selected profile
+    Set {
+        #[arg(value_enum, help = "Setting to change")]
+        key: ProfileKey,
+        #[arg(help = "New value")]
+        value: String,
+    },
+    /// Print a default from the selected profile
+    Get {
+        #[arg(value_enum, help = "Setting to print")]
+        key: ProfileKey,
+    },
+    /// List configured profiles
+    ListProfiles,
+    /// Show the project, zone, and region commands would use, and where
+    /// each comes from
+    Doctor(DoctorArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct DoctorArgs {
+    // flags as another command would be given them
+    #[arg(long, help = "Project ID as passed to a command")]
+    pub project: Option<String>,
+    #[arg(long, help = "Zone as passed to a command")]
+    pub zone: Option<String>,
+    #[arg(long, help = "Region as passed to a command")]
+    pub region: Option<String>,
+}
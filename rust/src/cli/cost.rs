@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+
+use super::{ZonalArgs, parse_duration};
+
+#[derive(Debug, Subcommand)]
+pub enum CostCommand {
+    /// Estimate the monthly cost of instances from list prices
+    Estimate(CostEstimateArgs),
+    /// Show billed spend per instance from the Cloud Billing export to BigQuery
+    Actual(CostActualArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct CostEstimateArgs {
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // ignore the profile's default zone
+    #[arg(
+        long = "all-zones",
+        help = "Estimate instances in every zone of the project",
+        conflicts_with = "zone",
+        default_value_t = false
+    )]
+    pub all_zones: bool,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only estimate matching instances, e.g. 'status=RUNNING'"
+    )]
+    pub filter: Option<Filter>,
+}
+
+#[derive(Debug, Args)]
+pub struct CostActualArgs {
+    // project whose charges are shown, not the one holding the export
+    #[arg(long, help = "Google Cloud project ID [default: from profile]")]
+    pub project: Option<String>,
+
+    // the export lags usage by up to a day, so short windows read low
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "7d",
+        value_parser = parse_duration,
+        help = "Spend over the last DURATION, e.g. 24h or 30d"
+    )]
+    pub last: Duration,
+
+    #[arg(
+        long,
+        value_name = "PROJECT.DATASET.TABLE",
+        help = "BigQuery table of the billing export [default: billing_export_table from profile]"
+    )]
+    pub table: Option<String>,
+
+    #[arg(
+        long = "by-label",
+        value_name = "KEY",
+        help = "Attribute spend to the values of label KEY instead of to instances"
+    )]
+    pub by_label: Option<String>,
+}
@@ -0,0 +1,7 @@
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct DiagnoseArgs {
+    #[arg(long, help = "Project ID to check the Compute API on")]
+    pub project: Option<String>,
+}
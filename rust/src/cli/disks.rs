@@ -0,0 +1,296 @@
+use std::time::Duration;
+
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+use gcectl_core::resources::disk::DiskMode;
+
+use super::{
+    LabelKeysArgs, LabelsArgs, PagingArgs, ZonalArgs, parse_duration, parse_key_value,
+    parse_kms_key,
+};
+
+#[derive(Debug, Subcommand)]
+pub enum DisksCommand {
+    /// List disks in a zone, or in every zone when none is configured
+    List(DiskListArgs),
+    /// Show the details of a disk
+    Describe(DiskArgs),
+    /// Print the Cloud Console URL of a disk, or open it
+    Open(DiskOpenArgs),
+    /// Create a persistent disk
+    Create(DiskCreateArgs),
+    /// Delete one or more disks
+    Delete(DiskDeleteArgs),
+    /// Grow a disk
+    Resize(DiskResizeArgs),
+    /// Attach a disk to an instance
+    Attach(DiskAttachArgs),
+    /// Detach a disk from an instance
+    Detach(DiskDetachArgs),
+    /// Add or overwrite labels on a disk
+    AddLabels(DiskAddLabelsArgs),
+    /// Remove labels from a disk by key
+    RemoveLabels(DiskRemoveLabelsArgs),
+    /// Check measured I/O against the disk's limits and suggest a resize or type
+    Analyze(DiskAnalyzeArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct DiskListArgs {
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long = "all-zones",
+        help = "List disks in every zone of the project",
+        conflicts_with = "zone",
+        default_value_t = false
+    )]
+    pub all_zones: bool,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching disks, e.g. 'labels.env=prod'"
+    )]
+    pub filter: Option<Filter>,
+
+    #[command(flatten)]
+    pub paging: PagingArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct DiskArgs {
+    #[arg(value_name = "NAME", help = "Disk name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct DiskAddLabelsArgs {
+    #[arg(value_name = "NAME", help = "Disk name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub labels: LabelsArgs,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct DiskRemoveLabelsArgs {
+    #[arg(value_name = "NAME", help = "Disk name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub keys: LabelKeysArgs,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct DiskAnalyzeArgs {
+    #[arg(value_name = "NAME", help = "Disk name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // peaks are per-minute rates over a day; longer windows smooth them
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "24h",
+        value_parser = parse_duration,
+        help = "Lookback window, e.g. 6h or 7d"
+    )]
+    pub window: Duration,
+}
+
+#[derive(Debug, Args)]
+pub struct DiskCreateArgs {
+    #[arg(value_name = "NAME", help = "Name of the new disk")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(long, value_name = "GB", help = "Disk size in GB")]
+    pub size: Option<u64>,
+
+    #[arg(
+        long = "type",
+        value_name = "TYPE",
+        help = "Disk type, e.g. pd-balanced or pd-ssd"
+    )]
+    pub disk_type: Option<String>,
+
+    // initial contents; a blank disk when none is given
+    #[arg(
+        long = "image-family",
+        value_name = "FAMILY",
+        conflicts_with_all = ["image", "snapshot"],
+        help = "Initialise from the newest image of a family"
+    )]
+    pub image_family: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "IMAGE",
+        conflicts_with = "snapshot",
+        help = "Initialise from an image"
+    )]
+    pub image: Option<String>,
+
+    #[arg(
+        long = "image-project",
+        value_name = "PROJECT",
+        default_value = "debian-cloud",
+        help = "Project the image belongs to"
+    )]
+    pub image_project: String,
+
+    #[arg(long, value_name = "SNAPSHOT", help = "Initialise from a snapshot")]
+    pub snapshot: Option<String>,
+
+    // the Compute Engine service agent needs Encrypter/Decrypter on the key
+    #[arg(
+        long = "kms-key",
+        value_name = "KEY",
+        value_parser = parse_kms_key,
+        help = "Cloud KMS key to encrypt the disk with, as projects/P/locations/L/keyRings/R/cryptoKeys/K"
+    )]
+    pub kms_key: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "KEY=VALUE",
+        value_delimiter = ',',
+        value_parser = parse_key_value,
+        help = "Labels to apply, comma separated or repeated"
+    )]
+    pub labels: Vec<(String, String)>,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct DiskDeleteArgs {
+    #[arg(value_name = "NAME", required = true, help = "Disks to delete")]
+    pub names: Vec<String>,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Delete without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct DiskResizeArgs {
+    #[arg(value_name = "NAME", help = "Disk name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // disks can only grow
+    #[arg(
+        long,
+        value_name = "GB",
+        help = "New size in GB; must exceed the current size"
+    )]
+    pub size: u64,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct DiskAttachArgs {
+    #[arg(value_name = "DISK", help = "Disk to attach")]
+    pub disk: String,
+
+    #[arg(long, value_name = "NAME", help = "Instance to attach the disk to")]
+    pub instance: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // appears as /dev/disk/by-id/google-NAME in the guest
+    #[arg(
+        long = "device-name",
+        value_name = "NAME",
+        help = "Device name in the guest [default: the disk name]"
+    )]
+    pub device_name: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = DiskMode::ReadWrite,
+        help = "Attach read-write or read-only"
+    )]
+    pub mode: DiskMode,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct DiskDetachArgs {
+    #[arg(value_name = "DISK", help = "Disk to detach")]
+    pub disk: String,
+
+    #[arg(long, value_name = "NAME", help = "Instance to detach the disk from")]
+    pub instance: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct DiskOpenArgs {
+    #[arg(value_name = "NAME", help = "Disk name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long,
+        help = "Open the page in the browser as well",
+        default_value_t = false
+    )]
+    pub web: bool,
+}
@@ -0,0 +1,84 @@
+use clap::{Args, Subcommand};
+
+use super::ZonalArgs;
+
+#[derive(Debug, Subcommand)]
+pub enum DnsCommand {
+    /// Create or update the A record NAME.DOMAIN to point at instance NAME
+    Register(DnsRegisterArgs),
+    /// Delete the A record NAME.DOMAIN
+    Unregister(DnsUnregisterArgs),
+}
+
+/// Where an instance's record lives.
+#[derive(Debug, Args)]
+pub struct DnsRecordArgs {
+    #[arg(
+        long,
+        value_name = "DOMAIN",
+        help = "Domain the hostname goes under, e.g. dev.example.com [default: dns_domain from profile]"
+    )]
+    pub domain: Option<String>,
+
+    // otherwise the zone whose DNS name is the longest suffix of the hostname
+    #[arg(
+        long = "managed-zone",
+        value_name = "ZONE",
+        help = "Cloud DNS managed zone holding the record [default: the zone serving DOMAIN]"
+    )]
+    pub managed_zone: Option<String>,
+
+    // shared VPC setups often keep DNS in the host project
+    #[arg(
+        long = "dns-project",
+        value_name = "PROJECT",
+        help = "Project owning the managed zone [default: the instance's project]"
+    )]
+    pub dns_project: Option<String>,
+
+    // private zones resolve only inside their networks
+    #[arg(
+        long,
+        help = "Use the internal IP and prefer a private managed zone",
+        default_value_t = false
+    )]
+    pub internal: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct DnsRegisterArgs {
+    #[arg(
+        value_name = "NAME",
+        help = "Instance name, also the hostname's first label"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[command(flatten)]
+    pub record: DnsRecordArgs,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 300,
+        help = "How long resolvers may cache the record"
+    )]
+    pub ttl: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct DnsUnregisterArgs {
+    #[arg(
+        value_name = "NAME",
+        help = "Instance name, the hostname's first label"
+    )]
+    pub name: String,
+
+    #[arg(long, help = "Google Cloud project ID [default: from profile]")]
+    pub project: Option<String>,
+
+    #[command(flatten)]
+    pub record: DnsRecordArgs,
+}
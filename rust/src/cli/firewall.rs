@@ -0,0 +1,170 @@
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+use gcectl_core::resources::firewall::{Allowed, Direction};
+
+use super::ProjectArgs;
+
+#[derive(Debug, Subcommand)]
+pub enum FirewallCommand {
+    /// List firewall rules in the project
+    List(FirewallListArgs),
+    /// Show the details of a firewall rule
+    Describe(FirewallArgs),
+    /// Print the Cloud Console URL of a firewall rule, or open it
+    Open(FirewallOpenArgs),
+    /// Create a firewall rule
+    Create(FirewallCreateArgs),
+    /// Change fields of an existing firewall rule
+    Update(FirewallUpdateArgs),
+    /// Delete one or more firewall rules
+    Delete(FirewallDeleteArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct FirewallListArgs {
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching rules, e.g. 'direction=INGRESS'"
+    )]
+    pub filter: Option<Filter>,
+}
+
+#[derive(Debug, Args)]
+pub struct FirewallArgs {
+    #[arg(value_name = "NAME", help = "Rule name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+}
+
+/// Rule fields accepted by both create and update.
+#[derive(Debug, Args)]
+pub struct FirewallRuleArgs {
+    #[arg(long, value_enum, help = "Traffic direction [default: ingress]")]
+    pub direction: Option<Direction>,
+
+    // lower numbers win
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u32).range(0..=65535),
+        help = "Rule priority, 0-65535 [default: 1000]"
+    )]
+    pub priority: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "PROTOCOL[:PORTS]",
+        value_delimiter = ',',
+        help = "Allowed traffic, e.g. tcp:22,tcp:8000-8080,icmp"
+    )]
+    pub allow: Vec<Allowed>,
+
+    #[arg(
+        long = "source-ranges",
+        value_name = "CIDR",
+        value_delimiter = ',',
+        help = "Source CIDR ranges of ingress traffic [default: 0.0.0.0/0]"
+    )]
+    pub source_ranges: Vec<String>,
+
+    #[arg(
+        long = "destination-ranges",
+        value_name = "CIDR",
+        value_delimiter = ',',
+        help = "Destination CIDR ranges of egress traffic"
+    )]
+    pub destination_ranges: Vec<String>,
+
+    #[arg(
+        long = "source-tags",
+        value_name = "TAG",
+        value_delimiter = ',',
+        help = "Network tags of instances ingress traffic may come from"
+    )]
+    pub source_tags: Vec<String>,
+
+    // without target tags the rule applies to every instance in the network
+    #[arg(
+        long = "target-tags",
+        value_name = "TAG",
+        value_delimiter = ',',
+        help = "Network tags of instances the rule applies to"
+    )]
+    pub target_tags: Vec<String>,
+
+    #[arg(long, help = "Rule description")]
+    pub description: Option<String>,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct FirewallCreateArgs {
+    #[arg(value_name = "NAME", help = "Name of the new rule")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(long, default_value = "default", help = "VPC network")]
+    pub network: String,
+
+    #[command(flatten)]
+    pub rule: FirewallRuleArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct FirewallUpdateArgs {
+    #[arg(value_name = "NAME", help = "Rule to update")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[command(flatten)]
+    pub rule: FirewallRuleArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct FirewallDeleteArgs {
+    #[arg(value_name = "NAME", required = true, help = "Rules to delete")]
+    pub names: Vec<String>,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Delete without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct FirewallOpenArgs {
+    #[arg(value_name = "NAME", help = "Rule name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long,
+        help = "Open the page in the browser as well",
+        default_value_t = false
+    )]
+    pub web: bool,
+}
@@ -0,0 +1,93 @@
+use clap::{Args, Subcommand};
+
+use super::parse_key_value;
+
+#[derive(Debug, Subcommand)]
+pub enum FleetCommand {
+    /// Create identically configured Spot instances spread across zones
+    Create(FleetCreateArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct FleetCreateArgs {
+    // members are named PREFIX-1 .. PREFIX-COUNT
+    #[arg(value_name = "PREFIX", help = "Name prefix of the fleet's instances")]
+    pub prefix: String,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Number of instances to create"
+    )]
+    pub count: u32,
+
+    #[arg(long, help = "Google Cloud project ID [default: from profile]")]
+    pub project: Option<String>,
+
+    // members are spread round-robin; stocked-out zones fall through to the next
+    #[arg(
+        long,
+        value_name = "ZONE",
+        value_delimiter = ',',
+        help = "Zones to spread the fleet over, comma separated [default: profile zone]"
+    )]
+    pub zones: Vec<String>,
+
+    // the zones `placement suggest` ranks for the machine type, best first
+    #[arg(
+        long,
+        conflicts_with = "zones",
+        help = "Spread the fleet over the region's zones likeliest to have capacity"
+    )]
+    pub region: Option<String>,
+
+    #[arg(
+        long = "machine-type",
+        default_value = "e2-medium",
+        help = "Machine type"
+    )]
+    pub machine_type: String,
+
+    #[arg(
+        long = "image-family",
+        value_name = "FAMILY",
+        help = "Boot image family [default: debian-12]",
+        conflicts_with = "image"
+    )]
+    pub image_family: Option<String>,
+
+    #[arg(long, value_name = "IMAGE", help = "Specific boot image")]
+    pub image: Option<String>,
+
+    #[arg(
+        long = "image-project",
+        value_name = "PROJECT",
+        default_value = "debian-cloud",
+        help = "Project the boot image belongs to"
+    )]
+    pub image_project: String,
+
+    #[arg(
+        long = "boot-disk-size",
+        value_name = "GB",
+        help = "Boot disk size in GB"
+    )]
+    pub boot_disk_size: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "KEY=VALUE",
+        value_delimiter = ',',
+        value_parser = parse_key_value,
+        help = "Labels to apply, comma separated or repeated"
+    )]
+    pub labels: Vec<(String, String)>,
+
+    #[arg(
+        long,
+        value_name = "KEY=VALUE",
+        value_parser = parse_key_value,
+        help = "Metadata entry; may be repeated"
+    )]
+    pub metadata: Vec<(String, String)>,
+}
@@ -0,0 +1,18 @@
+use clap::{Args, Subcommand};
+
+use super::ProjectArgs;
+
+#[derive(Debug, Subcommand)]
+pub enum GpusCommand {
+    /// List the zones that offer an accelerator type
+    ListZones(GpuListZonesArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct GpuListZonesArgs {
+    #[arg(value_name = "TYPE", help = "Accelerator type, e.g. nvidia-tesla-t4")]
+    pub accelerator_type: String,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+}
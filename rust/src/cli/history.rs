@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use clap::Args;
+
+use super::parse_duration;
+
+#[derive(Debug, Args)]
+pub struct HistoryArgs {
+    #[arg(
+        long,
+        short = 'n',
+        value_name = "N",
+        default_value_t = 20,
+        help = "Show at most the N most recent entries"
+    )]
+    pub limit: usize,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "Only entries from the last DURATION, e.g. 12h or 7d"
+    )]
+    pub since: Option<Duration>,
+
+    // not defaulted from the profile: history is local and spans projects
+    #[arg(long, help = "Only changes to this project")]
+    pub project: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "TEXT",
+        help = "Only changes whose target contains TEXT, e.g. instances/web"
+    )]
+    pub target: Option<String>,
+
+    #[arg(long, help = "Only changes made by this local user")]
+    pub user: Option<String>,
+}
@@ -0,0 +1,152 @@
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+use gcectl_core::resources::image::DeprecationState;
+
+use super::{ProjectArgs, ZonalArgs, parse_key_value};
+
+#[derive(Debug, Subcommand)]
+pub enum ImagesCommand {
+    /// List images in a project, e.g. --project debian-cloud
+    List(ImageListArgs),
+    /// Show the details of an image
+    Describe(ImageDescribeArgs),
+    /// Print the Cloud Console URL of an image, or open it
+    Open(ImageOpenArgs),
+    /// Create an image from a disk
+    Create(ImageCreateArgs),
+    /// Delete one or more images
+    Delete(ImageDeleteArgs),
+    /// Change the deprecation state of an image
+    Deprecate(ImageDeprecateArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ImageListArgs {
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching images, e.g. 'family=debian-12'"
+    )]
+    pub filter: Option<Filter>,
+
+    // public image projects keep years of superseded images
+    #[arg(
+        long = "show-deprecated",
+        help = "Include deprecated and obsolete images",
+        default_value_t = false
+    )]
+    pub show_deprecated: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ImageDescribeArgs {
+    #[arg(
+        value_name = "NAME",
+        required_unless_present = "family",
+        help = "Image name"
+    )]
+    pub name: Option<String>,
+
+    // resolve the newest image of a family instead of a fixed name
+    #[arg(
+        long,
+        value_name = "FAMILY",
+        conflicts_with = "name",
+        help = "Describe the newest image in this family"
+    )]
+    pub family: Option<String>,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct ImageCreateArgs {
+    #[arg(value_name = "NAME", help = "Name of the new image")]
+    pub name: String,
+
+    // --zone is the zone of the source disk
+    #[arg(long = "source-disk", value_name = "DISK", help = "Disk to image")]
+    pub source_disk: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(long, value_name = "FAMILY", help = "Image family to add the image to")]
+    pub family: Option<String>,
+
+    #[arg(long, help = "Image description")]
+    pub description: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "KEY=VALUE",
+        value_delimiter = ',',
+        value_parser = parse_key_value,
+        help = "Labels to apply, comma separated or repeated"
+    )]
+    pub labels: Vec<(String, String)>,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ImageDeleteArgs {
+    #[arg(value_name = "NAME", required = true, help = "Images to delete")]
+    pub names: Vec<String>,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Delete without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ImageDeprecateArgs {
+    #[arg(value_name = "NAME", help = "Image name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(long, value_enum, help = "New deprecation state")]
+    pub state: DeprecationState,
+
+    #[arg(
+        long,
+        value_name = "IMAGE",
+        help = "Image users should move to, in the same project"
+    )]
+    pub replacement: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ImageOpenArgs {
+    #[arg(value_name = "NAME", help = "Image name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long,
+        help = "Open the page in the browser as well",
+        default_value_t = false
+    )]
+    pub web: bool,
+}
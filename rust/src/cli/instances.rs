@@ -0,0 +1,1256 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{ArgGroup, Args, Subcommand};
+use clap_complete::ArgValueCandidates;
+use gcectl_core::filter::Filter;
+use gcectl_core::naming::NameTemplate;
+use gcectl_core::resources::Accelerator;
+use gcectl_core::resources::instance::{
+    MaintenancePolicy, ProvisioningModel, SchedulingChange, TerminationAction,
+};
+
+use super::{
+    LabelKeysArgs, LabelsArgs, MetadataArgs, MetadataKeysArgs, PagingArgs, ZonalArgs,
+    parse_duration, parse_key_value, parse_kms_key, parse_non_negative, parse_percent,
+};
+use crate::completion;
+
+#[derive(Debug, Subcommand)]
+pub enum InstancesCommand {
+    /// List instances in a zone, or in every zone when none is configured
+    List(InstanceListArgs),
+    /// Show the full configuration of an instance
+    Describe(DescribeArgs),
+    /// Create a new instance
+    Create(Box<CreateArgs>),
+    /// Create a copy of an existing instance, with fresh disks and addresses
+    CreateFrom(CreateFromArgs),
+    /// Change an instance's Shielded VM and Confidential VM settings,
+    /// stopping and restarting it if needed
+    Update(InstanceUpdateArgs),
+    /// Delete instances by name, glob, or filter
+    Delete(DeleteArgs),
+    /// Re-poll and redraw instance status until interrupted
+    Watch(WatchArgs),
+    /// Start stopped instances by name, glob, or filter
+    Start(LifecycleArgs),
+    /// Stop running instances by name, glob, or filter
+    Stop(StopArgs),
+    /// Hard-reset running instances, like pressing the reset button
+    Reset(LifecycleArgs),
+    /// Suspend running instances, keeping memory state; only disks are billed
+    Suspend(LifecycleArgs),
+    /// Resume suspended instances
+    Resume(LifecycleArgs),
+    /// Stream an instance's serial console output, like `tail -f`
+    TailSerial(TailSerialArgs),
+    /// Save a PNG of what an instance's display shows, e.g. a kernel panic
+    Screenshot(ScreenshotArgs),
+    /// Test whether a TCP port of an instance is reachable, directly and
+    /// through IAP, and explain which firewall rule decides it
+    CheckPort(CheckPortArgs),
+    /// Add or update metadata entries on an instance
+    AddMetadata(AddMetadataArgs),
+    /// Remove metadata entries from an instance by key
+    RemoveMetadata(RemoveMetadataArgs),
+    /// Add or overwrite labels on an instance
+    AddLabels(InstanceAddLabelsArgs),
+    /// Remove labels from an instance by key
+    RemoveLabels(InstanceRemoveLabelsArgs),
+    /// Add network tags to an instance, e.g. to match firewall rules
+    AddTags(InstanceTagsArgs),
+    /// Remove network tags from an instance
+    RemoveTags(InstanceTagsArgs),
+    /// Print the Cloud Console URL of an instance, or open it
+    Open(InstanceOpenArgs),
+    /// Replace an instance's startup script, optionally resetting it to run now
+    SetStartupScript(SetStartupScriptArgs),
+    /// Print an instance's startup script
+    GetStartupScript(GetStartupScriptArgs),
+    /// Create or reset a Windows account through the guest agent, printing its new password
+    ResetWindowsPassword(ResetWindowsPasswordArgs),
+    /// Compare the configuration of two instances
+    Diff(InstanceDiffArgs),
+    /// Change an instance's machine type, stopping and restarting it if needed
+    SetMachineType(SetMachineTypeArgs),
+    /// Change an instance's service account and scopes, stopping and restarting it if needed
+    SetServiceAccount(SetServiceAccountArgs),
+    /// Change how an instance is scheduled, stopping and restarting it if a
+    /// change needs that
+    SetScheduling(SetSchedulingArgs),
+    /// Give an instance a reserved static external IP address
+    AssignIp(AssignIpArgs),
+    /// Move an instance to another zone by recreating it from snapshots of
+    /// its disks
+    Move(MoveArgs),
+    /// List running instances whose CPU and network stayed low
+    Idle(IdleArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ListArgs {
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // ignore the profile's default zone
+    #[arg(
+        long = "all-zones",
+        help = "List instances in every zone of the project",
+        conflicts_with = "zone",
+        default_value_t = false
+    )]
+    pub all_zones: bool,
+
+    // the profile's `projects` if set, else every active project the
+    // account can see through Cloud Resource Manager
+    #[arg(
+        long = "all-projects",
+        help = "List instances across projects, with a Project column",
+        conflicts_with = "project",
+        default_value_t = false
+    )]
+    pub all_projects: bool,
+
+    // translated into the API's server-side filter
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching instances, e.g. 'labels.env=prod AND status=RUNNING'"
+    )]
+    pub filter: Option<Filter>,
+
+    // network tags decide which firewall rules apply to an instance
+    #[arg(
+        long = "show-tags",
+        help = "Add a column with each instance's network tags",
+        default_value_t = false
+    )]
+    pub show_tags: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct InstanceListArgs {
+    #[command(flatten)]
+    pub list: ListArgs,
+
+    #[command(flatten)]
+    pub paging: PagingArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct WatchArgs {
+    #[command(flatten)]
+    pub list: ListArgs,
+
+    #[arg(
+        long,
+        short = 'n',
+        value_name = "SECONDS",
+        default_value_t = 5,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Seconds between polls"
+    )]
+    pub interval: u64,
+
+    // handy when waiting for a fleet to boot or shut down
+    #[arg(
+        long,
+        value_name = "STATUS",
+        help = "Exit once every listed instance reaches STATUS, e.g. RUNNING"
+    )]
+    pub until: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct DescribeArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+}
+
+/// Which instances of the zone a batch command acts on; at least one of
+/// names, `--filter`, or `--all` is required.
+#[derive(Debug, Args)]
+#[group(required = true, multiple = true)]
+pub struct SelectionArgs {
+    // quote globs so the shell does not expand them against local files
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance names or globs such as 'training-*'"
+    )]
+    pub names: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Act on instances matching a filter, e.g. 'labels.env=dev'"
+    )]
+    pub filter: Option<Filter>,
+
+    #[arg(
+        long,
+        conflicts_with = "names",
+        help = "Act on every instance in the zone",
+        default_value_t = false
+    )]
+    pub all: bool,
+}
+
+/// Arguments for commands that change instances' power state.
+#[derive(Debug, Args)]
+pub struct LifecycleArgs {
+    #[command(flatten)]
+    pub selection: SelectionArgs,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // only asked when globs, --filter, or --all picked the instances
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Act on a glob, filter, or --all selection without asking",
+        default_value_t = false
+    )]
+    pub force: bool,
+
+    // return as soon as the operation is accepted
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+
+    // hooks come from the config file's [notify] table
+    #[arg(
+        long,
+        conflicts_with = "no_wait",
+        help = "Notify through the configured hooks when the operation finishes or fails",
+        default_value_t = false
+    )]
+    pub notify: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct StopArgs {
+    #[command(flatten)]
+    pub lifecycle: LifecycleArgs,
+
+    // membership comes back on the next `instances start`
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Leave instance groups, and so load balancer backends, then wait SECONDS for connections to drain before stopping"
+    )]
+    pub drain: Option<u64>,
+}
+
+#[derive(Debug, Args)]
+pub struct ResumeArgs {
+    // written under the config directory when some members of a batch fail
+    #[arg(value_name = "FILE", help = "Resume file named by the failed batch")]
+    pub file: PathBuf,
+
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Retry without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct CreateArgs {
+    #[arg(
+        value_name = "NAME",
+        required_unless_present = "name_template",
+        conflicts_with = "name_template",
+        help = "Name of the new instance"
+    )]
+    pub name: Option<String>,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // placeholders: {index}, {index:03} for zero padding, {zone}, {date}
+    #[arg(
+        long = "name-template",
+        value_name = "TEMPLATE",
+        help = "Name instances from a template, e.g. 'worker-{index:03}'"
+    )]
+    pub name_template: Option<NameTemplate>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..=1000),
+        requires = "name_template",
+        help = "Number of instances to create concurrently"
+    )]
+    pub count: u32,
+
+    // to add workers after ones already numbered
+    #[arg(
+        long = "start-index",
+        value_name = "N",
+        default_value_t = 1,
+        requires = "name_template",
+        help = "Index of the first instance"
+    )]
+    pub start_index: u32,
+
+    #[command(flatten)]
+    pub properties: InstancePropertiesArgs,
+
+    // a guest-side shutdown timer, so the instance stops even if gcectl is
+    // never run again
+    #[arg(
+        long = "max-lifetime",
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "Stop the instance this long after each boot, e.g. 8h"
+    )]
+    pub max_lifetime: Option<Duration>,
+
+    // machine type, image, boot disk size, GPUs, and names are otherwise
+    // checked before anything is sent
+    #[arg(
+        long = "skip-validation",
+        help = "Leave checking the request to the API",
+        default_value_t = false
+    )]
+    pub skip_validation: bool,
+
+    // NAME.<dns_domain> in the managed zone serving the profile's domain;
+    // instances without an external IP get their internal one, in a
+    // private zone
+    #[arg(
+        long = "auto-dns",
+        help = "Register a Cloud DNS A record for each instance once it is created",
+        conflicts_with = "no_wait",
+        default_value_t = false
+    )]
+    pub auto_dns: bool,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+
+    // hooks come from the config file's [notify] table
+    #[arg(
+        long,
+        conflicts_with = "no_wait",
+        help = "Notify through the configured hooks when the operation finishes or fails",
+        default_value_t = false
+    )]
+    pub notify: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct CreateFromArgs {
+    #[arg(value_name = "NAME", help = "Name of the new instance")]
+    pub name: String,
+
+    #[arg(
+        long,
+        value_name = "INSTANCE",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance to copy"
+    )]
+    pub source: String,
+
+    // the new instance goes to --zone; the source may live elsewhere
+    #[arg(
+        long = "source-zone",
+        value_name = "ZONE",
+        add = ArgValueCandidates::new(completion::zones),
+        help = "Zone of the source instance [default: same as --zone]"
+    )]
+    pub source_zone: Option<String>,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long = "machine-type",
+        value_name = "TYPE",
+        help = "Machine type [default: the source's]"
+    )]
+    pub machine_type: Option<String>,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+/// Machine, disk, network, and identity settings shared by instances and
+/// instance templates.
+#[derive(Debug, Args)]
+pub struct InstancePropertiesArgs {
+    #[arg(
+        long = "machine-type",
+        default_value = "e2-medium",
+        add = ArgValueCandidates::new(completion::machine_types),
+        help = "Machine type"
+    )]
+    pub machine_type: String,
+
+    // boot image; a family resolves to its newest image
+    #[arg(
+        long = "image-family",
+        value_name = "FAMILY",
+        add = ArgValueCandidates::new(completion::image_families),
+        help = "Boot image family [default: debian-12]",
+        conflicts_with = "image"
+    )]
+    pub image_family: Option<String>,
+
+    #[arg(long, value_name = "IMAGE", help = "Specific boot image")]
+    pub image: Option<String>,
+
+    #[arg(
+        long = "image-project",
+        value_name = "PROJECT",
+        default_value = "debian-cloud",
+        help = "Project the boot image belongs to"
+    )]
+    pub image_project: String,
+
+    #[arg(
+        long = "boot-disk-size",
+        value_name = "GB",
+        help = "Boot disk size in GB"
+    )]
+    pub boot_disk_size: Option<u64>,
+
+    #[arg(
+        long = "boot-disk-type",
+        value_name = "TYPE",
+        help = "Boot disk type, e.g. pd-balanced or pd-ssd"
+    )]
+    pub boot_disk_type: Option<String>,
+
+    // the Compute Engine service agent needs Encrypter/Decrypter on the key
+    #[arg(
+        long = "kms-key",
+        value_name = "KEY",
+        value_parser = parse_kms_key,
+        help = "Cloud KMS key to encrypt the boot disk with, as projects/P/locations/L/keyRings/R/cryptoKeys/K"
+    )]
+    pub kms_key: Option<String>,
+
+    #[arg(long, help = "VPC network [default: default]")]
+    pub network: Option<String>,
+
+    #[arg(long, help = "Subnetwork in the instance's region")]
+    pub subnet: Option<String>,
+
+    // skip the ephemeral external IP
+    #[arg(
+        long = "no-address",
+        help = "Do not assign an external IP",
+        default_value_t = false
+    )]
+    pub no_address: bool,
+
+    #[arg(
+        long,
+        value_name = "KEY=VALUE",
+        value_delimiter = ',',
+        value_parser = parse_key_value,
+        help = "Labels to apply, comma separated or repeated"
+    )]
+    pub labels: Vec<(String, String)>,
+
+    #[arg(
+        long,
+        value_name = "KEY=VALUE",
+        value_parser = parse_key_value,
+        help = "Metadata entry; may be repeated"
+    )]
+    pub metadata: Vec<(String, String)>,
+
+    #[arg(long, help = "Create a preemptible instance", conflicts_with = "spot")]
+    pub preemptible: bool,
+
+    #[arg(long, help = "Create a Spot instance")]
+    pub spot: bool,
+
+    #[arg(
+        long = "service-account",
+        value_name = "EMAIL",
+        help = "Service account to attach"
+    )]
+    pub service_account: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SCOPE",
+        value_delimiter = ',',
+        requires = "service_account",
+        help = "OAuth scopes for the service account [default: cloud-platform]"
+    )]
+    pub scopes: Vec<String>,
+
+    // also switches host maintenance to TERMINATE, which GPUs require
+    #[arg(
+        long,
+        value_name = "type=TYPE[,count=N]",
+        help = "GPUs to attach, e.g. type=nvidia-tesla-t4,count=1; may be repeated"
+    )]
+    pub accelerator: Vec<Accelerator>,
+
+    #[command(flatten)]
+    pub shielded: ShieldedArgs,
+
+    // shorthand for a node affinity on the group's name
+    #[arg(
+        long = "node-group",
+        value_name = "GROUP",
+        help = "Run on the sole-tenant nodes of this node group"
+    )]
+    pub node_group: Option<String>,
+
+    // needs a machine type that supports it, such as n2d-standard-2
+    #[arg(
+        long = "confidential-compute",
+        help = "Create a Confidential VM, encrypting memory in use",
+        default_value_t = false
+    )]
+    pub confidential_compute: bool,
+}
+
+/// Shielded VM options; each flag alone turns the option on and
+/// `--flag=false` turns it off.
+#[derive(Debug, Args)]
+pub struct ShieldedArgs {
+    #[arg(
+        long = "shielded-secure-boot",
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Only boot software signed by a trusted authority"
+    )]
+    pub secure_boot: Option<bool>,
+
+    #[arg(
+        long = "shielded-vtpm",
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Attach a virtual Trusted Platform Module"
+    )]
+    pub vtpm: Option<bool>,
+
+    #[arg(
+        long = "shielded-integrity-monitoring",
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Compare each boot against the instance's integrity baseline"
+    )]
+    pub integrity_monitoring: Option<bool>,
+}
+
+#[derive(Debug, Args)]
+pub struct InstanceUpdateArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[command(flatten)]
+    pub shielded: ShieldedArgs,
+
+    #[arg(
+        long = "confidential-compute",
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Turn Confidential VM on or off"
+    )]
+    pub confidential_compute: Option<bool>,
+
+    #[command(flatten)]
+    pub restart: RestartArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct DeleteArgs {
+    #[command(flatten)]
+    pub selection: SelectionArgs,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // skip the confirmation prompt, for scripts
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Delete without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct AddMetadataArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[command(flatten)]
+    pub entries: MetadataArgs,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RemoveMetadataArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[command(flatten)]
+    pub keys: MetadataKeysArgs,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct InstanceAddLabelsArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub labels: LabelsArgs,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct InstanceRemoveLabelsArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub keys: LabelKeysArgs,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct InstanceOpenArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long,
+        help = "Open the page in the browser as well",
+        default_value_t = false
+    )]
+    pub web: bool,
+
+    // signs in through the console, so no keys or firewall rule for port 22
+    // from this machine are needed
+    #[arg(
+        long = "ssh-in-browser",
+        help = "Use the browser-based SSH session instead of the details page",
+        default_value_t = false
+    )]
+    pub ssh_in_browser: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct InstanceTagsArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[arg(value_name = "TAG", required = true, help = "Network tags")]
+    pub tags: Vec<String>,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SetStartupScriptArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // `-` reads the script from stdin
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "File holding the script, or - for stdin"
+    )]
+    pub from_file: String,
+
+    // stored under windows-startup-script-ps1 instead of startup-script
+    #[arg(long, help = "Set the PowerShell script Windows instances run")]
+    pub windows: bool,
+
+    // scripts run on every boot, so a reset is the quickest way to re-run one
+    #[arg(
+        long,
+        help = "Reset a running instance afterwards so the script runs now"
+    )]
+    pub reset: bool,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false,
+        conflicts_with = "reset"
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct GetStartupScriptArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(long, help = "Print the PowerShell script Windows instances run")]
+    pub windows: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ResetWindowsPasswordArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Windows instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // the account is created if it does not exist yet
+    #[arg(long, help = "Windows account to reset [default: $USER]")]
+    pub user: Option<String>,
+
+    // resetting signs the account out of anything using the old password
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Reset without asking",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct InstanceDiffArgs {
+    #[arg(
+        value_name = "NAME1",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "First instance"
+    )]
+    pub first: String,
+
+    #[arg(
+        value_name = "NAME2",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Second instance"
+    )]
+    pub second: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // for comparing across zones, e.g. a VM with its copy in another region
+    #[arg(
+        long = "second-zone",
+        value_name = "ZONE",
+        add = ArgValueCandidates::new(completion::zones),
+        help = "Zone of the second instance [default: same as --zone]"
+    )]
+    pub second_zone: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ScreenshotArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // -o is the global --output
+    #[arg(
+        long,
+        short = 'f',
+        value_name = "PATH",
+        help = "PNG file to write [default: NAME.png]"
+    )]
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct CheckPortArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[arg(value_name = "PORT", help = "TCP port on the instance")]
+    pub port: u16,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // from inside the VPC, e.g. a bastion or another instance
+    #[arg(
+        long = "internal-ip",
+        help = "Connect directly to the internal IP instead of the external one",
+        default_value_t = false
+    )]
+    pub internal_ip: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 5,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Seconds to wait for each connection"
+    )]
+    pub timeout: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct TailSerialArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u8).range(1..=4),
+        help = "Serial port to read"
+    )]
+    pub port: u8,
+
+    #[arg(
+        long,
+        short = 'n',
+        value_name = "SECONDS",
+        default_value_t = 2,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Seconds between polls for new output"
+    )]
+    pub interval: u64,
+
+    // print what is buffered and exit
+    #[arg(
+        long = "no-follow",
+        help = "Exit after printing the buffered output",
+        default_value_t = false
+    )]
+    pub no_follow: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct AssignIpArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // must be reserved in the instance's region
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Name of the reserved address to assign"
+    )]
+    pub address: String,
+
+    #[arg(
+        long = "network-interface",
+        value_name = "NIC",
+        default_value = "nic0",
+        help = "Network interface to attach the address to"
+    )]
+    pub network_interface: String,
+}
+
+#[derive(Debug, Args)]
+pub struct MoveArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long = "destination-zone",
+        value_name = "ZONE",
+        add = ArgValueCandidates::new(completion::zones),
+        help = "Zone to move the instance to"
+    )]
+    pub destination_zone: String,
+
+    // otherwise the stopped source stays as a fallback
+    #[arg(
+        long = "delete-source",
+        help = "Delete the source instance once its replacement runs",
+        default_value_t = false
+    )]
+    pub delete_source: bool,
+
+    // skip the confirmation prompt, for scripts
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Move without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct IdleArgs {
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // ignore the profile's default zone
+    #[arg(
+        long = "all-zones",
+        help = "Check instances in every zone of the project",
+        conflicts_with = "zone",
+        default_value_t = false
+    )]
+    pub all_zones: bool,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "24h",
+        value_parser = parse_duration,
+        help = "Lookback window, e.g. 6h or 7d"
+    )]
+    pub window: Duration,
+
+    #[arg(
+        long = "cpu-threshold",
+        value_name = "PERCENT",
+        default_value_t = 5.0,
+        value_parser = parse_percent,
+        help = "Mean CPU utilization below which an instance is idle"
+    )]
+    pub cpu_threshold: f64,
+
+    // sent and received combined
+    #[arg(
+        long = "network-threshold",
+        value_name = "KB/S",
+        default_value_t = 10.0,
+        value_parser = parse_non_negative,
+        help = "Mean network throughput below which an instance is idle"
+    )]
+    pub network_threshold: f64,
+
+    #[arg(
+        long,
+        help = "Stop the idle instances after confirmation",
+        default_value_t = false
+    )]
+    pub stop: bool,
+
+    // skip the confirmation prompt, for scripts
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        requires = "stop",
+        help = "Stop without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SetMachineTypeArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long = "machine-type",
+        value_name = "TYPE",
+        help = "New machine type, e.g. e2-standard-4"
+    )]
+    pub machine_type: String,
+
+    #[arg(
+        long = "skip-validation",
+        help = "Leave checking the machine type to the API",
+        default_value_t = false
+    )]
+    pub skip_validation: bool,
+
+    #[command(flatten)]
+    pub restart: RestartArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct SetServiceAccountArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long = "service-account",
+        value_name = "EMAIL",
+        help = "Service account to attach, e.g. the project's default compute account"
+    )]
+    pub service_account: String,
+
+    #[arg(
+        long,
+        value_name = "SCOPE",
+        value_delimiter = ',',
+        help = "OAuth scopes for the service account [default: cloud-platform]"
+    )]
+    pub scopes: Vec<String>,
+
+    #[command(flatten)]
+    pub restart: RestartArgs,
+}
+
+#[derive(Debug, Args)]
+#[command(group(
+    ArgGroup::new("settings")
+        .args([
+            "maintenance_policy",
+            "restart_on_failure",
+            "no_restart_on_failure",
+            "provisioning_model",
+            "termination_action",
+        ])
+        .multiple(true)
+        .required(true)
+))]
+pub struct SetSchedulingArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // instances with GPUs can only terminate
+    #[arg(
+        long = "maintenance-policy",
+        value_enum,
+        ignore_case = true,
+        help = "What the instance does during host maintenance"
+    )]
+    pub maintenance_policy: Option<MaintenancePolicy>,
+
+    #[arg(
+        long = "restart-on-failure",
+        conflicts_with = "no_restart_on_failure",
+        help = "Restart the instance after a crash or maintenance termination",
+        default_value_t = false
+    )]
+    pub restart_on_failure: bool,
+
+    #[arg(
+        long = "no-restart-on-failure",
+        help = "Leave the instance stopped after a crash or maintenance termination",
+        default_value_t = false
+    )]
+    pub no_restart_on_failure: bool,
+
+    // needs a stopped instance; Spot also turns off migration and restarts
+    #[arg(
+        long = "provisioning-model",
+        value_enum,
+        ignore_case = true,
+        help = "Run on standard or Spot capacity"
+    )]
+    pub provisioning_model: Option<ProvisioningModel>,
+
+    #[arg(
+        long = "termination-action",
+        value_enum,
+        ignore_case = true,
+        help = "What happens to a Spot instance when its capacity is reclaimed [default: stop]"
+    )]
+    pub termination_action: Option<TerminationAction>,
+
+    #[command(flatten)]
+    pub restart: RestartArgs,
+}
+
+impl SetSchedulingArgs {
+    pub fn change(&self) -> SchedulingChange {
+        let automatic_restart = match (self.restart_on_failure, self.no_restart_on_failure) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        };
+        SchedulingChange {
+            maintenance_policy: self.maintenance_policy,
+            automatic_restart,
+            provisioning_model: self.provisioning_model,
+            termination_action: self.termination_action,
+        }
+    }
+}
+
+/// How changes that need a stopped instance treat a running one.
+#[derive(Debug, Args)]
+pub struct RestartArgs {
+    // by default a running instance is started again afterwards
+    #[arg(
+        long = "no-restart",
+        help = "Leave a running instance stopped after the change",
+        default_value_t = false
+    )]
+    pub no_restart: bool,
+
+    // skip the confirmation prompt, for scripts
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Stop a running instance without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
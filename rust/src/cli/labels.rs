@@ -0,0 +1,36 @@
+use clap::Args;
+
+use super::parse_key_value;
+
+/// Labels to set on a resource.
+#[derive(Debug, Args)]
+pub struct LabelsArgs {
+    #[arg(
+        value_name = "KEY=VALUE",
+        required = true,
+        value_parser = parse_key_value,
+        help = "Labels to add or overwrite"
+    )]
+    pub labels: Vec<(String, String)>,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+/// Keys of labels to remove from a resource.
+#[derive(Debug, Args)]
+pub struct LabelKeysArgs {
+    #[arg(value_name = "KEY", required = true, help = "Label keys to remove")]
+    pub keys: Vec<String>,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
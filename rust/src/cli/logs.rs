@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use clap::Args;
+use clap_complete::ArgValueCandidates;
+
+use super::{ZonalArgs, parse_duration};
+use crate::completion;
+
+#[derive(Debug, Args)]
+pub struct LogsArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // Cloud Logging query language, ANDed with the instance's own filter
+    #[arg(
+        long,
+        value_name = "QUERY",
+        help = "Only matching entries, e.g. 'severity>=ERROR' or 'log_id(\"syslog\")'"
+    )]
+    pub filter: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "1h",
+        value_parser = parse_duration,
+        help = "Only entries from the last DURATION, e.g. 30m or 2d"
+    )]
+    pub since: Duration,
+
+    #[arg(
+        long,
+        short = 'n',
+        value_name = "N",
+        default_value_t = 100,
+        help = "Show at most the N most recent entries"
+    )]
+    pub limit: usize,
+
+    #[arg(
+        long,
+        short = 'f',
+        help = "Keep polling for new entries until interrupted",
+        default_value_t = false
+    )]
+    pub follow: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 5,
+        value_parser = clap::value_parser!(u64).range(1..),
+        requires = "follow",
+        help = "Seconds between polls with --follow"
+    )]
+    pub interval: u64,
+}
@@ -0,0 +1,55 @@
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+use gcectl_core::resources::machine_type::SortKey;
+
+use super::ZonalArgs;
+
+#[derive(Debug, Subcommand)]
+pub enum MachineTypesCommand {
+    /// List the machine types a zone offers, smallest first with --sort-by
+    List(MachineTypeListArgs),
+    /// Show the vCPUs, memory, limits, and estimated price of a machine type
+    Describe(MachineTypeDescribeArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct MachineTypeListArgs {
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // lower bounds are applied after listing
+    #[arg(long, value_name = "N", help = "Only list types with at least N vCPUs")]
+    pub min_cpus: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "GB",
+        help = "Only list types with at least this much memory"
+    )]
+    pub min_memory: Option<f64>,
+
+    // takes the place of the global --sort-by, pricing unknown types last
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        help = "Order of the listing; price uses the bundled estimate"
+    )]
+    pub sort_by: SortKey,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching types, e.g. 'isSharedCpu=false'"
+    )]
+    pub filter: Option<Filter>,
+}
+
+#[derive(Debug, Args)]
+pub struct MachineTypeDescribeArgs {
+    #[arg(value_name = "NAME", help = "Machine type, e.g. n2-standard-8")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+}
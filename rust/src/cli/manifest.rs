@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+use clap_complete::ArgValueCandidates;
+
+use super::{ListArgs, ProjectArgs, ZonalArgs};
+use crate::completion;
+
+#[derive(Debug, Subcommand)]
+pub enum ExportCommand {
+    /// Print instances as a manifest for `gcectl apply`
+    Instances(ListArgs),
+    /// Print an instance as a Terraform resource, with its import command
+    Terraform(TerraformArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct TerraformArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // the instance name with `-` turned into `_` otherwise
+    #[arg(
+        long = "resource-name",
+        value_name = "NAME",
+        help = "Name of the resource in the Terraform configuration"
+    )]
+    pub resource_name: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ApplyArgs {
+    // `-` reads the manifest from stdin
+    #[arg(
+        long,
+        short = 'f',
+        value_name = "PATH",
+        help = "Manifest to apply, as written by `gcectl export instances`"
+    )]
+    pub file: PathBuf,
+
+    // the manifest's `project` is used when this is not given
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    // only in zones the manifest declares instances in
+    #[arg(
+        long,
+        help = "Delete instances the manifest does not declare",
+        default_value_t = false
+    )]
+    pub prune: bool,
+
+    #[arg(
+        long,
+        help = "Print the plan without changing anything",
+        default_value_t = false
+    )]
+    pub plan: bool,
+
+    // skip the confirmation prompt, for scripts
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Apply the plan without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
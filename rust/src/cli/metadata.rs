@@ -0,0 +1,80 @@
+use clap::{Args, Subcommand};
+
+use super::{ProjectArgs, parse_key_value};
+
+#[derive(Debug, Subcommand)]
+pub enum ProjectMetadataCommand {
+    /// List metadata shared by every instance in the project
+    List(ProjectArgs),
+    /// Add or update project-wide metadata entries
+    Add(ProjectMetadataAddArgs),
+    /// Remove project-wide metadata entries by key
+    Remove(ProjectMetadataRemoveArgs),
+}
+
+/// Metadata entries to set, given inline or read from files.
+#[derive(Debug, Args)]
+#[group(required = true, multiple = true)]
+pub struct MetadataArgs {
+    #[arg(
+        long,
+        value_name = "KEY=VALUE",
+        value_parser = parse_key_value,
+        help = "Metadata entry to set; may be repeated"
+    )]
+    pub metadata: Vec<(String, String)>,
+
+    // handy for startup scripts and other multi-line values
+    #[arg(
+        long = "metadata-from-file",
+        value_name = "KEY=PATH",
+        value_parser = parse_key_value,
+        help = "Metadata entry whose value is read from PATH; may be repeated"
+    )]
+    pub metadata_from_file: Vec<(String, String)>,
+}
+
+/// Keys of metadata entries to remove.
+#[derive(Debug, Args)]
+pub struct MetadataKeysArgs {
+    #[arg(
+        long,
+        value_name = "KEY",
+        value_delimiter = ',',
+        required = true,
+        help = "Metadata keys to remove, comma separated or repeated"
+    )]
+    pub keys: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ProjectMetadataAddArgs {
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[command(flatten)]
+    pub entries: MetadataArgs,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ProjectMetadataRemoveArgs {
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[command(flatten)]
+    pub keys: MetadataKeysArgs,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
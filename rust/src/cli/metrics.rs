@@ -0,0 +1,51 @@
+use clap::{Args, Subcommand};
+
+use super::ListArgs;
+
+#[derive(Debug, Subcommand)]
+pub enum MetricsCommand {
+    /// Serve fleet gauges on /metrics for Prometheus to scrape
+    Serve(MetricsServeArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct MetricsServeArgs {
+    #[command(flatten)]
+    pub list: ListArgs,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        default_value_t = 9090,
+        help = "Port to serve /metrics on"
+    )]
+    pub port: u16,
+
+    // 0.0.0.0 lets a Prometheus on another host scrape
+    #[arg(
+        long,
+        value_name = "ADDR",
+        default_value = "127.0.0.1",
+        help = "Address to listen on"
+    )]
+    pub address: String,
+
+    // every refresh lists instances, so keep this well above the scrape interval
+    #[arg(
+        long,
+        short = 'n',
+        value_name = "SECONDS",
+        default_value_t = 60,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Seconds between refreshes of the fleet state"
+    )]
+    pub interval: u64,
+
+    // each key becomes a `label` value of gcectl_instances_by_label
+    #[arg(
+        long = "label",
+        value_name = "KEY",
+        help = "Break instance counts down by this label; repeat for several"
+    )]
+    pub labels: Vec<String>,
+}
@@ -0,0 +1,157 @@
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+
+use super::ZonalArgs;
+
+#[derive(Debug, Subcommand)]
+pub enum MigsCommand {
+    /// List managed instance groups
+    List(MigListArgs),
+    /// Show a managed instance group and its pending actions
+    Describe(MigArgs),
+    /// Create a managed instance group from a template
+    Create(MigCreateArgs),
+    /// Change the number of instances in a group
+    Resize(MigResizeArgs),
+    /// Replace a group's instances with ones from a new template
+    RollingUpdate(MigRollingUpdateArgs),
+    /// Block until a group has no pending actions
+    WaitUntilStable(MigArgs),
+    /// Delete one or more managed instance groups and their instances
+    Delete(MigDeleteArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct MigListArgs {
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long = "all-zones",
+        help = "List groups in every zone of the project",
+        conflicts_with = "zone",
+        default_value_t = false
+    )]
+    pub all_zones: bool,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching groups, e.g. 'name=web'"
+    )]
+    pub filter: Option<Filter>,
+}
+
+#[derive(Debug, Args)]
+pub struct MigArgs {
+    #[arg(value_name = "NAME", help = "Group name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct MigCreateArgs {
+    #[arg(value_name = "NAME", help = "Name of the new group")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        help = "Instance template to create from"
+    )]
+    pub template: String,
+
+    #[arg(long, value_name = "N", help = "Number of instances")]
+    pub size: u32,
+
+    // instances are named BASE-xxxx
+    #[arg(
+        long = "base-instance-name",
+        value_name = "BASE",
+        help = "Prefix of instance names [default: group name]"
+    )]
+    pub base_instance_name: Option<String>,
+
+    #[arg(
+        long = "wait-until-stable",
+        help = "After the group is created, wait until every instance is running",
+        default_value_t = false
+    )]
+    pub wait_until_stable: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MigResizeArgs {
+    #[arg(value_name = "NAME", help = "Group name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(long, value_name = "N", help = "New number of instances")]
+    pub size: u32,
+
+    #[arg(
+        long = "wait-until-stable",
+        help = "Wait until the group has reached the new size",
+        default_value_t = false
+    )]
+    pub wait_until_stable: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MigRollingUpdateArgs {
+    #[arg(value_name = "NAME", help = "Group name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(long, value_name = "TEMPLATE", help = "Template to roll out")]
+    pub template: String,
+
+    // both default to the API's policy when omitted
+    #[arg(
+        long = "max-surge",
+        value_name = "N",
+        help = "Instances that may be created above the target size"
+    )]
+    pub max_surge: Option<u32>,
+
+    #[arg(
+        long = "max-unavailable",
+        value_name = "N",
+        help = "Instances that may be offline at the same time"
+    )]
+    pub max_unavailable: Option<u32>,
+
+    #[arg(
+        long = "wait-until-stable",
+        help = "Wait until every instance runs the new template",
+        default_value_t = false
+    )]
+    pub wait_until_stable: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MigDeleteArgs {
+    #[arg(value_name = "NAME", required = true, help = "Groups to delete")]
+    pub names: Vec<String>,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Delete without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
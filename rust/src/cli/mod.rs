@@ -0,0 +1,623 @@
+//! Command-line interface definition.
+
+mod addresses;
+mod alias;
+mod bake;
+mod cache;
+mod config;
+mod cost;
+mod diagnose;
+mod disks;
+mod dns;
+mod firewall;
+mod fleet;
+mod gpus;
+mod history;
+mod images;
+mod instances;
+mod labels;
+mod logs;
+mod machine_types;
+mod manifest;
+mod metadata;
+mod metrics;
+mod migs;
+mod networks;
+mod nodes;
+mod operations;
+mod os;
+mod placement;
+mod project_info;
+mod quotas;
+mod reservations;
+mod resource_policies;
+mod schedule;
+mod security;
+mod self_update;
+mod service_accounts;
+mod shell;
+mod snapshots;
+mod ssh;
+mod ssh_keys;
+mod templates;
+mod top;
+mod tunnel;
+mod zones;
+
+pub use addresses::*;
+pub use alias::*;
+pub use bake::*;
+pub use cache::*;
+pub use config::*;
+pub use cost::*;
+pub use diagnose::*;
+pub use disks::*;
+pub use dns::*;
+pub use firewall::*;
+pub use fleet::*;
+use gcectl_core::compute::Paging;
+use gcectl_core::endpoints::GoogleApis;
+use gcectl_core::events::EventFormat;
+use gcectl_core::output::{OutputFormat, Projection, SortKey};
+pub use gpus::*;
+pub use history::*;
+pub use images::*;
+pub use instances::*;
+pub use labels::*;
+pub use logs::*;
+pub use machine_types::*;
+pub use manifest::*;
+pub use metadata::*;
+pub use metrics::*;
+pub use migs::*;
+pub use networks::*;
+pub use nodes::*;
+pub use operations::*;
+pub use os::*;
+pub use placement::*;
+pub use project_info::*;
+pub use quotas::*;
+pub use reservations::*;
+pub use resource_policies::*;
+pub use schedule::*;
+pub use security::*;
+pub use self_update::*;
+pub use service_accounts::*;
+pub use shell::*;
+pub use snapshots::*;
+pub use ssh::*;
+pub use ssh_keys::*;
+pub use templates::*;
+pub use top::*;
+pub use tunnel::*;
+pub use zones::*;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use clap::{ArgAction, Args, Parser, Subcommand};
+use clap_complete::ArgValueCandidates;
+
+use crate::completion::{self, Shell};
+
+#[derive(Debug, Parser)]
+#[command(author, version, about, long_about = None)]
+pub struct Cli {
+    // profile from the config file to take defaults from
+    #[arg(
+        long,
+        global = true,
+        env = "GCECTL_PROFILE",
+        help = "Configuration profile to use"
+    )]
+    pub profile: Option<String>,
+
+    // before the subcommand, so one project, zone, or region can front any
+    // command line; a subcommand's own flag still wins
+    #[arg(long, help = "Google Cloud project ID [default: from profile]")]
+    pub project: Option<String>,
+
+    #[arg(
+        long,
+        add = ArgValueCandidates::new(completion::zones),
+        help = "Compute Engine zone, e.g. asia-northeast1-a [default: from profile]"
+    )]
+    pub zone: Option<String>,
+
+    #[arg(
+        long,
+        help = "Compute Engine region, e.g. asia-northeast1 [default: from profile or its zone]"
+    )]
+    pub region: Option<String>,
+
+    // how list/describe results are printed
+    #[arg(
+        long,
+        short = 'o',
+        global = true,
+        value_enum,
+        help = "Output format [default: from profile, else table]"
+    )]
+    pub output: Option<OutputFormat>,
+
+    // API field names, e.g. value(name,networkInterfaces[0].networkIP)
+    #[arg(
+        long,
+        global = true,
+        value_name = "PROJECTION",
+        conflicts_with = "output",
+        help = "Print chosen fields with value(...), csv(...), table(...), or json(...)"
+    )]
+    pub format: Option<Projection>,
+
+    // header names, or prefixes naming one header, e.g. name,zone,status,internal
+    #[arg(
+        long,
+        global = true,
+        value_name = "COLUMNS",
+        conflicts_with = "format",
+        help = "Comma-separated table and CSV columns [default: columns from profile, else all]"
+    )]
+    pub columns: Option<String>,
+
+    // a column as --columns names it; ~ before it sorts descending
+    #[arg(
+        long,
+        global = true,
+        value_name = "COLUMN",
+        help = "Sort listings by a column, e.g. zone or ~name"
+    )]
+    pub sort_by: Option<SortKey>,
+
+    // -v steps, -vv debugging detail, -vvv HTTP requests and responses
+    #[arg(
+        long,
+        short = 'v',
+        global = true,
+        action = ArgAction::Count,
+        help = "Log what gcectl does to stderr; repeat for more detail"
+    )]
+    pub verbose: u8,
+
+    // for scripts: results only, one name per line for tables; errors still
+    // go to stderr and the exit status says what kind of failure it was
+    #[arg(
+        long,
+        short = 'q',
+        global = true,
+        env = "GCECTL_QUIET",
+        help = "Print only results, without spinners, progress lines, or tables"
+    )]
+    pub quiet: bool,
+
+    // for wrapping tools: spinners and progress lines become JSON events,
+    // and results arrive as `output` events
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "FORMAT",
+        env = "GCECTL_EVENTS",
+        help = "Report progress as machine-readable events on stdout"
+    )]
+    pub events: Option<EventFormat>,
+
+    // credentials are redacted from the HTTP dumps
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        env = "GCECTL_LOG_FILE",
+        help = "Append logs to PATH instead of stderr"
+    )]
+    pub log_file: Option<PathBuf>,
+
+    // Ctrl-C also stops a command; either way a pending operation is named
+    // so `operations wait` can pick it up
+    #[arg(
+        long,
+        global = true,
+        value_name = "DURATION",
+        env = "GCECTL_TIMEOUT",
+        value_parser = parse_duration,
+        help = "Give up on the command after this long, e.g. 10m"
+    )]
+    pub timeout: Option<Duration>,
+
+    // transient failures: 429, 5xx, and connection errors
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        env = "GCECTL_RETRIES",
+        help = "Retries for throttled or failed API calls [default: from profile, else 3]"
+    )]
+    pub retries: Option<u32>,
+
+    // shared by every request of the run, so concurrent batches queue up
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        env = "GCECTL_QPS",
+        help = "Most API requests per second, 0 for no limit [default: from profile, else 20]"
+    )]
+    pub qps: Option<f64>,
+
+    // needs roles/iam.serviceAccountTokenCreator on the account
+    #[arg(
+        long = "impersonate-service-account",
+        global = true,
+        value_name = "EMAIL",
+        env = "GCECTL_IMPERSONATE_SERVICE_ACCOUNT",
+        help = "Act as this service account, with tokens minted from the current credentials [default: from profile]"
+    )]
+    pub impersonate_service_account: Option<String>,
+
+    // tokens then last only for the run; nothing is written to disk instead
+    #[arg(
+        long = "no-keyring",
+        global = true,
+        env = "GCECTL_NO_KEYRING",
+        help = "Keep access tokens out of the OS keyring, fetching a fresh one every run"
+    )]
+    pub no_keyring: bool,
+
+    // reads still reach the API so commands can work out what they would change
+    #[arg(
+        long = "dry-run",
+        global = true,
+        env = "GCECTL_DRY_RUN",
+        help = "Print the API requests that would change resources instead of sending them"
+    )]
+    pub dry_run: bool,
+
+    // list results and ssh lookups are reused for --cache-ttl seconds
+    #[arg(
+        long,
+        global = true,
+        env = "GCECTL_NO_CACHE",
+        help = "Always call the API instead of reusing recent results"
+    )]
+    pub no_cache: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "SECONDS",
+        env = "GCECTL_CACHE_TTL",
+        help = "How long cached results stay fresh [default: 30]"
+    )]
+    pub cache_ttl: Option<u64>,
+
+    // a local mock, emulator, or proxy speaking the Compute Engine v1 API
+    #[arg(
+        long,
+        global = true,
+        value_name = "URL",
+        env = "GCECTL_API_ENDPOINT",
+        help = "Send Compute Engine requests to URL instead of compute.googleapis.com [default: from profile]"
+    )]
+    pub api_endpoint: Option<String>,
+
+    // credentials are redacted and token exchanges left out, so the
+    // fixtures can go with a bug report
+    #[arg(
+        long,
+        global = true,
+        value_name = "DIR",
+        env = "GCECTL_RECORD",
+        conflicts_with = "replay",
+        help = "Save every API exchange under DIR as a fixture for --replay"
+    )]
+    pub record: Option<PathBuf>,
+
+    // for demos and deterministic tests
+    #[arg(
+        long,
+        global = true,
+        value_name = "DIR",
+        env = "GCECTL_REPLAY",
+        help = "Answer API requests from the fixtures under DIR, without network or credentials"
+    )]
+    pub replay: Option<PathBuf>,
+
+    // for VMs with Private Google Access and no external route
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        env = "GCECTL_GOOGLE_APIS",
+        help = "Reach Google APIs through the private or restricted VIP [default: from profile]"
+    )]
+    pub google_apis: Option<GoogleApis>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Manage Compute Engine instances
+    #[command(subcommand)]
+    Instances(InstancesCommand),
+    /// Manage instance templates
+    #[command(subcommand)]
+    Templates(TemplatesCommand),
+    /// Create, resize, and roll out managed instance groups
+    #[command(subcommand)]
+    Migs(MigsCommand),
+    /// Manage VPC firewall rules
+    #[command(subcommand)]
+    Firewall(FirewallCommand),
+    /// Estimate what instances cost per month and report what they were billed
+    #[command(subcommand)]
+    Cost(CostCommand),
+    /// Reserve and release static IP addresses
+    #[command(subcommand)]
+    Addresses(AddressesCommand),
+    /// Point Cloud DNS hostnames at instances
+    #[command(subcommand)]
+    Dns(DnsCommand),
+    /// Inspect VPC networks
+    #[command(subcommand)]
+    Networks(NetworksCommand),
+    /// Inspect subnets, their ranges, and free addresses
+    #[command(subcommand)]
+    Subnets(SubnetsCommand),
+    /// List zones and their status
+    #[command(subcommand)]
+    Zones(ZonesCommand),
+    /// List regions with quota usage, e.g. to pick where Spot capacity fits
+    #[command(subcommand)]
+    Regions(RegionsCommand),
+    /// Show quota usage and catch quotas close to their limit
+    #[command(subcommand)]
+    Quotas(QuotasCommand),
+    /// Find where GPU accelerators are offered
+    #[command(subcommand)]
+    Gpus(GpusCommand),
+    /// Browse machine types by size and estimated price
+    #[command(subcommand)]
+    MachineTypes(MachineTypesCommand),
+    /// List service accounts instances can run as
+    #[command(subcommand)]
+    ServiceAccounts(ServiceAccountsCommand),
+    /// Create fleets of Spot instances spread across zones
+    #[command(subcommand)]
+    Fleet(FleetCommand),
+    /// Pick the zones where new capacity is likeliest to fit
+    #[command(subcommand)]
+    Placement(PlacementCommand),
+    /// Manage disk snapshots
+    #[command(subcommand)]
+    Snapshots(SnapshotsCommand),
+    /// Manage custom images
+    #[command(subcommand)]
+    Images(ImagesCommand),
+    /// Bake images from a file: boot a builder, provision it over ssh, and
+    /// image its disk into a family
+    Bake(BakeArgs),
+    /// Manage persistent disks
+    #[command(subcommand)]
+    Disks(DisksCommand),
+    /// Inspect and wait on long-running operations
+    #[command(subcommand)]
+    Operations(OperationsCommand),
+    /// Manage metadata shared by every instance in a project
+    #[command(subcommand)]
+    ProjectMetadata(ProjectMetadataCommand),
+    /// Inspect and change project-wide defaults that affect every VM
+    #[command(subcommand)]
+    ProjectInfo(ProjectInfoCommand),
+    /// Start and stop instances on a recurring schedule
+    #[command(subcommand)]
+    Schedule(ScheduleCommand),
+    /// Manage templates for sole-tenant nodes
+    #[command(subcommand)]
+    NodeTemplates(NodeTemplatesCommand),
+    /// Manage groups of sole-tenant nodes, physical servers dedicated to
+    /// this project
+    #[command(subcommand)]
+    NodeGroups(NodeGroupsCommand),
+    /// Manage reservations, zonal capacity held for VMs of one shape
+    #[command(subcommand)]
+    Reservations(ReservationsCommand),
+    /// Manage snapshot schedules for disks and start/stop schedules for
+    /// instances, run by Compute Engine itself
+    #[command(subcommand)]
+    ResourcePolicies(ResourcePoliciesCommand),
+    /// Inspect guest OS packages and patch results through OS Config
+    #[command(subcommand)]
+    Os(OsCommand),
+    /// Connect to an instance over ssh
+    Ssh(SshArgs),
+    /// Run a command over ssh on many instances at once, e.g.
+    /// `gcectl run 'web-*' -- uptime`
+    Run(RunArgs),
+    /// Copy files to or from an instance with scp
+    Scp(ScpArgs),
+    /// Sync files to or from an instance with rsync over ssh
+    Rsync(RsyncArgs),
+    /// Manage ssh keys in instance or project metadata
+    #[command(subcommand)]
+    SshKeys(SshKeysCommand),
+    /// Forward local ports to instances through Identity-Aware Proxy, one or a file's worth
+    Tunnel(TunnelArgs),
+    /// Live dashboard of instances with start/stop/ssh key bindings
+    Top(TopArgs),
+    /// Show an instance's Cloud Logging entries, and follow new ones
+    Logs(LogsArgs),
+    /// Export fleet state as Prometheus metrics
+    #[command(subcommand)]
+    Metrics(MetricsCommand),
+    /// Print resources as a declarative manifest
+    #[command(subcommand)]
+    Export(ExportCommand),
+    /// Create, update, and delete instances to match a manifest
+    Apply(ApplyArgs),
+    /// Manage configuration profiles
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Manage command aliases, e.g. `gcectl up` for a long `instances start`
+    #[command(subcommand)]
+    Alias(AliasCommand),
+    /// Show the changes gcectl made, from the local audit log
+    History(HistoryArgs),
+    /// Retry only the instances a partially failed start, stop, or delete left undone
+    Resume(ResumeArgs),
+    /// Check credentials, API access, and the network, and suggest fixes
+    Diagnose(DiagnoseArgs),
+    /// Report risky settings across instances
+    #[command(subcommand)]
+    Security(SecurityCommand),
+    /// Manage the local cache of API responses
+    #[command(subcommand)]
+    Cache(CacheCommand),
+    /// Run commands interactively, keeping credentials and context between them
+    Shell(ShellArgs),
+    /// Replace this binary with the latest GitHub release
+    SelfUpdate(SelfUpdateArgs),
+    /// Print a shell completion script, e.g. `source <(gcectl completion bash)`
+    Completion(CompletionArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct CompletionArgs {
+    // instance names and zones complete from the last `instances list`
+    #[arg(value_enum, help = "Shell to print the completion script for")]
+    pub shell: Shell,
+}
+
+/// Project and zone selection shared by zonal commands.
+#[derive(Debug, Args)]
+pub struct ZonalArgs {
+    // project that owns the resources
+    #[arg(long, help = "Google Cloud project ID [default: from profile]")]
+    pub project: Option<String>,
+
+    // zone the resources live in
+    #[arg(
+        long,
+        add = ArgValueCandidates::new(completion::zones),
+        help = "Compute Engine zone, e.g. asia-northeast1-a [default: from profile]"
+    )]
+    pub zone: Option<String>,
+}
+
+/// Project and region selection shared by regional commands.
+#[derive(Debug, Args)]
+pub struct RegionalArgs {
+    // project that owns the resources
+    #[arg(long, help = "Google Cloud project ID [default: from profile]")]
+    pub project: Option<String>,
+
+    // region the resources live in
+    #[arg(
+        long,
+        help = "Compute Engine region, e.g. asia-northeast1 [default: from profile or its zone]"
+    )]
+    pub region: Option<String>,
+}
+
+/// Project selection for global resources.
+#[derive(Debug, Args)]
+pub struct ProjectArgs {
+    // project that owns the resources
+    #[arg(long, help = "Google Cloud project ID [default: from profile]")]
+    pub project: Option<String>,
+}
+
+/// How much of a long listing to fetch, shared by list commands that
+/// print pages as they arrive.
+#[derive(Debug, Args)]
+pub struct PagingArgs {
+    // counted across pages; the API is asked for no more than remain
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Stop after N results"
+    )]
+    pub limit: Option<u32>,
+
+    // smaller pages show the first results sooner
+    #[arg(
+        long = "page-size",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..=500),
+        help = "Results fetched per API request, up to 500 [default: 500]"
+    )]
+    pub page_size: Option<u32>,
+}
+
+impl PagingArgs {
+    pub fn paging(&self) -> Paging {
+        Paging {
+            page_size: self.page_size,
+            limit: self.limit,
+        }
+    }
+}
+
+/// Parses `KEY=VALUE` flag values.
+pub fn parse_key_value(s: &str) -> Result<(String, String)> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => bail!("expected KEY=VALUE, got `{s}`"),
+    }
+}
+
+/// Parses Cloud KMS key names,
+/// `projects/P/locations/L/keyRings/R/cryptoKeys/K`.
+pub fn parse_kms_key(s: &str) -> Result<String> {
+    let parts: Vec<&str> = s.split('/').collect();
+    let labels = ["projects", "locations", "keyRings", "cryptoKeys"];
+    let valid = parts.len() == 8
+        && parts
+            .chunks(2)
+            .zip(labels)
+            .all(|(pair, label)| pair[0] == label && !pair[1].is_empty());
+    if !valid {
+        bail!("expected projects/P/locations/L/keyRings/R/cryptoKeys/K, got `{s}`");
+    }
+    Ok(s.to_string())
+}
+
+/// Parses durations such as `90s`, `30m`, `24h`, or `7d`; zero is refused.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (amount, unit) = s.split_at(split);
+    let Ok(amount) = amount.parse::<u64>() else {
+        bail!("expected a duration like 30m, 24h, or 7d, got `{s}`");
+    };
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => bail!("unknown unit in `{s}`; use s, m, h, or d"),
+    };
+    match amount.checked_mul(seconds) {
+        Some(0) => bail!("`{s}` is no time at all; give a duration above zero"),
+        Some(seconds) => Ok(Duration::from_secs(seconds)),
+        None => bail!("`{s}` is too long a duration"),
+    }
+}
+
+/// Parses percentages from 0 to 100.
+pub fn parse_percent(s: &str) -> Result<f64> {
+    match s.parse::<f64>() {
+        Ok(pct) if (0.0..=100.0).contains(&pct) => Ok(pct),
+        _ => bail!("expected a percentage from 0 to 100, got `{s}`"),
+    }
+}
+
+/// Parses amounts of 0 or more.
+pub fn parse_non_negative(s: &str) -> Result<f64> {
+    match s.parse::<f64>() {
+        Ok(amount) if amount.is_finite() && amount >= 0.0 => Ok(amount),
+        _ => bail!("expected a number of 0 or more, got `{s}`"),
+    }
+}
@@ -0,0 +1,72 @@
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+
+use super::{ProjectArgs, RegionalArgs};
+
+#[derive(Debug, Subcommand)]
+pub enum NetworksCommand {
+    /// List VPC networks in the project
+    List(NetworkListArgs),
+    /// Show a network and its subnets
+    Describe(NetworkArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct NetworkListArgs {
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching networks, e.g. 'name!=default'"
+    )]
+    pub filter: Option<Filter>,
+}
+
+#[derive(Debug, Args)]
+pub struct NetworkArgs {
+    #[arg(value_name = "NAME", help = "Network name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SubnetsCommand {
+    /// List subnets with their primary and secondary ranges
+    List(SubnetListArgs),
+    /// Show a subnet, including how many of its addresses are free
+    Describe(SubnetArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct SubnetListArgs {
+    #[command(flatten)]
+    pub regional: RegionalArgs,
+
+    #[arg(
+        long = "all-regions",
+        help = "List subnets in every region of the project",
+        conflicts_with = "region",
+        default_value_t = false
+    )]
+    pub all_regions: bool,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching subnets, e.g. 'privateIpGoogleAccess=true'"
+    )]
+    pub filter: Option<Filter>,
+}
+
+#[derive(Debug, Args)]
+pub struct SubnetArgs {
+    #[arg(value_name = "NAME", help = "Subnet name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub regional: RegionalArgs,
+}
@@ -0,0 +1,218 @@
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+use gcectl_core::resources::node::MaintenancePolicy;
+
+use super::{RegionalArgs, ZonalArgs, parse_key_value};
+
+#[derive(Debug, Subcommand)]
+pub enum NodeTemplatesCommand {
+    /// List node templates in a region, or in every region when none is configured
+    List(NodeTemplateListArgs),
+    /// Show the details of a node template
+    Describe(NodeTemplateArgs),
+    /// Create a node template
+    Create(NodeTemplateCreateArgs),
+    /// Delete one or more node templates that no node group uses
+    Delete(NodeTemplateDeleteArgs),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum NodeGroupsCommand {
+    /// List node groups in a zone, or in every zone when none is configured
+    List(NodeGroupListArgs),
+    /// Show the details of a node group
+    Describe(NodeGroupArgs),
+    /// Create a node group from a node template
+    Create(NodeGroupCreateArgs),
+    /// Delete one or more node groups that no instance runs on
+    Delete(NodeGroupDeleteArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct NodeTemplateListArgs {
+    #[command(flatten)]
+    pub regional: RegionalArgs,
+
+    #[arg(
+        long = "all-regions",
+        help = "List node templates in every region of the project",
+        conflicts_with = "region",
+        default_value_t = false
+    )]
+    pub all_regions: bool,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching templates, e.g. 'nodeType=n2-*'"
+    )]
+    pub filter: Option<Filter>,
+}
+
+#[derive(Debug, Args)]
+pub struct NodeTemplateArgs {
+    #[arg(value_name = "NAME", help = "Node template name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub regional: RegionalArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct NodeTemplateCreateArgs {
+    #[arg(value_name = "NAME", help = "Name of the new node template")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub regional: RegionalArgs,
+
+    // `gcloud compute sole-tenancy node-types list` shows what a zone offers
+    #[arg(
+        long = "node-type",
+        value_name = "TYPE",
+        help = "Type of node to provision, e.g. n2-node-80-640"
+    )]
+    pub node_type: String,
+
+    // instances pick nodes by these with node affinities
+    #[arg(
+        long = "node-affinity-label",
+        value_name = "KEY=VALUE",
+        value_parser = parse_key_value,
+        help = "Label nodes carry for instances to select; may be repeated"
+    )]
+    pub node_affinity_labels: Vec<(String, String)>,
+
+    // for licenses counted per physical core or socket
+    #[arg(
+        long = "minimal-servers",
+        help = "Keep instances on as few physical servers as possible across maintenance",
+        default_value_t = false
+    )]
+    pub minimal_servers: bool,
+
+    #[arg(long, help = "Node template description")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct NodeTemplateDeleteArgs {
+    #[arg(
+        value_name = "NAME",
+        required = true,
+        help = "Node templates to delete"
+    )]
+    pub names: Vec<String>,
+
+    #[command(flatten)]
+    pub regional: RegionalArgs,
+
+    // skip the confirmation prompt, for scripts
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Delete without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct NodeGroupListArgs {
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long = "all-zones",
+        help = "List node groups in every zone of the project",
+        conflicts_with = "zone",
+        default_value_t = false
+    )]
+    pub all_zones: bool,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching node groups, e.g. 'status=READY'"
+    )]
+    pub filter: Option<Filter>,
+}
+
+#[derive(Debug, Args)]
+pub struct NodeGroupArgs {
+    #[arg(value_name = "NAME", help = "Node group name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct NodeGroupCreateArgs {
+    #[arg(value_name = "NAME", help = "Name of the new node group")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // looked up in the zone's region
+    #[arg(
+        long = "node-template",
+        value_name = "TEMPLATE",
+        help = "Node template the group's nodes are built from"
+    )]
+    pub node_template: String,
+
+    // every node is billed from creation, with or without instances
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        help = "Number of nodes to start with"
+    )]
+    pub size: u32,
+
+    #[arg(
+        long = "maintenance-policy",
+        value_enum,
+        help = "How instances are treated during host maintenance [default: API default]"
+    )]
+    pub maintenance_policy: Option<MaintenancePolicy>,
+
+    #[arg(
+        long = "autoscale-max",
+        value_name = "N",
+        help = "Let the group add nodes up to N as instances need them"
+    )]
+    pub autoscale_max: Option<u32>,
+
+    #[arg(long, help = "Node group description")]
+    pub description: Option<String>,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct NodeGroupDeleteArgs {
+    #[arg(value_name = "NAME", required = true, help = "Node groups to delete")]
+    pub names: Vec<String>,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // skip the confirmation prompt, for scripts
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Delete without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
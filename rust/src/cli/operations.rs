@@ -0,0 +1,78 @@
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+use gcectl_core::resources::operation::OperationScope;
+
+#[derive(Debug, Subcommand)]
+pub enum OperationsCommand {
+    /// List operations, in progress only unless --all is given
+    List(OperationListArgs),
+    /// Show the details of an operation
+    Describe(OperationArgs),
+    /// Wait for an operation to finish, e.g. after --no-wait
+    Wait(OperationArgs),
+}
+
+/// Which operations collection to use; every scope when none is given.
+#[derive(Debug, Args)]
+pub struct OperationScopeArgs {
+    #[arg(long, help = "Google Cloud project ID [default: from profile]")]
+    pub project: Option<String>,
+
+    #[arg(long, help = "Zonal operations in this zone")]
+    pub zone: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with = "zone",
+        help = "Regional operations in this region"
+    )]
+    pub region: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["zone", "region"],
+        help = "Global operations",
+        default_value_t = false
+    )]
+    pub global: bool,
+}
+
+impl OperationScopeArgs {
+    pub fn scope(&self) -> Option<OperationScope> {
+        match (&self.zone, &self.region, self.global) {
+            (Some(zone), _, _) => Some(OperationScope::Zone(zone.clone())),
+            (None, Some(region), _) => Some(OperationScope::Region(region.clone())),
+            (None, None, true) => Some(OperationScope::Global),
+            (None, None, false) => None,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct OperationListArgs {
+    #[command(flatten)]
+    pub scope: OperationScopeArgs,
+
+    #[arg(long, help = "Include finished operations", default_value_t = false)]
+    pub all: bool,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching operations, e.g. 'operationType=insert'"
+    )]
+    pub filter: Option<Filter>,
+}
+
+#[derive(Debug, Args)]
+pub struct OperationArgs {
+    #[arg(
+        value_name = "NAME",
+        help = "Operation name, e.g. operation-1700000000000-..."
+    )]
+    pub name: String,
+
+    // searching every scope costs an extra aggregated request
+    #[command(flatten)]
+    pub scope: OperationScopeArgs,
+}
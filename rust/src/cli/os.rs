@@ -0,0 +1,52 @@
+use clap::{Args, Subcommand};
+use clap_complete::ArgValueCandidates;
+
+use super::ZonalArgs;
+use crate::completion;
+
+#[derive(Debug, Subcommand)]
+pub enum OsCommand {
+    /// Show the OS, packages, and pending updates the guest agent reported
+    Inventory(OsInventoryArgs),
+    /// Show pending updates and the latest patch job result of each instance in a zone
+    PatchStatus(OsPatchStatusArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct OsInventoryArgs {
+    #[arg(
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance name"
+    )]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long,
+        conflicts_with = "updates",
+        help = "List the installed packages instead of the summary"
+    )]
+    pub packages: bool,
+
+    #[arg(long, help = "List the pending updates instead of the summary")]
+    pub updates: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct OsPatchStatusArgs {
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // each job costs one more request for its per-instance results
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 10,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "How many of the newest patch jobs to look through"
+    )]
+    pub jobs: u32,
+}
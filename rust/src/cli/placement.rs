@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use clap::{Args, Subcommand};
+use gcectl_core::resources::Accelerator;
+
+use super::{RegionalArgs, parse_duration};
+
+#[derive(Debug, Subcommand)]
+pub enum PlacementCommand {
+    /// Rank a region's zones for new capacity by availability, recent stock-outs, and quota
+    Suggest(PlacementSuggestArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct PlacementSuggestArgs {
+    #[arg(
+        long = "machine-type",
+        value_name = "TYPE",
+        help = "Machine type of the capacity, e.g. a2-highgpu-1g"
+    )]
+    pub machine_type: String,
+
+    // on top of the GPUs the machine type comes with
+    #[arg(
+        long,
+        value_name = "type=TYPE[,count=N]",
+        help = "GPUs each instance attaches, e.g. type=nvidia-tesla-t4,count=1; may be repeated"
+    )]
+    pub accelerator: Vec<Accelerator>,
+
+    #[command(flatten)]
+    pub regional: RegionalArgs,
+
+    // Spot capacity counts against the preemptible quotas
+    #[arg(long, help = "Rank for Spot instances")]
+    pub spot: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        help = "Instances needed; warns when the region's quota leaves room for fewer"
+    )]
+    pub count: u64,
+
+    // stock-outs come from the local audit log of gcectl's own requests
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        default_value = "7d",
+        help = "Count stock-outs from the last DURATION, e.g. 12h or 30d"
+    )]
+    pub since: Duration,
+}
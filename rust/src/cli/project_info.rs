@@ -0,0 +1,70 @@
+use clap::{Args, Subcommand};
+
+use super::ProjectArgs;
+
+#[derive(Debug, Subcommand)]
+pub enum ProjectInfoCommand {
+    /// Show project-wide settings and the metadata every instance inherits
+    Describe(ProjectArgs),
+    /// Change the service account new instances run as by default
+    SetDefaultServiceAccount(SetDefaultServiceAccountArgs),
+    /// Export daily usage reports to a Cloud Storage bucket, or stop exporting
+    SetUsageExportBucket(SetUsageExportBucketArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct SetDefaultServiceAccountArgs {
+    #[arg(
+        value_name = "EMAIL",
+        help = "Service account email, e.g. runner@my-project.iam.gserviceaccount.com"
+    )]
+    pub email: String,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SetUsageExportBucketArgs {
+    #[arg(
+        long,
+        value_name = "BUCKET",
+        required_unless_present = "clear",
+        help = "Bucket the reports are written to, e.g. gs://usage-reports"
+    )]
+    pub bucket: Option<String>,
+
+    // reports are named PREFIX_YYYYMMDD.csv; the API defaults to "usage_gce"
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        requires = "bucket",
+        help = "Prefix of the report file names"
+    )]
+    pub prefix: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with = "bucket",
+        help = "Stop exporting usage reports",
+        default_value_t = false
+    )]
+    pub clear: bool,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
@@ -0,0 +1,44 @@
+use clap::{Args, Subcommand};
+
+use super::RegionalArgs;
+
+#[derive(Debug, Subcommand)]
+pub enum QuotasCommand {
+    /// Show quota usage of a region or the whole project
+    List(QuotasListArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct QuotasListArgs {
+    #[command(flatten)]
+    pub regional: RegionalArgs,
+
+    // snapshots, networks, firewall rules, and other global resources
+    #[arg(
+        long,
+        help = "Show project-wide quotas instead of a region's",
+        conflicts_with = "region",
+        default_value_t = false
+    )]
+    pub global: bool,
+
+    #[arg(
+        long = "warn-above",
+        value_name = "PERCENT",
+        default_value_t = 80.0,
+        help = "Highlight quotas whose usage is above PERCENT of the limit"
+    )]
+    pub warn_above: f64,
+
+    // for CI pre-flight checks before launching large fleets
+    #[arg(
+        long = "fail-above",
+        value_name = "PERCENT",
+        help = "Exit with an error if any quota's usage is above PERCENT of the limit"
+    )]
+    pub fail_above: Option<f64>,
+
+    // most regions report dozens of metrics that are not in use
+    #[arg(long, help = "Include quotas with no usage", default_value_t = false)]
+    pub all: bool,
+}
@@ -0,0 +1,102 @@
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+use gcectl_core::resources::Accelerator;
+
+use super::ZonalArgs;
+
+#[derive(Debug, Subcommand)]
+pub enum ReservationsCommand {
+    /// List reservations in a zone, or in every zone when none is configured
+    List(ReservationListArgs),
+    /// Show a reservation and how much of it is in use
+    Describe(ReservationArgs),
+    /// Reserve capacity for a number of VMs of one shape
+    Create(ReservationCreateArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ReservationListArgs {
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long = "all-zones",
+        help = "List reservations in every zone of the project",
+        conflicts_with = "zone",
+        default_value_t = false
+    )]
+    pub all_zones: bool,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching reservations, e.g. 'status=READY'"
+    )]
+    pub filter: Option<Filter>,
+}
+
+#[derive(Debug, Args)]
+pub struct ReservationArgs {
+    #[arg(value_name = "NAME", help = "Reservation name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct ReservationCreateArgs {
+    #[arg(value_name = "NAME", help = "Name of the new reservation")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long = "machine-type",
+        value_name = "TYPE",
+        help = "Machine type to reserve, e.g. n2-standard-4"
+    )]
+    pub machine_type: String,
+
+    // reserved VMs are billed from creation, used or not
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Number of VMs to reserve"
+    )]
+    pub count: u32,
+
+    #[arg(
+        long,
+        value_name = "type=TYPE[,count=N]",
+        help = "GPUs each reserved VM gets, e.g. type=nvidia-tesla-t4,count=1; may be repeated"
+    )]
+    pub accelerator: Vec<Accelerator>,
+
+    #[arg(
+        long = "min-cpu-platform",
+        value_name = "PLATFORM",
+        help = "Oldest CPU platform to reserve, e.g. 'Intel Ice Lake'"
+    )]
+    pub min_cpu_platform: Option<String>,
+
+    // otherwise any instance of a matching shape consumes it
+    #[arg(
+        long = "require-specific",
+        help = "Only let instances that name the reservation consume it",
+        default_value_t = false
+    )]
+    pub require_specific: bool,
+
+    #[arg(long, help = "Reservation description")]
+    pub description: Option<String>,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
@@ -0,0 +1,188 @@
+use clap::{ArgGroup, Args, Subcommand};
+use clap_complete::ArgValueCandidates;
+use gcectl_core::filter::Filter;
+
+use super::{RegionalArgs, ZonalArgs};
+use crate::completion;
+
+#[derive(Debug, Subcommand)]
+pub enum ResourcePoliciesCommand {
+    /// List snapshot and instance schedules
+    List(ResourcePolicyListArgs),
+    /// Show a resource policy
+    Describe(ResourcePolicyArgs),
+    /// Create a schedule that snapshots the disks it is attached to
+    CreateSnapshotSchedule(SnapshotScheduleCreateArgs),
+    /// Create a schedule that starts and stops the instances it is attached to
+    CreateInstanceSchedule(InstanceScheduleCreateArgs),
+    /// Attach a policy to a disk or instance
+    Attach(ResourcePolicyAttachArgs),
+    /// Detach a policy from a disk or instance
+    Detach(ResourcePolicyAttachArgs),
+    /// Delete one or more policies that nothing is attached to
+    Delete(ResourcePolicyDeleteArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ResourcePolicyListArgs {
+    #[command(flatten)]
+    pub regional: RegionalArgs,
+
+    #[arg(
+        long = "all-regions",
+        help = "List policies in every region of the project",
+        conflicts_with = "region",
+        default_value_t = false
+    )]
+    pub all_regions: bool,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching policies, e.g. 'name=daily-*'"
+    )]
+    pub filter: Option<Filter>,
+}
+
+#[derive(Debug, Args)]
+pub struct ResourcePolicyArgs {
+    #[arg(value_name = "NAME", help = "Policy name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub regional: RegionalArgs,
+}
+
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("cadence").args(["hourly", "daily"])))]
+pub struct SnapshotScheduleCreateArgs {
+    #[arg(value_name = "NAME", help = "Name of the new policy")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub regional: RegionalArgs,
+
+    #[arg(long, value_name = "HOURS", help = "Snapshot every this many hours")]
+    pub hourly: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "DAYS",
+        help = "Snapshot every this many days [default: 1]"
+    )]
+    pub daily: Option<u32>,
+
+    // the API only accepts whole hours
+    #[arg(
+        long = "start-time",
+        value_name = "HH:00",
+        default_value = "00:00",
+        help = "UTC time the first snapshot of a cycle is taken"
+    )]
+    pub start_time: String,
+
+    #[arg(
+        long = "retention-days",
+        value_name = "DAYS",
+        default_value_t = 14,
+        help = "Delete snapshots older than this"
+    )]
+    pub retention_days: u32,
+
+    #[arg(
+        long = "storage-location",
+        value_name = "LOCATION",
+        help = "Where snapshots are stored, e.g. asia or us-central1 [default: nearest multi-region]"
+    )]
+    pub storage_location: Vec<String>,
+
+    #[arg(long, help = "Policy description")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("action").args(["start", "stop"]).multiple(true).required(true)))]
+pub struct InstanceScheduleCreateArgs {
+    #[arg(value_name = "NAME", help = "Name of the new policy")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub regional: RegionalArgs,
+
+    #[arg(
+        long,
+        value_name = "CRON",
+        help = "When to start instances, e.g. '0 9 * * 1-5'"
+    )]
+    pub start: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "CRON",
+        help = "When to stop instances, e.g. '0 20 * * 1-5'"
+    )]
+    pub stop: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "TZ",
+        default_value = "UTC",
+        help = "IANA time zone the cron expressions are in, e.g. Asia/Tokyo"
+    )]
+    pub timezone: String,
+
+    #[arg(long, help = "Policy description")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("target").args(["disk", "instance"]).required(true)))]
+pub struct ResourcePolicyAttachArgs {
+    #[arg(value_name = "POLICY", help = "Policy name")]
+    pub policy: String,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Disk to attach a snapshot schedule to"
+    )]
+    pub disk: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance to attach an instance schedule to"
+    )]
+    pub instance: Option<String>,
+
+    // the policy lives in the zone's region
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ResourcePolicyDeleteArgs {
+    #[arg(value_name = "NAME", required = true, help = "Policies to delete")]
+    pub names: Vec<String>,
+
+    #[command(flatten)]
+    pub regional: RegionalArgs,
+
+    // skip the confirmation prompt, for scripts
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Delete without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
@@ -0,0 +1,72 @@
+use chrono_tz::Tz;
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+use gcectl_core::schedule::{Action, Days, TimeOfDay};
+
+use super::ZonalArgs;
+
+#[derive(Debug, Subcommand)]
+pub enum ScheduleCommand {
+    /// Register a recurring start/stop rule
+    Add(ScheduleAddArgs),
+    /// List registered rules and when they next run
+    List,
+    /// Remove a rule
+    Remove {
+        #[arg(value_name = "NAME", help = "Rule to remove")]
+        name: String,
+    },
+    /// Run one rule immediately
+    Trigger {
+        #[arg(value_name = "NAME", help = "Rule to run")]
+        name: String,
+    },
+    /// Run in the foreground, executing rules as they come due
+    Run,
+}
+
+#[derive(Debug, Args)]
+pub struct ScheduleAddArgs {
+    #[arg(value_name = "NAME", help = "Name of the rule")]
+    pub name: String,
+
+    #[arg(long, value_enum, help = "What to do with matching instances")]
+    pub action: Action,
+
+    #[arg(long, value_name = "HH:MM", help = "Time of day to run at")]
+    pub at: TimeOfDay,
+
+    // `daily`, `weekdays`, `mon-fri`, `sat,sun`, ...
+    #[arg(
+        long,
+        value_name = "DAYS",
+        default_value = "daily",
+        help = "Days to run on, e.g. mon-fri or sat,sun"
+    )]
+    pub days: Days,
+
+    #[arg(
+        long,
+        value_name = "TZ",
+        help = "IANA time zone for --at, e.g. Asia/Tokyo [default: local time]"
+    )]
+    pub timezone: Option<Tz>,
+
+    // zone defaults to every zone rather than the profile's
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        value_parser = parse_filter,
+        help = "Only act on matching instances, e.g. 'labels.env=dev'"
+    )]
+    pub filter: Option<String>,
+}
+
+/// Validates a filter expression but keeps its original text for storage.
+fn parse_filter(s: &str) -> anyhow::Result<String> {
+    s.parse::<Filter>()?;
+    Ok(s.to_string())
+}
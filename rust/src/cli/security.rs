@@ -0,0 +1,33 @@
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+
+use super::ZonalArgs;
+
+#[derive(Debug, Subcommand)]
+pub enum SecurityCommand {
+    /// Score instances on external IPs, scopes, service accounts, and
+    /// metadata settings
+    Audit(SecurityAuditArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct SecurityAuditArgs {
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // ignore the profile's default zone
+    #[arg(
+        long = "all-zones",
+        help = "Audit instances in every zone of the project",
+        conflicts_with = "zone",
+        default_value_t = false
+    )]
+    pub all_zones: bool,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only audit matching instances, e.g. 'labels.env=prod'"
+    )]
+    pub filter: Option<Filter>,
+}
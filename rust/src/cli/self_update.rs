@@ -0,0 +1,14 @@
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct SelfUpdateArgs {
+    #[arg(long, help = "Only report whether a newer release exists")]
+    pub check: bool,
+
+    // reinstalls the latest release even when it is not newer
+    #[arg(long, help = "Install the latest release even if it is not newer")]
+    pub force: bool,
+
+    #[arg(short = 'y', long, help = "Do not ask before replacing the binary")]
+    pub yes: bool,
+}
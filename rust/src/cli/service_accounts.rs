@@ -0,0 +1,9 @@
+use clap::Subcommand;
+
+use super::ProjectArgs;
+
+#[derive(Debug, Subcommand)]
+pub enum ServiceAccountsCommand {
+    /// List the project's service accounts
+    List(ProjectArgs),
+}
@@ -0,0 +1,12 @@
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct ShellArgs {
+    // lines are otherwise kept in shell-history under the config directory
+    #[arg(
+        long = "no-history",
+        help = "Keep this session's lines out of the history file",
+        default_value_t = false
+    )]
+    pub no_history: bool,
+}
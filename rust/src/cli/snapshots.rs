@@ -0,0 +1,211 @@
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+use gcectl_core::resources::disk::DiskMode;
+
+use super::{
+    LabelKeysArgs, LabelsArgs, PagingArgs, ProjectArgs, ZonalArgs, parse_key_value, parse_kms_key,
+};
+
+#[derive(Debug, Subcommand)]
+pub enum SnapshotsCommand {
+    /// List snapshots in the project
+    List(SnapshotListArgs),
+    /// Show the details of a snapshot
+    Describe(SnapshotDescribeArgs),
+    /// Print the Cloud Console URL of a snapshot, or open it
+    Open(SnapshotOpenArgs),
+    /// Snapshot a persistent disk
+    Create(SnapshotCreateArgs),
+    /// Create a disk from a snapshot, optionally attaching it to an instance
+    Restore(SnapshotRestoreArgs),
+    /// Delete one or more snapshots
+    Delete(SnapshotDeleteArgs),
+    /// Add or overwrite labels on a snapshot
+    AddLabels(SnapshotAddLabelsArgs),
+    /// Remove labels from a snapshot by key
+    RemoveLabels(SnapshotRemoveLabelsArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotListArgs {
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching snapshots, e.g. 'labels.env=prod'"
+    )]
+    pub filter: Option<Filter>,
+
+    #[command(flatten)]
+    pub paging: PagingArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotDescribeArgs {
+    #[arg(value_name = "NAME", help = "Snapshot name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotAddLabelsArgs {
+    #[arg(value_name = "NAME", help = "Snapshot name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub labels: LabelsArgs,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotRemoveLabelsArgs {
+    #[arg(value_name = "NAME", help = "Snapshot name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub keys: LabelKeysArgs,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotCreateArgs {
+    #[arg(value_name = "NAME", help = "Name of the new snapshot")]
+    pub name: String,
+
+    // disk to snapshot; --zone is the disk's zone
+    #[arg(long = "source-disk", value_name = "DISK", help = "Disk to snapshot")]
+    pub source_disk: String,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(long, help = "Snapshot description")]
+    pub description: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "KEY=VALUE",
+        value_delimiter = ',',
+        value_parser = parse_key_value,
+        help = "Labels to apply, comma separated or repeated"
+    )]
+    pub labels: Vec<(String, String)>,
+
+    // multi-region or region such as `us` or `asia-northeast1`
+    #[arg(
+        long = "storage-location",
+        value_name = "LOCATION",
+        help = "Cloud Storage location for the snapshot [default: nearest multi-region]"
+    )]
+    pub storage_location: Option<String>,
+
+    // independent of the source disk's key, which the API reads with
+    #[arg(
+        long = "kms-key",
+        value_name = "KEY",
+        value_parser = parse_kms_key,
+        help = "Cloud KMS key to encrypt the snapshot with, as projects/P/locations/L/keyRings/R/cryptoKeys/K"
+    )]
+    pub kms_key: Option<String>,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotRestoreArgs {
+    #[arg(value_name = "SNAPSHOT", help = "Snapshot to restore")]
+    pub snapshot: String,
+
+    #[arg(long = "as-disk", value_name = "NAME", help = "Name of the new disk")]
+    pub as_disk: String,
+
+    // --zone is where the disk, and the instance it is attached to, live
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    #[arg(
+        long,
+        value_name = "GB",
+        help = "Disk size in GB [default: the snapshot's source disk size]"
+    )]
+    pub size: Option<u64>,
+
+    #[arg(
+        long = "type",
+        value_name = "TYPE",
+        help = "Disk type, e.g. pd-balanced or pd-ssd"
+    )]
+    pub disk_type: Option<String>,
+
+    #[arg(
+        long = "attach-to",
+        value_name = "INSTANCE",
+        help = "Attach the restored disk to this instance"
+    )]
+    pub attach_to: Option<String>,
+
+    // appears as /dev/disk/by-id/google-NAME in the guest
+    #[arg(
+        long = "device-name",
+        value_name = "NAME",
+        requires = "attach_to",
+        help = "Device name in the guest [default: the disk name]"
+    )]
+    pub device_name: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = DiskMode::ReadWrite,
+        requires = "attach_to",
+        help = "Attach read-write or read-only"
+    )]
+    pub mode: DiskMode,
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotDeleteArgs {
+    #[arg(value_name = "NAME", required = true, help = "Snapshots to delete")]
+    pub names: Vec<String>,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Delete without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotOpenArgs {
+    #[arg(value_name = "NAME", help = "Snapshot name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long,
+        help = "Open the page in the browser as well",
+        default_value_t = false
+    )]
+    pub web: bool,
+}
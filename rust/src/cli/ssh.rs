@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use clap_complete::ArgValueCandidates;
+
+use super::{SelectionArgs, ZonalArgs};
+use crate::completion;
+
+#[derive(Debug, Args)]
+pub struct SshArgs {
+    // gcloud-style target; the user defaults to the OS Login username or the local login name
+    #[arg(
+        value_name = "[USER@]NAME",
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance to connect to"
+    )]
+    pub target: String,
+
+    #[command(flatten)]
+    pub connect: SshConnectArgs,
+
+    // everything after `--` goes straight to ssh
+    #[arg(last = true, value_name = "SSH_ARGS", help = "Extra arguments for ssh")]
+    pub ssh_args: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct RunArgs {
+    #[command(flatten)]
+    pub selection: SelectionArgs,
+
+    #[command(flatten)]
+    pub connect: SshConnectArgs,
+
+    // the same user on every instance instead of each one's OS Login or
+    // local user
+    #[arg(long, value_name = "USER", help = "User to connect as")]
+    pub user: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 10,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Instances to run the command on at once"
+    )]
+    pub parallel: u32,
+
+    // handed to the remote login shell, so quote what it should not expand
+    #[arg(
+        last = true,
+        required = true,
+        value_name = "COMMAND",
+        help = "Command to run on each instance, after --"
+    )]
+    pub command: Vec<String>,
+}
+
+/// How `ssh`, `scp`, and `rsync` reach the instance.
+#[derive(Debug, Args)]
+pub struct SshConnectArgs {
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // connect to the VPC address instead of the external one
+    #[arg(
+        long = "internal-ip",
+        help = "Connect to the instance's internal IP",
+        default_value_t = false
+    )]
+    pub internal_ip: bool,
+
+    // for instances without an external IP or open firewall
+    #[arg(
+        long = "tunnel-through-iap",
+        help = "Connect through an Identity-Aware Proxy TCP tunnel",
+        default_value_t = false,
+        conflicts_with = "internal_ip"
+    )]
+    pub tunnel_through_iap: bool,
+
+    // private key passed to ssh with -i
+    #[arg(
+        long = "ssh-key-file",
+        value_name = "PATH",
+        help = "Private key to use [default: ~/.ssh/google_compute_engine if present]"
+    )]
+    pub ssh_key_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct ScpArgs {
+    // exactly one instance may appear among the operands
+    #[arg(
+        value_name = "[[USER@]NAME:]PATH",
+        num_args = 2..,
+        required = true,
+        help = "Sources followed by the destination; prefix remote paths with the instance name"
+    )]
+    pub paths: Vec<String>,
+
+    #[command(flatten)]
+    pub connect: SshConnectArgs,
+
+    #[arg(
+        long,
+        short = 'r',
+        help = "Copy directories recursively",
+        default_value_t = false
+    )]
+    pub recurse: bool,
+
+    // everything after `--` goes straight to scp
+    #[arg(last = true, value_name = "SCP_ARGS", help = "Extra arguments for scp")]
+    pub scp_args: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct RsyncArgs {
+    // exactly one instance may appear among the operands
+    #[arg(
+        value_name = "[[USER@]NAME:]PATH",
+        num_args = 2..,
+        required = true,
+        help = "Sources followed by the destination; prefix remote paths with the instance name"
+    )]
+    pub paths: Vec<String>,
+
+    #[command(flatten)]
+    pub connect: SshConnectArgs,
+
+    // e.g. `-- -avz --delete`; only the remote shell is set by gcectl
+    #[arg(
+        last = true,
+        value_name = "RSYNC_ARGS",
+        help = "Extra arguments for rsync, such as -avz"
+    )]
+    pub rsync_args: Vec<String>,
+}
@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+
+use super::ZonalArgs;
+
+#[derive(Debug, Subcommand)]
+pub enum SshKeysCommand {
+    /// List the keys in an instance's or the project's `ssh-keys` metadata
+    List(SshKeyScopeArgs),
+    /// Authorize a public key for a user
+    Add(SshKeyAddArgs),
+    /// Revoke a user's keys
+    Remove(SshKeyRemoveArgs),
+}
+
+/// Whether keys are managed on one instance or project-wide.
+#[derive(Debug, Args)]
+pub struct SshKeyScopeArgs {
+    // without it, the project's common metadata is edited
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Manage keys on this instance instead of the whole project"
+    )]
+    pub instance: Option<String>,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct SshKeyAddArgs {
+    #[command(flatten)]
+    pub scope: SshKeyScopeArgs,
+
+    #[arg(long, help = "Login name the key is for [default: local user]")]
+    pub user: Option<String>,
+
+    #[arg(
+        long = "key-file",
+        value_name = "PATH",
+        help = "Public key to add [default: ~/.ssh/google_compute_engine.pub if present]"
+    )]
+    pub key_file: Option<PathBuf>,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SshKeyRemoveArgs {
+    #[command(flatten)]
+    pub scope: SshKeyScopeArgs,
+
+    #[arg(long, help = "Login name whose keys are removed")]
+    pub user: String,
+
+    // as shown by `ssh-keys list`; without it every key of the user goes
+    #[arg(
+        long,
+        value_name = "SHA256:...",
+        help = "Only remove the key with this fingerprint"
+    )]
+    pub fingerprint: Option<String>,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
@@ -0,0 +1,101 @@
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+
+use super::{InstancePropertiesArgs, ProjectArgs};
+
+#[derive(Debug, Subcommand)]
+pub enum TemplatesCommand {
+    /// List instance templates in the project
+    List(TemplateListArgs),
+    /// Show the details of an instance template
+    Describe(TemplateDescribeArgs),
+    /// Print the Cloud Console URL of a template, or open it
+    Open(TemplateOpenArgs),
+    /// Create an instance template
+    Create(Box<TemplateCreateArgs>),
+    /// Delete one or more instance templates
+    Delete(TemplateDeleteArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct TemplateListArgs {
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching templates, e.g. 'name!=legacy'"
+    )]
+    pub filter: Option<Filter>,
+}
+
+#[derive(Debug, Args)]
+pub struct TemplateDescribeArgs {
+    #[arg(value_name = "NAME", help = "Template name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct TemplateCreateArgs {
+    #[arg(value_name = "NAME", help = "Name of the new template")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[command(flatten)]
+    pub properties: InstancePropertiesArgs,
+
+    // templates are global, so a subnet needs its region spelled out
+    #[arg(
+        long,
+        requires = "subnet",
+        help = "Region of --subnet, e.g. asia-northeast1"
+    )]
+    pub region: Option<String>,
+
+    #[arg(
+        long = "no-wait",
+        help = "Return immediately instead of waiting for the operation to finish",
+        default_value_t = false
+    )]
+    pub no_wait: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct TemplateDeleteArgs {
+    #[arg(value_name = "NAME", required = true, help = "Templates to delete")]
+    pub names: Vec<String>,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long,
+        short = 'y',
+        visible_alias = "yes",
+        help = "Delete without asking for confirmation",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct TemplateOpenArgs {
+    #[arg(value_name = "NAME", help = "Template name")]
+    pub name: String,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long,
+        help = "Open the page in the browser as well",
+        default_value_t = false
+    )]
+    pub web: bool,
+}
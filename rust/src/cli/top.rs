@@ -0,0 +1,19 @@
+use clap::Args;
+
+use super::ListArgs;
+
+#[derive(Debug, Args)]
+pub struct TopArgs {
+    #[command(flatten)]
+    pub list: ListArgs,
+
+    #[arg(
+        long,
+        short = 'n',
+        value_name = "SECONDS",
+        default_value_t = 10,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Seconds between refreshes"
+    )]
+    pub interval: u64,
+}
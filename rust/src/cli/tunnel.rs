@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{Args, Subcommand};
+use clap_complete::ArgValueCandidates;
+
+use super::{ZonalArgs, parse_duration};
+use crate::completion;
+
+// a single forward by default; `up`, `status`, and `down` manage many
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct TunnelArgs {
+    #[command(subcommand)]
+    pub command: Option<TunnelCommand>,
+
+    #[arg(
+        value_name = "NAME",
+        required = true,
+        add = ArgValueCandidates::new(completion::instance_names),
+        help = "Instance to tunnel to"
+    )]
+    pub name: Option<String>,
+
+    #[command(flatten)]
+    pub zonal: ZonalArgs,
+
+    // port on the instance
+    #[arg(
+        long = "remote-port",
+        value_name = "PORT",
+        required = true,
+        help = "Port on the instance"
+    )]
+    pub remote_port: Option<u16>,
+
+    // port on localhost; 0 picks a free one
+    #[arg(
+        long = "local-port",
+        value_name = "PORT",
+        help = "Local port to listen on [default: same as --remote-port]",
+        conflicts_with = "listen_on_stdin"
+    )]
+    pub local_port: Option<u16>,
+
+    // relay a single connection over stdin/stdout, as used by ssh ProxyCommand
+    #[arg(
+        long = "listen-on-stdin",
+        help = "Relay stdin/stdout instead of listening on a local port",
+        default_value_t = false
+    )]
+    pub listen_on_stdin: bool,
+
+    // network interface of the instance
+    #[arg(long, default_value = "nic0", help = "Instance network interface")]
+    pub interface: String,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TunnelCommand {
+    /// Open every forward in a tunnels file and keep them up until interrupted
+    Up(TunnelUpArgs),
+    /// Show the forwards a running `tunnel up` holds open
+    Status,
+    /// Close the forwards of a running `tunnel up`
+    Down,
+}
+
+#[derive(Debug, Args)]
+pub struct TunnelUpArgs {
+    // YAML listing name, instance, port, and optionally localPort, zone, via
+    #[arg(
+        long,
+        short = 'f',
+        value_name = "FILE",
+        help = "Tunnels file, e.g. tunnels.yaml"
+    )]
+    pub file: PathBuf,
+
+    #[arg(
+        long = "probe-interval",
+        value_name = "DURATION",
+        default_value = "30s",
+        value_parser = parse_duration,
+        help = "How often to check that IAP forwards can reach their port"
+    )]
+    pub probe_interval: Duration,
+}
@@ -0,0 +1,29 @@
+use clap::{Args, Subcommand};
+use gcectl_core::filter::Filter;
+
+use super::ProjectArgs;
+
+#[derive(Debug, Subcommand)]
+pub enum ZonesCommand {
+    /// List zones and whether they are up
+    List(LocationListArgs),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RegionsCommand {
+    /// List regions with their CPU and IP quota usage
+    List(LocationListArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct LocationListArgs {
+    #[command(flatten)]
+    pub project: ProjectArgs,
+
+    #[arg(
+        long,
+        value_name = "EXPR",
+        help = "Only list matching locations, e.g. 'status=UP'"
+    )]
+    pub filter: Option<Filter>,
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use gcectl_core::output::{print_list, print_one};
+use gcectl_core::resources::address::AddressSpec;
+
+use super::{Session, confirm_delete, delete_all, requested, success, wait_with_spinner};
+use crate::cli::{
+    AddressArgs, AddressListArgs, AddressReleaseArgs, AddressReserveArgs, AddressScopeArgs,
+    AddressesCommand,
+};
+
+pub async fn run(session: &Session, cmd: AddressesCommand) -> Result<()> {
+    match cmd {
+        AddressesCommand::List(args) => list(session, args).await,
+        AddressesCommand::Describe(args) => describe(session, args).await,
+        AddressesCommand::Reserve(args) => reserve(session, args).await,
+        AddressesCommand::Release(args) => release(session, args).await,
+    }
+}
+
+/// Region the addresses live in, or `None` for global addresses.
+fn region(session: &Session, scope: &AddressScopeArgs) -> Result<Option<String>> {
+    match scope.global {
+        true => Ok(None),
+        false => session.region(scope.region.as_deref()).map(Some),
+    }
+}
+
+fn scope_label(region: Option<&str>) -> &str {
+    region.unwrap_or("global")
+}
+
+async fn list(session: &Session, args: AddressListArgs) -> Result<()> {
+    let project = session.project(args.scope.project.as_deref())?;
+    let compute = session.compute().await?;
+    let filter = args.filter.as_ref();
+    // without a region to scope to, cover every region like `instances list`
+    let addresses = match (args.all_regions, region(session, &args.scope)) {
+        (false, Ok(region)) => {
+            compute
+                .list_addresses(&project, region.as_deref(), filter)
+                .await?
+        }
+        _ => compute.list_addresses_everywhere(&project, filter).await?,
+    };
+    print_list(session.output, &addresses)
+}
+
+async fn describe(session: &Session, args: AddressArgs) -> Result<()> {
+    let project = session.project(args.scope.project.as_deref())?;
+    let region = region(session, &args.scope)?;
+    let compute = session.compute().await?;
+    let address = compute
+        .get_address(&project, region.as_deref(), &args.name)
+        .await?;
+    print_one(session.output, &address)
+}
+
+async fn reserve(session: &Session, args: AddressReserveArgs) -> Result<()> {
+    let project = session.project(args.scope.project.as_deref())?;
+    let region = region(session, &args.scope)?;
+    let spec = AddressSpec {
+        name: args.name.clone(),
+        description: args.description,
+        address: args.address,
+        network_tier: args.network_tier,
+        subnetwork: args.subnet.map(|subnet| {
+            format!(
+                "regions/{}/subnetworks/{subnet}",
+                scope_label(region.as_deref())
+            )
+        }),
+    };
+    let compute = session.compute().await?;
+    let op = compute
+        .insert_address(&project, region.as_deref(), &spec.to_body())
+        .await?;
+    if args.no_wait {
+        requested("Reserve", &op);
+        return Ok(());
+    }
+    wait_with_spinner(&compute, op, format!("Reserving address {}", args.name)).await?;
+    // nothing was reserved, so there is no address to read back
+    if session.dry_run {
+        return Ok(());
+    }
+    let address = compute
+        .get_address(&project, region.as_deref(), &args.name)
+        .await?;
+    success(&format!(
+        "Address {} reserved: {}",
+        args.name, address.address
+    ));
+    Ok(())
+}
+
+async fn release(session: &Session, args: AddressReleaseArgs) -> Result<()> {
+    let project = session.project(args.scope.project.as_deref())?;
+    let region = region(session, &args.scope)?;
+    confirm_delete(
+        args.force,
+        "address",
+        scope_label(region.as_deref()),
+        &args.names,
+    )?;
+    let compute = session.compute().await?;
+    delete_all(&compute, "address", &args.names, |name| {
+        let (compute, project, region) = (&compute, &project, region.as_deref());
+        async move { compute.delete_address(project, region, &name).await }
+    })
+    .await
+}
@@ -0,0 +1,44 @@
+use anyhow::{Result, bail};
+use gcectl_core::error::GcectlError;
+use gcectl_core::output::print_list;
+
+use super::{Session, success};
+use crate::alias::{self, Alias};
+use crate::cli::AliasCommand;
+
+pub fn run(session: &Session, cmd: AliasCommand) -> Result<()> {
+    let mut config = session.config.clone();
+    match cmd {
+        AliasCommand::List => {
+            let aliases: Vec<Alias> = config
+                .alias
+                .into_iter()
+                .map(|(name, expansion)| Alias { name, expansion })
+                .collect();
+            print_list(session.output, &aliases)?;
+        }
+        AliasCommand::Add { name, expansion } => {
+            if alias::builtin(&name) {
+                return Err(GcectlError::Usage(format!(
+                    "{name} is a gcectl command, which an alias cannot replace"
+                ))
+                .into());
+            }
+            let replaced = config
+                .alias
+                .insert(name.clone(), expansion.clone())
+                .is_some();
+            config.save()?;
+            let verb = if replaced { "Replaced" } else { "Added" };
+            success(&format!("{verb} alias {name} = {expansion}"));
+        }
+        AliasCommand::Remove { name } => {
+            if config.alias.remove(&name).is_none() {
+                bail!("no alias named {name}");
+            }
+            config.save()?;
+            success(&format!("Removed alias {name}"));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use futures_util::stream::{self, StreamExt};
+use gcectl_core::bake::{self, BakeFile, Baked, Build};
+use gcectl_core::compute::Compute;
+use gcectl_core::output::{self, print_list};
+use gcectl_core::resources::Image;
+use gcectl_core::ssh::{self, SshTarget};
+
+use super::{Session, batch_failed, failure, success};
+use crate::cli::BakeArgs;
+
+// a fresh instance takes a minute or two to accept ssh
+const SSH_ATTEMPTS: u32 = 30;
+const SSH_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Bakes every build in the file, `--parallel` at a time, and prints what
+/// each produced. Fails if any build did.
+pub async fn run(session: &Session, args: BakeArgs) -> Result<()> {
+    let yaml = fs::read_to_string(&args.file)
+        .with_context(|| format!("failed to read {}", args.file.display()))?;
+    let file = BakeFile::parse(&yaml)?;
+    let version = match &args.version {
+        Some(version) => version.clone(),
+        None => bake::default_version(Utc::now()),
+    };
+    bake::check_version(&version)?;
+    let project = session.project(
+        args.connect
+            .zonal
+            .project
+            .as_deref()
+            .or(file.project.as_deref()),
+    )?;
+    let dir = args.file.parent().unwrap_or(Path::new("."));
+    // every script is read before any instance is created
+    let mut plans = Vec::new();
+    for build in &file.builds {
+        let zone = session.zone(
+            args.connect
+                .zonal
+                .zone
+                .as_deref()
+                .or(build.zone.as_deref())
+                .or(file.zone.as_deref()),
+        )?;
+        plans.push((build, zone, build.commands(dir)?));
+    }
+    let compute = session.compute().await?;
+
+    let args = &args;
+    let baked: Vec<Baked> = stream::iter(plans)
+        .map(|(build, zone, commands)| {
+            let (compute, project, version) = (&compute, &project, &version);
+            async move {
+                let result = bake_one(
+                    session,
+                    compute,
+                    args,
+                    build,
+                    (project, &zone),
+                    version,
+                    &commands,
+                )
+                .await;
+                let image = build.image_name(version);
+                match &result {
+                    Ok(()) => success(&format!("Image {image} baked")),
+                    Err(err) => failure(&format!("{}: {err:#}", build.family)),
+                }
+                Baked {
+                    family: build.family.clone(),
+                    image,
+                    error: result.err().map(|err| format!("{err:#}")),
+                }
+            }
+        })
+        .buffer_unordered(args.parallel as usize)
+        .collect()
+        .await;
+    session.forget_instances(&project);
+    print_list(session.output, &baked)?;
+    let failed = baked.iter().filter(|b| b.error.is_some()).count();
+    if failed > 0 {
+        return Err(batch_failed("bake", "image", failed, baked.len()));
+    }
+    Ok(())
+}
+
+/// Boots the builder, provisions and images it, and deletes it again,
+/// unless it failed and `--keep-on-failure` is given.
+async fn bake_one(
+    session: &Session,
+    compute: &Compute,
+    args: &BakeArgs,
+    build: &Build,
+    (project, zone): (&str, &str),
+    version: &str,
+    commands: &[String],
+) -> Result<()> {
+    let name = build.builder_name(version);
+    let say = |message: &str| progress(build, message);
+    say(&format!("creating builder {name} in {zone}"));
+    let body = build.builder(version, zone)?.build();
+    let op = compute.insert_instance(project, zone, &body).await?;
+    compute.wait_operation(op).await?;
+
+    let result = provision(
+        session,
+        compute,
+        args,
+        build,
+        (project, zone),
+        &name,
+        commands,
+    )
+    .await;
+    let result = match result {
+        Ok(()) => image(compute, build, (project, zone), &name, version).await,
+        Err(err) => Err(err),
+    };
+    if result.is_err() && args.keep_on_failure {
+        say(&format!("keeping builder {name} for inspection"));
+        return result;
+    }
+    say(&format!("deleting builder {name}"));
+    let deleted = async {
+        let op = compute.delete_instance(project, zone, &name).await?;
+        compute.wait_operation(op).await
+    };
+    match (result, deleted.await) {
+        (Err(err), _) => Err(err),
+        (Ok(()), Err(err)) => Err(err.context(format!("the image was baked, but {name} remains"))),
+        (Ok(()), Ok(_)) => Ok(()),
+    }
+}
+
+// runs the build's steps on the builder, in order
+async fn provision(
+    session: &Session,
+    compute: &Compute,
+    args: &BakeArgs,
+    build: &Build,
+    (project, zone): (&str, &str),
+    name: &str,
+    commands: &[String],
+) -> Result<()> {
+    if commands.is_empty() {
+        return Ok(());
+    }
+    let instance = compute.get_instance(project, zone, name).await?;
+    let target = super::ssh::resolve_all(
+        session,
+        compute,
+        &args.connect,
+        (project, zone),
+        None,
+        std::slice::from_ref(&instance),
+    )
+    .await?
+    .remove(0);
+    wait_for_ssh(&target).await?;
+    let prefix = format!("{} | ", build.family);
+    for (i, command) in commands.iter().enumerate() {
+        progress(build, &format!("step {}/{}", i + 1, commands.len()));
+        ssh::run_prefixed(
+            "ssh",
+            &target.command_args(std::slice::from_ref(command)),
+            &prefix,
+        )
+        .await
+        .with_context(|| format!("step {} failed", i + 1))?;
+    }
+    Ok(())
+}
+
+// a line on stderr, with the family in the place the steps' output has it
+fn progress(build: &Build, message: &str) {
+    if !output::quiet() {
+        eprintln!("{} | {message}", build.family);
+    }
+}
+
+async fn wait_for_ssh(target: &SshTarget) -> Result<()> {
+    let args = target.command_args(&["true".to_string()]);
+    for _ in 0..SSH_ATTEMPTS {
+        let status = tokio::process::Command::new("ssh")
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+            .context("failed to run ssh")?;
+        if status.success() {
+            return Ok(());
+        }
+        tokio::time::sleep(SSH_RETRY_DELAY).await;
+    }
+    bail!("the builder never accepted ssh; is port 22 open to this machine?")
+}
+
+// stops the builder so its disk is consistent, then images the disk
+async fn image(
+    compute: &Compute,
+    build: &Build,
+    (project, zone): (&str, &str),
+    name: &str,
+    version: &str,
+) -> Result<()> {
+    progress(build, &format!("stopping builder {name}"));
+    let op = compute.stop_instance(project, zone, name).await?;
+    compute.wait_operation(op).await?;
+    let image = build.image_name(version);
+    progress(build, &format!("creating image {image}"));
+    let labels: Vec<(String, String)> = build.labels.clone().into_iter().collect();
+    let body = Image::create_request(
+        &image,
+        // the boot disk is named after the instance
+        &format!("projects/{project}/zones/{zone}/disks/{name}"),
+        Some(&build.family),
+        build.description.as_deref(),
+        &labels,
+    );
+    let op = compute.insert_image(project, &body).await?;
+    compute.wait_operation(op).await?;
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use anyhow::Result;
+use gcectl_core::cache::Cache;
+
+use super::success;
+use crate::cli::CacheCommand;
+
+pub fn run(cmd: CacheCommand) -> Result<()> {
+    match cmd {
+        CacheCommand::Clear => {
+            let removed = Cache::clear()?;
+            success(&format!(
+                "Removed {removed} cached entry(s) from {}",
+                Cache::dir()?.display()
+            ));
+        }
+    }
+    Ok(())
+}
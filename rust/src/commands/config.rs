@@ -0,0 +1,55 @@
+use anyhow::Result;
+use gcectl_core::config::DEFAULT_PROFILE;
+use gcectl_core::context::Setting;
+use gcectl_core::output::{print_list, print_text};
+
+use super::{Session, success};
+use crate::cli::ConfigCommand;
+
+pub fn run(session: &Session, cmd: ConfigCommand) -> Result<()> {
+    match cmd {
+        ConfigCommand::Set { key, value } => {
+            let mut config = session.config.clone();
+            config
+                .profiles
+                .entry(session.profile_name.clone())
+                .or_default()
+                .set(key, value.clone())?;
+            config.save()?;
+            success(&format!(
+                "Set {key} to {value} in profile {}",
+                session.profile_name
+            ));
+        }
+        ConfigCommand::Get { key } => match session.profile.get(key) {
+            Some(value) => print_text(&format!("{value}\n")),
+            None => eprintln!("{key} is not set in profile {}", session.profile_name),
+        },
+        ConfigCommand::ListProfiles => {
+            let mut names: Vec<&str> = session.config.profiles.keys().map(String::as_str).collect();
+            if names.is_empty() {
+                names.push(DEFAULT_PROFILE);
+            }
+            for name in names {
+                let marker = if name == session.profile_name {
+                    "*"
+                } else {
+                    " "
+                };
+                print_text(&format!("{marker} {name}\n"));
+            }
+        }
+        ConfigCommand::Doctor(args) => {
+            let context = &session.context;
+            print_list(
+                session.output,
+                &[
+                    Setting::new("project", context.project(args.project.as_deref())),
+                    Setting::new("zone", context.zone(args.zone.as_deref())),
+                    Setting::new("region", context.region(args.region.as_deref())),
+                ],
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use gcectl_core::billing::{Grouping, Spend};
+use gcectl_core::cost::{self, DEFAULT_DISK_TYPE, Estimate, Usage};
+use gcectl_core::output::print_list;
+use gcectl_core::resources::{Disk, Instance, region_of, short_name};
+
+use super::{Session, with_spinner};
+use crate::cli::{CostActualArgs, CostCommand, CostEstimateArgs};
+
+pub async fn run(session: &Session, cmd: CostCommand) -> Result<()> {
+    match cmd {
+        CostCommand::Estimate(args) => estimate(session, args).await,
+        CostCommand::Actual(args) => actual(session, args).await,
+    }
+}
+
+async fn estimate(session: &Session, args: CostEstimateArgs) -> Result<()> {
+    let project = session.project(args.zonal.project.as_deref())?;
+    let zone = session.list_zone(&args.zonal, args.all_zones);
+    let compute = session.compute().await?;
+    let instances = session
+        .list_instances(&compute, &project, zone.as_deref(), args.filter.as_ref())
+        .await?;
+    // attached disks only carry their size, so look their types up in bulk
+    let disks = compute.list_disks_all_zones(&project, None).await?;
+    let disks: HashMap<&str, &Disk> = disks
+        .iter()
+        .filter_map(|d| Some((d.self_link.as_deref()?, d)))
+        .collect();
+
+    let estimates: Vec<Estimate> = usages(&instances, &disks)
+        .iter()
+        .map(cost::estimate)
+        .collect();
+    print_list(session.output, &estimates)?;
+
+    let total: f64 = estimates.iter().map(Estimate::total).sum();
+    let partial = estimates.iter().any(Estimate::is_partial);
+    eprintln!(
+        "Estimated monthly total: ${total:.2}{} for {} instance(s), at list prices",
+        if partial {
+            " plus unpriced items (?)"
+        } else {
+            ""
+        },
+        estimates.len()
+    );
+    Ok(())
+}
+
+async fn actual(session: &Session, args: CostActualArgs) -> Result<()> {
+    let project = session.project(args.project.as_deref())?;
+    let table = args
+        .table
+        .as_deref()
+        .or(session.profile.billing_export_table.as_deref())
+        .context(
+            "no billing export table; pass --table or run \
+             `gcectl config set billing-export-table PROJECT.DATASET.TABLE`",
+        )?;
+    let grouping = match args.by_label {
+        Some(key) => Grouping::Label(key),
+        None => Grouping::Instance,
+    };
+    let billing = session.billing().await?;
+    let spend = with_spinner(
+        "Querying the billing export".to_string(),
+        billing.spend(table, &project, args.last, &grouping),
+    )
+    .await?;
+    print_list(session.output, &spend)?;
+
+    let net: f64 = spend.iter().map(Spend::net).sum();
+    let currency = spend.first().map_or("", |s| s.currency.as_str());
+    eprintln!(
+        "Billed over the last {}: {net:.2} {currency} net of credits for {} {}",
+        span(args.last),
+        spend.len(),
+        match grouping {
+            Grouping::Instance => "instance(s)".to_string(),
+            Grouping::Label(key) => format!("value(s) of label {key}"),
+        }
+    );
+    Ok(())
+}
+
+// `args.last` as it was likely written, e.g. `7d` or `36h`
+fn span(window: Duration) -> String {
+    let secs = window.as_secs();
+    match secs % 86_400 {
+        0 => format!("{}d", secs / 86_400),
+        _ => format!("{}h", secs.div_ceil(3600)),
+    }
+}
+
+/// What each of `instances` is billed for. A disk attached to several of
+/// them is charged to the first only.
+pub(super) fn usages(instances: &[Instance], disks: &HashMap<&str, &Disk>) -> Vec<Usage> {
+    let mut charged = HashSet::new();
+    instances
+        .iter()
+        .map(|instance| usage(instance, disks, &mut charged))
+        .collect()
+}
+
+fn usage<'a>(
+    instance: &'a Instance,
+    disks: &HashMap<&str, &Disk>,
+    charged: &mut HashSet<&'a str>,
+) -> Usage {
+    let disks = instance
+        .disks
+        .iter()
+        .filter(|attached| attached.source.as_deref().is_none_or(|s| charged.insert(s)))
+        .map(|attached| {
+            let disk = attached.source.as_deref().and_then(|s| disks.get(s));
+            let size = disk
+                .and_then(|d| d.size_gb.as_deref())
+                .or(attached.disk_size_gb.as_deref())
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0);
+            let kind = disk.map_or(DEFAULT_DISK_TYPE, |d| d.type_name());
+            (kind.to_string(), size)
+        })
+        .collect();
+    Usage {
+        name: instance.name.clone(),
+        zone: instance.zone_name().to_string(),
+        region: region_of(instance.zone_name()).to_string(),
+        machine_type: instance.machine_type_name().to_string(),
+        status: instance.status.clone(),
+        spot: instance.is_spot(),
+        gpus: instance
+            .guest_accelerators
+            .iter()
+            .map(|gpu| {
+                (
+                    short_name(&gpu.accelerator_type).to_string(),
+                    gpu.accelerator_count,
+                )
+            })
+            .collect(),
+        disks,
+    }
+}
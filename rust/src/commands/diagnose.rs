@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow, bail};
+use gcectl_core::compute::COMPUTE_ENDPOINT;
+use gcectl_core::config::Config;
+use gcectl_core::diagnose::{Check, Status, summary};
+use gcectl_core::output::print_list;
+
+use super::Session;
+use crate::cli::DiagnoseArgs;
+
+// how long the reachability check waits for any answer
+const REACH_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub async fn run(session: &Session, args: DiagnoseArgs) -> Result<()> {
+    let mut checks = vec![config(session), network(session).await];
+
+    let auth = match session.auth().await {
+        Ok(auth) => {
+            checks.push(Check::ok("credentials", auth.describe()));
+            Some(auth)
+        }
+        Err(err) => {
+            checks.push(Check::failed(
+                "credentials",
+                &err,
+                "run `gcloud auth application-default login`, or point \
+                 GOOGLE_APPLICATION_CREDENTIALS at a service account key",
+            ));
+            None
+        }
+    };
+
+    let token = match &auth {
+        Some(auth) => match auth.token().await {
+            Ok(_) => {
+                let account = auth
+                    .account_email()
+                    .await
+                    .unwrap_or_else(|_| "the credentials".to_string());
+                checks.push(Check::ok("token", format!("minted a token for {account}")));
+                true
+            }
+            Err(err) => {
+                checks.push(Check::failed(
+                    "token",
+                    &err,
+                    "the credentials may be revoked; log in again with \
+                     `gcloud auth application-default login`",
+                ));
+                false
+            }
+        },
+        None => {
+            checks.push(Check::skipped("token", "credentials"));
+            false
+        }
+    };
+
+    let project = session.context.project(args.project.as_deref());
+    match &project {
+        Some(project) => checks.push(Check::ok(
+            "project",
+            format!("{} (from {})", project.value, project.source),
+        )),
+        None => checks.push(Check::fail(
+            "project",
+            "no project is set",
+            "pass --project, or run `gcectl config set project PROJECT_ID`",
+        )),
+    }
+
+    checks.push(match (&project, token) {
+        (Some(project), true) => compute_api(session, &project.value).await,
+        (None, _) => Check::skipped("compute api", "project"),
+        (_, false) => Check::skipped("compute api", "token"),
+    });
+
+    checks.push(gcloud(session, project.as_ref().map(|p| p.value.as_str())));
+
+    print_list(session.output, &checks)?;
+    eprintln!("{}", summary(&checks));
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if failed > 0 {
+        bail!(
+            "{failed} of {} checks failed; see the Fix column",
+            checks.len()
+        );
+    }
+    Ok(())
+}
+
+fn config(session: &Session) -> Check {
+    let path = Config::path().map_or_else(
+        |_| "no config file".to_string(),
+        |p| p.display().to_string(),
+    );
+    let exists = session.config.profiles.contains_key(&session.profile_name);
+    match exists || session.config.profiles.is_empty() {
+        true => Check::ok(
+            "config",
+            format!("profile {} in {path}", session.profile_name),
+        ),
+        false => Check::warn(
+            "config",
+            format!("profile {} is not in {path}", session.profile_name),
+            "`gcectl config list-profiles` shows the profiles there",
+        ),
+    }
+}
+
+async fn network(session: &Session) -> Check {
+    let url = session
+        .endpoints
+        .compute
+        .clone()
+        .unwrap_or_else(|| COMPUTE_ENDPOINT.to_string());
+    let host = reqwest::Url::parse(&url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.clone());
+    let reached = tokio::time::timeout(REACH_TIMEOUT, session.http.send(session.http.get(&url)))
+        .await
+        .map_err(|_| anyhow!("no answer from {host} within {REACH_TIMEOUT:?}"))
+        .and_then(|result| result);
+    match reached {
+        // any HTTP answer, even an error, means the host is reachable
+        Ok(resp) => Check::ok(
+            "network",
+            format!("reached {host} (HTTP {})", resp.status()),
+        ),
+        Err(err) => Check::failed(
+            "network",
+            &err,
+            "check the connection and HTTPS_PROXY; networks without internet egress need \
+             `--google-apis private`",
+        ),
+    }
+}
+
+async fn compute_api(session: &Session, project: &str) -> Check {
+    let result = async { session.compute().await?.get_project(project).await }.await;
+    match result {
+        Ok(_) => Check::ok("compute api", format!("read project {project}")),
+        Err(err) => Check::failed(
+            "compute api",
+            &err,
+            "check the project ID and that the account has a Compute role on it",
+        ),
+    }
+}
+
+fn gcloud(session: &Session, project: Option<&str>) -> Check {
+    let Some((name, gcloud_project)) = session.context.gcloud() else {
+        return Check::warn(
+            "gcloud",
+            "no gcloud configuration found",
+            "optional: gcectl reads project and zone defaults from gcloud once \
+             `gcloud init` has run",
+        );
+    };
+    match (gcloud_project, project) {
+        (Some(theirs), Some(ours)) if theirs != ours => Check::warn(
+            "gcloud",
+            format!("configuration {name} uses project {theirs}, gcectl uses {ours}"),
+            format!(
+                "run `gcloud config set project {ours}`, or `gcectl config set project {theirs}` \
+                 to follow gcloud"
+            ),
+        ),
+        (Some(theirs), _) => Check::ok("gcloud", format!("configuration {name}, project {theirs}")),
+        (None, _) => Check::ok("gcloud", format!("configuration {name}, no project set")),
+    }
+}
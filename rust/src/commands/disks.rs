@@ -0,0 +1,307 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow, bail};
+use gcectl_core::console::Resource;
+use gcectl_core::disk_advisor::{self, DiskSpec, Usage};
+use gcectl_core::labels::LabelEdit;
+use gcectl_core::monitoring::{
+    DISK_READ_BYTES, DISK_READ_OPS, DISK_WRITE_BYTES, DISK_WRITE_OPS, TimeSeries,
+};
+use gcectl_core::output::{ListPrinter, print_list, print_one};
+use gcectl_core::resources::disk::DiskSource;
+use gcectl_core::resources::instance::builder::ImageSource;
+use gcectl_core::resources::{Disk, short_name};
+
+use super::{
+    Session, confirm_delete, delete_all, finish_label_edit, open_url, requested, success,
+    wait_with_spinner, with_spinner,
+};
+use crate::cli::{
+    DiskAddLabelsArgs, DiskAnalyzeArgs, DiskArgs, DiskAttachArgs, DiskCreateArgs, DiskDeleteArgs,
+    DiskDetachArgs, DiskListArgs, DiskOpenArgs, DiskRemoveLabelsArgs, DiskResizeArgs, DisksCommand,
+};
+
+pub async fn run(session: &Session, cmd: DisksCommand) -> Result<()> {
+    match cmd {
+        DisksCommand::List(args) => list(session, args).await,
+        DisksCommand::Describe(args) => describe(session, args).await,
+        DisksCommand::Open(args) => open(session, args),
+        DisksCommand::Create(args) => create(session, args).await,
+        DisksCommand::Delete(args) => delete(session, args).await,
+        DisksCommand::Resize(args) => resize(session, args).await,
+        DisksCommand::Attach(args) => attach(session, args).await,
+        DisksCommand::Detach(args) => detach(session, args).await,
+        DisksCommand::AddLabels(args) => add_labels(session, args).await,
+        DisksCommand::RemoveLabels(args) => remove_labels(session, args).await,
+        DisksCommand::Analyze(args) => analyze(session, args).await,
+    }
+}
+
+async fn add_labels(session: &Session, args: DiskAddLabelsArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let edit = LabelEdit::set(args.labels.labels)?;
+    let compute = session.compute().await?;
+    let op = compute
+        .update_disk_labels(&project, &zone, &args.name, &edit)
+        .await?;
+    finish_label_edit(&compute, op, "disk", &args.name, &edit, args.labels.no_wait).await
+}
+
+async fn remove_labels(session: &Session, args: DiskRemoveLabelsArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let edit = LabelEdit::remove(args.keys.keys);
+    let compute = session.compute().await?;
+    let op = compute
+        .update_disk_labels(&project, &zone, &args.name, &edit)
+        .await?;
+    finish_label_edit(&compute, op, "disk", &args.name, &edit, args.keys.no_wait).await
+}
+
+async fn list(session: &Session, args: DiskListArgs) -> Result<()> {
+    let project = session.project(args.zonal.project.as_deref())?;
+    let zone = session.list_zone(&args.zonal, args.all_zones);
+    let compute = session.compute().await?;
+    let mut printer = ListPrinter::stdout(session.output);
+    let listed = compute
+        .list_disk_pages(
+            &project,
+            zone.as_deref(),
+            args.filter.as_ref(),
+            args.paging.paging(),
+            |page| printer.page(&page),
+        )
+        .await;
+    printer.close::<Disk, _>(listed)
+}
+
+async fn describe(session: &Session, args: DiskArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let disk = compute.get_disk(&project, &zone, &args.name).await?;
+    print_one(session.output, &disk)
+}
+
+/// Measures the disk's I/O through the instance it is attached to, as
+/// Monitoring records disk metrics per instance and device name.
+async fn analyze(session: &Session, args: DiskAnalyzeArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let disk = compute.get_disk(&project, &zone, &args.name).await?;
+    let Some(user) = disk.user_names().first().map(|name| name.to_string()) else {
+        bail!(
+            "disk {} is not attached to an instance, so there is no I/O to measure",
+            args.name
+        );
+    };
+    let instance = compute.get_instance(&project, &zone, &user).await?;
+    let device = instance
+        .disks
+        .iter()
+        .find(|d| d.source.as_deref().map(short_name) == Some(args.name.as_str()))
+        .and_then(|d| d.device_name.clone())
+        .ok_or_else(|| anyhow!("instance {user} does not list disk {}", args.name))?;
+    let id = instance
+        .id
+        .ok_or_else(|| anyhow!("instance {user} has no ID"))?;
+
+    let monitoring = session.monitoring().await?;
+    let period = (args.window / 1440).max(Duration::from_secs(60));
+    let series =
+        |metric| monitoring.disk_series(&project, metric, &id, &device, args.window, period);
+    let (read_ops, write_ops, read_bytes, write_bytes) = with_spinner(
+        format!("Reading I/O of disk {} from Cloud Monitoring", args.name),
+        async {
+            tokio::try_join!(
+                series(DISK_READ_OPS),
+                series(DISK_WRITE_OPS),
+                series(DISK_READ_BYTES),
+                series(DISK_WRITE_BYTES),
+            )
+        },
+    )
+    .await?;
+
+    let spec = DiskSpec {
+        disk_type: disk.type_name().to_string(),
+        size_gb: disk
+            .size_gb
+            .as_deref()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0.0),
+        provisioned_iops: disk
+            .provisioned_iops
+            .as_deref()
+            .and_then(|s| s.parse().ok()),
+        provisioned_throughput: disk
+            .provisioned_throughput
+            .as_deref()
+            .and_then(|s| s.parse().ok()),
+    };
+    let throughput = combined(&[read_bytes, write_bytes]).map(|usage| Usage {
+        mean: usage.mean / MIB,
+        peak: usage.peak / MIB,
+    });
+    let findings = disk_advisor::analyze(&spec, combined(&[read_ops, write_ops]), throughput);
+    print_list(session.output, &findings)
+}
+
+const MIB: f64 = 1024.0 * 1024.0;
+
+/// Read and write rates added up point by point, as aligned series of one
+/// device share their periods.
+fn combined(series: &[Vec<TimeSeries>]) -> Option<Usage> {
+    let mut totals: Vec<f64> = Vec::new();
+    for s in series.iter().flatten() {
+        for (i, point) in s.points.iter().enumerate() {
+            let value = point.value.as_f64().unwrap_or(0.0);
+            match totals.get_mut(i) {
+                Some(total) => *total += value,
+                None => totals.push(value),
+            }
+        }
+    }
+    if totals.is_empty() {
+        return None;
+    }
+    Some(Usage {
+        mean: totals.iter().sum::<f64>() / totals.len() as f64,
+        peak: totals.iter().copied().fold(0.0, f64::max),
+    })
+}
+
+/// Prints the console URL of the disk, opening it with `--web`.
+fn open(session: &Session, args: DiskOpenArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let url = Resource::Disk {
+        zone: &zone,
+        name: &args.name,
+    }
+    .url(&project);
+    open_url(&url, args.web)
+}
+
+async fn create(session: &Session, args: DiskCreateArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let source = if let Some(snapshot) = args.snapshot {
+        DiskSource::Snapshot(snapshot)
+    } else if let Some(image) = args.image {
+        DiskSource::Image(ImageSource::Image {
+            project: args.image_project,
+            image,
+        })
+    } else if let Some(family) = args.image_family {
+        DiskSource::Image(ImageSource::Family {
+            project: args.image_project,
+            family,
+        })
+    } else {
+        DiskSource::Blank
+    };
+    let body = Disk::create_request(
+        &args.name,
+        &zone,
+        args.size,
+        args.disk_type.as_deref(),
+        &source,
+        &args.labels,
+        args.kms_key.as_deref(),
+    );
+
+    let compute = session.compute().await?;
+    let op = compute.insert_disk(&project, &zone, &body).await?;
+    if args.no_wait {
+        requested("Create", &op);
+        return Ok(());
+    }
+    wait_with_spinner(&compute, op, format!("Creating disk {}", args.name)).await?;
+    success(&format!("Disk {} created", args.name));
+    Ok(())
+}
+
+async fn delete(session: &Session, args: DiskDeleteArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    confirm_delete(args.force, "disk", &zone, &args.names)?;
+    let compute = session.compute().await?;
+    delete_all(&compute, "disk", &args.names, |name| {
+        let (compute, project, zone) = (&compute, &project, &zone);
+        async move { compute.delete_disk(project, zone, &name).await }
+    })
+    .await
+}
+
+async fn resize(session: &Session, args: DiskResizeArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let op = compute
+        .resize_disk(&project, &zone, &args.name, args.size)
+        .await?;
+    if args.no_wait {
+        requested("Resize", &op);
+        return Ok(());
+    }
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Resizing disk {} to {} GB", args.name, args.size),
+    )
+    .await?;
+    success(&format!("Disk {} resized to {} GB", args.name, args.size));
+    Ok(())
+}
+
+async fn attach(session: &Session, args: DiskAttachArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let body = Disk::attach_request(
+        &project,
+        &zone,
+        &args.disk,
+        args.device_name.as_deref(),
+        args.mode,
+    );
+    let compute = session.compute().await?;
+    let op = compute
+        .attach_disk(&project, &zone, &args.instance, &body)
+        .await?;
+    if args.no_wait {
+        requested("Attach", &op);
+        return Ok(());
+    }
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Attaching disk {} to {}", args.disk, args.instance),
+    )
+    .await?;
+    success(&format!("Disk {} attached to {}", args.disk, args.instance));
+    Ok(())
+}
+
+async fn detach(session: &Session, args: DiskDetachArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    // the API detaches by device name, which may differ from the disk name
+    let instance = compute
+        .get_instance(&project, &zone, &args.instance)
+        .await?;
+    let device_name = instance
+        .device_name_of(&args.disk)
+        .ok_or_else(|| anyhow!("disk {} is not attached to {}", args.disk, args.instance))?;
+    let op = compute
+        .detach_disk(&project, &zone, &args.instance, device_name)
+        .await?;
+    if args.no_wait {
+        requested("Detach", &op);
+        return Ok(());
+    }
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Detaching disk {} from {}", args.disk, args.instance),
+    )
+    .await?;
+    success(&format!(
+        "Disk {} detached from {}",
+        args.disk, args.instance
+    ));
+    Ok(())
+}
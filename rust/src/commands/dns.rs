@@ -0,0 +1,168 @@
+use anyhow::Result;
+use gcectl_core::compute::Compute;
+use gcectl_core::dns::{self, Dns, RecordSet, Upserted};
+use gcectl_core::error::GcectlError;
+use gcectl_core::output::{print_json, print_note};
+use gcectl_core::resources::Instance;
+
+use super::{Session, success, warning, with_spinner};
+use crate::cli::{DnsCommand, DnsRecordArgs, DnsRegisterArgs, DnsUnregisterArgs};
+
+// `dns register`'s default
+const DEFAULT_TTL: u32 = 300;
+
+pub async fn run(session: &Session, cmd: DnsCommand) -> Result<()> {
+    match cmd {
+        DnsCommand::Register(args) => register(session, args).await,
+        DnsCommand::Unregister(args) => unregister(session, args).await,
+    }
+}
+
+/// Where a hostname is written: the managed zone's project, the domain,
+/// and, when not discovered from the domain, the managed zone.
+pub(super) struct Target<'a> {
+    pub project: &'a str,
+    pub domain: &'a str,
+    pub managed_zone: Option<&'a str>,
+    pub internal: bool,
+}
+
+/// `--domain`, else the profile's `dns_domain`.
+pub(super) fn domain(session: &Session, flag: Option<&str>) -> Result<String> {
+    match flag.or(session.profile.dns_domain.as_deref()) {
+        Some(domain) => Ok(domain.to_string()),
+        None => Err(GcectlError::Usage(
+            "no DNS domain; pass --domain or run `gcectl config set dns-domain DOMAIN`".to_string(),
+        )
+        .into()),
+    }
+}
+
+async fn register(session: &Session, args: DnsRegisterArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let domain = domain(session, args.record.domain.as_deref())?;
+    let compute = session.compute().await?;
+    let instance = compute.get_instance(&project, &zone, &args.name).await?;
+    let target = target(&args.record, &project, &domain);
+    let dns = session.dns().await?;
+    let message = point(session, &dns, &target, &instance, args.ttl).await?;
+    success(&message);
+    Ok(())
+}
+
+/// Points NAME.DOMAIN at `instance`'s internal or external IP, returning
+/// what was done.
+pub(super) async fn point(
+    session: &Session,
+    dns: &Dns,
+    target: &Target<'_>,
+    instance: &Instance,
+    ttl: u32,
+) -> Result<String> {
+    let ip = match target.internal {
+        true => instance.internal_ip(),
+        false => instance.external_ip(),
+    };
+    let Some(ip) = ip else {
+        let hint = match target.internal {
+            true => "has no internal IP",
+            false => "has no external IP; --internal registers its internal IP",
+        };
+        return Err(GcectlError::Usage(format!("instance {} {hint}", instance.name)).into());
+    };
+    let record = RecordSet {
+        name: dns::hostname(&instance.name, target.domain),
+        record_type: "A".to_string(),
+        ttl,
+        rrdatas: vec![ip.to_string()],
+    };
+    let managed_zone = managed_zone(dns, target, &record.name).await?;
+    if session.dry_run {
+        print_json(&serde_json::to_value(&record)?)?;
+        return Ok(format!(
+            "Would point {} at {ip} in {managed_zone}",
+            record.name
+        ));
+    }
+    let upserted = with_spinner(
+        format!("Pointing {} at {ip}", record.name),
+        dns.upsert(target.project, &managed_zone, &record),
+    )
+    .await?;
+    Ok(match upserted {
+        Upserted::Created => format!("{} now points at {ip}", record.name),
+        Upserted::Updated => format!("{} updated to point at {ip}", record.name),
+        Upserted::Unchanged => format!("{} already points at {ip}", record.name),
+    })
+}
+
+async fn unregister(session: &Session, args: DnsUnregisterArgs) -> Result<()> {
+    let project = session.project(args.project.as_deref())?;
+    let domain = domain(session, args.record.domain.as_deref())?;
+    let target = target(&args.record, &project, &domain);
+    let dns = session.dns().await?;
+    let name = dns::hostname(&args.name, &domain);
+    let managed_zone = managed_zone(&dns, &target, &name).await?;
+    if session.dry_run {
+        print_note(&format!(
+            "Would delete the A record {name} from {managed_zone}"
+        ));
+        return Ok(());
+    }
+    dns.delete_record_set(target.project, &managed_zone, &name, "A")
+        .await?;
+    success(&format!("Deleted the A record {name}"));
+    Ok(())
+}
+
+fn target<'a>(args: &'a DnsRecordArgs, project: &'a str, domain: &'a str) -> Target<'a> {
+    Target {
+        project: args.dns_project.as_deref().unwrap_or(project),
+        domain,
+        managed_zone: args.managed_zone.as_deref(),
+        internal: args.internal,
+    }
+}
+
+// the managed zone given, else the one serving `fqdn`
+async fn managed_zone(dns: &Dns, target: &Target<'_>, fqdn: &str) -> Result<String> {
+    if let Some(zone) = target.managed_zone {
+        return Ok(zone.to_string());
+    }
+    let zones = dns.list_managed_zones(target.project).await?;
+    let visibility = match target.internal {
+        true => "private",
+        false => "public",
+    };
+    Ok(dns::zone_for(&zones, fqdn, visibility)?.name.clone())
+}
+
+/// `instances create --auto-dns`: registers each of `names` under `domain`,
+/// warning about those that cannot be, since the instances exist either way.
+pub(super) async fn register_created(
+    session: &Session,
+    compute: &Compute,
+    project: &str,
+    zone: &str,
+    domain: &str,
+    names: &[&str],
+) -> Result<()> {
+    let dns = session.dns().await?;
+    for name in names {
+        let registered = async {
+            let instance = compute.get_instance(project, zone, name).await?;
+            let target = Target {
+                project,
+                domain,
+                managed_zone: None,
+                internal: instance.external_ip().is_none(),
+            };
+            point(session, &dns, &target, &instance, DEFAULT_TTL).await
+        };
+        match registered.await {
+            Ok(message) => success(&message),
+            Err(err) => warning(&format!("{name} was not registered in DNS: {err:#}")),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use anyhow::{Result, bail};
+use gcectl_core::console::Resource;
+use gcectl_core::output::{print_json, print_list, print_one};
+use gcectl_core::resources::firewall::FirewallSpec;
+use serde_json::json;
+
+use super::{Session, confirm_delete, delete_all, open_url, requested, success, wait_with_spinner};
+use crate::cli::{
+    FirewallArgs, FirewallCommand, FirewallCreateArgs, FirewallDeleteArgs, FirewallListArgs,
+    FirewallOpenArgs, FirewallRuleArgs, FirewallUpdateArgs,
+};
+
+pub async fn run(session: &Session, cmd: FirewallCommand) -> Result<()> {
+    match cmd {
+        FirewallCommand::List(args) => list(session, args).await,
+        FirewallCommand::Describe(args) => describe(session, args).await,
+        FirewallCommand::Open(args) => open(session, args),
+        FirewallCommand::Create(args) => create(session, args).await,
+        FirewallCommand::Update(args) => update(session, args).await,
+        FirewallCommand::Delete(args) => delete(session, args).await,
+    }
+}
+
+async fn list(session: &Session, args: FirewallListArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let rules = compute
+        .list_firewalls(&project, args.filter.as_ref())
+        .await?;
+    print_list(session.output, &rules)
+}
+
+async fn describe(session: &Session, args: FirewallArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let rule = compute.get_firewall(&project, &args.name).await?;
+    print_one(session.output, &rule)
+}
+
+/// Prints the console URL of the firewall rule, opening it with `--web`.
+fn open(session: &Session, args: FirewallOpenArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let url = Resource::Firewall { name: &args.name }.url(&project);
+    open_url(&url, args.web)
+}
+
+async fn create(session: &Session, args: FirewallCreateArgs) -> Result<()> {
+    if args.rule.allow.is_empty() {
+        bail!("a new rule needs --allow, e.g. --allow tcp:22");
+    }
+    let mut spec = rule_spec(&args.rule);
+    spec.network = Some(args.network.clone());
+    let mut body = spec.to_body();
+    body["name"] = json!(args.name);
+    if session.dry_run {
+        print_json(&body)?;
+        return Ok(());
+    }
+
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let op = compute.insert_firewall(&project, &body).await?;
+    if args.rule.no_wait {
+        requested("Create", &op);
+        return Ok(());
+    }
+    wait_with_spinner(&compute, op, format!("Creating rule {}", args.name)).await?;
+    success(&format!("Firewall rule {} created", args.name));
+    Ok(())
+}
+
+async fn update(session: &Session, args: FirewallUpdateArgs) -> Result<()> {
+    let spec = rule_spec(&args.rule);
+    if spec.is_empty() {
+        bail!("nothing to update; pass at least one rule field");
+    }
+    let body = spec.to_body();
+    if session.dry_run {
+        print_json(&body)?;
+        return Ok(());
+    }
+
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let op = compute.patch_firewall(&project, &args.name, &body).await?;
+    if args.rule.no_wait {
+        requested("Update", &op);
+        return Ok(());
+    }
+    wait_with_spinner(&compute, op, format!("Updating rule {}", args.name)).await?;
+    success(&format!("Firewall rule {} updated", args.name));
+    Ok(())
+}
+
+async fn delete(session: &Session, args: FirewallDeleteArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    confirm_delete(args.force, "firewall rule", &project, &args.names)?;
+    let compute = session.compute().await?;
+    delete_all(&compute, "firewall rule", &args.names, |name| {
+        let (compute, project) = (&compute, &project);
+        async move { compute.delete_firewall(project, &name).await }
+    })
+    .await
+}
+
+fn rule_spec(args: &FirewallRuleArgs) -> FirewallSpec {
+    FirewallSpec {
+        description: args.description.clone(),
+        network: None,
+        direction: args.direction,
+        priority: args.priority,
+        source_ranges: args.source_ranges.clone(),
+        destination_ranges: args.destination_ranges.clone(),
+        source_tags: args.source_tags.clone(),
+        target_tags: args.target_tags.clone(),
+        allow: args.allow.clone(),
+    }
+}
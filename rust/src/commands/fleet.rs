@@ -0,0 +1,130 @@
+use anyhow::{Result, bail};
+use futures_util::future::join_all;
+use gcectl_core::compute::Compute;
+use gcectl_core::fleet::{self, Attempt, Outcome};
+use gcectl_core::output::print_list;
+use gcectl_core::placement::{DEFAULT_WINDOW, ranked_zones};
+use gcectl_core::resources::instance::builder::{ImageSource, InstanceBuilder, Provisioning};
+
+use super::placement::suggest;
+use super::{Session, batch_failed, failure, success, with_spinner};
+use crate::cli::{FleetCommand, FleetCreateArgs};
+
+pub async fn run(session: &Session, cmd: FleetCommand) -> Result<()> {
+    match cmd {
+        FleetCommand::Create(args) => create(session, args).await,
+    }
+}
+
+async fn create(session: &Session, args: FleetCreateArgs) -> Result<()> {
+    let project = session.project(args.project.as_deref())?;
+    let compute = session.compute().await?;
+    let zones = match (&args.region, args.zones.is_empty()) {
+        (Some(region), _) => {
+            let placements = suggest(
+                &compute,
+                &project,
+                region,
+                &args.machine_type,
+                &[],
+                true,
+                DEFAULT_WINDOW,
+            )
+            .await?;
+            let zones = ranked_zones(&placements);
+            if zones.is_empty() {
+                bail!("no zone in {region} offers {}", args.machine_type);
+            }
+            zones
+        }
+        (None, true) => vec![session.zone(None)?],
+        (None, false) => args.zones.clone(),
+    };
+    let names = fleet::member_names(&args.prefix, args.count as usize);
+
+    let tasks = names.iter().enumerate().map(|(i, name)| {
+        create_member(
+            &compute,
+            &project,
+            &args,
+            name,
+            fleet::zone_order(&zones, i),
+        )
+    });
+    let outcomes = with_spinner(
+        format!(
+            "Creating {} Spot instance(s) across {} zone(s)",
+            names.len(),
+            zones.len()
+        ),
+        join_all(tasks),
+    )
+    .await;
+    session.forget_instances(&project);
+
+    let mut failed = 0;
+    for outcome in &outcomes {
+        match (outcome.zone(), outcome.error()) {
+            (Some(zone), _) => success(&format!("Instance {} created in {zone}", outcome.name)),
+            (None, error) => {
+                failed += 1;
+                failure(&format!(
+                    "Instance {}: {}",
+                    outcome.name,
+                    error.unwrap_or_default()
+                ));
+            }
+        }
+    }
+    print_list(session.output, &fleet::summarize(&zones, &outcomes))?;
+    if failed > 0 {
+        return Err(batch_failed("create", "instance", failed, names.len()));
+    }
+    Ok(())
+}
+
+/// Creates one member, moving on to the next zone whenever a zone is out of
+/// capacity. Any other error ends the attempt.
+async fn create_member(
+    compute: &Compute,
+    project: &str,
+    args: &FleetCreateArgs,
+    name: &str,
+    zones: Vec<&str>,
+) -> Outcome {
+    let mut outcome = Outcome::new(name);
+    for zone in zones {
+        let body = member_request(args, name, zone).build();
+        let result = async {
+            let op = compute.insert_instance(project, zone, &body).await?;
+            compute.wait_operation(op).await
+        }
+        .await;
+        match result {
+            Ok(_) => {
+                outcome.record(zone, Attempt::Created);
+                break;
+            }
+            Err(err) if fleet::is_stockout(&err) => outcome.record(zone, Attempt::Stockout),
+            Err(err) => {
+                outcome.record(zone, Attempt::Failed(format!("{err:#}")));
+                break;
+            }
+        }
+    }
+    outcome
+}
+
+fn member_request(args: &FleetCreateArgs, name: &str, zone: &str) -> InstanceBuilder {
+    InstanceBuilder::new(name, zone)
+        .machine_type(&args.machine_type)
+        .image(ImageSource::from_flags(
+            args.image_project.clone(),
+            args.image.clone(),
+            args.image_family.clone(),
+        ))
+        .boot_disk_size_gb(args.boot_disk_size)
+        .labels(args.labels.clone())
+        .metadata(args.metadata.clone())
+        .provisioning(Provisioning::Spot)
+}
@@ -0,0 +1,28 @@
+use anyhow::Result;
+use gcectl_core::filter::Filter;
+use gcectl_core::output::print_list;
+
+use super::{Session, warning};
+use crate::cli::{GpuListZonesArgs, GpusCommand};
+
+pub async fn run(session: &Session, cmd: GpusCommand) -> Result<()> {
+    match cmd {
+        GpusCommand::ListZones(args) => list_zones(session, args).await,
+    }
+}
+
+async fn list_zones(session: &Session, args: GpuListZonesArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let filter: Filter = format!("name={}", args.accelerator_type).parse()?;
+    let compute = session.compute().await?;
+    let types = compute
+        .list_accelerator_types_all_zones(&project, Some(&filter))
+        .await?;
+    if types.is_empty() {
+        warning(&format!(
+            "No zone offers accelerator type {}",
+            args.accelerator_type
+        ));
+    }
+    print_list(session.output, &types)
+}
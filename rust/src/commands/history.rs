@@ -0,0 +1,29 @@
+use anyhow::Result;
+use chrono::Utc;
+use gcectl_core::audit::{self, Entry};
+use gcectl_core::output::print_list;
+
+use super::Session;
+use crate::cli::HistoryArgs;
+
+pub fn run(session: &Session, args: HistoryArgs) -> Result<()> {
+    let since = args
+        .since
+        .map(|window| Utc::now() - chrono::Duration::from_std(window).unwrap_or_default());
+    let matches = |entry: &Entry| {
+        since.is_none_or(|since| entry.time >= since)
+            && args
+                .project
+                .as_ref()
+                .is_none_or(|p| entry.project.as_ref() == Some(p))
+            && args
+                .target
+                .as_ref()
+                .is_none_or(|t| entry.target.contains(t.as_str()))
+            && args.user.as_ref().is_none_or(|u| &entry.user == u)
+    };
+    let mut entries: Vec<Entry> = audit::read()?.into_iter().filter(matches).collect();
+    let skip = entries.len().saturating_sub(args.limit);
+    entries.drain(..skip);
+    print_list(session.output, &entries)
+}
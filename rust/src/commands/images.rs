@@ -0,0 +1,102 @@
+use anyhow::Result;
+use gcectl_core::console::Resource;
+use gcectl_core::output::{print_list, print_one};
+use gcectl_core::resources::Image;
+
+use super::{Session, confirm_delete, delete_all, open_url, requested, success, wait_with_spinner};
+use crate::cli::{
+    ImageCreateArgs, ImageDeleteArgs, ImageDeprecateArgs, ImageDescribeArgs, ImageListArgs,
+    ImageOpenArgs, ImagesCommand,
+};
+
+pub async fn run(session: &Session, cmd: ImagesCommand) -> Result<()> {
+    match cmd {
+        ImagesCommand::List(args) => list(session, args).await,
+        ImagesCommand::Describe(args) => describe(session, args).await,
+        ImagesCommand::Open(args) => open(session, args),
+        ImagesCommand::Create(args) => create(session, args).await,
+        ImagesCommand::Delete(args) => delete(session, args).await,
+        ImagesCommand::Deprecate(args) => deprecate(session, args).await,
+    }
+}
+
+async fn list(session: &Session, args: ImageListArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let mut images = compute.list_images(&project, args.filter.as_ref()).await?;
+    if !args.show_deprecated {
+        images.retain(|image| image.deprecation_state().is_none());
+    }
+    print_list(session.output, &images)
+}
+
+async fn describe(session: &Session, args: ImageDescribeArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let image = match (&args.name, &args.family) {
+        (_, Some(family)) => compute.get_image_from_family(&project, family).await?,
+        (Some(name), None) => compute.get_image(&project, name).await?,
+        (None, None) => unreachable!("clap requires NAME or --family"),
+    };
+    print_one(session.output, &image)
+}
+
+/// Prints the console URL of the image, opening it with `--web`.
+fn open(session: &Session, args: ImageOpenArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let url = Resource::Image { name: &args.name }.url(&project);
+    open_url(&url, args.web)
+}
+
+async fn create(session: &Session, args: ImageCreateArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let body = Image::create_request(
+        &args.name,
+        &format!("projects/{project}/zones/{zone}/disks/{}", args.source_disk),
+        args.family.as_deref(),
+        args.description.as_deref(),
+        &args.labels,
+    );
+    let compute = session.compute().await?;
+    let op = compute.insert_image(&project, &body).await?;
+    if args.no_wait {
+        requested("Create", &op);
+        return Ok(());
+    }
+    wait_with_spinner(
+        &compute,
+        op,
+        format!(
+            "Creating image {} from disk {}",
+            args.name, args.source_disk
+        ),
+    )
+    .await?;
+    success(&format!("Image {} created", args.name));
+    Ok(())
+}
+
+async fn delete(session: &Session, args: ImageDeleteArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    confirm_delete(args.force, "image", &project, &args.names)?;
+    let compute = session.compute().await?;
+    delete_all(&compute, "image", &args.names, |name| {
+        let (compute, project) = (&compute, &project);
+        async move { compute.delete_image(project, &name).await }
+    })
+    .await
+}
+
+async fn deprecate(session: &Session, args: ImageDeprecateArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let body = Image::deprecate_request(&project, args.state, args.replacement.as_deref());
+    let compute = session.compute().await?;
+    let op = compute.deprecate_image(&project, &args.name, &body).await?;
+    wait_with_spinner(&compute, op, format!("Updating image {}", args.name)).await?;
+    success(&format!(
+        "Image {} marked {}",
+        args.name,
+        args.state.api_name()
+    ));
+    Ok(())
+}
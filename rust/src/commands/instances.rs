@@ -0,0 +1,2131 @@
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{Local, Utc};
+use futures_util::future::{join_all, try_join_all};
+use gcectl_core::batch::{self, Outcome, ResumeFile};
+use gcectl_core::compute::{self, COMPUTE_ENDPOINT, Compute, Paging};
+use gcectl_core::console::{self, Resource};
+use gcectl_core::diagnose::{Check, Status, summary};
+use gcectl_core::diff;
+use gcectl_core::drain::{self, Drained};
+use gcectl_core::error::GcectlError;
+use gcectl_core::events;
+use gcectl_core::idle::{Thresholds, Utilization};
+use gcectl_core::labels::LabelEdit;
+use gcectl_core::monitoring::{CPU_UTILIZATION, NETWORK_RECEIVED, NETWORK_SENT};
+use gcectl_core::output::{
+    self, InProject, ListPrinter, OutputFormat, print_list, print_note, print_one, print_text,
+};
+use gcectl_core::relocate;
+use gcectl_core::resources::firewall::{IAP_SOURCE_RANGE, Ingress};
+use gcectl_core::resources::instance::builder::{
+    DEFAULT_SCOPE, ImageSource, InstanceBuilder, Provisioning,
+};
+use gcectl_core::resources::instance::clone::{self, Overrides};
+use gcectl_core::resources::instance::{
+    AccessConfig, LINUX_STARTUP_SCRIPT, MaintenancePolicy, ShieldedInstanceConfig,
+    StartupScriptKeys, WINDOWS_STARTUP_SCRIPT, WithTags,
+};
+use gcectl_core::resources::{
+    Accelerator, Disk, Firewall, Instance, Operation, region_of, short_name,
+};
+use gcectl_core::ssh;
+use gcectl_core::tunnel::{self, IapTarget};
+use gcectl_core::validate;
+use gcectl_core::watch::{self, StatusTracker};
+use gcectl_core::windows::{self, WINDOWS_KEYS, WindowsCredentials, WindowsKey};
+use serde_json::json;
+use tracing::debug;
+
+use super::{
+    Drive, Session, Verb, apply_all, batch_failed, capitalize, confirm_delete, dns,
+    finish_label_edit, metadata_entries, notifying, open_url, remove_metadata_keys, requested,
+    success, wait_with_spinner, warning, with_spinner,
+};
+use crate::cli::{
+    AddMetadataArgs, AssignIpArgs, CheckPortArgs, CreateArgs, CreateFromArgs, DeleteArgs,
+    DescribeArgs, GetStartupScriptArgs, IdleArgs, InstanceAddLabelsArgs, InstanceDiffArgs,
+    InstanceListArgs, InstanceOpenArgs, InstancePropertiesArgs, InstanceRemoveLabelsArgs,
+    InstanceTagsArgs, InstanceUpdateArgs, InstancesCommand, LifecycleArgs, ListArgs, MoveArgs,
+    RemoveMetadataArgs, ResetWindowsPasswordArgs, RestartArgs, ResumeArgs, ScreenshotArgs,
+    SelectionArgs, SetMachineTypeArgs, SetSchedulingArgs, SetServiceAccountArgs,
+    SetStartupScriptArgs, ShieldedArgs, StopArgs, TailSerialArgs, WatchArgs,
+};
+use crate::completion;
+use crate::prompt;
+
+// the agent answers within a minute or two of seeing the key
+const WINDOWS_AGENT_INTERVAL: Duration = Duration::from_secs(3);
+const WINDOWS_AGENT_POLLS: u32 = 100;
+
+pub async fn run(session: &Session, cmd: InstancesCommand) -> Result<()> {
+    match cmd {
+        InstancesCommand::List(args) => list(session, args).await,
+        InstancesCommand::Describe(args) => describe(session, args).await,
+        InstancesCommand::Open(args) => open(session, args),
+        InstancesCommand::Create(args) => {
+            notifying(session, args.notify, create(session, *args)).await
+        }
+        InstancesCommand::CreateFrom(args) => create_from(session, args).await,
+        InstancesCommand::Update(args) => update(session, args).await,
+        InstancesCommand::Delete(args) => delete(session, args).await,
+        InstancesCommand::Watch(args) => watch(session, args).await,
+        InstancesCommand::Start(args) => {
+            notifying(session, args.notify, start(session, args)).await
+        }
+        InstancesCommand::Stop(args) => {
+            notifying(session, args.lifecycle.notify, stop(session, args)).await
+        }
+        InstancesCommand::Reset(args) => {
+            notifying(session, args.notify, reset(session, args)).await
+        }
+        InstancesCommand::Suspend(args) => {
+            notifying(session, args.notify, suspend(session, args)).await
+        }
+        InstancesCommand::Resume(args) => {
+            notifying(session, args.notify, resume(session, args)).await
+        }
+        InstancesCommand::TailSerial(args) => tail_serial(session, args).await,
+        InstancesCommand::Screenshot(args) => screenshot(session, args).await,
+        InstancesCommand::CheckPort(args) => check_port(session, args).await,
+        InstancesCommand::AddMetadata(args) => add_metadata(session, args).await,
+        InstancesCommand::RemoveMetadata(args) => remove_metadata(session, args).await,
+        InstancesCommand::AddLabels(args) => add_labels(session, args).await,
+        InstancesCommand::RemoveLabels(args) => remove_labels(session, args).await,
+        InstancesCommand::AddTags(args) => edit_tags(session, args, true).await,
+        InstancesCommand::RemoveTags(args) => edit_tags(session, args, false).await,
+        InstancesCommand::SetStartupScript(args) => set_startup_script(session, args).await,
+        InstancesCommand::GetStartupScript(args) => get_startup_script(session, args).await,
+        InstancesCommand::ResetWindowsPassword(args) => reset_windows_password(session, args).await,
+        InstancesCommand::Diff(args) => diff(session, args).await,
+        InstancesCommand::SetMachineType(args) => set_machine_type(session, args).await,
+        InstancesCommand::SetServiceAccount(args) => set_service_account(session, args).await,
+        InstancesCommand::SetScheduling(args) => set_scheduling(session, args).await,
+        InstancesCommand::AssignIp(args) => assign_ip(session, args).await,
+        InstancesCommand::Move(args) => move_instance(session, args).await,
+        InstancesCommand::Idle(args) => idle(session, args).await,
+    }
+}
+
+async fn list(session: &Session, args: InstanceListArgs) -> Result<()> {
+    let InstanceListArgs { list: args, paging } = args;
+    let paging = paging.paging();
+    if args.all_projects {
+        return list_all_projects(session, args, paging).await;
+    }
+    let project = session.project(args.zonal.project.as_deref())?;
+    let zone = session.list_zone(&args.zonal, args.all_zones);
+    let compute = session.compute().await?;
+    let (zone, filter) = (zone.as_deref(), args.filter.as_ref());
+    let listed = if args.show_tags {
+        let mut printer = ListPrinter::stdout(session.output);
+        let listed = session
+            .list_instance_pages(&compute, &project, zone, filter, paging, |page| {
+                printer.page(&page.iter().map(WithTags).collect::<Vec<_>>())
+            })
+            .await;
+        printer.close::<WithTags, _>(listed)?
+    } else {
+        let mut printer = ListPrinter::stdout(session.output);
+        let listed = session
+            .list_instance_pages(&compute, &project, zone, filter, paging, |page| {
+                printer.page(page)
+            })
+            .await;
+        printer.close::<Instance, _>(listed)?
+    };
+    // a filtered listing says nothing about the instances it left out
+    if filter.is_none()
+        && let Some(instances) = listed
+        && let Err(err) = completion::remember_instances(&instances, zone)
+    {
+        debug!("not saving completions: {err:#}");
+    }
+    Ok(())
+}
+
+/// Lists instances of every project `--all-projects` covers concurrently.
+/// Projects that cannot be listed, e.g. without the Compute API enabled,
+/// are reported and skipped.
+async fn list_all_projects(session: &Session, args: ListArgs, paging: Paging) -> Result<()> {
+    let projects = session.all_projects().await?;
+    let zone = session.list_zone(&args.zonal, args.all_zones);
+    let compute = session.compute().await?;
+    let results = with_spinner(
+        format!("Listing instances in {} project(s)", projects.len()),
+        join_all(projects.iter().map(|project| {
+            session.list_instances(&compute, project, zone.as_deref(), args.filter.as_ref())
+        })),
+    )
+    .await;
+    let mut listed = Vec::new();
+    let mut failed = 0;
+    for (project, result) in projects.iter().zip(results) {
+        match result {
+            Ok(instances) => listed.extend(instances.into_iter().map(|i| (project.as_str(), i))),
+            Err(err) => {
+                failed += 1;
+                warning(&format!("skipping project {project}: {err:#}"));
+            }
+        }
+    }
+    if failed == projects.len() {
+        bail!("could not list instances in any of {failed} project(s)");
+    }
+    if let Some(limit) = paging.limit {
+        listed.truncate(limit as usize);
+    }
+    if args.show_tags {
+        let tagged: Vec<(&str, WithTags)> = listed.iter().map(|(p, i)| (*p, WithTags(i))).collect();
+        let rows: Vec<InProject<WithTags>> = tagged
+            .iter()
+            .map(|(project, item)| InProject { project, item })
+            .collect();
+        return print_list(session.output, &rows);
+    }
+    let rows: Vec<InProject<Instance>> = listed
+        .iter()
+        .map(|(project, item)| InProject { project, item })
+        .collect();
+    print_list(session.output, &rows)
+}
+
+/// Redraws the instance table every `--interval` seconds, flagging status
+/// changes since the previous poll.
+async fn watch(session: &Session, args: WatchArgs) -> Result<()> {
+    if args.list.all_projects {
+        bail!("watch covers a single project; pass --project instead of --all-projects");
+    }
+    let project = session.project(args.list.zonal.project.as_deref())?;
+    let zone = session.list_zone(&args.list.zonal, args.list.all_zones);
+    let scope = zone.as_deref().unwrap_or("all zones");
+    let compute = session.compute().await?;
+    // events carry each frame as a message instead
+    let interactive = io::stdout().is_terminal() && !events::enabled();
+    let mut tracker = StatusTracker::default();
+    loop {
+        let instances = compute
+            .list_instances_in(&project, zone.as_deref(), args.list.filter.as_ref())
+            .await?;
+        let changes = tracker.update(&instances);
+        if interactive {
+            // clear the screen and move the cursor home
+            print!("\x1b[2J\x1b[H");
+        }
+        print_note(&format!(
+            "Every {}s: {project}/{scope} at {}\n{}",
+            args.interval,
+            Local::now().format("%H:%M:%S"),
+            watch::render(&instances, &changes)
+        ));
+
+        if let Some(until) = &args.until
+            && !instances.is_empty()
+            && instances
+                .iter()
+                .all(|i| i.status.eq_ignore_ascii_case(until))
+        {
+            success(&format!("All {} instance(s) are {until}", instances.len()));
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(args.interval)).await;
+    }
+}
+
+/// Prints serial port output as it arrives, resuming each poll from the
+/// byte offset the previous one ended at.
+async fn tail_serial(session: &Session, args: TailSerialArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let mut offset = 0;
+    loop {
+        let output = compute
+            .get_serial_port_output(&project, &zone, &args.name, args.port, offset)
+            .await?;
+        if output.start_offset() > offset && offset > 0 {
+            warning(&format!(
+                "{} byte(s) of output rotated out of the buffer before they were read",
+                output.start_offset() - offset
+            ));
+        }
+        print_text(&output.contents);
+        io::stdout().flush()?;
+        offset = output.next_offset().max(offset);
+
+        if args.no_follow {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(args.interval)).await;
+    }
+}
+
+async fn screenshot(session: &Session, args: ScreenshotArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let shot = with_spinner(
+        format!("Capturing the display of {}...", args.name),
+        compute.get_screenshot(&project, &zone, &args.name),
+    )
+    .await?;
+    let path = args
+        .file
+        .unwrap_or_else(|| format!("{}.png", args.name).into());
+    fs::write(&path, shot.png()?).with_context(|| format!("failed to write {}", path.display()))?;
+    success(&format!(
+        "Saved the display of {} to {}",
+        args.name,
+        path.display()
+    ));
+    Ok(())
+}
+
+/// Tries the port directly and through IAP, then works out from the firewall
+/// rules of the instance's network what lets the traffic in or keeps it out.
+/// Fails unless one of the two connections got through.
+async fn check_port(session: &Session, args: CheckPortArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let instance = compute.get_instance(&project, &zone, &args.name).await?;
+    let (name, port) = (&args.name, args.port);
+    let timeout = Duration::from_secs(args.timeout);
+    let running = instance.status == "RUNNING";
+    let mut checks = vec![match running {
+        true => Check::ok("instance", format!("{name} is RUNNING")),
+        false => Check::fail(
+            "instance",
+            format!("{name} is {}", instance.status),
+            format!("start it with `gcectl instances start {name}`"),
+        ),
+    }];
+
+    let address = match args.internal_ip {
+        true => instance.internal_ip(),
+        false => instance.external_ip(),
+    };
+    checks.push(match (running, address) {
+        (false, _) => Check::skipped("direct", "instance"),
+        (true, Some(ip)) => connect_directly(ip, port, timeout).await,
+        (true, None) if args.internal_ip => Check::fail("direct", "no internal IP", ""),
+        (true, None) => Check::warn(
+            "direct",
+            "no external IP",
+            "use --internal-ip from inside the VPC, or go through IAP",
+        ),
+    });
+    checks.push(if running {
+        let target = IapTarget {
+            project: project.clone(),
+            zone: zone.clone(),
+            instance: name.clone(),
+            interface: "nic0".to_string(),
+            port,
+        };
+        let auth = session.auth().await?;
+        match tokio::time::timeout(timeout * 2, tunnel::probe(&auth, &target)).await {
+            Ok(Ok(())) => Check::ok("iap", format!("connected to {name}:{port} through IAP")),
+            Ok(Err(err)) => Check::failed(
+                "iap",
+                &err,
+                "needs a firewall rule for IAP, something listening on the port, \
+                 and roles/iap.tunnelResourceAccessor",
+            ),
+            Err(_) => Check::fail(
+                "iap",
+                format!("no answer through IAP within {}s", args.timeout * 2),
+                "see the firewall (iap) check",
+            ),
+        }
+    } else {
+        Check::skipped("iap", "instance")
+    });
+
+    let network = instance
+        .network_interfaces
+        .first()
+        .and_then(|nic| nic.network.as_deref())
+        .unwrap_or_default();
+    // rules of a shared VPC live in its host project
+    let network_project = network
+        .split("projects/")
+        .nth(1)
+        .and_then(|rest| rest.split('/').next())
+        .unwrap_or(&project);
+    match compute.list_firewalls(network_project, None).await {
+        Ok(rules) => {
+            let service_accounts: Vec<String> = instance
+                .service_accounts
+                .iter()
+                .map(|sa| sa.email.clone())
+                .collect();
+            let ingress = Ingress {
+                network,
+                tags: instance.tags(),
+                service_accounts: &service_accounts,
+                port,
+            };
+            checks.extend(firewall_checks(&ingress, &rules));
+        }
+        Err(err) => {
+            let check = Check::failed("firewall", &err, "needs compute.firewalls.list");
+            checks.push(check);
+        }
+    }
+
+    print_list(session.output, &checks)?;
+    eprintln!("{}", summary(&checks));
+    let reached = |check: &str| {
+        checks
+            .iter()
+            .any(|c| c.check == check && c.status == Status::Ok)
+    };
+    if !reached("direct") && !reached("iap") {
+        bail!("port {port} of {name} is not reachable");
+    }
+    Ok(())
+}
+
+async fn connect_directly(ip: &str, port: u16, timeout: Duration) -> Check {
+    let connect = tokio::net::TcpStream::connect((ip, port));
+    match tokio::time::timeout(timeout, connect).await {
+        Ok(Ok(_)) => Check::ok("direct", format!("connected to {ip}:{port}")),
+        Ok(Err(err)) if err.kind() == io::ErrorKind::ConnectionRefused => Check::fail(
+            "direct",
+            format!("{ip}:{port} refused the connection"),
+            "the firewall let it through but nothing listens on the port; \
+             check that the service is up and not bound to localhost only",
+        ),
+        Ok(Err(err)) => Check::fail("direct", format!("{ip}:{port}: {err}"), ""),
+        Err(_) => Check::fail(
+            "direct",
+            format!("no answer from {ip}:{port} within {}s", timeout.as_secs()),
+            "a firewall is probably dropping it; see the firewall (direct) check",
+        ),
+    }
+}
+
+/// What the firewall rules decide for traffic from IAP and from anywhere.
+fn firewall_checks(ingress: &Ingress, rules: &[Firewall]) -> [Check; 2] {
+    let port = ingress.port;
+    let misses = ingress.near_misses(rules);
+    let missed = |detail: String| match misses.is_empty() {
+        true => detail,
+        false => format!("{detail}; {}", misses.join("; ")),
+    };
+    let create = |name: &str, sources: &str| {
+        let tags = match ingress.tags.first() {
+            Some(tag) => format!(" --target-tags {tag}"),
+            None => String::new(),
+        };
+        format!(
+            "gcectl firewall create {name}-{port} --network {} --allow tcp:{port} \
+             --source-ranges {sources}{tags}",
+            short_name(ingress.network)
+        )
+    };
+    let decided = |check: &'static str, rule: &Firewall, from: &str| match rule.is_deny() {
+        true => Check::fail(
+            check,
+            format!("denied by {} at priority {}", rule.name, rule.priority),
+            format!(
+                "allow {from} at a priority number below {}, or narrow {}",
+                rule.priority, rule.name
+            ),
+        ),
+        false => Check::ok(
+            check,
+            format!(
+                "allowed from {} by {} at priority {}",
+                rule.source_ranges.join(", "),
+                rule.name,
+                rule.priority
+            ),
+        ),
+    };
+    let iap = match ingress.deciding(rules, Some(IAP_SOURCE_RANGE)) {
+        Some(rule) => decided("firewall (iap)", rule, IAP_SOURCE_RANGE),
+        None => Check::fail(
+            "firewall (iap)",
+            missed(format!(
+                "no rule lets {IAP_SOURCE_RANGE} reach tcp:{port}, so the implied deny applies"
+            )),
+            create("allow-iap", IAP_SOURCE_RANGE),
+        ),
+    };
+    let direct = match ingress.deciding(rules, None) {
+        Some(rule) => decided("firewall (direct)", rule, "your address"),
+        None => Check::fail(
+            "firewall (direct)",
+            missed(format!(
+                "no rule allows tcp:{port}, so the implied deny applies"
+            )),
+            create("allow", "YOUR_IP/32"),
+        ),
+    };
+    [iap, direct]
+}
+
+async fn describe(session: &Session, args: DescribeArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let instance = compute.get_instance(&project, &zone, &args.name).await?;
+    print_one(session.output, &instance)
+}
+
+/// Prints the console URL of the instance, or of its SSH-in-browser
+/// session, opening it with `--web`.
+fn open(session: &Session, args: InstanceOpenArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let url = match args.ssh_in_browser {
+        true => console::ssh_in_browser_url(&project, &zone, &args.name),
+        false => Resource::Instance {
+            zone: &zone,
+            name: &args.name,
+        }
+        .url(&project),
+    };
+    open_url(&url, args.web)
+}
+
+async fn create(session: &Session, args: CreateArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let Some(name) = &args.name else {
+        return create_many(session, &args, &project, &zone).await;
+    };
+    let domain = dns_domain(session, &args)?;
+    let body = create_body(&args, name, &zone)?;
+    let compute = create_client(session).await?;
+    validate_create(
+        session,
+        compute.as_ref(),
+        &args,
+        &project,
+        &zone,
+        std::slice::from_ref(name),
+    )
+    .await?;
+    if session.dry_run {
+        return preview_inserts(session, &project, &zone, std::slice::from_ref(&body));
+    }
+
+    let compute = compute.expect("only dry runs go without a client");
+    let op = compute.insert_instance(&project, &zone, &body).await?;
+    session.forget_instances(&project);
+    if args.no_wait {
+        requested("Create", &op);
+        return Ok(());
+    }
+    wait_with_spinner(&compute, op, format!("Creating instance {name}")).await?;
+    success(&format!("Instance {name} created"));
+    if let Some(domain) = &domain {
+        dns::register_created(session, &compute, &project, &zone, domain, &[name]).await?;
+    }
+    Ok(())
+}
+
+/// The domain `--auto-dns` registers under, checked before anything is
+/// created.
+fn dns_domain(session: &Session, args: &CreateArgs) -> Result<Option<String>> {
+    args.auto_dns
+        .then(|| dns::domain(session, None))
+        .transpose()
+}
+
+fn create_body(args: &CreateArgs, name: &str, zone: &str) -> Result<serde_json::Value> {
+    let mut builder = instance_builder(&args.properties, name, zone);
+    if let Some(lifetime) = args.max_lifetime {
+        builder = builder.max_lifetime(lifetime)?;
+    }
+    Ok(builder.build())
+}
+
+/// The client a create is validated and sent with. The request is built
+/// from flags alone, so a dry run without credentials still previews it,
+/// validated as far as the flags allow.
+async fn create_client(session: &Session) -> Result<Option<Compute>> {
+    match session.compute().await {
+        Ok(compute) => Ok(Some(compute)),
+        Err(err) if session.dry_run => {
+            debug!("previewing without credentials: {err:#}");
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Prints the inserts of `bodies` as the client's dry-run mode would,
+/// without needing credentials for one.
+fn preview_inserts(
+    session: &Session,
+    project: &str,
+    zone: &str,
+    bodies: &[serde_json::Value],
+) -> Result<()> {
+    let endpoint = session
+        .endpoints
+        .compute
+        .as_deref()
+        .unwrap_or(COMPUTE_ENDPOINT);
+    let url = format!("{endpoint}/projects/{project}/zones/{zone}/instances");
+    for body in bodies {
+        compute::print_dry_run("POST", &url, Some(body))?;
+    }
+    Ok(())
+}
+
+/// Checks a create request against the zone's machine types and the boot
+/// image's project before anything is sent, reporting every problem at
+/// once. A catalog that cannot be read, or a missing client, leaves its
+/// checks to the API.
+async fn validate_create(
+    session: &Session,
+    compute: Option<&Compute>,
+    args: &CreateArgs,
+    project: &str,
+    zone: &str,
+    names: &[String],
+) -> Result<()> {
+    if args.skip_validation {
+        return Ok(());
+    }
+    let properties = &args.properties;
+    let image = ImageSource::from_flags(
+        properties.image_project.clone(),
+        properties.image.clone(),
+        properties.image_family.clone(),
+    );
+    let (machine_types, images) = match compute {
+        Some(compute) => tokio::join!(
+            session.machine_types(compute, project, zone),
+            session.images(compute, &properties.image_project),
+        ),
+        None => (
+            Err(anyhow!("no credentials to read it with")),
+            Err(anyhow!("no credentials to read it with")),
+        ),
+    };
+    let request = validate::Request {
+        names,
+        zone,
+        machine_type: &properties.machine_type,
+        image: Some(&image),
+        boot_disk_size_gb: properties.boot_disk_size,
+        accelerators: &properties.accelerator,
+    };
+    let catalogs = validate::Catalogs {
+        machine_types: readable(&machine_types),
+        images: readable(&images),
+    };
+    report_invalid(validate::check(&request, catalogs))
+}
+
+/// A catalog validation can use, logging why if there is none.
+fn readable<T>(catalog: &Result<Vec<T>>) -> Option<&[T]> {
+    if let Err(err) = catalog {
+        debug!("validating without a catalog: {err:#}");
+    }
+    catalog.as_deref().ok()
+}
+
+fn report_invalid(problems: Vec<String>) -> Result<()> {
+    if problems.is_empty() {
+        return Ok(());
+    }
+    let list: String = problems.iter().map(|p| format!("\n  - {p}")).collect();
+    Err(GcectlError::Usage(format!(
+        "the request would be rejected:{list}\n(--skip-validation sends it anyway)"
+    ))
+    .into())
+}
+
+/// Creates `--count` instances named by `--name-template` concurrently and
+/// prints what became of each.
+async fn create_many(
+    session: &Session,
+    args: &CreateArgs,
+    project: &str,
+    zone: &str,
+) -> Result<()> {
+    let template = args
+        .name_template
+        .as_ref()
+        .expect("clap requires a name or a template");
+    let today = Local::now().date_naive();
+    let names = template.names(args.start_index, args.count, zone, today)?;
+    let domain = dns_domain(session, args)?;
+    let bodies = names
+        .iter()
+        .map(|name| create_body(args, name, zone))
+        .collect::<Result<Vec<_>>>()?;
+    let compute = create_client(session).await?;
+    validate_create(session, compute.as_ref(), args, project, zone, &names).await?;
+    if session.dry_run {
+        return preview_inserts(session, project, zone, &bodies);
+    }
+
+    let compute = compute.expect("only dry runs go without a client");
+    let tasks = bodies.iter().map(|body| async {
+        let op = compute.insert_instance(project, zone, body).await?;
+        match args.no_wait {
+            true => Ok(op),
+            false => compute.wait_operation(op).await,
+        }
+    });
+    let results = with_spinner(
+        format!("Creating {} instance(s) in {zone}", names.len()),
+        join_all(tasks),
+    )
+    .await;
+    session.forget_instances(project);
+    let outcomes: Vec<Outcome> = names
+        .iter()
+        .zip(results)
+        .map(|(name, result)| match result {
+            Ok(op) if args.no_wait => {
+                Outcome::new(name, &format!("requested ({})", op.name), Ok(()))
+            }
+            result => Outcome::new(name, "created", result.map(drop)),
+        })
+        .collect();
+    print_list(session.output, &outcomes)?;
+    if let Some(domain) = &domain {
+        let created: Vec<&str> = outcomes
+            .iter()
+            .filter(|o| o.ok)
+            .map(|o| o.name.as_str())
+            .collect();
+        dns::register_created(session, &compute, project, zone, domain, &created).await?;
+    }
+    let failed = outcomes.iter().filter(|o| !o.ok).count();
+    if failed > 0 {
+        return Err(batch_failed("create", "instance", failed, names.len()));
+    }
+    Ok(())
+}
+
+/// Copies `--source` into a new instance in `--zone`, recreating its disks
+/// from the images or snapshots they were made from.
+async fn create_from(session: &Session, args: CreateFromArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let source_zone = args.source_zone.as_deref().unwrap_or(&zone);
+    let compute = session.compute().await?;
+    let source = compute
+        .get_instance(&project, source_zone, &args.source)
+        .await?;
+    let sources: Vec<&str> = source
+        .disks
+        .iter()
+        .filter_map(|d| d.source.as_deref())
+        .collect();
+    if let Some(regional) = sources.iter().find(|url| !url.contains("/zones/")) {
+        bail!(
+            "instance {} uses regional disk {}, which cannot be copied",
+            args.source,
+            short_name(regional)
+        );
+    }
+    let disks: Vec<Disk> = try_join_all(
+        sources
+            .iter()
+            .map(|url| compute.get_disk(&project, source_zone, short_name(url))),
+    )
+    .await?;
+    let disks = sources.iter().copied().zip(disks.iter()).collect();
+    let overrides = Overrides {
+        name: &args.name,
+        zone: &zone,
+        machine_type: args.machine_type.as_deref(),
+        keep_disk_names: false,
+    };
+    let body = clone::clone_body(&source, &disks, &overrides)?;
+    if session.dry_run {
+        return preview_inserts(session, &project, &zone, std::slice::from_ref(&body));
+    }
+    let op = compute.insert_instance(&project, &zone, &body).await?;
+    session.forget_instances(&project);
+    if args.no_wait {
+        requested("Create", &op);
+        return Ok(());
+    }
+    let message = format!("Creating instance {} from {}", args.name, args.source);
+    wait_with_spinner(&compute, op, message).await?;
+    success(&format!(
+        "Instance {} created from {}",
+        args.name, args.source
+    ));
+    Ok(())
+}
+
+/// Builder for `name` configured from the shared instance flags.
+pub(super) fn instance_builder(
+    args: &InstancePropertiesArgs,
+    name: &str,
+    zone: &str,
+) -> InstanceBuilder {
+    let image = ImageSource::from_flags(
+        args.image_project.clone(),
+        args.image.clone(),
+        args.image_family.clone(),
+    );
+    let provisioning = if args.spot {
+        Provisioning::Spot
+    } else if args.preemptible {
+        Provisioning::Preemptible
+    } else {
+        Provisioning::Standard
+    };
+    let mut builder = InstanceBuilder::new(name, zone)
+        .machine_type(&args.machine_type)
+        .image(image)
+        .boot_disk_size_gb(args.boot_disk_size)
+        .boot_disk_type(args.boot_disk_type.clone())
+        .boot_disk_kms_key(args.kms_key.clone())
+        .network(args.network.clone())
+        .subnet(args.subnet.clone())
+        .external_ip(!args.no_address)
+        .labels(args.labels.clone())
+        .metadata(args.metadata.clone())
+        .provisioning(provisioning)
+        .service_account(args.service_account.clone())
+        .accelerators(args.accelerator.clone())
+        .shielded(shielded_config(&args.shielded))
+        .confidential_compute(args.confidential_compute)
+        .node_group(args.node_group.clone());
+    if !args.scopes.is_empty() {
+        builder = builder.scopes(args.scopes.clone());
+    }
+    builder
+}
+
+fn shielded_config(args: &ShieldedArgs) -> ShieldedInstanceConfig {
+    ShieldedInstanceConfig {
+        enable_secure_boot: args.secure_boot,
+        enable_vtpm: args.vtpm,
+        enable_integrity_monitoring: args.integrity_monitoring,
+    }
+}
+
+async fn update(session: &Session, args: InstanceUpdateArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let changes = shielded_config(&args.shielded);
+    if changes == ShieldedInstanceConfig::default() && args.confidential_compute.is_none() {
+        bail!(
+            "nothing to update; pass --shielded-secure-boot, --shielded-vtpm, \
+             --shielded-integrity-monitoring, or --confidential-compute"
+        );
+    }
+    let compute = session.compute().await?;
+    let instance = compute.get_instance(&project, &zone, &args.name).await?;
+    let updated = with_security_settings(&instance, &changes, args.confidential_compute);
+    let shielded = instance
+        .shielded_instance_config
+        .clone()
+        .unwrap_or_default();
+    if updated.shielded_instance_config == Some(shielded)
+        && updated.confidential_instance_config == instance.confidential_instance_config
+    {
+        success(&format!(
+            "Instance {} already has these settings",
+            args.name
+        ));
+        return Ok(());
+    }
+    // stopping the instance changes its fingerprint, so the body is built
+    // from the instance as it is once stopped
+    while_stopped(
+        session,
+        &compute,
+        &project,
+        &instance,
+        "update its security settings",
+        &args.restart,
+        || async {
+            let stopped = compute.get_instance(&project, &zone, &args.name).await?;
+            let updated = with_security_settings(&stopped, &changes, args.confidential_compute);
+            compute
+                .update_instance(&project, &zone, &args.name, &updated)
+                .await
+        },
+    )
+    .await?;
+    success(&format!("Instance {} updated", args.name));
+    Ok(())
+}
+
+// `instance` with the shielded VM `changes` and Confidential Computing
+// setting applied
+fn with_security_settings(
+    instance: &Instance,
+    changes: &ShieldedInstanceConfig,
+    confidential_compute: Option<bool>,
+) -> Instance {
+    let mut updated = instance.clone();
+    let shielded = instance
+        .shielded_instance_config
+        .clone()
+        .unwrap_or_default();
+    updated.shielded_instance_config = Some(shielded.merge(changes));
+    if let Some(enabled) = confidential_compute {
+        updated
+            .confidential_instance_config
+            .get_or_insert_default()
+            .enable_confidential_compute = Some(enabled);
+    }
+    updated
+}
+
+async fn delete(session: &Session, args: DeleteArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let (names, _) = select_instances(session, &project, &zone, &args.selection).await?;
+    confirm_delete(args.force, "instance", &zone, &names)?;
+    let compute = session.compute().await?;
+    let requests = names
+        .iter()
+        .map(|name| compute.delete_instance(&project, &zone, name));
+    let batch = Batch::new(&project, &zone, &Verb::DELETE);
+    let result = apply_all(
+        &compute,
+        "instance",
+        batch.verb,
+        &names,
+        requests,
+        batch.drive(session, false),
+    )
+    .await;
+    session.forget_instances(&project);
+    result
+}
+
+async fn start(session: &Session, args: LifecycleArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let names = select_for(session, &project, &zone, &args, &Verb::START).await?;
+    let compute = session.compute().await?;
+    let requests = names
+        .iter()
+        .map(|name| compute.start_instance(&project, &zone, name));
+    let batch = Batch::new(&project, &zone, &Verb::START);
+    let result = apply_all(
+        &compute,
+        "instance",
+        batch.verb,
+        &names,
+        requests,
+        batch.drive(session, args.no_wait),
+    )
+    .await;
+    session.forget_instances(&project);
+    result?;
+    rejoin_groups(&compute, &project, &zone, &names).await
+}
+
+async fn stop(session: &Session, args: StopArgs) -> Result<()> {
+    let StopArgs {
+        lifecycle: args,
+        drain,
+    } = args;
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let names = select_for(session, &project, &zone, &args, &Verb::STOP).await?;
+    let compute = session.compute().await?;
+    if let Some(seconds) = drain {
+        drain_instances(session, &compute, &project, &zone, &names, seconds).await?;
+    }
+    let requests = names
+        .iter()
+        .map(|name| compute.stop_instance(&project, &zone, name));
+    let batch = Batch {
+        drain,
+        ..Batch::new(&project, &zone, &Verb::STOP)
+    };
+    let result = apply_all(
+        &compute,
+        "instance",
+        batch.verb,
+        &names,
+        requests,
+        batch.drive(session, args.no_wait),
+    )
+    .await;
+    session.forget_instances(&project);
+    result
+}
+
+async fn reset(session: &Session, args: LifecycleArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let names = select_for(session, &project, &zone, &args, &Verb::RESET).await?;
+    let compute = session.compute().await?;
+    let requests = names
+        .iter()
+        .map(|name| compute.reset_instance(&project, &zone, name));
+    let batch = Batch::new(&project, &zone, &Verb::RESET);
+    apply_all(
+        &compute,
+        "instance",
+        batch.verb,
+        &names,
+        requests,
+        batch.drive(session, args.no_wait),
+    )
+    .await
+}
+
+async fn suspend(session: &Session, args: LifecycleArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let names = select_for(session, &project, &zone, &args, &Verb::SUSPEND).await?;
+    let compute = session.compute().await?;
+    let requests = names
+        .iter()
+        .map(|name| compute.suspend_instance(&project, &zone, name));
+    let batch = Batch::new(&project, &zone, &Verb::SUSPEND);
+    let result = apply_all(
+        &compute,
+        "instance",
+        batch.verb,
+        &names,
+        requests,
+        batch.drive(session, args.no_wait),
+    )
+    .await;
+    session.forget_instances(&project);
+    result
+}
+
+async fn resume(session: &Session, args: LifecycleArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let names = select_for(session, &project, &zone, &args, &Verb::RESUME).await?;
+    let compute = session.compute().await?;
+    let requests = names
+        .iter()
+        .map(|name| compute.resume_instance(&project, &zone, name));
+    let batch = Batch::new(&project, &zone, &Verb::RESUME);
+    let result = apply_all(
+        &compute,
+        "instance",
+        batch.verb,
+        &names,
+        requests,
+        batch.drive(session, args.no_wait),
+    )
+    .await;
+    session.forget_instances(&project);
+    result
+}
+
+/// Takes `names` out of the unmanaged instance groups they belong to,
+/// noting the groups for `start`, then gives the load balancers whose
+/// backends those groups are `seconds` to drain open connections. A dry
+/// run prints the removals and stops there.
+async fn drain_instances(
+    session: &Session,
+    compute: &Compute,
+    project: &str,
+    zone: &str,
+    names: &[String],
+    seconds: u64,
+) -> Result<()> {
+    let (groups, migs) = tokio::try_join!(
+        compute.list_instance_groups(project, zone),
+        compute.list_migs(project, zone, None),
+    )?;
+    let members = try_join_all(
+        groups
+            .iter()
+            .map(|group| compute.list_group_members(project, zone, &group.name)),
+    )
+    .await?;
+    let mut memberships: Vec<(String, Vec<String>)> = names
+        .iter()
+        .map(|name| (name.clone(), Vec::new()))
+        .collect();
+    for (group, members) in groups.iter().zip(members) {
+        for member in members {
+            let Some((name, member_of)) = memberships
+                .iter_mut()
+                .find(|(name, _)| name == member.instance_name())
+            else {
+                continue;
+            };
+            // a managed group would recreate the instance rather than let go
+            if migs.iter().any(|mig| mig.name == group.name) {
+                bail!(
+                    "instance {name} belongs to managed instance group {}; \
+                     resize the group instead of draining the instance",
+                    group.name
+                );
+            }
+            member_of.push(group.name.clone());
+        }
+    }
+    memberships.retain(|(name, member_of)| {
+        if member_of.is_empty() {
+            warning(&format!(
+                "instance {name} is in no instance group; nothing to drain"
+            ));
+        }
+        !member_of.is_empty()
+    });
+    if memberships.is_empty() {
+        return Ok(());
+    }
+
+    let services = compute.list_backend_services_all(project).await?;
+    let targets = drain::by_group(&memberships);
+    let serving: Vec<_> = services
+        .iter()
+        .filter(|s| targets.keys().any(|group| s.serves_group(zone, group)))
+        .collect();
+    let ops =
+        try_join_all(targets.iter().map(|(group, names)| {
+            compute.remove_instances_from_group(project, zone, group, names)
+        }))
+        .await?;
+    // nothing left its group, so there is nothing to note or wait for
+    if session.dry_run {
+        return Ok(());
+    }
+    let mut drained = Drained::load()?;
+    for (name, member_of) in &memberships {
+        drained.record(project, zone, name, member_of);
+    }
+    drained.save()?;
+    with_spinner(
+        "Leaving instance groups".to_string(),
+        try_join_all(ops.into_iter().map(|op| compute.wait_operation(op))),
+    )
+    .await?;
+    for (name, member_of) in &memberships {
+        success(&format!(
+            "Instance {name} left instance group(s) {}",
+            member_of.join(", ")
+        ));
+    }
+
+    if let Some(longest) = serving.iter().map(|s| s.draining_timeout()).max() {
+        let names: Vec<_> = serving.iter().map(|s| s.name.as_str()).collect();
+        if seconds < longest {
+            warning(&format!(
+                "backend service(s) {} drain for up to {longest}s, longer than --drain {seconds}",
+                names.join(", ")
+            ));
+        }
+    }
+    with_spinner(
+        format!("Draining connections for {seconds}s"),
+        tokio::time::sleep(Duration::from_secs(seconds)),
+    )
+    .await;
+    Ok(())
+}
+
+/// Puts `names` back into the instance groups `stop --drain` took them
+/// out of. Instances that were never drained cost no API calls.
+async fn rejoin_groups(
+    compute: &Compute,
+    project: &str,
+    zone: &str,
+    names: &[String],
+) -> Result<()> {
+    let mut drained = Drained::load()?;
+    let memberships = drained.take(project, zone, names);
+    if memberships.is_empty() {
+        return Ok(());
+    }
+    let ops = try_join_all(
+        drain::by_group(&memberships)
+            .iter()
+            .map(|(group, names)| compute.add_instances_to_group(project, zone, group, names)),
+    )
+    .await?;
+    with_spinner(
+        "Rejoining instance groups".to_string(),
+        try_join_all(ops.into_iter().map(|op| compute.wait_operation(op))),
+    )
+    .await?;
+    drained.save()?;
+    for (name, member_of) in &memberships {
+        success(&format!(
+            "Instance {name} rejoined instance group(s) {}",
+            member_of.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Instance names `selection` picks in `zone`, and whether picking them
+/// meant expanding globs, `--filter`, or `--all` against a fresh listing.
+/// Plain names are taken as given without calling the API.
+async fn select_instances(
+    session: &Session,
+    project: &str,
+    zone: &str,
+    selection: &SelectionArgs,
+) -> Result<(Vec<String>, bool)> {
+    let expand = selection.all
+        || selection.filter.is_some()
+        || selection.names.iter().any(|n| batch::is_glob(n));
+    if !expand {
+        return Ok((selection.names.clone(), false));
+    }
+    // not the cached listing: acting on instances that are gone helps no one
+    let compute = session.compute().await?;
+    let listed: Vec<String> = compute
+        .list_instances(project, zone, selection.filter.as_ref())
+        .await?
+        .into_iter()
+        .map(|i| i.name)
+        .collect();
+    let names = batch::select(&selection.names, &listed)?;
+    if names.is_empty() {
+        bail!("no instances in {zone} match the selection");
+    }
+    Ok((names, true))
+}
+
+/// Selects the instances of a start or stop, asking first when the set came
+/// from globs, `--filter`, or `--all` unless `--force` is given.
+async fn select_for(
+    session: &Session,
+    project: &str,
+    zone: &str,
+    args: &LifecycleArgs,
+    verb: &Verb,
+) -> Result<Vec<String>> {
+    let (names, expanded) = select_instances(session, project, zone, &args.selection).await?;
+    if expanded && !args.force {
+        let question = format!(
+            "{} {} instance(s) in {zone}: {}?",
+            capitalize(verb.base),
+            names.len(),
+            names.join(", ")
+        );
+        if !prompt::confirm(&question)? {
+            bail!("aborted");
+        }
+    }
+    Ok(names)
+}
+
+/// Where an instance batch runs, and the resume file it retries, if any.
+struct Batch<'a> {
+    project: &'a str,
+    zone: &'a str,
+    verb: &'a Verb,
+    resumed: Option<&'a Path>,
+    // `stop --drain` seconds, drained again on resuming
+    drain: Option<u64>,
+}
+
+impl<'a> Batch<'a> {
+    fn new(project: &'a str, zone: &'a str, verb: &'a Verb) -> Self {
+        Self {
+            project,
+            zone,
+            verb,
+            resumed: None,
+            drain: None,
+        }
+    }
+
+    /// How [`apply_all`] runs the batch: a result table, then its failures
+    /// remembered, except on a dry run whose placeholders all succeed and
+    /// would discard a resume file.
+    fn drive(&'a self, session: &Session, no_wait: bool) -> Drive<'a> {
+        let remember = move |failed| self.remember(failed);
+        Drive {
+            no_wait,
+            outcomes: Some(session.output),
+            as_batch: self.resumed.is_some(),
+            on_failed: match session.dry_run {
+                true => None,
+                false => Some(Box::new(remember)),
+            },
+        }
+    }
+
+    /// Saves the `failed` members for `gcectl resume`, over the file this
+    /// batch was resumed from if there is one, or removes that file once
+    /// nothing is left.
+    fn remember(&self, failed: Vec<String>) {
+        if failed.is_empty() {
+            if let Some(path) = self.resumed {
+                let _ = fs::remove_file(path);
+            }
+            return;
+        }
+        let count = failed.len();
+        let file = ResumeFile {
+            drain: self.drain,
+            ..ResumeFile::new(self.verb.base, self.project, self.zone, failed)
+        };
+        match file.save(self.resumed) {
+            Ok(path) => warning(&format!(
+                "Retry the {count} failed instance(s) with: gcectl resume {}",
+                path.display()
+            )),
+            Err(err) => warning(&format!("cannot save a resume file: {err:#}")),
+        }
+    }
+}
+
+/// Retries what a partially failed batch left in its resume file.
+pub async fn resume_batch(session: &Session, args: ResumeArgs) -> Result<()> {
+    let file = ResumeFile::read(&args.file)?;
+    let verb = match file.action.as_str() {
+        "start" => &Verb::START,
+        "stop" => &Verb::STOP,
+        "delete" => &Verb::DELETE,
+        "reset" => &Verb::RESET,
+        "suspend" => &Verb::SUSPEND,
+        "resume" => &Verb::RESUME,
+        action => bail!("{} has an unknown action '{action}'", args.file.display()),
+    };
+    let (project, zone) = (file.project.as_str(), file.zone.as_str());
+    let names = &file.pending;
+    if names.is_empty() {
+        if !session.dry_run {
+            let _ = fs::remove_file(&args.file);
+        }
+        success(&format!("Nothing left to {}", verb.base));
+        return Ok(());
+    }
+    match verb.base {
+        "delete" => confirm_delete(args.force, "instance", zone, names)?,
+        _ => {
+            let question = format!(
+                "{} {} instance(s) left by an earlier batch in {zone}: {}?",
+                capitalize(verb.base),
+                names.len(),
+                names.join(", ")
+            );
+            if !args.force && !prompt::confirm(&question)? {
+                bail!("aborted");
+            }
+        }
+    }
+    let compute = session.compute().await?;
+    // the instances that failed to stop may be back in their groups
+    if let Some(seconds) = file.drain.filter(|_| verb.base == "stop") {
+        drain_instances(session, &compute, project, zone, names, seconds).await?;
+    }
+    let requests = names.iter().map(|name| {
+        let compute = &compute;
+        async move {
+            match verb.base {
+                "start" => compute.start_instance(project, zone, name).await,
+                "stop" => compute.stop_instance(project, zone, name).await,
+                "delete" => compute.delete_instance(project, zone, name).await,
+                "reset" => compute.reset_instance(project, zone, name).await,
+                "suspend" => compute.suspend_instance(project, zone, name).await,
+                _ => compute.resume_instance(project, zone, name).await,
+            }
+        }
+    });
+    let batch = Batch {
+        resumed: Some(&args.file),
+        drain: file.drain,
+        ..Batch::new(project, zone, verb)
+    };
+    let result = apply_all(
+        &compute,
+        "instance",
+        verb,
+        names,
+        requests,
+        batch.drive(session, false),
+    )
+    .await;
+    session.forget_instances(project);
+    result
+}
+
+async fn add_metadata(session: &Session, args: AddMetadataArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let entries = metadata_entries(&args.entries)?;
+    let compute = session.compute().await?;
+    let op = compute
+        .update_instance_metadata(&project, &zone, &args.name, |metadata| {
+            for (key, value) in &entries {
+                metadata.set(key, value.clone());
+            }
+            Ok(())
+        })
+        .await?;
+    session.forget_instances(&project);
+    if args.no_wait {
+        requested("Metadata update", &op);
+        return Ok(());
+    }
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Updating metadata of instance {}", args.name),
+    )
+    .await?;
+    success(&format!(
+        "Set {} metadata entry(s) on instance {}",
+        entries.len(),
+        args.name
+    ));
+    Ok(())
+}
+
+async fn remove_metadata(session: &Session, args: RemoveMetadataArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let keys = &args.keys.keys;
+    let compute = session.compute().await?;
+    let op = compute
+        .update_instance_metadata(&project, &zone, &args.name, |metadata| {
+            remove_metadata_keys(metadata, keys)
+        })
+        .await?;
+    session.forget_instances(&project);
+    if args.no_wait {
+        requested("Metadata update", &op);
+        return Ok(());
+    }
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Updating metadata of instance {}", args.name),
+    )
+    .await?;
+    success(&format!(
+        "Removed {} from instance {}",
+        keys.join(", "),
+        args.name
+    ));
+    Ok(())
+}
+
+/// Adds or removes network tags under the tags fingerprint.
+async fn edit_tags(session: &Session, args: InstanceTagsArgs, add: bool) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let tags = &args.tags;
+    let compute = session.compute().await?;
+    let op = compute
+        .update_instance_tags(&project, &zone, &args.name, |current| match add {
+            true => current.add(tags),
+            false => current.remove(tags),
+        })
+        .await?;
+    session.forget_instances(&project);
+    if args.no_wait {
+        requested("Tags update", &op);
+        return Ok(());
+    }
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Updating network tags of instance {}", args.name),
+    )
+    .await?;
+    let (done, preposition) = match add {
+        true => ("Added", "to"),
+        false => ("Removed", "from"),
+    };
+    success(&format!(
+        "{done} network tag(s) {} {preposition} instance {}",
+        tags.join(", "),
+        args.name
+    ));
+    Ok(())
+}
+
+async fn add_labels(session: &Session, args: InstanceAddLabelsArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let edit = LabelEdit::set(args.labels.labels)?;
+    let compute = session.compute().await?;
+    let op = compute
+        .update_instance_labels(&project, &zone, &args.name, &edit)
+        .await?;
+    session.forget_instances(&project);
+    finish_label_edit(
+        &compute,
+        op,
+        "instance",
+        &args.name,
+        &edit,
+        args.labels.no_wait,
+    )
+    .await
+}
+
+async fn remove_labels(session: &Session, args: InstanceRemoveLabelsArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let edit = LabelEdit::remove(args.keys.keys);
+    let compute = session.compute().await?;
+    let op = compute
+        .update_instance_labels(&project, &zone, &args.name, &edit)
+        .await?;
+    session.forget_instances(&project);
+    finish_label_edit(
+        &compute,
+        op,
+        "instance",
+        &args.name,
+        &edit,
+        args.keys.no_wait,
+    )
+    .await
+}
+
+fn startup_script_keys(windows: bool) -> StartupScriptKeys {
+    match windows {
+        true => WINDOWS_STARTUP_SCRIPT,
+        false => LINUX_STARTUP_SCRIPT,
+    }
+}
+
+async fn set_startup_script(session: &Session, args: SetStartupScriptArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let script = match args.from_file.as_str() {
+        "-" => {
+            let mut script = String::new();
+            io::stdin()
+                .read_to_string(&mut script)
+                .context("failed to read the script from stdin")?;
+            script
+        }
+        path => fs::read_to_string(path).with_context(|| format!("failed to read {path}"))?,
+    };
+    let keys = startup_script_keys(args.windows);
+    let compute = session.compute().await?;
+    let op = compute
+        .update_instance_metadata(&project, &zone, &args.name, |metadata| {
+            metadata.set_startup_script(keys, script.clone());
+            Ok(())
+        })
+        .await?;
+    session.forget_instances(&project);
+    if args.no_wait {
+        requested("Metadata update", &op);
+        return Ok(());
+    }
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Updating the startup script of instance {}", args.name),
+    )
+    .await?;
+    success(&format!("Set {} on instance {}", keys.inline, args.name));
+    if !args.reset {
+        return Ok(());
+    }
+    let instance = compute.get_instance(&project, &zone, &args.name).await?;
+    if instance.status != "RUNNING" {
+        warning(&format!(
+            "Instance {} is {}; the script runs when it next starts",
+            args.name, instance.status
+        ));
+        return Ok(());
+    }
+    let op = compute.reset_instance(&project, &zone, &args.name).await?;
+    wait_with_spinner(&compute, op, format!("Resetting instance {}", args.name)).await?;
+    success(&format!(
+        "Reset instance {} to run its startup script",
+        args.name
+    ));
+    Ok(())
+}
+
+async fn get_startup_script(session: &Session, args: GetStartupScriptArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let keys = startup_script_keys(args.windows);
+    let compute = session.compute().await?;
+    let instance = session
+        .get_instance(&compute, &project, &zone, &args.name)
+        .await?;
+    let metadata = instance.metadata.unwrap_or_default();
+    match (metadata.get(keys.inline), metadata.get(keys.url)) {
+        (Some(script), _) => match script.ends_with('\n') {
+            true => print_text(script),
+            false => print_text(&format!("{script}\n")),
+        },
+        (None, Some(url)) => print_text(&format!("{}: {url}\n", keys.url)),
+        (None, None) => bail!("instance {} has no {}", args.name, keys.inline),
+    }
+    Ok(())
+}
+
+/// Runs the `windows-keys` handshake: publishes a one-time public key, then
+/// waits for the guest agent to answer on serial port 4 with the new
+/// password encrypted to it.
+async fn reset_windows_password(session: &Session, args: ResetWindowsPasswordArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let user = match args.user {
+        Some(user) => user,
+        None => ssh::default_user()?,
+    };
+    windows::validate_user(&user)?;
+    if !args.force {
+        let question = format!(
+            "Reset the password of Windows user {user} on instance {}?",
+            args.name
+        );
+        if !prompt::confirm(&question)? {
+            bail!("aborted");
+        }
+    }
+    let email = session.account_email().await?;
+    let key = with_spinner(
+        "Generating a key".to_string(),
+        tokio::task::spawn_blocking(move || WindowsKey::generate(&user, &email)),
+    )
+    .await??;
+
+    let compute = session.compute().await?;
+    // answers to earlier requests are already on the port; skip past them
+    let mut offset = compute
+        .get_serial_port_output(&project, &zone, &args.name, windows::AGENT_PORT, 0)
+        .await?
+        .next_offset();
+    let op = compute
+        .update_instance_metadata(&project, &zone, &args.name, |metadata| {
+            let keys = key.append_to(metadata.get(WINDOWS_KEYS), Utc::now());
+            metadata.set(WINDOWS_KEYS, keys);
+            Ok(())
+        })
+        .await?;
+    // no key was sent, so no guest agent will answer
+    if session.dry_run {
+        return Ok(());
+    }
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Sending the key to instance {}", args.name),
+    )
+    .await?;
+
+    let poll = async {
+        for _ in 0..WINDOWS_AGENT_POLLS {
+            let output = compute
+                .get_serial_port_output(&project, &zone, &args.name, windows::AGENT_PORT, offset)
+                .await?;
+            offset = output.next_offset();
+            if let Some(password) = key.find_password(&output.contents)? {
+                return Ok(password);
+            }
+            tokio::time::sleep(WINDOWS_AGENT_INTERVAL).await;
+        }
+        bail!(
+            "the Windows guest agent on {} did not answer; is it a running Windows instance?",
+            args.name
+        )
+    };
+    let password = with_spinner(
+        format!("Waiting for the guest agent on {}", args.name),
+        poll,
+    )
+    .await?;
+    let instance = compute.get_instance(&project, &zone, &args.name).await?;
+    let credentials = WindowsCredentials {
+        username: key.user().to_string(),
+        password,
+        ip_address: instance
+            .external_ip()
+            .or(instance.internal_ip())
+            .map(str::to_string),
+    };
+    print_one(session.output, &credentials)
+}
+
+async fn diff(session: &Session, args: InstanceDiffArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let second_zone = args.second_zone.as_deref().unwrap_or(&zone);
+    let compute = session.compute().await?;
+    let (first, second) = tokio::try_join!(
+        compute.get_instance(&project, &zone, &args.first),
+        compute.get_instance(&project, second_zone, &args.second),
+    )?;
+    let differences = diff::compare(&diff::normalize(&first), &diff::normalize(&second));
+    if session.output != OutputFormat::Table || output::quiet() {
+        return print_list(session.output, &differences);
+    }
+    if differences.is_empty() {
+        success(&format!(
+            "Instances {} and {} are configured the same",
+            args.first, args.second
+        ));
+        return Ok(());
+    }
+    print_text(&format!(
+        "{}\n",
+        diff::render(&args.first, &args.second, &differences)
+    ));
+    Ok(())
+}
+
+/// Stops the instance if it is running, changes its machine type, and starts
+/// it again unless `--no-restart` is given.
+async fn set_machine_type(session: &Session, args: SetMachineTypeArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let instance = compute.get_instance(&project, &zone, &args.name).await?;
+    if !args.skip_validation {
+        // GPUs already attached have to suit the new type too
+        let accelerators: Vec<Accelerator> = instance
+            .guest_accelerators
+            .iter()
+            .map(|a| Accelerator {
+                accelerator_type: short_name(&a.accelerator_type).to_string(),
+                count: a.accelerator_count,
+            })
+            .collect();
+        let machine_types = session.machine_types(&compute, &project, &zone).await;
+        let request = validate::Request {
+            zone: &zone,
+            machine_type: &args.machine_type,
+            accelerators: &accelerators,
+            ..Default::default()
+        };
+        let catalogs = validate::Catalogs {
+            machine_types: readable(&machine_types),
+            images: None,
+        };
+        report_invalid(validate::check(&request, catalogs))?;
+    }
+    let current = instance.machine_type_name();
+    if current == args.machine_type {
+        success(&format!(
+            "Instance {} is already {}",
+            args.name, args.machine_type
+        ));
+        return Ok(());
+    }
+    let change = format!("change {current} to {}", args.machine_type);
+    while_stopped(
+        session,
+        &compute,
+        &project,
+        &instance,
+        &change,
+        &args.restart,
+        || compute.set_machine_type(&project, &zone, &args.name, &args.machine_type),
+    )
+    .await?;
+    success(&format!(
+        "Instance {} is now {}",
+        args.name, args.machine_type
+    ));
+    Ok(())
+}
+
+async fn set_service_account(session: &Session, args: SetServiceAccountArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let scopes = match args.scopes.is_empty() {
+        true => vec![DEFAULT_SCOPE.to_string()],
+        false => args.scopes.clone(),
+    };
+    let compute = session.compute().await?;
+    let instance = compute.get_instance(&project, &zone, &args.name).await?;
+    if let [current] = instance.service_accounts.as_slice()
+        && current.email == args.service_account
+        && current.scopes == scopes
+    {
+        success(&format!(
+            "Instance {} already runs as {}",
+            args.name, args.service_account
+        ));
+        return Ok(());
+    }
+    let change = format!("run it as {}", args.service_account);
+    while_stopped(
+        session,
+        &compute,
+        &project,
+        &instance,
+        &change,
+        &args.restart,
+        || compute.set_service_account(&project, &zone, &args.name, &args.service_account, &scopes),
+    )
+    .await?;
+    success(&format!(
+        "Instance {} now runs as {}",
+        args.name, args.service_account
+    ));
+    Ok(())
+}
+
+async fn set_scheduling(session: &Session, args: SetSchedulingArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let instance = compute.get_instance(&project, &zone, &args.name).await?;
+    let change = args.change();
+    if change.maintenance_policy == Some(MaintenancePolicy::Migrate)
+        && !instance.guest_accelerators.is_empty()
+    {
+        bail!(
+            "instance {} has GPUs, which cannot live-migrate; use TERMINATE",
+            args.name
+        );
+    }
+    let current = instance.scheduling.clone().unwrap_or_default();
+    let scheduling = current.with(&change)?;
+    if scheduling == current {
+        success(&format!(
+            "Instance {} already runs {}",
+            args.name,
+            scheduling.summary()
+        ));
+        return Ok(());
+    }
+    let apply = || compute.set_scheduling(&project, &zone, &args.name, &scheduling);
+    if current.needs_stop(&scheduling) {
+        let change = format!("switch it to {}", scheduling.summary());
+        while_stopped(
+            session,
+            &compute,
+            &project,
+            &instance,
+            &change,
+            &args.restart,
+            apply,
+        )
+        .await?;
+    } else {
+        let op = apply().await?;
+        session.forget_instances(&project);
+        wait_with_spinner(&compute, op, format!("Updating instance {}", args.name)).await?;
+    }
+    success(&format!(
+        "Instance {} now runs {}",
+        args.name,
+        scheduling.summary()
+    ));
+    Ok(())
+}
+
+/// Applies `change`, which the API only accepts on a stopped instance. A
+/// running instance is stopped first, after confirmation unless `--force`,
+/// and started again afterwards unless `--no-restart`.
+async fn while_stopped<F, Fut>(
+    session: &Session,
+    compute: &Compute,
+    project: &str,
+    instance: &Instance,
+    change: &str,
+    restart: &RestartArgs,
+    apply: F,
+) -> Result<()>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Operation>>,
+{
+    let (name, zone) = (instance.name.as_str(), instance.zone_name());
+    let running = match instance.status.as_str() {
+        "RUNNING" => true,
+        "TERMINATED" => false,
+        status => bail!("instance {name} is {status}; wait until it is RUNNING or TERMINATED"),
+    };
+
+    if running {
+        let question = format!("Instance {name} is running; stop it to {change}?");
+        if !restart.force && !prompt::confirm(&question)? {
+            bail!("aborted");
+        }
+        let op = compute.stop_instance(project, zone, name).await?;
+        session.forget_instances(project);
+        wait_with_spinner(compute, op, format!("Stopping instance {name}")).await?;
+    }
+    let changed = async {
+        let op = apply().await?;
+        session.forget_instances(project);
+        wait_with_spinner(compute, op, format!("Updating instance {name}")).await
+    }
+    .await;
+    if running {
+        changed.with_context(|| format!("instance {name} was left stopped"))?;
+    } else {
+        changed?;
+    }
+
+    if running && !restart.no_restart {
+        let op = compute.start_instance(project, zone, name).await?;
+        wait_with_spinner(compute, op, format!("Starting instance {name}")).await?;
+    }
+    Ok(())
+}
+
+/// Stops the instance, snapshots its disks, and recreates it in
+/// `--destination-zone` after confirming the printed plan. A failed create
+/// hands the static IPs back to the stopped source.
+async fn move_instance(session: &Session, args: MoveArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let to = &args.destination_zone;
+    let compute = session.compute().await?;
+    let source = compute.get_instance(&project, &zone, &args.name).await?;
+    let attached = source.disks.iter().filter_map(|d| d.source.as_deref());
+    let disks: Vec<Disk> = try_join_all(
+        attached
+            .filter(|url| url.contains("/zones/"))
+            .map(|url| compute.get_disk(&project, &zone, short_name(url))),
+    )
+    .await?;
+    let reserved = compute
+        .list_addresses(&project, Some(region_of(&zone)), None)
+        .await?;
+    let stamp = Utc::now().format("%Y%m%d%H%M%S").to_string();
+    let plan = relocate::plan(&source, &reserved, to, args.delete_source, &stamp)?;
+    let body = relocate::target_body(&source, &disks, &plan, &project)?;
+    print_note(&plan.to_string());
+    if !args.force && !prompt::confirm(&format!("Move instance {} to {to}?", args.name))? {
+        bail!("aborted");
+    }
+
+    let name = args.name.as_str();
+    if plan.stop {
+        let op = compute.stop_instance(&project, &zone, name).await?;
+        session.forget_instances(&project);
+        wait_with_spinner(&compute, op, format!("Stopping instance {name}")).await?;
+    }
+    let snapshots = plan.snapshots.iter().map(|s| async {
+        let body = json!({ "name": s.snapshot });
+        let op = compute
+            .create_snapshot(&project, &zone, &s.disk, &body)
+            .await?;
+        compute.wait_operation(op).await
+    });
+    with_spinner(
+        format!("Snapshotting {} disk(s)", plan.snapshots.len()),
+        try_join_all(snapshots),
+    )
+    .await
+    .with_context(|| format!("instance {name} was left stopped in {zone}"))?;
+    for moved in &plan.addresses {
+        let op = compute
+            .delete_access_config(&project, &zone, name, &moved.interface, &moved.config)
+            .await?;
+        wait_with_spinner(&compute, op, format!("Releasing {}", moved.ip)).await?;
+    }
+
+    let created = async {
+        let op = compute.insert_instance(&project, to, &body).await?;
+        wait_with_spinner(&compute, op, format!("Creating instance {name} in {to}")).await
+    }
+    .await;
+    session.forget_instances(&project);
+    if let Err(err) = created {
+        for moved in &plan.addresses {
+            let config =
+                json!({ "name": moved.config, "natIP": moved.ip, "type": "ONE_TO_ONE_NAT" });
+            let restored = match compute
+                .add_access_config(&project, &zone, name, &moved.interface, &config)
+                .await
+            {
+                Ok(op) => compute.wait_operation(op).await.map(drop),
+                Err(err) => Err(err),
+            };
+            if let Err(err) = restored {
+                warning(&format!(
+                    "could not give {} back to {name}: {err:#}",
+                    moved.ip
+                ));
+            }
+        }
+        return Err(err.context(format!(
+            "instance {name} was left stopped in {zone}; its snapshots are kept"
+        )));
+    }
+    if args.delete_source {
+        let op = compute.delete_instance(&project, &zone, name).await?;
+        wait_with_spinner(&compute, op, format!("Deleting instance {name} in {zone}")).await?;
+    }
+    success(&format!("Instance {name} moved to {to}"));
+    Ok(())
+}
+
+/// Replaces the interface's access config with one using the reserved
+/// address, keeping the config's name so scripts keyed on it still work.
+async fn assign_ip(session: &Session, args: AssignIpArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let address = compute
+        .get_address(&project, Some(region_of(&zone)), &args.address)
+        .await?;
+    if address.is_internal() {
+        bail!("{} is an internal address", args.address);
+    }
+    let instance = compute.get_instance(&project, &zone, &args.name).await?;
+    if let Some(user) = address
+        .users
+        .iter()
+        .find(|user| !user.ends_with(&format!("/instances/{}", args.name)))
+    {
+        bail!("{} is already in use by {user}", args.address);
+    }
+    let Some(nic) = instance
+        .network_interfaces
+        .iter()
+        .find(|nic| nic.name.as_deref() == Some(args.network_interface.as_str()))
+    else {
+        bail!(
+            "instance {} has no network interface {}",
+            args.name,
+            args.network_interface
+        );
+    };
+    if nic
+        .access_configs
+        .iter()
+        .any(|ac| ac.nat_ip.as_deref() == Some(address.address.as_str()))
+    {
+        success(&format!(
+            "Instance {} already uses {} ({})",
+            args.name, args.address, address.address
+        ));
+        return Ok(());
+    }
+
+    let old = nic.access_configs.first();
+    let config_name = old
+        .and_then(|ac| ac.name.clone())
+        .unwrap_or_else(|| "External NAT".to_string());
+    // the address's own tier, which the config has to match, else the old one's
+    let tier = address.network_tier.clone().or_else(|| {
+        old.and_then(|ac| ac.extra.get("networkTier")?.as_str())
+            .map(str::to_string)
+    });
+    let mut removed = Vec::new();
+    for config in &nic.access_configs {
+        let Some(name) = &config.name else { continue };
+        let op = compute
+            .delete_access_config(&project, &zone, &args.name, &args.network_interface, name)
+            .await?;
+        wait_with_spinner(&compute, op, format!("Removing access config {name}")).await?;
+        removed.push(config);
+    }
+    session.forget_instances(&project);
+    let mut config = AccessConfig {
+        name: Some(config_name),
+        nat_ip: Some(address.address.clone()),
+        ..Default::default()
+    };
+    if let Some(tier) = tier {
+        config.extra.insert("networkTier".to_string(), json!(tier));
+    }
+    let mut body = json!(config);
+    body["type"] = json!("ONE_TO_ONE_NAT");
+    let message = format!("Assigning {} to instance {}", address.address, args.name);
+    let assigned = async {
+        let op = compute
+            .add_access_config(&project, &zone, &args.name, &args.network_interface, &body)
+            .await?;
+        wait_with_spinner(&compute, op, message).await
+    }
+    .await;
+    if let Err(err) = assigned {
+        restore_access_configs(&compute, (&project, &zone), &args, &removed).await;
+        return Err(err);
+    }
+    success(&format!(
+        "Instance {} now uses {} ({})",
+        args.name, args.address, address.address
+    ));
+    Ok(())
+}
+
+/// Puts back the access configs `assign_ip` removed before failing to add
+/// the new one, so the instance keeps an external IP, and says how to
+/// recover when that fails too.
+async fn restore_access_configs(
+    compute: &Compute,
+    (project, zone): (&str, &str),
+    args: &AssignIpArgs,
+    removed: &[&AccessConfig],
+) {
+    for config in removed {
+        let name = config.name.as_deref().unwrap_or_default();
+        let restored = async {
+            let op = compute
+                .add_access_config(
+                    project,
+                    zone,
+                    &args.name,
+                    &args.network_interface,
+                    &json!(config),
+                )
+                .await?;
+            wait_with_spinner(compute, op, format!("Restoring access config {name}")).await
+        }
+        .await;
+        if let Err(err) = restored {
+            warning(&format!(
+                "instance {} was left without access config {name} ({}): {err:#}; \
+                 rerun `gcectl instances assign-ip {} --address {}` once the problem is fixed",
+                args.name,
+                config.nat_ip.as_deref().unwrap_or("ephemeral"),
+                args.name,
+                args.address,
+            ));
+        }
+    }
+}
+
+async fn idle(session: &Session, args: IdleArgs) -> Result<()> {
+    let project = session.project(args.zonal.project.as_deref())?;
+    let zone = session.list_zone(&args.zonal, args.all_zones);
+    let compute = session.compute().await?;
+    let monitoring = session.monitoring().await?;
+    let mut instances = compute
+        .list_instances_in(&project, zone.as_deref(), None)
+        .await?;
+    instances.retain(|i| i.status == "RUNNING");
+
+    let (cpu, received, sent) = tokio::try_join!(
+        monitoring.mean_per_instance(&project, CPU_UTILIZATION, args.window, "ALIGN_MEAN"),
+        monitoring.mean_per_instance(&project, NETWORK_RECEIVED, args.window, "ALIGN_RATE"),
+        monitoring.mean_per_instance(&project, NETWORK_SENT, args.window, "ALIGN_RATE"),
+    )?;
+    let thresholds = Thresholds {
+        cpu: args.cpu_threshold / 100.0,
+        network: args.network_threshold * 1024.0,
+    };
+    let idle: Vec<Utilization> = instances
+        .iter()
+        .map(|instance| {
+            let id = instance.id.as_deref().unwrap_or_default();
+            let network = match (received.get(id), sent.get(id)) {
+                (None, None) => None,
+                (r, s) => Some(r.unwrap_or(&0.0) + s.unwrap_or(&0.0)),
+            };
+            Utilization {
+                name: instance.name.clone(),
+                zone: instance.zone_name().to_string(),
+                machine_type: instance.machine_type_name().to_string(),
+                cpu: cpu.get(id).copied(),
+                network_bytes_per_sec: network,
+            }
+        })
+        .filter(|u| u.is_idle(thresholds))
+        .collect();
+    print_list(session.output, &idle)?;
+
+    if !args.stop || idle.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<String> = idle.iter().map(|u| u.name.clone()).collect();
+    let question = format!(
+        "Stop {} idle instance(s): {}?",
+        names.len(),
+        names.join(", ")
+    );
+    if !args.force && !prompt::confirm(&question)? {
+        bail!("aborted");
+    }
+    session.forget_instances(&project);
+    let requests = idle
+        .iter()
+        .map(|u| compute.stop_instance(&project, &u.zone, &u.name));
+    apply_all(
+        &compute,
+        "instance",
+        &Verb::STOP,
+        &names,
+        requests,
+        Drive::default(),
+    )
+    .await
+}
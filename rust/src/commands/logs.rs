@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use gcectl_core::cloud_logging::{self, LogEntry, Order};
+use gcectl_core::events::{self, Event};
+use gcectl_core::output::OutputFormat;
+
+use super::Session;
+use crate::cli::LogsArgs;
+
+// entries read per poll with --follow
+const FOLLOW_BATCH: usize = 1000;
+
+/// Prints the newest `--limit` entries of the instance, then with
+/// `--follow` polls for entries at or after the last one printed.
+pub async fn run(session: &Session, args: LogsArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let instance = compute.get_instance(&project, &zone, &args.name).await?;
+    let id = instance
+        .id
+        .with_context(|| format!("instance {} has no ID", args.name))?;
+    let logging = session.cloud_logging().await?;
+    let filter = args.filter.as_deref();
+
+    let since = Utc::now() - chrono::Duration::from_std(args.since)?;
+    let entries = logging
+        .entries(
+            &project,
+            &cloud_logging::instance_filter(&id, since, filter),
+            Order::Newest,
+            args.limit,
+        )
+        .await?;
+    let mut cursor = Cursor::new(since);
+    for entry in &entries {
+        cursor.advance(entry);
+        print_entry(session.output, entry)?;
+    }
+
+    if !args.follow {
+        return Ok(());
+    }
+    loop {
+        tokio::time::sleep(Duration::from_secs(args.interval)).await;
+        let filter = cloud_logging::instance_filter(&id, cursor.at, filter);
+        let entries = logging
+            .entries(&project, &filter, Order::Oldest, FOLLOW_BATCH)
+            .await?;
+        for entry in &entries {
+            if cursor.is_new(entry) {
+                cursor.advance(entry);
+                print_entry(session.output, entry)?;
+            }
+        }
+    }
+}
+
+/// The timestamp polling resumes from, and the entries already printed
+/// with exactly that timestamp, which the next poll returns again.
+struct Cursor {
+    at: DateTime<Utc>,
+    printed: HashSet<String>,
+}
+
+impl Cursor {
+    fn new(at: DateTime<Utc>) -> Self {
+        Self {
+            at,
+            printed: HashSet::new(),
+        }
+    }
+
+    fn is_new(&self, entry: &LogEntry) -> bool {
+        match (timestamp(entry), &entry.insert_id) {
+            (Some(at), Some(id)) if at == self.at => !self.printed.contains(id),
+            (Some(at), _) => at >= self.at,
+            (None, _) => true,
+        }
+    }
+
+    fn advance(&mut self, entry: &LogEntry) {
+        let Some(at) = timestamp(entry) else {
+            return;
+        };
+        if at > self.at {
+            self.at = at;
+            self.printed.clear();
+        }
+        if at == self.at
+            && let Some(id) = &entry.insert_id
+        {
+            self.printed.insert(id.clone());
+        }
+    }
+}
+
+fn timestamp(entry: &LogEntry) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&entry.timestamp)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+// one line per entry: text for tables, the whole entry for JSON and YAML
+fn print_entry(format: OutputFormat, entry: &LogEntry) -> Result<()> {
+    if events::enabled() {
+        let data = serde_json::to_value(entry)?;
+        events::emit(&Event::Output { data });
+        return Ok(());
+    }
+    let mut stdout = io::stdout().lock();
+    match format {
+        OutputFormat::Json | OutputFormat::Yaml => {
+            writeln!(stdout, "{}", serde_json::to_string(entry)?)?
+        }
+        OutputFormat::Table | OutputFormat::Csv => writeln!(
+            stdout,
+            "{} {:<8} {}: {}",
+            entry.timestamp,
+            entry.severity.as_deref().unwrap_or("DEFAULT"),
+            entry.log(),
+            entry.message()
+        )?,
+    }
+    stdout.flush()?;
+    Ok(())
+}
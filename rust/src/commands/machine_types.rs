@@ -0,0 +1,44 @@
+use anyhow::Result;
+use gcectl_core::output::{print_list, print_one};
+use gcectl_core::resources::machine_type;
+
+use super::Session;
+use crate::cli::{MachineTypeDescribeArgs, MachineTypeListArgs, MachineTypesCommand};
+
+pub async fn run(session: &Session, cmd: MachineTypesCommand) -> Result<()> {
+    match cmd {
+        MachineTypesCommand::List(args) => list(session, args).await,
+        MachineTypesCommand::Describe(args) => describe(session, args).await,
+    }
+}
+
+async fn list(session: &Session, args: MachineTypeListArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let listed = match &args.filter {
+        Some(filter) => {
+            compute
+                .list_machine_types(&project, &zone, Some(filter))
+                .await?
+        }
+        None => session.machine_types(&compute, &project, &zone).await?,
+    };
+    let mut types: Vec<_> = listed
+        .into_iter()
+        .filter(|t| args.min_cpus.is_none_or(|min| t.guest_cpus >= min))
+        .filter(|t| args.min_memory.is_none_or(|min| t.memory_gb() >= min))
+        .map(|t| t.with_price())
+        .collect();
+    machine_type::sort(&mut types, args.sort_by);
+    print_list(session.output, &types)
+}
+
+async fn describe(session: &Session, args: MachineTypeDescribeArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let machine_type = compute
+        .get_machine_type(&project, &zone, &args.name)
+        .await?
+        .with_price();
+    print_one(session.output, &machine_type)
+}
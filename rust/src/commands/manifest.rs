@@ -0,0 +1,221 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, Read};
+
+use anyhow::{Context, Result, bail};
+use gcectl_core::compute::Compute;
+use gcectl_core::manifest::{self, Change, InstanceSpec, Manifest, PowerState, Update};
+use gcectl_core::output::{print_note, print_text};
+use gcectl_core::resources::{Disk, short_name};
+use gcectl_core::terraform;
+
+use super::{Session, batch_failed, failure, success, wait_with_spinner};
+use crate::cli::{ApplyArgs, ExportCommand, ListArgs, TerraformArgs};
+use crate::prompt;
+
+pub async fn export(session: &Session, cmd: ExportCommand) -> Result<()> {
+    match cmd {
+        ExportCommand::Instances(args) => export_instances(session, args).await,
+        ExportCommand::Terraform(args) => export_terraform(session, args).await,
+    }
+}
+
+async fn export_terraform(session: &Session, args: TerraformArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let instance = compute.get_instance(&project, &zone, &args.name).await?;
+    // disks keep the image, size, and type the boot disk was made with
+    let urls: Vec<&str> = instance
+        .disks
+        .iter()
+        .filter_map(|d| d.source.as_deref())
+        .collect();
+    let mut disks = Vec::new();
+    for url in &urls {
+        disks.push(compute.get_disk(&project, &zone, short_name(url)).await?);
+    }
+    let disks: BTreeMap<&str, &Disk> = urls.iter().copied().zip(&disks).collect();
+    let resource = args
+        .resource_name
+        .unwrap_or_else(|| terraform::resource_name(&args.name));
+    print_text(&terraform::instance_block(
+        &instance, &disks, &project, &resource,
+    ));
+    Ok(())
+}
+
+async fn export_instances(session: &Session, args: ListArgs) -> Result<()> {
+    let project = session.project(args.zonal.project.as_deref())?;
+    let zone = session.list_zone(&args.zonal, args.all_zones);
+    let compute = session.compute().await?;
+    let instances = compute
+        .list_instances_in(&project, zone.as_deref(), args.filter.as_ref())
+        .await?;
+    // boot disks carry the image, size, and type the instances were made with
+    let disks = compute.list_disks_all_zones(&project, None).await?;
+    let disks: HashMap<&str, &Disk> = disks
+        .iter()
+        .filter_map(|d| Some((d.self_link.as_deref()?, d)))
+        .collect();
+    let manifest = Manifest {
+        project: Some(project),
+        instances: instances
+            .iter()
+            .map(|instance| InstanceSpec::from_instance(instance, &disks))
+            .collect(),
+    };
+    print_text(&serde_yaml::to_string(&manifest)?);
+    Ok(())
+}
+
+pub async fn apply(session: &Session, args: ApplyArgs) -> Result<()> {
+    let manifest = Manifest::parse(&read_manifest(&args)?)?;
+    let flag = args.project.project.as_deref();
+    let project = session.project(flag.or(manifest.project.as_deref()))?;
+    let compute = session.compute().await?;
+    let actual = compute.list_instances_all_zones(&project, None).await?;
+    let changes = manifest::plan(&manifest, &actual, args.prune);
+    if changes.is_empty() {
+        success(&format!("Instances in {project} match the manifest"));
+        return Ok(());
+    }
+    for change in &changes {
+        print_note(&change.to_string());
+    }
+    print_note(&manifest::summary(&changes));
+    if args.plan {
+        return Ok(());
+    }
+    if !args.force && !prompt::confirm(&format!("Apply these changes to {project}?"))? {
+        bail!("aborted");
+    }
+
+    let mut failed = 0;
+    for change in &changes {
+        let result = apply_change(&compute, &project, change).await;
+        session.forget_instances(&project);
+        if let Err(err) = result {
+            failed += 1;
+            failure(&format!("{}: {err:#}", target(change)));
+        }
+    }
+    if failed > 0 {
+        return Err(batch_failed("apply", "change", failed, changes.len()));
+    }
+    Ok(())
+}
+
+fn read_manifest(args: &ApplyArgs) -> Result<String> {
+    if args.file.as_os_str() == "-" {
+        let mut yaml = String::new();
+        io::stdin()
+            .read_to_string(&mut yaml)
+            .context("failed to read the manifest from stdin")?;
+        return Ok(yaml);
+    }
+    fs::read_to_string(&args.file)
+        .with_context(|| format!("failed to read {}", args.file.display()))
+}
+
+fn target(change: &Change) -> String {
+    match change {
+        Change::Create(spec) => format!("Instance {}", spec.name),
+        Change::Update { name, .. } | Change::Delete { name, .. } => format!("Instance {name}"),
+    }
+}
+
+async fn apply_change(compute: &Compute, project: &str, change: &Change) -> Result<()> {
+    match change {
+        Change::Create(spec) => {
+            let body = spec.builder()?.build();
+            let op = compute.insert_instance(project, &spec.zone, &body).await?;
+            wait_with_spinner(compute, op, format!("Creating instance {}", spec.name)).await?;
+            if spec.status == Some(PowerState::Terminated) {
+                let op = compute
+                    .stop_instance(project, &spec.zone, &spec.name)
+                    .await?;
+                wait_with_spinner(compute, op, format!("Stopping instance {}", spec.name)).await?;
+            }
+            success(&format!("Instance {} created", spec.name));
+        }
+        Change::Update {
+            name,
+            zone,
+            status,
+            updates,
+        } => {
+            update(compute, project, zone, name, status, updates).await?;
+            success(&format!("Instance {name} updated"));
+        }
+        Change::Delete { name, zone } => {
+            let op = compute.delete_instance(project, zone, name).await?;
+            wait_with_spinner(compute, op, format!("Deleting instance {name}")).await?;
+            success(&format!("Instance {name} deleted"));
+        }
+    }
+    Ok(())
+}
+
+/// Applies `updates` to an instance that was `status` when planned. A
+/// machine type change stops a running instance, which is started again
+/// unless the manifest asks for it to be stopped.
+async fn update(
+    compute: &Compute,
+    project: &str,
+    zone: &str,
+    name: &str,
+    status: &str,
+    updates: &[Update],
+) -> Result<()> {
+    let mut current = status.to_string();
+    let mut desired = status.to_string();
+    for update in updates {
+        match update {
+            Update::Labels(edit) => {
+                let op = compute
+                    .update_instance_labels(project, zone, name, edit)
+                    .await?;
+                wait_with_spinner(compute, op, format!("Updating labels of instance {name}"))
+                    .await?;
+            }
+            Update::Metadata(edit) => {
+                let op = compute
+                    .update_instance_metadata(project, zone, name, |metadata| {
+                        for key in &edit.remove {
+                            metadata.remove(key);
+                        }
+                        for (key, value) in &edit.set {
+                            metadata.set(key, value.clone());
+                        }
+                        Ok(())
+                    })
+                    .await?;
+                wait_with_spinner(compute, op, format!("Updating metadata of instance {name}"))
+                    .await?;
+            }
+            Update::MachineType { to, .. } => {
+                if current == "RUNNING" {
+                    let op = compute.stop_instance(project, zone, name).await?;
+                    wait_with_spinner(compute, op, format!("Stopping instance {name}")).await?;
+                    current = "TERMINATED".to_string();
+                }
+                let op = compute.set_machine_type(project, zone, name, to).await?;
+                wait_with_spinner(compute, op, format!("Updating instance {name}")).await?;
+            }
+            Update::Power { to, .. } => desired = to.api_name().to_string(),
+        }
+    }
+    if current == desired {
+        return Ok(());
+    }
+    let op = match desired.as_str() {
+        "RUNNING" => compute.start_instance(project, zone, name).await?,
+        _ => compute.stop_instance(project, zone, name).await?,
+    };
+    let verb = match desired.as_str() {
+        "RUNNING" => "Starting",
+        _ => "Stopping",
+    };
+    wait_with_spinner(compute, op, format!("{verb} instance {name}")).await?;
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use gcectl_core::compute::Compute;
+use gcectl_core::cost;
+use gcectl_core::filter::Filter;
+use gcectl_core::metrics::{self, Sample};
+use gcectl_core::output::print_note;
+use gcectl_core::resources::Disk;
+use tokio::net::TcpListener;
+use tokio::sync::RwLock;
+use tracing::warn;
+
+use super::Session;
+use super::cost::usages;
+use crate::cli::{MetricsCommand, MetricsServeArgs};
+
+pub async fn run(session: &Session, cmd: MetricsCommand) -> Result<()> {
+    match cmd {
+        MetricsCommand::Serve(args) => serve(session, args).await,
+    }
+}
+
+async fn serve(session: &Session, args: MetricsServeArgs) -> Result<()> {
+    let project = session.project(args.list.zonal.project.as_deref())?;
+    let zone = session.list_zone(&args.list.zonal, args.list.all_zones);
+    let compute = session.compute().await?;
+    let listener = TcpListener::bind((args.address.as_str(), args.port))
+        .await
+        .with_context(|| format!("failed to listen on {}:{}", args.address, args.port))?;
+    let addr = listener.local_addr()?;
+
+    // fail fast on bad credentials or filters instead of serving nothing
+    let first = collect(
+        &compute,
+        &project,
+        zone.as_deref(),
+        args.list.filter.as_ref(),
+        &args.labels,
+    )
+    .await?;
+    let body = Arc::new(RwLock::new(first));
+    print_note(&format!("Serving metrics on http://{addr}/metrics"));
+    let mut server = tokio::spawn(metrics::serve(listener, body.clone()));
+
+    let mut ticks = tokio::time::interval(Duration::from_secs(args.interval));
+    ticks.tick().await;
+    loop {
+        tokio::select! {
+            result = &mut server => return result?,
+            _ = ticks.tick() => {}
+        }
+        // the last good exposition keeps being served through API hiccups
+        match collect(
+            &compute,
+            &project,
+            zone.as_deref(),
+            args.list.filter.as_ref(),
+            &args.labels,
+        )
+        .await
+        {
+            Ok(text) => *body.write().await = text,
+            Err(err) => warn!("failed to refresh metrics: {err:#}"),
+        }
+    }
+}
+
+/// Lists the fleet and renders it as a Prometheus exposition.
+async fn collect(
+    compute: &Compute,
+    project: &str,
+    zone: Option<&str>,
+    filter: Option<&Filter>,
+    label_keys: &[String],
+) -> Result<String> {
+    let instances = compute.list_instances_in(project, zone, filter).await?;
+    let disks = compute.list_disks_all_zones(project, None).await?;
+    let disks: HashMap<&str, &Disk> = disks
+        .iter()
+        .filter_map(|d| Some((d.self_link.as_deref()?, d)))
+        .collect();
+    let costs: Vec<f64> = usages(&instances, &disks)
+        .iter()
+        .map(|usage| cost::estimate(usage).total())
+        .collect();
+    Ok(metrics::render(&Sample {
+        project,
+        instances: &instances,
+        costs: &costs,
+        label_keys,
+        timestamp: Utc::now().timestamp(),
+    }))
+}
@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use gcectl_core::compute::Compute;
+use gcectl_core::output::{print_list, print_one};
+use gcectl_core::resources::InstanceGroupManager;
+use gcectl_core::resources::mig::RolloutLimits;
+use tracing::debug;
+
+use super::{Session, confirm_delete, delete_all, success, wait_with_spinner, with_spinner};
+use crate::cli::{
+    MigArgs, MigCreateArgs, MigDeleteArgs, MigListArgs, MigResizeArgs, MigRollingUpdateArgs,
+    MigsCommand,
+};
+
+// groups settle over minutes; no need to poll faster
+const STABLE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+pub async fn run(session: &Session, cmd: MigsCommand) -> Result<()> {
+    match cmd {
+        MigsCommand::List(args) => list(session, args).await,
+        MigsCommand::Describe(args) => describe(session, args).await,
+        MigsCommand::Create(args) => create(session, args).await,
+        MigsCommand::Resize(args) => resize(session, args).await,
+        MigsCommand::RollingUpdate(args) => rolling_update(session, args).await,
+        MigsCommand::WaitUntilStable(args) => wait(session, args).await,
+        MigsCommand::Delete(args) => delete(session, args).await,
+    }
+}
+
+async fn list(session: &Session, args: MigListArgs) -> Result<()> {
+    let project = session.project(args.zonal.project.as_deref())?;
+    let zone = session.list_zone(&args.zonal, args.all_zones);
+    let compute = session.compute().await?;
+    let migs = match zone {
+        Some(zone) => {
+            compute
+                .list_migs(&project, &zone, args.filter.as_ref())
+                .await?
+        }
+        None => {
+            let mut migs = compute
+                .list_migs_all_zones(&project, args.filter.as_ref())
+                .await?;
+            migs.sort_by(|a, b| (a.zone_name(), &a.name).cmp(&(b.zone_name(), &b.name)));
+            migs
+        }
+    };
+    print_list(session.output, &migs)
+}
+
+async fn describe(session: &Session, args: MigArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let mig = compute.get_mig(&project, &zone, &args.name).await?;
+    print_one(session.output, &mig)
+}
+
+async fn create(session: &Session, args: MigCreateArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let body = InstanceGroupManager::create_request(
+        &args.name,
+        &project,
+        &args.template,
+        args.size,
+        args.base_instance_name.as_deref(),
+    );
+    let compute = session.compute().await?;
+    let op = compute.insert_mig(&project, &zone, &body).await?;
+    wait_with_spinner(&compute, op, format!("Creating group {}", args.name)).await?;
+    if args.wait_until_stable {
+        wait_until_stable(&compute, &project, &zone, &args.name).await?;
+    }
+    success(&format!(
+        "Group {} created with {} instance(s)",
+        args.name, args.size
+    ));
+    Ok(())
+}
+
+async fn resize(session: &Session, args: MigResizeArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let op = compute
+        .resize_mig(&project, &zone, &args.name, args.size)
+        .await?;
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Resizing group {} to {}", args.name, args.size),
+    )
+    .await?;
+    if args.wait_until_stable {
+        wait_until_stable(&compute, &project, &zone, &args.name).await?;
+    }
+    success(&format!("Group {} resized to {}", args.name, args.size));
+    Ok(())
+}
+
+async fn rolling_update(session: &Session, args: MigRollingUpdateArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let body = InstanceGroupManager::rolling_update_request(
+        &project,
+        &args.template,
+        RolloutLimits {
+            max_surge: args.max_surge,
+            max_unavailable: args.max_unavailable,
+        },
+    );
+    let compute = session.compute().await?;
+    let op = compute
+        .patch_mig(&project, &zone, &args.name, &body)
+        .await?;
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Starting rollout of {} to {}", args.template, args.name),
+    )
+    .await?;
+    if args.wait_until_stable {
+        wait_until_stable(&compute, &project, &zone, &args.name).await?;
+        success(&format!(
+            "Group {} now runs template {}",
+            args.name, args.template
+        ));
+    } else {
+        success(&format!(
+            "Rollout of {} to group {} started",
+            args.template, args.name
+        ));
+    }
+    Ok(())
+}
+
+async fn wait(session: &Session, args: MigArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    wait_until_stable(&compute, &project, &zone, &args.name).await?;
+    success(&format!("Group {} is stable", args.name));
+    Ok(())
+}
+
+async fn delete(session: &Session, args: MigDeleteArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    confirm_delete(args.force, "group", &zone, &args.names)?;
+    let compute = session.compute().await?;
+    delete_all(&compute, "group", &args.names, |name| {
+        let (compute, project, zone) = (&compute, &project, &zone);
+        async move { compute.delete_mig(project, zone, &name).await }
+    })
+    .await
+}
+
+/// Polls the group until it reports itself stable, with every instance
+/// existing and no action in progress, and reports its version target
+/// reached, with every instance running the target template.
+async fn wait_until_stable(compute: &Compute, project: &str, zone: &str, name: &str) -> Result<()> {
+    let poll = async {
+        loop {
+            let mig = compute.get_mig(project, zone, name).await?;
+            if mig.is_stable() && mig.reached_version_target() {
+                return Ok(());
+            }
+            debug!("group {name} pending: {}", mig.pending_actions().join(", "));
+            tokio::time::sleep(STABLE_POLL_INTERVAL).await;
+        }
+    };
+    with_spinner(format!("Waiting for group {name} to become stable"), poll).await
+}
@@ -0,0 +1,961 @@
+//! Subcommand handlers.
+
+mod addresses;
+mod alias;
+mod bake;
+mod cache;
+mod config;
+mod cost;
+mod diagnose;
+mod disks;
+mod dns;
+mod firewall;
+mod fleet;
+mod gpus;
+mod history;
+mod images;
+mod instances;
+mod logs;
+mod machine_types;
+mod manifest;
+mod metrics;
+mod migs;
+mod networks;
+mod nodes;
+mod operations;
+mod os;
+mod placement;
+mod project_info;
+mod project_metadata;
+mod quotas;
+mod reservations;
+mod resource_policies;
+mod schedule;
+mod security;
+mod self_update;
+mod service_accounts;
+mod shell;
+mod snapshots;
+mod ssh;
+mod ssh_keys;
+mod templates;
+mod top;
+mod tunnel;
+mod zones;
+
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use futures_util::future::join_all;
+use gcectl_core::auth::{Authenticator, Credentials};
+use gcectl_core::batch::Outcome;
+use gcectl_core::billing::Billing;
+use gcectl_core::cache::{
+    Cache, DEFAULT_TTL, IMAGES_CATALOG, MACHINE_TYPES_CATALOG, ZONES_CATALOG,
+};
+use gcectl_core::cancel::{self, Deadline};
+use gcectl_core::cloud_logging::CloudLogging;
+use gcectl_core::compute::{Compute, Paging, Revalidated};
+use gcectl_core::config::{Config, Profile};
+use gcectl_core::console;
+use gcectl_core::context::{Resolved, Resolver};
+use gcectl_core::dns::Dns;
+use gcectl_core::endpoints::{Endpoints, GoogleApis};
+use gcectl_core::error::{self, GcectlError};
+use gcectl_core::events::{self, Event};
+use gcectl_core::filter::Filter;
+use gcectl_core::fixtures::{self, Fixtures};
+use gcectl_core::iam::Iam;
+use gcectl_core::labels::LabelEdit;
+use gcectl_core::monitoring::Monitoring;
+use gcectl_core::notify::{self, Finished};
+use gcectl_core::osconfig::OsConfig;
+use gcectl_core::oslogin::OsLogin;
+use gcectl_core::output::{self, Layout, OutputFormat, print_list};
+use gcectl_core::resource_manager::ResourceManager;
+use gcectl_core::resources::instance::Metadata;
+use gcectl_core::resources::{Image, Instance, MachineType, Operation, Zone};
+use gcectl_core::transport::{DEFAULT_QPS, DEFAULT_RETRIES, RateLimiter, RetryPolicy, Transport};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tokio::sync::OnceCell;
+use tracing::debug;
+
+use crate::cli::{Cli, Command, ConfigCommand, MetadataArgs, RegionalArgs, ZonalArgs};
+use crate::completion;
+use crate::prompt;
+
+pub async fn run(cli: Cli) -> Result<()> {
+    let session = Session::new(&cli)?;
+    // the shell races each of its commands instead
+    if matches!(cli.command, Command::Shell(_)) {
+        return dispatch(&session, cli.command).await;
+    }
+    let deadline = session.deadline;
+    // polled first so a cancelled operation wait can name its operation
+    tokio::select! {
+        biased;
+        result = dispatch(&session, cli.command) => result,
+        err = cancel::cancelled(deadline, None) => Err(err.into()),
+    }
+}
+
+async fn dispatch(session: &Session, command: Command) -> Result<()> {
+    let result = route(session, command).await;
+    // a stored token the API no longer accepts would fail every run until
+    // it expired
+    if let Err(err) = &result
+        && matches!(error::find(err), Some(GcectlError::Unauthenticated(_)))
+        && let Some(auth) = session.auth.get()
+    {
+        auth.forget().await;
+    }
+    result
+}
+
+async fn route(session: &Session, command: Command) -> Result<()> {
+    match command {
+        Command::Instances(cmd) => instances::run(session, cmd).await,
+        Command::Images(cmd) => images::run(session, cmd).await,
+        Command::Bake(args) => notifying(session, args.notify, bake::run(session, args)).await,
+        Command::Disks(cmd) => disks::run(session, cmd).await,
+        Command::Templates(cmd) => templates::run(session, cmd).await,
+        Command::Migs(cmd) => migs::run(session, cmd).await,
+        Command::Firewall(cmd) => firewall::run(session, cmd).await,
+        Command::Cost(cmd) => cost::run(session, cmd).await,
+        Command::Addresses(cmd) => addresses::run(session, cmd).await,
+        Command::Dns(cmd) => dns::run(session, cmd).await,
+        Command::Networks(cmd) => networks::run_networks(session, cmd).await,
+        Command::Subnets(cmd) => networks::run_subnets(session, cmd).await,
+        Command::Zones(cmd) => zones::run_zones(session, cmd).await,
+        Command::Regions(cmd) => zones::run_regions(session, cmd).await,
+        Command::Quotas(cmd) => quotas::run(session, cmd).await,
+        Command::Gpus(cmd) => gpus::run(session, cmd).await,
+        Command::MachineTypes(cmd) => machine_types::run(session, cmd).await,
+        Command::ServiceAccounts(cmd) => service_accounts::run(session, cmd).await,
+        Command::Fleet(cmd) => fleet::run(session, cmd).await,
+        Command::Placement(cmd) => placement::run(session, cmd).await,
+        Command::Snapshots(cmd) => snapshots::run(session, cmd).await,
+        Command::Operations(cmd) => operations::run(session, cmd).await,
+        Command::ProjectMetadata(cmd) => project_metadata::run(session, cmd).await,
+        Command::ProjectInfo(cmd) => project_info::run(session, cmd).await,
+        Command::Schedule(cmd) => schedule::run(session, cmd).await,
+        Command::NodeTemplates(cmd) => nodes::run_templates(session, cmd).await,
+        Command::NodeGroups(cmd) => nodes::run_groups(session, cmd).await,
+        Command::Reservations(cmd) => reservations::run(session, cmd).await,
+        Command::ResourcePolicies(cmd) => resource_policies::run(session, cmd).await,
+        Command::Os(cmd) => os::run(session, cmd).await,
+        Command::Ssh(args) => ssh::run(session, args).await,
+        Command::Run(args) => ssh::run_everywhere(session, args).await,
+        Command::Scp(args) => ssh::scp(session, args).await,
+        Command::Rsync(args) => ssh::rsync(session, args).await,
+        Command::SshKeys(cmd) => ssh_keys::run(session, cmd).await,
+        Command::Tunnel(args) => tunnel::run(session, args).await,
+        Command::Top(args) => top::run(session, args).await,
+        Command::Logs(args) => logs::run(session, args).await,
+        Command::Metrics(cmd) => metrics::run(session, cmd).await,
+        Command::Export(cmd) => manifest::export(session, cmd).await,
+        Command::Apply(args) => manifest::apply(session, args).await,
+        Command::Config(cmd) => config::run(session, cmd),
+        Command::Alias(cmd) => alias::run(session, cmd),
+        Command::History(args) => history::run(session, args),
+        Command::Resume(args) => instances::resume_batch(session, args).await,
+        Command::Diagnose(args) => diagnose::run(session, args).await,
+        Command::Security(cmd) => security::run(session, cmd).await,
+        Command::Cache(cmd) => cache::run(cmd),
+        Command::Shell(args) => shell::run(session, args).await,
+        Command::SelfUpdate(args) => self_update::run(args).await,
+        Command::Completion(args) => {
+            completion::write_registration(args.shell, &mut std::io::stdout())
+        }
+    }
+}
+
+/// A value `parsed` from the profile, or `fallback` when `lenient`.
+fn from_profile<T>(parsed: Result<T>, lenient: bool, fallback: T) -> Result<T> {
+    match parsed {
+        Err(err) if lenient => {
+            debug!("ignoring {err:#}");
+            Ok(fallback)
+        }
+        parsed => parsed,
+    }
+}
+
+/// State shared by a single gcectl invocation: the loaded config file, the
+/// profile selected for this run, the response cache, and lazily resolved
+/// credentials.
+#[derive(Clone)]
+pub struct Session {
+    pub config: Config,
+    pub profile_name: String,
+    pub profile: Profile,
+    pub context: Resolver,
+    pub output: OutputFormat,
+    pub cache: Cache,
+    pub dry_run: bool,
+    // what notifications say ran: this process's arguments, or the shell line
+    command_line: String,
+    deadline: Option<Deadline>,
+    endpoints: Endpoints,
+    http: Transport,
+    impersonate: Option<String>,
+    keyring: bool,
+    // answering from fixtures, which needs no credentials
+    replaying: bool,
+    // shared with the copies `gcectl shell` runs each command in
+    auth: Arc<OnceCell<Arc<Authenticator>>>,
+}
+
+impl Session {
+    fn new(cli: &Cli) -> Result<Self> {
+        let config = Config::load()?;
+        let profile_name = config.profile_name(cli.profile.as_deref());
+        // `config set` is how new profiles come into existence
+        let create = matches!(cli.command, Command::Config(ConfigCommand::Set { .. }));
+        let profile = config.profile(&profile_name, create)?;
+        // `config` is how a bad value gets fixed, so it runs on defaults
+        let lenient = matches!(cli.command, Command::Config(_));
+        let output = match (cli.output, profile.output.as_deref()) {
+            (Some(flag), _) => flag,
+            (None, Some(name)) => from_profile(
+                name.parse::<OutputFormat>()
+                    .with_context(|| format!("invalid output format in profile {profile_name}")),
+                lenient,
+                OutputFormat::default(),
+            )?,
+            (None, None) => OutputFormat::default(),
+        };
+        output::set_layout(Layout::new(
+            cli.columns.as_deref(),
+            profile.columns.as_deref(),
+            cli.sort_by.clone(),
+        ));
+        let retries = match (cli.retries, profile.retries.as_deref()) {
+            (Some(flag), _) => flag,
+            (None, Some(value)) => from_profile(
+                value
+                    .parse()
+                    .with_context(|| format!("invalid retries in profile {profile_name}")),
+                lenient,
+                DEFAULT_RETRIES,
+            )?,
+            (None, None) => DEFAULT_RETRIES,
+        };
+        let qps = match (cli.qps, profile.qps.as_deref()) {
+            (Some(flag), _) => flag,
+            (None, Some(value)) => from_profile(
+                value
+                    .parse()
+                    .with_context(|| format!("invalid qps in profile {profile_name}")),
+                lenient,
+                DEFAULT_QPS,
+            )?,
+            (None, None) => DEFAULT_QPS,
+        };
+        let google_apis = match (cli.google_apis, profile.google_apis.as_deref()) {
+            (Some(flag), _) => Some(flag),
+            (None, Some(name)) => from_profile(
+                name.parse::<GoogleApis>()
+                    .map(Some)
+                    .with_context(|| format!("invalid google_apis in profile {profile_name}")),
+                lenient,
+                None,
+            )?,
+            (None, None) => None,
+        };
+        let client = match google_apis {
+            Some(vip) => vip.pin(reqwest::Client::builder()),
+            None => reqwest::Client::builder(),
+        }
+        .build()
+        .context("failed to build the HTTP client")?;
+        let impersonate = cli
+            .impersonate_service_account
+            .clone()
+            .or_else(|| profile.impersonate_service_account.clone());
+        let fixtures = match (&cli.record, &cli.replay) {
+            (Some(dir), _) => Some(Fixtures::record(dir)?),
+            (None, Some(dir)) => Some(Fixtures::replay(dir)?),
+            (None, None) => None,
+        };
+        let endpoints = Endpoints::resolve(
+            cli.api_endpoint
+                .as_deref()
+                .or(profile.api_endpoint.as_deref()),
+        );
+        Ok(Self {
+            config,
+            context: Resolver::load(&profile_name, &profile).with_flags(
+                cli.project.clone(),
+                cli.zone.clone(),
+                cli.region.clone(),
+            ),
+            profile_name,
+            profile,
+            output,
+            // cached results would be missing from a recording, and stale
+            // in a replay
+            cache: Cache::new(
+                !cli.no_cache && fixtures.is_none(),
+                cli.cache_ttl.map_or(DEFAULT_TTL, Duration::from_secs),
+            ),
+            dry_run: cli.dry_run,
+            command_line: std::env::args().skip(1).collect::<Vec<_>>().join(" "),
+            deadline: cli.timeout.map(Deadline::after),
+            endpoints,
+            http: Transport::new(client, RetryPolicy::with_retries(retries))
+                .rate_limit(RateLimiter::new(qps))
+                .fixtures(fixtures),
+            impersonate,
+            keyring: !cli.no_keyring,
+            replaying: cli.replay.is_some(),
+            auth: Arc::default(),
+        })
+    }
+
+    /// Application Default Credentials, discovered on first use.
+    async fn auth(&self) -> Result<Arc<Authenticator>> {
+        self.auth
+            .get_or_try_init(|| async {
+                if self.replaying {
+                    let token = Credentials::AccessToken(fixtures::REPLAY_TOKEN.to_string());
+                    return Ok(Arc::new(Authenticator::new(self.http.clone(), token)));
+                }
+                let auth = Authenticator::discover(self.http.clone())
+                    .await?
+                    .impersonate(self.impersonate.clone())
+                    .keyring(self.keyring)
+                    .endpoint(self.endpoints.iam_credentials.as_deref());
+                Ok(Arc::new(auth))
+            })
+            .await
+            .cloned()
+    }
+
+    /// Builds an authenticated Compute Engine client.
+    async fn compute(&self) -> Result<Compute> {
+        Ok(Compute::new(self.http.clone(), self.auth().await?)
+            .endpoint(self.endpoints.compute.as_deref())
+            .dry_run(self.dry_run)
+            .deadline(self.deadline))
+    }
+
+    /// Builds an authenticated Cloud Monitoring client.
+    async fn monitoring(&self) -> Result<Monitoring> {
+        Ok(Monitoring::new(self.http.clone(), self.auth().await?)
+            .endpoint(self.endpoints.monitoring.as_deref()))
+    }
+
+    /// Builds an authenticated Cloud Logging client.
+    async fn cloud_logging(&self) -> Result<CloudLogging> {
+        Ok(CloudLogging::new(self.http.clone(), self.auth().await?)
+            .endpoint(self.endpoints.logging.as_deref()))
+    }
+
+    /// Builds an authenticated BigQuery client for the billing export.
+    async fn billing(&self) -> Result<Billing> {
+        Ok(Billing::new(self.http.clone(), self.auth().await?)
+            .endpoint(self.endpoints.bigquery.as_deref()))
+    }
+
+    /// Builds an authenticated Cloud DNS client.
+    async fn dns(&self) -> Result<Dns> {
+        Ok(Dns::new(self.http.clone(), self.auth().await?).endpoint(self.endpoints.dns.as_deref()))
+    }
+
+    /// Builds an authenticated IAM client.
+    async fn iam(&self) -> Result<Iam> {
+        Ok(Iam::new(self.http.clone(), self.auth().await?).endpoint(self.endpoints.iam.as_deref()))
+    }
+
+    /// Builds an authenticated OS Login client.
+    async fn oslogin(&self) -> Result<OsLogin> {
+        Ok(OsLogin::new(self.http.clone(), self.auth().await?)
+            .endpoint(self.endpoints.oslogin.as_deref()))
+    }
+
+    /// Builds an authenticated OS Config client.
+    async fn osconfig(&self) -> Result<OsConfig> {
+        Ok(OsConfig::new(self.http.clone(), self.auth().await?)
+            .endpoint(self.endpoints.osconfig.as_deref()))
+    }
+
+    /// Builds an authenticated Cloud Resource Manager client.
+    async fn resource_manager(&self) -> Result<ResourceManager> {
+        Ok(ResourceManager::new(self.http.clone(), self.auth().await?)
+            .endpoint(self.endpoints.resource_manager.as_deref()))
+    }
+
+    /// Projects `--all-projects` covers: the profile's `projects`, else
+    /// every active project the account can see.
+    async fn all_projects(&self) -> Result<Vec<String>> {
+        let listed = self.profile.project_list();
+        if !listed.is_empty() {
+            return Ok(listed);
+        }
+        let projects = self
+            .resource_manager()
+            .await?
+            .list_project_ids()
+            .await
+            .context("failed to discover projects; list them with `gcectl config set projects`")?;
+        if projects.is_empty() {
+            bail!("the account cannot see any active project");
+        }
+        Ok(projects)
+    }
+
+    /// The account the credentials act as.
+    async fn account_email(&self) -> Result<String> {
+        self.auth().await?.account_email().await
+    }
+
+    /// `list_instances_in` served from the cache while it is fresh.
+    async fn list_instances(
+        &self,
+        compute: &Compute,
+        project: &str,
+        zone: Option<&str>,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Instance>> {
+        let key = instances_key(project, zone, filter);
+        if let Some(instances) = self.cache.get(&instances_namespace(project), &key) {
+            return Ok(instances);
+        }
+        let instances = compute.list_instances_in(project, zone, filter).await?;
+        self.cache
+            .put(&instances_namespace(project), &key, &instances);
+        Ok(instances)
+    }
+
+    /// `list_instance_pages` answered in one page from the cache while it
+    /// is fresh. Returns the whole listing when it was short enough to
+    /// keep, which is also when it gets cached.
+    async fn list_instance_pages(
+        &self,
+        compute: &Compute,
+        project: &str,
+        zone: Option<&str>,
+        filter: Option<&Filter>,
+        paging: Paging,
+        mut on_page: impl FnMut(&[Instance]) -> Result<()>,
+    ) -> Result<Option<Vec<Instance>>> {
+        let namespace = instances_namespace(project);
+        let key = instances_key(project, zone, filter);
+        if let Some(mut instances) = self.cache.get::<Vec<Instance>>(&namespace, &key) {
+            if let Some(limit) = paging.limit {
+                instances.truncate(limit as usize);
+            }
+            on_page(&instances)?;
+            return Ok(paging.limit.is_none().then_some(instances));
+        }
+        // a limited listing is not the whole of it
+        let mut kept = paging.limit.is_none().then(Vec::new);
+        compute
+            .list_instance_pages(project, zone, filter, paging, |page| {
+                on_page(&page)?;
+                match &mut kept {
+                    Some(all) if all.len() + page.len() <= KEPT_INSTANCES => all.extend(page),
+                    _ => kept = None,
+                }
+                Ok(())
+            })
+            .await?;
+        if let Some(instances) = &kept {
+            self.cache.put(&namespace, &key, instances);
+        }
+        Ok(kept)
+    }
+
+    /// `get_instance` answered from a fresh unfiltered listing when one
+    /// covers `zone`, otherwise from the API.
+    async fn get_instance(
+        &self,
+        compute: &Compute,
+        project: &str,
+        zone: &str,
+        name: &str,
+    ) -> Result<Instance> {
+        let namespace = instances_namespace(project);
+        let listed = [Some(zone), None].into_iter().find_map(|scope| {
+            self.cache
+                .get::<Vec<Instance>>(&namespace, &instances_key(project, scope, None))?
+                .into_iter()
+                .find(|i| i.name == name && i.zone_name() == zone)
+        });
+        match listed {
+            Some(instance) => Ok(instance),
+            None => compute.get_instance(project, zone, name).await,
+        }
+    }
+
+    /// Drops cached instance listings of `project` after a command changed
+    /// its instances.
+    fn forget_instances(&self, project: &str) {
+        self.cache.invalidate(&instances_namespace(project));
+    }
+
+    /// Zones of `project`, from the catalog cache while it is fresh.
+    async fn zones(&self, compute: &Compute, project: &str) -> Result<Vec<Zone>> {
+        self.catalog(ZONES_CATALOG, project, |etag| async move {
+            compute
+                .list_zones_if_modified(project, etag.as_deref())
+                .await
+        })
+        .await
+    }
+
+    /// Machine types offered in `zone`, from the catalog cache while it is
+    /// fresh.
+    async fn machine_types(
+        &self,
+        compute: &Compute,
+        project: &str,
+        zone: &str,
+    ) -> Result<Vec<MachineType>> {
+        self.catalog(
+            MACHINE_TYPES_CATALOG,
+            &format!("{project}/{zone}"),
+            |etag| async move {
+                compute
+                    .list_machine_types_if_modified(project, zone, etag.as_deref())
+                    .await
+            },
+        )
+        .await
+    }
+
+    /// Images of `project`, such as `debian-cloud`, from the catalog cache
+    /// while it is fresh.
+    async fn images(&self, compute: &Compute, project: &str) -> Result<Vec<Image>> {
+        self.catalog(IMAGES_CATALOG, project, |etag| async move {
+            compute
+                .list_images_if_modified(project, etag.as_deref())
+                .await
+        })
+        .await
+    }
+
+    /// A catalog listing served from the cache while fresh; once stale, the
+    /// ETag it was stored with lets `fetch` learn it is still current
+    /// without downloading it again.
+    async fn catalog<T, F, Fut>(&self, namespace: &str, key: &str, fetch: F) -> Result<Vec<T>>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce(Option<String>) -> Fut,
+        Fut: Future<Output = Result<Revalidated<T>>>,
+    {
+        let (held, etag) = match self.cache.catalog::<Vec<T>>(namespace, key) {
+            Some(stored) if stored.fresh => return Ok(stored.value),
+            Some(stored) => (Some(stored.value), stored.etag),
+            None => (None, None),
+        };
+        let (items, etag) = match (fetch(etag.clone()).await?, held) {
+            (Revalidated::NotModified, Some(held)) => (held, etag),
+            (Revalidated::Modified { items, etag }, _) => (items, etag),
+            (Revalidated::NotModified, None) => {
+                bail!("the API answered 304 Not Modified for an uncached {key} listing")
+            }
+        };
+        self.cache
+            .put_catalog(namespace, key, &items, etag.as_deref());
+        Ok(items)
+    }
+
+    /// Resolves `(project, zone)` from flags, the environment, the active
+    /// profile, and gcloud.
+    fn zonal(&self, args: &ZonalArgs) -> Result<(String, String)> {
+        Ok((
+            self.project(args.project.as_deref())?,
+            self.zone(args.zone.as_deref())?,
+        ))
+    }
+
+    /// Zone a list command is scoped to, or `None` to cover every zone.
+    fn list_zone(&self, args: &ZonalArgs, all_zones: bool) -> Option<String> {
+        match all_zones {
+            true => None,
+            false => self.context.zone(args.zone.as_deref()).map(|z| z.value),
+        }
+    }
+
+    /// Region a list command is scoped to, or `None` to cover every region.
+    fn list_region(&self, args: &RegionalArgs, all_regions: bool) -> Option<String> {
+        match all_regions {
+            true => None,
+            false => self.context.region(args.region.as_deref()).map(|r| r.value),
+        }
+    }
+
+    /// Project from `--project`, falling back as described in [`gcectl_core::context`].
+    fn project(&self, flag: Option<&str>) -> Result<String> {
+        required(self.context.project(flag), "project", "GCECTL_PROJECT")
+    }
+
+    fn zone(&self, flag: Option<&str>) -> Result<String> {
+        required(self.context.zone(flag), "zone", "GCECTL_ZONE")
+    }
+
+    /// Region from `--region`, falling back to the region of the zone.
+    fn region(&self, flag: Option<&str>) -> Result<String> {
+        required(self.context.region(flag), "region", "GCECTL_REGION")
+    }
+}
+
+fn required(resolved: Option<Resolved>, key: &str, var: &str) -> Result<String> {
+    resolved.map(|r| r.value).ok_or_else(|| {
+        GcectlError::Usage(format!(
+            "no {key} specified; pass --{key}, set {var}, or run `gcectl config set {key} VALUE`"
+        ))
+        .into()
+    })
+}
+
+// instances a streamed listing holds on to for the cache and completions
+const KEPT_INSTANCES: usize = 5_000;
+
+fn instances_namespace(project: &str) -> String {
+    format!("instances-{project}")
+}
+
+fn instances_key(project: &str, zone: Option<&str>, filter: Option<&Filter>) -> String {
+    format!(
+        "instances/{project}/{}/{}",
+        zone.unwrap_or("*"),
+        filter.map(Filter::to_api).unwrap_or_default()
+    )
+}
+
+/// Waits for `op` to finish while showing a spinner with `message`.
+async fn wait_with_spinner(compute: &Compute, op: Operation, message: String) -> Result<Operation> {
+    with_spinner(message, compute.wait_operation(op)).await
+}
+
+/// Drives `task` to completion while showing a spinner with `message`.
+async fn with_spinner<T>(message: String, task: impl Future<Output = T>) -> T {
+    if events::enabled() {
+        let start = std::time::Instant::now();
+        events::emit(&Event::Started { message: &message });
+        let result = task.await;
+        let elapsed_ms = start.elapsed().as_millis().try_into().unwrap_or(u64::MAX);
+        events::emit(&Event::Finished {
+            message: &message,
+            elapsed_ms,
+        });
+        return result;
+    }
+    if output::quiet() {
+        return task.await;
+    }
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::with_template("{spinner:.green} {msg} ({elapsed})")
+            .expect("valid spinner template"),
+    );
+    spinner.set_message(message);
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    let result = task.await;
+    spinner.finish_and_clear();
+    result
+}
+
+/// Asks before deleting `names` unless `force` is set; declining aborts the
+/// command.
+fn confirm_delete(force: bool, kind: &str, scope: &str, names: &[String]) -> Result<()> {
+    if force {
+        return Ok(());
+    }
+    let question = format!(
+        "Delete {} {kind}(s) in {scope}: {}?",
+        names.len(),
+        names.join(", ")
+    );
+    if !prompt::confirm(&question)? {
+        bail!("aborted");
+    }
+    Ok(())
+}
+
+/// Runs `delete` for every name concurrently, waiting on each resulting
+/// operation, and reports per-resource results. Fails if any deletion did.
+async fn delete_all<F, Fut>(
+    compute: &Compute,
+    kind: &str,
+    names: &[String],
+    delete: F,
+) -> Result<()>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<Operation>>,
+{
+    let requests = names.iter().map(|name| delete(name.clone()));
+    apply_all(
+        compute,
+        kind,
+        &Verb::DELETE,
+        names,
+        requests,
+        Drive::default(),
+    )
+    .await
+}
+
+/// Forms of an action verb used in progress and result messages.
+struct Verb {
+    base: &'static str,
+    progressive: &'static str,
+    past: &'static str,
+}
+
+impl Verb {
+    const DELETE: Self = Self {
+        base: "delete",
+        progressive: "Deleting",
+        past: "deleted",
+    };
+    const START: Self = Self {
+        base: "start",
+        progressive: "Starting",
+        past: "started",
+    };
+    const STOP: Self = Self {
+        base: "stop",
+        progressive: "Stopping",
+        past: "stopped",
+    };
+    const RESET: Self = Self {
+        base: "reset",
+        progressive: "Resetting",
+        past: "reset",
+    };
+    const SUSPEND: Self = Self {
+        base: "suspend",
+        progressive: "Suspending",
+        past: "suspended",
+    };
+    const RESUME: Self = Self {
+        base: "resume",
+        progressive: "Resuming",
+        past: "resumed",
+    };
+}
+
+/// How [`apply_all`] drives a batch and reports it.
+#[derive(Default)]
+struct Drive<'a> {
+    // return once each request is accepted rather than waiting on it
+    no_wait: bool,
+    // a result table in this format instead of a result line each
+    outcomes: Option<OutputFormat>,
+    // report a lone resource as a batch too, as a resumed batch is
+    as_batch: bool,
+    // handed the names that failed once a batch is reported
+    on_failed: Option<Box<dyn FnOnce(Vec<String>) + 'a>>,
+}
+
+/// Drives `requests` concurrently, waiting on each resulting operation
+/// unless `drive` says not to. A lone resource gets the usual spinner and
+/// result line; a batch gets a result line or table row per entry of
+/// `names`. Fails if any request did.
+async fn apply_all<Fut>(
+    compute: &Compute,
+    kind: &str,
+    verb: &Verb,
+    names: &[String],
+    requests: impl Iterator<Item = Fut>,
+    drive: Drive<'_>,
+) -> Result<()>
+where
+    Fut: Future<Output = Result<Operation>>,
+{
+    let no_wait = drive.no_wait;
+    let tasks = requests.map(|op| async move {
+        let op = op.await?;
+        match no_wait {
+            true => Ok(op),
+            false => compute.wait_operation(op).await,
+        }
+    });
+    let message = match names {
+        [name] => format!("{} {kind} {name}", verb.progressive),
+        _ => format!("{} {} {kind}(s)", verb.progressive, names.len()),
+    };
+    let results = with_spinner(message, join_all(tasks)).await;
+
+    // a lone resource fails with its own error and exit status
+    if let [name] = names
+        && !drive.as_batch
+    {
+        let result = results.into_iter().next().expect("one result per name");
+        let op = result.with_context(|| format!("failed to {} {kind} {name}", verb.base))?;
+        match no_wait {
+            true => requested(&capitalize(verb.base), &op),
+            false => success(&format!("{} {name} {}", capitalize(kind), verb.past)),
+        }
+        return Ok(());
+    }
+    let outcomes: Vec<Outcome> = names
+        .iter()
+        .zip(results)
+        .map(|(name, result)| match result {
+            Ok(op) if no_wait => Outcome::new(name, &format!("requested ({})", op.name), Ok(())),
+            result => Outcome::new(name, verb.past, result.map(drop)),
+        })
+        .collect();
+    match drive.outcomes {
+        Some(format) => print_list(format, &outcomes)?,
+        None => {
+            for outcome in &outcomes {
+                let (kind, name) = (capitalize(kind), &outcome.name);
+                match outcome.ok {
+                    true => success(&format!("{kind} {name} {}", outcome.result)),
+                    false => failure(&format!("{kind} {name}: {}", outcome.result)),
+                }
+            }
+        }
+    }
+    let failed: Vec<String> = outcomes
+        .iter()
+        .filter(|o| !o.ok)
+        .map(|o| o.name.clone())
+        .collect();
+    let count = failed.len();
+    if let Some(on_failed) = drive.on_failed {
+        on_failed(failed);
+    }
+    if count > 0 {
+        return Err(batch_failed(verb.base, kind, count, names.len()));
+    }
+    Ok(())
+}
+
+/// The error a batch ends with once its per-resource results are reported.
+fn batch_failed(action: &str, kind: &str, failed: usize, total: usize) -> anyhow::Error {
+    GcectlError::BatchFailed {
+        action: action.to_string(),
+        kind: kind.to_string(),
+        failed,
+        total,
+    }
+    .into()
+}
+
+/// Resolves `--metadata` and `--metadata-from-file` into key/value pairs.
+fn metadata_entries(args: &MetadataArgs) -> Result<Vec<(String, String)>> {
+    let mut entries = args.metadata.clone();
+    for (key, path) in &args.metadata_from_file {
+        let value = fs::read_to_string(path).with_context(|| format!("failed to read {path}"))?;
+        entries.push((key.clone(), value));
+    }
+    Ok(entries)
+}
+
+/// Waits on the label edit `op` of `kind` `name` unless `no_wait`, then
+/// reports what `edit` changed.
+async fn finish_label_edit(
+    compute: &Compute,
+    op: Operation,
+    kind: &str,
+    name: &str,
+    edit: &LabelEdit,
+    no_wait: bool,
+) -> Result<()> {
+    if no_wait {
+        requested("Label update", &op);
+        return Ok(());
+    }
+    wait_with_spinner(compute, op, format!("Updating labels of {kind} {name}")).await?;
+    success(&format!("{} on {kind} {name}", edit.summary()));
+    Ok(())
+}
+
+/// Removes every key in `keys`, failing if any is not set.
+fn remove_metadata_keys(metadata: &mut Metadata, keys: &[String]) -> Result<()> {
+    let missing: Vec<&str> = keys
+        .iter()
+        .filter(|key| !metadata.remove(key))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        bail!("no metadata entry for {}", missing.join(", "));
+    }
+    Ok(())
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+/// Prints `url`, and opens it in the browser too when `web` is set.
+fn open_url(url: &str, web: bool) -> Result<()> {
+    output::print_text(&format!("{url}\n"));
+    if web {
+        console::open_in_browser(url)?;
+    }
+    Ok(())
+}
+
+/// Reports an operation started with `--no-wait`; quiet output is just the
+/// operation name, for `gcectl operations wait`.
+fn requested(what: &str, op: &Operation) {
+    if events::enabled() {
+        return events::emit(&Event::Requested {
+            what,
+            operation: &op.name,
+        });
+    }
+    match output::quiet() {
+        true => println!("{}", op.name),
+        false => println!("{what} requested: operation {}", op.name),
+    }
+}
+
+/// Runs `task`, then, if `notify` is set, tells the configured hooks how it
+/// ended. Hooks that fail only warn; a dry run has nothing to report.
+async fn notifying(
+    session: &Session,
+    notify: bool,
+    task: impl Future<Output = Result<()>>,
+) -> Result<()> {
+    if !notify || session.dry_run {
+        return task.await;
+    }
+    let start = std::time::Instant::now();
+    let result = task.await;
+    let error = result.as_ref().err().map(|err| format!("{err:#}"));
+    let finished = Finished::new(&session.command_line, error, start.elapsed());
+    for err in notify::send(&session.config.notify, &finished).await {
+        warning(&format!("notification not sent: {err:#}"));
+    }
+    result
+}
+
+fn success(msg: &str) {
+    if events::enabled() {
+        return events::emit(&Event::Success { message: msg });
+    }
+    if !output::quiet() {
+        println!("[SUCCESS] | {msg}");
+    }
+}
+
+fn failure(msg: &str) {
+    events::emit(&Event::Error {
+        message: msg,
+        hint: None,
+        exit_code: None,
+    });
+    eprintln!("[ERROR] | {msg}");
+}
+
+fn warning(msg: &str) {
+    events::emit(&Event::Warning { message: msg });
+    eprintln!("[WARNING] | {msg}");
+}
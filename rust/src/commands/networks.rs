@@ -0,0 +1,74 @@
+use anyhow::Result;
+use gcectl_core::output::{print_list, print_one};
+use gcectl_core::resources::Instance;
+
+use super::Session;
+use crate::cli::{
+    NetworkArgs, NetworkListArgs, NetworksCommand, SubnetArgs, SubnetListArgs, SubnetsCommand,
+};
+
+pub async fn run_networks(session: &Session, cmd: NetworksCommand) -> Result<()> {
+    match cmd {
+        NetworksCommand::List(args) => list_networks(session, args).await,
+        NetworksCommand::Describe(args) => describe_network(session, args).await,
+    }
+}
+
+pub async fn run_subnets(session: &Session, cmd: SubnetsCommand) -> Result<()> {
+    match cmd {
+        SubnetsCommand::List(args) => list_subnets(session, args).await,
+        SubnetsCommand::Describe(args) => describe_subnet(session, args).await,
+    }
+}
+
+async fn list_networks(session: &Session, args: NetworkListArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let networks = compute
+        .list_networks(&project, args.filter.as_ref())
+        .await?;
+    print_list(session.output, &networks)
+}
+
+async fn describe_network(session: &Session, args: NetworkArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let network = compute.get_network(&project, &args.name).await?;
+    print_one(session.output, &network)
+}
+
+async fn list_subnets(session: &Session, args: SubnetListArgs) -> Result<()> {
+    let project = session.project(args.regional.project.as_deref())?;
+    let region = session.list_region(&args.regional, args.all_regions);
+    let compute = session.compute().await?;
+    let subnets = compute
+        .list_subnets_in(&project, region.as_deref(), args.filter.as_ref())
+        .await?;
+    print_list(session.output, &subnets)
+}
+
+async fn describe_subnet(session: &Session, args: SubnetArgs) -> Result<()> {
+    let project = session.project(args.regional.project.as_deref())?;
+    let region = session.region(args.regional.region.as_deref())?;
+    let compute = session.compute().await?;
+    let mut subnet = compute.get_subnet(&project, &region, &args.name).await?;
+    // only this project's instances are visible, so shared-VPC service
+    // projects using the subnet are not counted
+    let instances = compute.list_instances_all_zones(&project, None).await?;
+    let suffix = format!("regions/{region}/subnetworks/{}", args.name);
+    subnet.addresses_in_use = Some(interfaces_in(&instances, &suffix));
+    print_one(session.output, &subnet)
+}
+
+/// Network interfaces attached to the subnet whose URL ends in `suffix`.
+fn interfaces_in(instances: &[Instance], suffix: &str) -> u64 {
+    instances
+        .iter()
+        .flat_map(|i| &i.network_interfaces)
+        .filter(|nic| {
+            nic.subnetwork
+                .as_deref()
+                .is_some_and(|s| s.ends_with(suffix))
+        })
+        .count() as u64
+}
@@ -0,0 +1,142 @@
+use anyhow::Result;
+use gcectl_core::output::{print_list, print_one};
+use gcectl_core::resources::node::{NodeGroupSpec, NodeTemplateSpec};
+use gcectl_core::resources::region_of;
+
+use super::{Session, confirm_delete, delete_all, requested, success, wait_with_spinner};
+use crate::cli::{
+    NodeGroupArgs, NodeGroupCreateArgs, NodeGroupDeleteArgs, NodeGroupListArgs, NodeGroupsCommand,
+    NodeTemplateArgs, NodeTemplateCreateArgs, NodeTemplateDeleteArgs, NodeTemplateListArgs,
+    NodeTemplatesCommand,
+};
+
+pub async fn run_templates(session: &Session, cmd: NodeTemplatesCommand) -> Result<()> {
+    match cmd {
+        NodeTemplatesCommand::List(args) => list_templates(session, args).await,
+        NodeTemplatesCommand::Describe(args) => describe_template(session, args).await,
+        NodeTemplatesCommand::Create(args) => create_template(session, args).await,
+        NodeTemplatesCommand::Delete(args) => delete_templates(session, args).await,
+    }
+}
+
+pub async fn run_groups(session: &Session, cmd: NodeGroupsCommand) -> Result<()> {
+    match cmd {
+        NodeGroupsCommand::List(args) => list_groups(session, args).await,
+        NodeGroupsCommand::Describe(args) => describe_group(session, args).await,
+        NodeGroupsCommand::Create(args) => create_group(session, args).await,
+        NodeGroupsCommand::Delete(args) => delete_groups(session, args).await,
+    }
+}
+
+async fn list_templates(session: &Session, args: NodeTemplateListArgs) -> Result<()> {
+    let project = session.project(args.regional.project.as_deref())?;
+    let region = session.list_region(&args.regional, args.all_regions);
+    let compute = session.compute().await?;
+    let templates = compute
+        .list_node_templates(&project, region.as_deref(), args.filter.as_ref())
+        .await?;
+    print_list(session.output, &templates)
+}
+
+async fn describe_template(session: &Session, args: NodeTemplateArgs) -> Result<()> {
+    let project = session.project(args.regional.project.as_deref())?;
+    let region = session.region(args.regional.region.as_deref())?;
+    let compute = session.compute().await?;
+    let template = compute
+        .get_node_template(&project, &region, &args.name)
+        .await?;
+    print_one(session.output, &template)
+}
+
+async fn create_template(session: &Session, args: NodeTemplateCreateArgs) -> Result<()> {
+    let project = session.project(args.regional.project.as_deref())?;
+    let region = session.region(args.regional.region.as_deref())?;
+    let spec = NodeTemplateSpec {
+        name: args.name.clone(),
+        description: args.description,
+        node_type: args.node_type,
+        affinity_labels: args.node_affinity_labels,
+        minimal_servers: args.minimal_servers,
+    };
+    let compute = session.compute().await?;
+    let op = compute
+        .insert_node_template(&project, &region, &spec.to_body())
+        .await?;
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Creating node template {}", args.name),
+    )
+    .await?;
+    success(&format!("Node template {} created in {region}", args.name));
+    Ok(())
+}
+
+async fn delete_templates(session: &Session, args: NodeTemplateDeleteArgs) -> Result<()> {
+    let project = session.project(args.regional.project.as_deref())?;
+    let region = session.region(args.regional.region.as_deref())?;
+    confirm_delete(args.force, "node template", &region, &args.names)?;
+    let compute = session.compute().await?;
+    delete_all(&compute, "node template", &args.names, |name| {
+        let (compute, project, region) = (&compute, &project, &region);
+        async move { compute.delete_node_template(project, region, &name).await }
+    })
+    .await
+}
+
+async fn list_groups(session: &Session, args: NodeGroupListArgs) -> Result<()> {
+    let project = session.project(args.zonal.project.as_deref())?;
+    let zone = session.list_zone(&args.zonal, args.all_zones);
+    let compute = session.compute().await?;
+    let groups = compute
+        .list_node_groups(&project, zone.as_deref(), args.filter.as_ref())
+        .await?;
+    print_list(session.output, &groups)
+}
+
+async fn describe_group(session: &Session, args: NodeGroupArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let group = compute.get_node_group(&project, &zone, &args.name).await?;
+    print_one(session.output, &group)
+}
+
+async fn create_group(session: &Session, args: NodeGroupCreateArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let spec = NodeGroupSpec {
+        name: args.name.clone(),
+        description: args.description,
+        node_template: format!(
+            "projects/{project}/regions/{}/nodeTemplates/{}",
+            region_of(&zone),
+            args.node_template
+        ),
+        maintenance_policy: args.maintenance_policy,
+        autoscale_max: args.autoscale_max,
+    };
+    let compute = session.compute().await?;
+    let op = compute
+        .insert_node_group(&project, &zone, &spec.to_body(), args.size)
+        .await?;
+    if args.no_wait {
+        requested("Create", &op);
+        return Ok(());
+    }
+    wait_with_spinner(&compute, op, format!("Creating node group {}", args.name)).await?;
+    success(&format!(
+        "Node group {} created in {zone} with {} node(s)",
+        args.name, args.size
+    ));
+    Ok(())
+}
+
+async fn delete_groups(session: &Session, args: NodeGroupDeleteArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    confirm_delete(args.force, "node group", &zone, &args.names)?;
+    let compute = session.compute().await?;
+    delete_all(&compute, "node group", &args.names, |name| {
+        let (compute, project, zone) = (&compute, &project, &zone);
+        async move { compute.delete_node_group(project, zone, &name).await }
+    })
+    .await
+}
@@ -0,0 +1,62 @@
+use anyhow::Result;
+use gcectl_core::output::{print_list, print_one};
+
+use super::{Session, success, wait_with_spinner};
+use crate::cli::{OperationArgs, OperationListArgs, OperationsCommand};
+
+pub async fn run(session: &Session, cmd: OperationsCommand) -> Result<()> {
+    match cmd {
+        OperationsCommand::List(args) => list(session, args).await,
+        OperationsCommand::Describe(args) => describe(session, args).await,
+        OperationsCommand::Wait(args) => wait(session, args).await,
+    }
+}
+
+async fn list(session: &Session, args: OperationListArgs) -> Result<()> {
+    let project = session.project(args.scope.project.as_deref())?;
+    let compute = session.compute().await?;
+    let mut operations = match args.scope.scope() {
+        Some(scope) => {
+            compute
+                .list_operations(&project, &scope, args.filter.as_ref())
+                .await?
+        }
+        None => {
+            compute
+                .list_operations_all_scopes(&project, args.filter.as_ref())
+                .await?
+        }
+    };
+    if !args.all {
+        operations.retain(|op| !op.is_done());
+    }
+    // newest first; insertTime is RFC 3339 so it sorts as a string
+    operations.sort_by(|a, b| b.insert_time.cmp(&a.insert_time));
+    print_list(session.output, &operations)
+}
+
+async fn describe(session: &Session, args: OperationArgs) -> Result<()> {
+    let project = session.project(args.scope.project.as_deref())?;
+    let compute = session.compute().await?;
+    let op = compute
+        .get_operation(&project, args.scope.scope().as_ref(), &args.name)
+        .await?;
+    print_one(session.output, &op)
+}
+
+async fn wait(session: &Session, args: OperationArgs) -> Result<()> {
+    let project = session.project(args.scope.project.as_deref())?;
+    let compute = session.compute().await?;
+    let op = compute
+        .get_operation(&project, args.scope.scope().as_ref(), &args.name)
+        .await?;
+    let message = format!("Waiting for {} on {}", op.operation_type, op.target());
+    let op = wait_with_spinner(&compute, op, message).await?;
+    success(&format!(
+        "Operation {} ({} {}) done",
+        op.name,
+        op.operation_type,
+        op.target()
+    ));
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use anyhow::Result;
+use futures_util::future::try_join_all;
+use gcectl_core::osconfig::PatchStatus;
+use gcectl_core::output::{print_list, print_one};
+
+use super::{Session, with_spinner};
+use crate::cli::{OsCommand, OsInventoryArgs, OsPatchStatusArgs};
+
+pub async fn run(session: &Session, cmd: OsCommand) -> Result<()> {
+    match cmd {
+        OsCommand::Inventory(args) => inventory(session, args).await,
+        OsCommand::PatchStatus(args) => patch_status(session, args).await,
+    }
+}
+
+async fn inventory(session: &Session, args: OsInventoryArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let osconfig = session.osconfig().await?;
+    let inventory = osconfig.get_inventory(&project, &zone, &args.name).await?;
+    match (args.packages, args.updates) {
+        (true, _) => print_list(session.output, &inventory.installed()),
+        (_, true) => print_list(session.output, &inventory.updates()),
+        _ => print_one(session.output, &inventory),
+    }
+}
+
+/// Joins the zone's instances with their inventories and the newest patch
+/// jobs, so instances without the agent still show up.
+async fn patch_status(session: &Session, args: OsPatchStatusArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let osconfig = session.osconfig().await?;
+    let fetch = async {
+        let (instances, inventories, jobs) = tokio::try_join!(
+            compute.list_instances(&project, &zone, None),
+            osconfig.list_inventories(&project, &zone),
+            osconfig.list_patch_jobs(&project, args.jobs as usize),
+        )?;
+        let results = try_join_all(
+            jobs.iter()
+                .map(|job| osconfig.list_patch_job_instances(job)),
+        )
+        .await?;
+        anyhow::Ok((
+            instances,
+            inventories,
+            jobs.into_iter().zip(results).collect::<Vec<_>>(),
+        ))
+    };
+    let (instances, inventories, jobs) =
+        with_spinner(format!("Collecting patch status in {zone}"), fetch).await?;
+    let instances: Vec<(String, String)> = instances
+        .into_iter()
+        .map(|i| (i.name, i.id.unwrap_or_default()))
+        .collect();
+    print_list(
+        session.output,
+        &PatchStatus::collect(&instances, &inventories, &jobs),
+    )
+}
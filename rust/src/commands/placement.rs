@@ -0,0 +1,124 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::Utc;
+use gcectl_core::audit;
+use gcectl_core::compute::Compute;
+use gcectl_core::filter::Filter;
+use gcectl_core::output::print_list;
+use gcectl_core::placement::{self, Placement, Shape};
+use gcectl_core::resources::{Accelerator, short_name};
+
+use super::{Session, warning};
+use crate::cli::{PlacementCommand, PlacementSuggestArgs};
+
+pub async fn run(session: &Session, cmd: PlacementCommand) -> Result<()> {
+    match cmd {
+        PlacementCommand::Suggest(args) => suggest_zones(session, args).await,
+    }
+}
+
+async fn suggest_zones(session: &Session, args: PlacementSuggestArgs) -> Result<()> {
+    let project = session.project(args.regional.project.as_deref())?;
+    let region = session.region(args.regional.region.as_deref())?;
+    let compute = session.compute().await?;
+    let placements = suggest(
+        &compute,
+        &project,
+        &region,
+        &args.machine_type,
+        &args.accelerator,
+        args.spot,
+        args.since,
+    )
+    .await?;
+    if placement::ranked_zones(&placements).is_empty() {
+        warning(&format!(
+            "No zone in {region} offers {} with the accelerators asked for",
+            args.machine_type
+        ));
+    } else if let Some(headroom) = placements[0].headroom.filter(|h| *h < args.count) {
+        warning(&format!(
+            "The quota in {region} leaves room for {headroom} instance(s) of {}, not {}",
+            args.machine_type, args.count
+        ));
+    }
+    print_list(session.output, &placements)
+}
+
+/// Every zone of `region`, ranked for instances of `machine_type` with
+/// `accelerators` attached, counting stock-outs from the last `window`.
+pub async fn suggest(
+    compute: &Compute,
+    project: &str,
+    region: &str,
+    machine_type: &str,
+    accelerators: &[Accelerator],
+    spot: bool,
+    window: Duration,
+) -> Result<Vec<Placement>> {
+    let filter: Filter = format!("name={machine_type}").parse()?;
+    let (region, machine_types, accelerator_types) = tokio::try_join!(
+        compute.get_region(project, region),
+        compute.list_machine_types_all_zones(project, Some(&filter)),
+        async {
+            match accelerators.is_empty() {
+                true => Ok(Vec::new()),
+                false => {
+                    compute
+                        .list_accelerator_types_all_zones(project, None)
+                        .await
+                }
+            }
+        },
+    )?;
+    let mut shape = Shape {
+        machine_type: machine_type.to_string(),
+        spot,
+        ..Default::default()
+    };
+    if let Some(offered) = machine_types.first() {
+        shape.cpus = offered.guest_cpus;
+        shape.gpus.extend(
+            offered
+                .accelerators
+                .iter()
+                .map(|a| (a.guest_accelerator_type.clone(), a.guest_accelerator_count)),
+        );
+    }
+    shape.gpus.extend(
+        accelerators
+            .iter()
+            .map(|a| (a.accelerator_type.clone(), a.count)),
+    );
+    let headroom = shape.headroom(&region);
+
+    let with_machine_type: BTreeSet<&str> = machine_types.iter().map(|t| t.zone_name()).collect();
+    let offers_accelerators = |zone: &str| {
+        accelerators.iter().all(|wanted| {
+            accelerator_types
+                .iter()
+                .any(|t| t.zone_name() == zone && t.name == wanted.accelerator_type)
+        })
+    };
+    let since = Utc::now() - chrono::Duration::from_std(window).unwrap_or_default();
+    let entries = audit::read()?;
+    let placements = region
+        .zones
+        .iter()
+        .map(|url| {
+            let zone = short_name(url);
+            let (stockouts, last_stockout) = placement::stockouts(&entries, zone, since);
+            Placement {
+                rank: None,
+                zone: zone.to_string(),
+                offered: with_machine_type.contains(zone) && offers_accelerators(zone),
+                stockouts,
+                last_stockout,
+                headroom,
+            }
+        })
+        .collect();
+    Ok(placement::rank(placements))
+}
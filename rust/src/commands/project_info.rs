@@ -0,0 +1,96 @@
+use anyhow::{Result, bail};
+use gcectl_core::output::print_one;
+use gcectl_core::resources::project::UsageExportLocation;
+
+use super::{Session, requested, success, wait_with_spinner};
+use crate::cli::{
+    ProjectArgs, ProjectInfoCommand, SetDefaultServiceAccountArgs, SetUsageExportBucketArgs,
+};
+
+pub async fn run(session: &Session, cmd: ProjectInfoCommand) -> Result<()> {
+    match cmd {
+        ProjectInfoCommand::Describe(args) => describe(session, args).await,
+        ProjectInfoCommand::SetDefaultServiceAccount(args) => {
+            set_default_service_account(session, args).await
+        }
+        ProjectInfoCommand::SetUsageExportBucket(args) => {
+            set_usage_export_bucket(session, args).await
+        }
+    }
+}
+
+async fn describe(session: &Session, args: ProjectArgs) -> Result<()> {
+    let project = session.project(args.project.as_deref())?;
+    let compute = session.compute().await?;
+    let project = compute.get_project(&project).await?;
+    print_one(session.output, &project)
+}
+
+async fn set_default_service_account(
+    session: &Session,
+    args: SetDefaultServiceAccountArgs,
+) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    if !args.email.contains('@') {
+        bail!(
+            "'{}' is not a service account email, e.g. runner@{project}.iam.gserviceaccount.com",
+            args.email
+        );
+    }
+    let compute = session.compute().await?;
+    let op = compute
+        .set_default_service_account(&project, &args.email)
+        .await?;
+    if args.no_wait {
+        requested("Default service account change", &op);
+        return Ok(());
+    }
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Setting the default service account of project {project}"),
+    )
+    .await?;
+    success(&format!(
+        "New instances in project {project} run as {} by default",
+        args.email
+    ));
+    Ok(())
+}
+
+async fn set_usage_export_bucket(session: &Session, args: SetUsageExportBucketArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let location = UsageExportLocation {
+        bucket_name: args.bucket.as_deref().map(bucket_url),
+        report_name_prefix: args.prefix.clone(),
+    };
+    let compute = session.compute().await?;
+    let op = compute.set_usage_export_bucket(&project, &location).await?;
+    if args.no_wait {
+        requested("Usage export change", &op);
+        return Ok(());
+    }
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Updating usage export of project {project}"),
+    )
+    .await?;
+    match &location.bucket_name {
+        Some(bucket) => success(&format!(
+            "Usage reports of project {project} are exported to {bucket}"
+        )),
+        None => success(&format!(
+            "Stopped exporting usage reports of project {project}"
+        )),
+    }
+    Ok(())
+}
+
+// the API wants the bucket as a gs:// URL but gcloud also takes a bare name
+fn bucket_url(bucket: &str) -> String {
+    match bucket.strip_prefix("gs://") {
+        Some(name) => format!("gs://{}", name.trim_end_matches('/')),
+        None => format!("gs://{}", bucket.trim_end_matches('/')),
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::Result;
+use gcectl_core::output::print_list;
+
+use super::{
+    Session, metadata_entries, remove_metadata_keys, requested, success, wait_with_spinner,
+};
+use crate::cli::{
+    ProjectArgs, ProjectMetadataAddArgs, ProjectMetadataCommand, ProjectMetadataRemoveArgs,
+};
+
+pub async fn run(session: &Session, cmd: ProjectMetadataCommand) -> Result<()> {
+    match cmd {
+        ProjectMetadataCommand::List(args) => list(session, args).await,
+        ProjectMetadataCommand::Add(args) => add(session, args).await,
+        ProjectMetadataCommand::Remove(args) => remove(session, args).await,
+    }
+}
+
+async fn list(session: &Session, args: ProjectArgs) -> Result<()> {
+    let project = session.project(args.project.as_deref())?;
+    let compute = session.compute().await?;
+    let metadata = compute
+        .get_project(&project)
+        .await?
+        .common_instance_metadata
+        .unwrap_or_default();
+    print_list(session.output, &metadata.items)
+}
+
+async fn add(session: &Session, args: ProjectMetadataAddArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let entries = metadata_entries(&args.entries)?;
+    let compute = session.compute().await?;
+    let op = compute
+        .update_common_instance_metadata(&project, |metadata| {
+            for (key, value) in &entries {
+                metadata.set(key, value.clone());
+            }
+            Ok(())
+        })
+        .await?;
+    if args.no_wait {
+        requested("Metadata update", &op);
+        return Ok(());
+    }
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Updating common metadata of project {project}"),
+    )
+    .await?;
+    success(&format!(
+        "Set {} metadata entry(s) on project {project}",
+        entries.len()
+    ));
+    Ok(())
+}
+
+async fn remove(session: &Session, args: ProjectMetadataRemoveArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let keys = &args.keys.keys;
+    let compute = session.compute().await?;
+    let op = compute
+        .update_common_instance_metadata(&project, |metadata| remove_metadata_keys(metadata, keys))
+        .await?;
+    if args.no_wait {
+        requested("Metadata update", &op);
+        return Ok(());
+    }
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Updating common metadata of project {project}"),
+    )
+    .await?;
+    success(&format!(
+        "Removed {} from project {project}",
+        keys.join(", ")
+    ));
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use std::cmp::Ordering;
+
+use anyhow::{Result, bail};
+use comfy_table::presets::UTF8_FULL;
+use comfy_table::{Cell, Color, Table};
+use gcectl_core::events;
+use gcectl_core::output::{self, OutputFormat, Render, print_list};
+use gcectl_core::resources::Quota;
+
+use super::{Session, warning};
+use crate::cli::{QuotasCommand, QuotasListArgs};
+
+pub async fn run(session: &Session, cmd: QuotasCommand) -> Result<()> {
+    match cmd {
+        QuotasCommand::List(args) => list(session, args).await,
+    }
+}
+
+async fn list(session: &Session, args: QuotasListArgs) -> Result<()> {
+    let project = session.project(args.regional.project.as_deref())?;
+    let compute = session.compute().await?;
+    let (scope, mut quotas) = match args.global {
+        true => (project.clone(), compute.get_project(&project).await?.quotas),
+        false => {
+            let region = session.region(args.regional.region.as_deref())?;
+            let quotas = compute.get_region(&project, &region).await?.quotas;
+            (region, quotas)
+        }
+    };
+    if !args.all {
+        quotas.retain(|q| q.usage > 0.0);
+    }
+    // fullest first, so what blocks a launch is at the top
+    quotas.sort_by(|a, b| {
+        let (a_pct, b_pct) = (a.percent().unwrap_or(0.0), b.percent().unwrap_or(0.0));
+        b_pct
+            .partial_cmp(&a_pct)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.metric.cmp(&b.metric))
+    });
+
+    match session.output {
+        OutputFormat::Table if !output::quiet() && !events::enabled() => {
+            println!("{}", table(&quotas, args.warn_above))
+        }
+        format => print_list(format, &quotas)?,
+    }
+    let above = |threshold: f64| -> Vec<&Quota> {
+        quotas
+            .iter()
+            .filter(|q| q.percent().is_some_and(|p| p > threshold))
+            .collect()
+    };
+    for quota in above(args.warn_above) {
+        warning(&format!(
+            "{} in {scope} is at {:.0}% of its limit ({})",
+            quota.metric,
+            quota.percent().unwrap_or_default(),
+            quota.summary()
+        ));
+    }
+    if let Some(threshold) = args.fail_above {
+        let failing = above(threshold);
+        if !failing.is_empty() {
+            let metrics: Vec<&str> = failing.iter().map(|q| q.metric.as_str()).collect();
+            bail!(
+                "{} quota(s) in {scope} above {threshold}%: {}",
+                failing.len(),
+                metrics.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Quota table with the percentage in yellow above `warn_above`, and in
+/// red once the limit is reached.
+fn table(quotas: &[Quota], warn_above: f64) -> Table {
+    let mut table = Table::new();
+    table.load_style(UTF8_FULL).set_header(Quota::headers());
+    for quota in quotas {
+        let mut cells: Vec<Cell> = quota.row().into_iter().map(Cell::new).collect();
+        let color = match quota.percent() {
+            Some(p) if p >= 100.0 => Some(Color::Red),
+            Some(p) if p > warn_above => Some(Color::Yellow),
+            _ => None,
+        };
+        if let (Some(color), Some(percent)) = (color, cells.pop()) {
+            cells.push(percent.fg(color));
+        }
+        table.add_row(cells);
+    }
+    table
+}
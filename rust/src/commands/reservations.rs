@@ -0,0 +1,60 @@
+use anyhow::Result;
+use gcectl_core::output::{print_list, print_one};
+use gcectl_core::resources::reservation::ReservationSpec;
+
+use super::{Session, requested, success, wait_with_spinner};
+use crate::cli::{
+    ReservationArgs, ReservationCreateArgs, ReservationListArgs, ReservationsCommand,
+};
+
+pub async fn run(session: &Session, cmd: ReservationsCommand) -> Result<()> {
+    match cmd {
+        ReservationsCommand::List(args) => list(session, args).await,
+        ReservationsCommand::Describe(args) => describe(session, args).await,
+        ReservationsCommand::Create(args) => create(session, args).await,
+    }
+}
+
+async fn list(session: &Session, args: ReservationListArgs) -> Result<()> {
+    let project = session.project(args.zonal.project.as_deref())?;
+    let zone = session.list_zone(&args.zonal, args.all_zones);
+    let compute = session.compute().await?;
+    let reservations = compute
+        .list_reservations(&project, zone.as_deref(), args.filter.as_ref())
+        .await?;
+    print_list(session.output, &reservations)
+}
+
+async fn describe(session: &Session, args: ReservationArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let reservation = compute.get_reservation(&project, &zone, &args.name).await?;
+    print_one(session.output, &reservation)
+}
+
+async fn create(session: &Session, args: ReservationCreateArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let spec = ReservationSpec {
+        name: args.name.clone(),
+        description: args.description,
+        machine_type: args.machine_type,
+        count: args.count,
+        accelerators: args.accelerator,
+        min_cpu_platform: args.min_cpu_platform,
+        specific_only: args.require_specific,
+    };
+    let compute = session.compute().await?;
+    let op = compute
+        .insert_reservation(&project, &zone, &spec.to_body())
+        .await?;
+    if args.no_wait {
+        requested("Create", &op);
+        return Ok(());
+    }
+    wait_with_spinner(&compute, op, format!("Creating reservation {}", args.name)).await?;
+    success(&format!(
+        "Reservation {} created in {zone} for {} VM(s)",
+        args.name, args.count
+    ));
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use anyhow::{Result, bail};
+use gcectl_core::compute::Compute;
+use gcectl_core::output::{print_list, print_one};
+use gcectl_core::resources::region_of;
+use gcectl_core::resources::resource_policy::{
+    InstanceScheduleSpec, SnapshotCadence, SnapshotScheduleSpec,
+};
+
+use super::{Session, confirm_delete, delete_all, requested, success, wait_with_spinner};
+use crate::cli::{
+    InstanceScheduleCreateArgs, ResourcePoliciesCommand, ResourcePolicyArgs,
+    ResourcePolicyAttachArgs, ResourcePolicyDeleteArgs, ResourcePolicyListArgs,
+    SnapshotScheduleCreateArgs,
+};
+
+pub async fn run(session: &Session, cmd: ResourcePoliciesCommand) -> Result<()> {
+    match cmd {
+        ResourcePoliciesCommand::List(args) => list(session, args).await,
+        ResourcePoliciesCommand::Describe(args) => describe(session, args).await,
+        ResourcePoliciesCommand::CreateSnapshotSchedule(args) => {
+            create_snapshot_schedule(session, args).await
+        }
+        ResourcePoliciesCommand::CreateInstanceSchedule(args) => {
+            create_instance_schedule(session, args).await
+        }
+        ResourcePoliciesCommand::Attach(args) => attach(session, args, true).await,
+        ResourcePoliciesCommand::Detach(args) => attach(session, args, false).await,
+        ResourcePoliciesCommand::Delete(args) => delete(session, args).await,
+    }
+}
+
+async fn list(session: &Session, args: ResourcePolicyListArgs) -> Result<()> {
+    let project = session.project(args.regional.project.as_deref())?;
+    let region = session.list_region(&args.regional, args.all_regions);
+    let compute = session.compute().await?;
+    let policies = compute
+        .list_resource_policies(&project, region.as_deref(), args.filter.as_ref())
+        .await?;
+    print_list(session.output, &policies)
+}
+
+async fn describe(session: &Session, args: ResourcePolicyArgs) -> Result<()> {
+    let project = session.project(args.regional.project.as_deref())?;
+    let region = session.region(args.regional.region.as_deref())?;
+    let compute = session.compute().await?;
+    let policy = compute
+        .get_resource_policy(&project, &region, &args.name)
+        .await?;
+    print_one(session.output, &policy)
+}
+
+async fn create_snapshot_schedule(
+    session: &Session,
+    args: SnapshotScheduleCreateArgs,
+) -> Result<()> {
+    let project = session.project(args.regional.project.as_deref())?;
+    let region = session.region(args.regional.region.as_deref())?;
+    let cadence = match (args.hourly, args.daily) {
+        (Some(hours), _) => SnapshotCadence::Hourly(hours),
+        (None, days) => SnapshotCadence::Daily(days.unwrap_or(1)),
+    };
+    let spec = SnapshotScheduleSpec {
+        name: args.name.clone(),
+        description: args.description,
+        cadence,
+        start_time: args.start_time,
+        retention_days: args.retention_days,
+        storage_locations: args.storage_location,
+    };
+    let compute = session.compute().await?;
+    insert(&compute, &project, &region, &args.name, &spec.to_body()).await
+}
+
+async fn create_instance_schedule(
+    session: &Session,
+    args: InstanceScheduleCreateArgs,
+) -> Result<()> {
+    let project = session.project(args.regional.project.as_deref())?;
+    let region = session.region(args.regional.region.as_deref())?;
+    let spec = InstanceScheduleSpec {
+        name: args.name.clone(),
+        description: args.description,
+        start: args.start,
+        stop: args.stop,
+        time_zone: args.timezone,
+    };
+    let compute = session.compute().await?;
+    insert(&compute, &project, &region, &args.name, &spec.to_body()).await
+}
+
+async fn insert(
+    compute: &Compute,
+    project: &str,
+    region: &str,
+    name: &str,
+    body: &serde_json::Value,
+) -> Result<()> {
+    let op = compute
+        .insert_resource_policy(project, region, body)
+        .await?;
+    wait_with_spinner(compute, op, format!("Creating resource policy {name}")).await?;
+    success(&format!("Resource policy {name} created in {region}"));
+    Ok(())
+}
+
+/// Attaches or detaches a policy from the zone's region to a disk or an
+/// instance in that zone.
+async fn attach(session: &Session, args: ResourcePolicyAttachArgs, attach: bool) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let (collection, kind, name) = match (&args.disk, &args.instance) {
+        (Some(disk), _) => ("disks", "disk", disk),
+        (None, Some(instance)) => ("instances", "instance", instance),
+        (None, None) => bail!("pass --disk or --instance"),
+    };
+    let region = region_of(&zone);
+    let policy = format!(
+        "projects/{project}/regions/{region}/resourcePolicies/{}",
+        args.policy
+    );
+    let compute = session.compute().await?;
+    let op = compute
+        .set_resource_policy(&project, &zone, collection, name, &policy, attach)
+        .await?;
+    if kind == "instance" {
+        session.forget_instances(&project);
+    }
+    let (verb, preposition, done) = match attach {
+        true => ("Attach", "to", "attached"),
+        false => ("Detach", "from", "detached"),
+    };
+    if args.no_wait {
+        requested(verb, &op);
+        return Ok(());
+    }
+    let target = format!("{preposition} {kind} {name}");
+    let message = format!("{verb}ing resource policy {} {target}", args.policy);
+    wait_with_spinner(&compute, op, message).await?;
+    success(&format!("Resource policy {} {done} {target}", args.policy));
+    Ok(())
+}
+
+async fn delete(session: &Session, args: ResourcePolicyDeleteArgs) -> Result<()> {
+    let project = session.project(args.regional.project.as_deref())?;
+    let region = session.region(args.regional.region.as_deref())?;
+    confirm_delete(args.force, "resource policy", &region, &args.names)?;
+    let compute = session.compute().await?;
+    delete_all(&compute, "resource policy", &args.names, |name| {
+        let (compute, project, region) = (&compute, &project, &region);
+        async move { compute.delete_resource_policy(project, region, &name).await }
+    })
+    .await
+}
@@ -0,0 +1,163 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use chrono::{Local, Utc};
+use gcectl_core::compute::Compute;
+use gcectl_core::filter::Filter;
+use gcectl_core::output::{print_list, print_note};
+use gcectl_core::schedule::{Action, Rule, ScheduledRule, Schedules};
+use tracing::warn;
+
+use super::{Drive, Session, Verb, apply_all, failure, success};
+use crate::cli::{ScheduleAddArgs, ScheduleCommand};
+
+// how often `schedule run` wakes up to reload rules and check for due ones
+const TICK: Duration = Duration::from_secs(30);
+
+pub async fn run(session: &Session, cmd: ScheduleCommand) -> Result<()> {
+    match cmd {
+        ScheduleCommand::Add(args) => add(session, args),
+        ScheduleCommand::List => list(session),
+        ScheduleCommand::Remove { name } => remove(&name),
+        ScheduleCommand::Trigger { name } => trigger(session, &name).await,
+        ScheduleCommand::Run => daemon(session).await,
+    }
+}
+
+fn add(session: &Session, args: ScheduleAddArgs) -> Result<()> {
+    let rule = Rule {
+        action: args.action,
+        at: args.at,
+        days: args.days,
+        timezone: args.timezone,
+        // pinned now so the daemon does not depend on the active profile
+        project: session.project(args.zonal.project.as_deref())?,
+        zone: args.zonal.zone,
+        filter: args.filter,
+    };
+    let mut schedules = Schedules::load()?;
+    let replaced = schedules.rules.insert(args.name.clone(), rule).is_some();
+    schedules.save()?;
+    match replaced {
+        true => success(&format!("Schedule {} updated", args.name)),
+        false => success(&format!("Schedule {} added", args.name)),
+    }
+    Ok(())
+}
+
+fn list(session: &Session) -> Result<()> {
+    let now = Utc::now();
+    let rules: Vec<_> = Schedules::load()?
+        .rules
+        .into_iter()
+        .map(|(name, rule)| ScheduledRule {
+            next_run: rule.next_fire(now),
+            name,
+            rule,
+        })
+        .collect();
+    print_list(session.output, &rules)
+}
+
+fn remove(name: &str) -> Result<()> {
+    let mut schedules = Schedules::load()?;
+    if schedules.rules.remove(name).is_none() {
+        return Err(anyhow!("schedule `{name}` does not exist"));
+    }
+    schedules.save()?;
+    success(&format!("Schedule {name} removed"));
+    Ok(())
+}
+
+async fn trigger(session: &Session, name: &str) -> Result<()> {
+    let schedules = Schedules::load()?;
+    let rule = schedules
+        .rules
+        .get(name)
+        .ok_or_else(|| anyhow!("schedule `{name}` does not exist"))?;
+    let compute = session.compute().await?;
+    session.forget_instances(&rule.project);
+    execute(&compute, name, rule).await
+}
+
+/// Wakes every [`TICK`], reloading the rules file so edits take effect
+/// without a restart, and runs each rule whose time fell since the last tick.
+async fn daemon(session: &Session) -> Result<()> {
+    let compute = session.compute().await?;
+    print_note(&format!(
+        "Running schedules from {} (Ctrl-C to exit)",
+        Schedules::path()?.display()
+    ));
+    let mut since = Utc::now();
+    loop {
+        tokio::time::sleep(TICK).await;
+        let now = Utc::now();
+        let schedules = match Schedules::load() {
+            Ok(schedules) => schedules,
+            Err(err) => {
+                warn!("cannot reload schedules, retrying next tick: {err:#}");
+                continue;
+            }
+        };
+        for (name, rule) in &schedules.rules {
+            if rule.is_due(since, now) {
+                print_note(&format!(
+                    "{} running schedule {name}",
+                    Local::now().format("%F %T")
+                ));
+                session.forget_instances(&rule.project);
+                if let Err(err) = execute(&compute, name, rule).await {
+                    failure(&format!("schedule {name}: {err:#}"));
+                }
+            }
+        }
+        since = now;
+    }
+}
+
+/// Applies the rule's action to every matching instance not already in the
+/// target state.
+async fn execute(compute: &Compute, name: &str, rule: &Rule) -> Result<()> {
+    let filter = rule
+        .filter
+        .as_deref()
+        .map(str::parse::<Filter>)
+        .transpose()?;
+    let mut instances = compute
+        .list_instances_in(&rule.project, rule.zone.as_deref(), filter.as_ref())
+        .await?;
+    instances.retain(|i| i.status == rule.applies_to_status());
+    if instances.is_empty() {
+        print_note(&format!("Schedule {name}: no instances to {}", rule.action));
+        return Ok(());
+    }
+
+    let names: Vec<String> = instances.iter().map(|i| i.name.clone()).collect();
+    let requests = instances.iter().map(|i| async move {
+        match rule.action {
+            Action::Start => {
+                compute
+                    .start_instance(&rule.project, i.zone_name(), &i.name)
+                    .await
+            }
+            Action::Stop => {
+                compute
+                    .stop_instance(&rule.project, i.zone_name(), &i.name)
+                    .await
+            }
+        }
+    });
+    let verb = match rule.action {
+        Action::Start => &Verb::START,
+        Action::Stop => &Verb::STOP,
+    };
+    apply_all(
+        compute,
+        "instance",
+        verb,
+        &names,
+        requests,
+        Drive::default(),
+    )
+    .await
+}
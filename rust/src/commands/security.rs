@@ -0,0 +1,49 @@
+use anyhow::Result;
+use gcectl_core::output::print_list;
+use gcectl_core::security::{self, Report};
+use tracing::debug;
+
+use super::Session;
+use crate::cli::{SecurityAuditArgs, SecurityCommand};
+
+pub async fn run(session: &Session, cmd: SecurityCommand) -> Result<()> {
+    match cmd {
+        SecurityCommand::Audit(args) => audit(session, args).await,
+    }
+}
+
+async fn audit(session: &Session, args: SecurityAuditArgs) -> Result<()> {
+    let project = session.project(args.zonal.project.as_deref())?;
+    let zone = session.list_zone(&args.zonal, args.all_zones);
+    let compute = session.compute().await?;
+    let (instances, info) = tokio::join!(
+        session.list_instances(&compute, &project, zone.as_deref(), args.filter.as_ref()),
+        compute.get_project(&project),
+    );
+    let instances = instances?;
+    // without project metadata, only what instances set themselves counts
+    let info = info.inspect_err(|err| debug!("auditing without project metadata: {err:#}"));
+    let metadata = info
+        .as_ref()
+        .ok()
+        .and_then(|p| p.common_instance_metadata.as_ref());
+
+    let mut reports: Vec<Report> = instances
+        .iter()
+        .map(|instance| security::audit(instance, metadata))
+        .collect();
+    reports.sort_by(|a, b| a.score.cmp(&b.score).then_with(|| a.name.cmp(&b.name)));
+    print_list(session.output, &reports)?;
+
+    let flagged = reports.iter().filter(|r| !r.findings.is_empty()).count();
+    match reports.first() {
+        Some(lowest) => eprintln!(
+            "{flagged} of {} instance(s) have findings; lowest score {} ({})",
+            reports.len(),
+            lowest.score,
+            lowest.name
+        ),
+        None => eprintln!("No instances to audit"),
+    }
+    Ok(())
+}
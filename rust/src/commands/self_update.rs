@@ -0,0 +1,37 @@
+use std::env;
+
+use anyhow::{Context, Result, bail};
+use gcectl_core::output::print_note;
+
+use super::success;
+use crate::cli::SelfUpdateArgs;
+use crate::prompt;
+use crate::self_update::{Updater, Version};
+
+pub async fn run(args: SelfUpdateArgs) -> Result<()> {
+    let current = Version::current();
+    let updater = Updater::new()?;
+    let release = updater.latest().await?;
+    let latest = release.version()?;
+    if latest <= current && !args.force {
+        print_note(&format!("gcectl {current} is up to date"));
+        return Ok(());
+    }
+    if args.check {
+        print_note(&format!(
+            "gcectl {latest} is available (installed: {current}); run `gcectl self-update`"
+        ));
+        return Ok(());
+    }
+    let target = env::current_exe().context("cannot locate the running binary")?;
+    let question = format!(
+        "Replace {} ({current}) with gcectl {latest}?",
+        target.display()
+    );
+    if !args.yes && !prompt::confirm(&question)? {
+        bail!("aborted");
+    }
+    updater.install(&release, &target).await?;
+    success(&format!("Updated gcectl {current} -> {latest}"));
+    Ok(())
+}
@@ -0,0 +1,16 @@
+use anyhow::Result;
+use gcectl_core::output::print_list;
+
+use super::Session;
+use crate::cli::ServiceAccountsCommand;
+
+pub async fn run(session: &Session, cmd: ServiceAccountsCommand) -> Result<()> {
+    match cmd {
+        ServiceAccountsCommand::List(args) => {
+            let project = session.project(args.project.as_deref())?;
+            let iam = session.iam().await?;
+            let accounts = iam.list_service_accounts(&project).await?;
+            print_list(session.output, &accounts)
+        }
+    }
+}
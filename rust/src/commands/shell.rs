@@ -0,0 +1,112 @@
+use std::ffi::OsString;
+
+use anyhow::{Result, bail};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches};
+use gcectl_core::cancel::{self, Deadline};
+
+use super::{Session, dispatch};
+use crate::alias;
+use crate::cli::{Cli, Command, ShellArgs};
+use crate::shell::LineEditor;
+
+// global flags a line may give for itself; the rest hold for the session
+const LINE_FLAGS: &[&str] = &["output", "dry_run", "timeout"];
+
+/// Reads commands until `exit` or the end of input and runs each in this
+/// session, so credentials, resolved defaults, and open connections carry
+/// over from one to the next. A failed command only prints its error.
+pub async fn run(session: &Session, args: ShellArgs) -> Result<()> {
+    let mut editor = LineEditor::new(!args.no_history);
+    let prompt = prompt(session);
+    loop {
+        // crossterm's reads block, so keep them off the async scheduler
+        let line = tokio::task::block_in_place(|| editor.read_line(&prompt))?;
+        let Some(line) = line else {
+            return Ok(());
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        editor.add(line);
+        if matches!(line, "exit" | "quit") {
+            return Ok(());
+        }
+        let result = match parse(session, line) {
+            Ok(Some(cli)) => run_line(session, line, cli).await,
+            // clap printed its error or the help asked for
+            Ok(None) => continue,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            crate::report(&err);
+        }
+    }
+}
+
+// `gcectl:project/zone> `, from the defaults commands resolve to
+fn prompt(session: &Session) -> String {
+    let context = [session.project(None), session.zone(None)]
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect::<Vec<_>>()
+        .join("/");
+    match context.is_empty() {
+        true => "gcectl> ".to_string(),
+        false => format!("gcectl:{context}> "),
+    }
+}
+
+/// `line` as a command line, with aliases expanded; `None` when clap
+/// rejected it or printed help, having said why.
+fn parse(session: &Session, line: &str) -> Result<Option<Cli>> {
+    let mut args: Vec<OsString> = vec!["gcectl".into()];
+    args.extend(alias::split(line)?.into_iter().map(OsString::from));
+    let args = alias::expand(args, &session.config.alias)?;
+    let command = Cli::command();
+    let matches = match command.clone().try_get_matches_from(args) {
+        Ok(matches) => matches,
+        Err(err) => {
+            let _ = err.print();
+            return Ok(None);
+        }
+    };
+    let fixed = command.get_arguments().find(|arg| {
+        let id = arg.get_id().as_str();
+        arg.is_global_set()
+            && !LINE_FLAGS.contains(&id)
+            && matches.value_source(id) == Some(ValueSource::CommandLine)
+    });
+    if let Some(arg) = fixed {
+        let flag = arg.get_long().unwrap_or(arg.get_id().as_str());
+        bail!("--{flag} holds for the whole shell; give it to `gcectl shell` instead");
+    }
+    let cli = Cli::from_arg_matches(&matches)?;
+    if matches!(cli.command, Command::Shell(_)) {
+        bail!("already in a gcectl shell");
+    }
+    Ok(Some(cli))
+}
+
+// runs `cli`, parsed from `text`, in a copy of the session with the line's
+// own flags applied; Ctrl-C and --timeout end the command, not the shell
+async fn run_line(session: &Session, text: &str, cli: Cli) -> Result<()> {
+    let mut line = session.clone();
+    line.command_line = text.to_string();
+    if let Some(output) = cli.output {
+        line.output = output;
+    }
+    line.dry_run |= cli.dry_run;
+    line.context = line.context.with_flags(cli.project, cli.zone, cli.region);
+    line.deadline = match cli.timeout {
+        Some(timeout) => Some(Deadline::after(timeout)),
+        None => session.deadline.map(|d| Deadline::after(d.timeout)),
+    };
+    let deadline = line.deadline;
+    tokio::select! {
+        biased;
+        result = Box::pin(dispatch(&line, cli.command)) => result,
+        err = cancel::cancelled(deadline, None) => Err(err.into()),
+    }
+}
@@ -0,0 +1,187 @@
+use anyhow::{Context, Result};
+use gcectl_core::console::Resource;
+use gcectl_core::labels::LabelEdit;
+use gcectl_core::output::{ListPrinter, print_one};
+use gcectl_core::resources::disk::DiskSource;
+use gcectl_core::resources::{Disk, Snapshot};
+
+use super::{
+    Session, confirm_delete, delete_all, finish_label_edit, open_url, requested, success,
+    wait_with_spinner,
+};
+use crate::cli::{
+    SnapshotAddLabelsArgs, SnapshotCreateArgs, SnapshotDeleteArgs, SnapshotDescribeArgs,
+    SnapshotListArgs, SnapshotOpenArgs, SnapshotRemoveLabelsArgs, SnapshotRestoreArgs,
+    SnapshotsCommand,
+};
+
+pub async fn run(session: &Session, cmd: SnapshotsCommand) -> Result<()> {
+    match cmd {
+        SnapshotsCommand::List(args) => list(session, args).await,
+        SnapshotsCommand::Describe(args) => describe(session, args).await,
+        SnapshotsCommand::Open(args) => open(session, args),
+        SnapshotsCommand::Create(args) => create(session, args).await,
+        SnapshotsCommand::Restore(args) => restore(session, args).await,
+        SnapshotsCommand::Delete(args) => delete(session, args).await,
+        SnapshotsCommand::AddLabels(args) => add_labels(session, args).await,
+        SnapshotsCommand::RemoveLabels(args) => remove_labels(session, args).await,
+    }
+}
+
+async fn add_labels(session: &Session, args: SnapshotAddLabelsArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let edit = LabelEdit::set(args.labels.labels)?;
+    let compute = session.compute().await?;
+    let op = compute
+        .update_snapshot_labels(&project, &args.name, &edit)
+        .await?;
+    finish_label_edit(
+        &compute,
+        op,
+        "snapshot",
+        &args.name,
+        &edit,
+        args.labels.no_wait,
+    )
+    .await
+}
+
+async fn remove_labels(session: &Session, args: SnapshotRemoveLabelsArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let edit = LabelEdit::remove(args.keys.keys);
+    let compute = session.compute().await?;
+    let op = compute
+        .update_snapshot_labels(&project, &args.name, &edit)
+        .await?;
+    finish_label_edit(
+        &compute,
+        op,
+        "snapshot",
+        &args.name,
+        &edit,
+        args.keys.no_wait,
+    )
+    .await
+}
+
+async fn list(session: &Session, args: SnapshotListArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let mut printer = ListPrinter::stdout(session.output);
+    let listed = compute
+        .list_snapshot_pages(
+            &project,
+            args.filter.as_ref(),
+            args.paging.paging(),
+            |page| printer.page(&page),
+        )
+        .await;
+    printer.close::<Snapshot, _>(listed)
+}
+
+async fn describe(session: &Session, args: SnapshotDescribeArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let snapshot = compute.get_snapshot(&project, &args.name).await?;
+    print_one(session.output, &snapshot)
+}
+
+/// Prints the console URL of the snapshot, opening it with `--web`.
+fn open(session: &Session, args: SnapshotOpenArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let url = Resource::Snapshot { name: &args.name }.url(&project);
+    open_url(&url, args.web)
+}
+
+async fn create(session: &Session, args: SnapshotCreateArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let body = Snapshot::create_request(
+        &args.name,
+        args.description.as_deref(),
+        &args.labels,
+        args.storage_location.as_deref(),
+        args.kms_key.as_deref(),
+    );
+    let compute = session.compute().await?;
+    let op = compute
+        .create_snapshot(&project, &zone, &args.source_disk, &body)
+        .await?;
+    if args.no_wait {
+        requested("Snapshot", &op);
+        return Ok(());
+    }
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("Snapshotting disk {} as {}", args.source_disk, args.name),
+    )
+    .await?;
+    success(&format!("Snapshot {} created", args.name));
+    Ok(())
+}
+
+/// Creates a disk from the snapshot, then attaches it when `--attach-to`
+/// is given; each step waits for the one before.
+async fn restore(session: &Session, args: SnapshotRestoreArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let body = Disk::create_request(
+        &args.as_disk,
+        &zone,
+        args.size,
+        args.disk_type.as_deref(),
+        &DiskSource::Snapshot(args.snapshot.clone()),
+        &[],
+        None,
+    );
+    let steps = if args.attach_to.is_some() { 2 } else { 1 };
+    let compute = session.compute().await?;
+    let op = compute.insert_disk(&project, &zone, &body).await?;
+    wait_with_spinner(
+        &compute,
+        op,
+        format!(
+            "[1/{steps}] Creating disk {} from snapshot {}",
+            args.as_disk, args.snapshot
+        ),
+    )
+    .await?;
+    success(&format!(
+        "Disk {} restored from snapshot {}",
+        args.as_disk, args.snapshot
+    ));
+    let Some(instance) = args.attach_to else {
+        return Ok(());
+    };
+
+    let body = Disk::attach_request(
+        &project,
+        &zone,
+        &args.as_disk,
+        args.device_name.as_deref(),
+        args.mode,
+    );
+    let op = compute
+        .attach_disk(&project, &zone, &instance, &body)
+        .await
+        .with_context(|| format!("disk {} was restored but not attached", args.as_disk))?;
+    wait_with_spinner(
+        &compute,
+        op,
+        format!("[2/2] Attaching disk {} to {instance}", args.as_disk),
+    )
+    .await
+    .with_context(|| format!("disk {} was restored but not attached", args.as_disk))?;
+    success(&format!("Disk {} attached to {instance}", args.as_disk));
+    Ok(())
+}
+
+async fn delete(session: &Session, args: SnapshotDeleteArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    confirm_delete(args.force, "snapshot", &project, &args.names)?;
+    let compute = session.compute().await?;
+    delete_all(&compute, "snapshot", &args.names, |name| {
+        let (compute, project) = (&compute, &project);
+        async move { compute.delete_snapshot(project, &name).await }
+    })
+    .await
+}
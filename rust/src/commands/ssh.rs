@@ -0,0 +1,217 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use futures_util::stream::{self, StreamExt};
+use gcectl_core::batch;
+use gcectl_core::compute::Compute;
+use gcectl_core::oslogin;
+use gcectl_core::resources::Instance;
+use gcectl_core::ssh::{self, Route, SshTarget};
+use tracing::debug;
+
+use super::{Session, failure, success, warning};
+use crate::cli::{RsyncArgs, RunArgs, ScpArgs, SshArgs, SshConnectArgs};
+
+const SSH_PORT: u16 = 22;
+
+pub async fn run(session: &Session, args: SshArgs) -> Result<()> {
+    let (user, name) = ssh::split_user(&args.target);
+    let target = resolve(session, &args.connect, user, name).await?;
+    ssh::exec("ssh", &target.ssh_args(&args.ssh_args))
+}
+
+/// Runs the command on every selected instance, `--parallel` at a time,
+/// with each output line prefixed by the instance it came from. Fails
+/// unless the command exited 0 everywhere.
+pub async fn run_everywhere(session: &Session, args: RunArgs) -> Result<()> {
+    let (project, zone) = session.zonal(&args.connect.zonal)?;
+    let compute = session.compute().await?;
+    // not the cached listing: stopped or deleted instances cannot answer
+    let listed = compute
+        .list_instances(&project, &zone, args.selection.filter.as_ref())
+        .await?;
+    let names: Vec<String> = listed.iter().map(|i| i.name.clone()).collect();
+    let selected = batch::select(&args.selection.names, &names)?;
+    let (running, stopped): (Vec<Instance>, Vec<Instance>) = listed
+        .into_iter()
+        .filter(|i| selected.contains(&i.name))
+        .partition(|i| i.status == "RUNNING");
+    for instance in &stopped {
+        warning(&format!(
+            "skipping {}: it is {}",
+            instance.name, instance.status
+        ));
+    }
+    if running.is_empty() {
+        bail!("no running instances in {zone} match the selection");
+    }
+    let targets = resolve_all(
+        session,
+        &compute,
+        &args.connect,
+        (&project, &zone),
+        args.user.as_deref(),
+        &running,
+    )
+    .await?;
+
+    let width = running.iter().map(|i| i.name.len()).max().unwrap_or(0);
+    let results: Vec<(&str, Result<()>)> = stream::iter(running.iter().zip(&targets))
+        .map(|(instance, target)| async {
+            let prefix = format!("{:width$} | ", instance.name);
+            let args = target.command_args(&args.command);
+            (
+                instance.name.as_str(),
+                ssh::run_prefixed("ssh", &args, &prefix).await,
+            )
+        })
+        .buffer_unordered(args.parallel as usize)
+        .collect()
+        .await;
+    let failed: Vec<String> = results
+        .into_iter()
+        .filter_map(|(name, result)| result.err().map(|err| format!("{name}: {err:#}")))
+        .collect();
+    for failed in &failed {
+        failure(failed);
+    }
+    if !failed.is_empty() {
+        bail!(
+            "the command failed on {} of {} instance(s)",
+            failed.len(),
+            running.len()
+        );
+    }
+    success(&format!("Exited 0 on {} instance(s)", running.len()));
+    Ok(())
+}
+
+pub async fn scp(session: &Session, args: ScpArgs) -> Result<()> {
+    let (user, name) = ssh::transfer_target(&args.paths)?;
+    let target = resolve(session, &args.connect, user, name).await?;
+    ssh::exec(
+        "scp",
+        &target.scp_args(&args.paths, args.recurse, &args.scp_args),
+    )
+}
+
+pub async fn rsync(session: &Session, args: RsyncArgs) -> Result<()> {
+    let (user, name) = ssh::transfer_target(&args.paths)?;
+    let target = resolve(session, &args.connect, user, name).await?;
+    ssh::exec("rsync", &target.rsync_args(&args.paths, &args.rsync_args))
+}
+
+/// Looks up instance `name` and works out how to reach it as `user`.
+async fn resolve(
+    session: &Session,
+    args: &SshConnectArgs,
+    user: Option<&str>,
+    name: &str,
+) -> Result<SshTarget> {
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let compute = session.compute().await?;
+    let instance = session
+        .get_instance(&compute, &project, &zone, name)
+        .await?;
+    let mut targets = resolve_all(
+        session,
+        &compute,
+        args,
+        (&project, &zone),
+        user,
+        std::slice::from_ref(&instance),
+    )
+    .await?;
+    Ok(targets.remove(0))
+}
+
+/// Works out how to reach each of `instances` in `project` and `zone` as
+/// `user`, or else as the OS Login or local user. Project metadata and the
+/// OS Login account are looked up at most once for the lot.
+pub(super) async fn resolve_all(
+    session: &Session,
+    compute: &Compute,
+    args: &SshConnectArgs,
+    (project, zone): (&str, &str),
+    user: Option<&str>,
+    instances: &[Instance],
+) -> Result<Vec<SshTarget>> {
+    let key_file = args.ssh_key_file.clone().or_else(ssh::default_key_file);
+    let undecided = instances.iter().any(|i| {
+        let metadata = i.metadata.as_ref();
+        metadata.is_none_or(|m| m.get(oslogin::ENABLE_OSLOGIN).is_none())
+    });
+    // a project the account cannot read is taken as not enabling OS Login
+    let project_metadata = match user {
+        None if undecided => match compute.get_project(project).await {
+            Ok(project) => project.common_instance_metadata,
+            Err(err) => {
+                debug!("taking OS Login as disabled in {project}: {err:#}");
+                None
+            }
+        },
+        _ => None,
+    };
+    let mut os_login = None;
+    let mut targets = Vec::with_capacity(instances.len());
+    for instance in instances {
+        let user = match user {
+            Some(user) => user.to_string(),
+            None if oslogin::enabled(instance.metadata.as_ref(), project_metadata.as_ref()) => {
+                if os_login.is_none() {
+                    os_login = Some(os_login_user(session, project, key_file.as_deref()).await?);
+                }
+                os_login.clone().expect("set above")
+            }
+            None => ssh::default_user()?,
+        };
+        let route = if args.tunnel_through_iap {
+            Route::Iap {
+                proxy_command: ssh::iap_proxy_command(project, zone, &instance.name, SSH_PORT)?,
+            }
+        } else {
+            Route::new(args.internal_ip)
+        };
+        targets.push(SshTarget::for_instance(
+            instance,
+            user,
+            key_file.clone(),
+            route,
+        )?);
+    }
+    Ok(targets)
+}
+
+/// Registers the public half of `key_file` with OS Login if needed and
+/// returns the POSIX username it assigned.
+async fn os_login_user(
+    session: &Session,
+    project: &str,
+    key_file: Option<&Path>,
+) -> Result<String> {
+    let public_key_file = match key_file {
+        Some(path) => {
+            let mut public = path.as_os_str().to_owned();
+            public.push(".pub");
+            PathBuf::from(public)
+        }
+        None => ssh::default_public_key_file().context(
+            "OS Login needs an ssh key; create one with \
+             `ssh-keygen -t ed25519 -f ~/.ssh/google_compute_engine`",
+        )?,
+    };
+    let public_key = fs::read_to_string(&public_key_file)
+        .with_context(|| format!("failed to read {}", public_key_file.display()))?;
+    let account = session.account_email().await?;
+    debug!("instance uses OS Login; connecting as {account}");
+    let profile = session
+        .oslogin()
+        .await?
+        .ensure_key(&account, project, public_key.trim())
+        .await?;
+    profile
+        .username()
+        .map(str::to_string)
+        .with_context(|| format!("OS Login has no POSIX account for {account}"))
+}
@@ -0,0 +1,128 @@
+use std::fs;
+
+use anyhow::{Context, Result, bail};
+use gcectl_core::output::print_list;
+use gcectl_core::resources::instance::Metadata;
+use gcectl_core::ssh;
+use gcectl_core::ssh_keys::{SSH_KEYS, SshKey, SshKeys};
+
+use super::{Session, requested, success, wait_with_spinner};
+use crate::cli::{SshKeyAddArgs, SshKeyRemoveArgs, SshKeyScopeArgs, SshKeysCommand};
+
+pub async fn run(session: &Session, cmd: SshKeysCommand) -> Result<()> {
+    match cmd {
+        SshKeysCommand::List(args) => list(session, args).await,
+        SshKeysCommand::Add(args) => add(session, args).await,
+        SshKeysCommand::Remove(args) => remove(session, args).await,
+    }
+}
+
+async fn list(session: &Session, args: SshKeyScopeArgs) -> Result<()> {
+    let metadata = match &args.instance {
+        Some(name) => {
+            let (project, zone) = session.zonal(&args.zonal)?;
+            let compute = session.compute().await?;
+            compute.get_instance(&project, &zone, name).await?.metadata
+        }
+        None => {
+            let project = session.project(args.zonal.project.as_deref())?;
+            let compute = session.compute().await?;
+            compute
+                .get_project(&project)
+                .await?
+                .common_instance_metadata
+        }
+    }
+    .unwrap_or_default();
+    let keys = SshKeys::parse(metadata.get(SSH_KEYS).unwrap_or_default());
+    let keys: Vec<SshKey> = keys.keys().cloned().collect();
+    print_list(session.output, &keys)
+}
+
+async fn add(session: &Session, args: SshKeyAddArgs) -> Result<()> {
+    let user = match args.user {
+        Some(user) => user,
+        None => ssh::default_user()?,
+    };
+    let Some(path) = args.key_file.or_else(ssh::default_public_key_file) else {
+        bail!("no public key found; pass --key-file");
+    };
+    let contents =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let key = SshKey::new(&user, &contents)
+        .with_context(|| format!("invalid key in {}", path.display()))?;
+
+    let scope = update(session, &args.scope, args.no_wait, |keys| {
+        if !keys.add(key.clone()) {
+            bail!("{user} already has this key");
+        }
+        Ok(())
+    })
+    .await?;
+    success(&format!(
+        "Added {} key for {user} to {scope}",
+        key.fingerprint()
+    ));
+    Ok(())
+}
+
+async fn remove(session: &Session, args: SshKeyRemoveArgs) -> Result<()> {
+    let (user, fingerprint) = (&args.user, args.fingerprint.as_deref());
+    let scope = update(session, &args.scope, args.no_wait, |keys| {
+        if keys.remove(user, fingerprint) == 0 {
+            match fingerprint {
+                Some(fp) => bail!("{user} has no key with fingerprint {fp}"),
+                None => bail!("{user} has no keys"),
+            }
+        }
+        Ok(())
+    })
+    .await?;
+    success(&format!("Removed {user}'s key(s) from {scope}"));
+    Ok(())
+}
+
+/// Rewrites the `ssh-keys` entry of the instance or project selected by
+/// `scope` through `edit`, returning a description of the scope.
+async fn update(
+    session: &Session,
+    scope: &SshKeyScopeArgs,
+    no_wait: bool,
+    edit: impl Fn(&mut SshKeys) -> Result<()>,
+) -> Result<String> {
+    let edit_metadata = |metadata: &mut Metadata| {
+        let mut keys = SshKeys::parse(metadata.get(SSH_KEYS).unwrap_or_default());
+        edit(&mut keys)?;
+        if keys.is_empty() {
+            metadata.remove(SSH_KEYS);
+        } else {
+            metadata.set(SSH_KEYS, keys.to_string());
+        }
+        Ok(())
+    };
+
+    let (compute, op, label) = match &scope.instance {
+        Some(name) => {
+            let (project, zone) = session.zonal(&scope.zonal)?;
+            let compute = session.compute().await?;
+            let op = compute
+                .update_instance_metadata(&project, &zone, name, edit_metadata)
+                .await?;
+            (compute, op, format!("instance {name}"))
+        }
+        None => {
+            let project = session.project(scope.zonal.project.as_deref())?;
+            let compute = session.compute().await?;
+            let op = compute
+                .update_common_instance_metadata(&project, edit_metadata)
+                .await?;
+            (compute, op, format!("project {project}"))
+        }
+    };
+    if no_wait {
+        requested("Metadata update", &op);
+    } else {
+        wait_with_spinner(&compute, op, format!("Updating ssh keys of {label}")).await?;
+    }
+    Ok(label)
+}
@@ -0,0 +1,78 @@
+use anyhow::{Result, bail};
+use gcectl_core::console::Resource;
+use gcectl_core::output::{print_json, print_list, print_one};
+
+use super::instances::instance_builder;
+use super::{Session, confirm_delete, delete_all, open_url, requested, success, wait_with_spinner};
+use crate::cli::{
+    TemplateCreateArgs, TemplateDeleteArgs, TemplateDescribeArgs, TemplateListArgs,
+    TemplateOpenArgs, TemplatesCommand,
+};
+
+pub async fn run(session: &Session, cmd: TemplatesCommand) -> Result<()> {
+    match cmd {
+        TemplatesCommand::List(args) => list(session, args).await,
+        TemplatesCommand::Describe(args) => describe(session, args).await,
+        TemplatesCommand::Open(args) => open(session, args),
+        TemplatesCommand::Create(args) => create(session, *args).await,
+        TemplatesCommand::Delete(args) => delete(session, args).await,
+    }
+}
+
+async fn list(session: &Session, args: TemplateListArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let templates = compute
+        .list_instance_templates(&project, args.filter.as_ref())
+        .await?;
+    print_list(session.output, &templates)
+}
+
+async fn describe(session: &Session, args: TemplateDescribeArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let template = compute.get_instance_template(&project, &args.name).await?;
+    print_one(session.output, &template)
+}
+
+/// Prints the console URL of the template, opening it with `--web`.
+fn open(session: &Session, args: TemplateOpenArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let url = Resource::Template { name: &args.name }.url(&project);
+    open_url(&url, args.web)
+}
+
+async fn create(session: &Session, args: TemplateCreateArgs) -> Result<()> {
+    if args.properties.subnet.is_some() && args.region.is_none() {
+        bail!("--subnet needs --region when creating a template");
+    }
+    // templates are global; the builder's zone is not used
+    let body =
+        instance_builder(&args.properties, &args.name, "").build_template(args.region.as_deref());
+    if session.dry_run {
+        print_json(&body)?;
+        return Ok(());
+    }
+
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let op = compute.insert_instance_template(&project, &body).await?;
+    if args.no_wait {
+        requested("Create", &op);
+        return Ok(());
+    }
+    wait_with_spinner(&compute, op, format!("Creating template {}", args.name)).await?;
+    success(&format!("Template {} created", args.name));
+    Ok(())
+}
+
+async fn delete(session: &Session, args: TemplateDeleteArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    confirm_delete(args.force, "template", &project, &args.names)?;
+    let compute = session.compute().await?;
+    delete_all(&compute, "template", &args.names, |name| {
+        let (compute, project) = (&compute, &project);
+        async move { compute.delete_instance_template(project, &name).await }
+    })
+    .await
+}
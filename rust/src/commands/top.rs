@@ -0,0 +1,28 @@
+use std::io::{self, IsTerminal};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+
+use super::Session;
+use crate::cli::TopArgs;
+use crate::tui::{self, Scope};
+
+pub async fn run(session: &Session, args: TopArgs) -> Result<()> {
+    if !io::stdout().is_terminal() {
+        bail!("gcectl top needs an interactive terminal; use `instances watch` instead");
+    }
+    let scope = Scope {
+        project: session.project(args.list.zonal.project.as_deref())?,
+        zone: session.list_zone(&args.list.zonal, args.list.all_zones),
+        filter: args.list.filter,
+        interval: Duration::from_secs(args.interval),
+    };
+    let project = scope.project.clone();
+    let compute = Arc::new(session.compute().await?);
+    let monitoring = Arc::new(session.monitoring().await?);
+    let result = tui::run(compute, monitoring, scope).await;
+    // key bindings may have started or stopped instances
+    session.forget_instances(&project);
+    result
+}
@@ -0,0 +1,99 @@
+use anyhow::{Result, bail};
+use gcectl_core::forwards::{self, Resolved, State, TunnelsFile};
+use gcectl_core::output::{print_list, print_note};
+use gcectl_core::tunnel::{self, IapTarget};
+
+use super::{Session, success};
+use crate::cli::{TunnelArgs, TunnelCommand, TunnelUpArgs};
+
+pub async fn run(session: &Session, args: TunnelArgs) -> Result<()> {
+    match args.command {
+        Some(TunnelCommand::Up(args)) => return up(session, args).await,
+        Some(TunnelCommand::Status) => return status(session),
+        Some(TunnelCommand::Down) => return down().await,
+        None => {}
+    }
+    let (project, zone) = session.zonal(&args.zonal)?;
+    let remote_port = args.remote_port.expect("clap requires --remote-port");
+    let target = IapTarget {
+        project,
+        zone,
+        instance: args.name.expect("clap requires NAME"),
+        interface: args.interface,
+        port: remote_port,
+    };
+    let auth = session.auth().await?;
+
+    if args.listen_on_stdin {
+        return tunnel::relay(&auth, &target, tokio::io::stdin(), tokio::io::stdout()).await;
+    }
+    let local_port = args.local_port.unwrap_or(remote_port);
+    tunnel::listen(auth, target, local_port).await
+}
+
+async fn up(session: &Session, args: TunnelUpArgs) -> Result<()> {
+    let file = TunnelsFile::read(&args.file)?;
+    if let Some(state) = State::read()?
+        && forwards::alive(state.pid)
+    {
+        bail!(
+            "tunnels from {} are already up in process {}; run `gcectl tunnel down` first",
+            state.file,
+            state.pid
+        );
+    }
+    let resolved = file
+        .tunnels
+        .iter()
+        .map(|forward| {
+            let project = forward.project.as_deref().or(file.project.as_deref());
+            let zone = forward.zone.as_deref().or(file.zone.as_deref());
+            Ok(Resolved {
+                forward: forward.clone(),
+                project: session.project(project)?,
+                zone: session.zone(zone)?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let auth = session.auth().await?;
+    print_note(&format!(
+        "Opening {} tunnel(s) from {}; press Ctrl-C to close them.",
+        resolved.len(),
+        args.file.display()
+    ));
+    forwards::serve(auth, resolved, &args.file, args.probe_interval).await
+}
+
+fn status(session: &Session) -> Result<()> {
+    match State::read()? {
+        Some(state) if forwards::alive(state.pid) => print_list(session.output, &state.tunnels),
+        _ => {
+            success("No tunnels are up");
+            Ok(())
+        }
+    }
+}
+
+async fn down() -> Result<()> {
+    let Some(state) = State::read()? else {
+        success("No tunnels are up");
+        return Ok(());
+    };
+    if forwards::alive(state.pid) {
+        forwards::terminate(state.pid)?;
+        for _ in 0..50 {
+            if !forwards::alive(state.pid) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+    }
+    // a process that was killed outright leaves its file behind
+    State::remove();
+    success(&format!(
+        "Closed {} tunnel(s) from {}",
+        state.tunnels.len(),
+        state.file
+    ));
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use anyhow::Result;
+use gcectl_core::output::print_list;
+
+use super::Session;
+use crate::cli::{LocationListArgs, RegionsCommand, ZonesCommand};
+
+pub async fn run_zones(session: &Session, cmd: ZonesCommand) -> Result<()> {
+    match cmd {
+        ZonesCommand::List(args) => list_zones(session, args).await,
+    }
+}
+
+pub async fn run_regions(session: &Session, cmd: RegionsCommand) -> Result<()> {
+    match cmd {
+        RegionsCommand::List(args) => list_regions(session, args).await,
+    }
+}
+
+async fn list_zones(session: &Session, args: LocationListArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let zones = match &args.filter {
+        Some(filter) => compute.list_zones(&project, Some(filter)).await?,
+        None => session.zones(&compute, &project).await?,
+    };
+    print_list(session.output, &zones)
+}
+
+async fn list_regions(session: &Session, args: LocationListArgs) -> Result<()> {
+    let project = session.project(args.project.project.as_deref())?;
+    let compute = session.compute().await?;
+    let regions = compute.list_regions(&project, args.filter.as_ref()).await?;
+    print_list(session.output, &regions)
+}
@@ -0,0 +1,201 @@
+//! Dynamic shell completion of instance names, zones, machine types, and
+//! image families.
+//!
+//! The shell re-invokes gcectl with `COMPLETE=<shell>` on every tab press,
+//! which is too often to call the API. Candidates instead come from the
+//! names the last `instances list` saw, saved under the cache directory,
+//! and from the catalogs cached there by earlier commands.
+
+use std::collections::BTreeSet;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::{CommandFactory, ValueEnum};
+use clap_complete::CompletionCandidate;
+use clap_complete::env::{Bash, EnvCompleter, Fish, Zsh};
+use gcectl_core::cache::{Cache, IMAGES_CATALOG, MACHINE_TYPES_CATALOG, ZONES_CATALOG};
+use gcectl_core::resources::{Image, Instance, MachineType, Zone};
+use gcectl_core::util::write_private;
+
+use crate::cli::Cli;
+
+/// Environment variable that switches gcectl into completion mode.
+pub const COMPLETE_VAR: &str = "COMPLETE";
+const INSTANCES_FILE: &str = "completion-instances";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Writes the script that hooks `shell` up to gcectl's completer.
+pub fn write_registration(shell: Shell, buf: &mut dyn Write) -> Result<()> {
+    let completer: &dyn EnvCompleter = match shell {
+        Shell::Bash => &Bash,
+        Shell::Zsh => &Zsh,
+        Shell::Fish => &Fish,
+    };
+    let exe = env::current_exe().context("cannot locate the gcectl executable")?;
+    completer.write_registration(
+        COMPLETE_VAR,
+        "gcectl",
+        "gcectl",
+        &exe.display().to_string(),
+        buf,
+    )?;
+    Ok(())
+}
+
+/// An instance name and the zone it was listed in.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Entry {
+    name: String,
+    zone: String,
+}
+
+fn instances_file() -> Result<PathBuf> {
+    Ok(Cache::dir()?.join(INSTANCES_FILE))
+}
+
+fn parse(contents: &str) -> BTreeSet<Entry> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (name, zone) = line.split_once('\t')?;
+            Some(Entry {
+                name: name.to_string(),
+                zone: zone.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn format(entries: &BTreeSet<Entry>) -> String {
+    entries
+        .iter()
+        .map(|e| format!("{}\t{}\n", e.name, e.zone))
+        .collect()
+}
+
+/// Replaces the remembered instances of `zone`, or of every zone when
+/// `zone` is `None`, with `listed`.
+fn merge(mut known: BTreeSet<Entry>, listed: &[Instance], zone: Option<&str>) -> BTreeSet<Entry> {
+    known.retain(|e| zone.is_some_and(|z| e.zone != z));
+    known.extend(listed.iter().map(|i| Entry {
+        name: i.name.clone(),
+        zone: i.zone_name().to_string(),
+    }));
+    known
+}
+
+/// Remembers the instances a list command returned for later completions.
+pub fn remember_instances(listed: &[Instance], zone: Option<&str>) -> Result<()> {
+    let path = instances_file()?;
+    let known = parse(&fs::read_to_string(&path).unwrap_or_default());
+    write_private(&path, format(&merge(known, listed, zone)).as_bytes(), false)
+}
+
+fn remembered() -> BTreeSet<Entry> {
+    instances_file()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|contents| parse(&contents))
+        .unwrap_or_default()
+}
+
+/// Candidates for instance name arguments, annotated with their zone.
+pub fn instance_names() -> Vec<CompletionCandidate> {
+    remembered()
+        .into_iter()
+        .map(|e| CompletionCandidate::new(e.name).help(Some(e.zone.into())))
+        .collect()
+}
+
+/// Candidates for `--zone`: every zone an instance was seen in or a zones
+/// listing returned.
+pub fn zones() -> Vec<CompletionCandidate> {
+    let mut zones: BTreeSet<String> = remembered().into_iter().map(|e| e.zone).collect();
+    zones.extend(
+        Cache::stored::<Vec<Zone>>(ZONES_CATALOG)
+            .into_iter()
+            .flatten()
+            .map(|z| z.name),
+    );
+    zones.into_iter().map(CompletionCandidate::new).collect()
+}
+
+/// Candidates for `--machine-type`, from the machine types of every zone
+/// listed so far.
+pub fn machine_types() -> Vec<CompletionCandidate> {
+    let types: BTreeSet<String> = Cache::stored::<Vec<MachineType>>(MACHINE_TYPES_CATALOG)
+        .into_iter()
+        .flatten()
+        .map(|t| t.name)
+        .collect();
+    types.into_iter().map(CompletionCandidate::new).collect()
+}
+
+/// Candidates for `--image-family`, from the image projects checked so far.
+pub fn image_families() -> Vec<CompletionCandidate> {
+    let families: BTreeSet<String> = Cache::stored::<Vec<Image>>(IMAGES_CATALOG)
+        .into_iter()
+        .flatten()
+        .filter_map(|i| i.family)
+        .collect();
+    families.into_iter().map(CompletionCandidate::new).collect()
+}
+
+/// What the last word of `line`, a command line without the `gcectl`,
+/// completes to, as the shell completer would offer it.
+pub fn complete_line(line: &str) -> Vec<String> {
+    let mut args: Vec<OsString> = vec!["gcectl".into()];
+    args.extend(line.split_whitespace().map(OsString::from));
+    if line.is_empty() || line.ends_with(char::is_whitespace) {
+        args.push(OsString::new());
+    }
+    let index = args.len() - 1;
+    let dir = env::current_dir().ok();
+    let candidates =
+        clap_complete::engine::complete(&mut Cli::command(), args, index, dir.as_deref());
+    candidates
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| !c.is_hide_set())
+        .map(|c| c.get_value().to_string_lossy().into_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(name: &str, zone: &str) -> Instance {
+        Instance {
+            name: name.into(),
+            zone: format!("https://x/zones/{zone}"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn round_trips_entries() {
+        let entries = parse("web\tus-central1-a\nbroken line\ndb\tasia-northeast1-b\n");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(parse(&format(&entries)), entries);
+    }
+
+    #[test]
+    fn zonal_list_only_replaces_its_zone() {
+        let known = parse("web\ta\nold\tb\n");
+        let merged = merge(known.clone(), &[instance("new", "b")], Some("b"));
+        assert_eq!(format(&merged), "new\tb\nweb\ta\n");
+        let merged = merge(known, &[instance("only", "c")], None);
+        assert_eq!(format(&merged), "only\tc\n");
+    }
+}
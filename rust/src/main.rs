@@ -1,33 +1,127 @@
-use clap::Parser;
-use log::debug;
-
-#[derive(Debug, Parser)]
-#[command(author, version, about, long_about=None)]
-pub struct Args {
-    // input text
-    #[arg(value_name = "TEXT", help = "Input text", required = true)]
-    text: Vec<String>,
-
-    // omits the newline at the end of the output
-    #[arg(
-        short = 'n',
-        long = "omit-newline",
-        help = "Do not print newline",
-        default_value_t = false
-    )]
-    omit_newline: bool,
+mod alias;
+mod cli;
+mod commands;
+mod completion;
+mod prompt;
+mod self_update;
+mod shell;
+mod tui;
+
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use clap::{CommandFactory, Parser};
+use clap_complete::CompleteEnv;
+use gcectl_core::error::{self, GcectlError};
+use gcectl_core::{events, output};
+use tracing::debug;
+use tracing_subscriber::EnvFilter;
+
+use crate::cli::Cli;
+
+#[tokio::main]
+async fn main() {
+    CompleteEnv::with_factory(Cli::command)
+        .var(completion::COMPLETE_VAR)
+        .complete();
+
+    let args = match alias::args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("Error: {err:#}");
+            std::process::exit(2);
+        }
+    };
+    let cli = Cli::parse_from(args);
+    if let Err(err) = init_logging(cli.verbose, cli.log_file.as_deref()) {
+        eprintln!("Error: {err:#}");
+        std::process::exit(1);
+    }
+    debug!("{:?}", cli);
+    output::set_quiet(cli.quiet);
+    events::set_format(cli.events);
+    if let Some(projection) = cli.format.clone() {
+        output::set_projection(projection);
+    }
+
+    if let Err(err) = commands::run(cli).await {
+        std::process::exit(report(&err));
+    }
 }
 
-fn main() {
-    env_logger::init();
+/// Prints `err` and its hint, returning the exit status it calls for.
+fn report(err: &anyhow::Error) -> i32 {
+    let classified = error::find(err);
+    // a plain Ctrl-C needs no explanation
+    if !matches!(
+        classified,
+        Some(GcectlError::Interrupted { operation: None })
+    ) {
+        eprintln!("Error: {err:#}");
+    }
+    let hint = classified.and_then(|e| e.hint());
+    if let Some(hint) = hint {
+        eprintln!("Hint: {hint}");
+    }
+    let code = classified.map_or(1, GcectlError::exit_code);
+    events::emit(&events::Event::Error {
+        message: &format!("{err:#}"),
+        hint,
+        exit_code: Some(code),
+    });
+    code
+}
 
-    let args = Args::parse();
-    debug!("{:?}", args);
+/// Installs the global subscriber. `-v` shows the steps gcectl takes, `-vv`
+/// adds debugging detail, and `-vvv` dumps HTTP traffic; `RUST_LOG`, when
+/// set, overrides the level. Logs go to stderr, or are appended to
+/// `log_file` without colors.
+fn init_logging(verbosity: u8, log_file: Option<&Path>) -> Result<()> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(directives(verbosity)));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(verbosity >= 2);
+    match log_file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("failed to open log file {}", path.display()))?;
+            builder
+                .with_ansi(false)
+                .with_writer(Mutex::new(file))
+                .init();
+        }
+        None => builder.with_writer(std::io::stderr).init(),
+    }
+    Ok(())
+}
 
-    let text = args.text;
-    let omit_newline = args.omit_newline;
+/// Filter directives for `-v` repeated `verbosity` times; libraries only
+/// get to warn unless `RUST_LOG` says otherwise.
+fn directives(verbosity: u8) -> String {
+    let level = match verbosity {
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    };
+    format!("warn,gcectl={level}")
+}
 
-    let ending = if omit_newline { "" } else { "\n" };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    print!("{}", text.join(" ") + ending);
+    #[test]
+    fn verbosity_raises_only_gcectl_levels() {
+        assert_eq!(directives(0), "warn,gcectl=warn");
+        assert_eq!(directives(1), "warn,gcectl=info");
+        assert_eq!(directives(3), "warn,gcectl=trace");
+        assert_eq!(directives(9), "warn,gcectl=trace");
+    }
 }
@@ -0,0 +1,43 @@
+//! Interactive confirmation prompts.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use anyhow::Result;
+use gcectl_core::error::GcectlError;
+
+/// Asks a yes/no question on the terminal, defaulting to "no".
+///
+/// Fails rather than guessing when stdin is not a terminal, so scripts must
+/// opt in explicitly with `--force`.
+pub fn confirm(question: &str) -> Result<bool> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return Err(GcectlError::Usage(
+            "cannot ask for confirmation: stdin is not a terminal (pass --force to skip)".into(),
+        )
+        .into());
+    }
+    eprint!("{question} [y/N]: ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    stdin.lock().read_line(&mut answer)?;
+    Ok(is_yes(&answer))
+}
+
+fn is_yes(answer: &str) -> bool {
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_only_explicit_yes() {
+        assert!(is_yes("y\n"));
+        assert!(is_yes(" YES "));
+        assert!(!is_yes("\n"));
+        assert!(!is_yes("no"));
+        assert!(!is_yes("yep"));
+    }
+}
@@ -0,0 +1,295 @@
+//! Finding, verifying, and installing newer gcectl releases from GitHub.
+//!
+//! This binary's releases are tagged `rust-v{version}`; the repository's
+//! other releases, tagged `go-v*`, are the Go CLI and never installed.
+//! Release archives follow the install script's naming,
+//! `gcectl_{version}_{os}_{arch}.tar.gz`, next to a `checksums.txt` of
+//! SHA-256 sums.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use gcectl_core::util::hex;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+const RELEASES_URL: &str = "https://api.github.com/repos/haru-256/gcectl/releases";
+const BINARY_NAME: &str = "gcectl";
+const TAG_PREFIX: &str = "rust-v";
+const CHECKSUMS_NAME: &str = "checksums.txt";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+/// A `major.minor.patch` release version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version(u64, u64, u64);
+
+impl Version {
+    /// The version of this binary.
+    pub fn current() -> Self {
+        Self::parse(env!("CARGO_PKG_VERSION")).expect("crate version is major.minor.patch")
+    }
+
+    /// Parses a version, with or without a leading `v`, ignoring any
+    /// pre-release suffix.
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.strip_prefix('v').unwrap_or(version);
+        let core = version.split(['-', '+']).next()?;
+        let mut parts = core.split('.').map(|p| p.parse::<u64>().ok());
+        let version = Self(parts.next()??, parts.next()??, parts.next()??);
+        parts.next().is_none().then_some(version)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+impl Release {
+    pub fn version(&self) -> Result<Version> {
+        self.tag_name
+            .strip_prefix(TAG_PREFIX)
+            .and_then(Version::parse)
+            .with_context(|| {
+                format!(
+                    "release tag {} is not {TAG_PREFIX}{{version}}",
+                    self.tag_name
+                )
+            })
+    }
+
+    // a published release of this binary, rather than of the Go CLI
+    fn is_installable(&self) -> bool {
+        !self.draft && !self.prerelease && self.version().is_ok()
+    }
+
+    fn asset(&self, name: &str) -> Result<&Asset> {
+        self.assets
+            .iter()
+            .find(|a| a.name == name)
+            .with_context(|| format!("release {} has no {name}", self.tag_name))
+    }
+}
+
+/// OS and architecture as the release archives name them.
+pub fn platform() -> Result<(&'static str, &'static str)> {
+    let os = match env::consts::OS {
+        "linux" => "linux",
+        "macos" => "darwin",
+        other => bail!("self-update does not support {other}; download the release manually"),
+    };
+    let arch = match env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        other => bail!("self-update does not support the {other} architecture"),
+    };
+    Ok((os, arch))
+}
+
+fn archive_name(version: Version, os: &str, arch: &str) -> String {
+    format!("{BINARY_NAME}_{version}_{os}_{arch}.tar.gz")
+}
+
+/// The SHA-256 listed for `file` in a `sha256sum`-style checksums file.
+fn checksum_for<'a>(checksums: &'a str, file: &str) -> Option<&'a str> {
+    checksums.lines().find_map(|line| {
+        let (sum, name) = line.split_once(char::is_whitespace)?;
+        (name.trim_start().trim_start_matches('*') == file).then_some(sum)
+    })
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+pub struct Updater {
+    http: reqwest::Client,
+    releases_url: String,
+}
+
+impl Updater {
+    pub fn new() -> Result<Self> {
+        let http = reqwest::Client::builder()
+            // GitHub rejects API requests without a user agent
+            .user_agent(concat!("gcectl/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Ok(Self {
+            http,
+            releases_url: env::var("GCECTL_RELEASES_URL")
+                .unwrap_or_else(|_| RELEASES_URL.to_string()),
+        })
+    }
+
+    /// The newest release of this binary among the repository's recent
+    /// releases.
+    pub async fn latest(&self) -> Result<Release> {
+        let resp = self
+            .http
+            .get(&self.releases_url)
+            .query(&[("per_page", "100")])
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .await
+            .context("request to the GitHub releases API failed")?
+            .error_for_status()
+            .context("cannot look up the latest release")?;
+        let releases: Vec<Release> = resp.json().await.context("failed to decode the releases")?;
+        latest(releases).with_context(|| format!("no {TAG_PREFIX}* release found"))
+    }
+
+    async fn download(&self, asset: &Asset) -> Result<Vec<u8>> {
+        let resp = self
+            .http
+            .get(&asset.browser_download_url)
+            .send()
+            .await
+            .with_context(|| format!("failed to download {}", asset.name))?
+            .error_for_status()
+            .with_context(|| format!("failed to download {}", asset.name))?;
+        Ok(resp.bytes().await?.to_vec())
+    }
+
+    /// Downloads the archive for this platform, checks it against the
+    /// release's checksums, and swaps it in for the binary at `target`.
+    pub async fn install(&self, release: &Release, target: &Path) -> Result<()> {
+        let version = release.version()?;
+        let (os, arch) = platform()?;
+        let archive = archive_name(version, os, arch);
+        let checksums = self.download(release.asset(CHECKSUMS_NAME)?).await?;
+        let checksums = String::from_utf8_lossy(&checksums);
+        let expected = checksum_for(&checksums, &archive)
+            .with_context(|| format!("the release checksums do not list {archive}"))?;
+        let bytes = self.download(release.asset(&archive)?).await?;
+        let actual = sha256_hex(&bytes);
+        if !actual.eq_ignore_ascii_case(expected) {
+            bail!("checksum mismatch for {archive}: expected {expected}, got {actual}");
+        }
+        replace_binary(&bytes, &archive, target)
+    }
+}
+
+// GitHub lists releases newest first, but by creation date
+fn latest(releases: Vec<Release>) -> Option<Release> {
+    releases
+        .into_iter()
+        .filter(Release::is_installable)
+        .max_by_key(|r| r.version().ok())
+}
+
+/// Unpacks the binary from `archive` next to `target` and renames it over
+/// `target`, so a failure part-way leaves the old binary in place.
+fn replace_binary(archive: &[u8], archive_name: &str, target: &Path) -> Result<()> {
+    let dir = target
+        .parent()
+        .context("the running binary has no parent directory")?;
+    // staged in the target's directory so the final rename stays on one filesystem
+    let staging = dir.join(format!(".gcectl-update-{}", std::process::id()));
+    fs::create_dir_all(&staging)
+        .with_context(|| format!("cannot write to {}; try again with sudo", dir.display()))?;
+    let result = unpack_and_swap(archive, archive_name, &staging, target);
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+fn unpack_and_swap(
+    archive: &[u8],
+    archive_name: &str,
+    staging: &Path,
+    target: &Path,
+) -> Result<()> {
+    let archive_path = staging.join(archive_name);
+    fs::write(&archive_path, archive)?;
+    let status = Command::new("tar")
+        .arg("-xzf")
+        .arg(&archive_path)
+        .arg("-C")
+        .arg(staging)
+        .status()
+        .context("failed to run tar")?;
+    if !status.success() {
+        bail!("tar could not unpack {archive_name}");
+    }
+    let binary = staging.join(BINARY_NAME);
+    if !binary.is_file() {
+        bail!("{archive_name} does not contain {BINARY_NAME}");
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755))?;
+    }
+    fs::rename(&binary, target).with_context(|| format!("failed to replace {}", target.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_release_tags() {
+        assert_eq!(Version::parse("v1.2.3"), Some(Version(1, 2, 3)));
+        assert_eq!(Version::parse("go-v0.10.0"), None);
+        assert_eq!(Version::parse("1.2.3-rc.1"), Some(Version(1, 2, 3)));
+        assert_eq!(Version::parse("v1.2"), None);
+        assert_eq!(Version::parse("nightly"), None);
+        assert!(Version(0, 10, 0) > Version(0, 9, 9));
+    }
+
+    #[test]
+    fn picks_the_newest_rust_release() {
+        let release = |tag: &str, prerelease: bool| Release {
+            tag_name: tag.into(),
+            draft: false,
+            prerelease,
+            assets: Vec::new(),
+        };
+        let releases = vec![
+            release("go-v9.0.0", false),
+            release("rust-v1.3.0-rc.1", true),
+            release("rust-v1.2.0", false),
+            release("rust-v1.10.0", false),
+        ];
+        let newest = latest(releases).unwrap();
+        assert_eq!(newest.version().unwrap(), Version(1, 10, 0));
+        assert!(latest(vec![release("go-v9.0.0", false)]).is_none());
+    }
+
+    #[test]
+    fn finds_archive_checksums() {
+        let version = Version(1, 2, 0);
+        let archive = archive_name(version, "linux", "amd64");
+        assert_eq!(archive, "gcectl_1.2.0_linux_amd64.tar.gz");
+        let checksums = "abc123  gcectl_1.2.0_darwin_arm64.tar.gz\n\
+                         def456  gcectl_1.2.0_linux_amd64.tar.gz\n";
+        assert_eq!(checksum_for(checksums, &archive), Some("def456"));
+        assert_eq!(
+            checksum_for(checksums, "gcectl_1.2.0_windows_amd64.zip"),
+            None
+        );
+        assert_eq!(
+            sha256_hex(b"gcectl"),
+            "bc0d523ce446d1a177cd947a7a4d0ab3fb8cf3465e8af5b79ec88600178f4e2c"
+        );
+    }
+}
@@ -0,0 +1,354 @@
+//! The line editor of `gcectl shell`: emacs-style editing keys, history
+//! kept under the config directory, and Tab completing commands, flags,
+//! and instance names as shell completion does.
+//!
+//! When stdin is not a terminal, lines are read as they come, without a
+//! prompt or history, so a script can be piped in.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use gcectl_core::config::Config;
+use ratatui::crossterm::cursor::MoveToColumn;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::style::Print;
+use ratatui::crossterm::terminal::{self, Clear, ClearType};
+use ratatui::crossterm::{execute, queue};
+
+use crate::completion;
+
+const HISTORY_FILE: &str = "shell-history";
+const HISTORY_LIMIT: usize = 1000;
+
+pub struct LineEditor {
+    interactive: bool,
+    history: Vec<String>,
+    // where history outlives the session, unless `--no-history`
+    file: Option<PathBuf>,
+}
+
+/// What a key press did to the line being edited.
+enum Edited {
+    Continue,
+    Done(String),
+    // Ctrl-C: start over on a fresh line
+    Abandoned,
+    // Ctrl-D on an empty line
+    Eof,
+}
+
+impl LineEditor {
+    pub fn new(keep_history: bool) -> Self {
+        let interactive = io::stdin().is_terminal();
+        let file = Config::dir()
+            .ok()
+            .map(|dir| dir.join(HISTORY_FILE))
+            .filter(|_| interactive && keep_history);
+        let mut history: Vec<String> = file
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| text.lines().map(String::from).collect())
+            .unwrap_or_default();
+        if history.len() > HISTORY_LIMIT {
+            history.drain(..history.len() - HISTORY_LIMIT);
+            if let Some(path) = &file {
+                let _ = fs::write(path, history.join("\n") + "\n");
+            }
+        }
+        Self {
+            interactive,
+            history,
+            file,
+        }
+    }
+
+    /// The next line, or `None` at the end of input.
+    pub fn read_line(&mut self, prompt: &str) -> Result<Option<String>> {
+        if !self.interactive {
+            let mut line = String::new();
+            return Ok(match io::stdin().lock().read_line(&mut line)? {
+                0 => None,
+                _ => Some(line.trim_end_matches(['\n', '\r']).to_string()),
+            });
+        }
+        terminal::enable_raw_mode()?;
+        let result = self.edit(prompt);
+        // commands run, and prompt, in the terminal's usual mode
+        terminal::disable_raw_mode()?;
+        result
+    }
+
+    /// Remembers `line` for Up and for later sessions.
+    pub fn add(&mut self, line: &str) {
+        if !self.interactive || self.history.last().is_some_and(|last| last == line) {
+            return;
+        }
+        self.history.push(line.to_string());
+        if let Some(path) = &self.file {
+            let _ = fs::create_dir_all(path.parent().unwrap_or(path));
+            let appended = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{line}"));
+            if appended.is_err() {
+                // one warning is enough
+                eprintln!(
+                    "[WARNING] | cannot save shell history to {}",
+                    path.display()
+                );
+                self.file = None;
+            }
+        }
+    }
+
+    fn edit(&mut self, prompt: &str) -> Result<Option<String>> {
+        let mut out = io::stderr();
+        let mut line = Line::default();
+        // position in the history while browsing it, and the line put aside
+        let mut browsing: Option<(usize, String)> = None;
+        draw(&mut out, prompt, &line)?;
+        loop {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let edited = match key.code {
+                KeyCode::Up => {
+                    self.browse(&mut line, &mut browsing, -1);
+                    Edited::Continue
+                }
+                KeyCode::Down => {
+                    self.browse(&mut line, &mut browsing, 1);
+                    Edited::Continue
+                }
+                KeyCode::Tab => {
+                    complete(&mut out, prompt, &mut line)?;
+                    Edited::Continue
+                }
+                _ => line.key(key),
+            };
+            match edited {
+                Edited::Continue => draw(&mut out, prompt, &line)?,
+                Edited::Done(text) => {
+                    execute!(out, Print("\r\n"))?;
+                    return Ok(Some(text));
+                }
+                Edited::Abandoned => {
+                    execute!(out, Print("^C\r\n"))?;
+                    line = Line::default();
+                    browsing = None;
+                    draw(&mut out, prompt, &line)?;
+                }
+                Edited::Eof => {
+                    execute!(out, Print("\r\n"))?;
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    // moves `step` through the history, putting the typed line back past
+    // its newest entry
+    fn browse(&self, line: &mut Line, browsing: &mut Option<(usize, String)>, step: isize) {
+        let (at, typed) = browsing
+            .take()
+            .unwrap_or_else(|| (self.history.len(), line.text()));
+        let next = at.saturating_add_signed(step).min(self.history.len());
+        if next == self.history.len() {
+            line.set(&typed);
+            return;
+        }
+        line.set(&self.history[next]);
+        *browsing = Some((next, typed));
+    }
+}
+
+fn draw(out: &mut impl Write, prompt: &str, line: &Line) -> Result<()> {
+    let column = prompt.chars().count() + line.cursor;
+    queue!(
+        out,
+        MoveToColumn(0),
+        Print(prompt),
+        Print(line.text()),
+        Clear(ClearType::UntilNewLine),
+        MoveToColumn(u16::try_from(column).unwrap_or(u16::MAX)),
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+// completes the word before the cursor, listing the choices when they
+// share nothing more than what is typed
+fn complete(out: &mut impl Write, prompt: &str, line: &mut Line) -> Result<()> {
+    let before: String = line.chars[..line.cursor].iter().collect();
+    let candidates = completion::complete_line(&before);
+    let word = before
+        .rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or_default();
+    match completed(word, &candidates) {
+        Some(text) => line.replace_word(word.chars().count(), &text),
+        None if candidates.len() > 1 => {
+            queue!(
+                out,
+                Print("\r\n"),
+                Print(candidates.join("  ")),
+                Print("\r\n")
+            )?;
+            draw(out, prompt, line)?;
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+/// What `word` becomes given its completions: the only one and a space, or
+/// the longest prefix they share if it is longer than `word`.
+fn completed(word: &str, candidates: &[String]) -> Option<String> {
+    match candidates {
+        [] => None,
+        [only] => Some(format!("{only} ")),
+        [first, rest @ ..] => {
+            let mut shared = first.len();
+            for candidate in rest {
+                shared = first
+                    .char_indices()
+                    .zip(candidate.chars())
+                    .take_while(|((_, a), b)| a == b)
+                    .last()
+                    .map_or(0, |((i, a), _)| i + a.len_utf8())
+                    .min(shared);
+            }
+            let prefix = &first[..shared];
+            (prefix.len() > word.len()).then(|| prefix.to_string())
+        }
+    }
+}
+
+/// The line being edited and the cursor, in characters.
+#[derive(Debug, Default)]
+struct Line {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl Line {
+    fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    fn set(&mut self, text: &str) {
+        self.chars = text.chars().collect();
+        self.cursor = self.chars.len();
+    }
+
+    // the `len` characters before the cursor become `text`
+    fn replace_word(&mut self, len: usize, text: &str) {
+        let start = self.cursor - len;
+        self.chars.splice(start..self.cursor, text.chars());
+        self.cursor = start + text.chars().count();
+    }
+
+    fn key(&mut self, key: KeyEvent) -> Edited {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => return Edited::Done(self.text()),
+            KeyCode::Char('c') if control => return Edited::Abandoned,
+            KeyCode::Char('d') if control && self.chars.is_empty() => return Edited::Eof,
+            KeyCode::Char('d') if control => self.delete(),
+            KeyCode::Char('a') if control => self.cursor = 0,
+            KeyCode::Char('e') if control => self.cursor = self.chars.len(),
+            KeyCode::Char('b') if control => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Char('f') if control => self.cursor = (self.cursor + 1).min(self.chars.len()),
+            KeyCode::Char('u') if control => {
+                self.chars.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            KeyCode::Char('k') if control => self.chars.truncate(self.cursor),
+            KeyCode::Char('w') if control => self.delete_word(),
+            KeyCode::Char(_) if control => {}
+            KeyCode::Char(c) => {
+                self.chars.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.chars.remove(self.cursor);
+            }
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.chars.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.chars.len(),
+            _ => {}
+        }
+        Edited::Continue
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    // the word before the cursor and the spaces after it, as a shell's Ctrl-W
+    fn delete_word(&mut self) {
+        let mut start = self.cursor;
+        while start > 0 && self.chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !self.chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        self.chars.drain(start..self.cursor);
+        self.cursor = start;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(line: &mut Line, code: KeyCode, modifiers: KeyModifiers) {
+        line.key(KeyEvent::new(code, modifiers));
+    }
+
+    #[test]
+    fn edits_like_a_shell() {
+        let mut line = Line::default();
+        for c in "instances lst web".chars() {
+            press(&mut line, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+        press(&mut line, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(line.text(), "instances lst ");
+        for _ in 0..3 {
+            press(&mut line, KeyCode::Left, KeyModifiers::NONE);
+        }
+        press(&mut line, KeyCode::Char('i'), KeyModifiers::NONE);
+        assert_eq!(line.text(), "instances list ");
+        press(&mut line, KeyCode::Char('a'), KeyModifiers::CONTROL);
+        press(&mut line, KeyCode::Delete, KeyModifiers::NONE);
+        assert_eq!(line.text(), "nstances list ");
+        assert!(matches!(
+            line.key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)),
+            Edited::Done(text) if text == "nstances list "
+        ));
+    }
+
+    #[test]
+    fn completes_to_the_shared_prefix() {
+        let candidates = ["start".to_string(), "stop".to_string()];
+        assert_eq!(completed("s", &candidates), Some("st".to_string()));
+        assert_eq!(completed("st", &candidates), None);
+        assert_eq!(
+            completed("web", &["web-1".to_string()]),
+            Some("web-1 ".to_string())
+        );
+        assert_eq!(completed("x", &[]), None);
+    }
+}
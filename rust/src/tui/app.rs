@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use gcectl_core::resources::Instance;
+
+// how long the footer shows a message before the key help comes back
+const MESSAGE_TTL: Duration = Duration::from_secs(5);
+
+/// State of the `top` dashboard, independent of the terminal.
+#[derive(Debug, Default)]
+pub struct App {
+    pub project: String,
+    instances: Vec<Instance>,
+    // latest CPU utilization (0.0–1.0) keyed by instance ID
+    cpu: HashMap<String, f64>,
+    selected: usize,
+    // footer message and when it was set
+    message: Option<(String, Instant)>,
+    pub last_refresh: Option<DateTime<Local>>,
+    // instance awaiting a second `x` before it is stopped
+    pending_stop: Option<String>,
+}
+
+/// One line of the instance table.
+#[derive(Debug)]
+pub enum Line<'a> {
+    Zone(&'a str),
+    Instance(&'a Instance),
+}
+
+impl App {
+    pub fn new(project: String) -> Self {
+        Self {
+            project,
+            ..Default::default()
+        }
+    }
+
+    /// Replaces the instance list, keeping the same instance selected when it
+    /// still exists. `instances` must be sorted by zone.
+    pub fn set_instances(&mut self, instances: Vec<Instance>) {
+        let key = self.selected().map(|i| (i.zone.clone(), i.name.clone()));
+        self.instances = instances;
+        self.selected = key
+            .and_then(|(zone, name)| {
+                self.instances
+                    .iter()
+                    .position(|i| i.zone == zone && i.name == name)
+            })
+            .unwrap_or(0)
+            .min(self.instances.len().saturating_sub(1));
+        self.last_refresh = Some(Local::now());
+    }
+
+    pub fn set_cpu(&mut self, cpu: HashMap<String, f64>) {
+        self.cpu = cpu;
+    }
+
+    pub fn cpu_of(&self, instance: &Instance) -> Option<f64> {
+        self.cpu.get(instance.id.as_deref()?).copied()
+    }
+
+    pub fn instance_count(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn selected(&self) -> Option<&Instance> {
+        self.instances.get(self.selected)
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.instances.len() {
+            self.selected += 1;
+        }
+        self.pending_stop = None;
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+        self.pending_stop = None;
+    }
+
+    /// Arms a stop on the first call and confirms it on the second; returns
+    /// the instance to stop once confirmed.
+    pub fn request_stop(&mut self) -> Option<Instance> {
+        let instance = self.selected()?.clone();
+        if self.pending_stop.as_deref() == Some(instance.name.as_str()) {
+            self.pending_stop = None;
+            return Some(instance);
+        }
+        self.set_message(format!("Press x again to stop {}", instance.name));
+        self.pending_stop = Some(instance.name);
+        None
+    }
+
+    pub fn cancel_pending(&mut self) {
+        self.pending_stop = None;
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_ref().map(|(message, _)| message.as_str())
+    }
+
+    pub fn set_message(&mut self, message: String) {
+        self.message = Some((message, Instant::now()));
+    }
+
+    /// Clears the footer message, as the next key press does.
+    pub fn dismiss_message(&mut self) {
+        self.message = None;
+    }
+
+    /// Clears the footer message once it has been shown for a while as of
+    /// `now`.
+    pub fn expire_message(&mut self, now: Instant) {
+        if self
+            .message
+            .as_ref()
+            .is_some_and(|(_, set)| now.duration_since(*set) >= MESSAGE_TTL)
+        {
+            self.message = None;
+        }
+    }
+
+    /// Table lines with a header before each zone's instances.
+    pub fn lines(&self) -> Vec<Line<'_>> {
+        let mut lines = Vec::with_capacity(self.instances.len());
+        let mut zone = None;
+        for instance in &self.instances {
+            if zone != Some(instance.zone_name()) {
+                zone = Some(instance.zone_name());
+                lines.push(Line::Zone(instance.zone_name()));
+            }
+            lines.push(Line::Instance(instance));
+        }
+        lines
+    }
+
+    /// Index into [`App::lines`] of the selected instance.
+    pub fn selected_line(&self) -> Option<usize> {
+        let selected = self.selected()?;
+        self.lines()
+            .iter()
+            .position(|line| matches!(line, Line::Instance(i) if std::ptr::eq(*i, selected)))
+    }
+}
+
+/// Ten-cell utilization bar, e.g. `████░░░░░░  42%`.
+pub fn cpu_bar(utilization: f64) -> String {
+    let pct = (utilization * 100.0).clamp(0.0, 100.0);
+    let filled = (pct / 10.0).round() as usize;
+    format!(
+        "{}{} {:>3.0}%",
+        "█".repeat(filled),
+        "░".repeat(10 - filled),
+        pct
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(zone: &str, name: &str) -> Instance {
+        Instance {
+            name: name.into(),
+            zone: format!("zones/{zone}"),
+            status: "RUNNING".into(),
+            ..Default::default()
+        }
+    }
+
+    fn app() -> App {
+        let mut app = App::new("p".into());
+        app.set_instances(vec![
+            instance("a", "vm-1"),
+            instance("a", "vm-2"),
+            instance("b", "vm-3"),
+        ]);
+        app
+    }
+
+    #[test]
+    fn groups_lines_by_zone() {
+        let app = app();
+        let lines = app.lines();
+        assert_eq!(lines.len(), 5);
+        assert!(matches!(lines[0], Line::Zone("a")));
+        assert!(matches!(lines[3], Line::Zone("b")));
+    }
+
+    #[test]
+    fn selection_skips_zone_headers() {
+        let mut app = app();
+        assert_eq!(app.selected_line(), Some(1));
+        app.select_next();
+        app.select_next();
+        assert_eq!(app.selected().unwrap().name, "vm-3");
+        assert_eq!(app.selected_line(), Some(4));
+        app.select_next();
+        assert_eq!(app.selected().unwrap().name, "vm-3");
+    }
+
+    #[test]
+    fn refresh_keeps_selected_instance() {
+        let mut app = app();
+        app.select_next();
+        app.set_instances(vec![instance("a", "vm-0"), instance("a", "vm-2")]);
+        assert_eq!(app.selected().unwrap().name, "vm-2");
+        app.set_instances(vec![instance("c", "vm-9")]);
+        assert_eq!(app.selected().unwrap().name, "vm-9");
+    }
+
+    #[test]
+    fn stop_needs_confirmation() {
+        let mut app = app();
+        assert!(app.request_stop().is_none());
+        assert_eq!(app.request_stop().unwrap().name, "vm-1");
+        assert!(app.request_stop().is_none());
+        app.select_next();
+        assert!(app.request_stop().is_none());
+    }
+
+    #[test]
+    fn messages_expire_or_go_with_the_next_key() {
+        let mut app = app();
+        app.set_message("Stop requested for vm-1".into());
+        app.expire_message(Instant::now());
+        assert_eq!(app.message(), Some("Stop requested for vm-1"));
+        app.expire_message(Instant::now() + MESSAGE_TTL);
+        assert_eq!(app.message(), None);
+
+        app.set_message("Refresh failed".into());
+        app.dismiss_message();
+        assert_eq!(app.message(), None);
+    }
+
+    #[test]
+    fn cpu_bar_scales() {
+        assert_eq!(cpu_bar(0.42), "████░░░░░░  42%");
+        assert_eq!(cpu_bar(1.5), "██████████ 100%");
+    }
+}
@@ -0,0 +1,206 @@
+//! Interactive instance dashboard behind `gcectl top`.
+//!
+//! The event loop runs on the command's task: it redraws, polls the keyboard
+//! briefly, and applies results that background refresh and action tasks
+//! send back over a channel.
+
+mod app;
+mod ui;
+
+use std::env;
+use std::process;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use gcectl_core::compute::Compute;
+use gcectl_core::filter::Filter;
+use gcectl_core::monitoring::Monitoring;
+use gcectl_core::resources::Instance;
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use tokio::sync::mpsc;
+
+use app::App;
+
+// how long each keyboard poll blocks before checking for updates
+const KEY_POLL: Duration = Duration::from_millis(200);
+
+/// What to show: one zone or all of them, optionally filtered.
+pub struct Scope {
+    pub project: String,
+    pub zone: Option<String>,
+    pub filter: Option<Filter>,
+    pub interval: Duration,
+}
+
+enum Update {
+    Instances(Vec<Instance>),
+    Cpu(std::collections::HashMap<String, f64>),
+    Message(String),
+}
+
+pub async fn run(compute: Arc<Compute>, monitoring: Arc<Monitoring>, scope: Scope) -> Result<()> {
+    let scope = Arc::new(scope);
+    let mut terminal = ratatui::try_init().context("failed to initialise the terminal")?;
+    let result = event_loop(&mut terminal, compute, monitoring, scope).await;
+    ratatui::try_restore().context("failed to restore the terminal")?;
+    result
+}
+
+async fn event_loop(
+    terminal: &mut DefaultTerminal,
+    compute: Arc<Compute>,
+    monitoring: Arc<Monitoring>,
+    scope: Arc<Scope>,
+) -> Result<()> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut app = App::new(scope.project.clone());
+    let mut next_refresh = Instant::now();
+
+    loop {
+        if Instant::now() >= next_refresh {
+            spawn_refresh(&compute, &monitoring, &scope, &tx);
+            next_refresh = Instant::now() + scope.interval;
+        }
+        while let Ok(update) = rx.try_recv() {
+            match update {
+                Update::Instances(instances) => app.set_instances(instances),
+                Update::Cpu(cpu) => app.set_cpu(cpu),
+                Update::Message(message) => app.set_message(message),
+            }
+        }
+        app.expire_message(Instant::now());
+        terminal.draw(|frame| ui::draw(frame, &app))?;
+
+        // crossterm's poll blocks, so keep it off the async scheduler
+        let event = tokio::task::block_in_place(|| -> Result<Option<Event>> {
+            Ok(match event::poll(KEY_POLL)? {
+                true => Some(event::read()?),
+                false => None,
+            })
+        })?;
+        let Some(Event::Key(key)) = event else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        app.dismiss_message();
+        if key.code != KeyCode::Char('x') {
+            app.cancel_pending();
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Down | KeyCode::Char('j') => app.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => app.select_previous(),
+            KeyCode::Char('r') => next_refresh = Instant::now(),
+            KeyCode::Char('s') => {
+                if let Some(instance) = app.selected().cloned() {
+                    app.set_message(format!("Starting {}…", instance.name));
+                    spawn_action(&compute, &scope, &tx, instance, Action::Start);
+                }
+            }
+            KeyCode::Char('x') => {
+                if let Some(instance) = app.request_stop() {
+                    app.set_message(format!("Stopping {}…", instance.name));
+                    spawn_action(&compute, &scope, &tx, instance, Action::Stop);
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(instance) = app.selected().cloned() {
+                    ssh(terminal, &scope.project, &instance, &mut app)?;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn spawn_refresh(
+    compute: &Arc<Compute>,
+    monitoring: &Arc<Monitoring>,
+    scope: &Arc<Scope>,
+    tx: &mpsc::UnboundedSender<Update>,
+) {
+    let project = scope.project.clone();
+    let (compute, scope, tx2) = (compute.clone(), scope.clone(), tx.clone());
+    tokio::spawn(async move {
+        let update = match compute
+            .list_instances_in(&scope.project, scope.zone.as_deref(), scope.filter.as_ref())
+            .await
+        {
+            Ok(instances) => Update::Instances(instances),
+            Err(err) => Update::Message(format!("Refresh failed: {err:#}")),
+        };
+        let _ = tx2.send(update);
+    });
+    // metrics are best-effort; the dashboard works without Monitoring access
+    let (monitoring, tx) = (monitoring.clone(), tx.clone());
+    tokio::spawn(async move {
+        let update = match monitoring.cpu_utilization(&project).await {
+            Ok(cpu) => Update::Cpu(cpu),
+            Err(err) => Update::Message(format!("CPU metrics unavailable: {err:#}")),
+        };
+        let _ = tx.send(update);
+    });
+}
+
+#[derive(Clone, Copy)]
+enum Action {
+    Start,
+    Stop,
+}
+
+fn spawn_action(
+    compute: &Arc<Compute>,
+    scope: &Arc<Scope>,
+    tx: &mpsc::UnboundedSender<Update>,
+    instance: Instance,
+    action: Action,
+) {
+    let (compute, scope, tx) = (compute.clone(), scope.clone(), tx.clone());
+    tokio::spawn(async move {
+        let zone = instance.zone_name();
+        let result = match action {
+            Action::Start => {
+                compute
+                    .start_instance(&scope.project, zone, &instance.name)
+                    .await
+            }
+            Action::Stop => {
+                compute
+                    .stop_instance(&scope.project, zone, &instance.name)
+                    .await
+            }
+        };
+        let message = match (result, action) {
+            (Ok(_), Action::Start) => format!("Start requested for {}", instance.name),
+            (Ok(_), Action::Stop) => format!("Stop requested for {}", instance.name),
+            (Err(err), _) => format!("{}: {err:#}", instance.name),
+        };
+        let _ = tx.send(Update::Message(message));
+    });
+}
+
+/// Leaves the dashboard for an interactive `gcectl ssh` session and returns
+/// to it afterwards.
+fn ssh(
+    terminal: &mut DefaultTerminal,
+    project: &str,
+    instance: &Instance,
+    app: &mut App,
+) -> Result<()> {
+    ratatui::try_restore()?;
+    let status = process::Command::new(env::current_exe()?)
+        .args(["ssh", &instance.name, "--project", project])
+        .args(["--zone", instance.zone_name()])
+        .status();
+    *terminal = ratatui::try_init()?;
+    app.set_message(match status {
+        Ok(status) if status.success() => format!("ssh session to {} ended", instance.name),
+        Ok(status) => format!("ssh to {} exited with {status}", instance.name),
+        Err(err) => format!("failed to run ssh: {err}"),
+    });
+    Ok(())
+}
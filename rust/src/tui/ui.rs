@@ -0,0 +1,86 @@
+use gcectl_core::output::status_emoji;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState};
+
+use super::app::{App, Line, cpu_bar};
+
+const KEYS: &str = "↑/↓ select  s start  x stop  enter ssh  r refresh  q quit";
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [title, body, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let refreshed = app.last_refresh.map_or_else(
+        || "loading…".to_string(),
+        |t| t.format("%H:%M:%S").to_string(),
+    );
+    frame.render_widget(
+        Paragraph::new(format!(
+            " gcectl top — {} • {} instance(s) • {refreshed}",
+            app.project,
+            app.instance_count()
+        ))
+        .style(Style::new().add_modifier(Modifier::BOLD)),
+        title,
+    );
+
+    let rows = app.lines().into_iter().map(|line| match line {
+        Line::Zone(zone) => Row::new([Cell::from(Span::styled(
+            zone.to_string(),
+            Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ))]),
+        Line::Instance(instance) => Row::new([
+            Cell::from(format!("  {}", instance.name)),
+            Cell::from(format!(
+                "{} {}",
+                status_emoji(&instance.status),
+                instance.status
+            )),
+            Cell::from(instance.machine_type_name().to_string()),
+            Cell::from(instance.internal_ip().unwrap_or("-").to_string()),
+            Cell::from(instance.external_ip().unwrap_or("-").to_string()),
+            Cell::from(
+                app.cpu_of(instance)
+                    .map_or_else(|| "-".to_string(), cpu_bar),
+            ),
+        ]),
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(24),
+            Constraint::Length(14),
+            Constraint::Length(16),
+            Constraint::Length(16),
+            Constraint::Length(16),
+            Constraint::Length(16),
+        ],
+    )
+    .header(
+        Row::new([
+            "Name",
+            "Status",
+            "Machine-Type",
+            "Internal-IP",
+            "External-IP",
+            "CPU",
+        ])
+        .style(Style::new().add_modifier(Modifier::UNDERLINED)),
+    )
+    .block(Block::new().borders(Borders::TOP | Borders::BOTTOM))
+    .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let mut state = TableState::default().with_selected(app.selected_line());
+    frame.render_stateful_widget(table, body, &mut state);
+
+    frame.render_widget(
+        Paragraph::new(format!(" {}", app.message().unwrap_or(KEYS))),
+        footer,
+    );
+}
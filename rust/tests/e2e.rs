@@ -0,0 +1,2053 @@
+//! End-to-end runs of gcectl against the mock API in `support`.
+
+mod support;
+
+use predicates::prelude::*;
+use serde_json::json;
+
+use support::{MockApi, PROJECT, ZONE, instance};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn instances_list_prints_instances() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": [instance("web-1", "RUNNING"), instance("db", "TERMINATED")]}),
+    );
+    api.command()
+        .args(["instances", "list", "--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("web-1").and(predicate::str::contains("TERMINATED")));
+    api.command()
+        .args(["instances", "list", "--project", PROJECT, "--zone", ZONE])
+        .args(["--filter", "status=RUNNING", "--no-cache"])
+        .assert()
+        .success();
+    let requests = api.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].query.contains("filter="));
+    Ok(())
+}
+
+#[test]
+fn instances_list_stops_at_the_limit() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({
+            "items": [instance("web-1", "RUNNING"), instance("web-2", "RUNNING"), instance("db", "TERMINATED")],
+            "nextPageToken": "page-2",
+        }),
+    );
+    api.command()
+        .args(["instances", "list", "--project", PROJECT, "--zone", ZONE])
+        .args(["--limit", "2", "--output", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("web-2").and(predicate::str::contains("db").not()));
+    let requests = api.requests();
+    assert_eq!(requests.len(), 1, "the next page is never fetched");
+    assert!(requests[0].query.contains("maxResults=2"));
+    Ok(())
+}
+
+#[test]
+fn columns_and_sort_by_shape_listings() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": [instance("db", "TERMINATED"), instance("web-1", "RUNNING")]}),
+    );
+    api.command()
+        .args(["instances", "list", "--project", PROJECT, "--zone", ZONE])
+        .args([
+            "-o",
+            "csv",
+            "--columns",
+            "status,name",
+            "--sort-by",
+            "~name",
+        ])
+        .assert()
+        .success()
+        .stdout("Status,Name\nRUNNING,web-1\nTERMINATED,db\n");
+    api.command()
+        .args(["instances", "list", "--project", PROJECT, "--zone", ZONE])
+        .args(["--columns", "name,owner"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown column `owner`"));
+    Ok(())
+}
+
+#[test]
+fn format_projects_chosen_fields() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": [instance("web-1", "RUNNING"), instance("db", "TERMINATED")]}),
+    );
+    api.command()
+        .args(["instances", "list", "--project", PROJECT, "--zone", ZONE])
+        .args(["--format", "value(name,networkInterfaces[0].networkIP)"])
+        .assert()
+        .success()
+        .stdout("web-1\t10.0.0.2\ndb\t10.0.0.2\n");
+    api.command()
+        .args(["instances", "list", "--project", PROJECT, "--zone", ZONE])
+        .args(["--format", "name", "--no-cache"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("PRINTER(FIELD,...)"));
+    Ok(())
+}
+
+#[test]
+fn instances_start_posts_and_waits() -> TestResult {
+    let api = MockApi::start();
+    api.operation("POST", "instances/web-1/start");
+    api.command()
+        .args([
+            "instances",
+            "start",
+            "web-1",
+            "--project",
+            PROJECT,
+            "--zone",
+            ZONE,
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[SUCCESS]"));
+    let requests = api.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "POST");
+    assert!(requests[0].path.ends_with("/instances/web-1/start"));
+    Ok(())
+}
+
+#[test]
+fn reset_suspend_and_resume_wait_for_their_operations() -> TestResult {
+    let api = MockApi::start();
+    let zonal = format!("projects/{PROJECT}/zones/{ZONE}");
+    for (verb, past) in [
+        ("reset", "reset"),
+        ("suspend", "suspended"),
+        ("resume", "resumed"),
+    ] {
+        let operation = format!("operation-{verb}");
+        let self_link = format!("{}/compute/v1/{zonal}/operations/{operation}", api.url());
+        api.compute(
+            "POST",
+            &format!("{zonal}/instances/web-1/{verb}"),
+            json!({"name": operation, "status": "RUNNING", "selfLink": self_link}),
+        )
+        .compute(
+            "POST",
+            &format!("{zonal}/operations/{operation}/wait"),
+            json!({"name": operation, "status": "DONE", "selfLink": self_link}),
+        );
+
+        api.command()
+            .args([
+                "instances",
+                verb,
+                "web-1",
+                "--project",
+                PROJECT,
+                "--zone",
+                ZONE,
+            ])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(format!("Instance web-1 {past}")));
+    }
+
+    let paths: Vec<String> = api.requests().into_iter().map(|r| r.path).collect();
+    let zonal = format!("/compute/v1/{zonal}");
+    assert_eq!(
+        paths,
+        [
+            format!("{zonal}/instances/web-1/reset"),
+            format!("{zonal}/operations/operation-reset/wait"),
+            format!("{zonal}/instances/web-1/suspend"),
+            format!("{zonal}/operations/operation-suspend/wait"),
+            format!("{zonal}/instances/web-1/resume"),
+            format!("{zonal}/operations/operation-resume/wait"),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn suspend_reports_an_operation_that_failed() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "POST",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances/gpu-1/suspend"),
+        json!({
+            "name": "operation-1",
+            "status": "DONE",
+            "error": {"errors": [{
+                "code": "UNSUPPORTED_OPERATION",
+                "message": "Instances with GPUs cannot be suspended",
+            }]},
+        }),
+    );
+    api.command()
+        .args([
+            "instances",
+            "suspend",
+            "gpu-1",
+            "--project",
+            PROJECT,
+            "--zone",
+            ZONE,
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Instances with GPUs cannot be suspended",
+        ));
+    Ok(())
+}
+
+#[test]
+fn events_jsonl_keeps_dry_run_previews_to_json_lines() -> TestResult {
+    let api = MockApi::start();
+    let assert = api
+        .command()
+        .args([
+            "instances",
+            "stop",
+            "web-1",
+            "--dry-run",
+            "--events",
+            "jsonl",
+        ])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+    let events: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("a JSON line"))
+        .collect();
+    let preview = events
+        .iter()
+        .find(|e| e["event"] == "dry_run")
+        .expect("a dry_run event");
+    assert_eq!(preview["method"], "POST");
+    assert!(
+        preview["url"]
+            .as_str()
+            .is_some_and(|url| url.ends_with("/instances/web-1/stop")),
+        "{stdout}"
+    );
+    assert!(api.requests().is_empty());
+    Ok(())
+}
+
+#[test]
+fn events_jsonl_reports_operations_as_json_lines() -> TestResult {
+    let api = MockApi::start();
+    api.operation("POST", "instances/web-1/start");
+    let assert = api
+        .command()
+        .args([
+            "instances",
+            "start",
+            "web-1",
+            "--events",
+            "jsonl",
+            "--project",
+            PROJECT,
+            "--zone",
+            ZONE,
+        ])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+    let kinds: Vec<String> = stdout
+        .lines()
+        .map(|line| {
+            let event: serde_json::Value = serde_json::from_str(line).expect("a JSON line");
+            event["event"].as_str().unwrap_or_default().to_string()
+        })
+        .collect();
+    assert!(kinds.contains(&"operation_started".to_string()), "{stdout}");
+    assert!(kinds.contains(&"operation_done".to_string()), "{stdout}");
+    assert_eq!(
+        kinds.last().map(String::as_str),
+        Some("success"),
+        "{stdout}"
+    );
+    Ok(())
+}
+
+#[test]
+fn project_info_sets_the_default_service_account_through_the_beta_api() -> TestResult {
+    let api = MockApi::start();
+    api.route(
+        "POST",
+        &format!("/compute/beta/projects/{PROJECT}/setDefaultServiceAccount"),
+        200,
+        json!({
+            "name": "operation-1",
+            "status": "DONE",
+            "selfLink": format!("{}/compute/v1/projects/{PROJECT}/global/operations/operation-1", api.url()),
+        }),
+    );
+    api.command()
+        .args(["project-info", "set-default-service-account"])
+        .args(["runner@demo.iam.gserviceaccount.com", "--project", PROJECT])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("run as runner@demo"));
+    let requests = api.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].body,
+        json!({"email": "runner@demo.iam.gserviceaccount.com"})
+    );
+    Ok(())
+}
+
+#[test]
+fn disks_analyze_flags_a_disk_at_its_iops_limit() -> TestResult {
+    let api = MockApi::start();
+    let mut web = instance("web-1", "RUNNING");
+    web["id"] = json!("123");
+    web["disks"] = json!([{
+        "deviceName": "data",
+        "source": format!("https://www.googleapis.com/compute/v1/projects/{PROJECT}/zones/{ZONE}/disks/data-1"),
+    }]);
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/disks/data-1"),
+        json!({
+            "name": "data-1",
+            "sizeGb": "100",
+            "type": format!("projects/{PROJECT}/zones/{ZONE}/diskTypes/pd-standard"),
+            "users": [format!("projects/{PROJECT}/zones/{ZONE}/instances/web-1")],
+        }),
+    )
+    .compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances/web-1"),
+        web,
+    )
+    // every metric answers the same: 35 per second of ops or bytes
+    .route(
+        "GET",
+        &format!("/v3/projects/{PROJECT}/timeSeries"),
+        200,
+        json!({"timeSeries": [{"points": [{"value": {"doubleValue": 35.0}}, {"value": {"doubleValue": 5.0}}]}]}),
+    );
+    api.command()
+        .env("CLOUDSDK_API_ENDPOINT_OVERRIDES_MONITORING", api.url())
+        .args(["--output", "csv", "disks", "analyze", "data-1"])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "IOPS,40,70,75,93%,THROTTLED,resize to 156 GB (limit 117)",
+        ))
+        .stdout(predicate::str::contains(
+            "Throughput,0.0 MiB/s,0.0 MiB/s,12.0 MiB/s,0%,OK,",
+        ));
+    Ok(())
+}
+
+#[test]
+fn resume_retries_only_the_failed_members_of_a_batch() -> TestResult {
+    let api = MockApi::start();
+    api.operation("POST", "instances/web-1/stop");
+    let web_2 = format!("/compute/v1/projects/{PROJECT}/zones/{ZONE}/instances/web-2/stop");
+    api.route(
+        "POST",
+        &web_2,
+        503,
+        json!({"error": {"message": "Backend unavailable"}}),
+    );
+    let assert = api
+        .command()
+        .args(["instances", "stop", "web-1", "web-2"])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .code(6);
+    let stderr = String::from_utf8(assert.get_output().stderr.clone())?;
+    let file = stderr
+        .lines()
+        .find_map(|line| line.split("gcectl resume ").nth(1))
+        .expect("a resume hint")
+        .trim()
+        .to_string();
+    let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+    assert_eq!(saved["action"], "stop");
+    assert_eq!(saved["pending"], json!(["web-2"]));
+
+    // a dry run sends nothing and keeps the file for the real retry
+    api.command()
+        .args(["--dry-run", "resume", &file, "-y"])
+        .assert()
+        .success();
+    assert!(std::path::Path::new(&file).exists());
+
+    api.operation("POST", "instances/web-2/stop");
+    api.command()
+        .args(["resume", &file, "-y"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("web-2"))
+        .stdout(predicate::str::contains("web-1").not());
+    let retried = api.requests();
+    assert_eq!(retried.len(), 3);
+    assert_eq!(retried[2].path, web_2);
+    assert!(!std::path::Path::new(&file).exists());
+    Ok(())
+}
+
+#[test]
+fn instances_stop_all_stops_every_listed_instance() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": [instance("web-1", "RUNNING"), instance("web-2", "RUNNING")]}),
+    );
+    api.operation("POST", "instances/web-1/stop");
+    api.operation("POST", "instances/web-2/stop");
+    api.command()
+        .args([
+            "instances",
+            "stop",
+            "--all",
+            "-y",
+            "--project",
+            PROJECT,
+            "--zone",
+            ZONE,
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("web-2"));
+    let stops = api
+        .requests()
+        .into_iter()
+        .filter(|r| r.method == "POST")
+        .count();
+    assert_eq!(stops, 2);
+    Ok(())
+}
+
+#[test]
+fn instances_create_sends_the_instance_body() -> TestResult {
+    let api = MockApi::start();
+    api.operation("POST", "instances");
+    api.command()
+        .args([
+            "instances",
+            "create",
+            "trainer",
+            "--machine-type",
+            "n2-standard-4",
+            "--project",
+            PROJECT,
+            "--zone",
+            ZONE,
+        ])
+        .assert()
+        .success();
+    let requests = api.requests();
+    let insert = requests
+        .iter()
+        .find(|r| r.method == "POST")
+        .ok_or("no insert request")?;
+    assert_eq!(insert.body["name"], "trainer");
+    assert!(
+        insert.body["machineType"]
+            .as_str()
+            .is_some_and(|t| t.ends_with("machineTypes/n2-standard-4"))
+    );
+    Ok(())
+}
+
+#[test]
+fn create_checks_the_machine_type_against_the_cached_catalog() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/machineTypes"),
+        json!({"items": [{"name": "n2-standard-4", "guestCpus": 4, "memoryMb": 16384}]}),
+    );
+    for _ in 0..2 {
+        api.command()
+            .args([
+                "instances",
+                "create",
+                "trainer",
+                "--machine-type",
+                "n2-standrd-4",
+            ])
+            .args(["--project", PROJECT, "--zone", ZONE])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "machine type n2-standrd-4 is not offered in",
+            ));
+    }
+    let requests = api.requests();
+    assert!(requests.iter().all(|r| r.method == "GET"));
+    // the second run answers from the catalog cache
+    let listings = requests
+        .iter()
+        .filter(|r| r.path.ends_with("/machineTypes"))
+        .count();
+    assert_eq!(listings, 1);
+
+    api.operation("POST", "instances");
+    api.command()
+        .args([
+            "instances",
+            "create",
+            "trainer",
+            "--machine-type",
+            "n2-standrd-4",
+        ])
+        .args(["--skip-validation", "--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success();
+    assert!(api.requests().iter().any(|r| r.method == "POST"));
+    Ok(())
+}
+
+#[test]
+fn instances_create_count_names_instances_from_the_template() -> TestResult {
+    let api = MockApi::start();
+    api.operation("POST", "instances");
+    api.command()
+        .args(["--output", "csv", "instances", "create", "--count", "3"])
+        .args(["--name-template", "worker-{index:03}", "--start-index", "9"])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success()
+        .stdout("Name,Result\nworker-009,created\nworker-010,created\nworker-011,created\n");
+    let mut names: Vec<String> = api
+        .requests()
+        .iter()
+        .filter(|r| r.method == "POST")
+        .map(|r| r.body["name"].as_str().unwrap_or_default().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["worker-009", "worker-010", "worker-011"]);
+    Ok(())
+}
+
+#[test]
+fn api_errors_come_with_a_hint() -> TestResult {
+    let api = MockApi::start();
+    api.route(
+        "POST",
+        &format!("/compute/v1/projects/{PROJECT}/zones/{ZONE}/instances/web-1/stop"),
+        403,
+        json!({"error": {"message": "Required 'compute.instances.stop' permission"}}),
+    );
+    api.command()
+        .args([
+            "instances",
+            "stop",
+            "web-1",
+            "--project",
+            PROJECT,
+            "--zone",
+            ZONE,
+        ])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("403 Forbidden").and(predicate::str::contains("Hint:")));
+    Ok(())
+}
+
+#[test]
+fn gcloud_endpoint_override_is_honored() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": [instance("web-1", "RUNNING")]}),
+    );
+    api.command()
+        .env_remove("GCECTL_API_ENDPOINT")
+        .env(
+            "CLOUDSDK_API_ENDPOINT_OVERRIDES_COMPUTE",
+            format!("{}/", api.url()),
+        )
+        .args(["instances", "list", "--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("web-1"));
+    Ok(())
+}
+
+#[test]
+fn self_update_check_reports_newer_release() -> TestResult {
+    let api = MockApi::start();
+    api.route(
+        "GET",
+        "/releases",
+        200,
+        json!([
+            {"tag_name": "go-v100.0.0", "assets": []},
+            {"tag_name": "rust-v99.0.0", "assets": []},
+        ]),
+    );
+    api.command()
+        .env("GCECTL_RELEASES_URL", format!("{}/releases", api.url()))
+        .args(["self-update", "--check"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("gcectl 99.0.0 is available"));
+    Ok(())
+}
+
+#[test]
+fn set_startup_script_keeps_the_metadata_fingerprint() -> TestResult {
+    let api = MockApi::start();
+    let dir = tempfile::tempdir()?;
+    let script = dir.path().join("boot.sh");
+    std::fs::write(&script, "#!/bin/bash\necho hi\n")?;
+    let mut web = instance("web-1", "RUNNING");
+    web["metadata"] = json!({"fingerprint": "fp-1", "items": [
+        {"key": "startup-script-url", "value": "gs://bucket/old.sh"}
+    ]});
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances/web-1"),
+        web,
+    );
+    api.operation("POST", "instances/web-1/setMetadata");
+    api.command()
+        .args(["instances", "set-startup-script", "web-1", "--from-file"])
+        .arg(&script)
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success();
+    let requests = api.requests();
+    let set = requests
+        .iter()
+        .find(|r| r.method == "POST")
+        .ok_or("no setMetadata request")?;
+    assert_eq!(set.body["fingerprint"], "fp-1");
+    assert_eq!(
+        set.body["items"],
+        json!([{"key": "startup-script", "value": "#!/bin/bash\necho hi\n"}])
+    );
+    Ok(())
+}
+
+#[test]
+fn add_labels_merges_under_the_label_fingerprint() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/disks/data"),
+        json!({"name": "data", "labels": {"team": "ml"}, "labelFingerprint": "lf-1"}),
+    );
+    api.compute(
+        "POST",
+        &format!("projects/{PROJECT}/zones/{ZONE}/disks/data/setLabels"),
+        json!({"name": "operation-1", "status": "DONE"}),
+    );
+    api.command()
+        .args(["disks", "add-labels", "data", "env=prod"])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Set 1 label(s) on disk data"));
+    let requests = api.requests();
+    let set = requests
+        .iter()
+        .find(|r| r.method == "POST")
+        .ok_or("no setLabels request")?;
+    assert_eq!(
+        set.body,
+        json!({"labels": {"env": "prod", "team": "ml"}, "labelFingerprint": "lf-1"})
+    );
+    Ok(())
+}
+
+#[test]
+fn add_tags_sends_the_tags_fingerprint() -> TestResult {
+    let api = MockApi::start();
+    let mut web = instance("web-1", "RUNNING");
+    web["tags"] = json!({"items": ["ssh"], "fingerprint": "tf-1"});
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances/web-1"),
+        web,
+    );
+    api.compute(
+        "POST",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances/web-1/setTags"),
+        json!({"name": "operation-1", "status": "DONE"}),
+    );
+    api.command()
+        .args(["instances", "add-tags", "web-1", "http-server", "ssh"])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Added network tag(s) http-server, ssh to instance web-1",
+        ));
+    let requests = api.requests();
+    let set = requests
+        .iter()
+        .find(|r| r.method == "POST")
+        .ok_or("no setTags request")?;
+    // so that a retried write is carried out once
+    assert!(set.query.contains("requestId="), "{}", set.query);
+    assert_eq!(
+        set.body,
+        json!({"fingerprint": "tf-1", "items": ["ssh", "http-server"]})
+    );
+    Ok(())
+}
+
+#[test]
+fn drained_instances_rejoin_their_groups_on_start() -> TestResult {
+    let api = MockApi::start();
+    let zonal = format!("projects/{PROJECT}/zones/{ZONE}");
+    api.compute(
+        "GET",
+        &format!("{zonal}/instanceGroups"),
+        json!({"items": [{"name": "web-ig"}]}),
+    );
+    api.compute("GET", &format!("{zonal}/instanceGroupManagers"), json!({}));
+    api.compute(
+        "POST",
+        &format!("{zonal}/instanceGroups/web-ig/listInstances"),
+        json!({"items": [{"instance": format!("https://x/{zonal}/instances/web-1"), "status": "RUNNING"}]}),
+    );
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/aggregated/backendServices"),
+        json!({"items": {"global": {"backendServices": [{
+            "name": "web-bs",
+            "backends": [{"group": format!("https://x/{zonal}/instanceGroups/web-ig")}]
+        }]}}}),
+    );
+    api.operation("POST", "instanceGroups/web-ig/removeInstances");
+    api.operation("POST", "instanceGroups/web-ig/addInstances");
+    api.operation("POST", "instances/web-1/stop");
+    api.operation("POST", "instances/web-1/start");
+
+    api.command()
+        .args(["instances", "stop", "web-1", "--drain", "0"])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Instance web-1 left instance group(s) web-ig",
+        ));
+    api.command()
+        .args(["instances", "start", "web-1"])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Instance web-1 rejoined instance group(s) web-ig",
+        ));
+
+    let requests = api.requests();
+    let member = json!({"instances": [{"instance": format!("{zonal}/instances/web-1")}]});
+    let removed = requests
+        .iter()
+        .position(|r| r.path.ends_with("/removeInstances"))
+        .ok_or("no removeInstances request")?;
+    let stopped = requests
+        .iter()
+        .position(|r| r.path.ends_with("/web-1/stop"))
+        .ok_or("no stop request")?;
+    let added = requests
+        .iter()
+        .position(|r| r.path.ends_with("/addInstances"))
+        .ok_or("no addInstances request")?;
+    assert!(removed < stopped && stopped < added);
+    assert_eq!(requests[removed].body, member);
+    assert_eq!(requests[added].body, member);
+    Ok(())
+}
+
+#[test]
+fn dry_run_drain_neither_waits_nor_rejoins() -> TestResult {
+    let api = MockApi::start();
+    let zonal = format!("projects/{PROJECT}/zones/{ZONE}");
+    api.compute(
+        "GET",
+        &format!("{zonal}/instanceGroups"),
+        json!({"items": [{"name": "web-ig"}]}),
+    );
+    api.compute("GET", &format!("{zonal}/instanceGroupManagers"), json!({}));
+    api.compute(
+        "POST",
+        &format!("{zonal}/instanceGroups/web-ig/listInstances"),
+        json!({"items": [{"instance": format!("https://x/{zonal}/instances/web-1"), "status": "RUNNING"}]}),
+    );
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/aggregated/backendServices"),
+        json!({}),
+    );
+    api.operation("POST", "instances/web-1/start");
+
+    api.command()
+        .args(["--dry-run", "instances", "stop", "web-1", "--drain", "600"])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .timeout(std::time::Duration::from_secs(30))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[DRY-RUN] | POST"))
+        .stdout(predicate::str::contains("web-ig/removeInstances"));
+    api.command()
+        .args(["instances", "start", "web-1"])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success();
+
+    assert!(
+        !api.requests()
+            .iter()
+            .any(|r| r.path.ends_with("/addInstances") || r.path.ends_with("/removeInstances"))
+    );
+    Ok(())
+}
+
+#[test]
+fn dry_run_reserve_reads_nothing_back() -> TestResult {
+    let api = MockApi::start();
+
+    api.command()
+        .args(["--dry-run", "addresses", "reserve", "web-ip"])
+        .args(["--project", PROJECT, "--region", "us-central1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[DRY-RUN] | POST"))
+        .stdout(predicate::str::contains("regions/us-central1/addresses"));
+
+    assert!(api.requests().is_empty());
+    Ok(())
+}
+
+#[test]
+fn snapshots_restore_creates_then_attaches_the_disk() -> TestResult {
+    let api = MockApi::start();
+    api.operation("POST", "disks");
+    api.operation("POST", "instances/web-1/attachDisk");
+    api.command()
+        .args([
+            "snapshots",
+            "restore",
+            "nightly",
+            "--as-disk",
+            "data-restored",
+        ])
+        .args(["--attach-to", "web-1", "--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Disk data-restored attached to web-1",
+        ));
+    let requests = api.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(
+        requests[0].body,
+        json!({"name": "data-restored", "sourceSnapshot": "global/snapshots/nightly"})
+    );
+    assert!(requests[1].path.ends_with("/instances/web-1/attachDisk"));
+    assert_eq!(
+        requests[1].body["source"],
+        format!("projects/{PROJECT}/zones/{ZONE}/disks/data-restored")
+    );
+    Ok(())
+}
+
+#[test]
+fn apply_creates_missing_instances_and_updates_labels() -> TestResult {
+    let api = MockApi::start();
+    let mut web = instance("web-1", "RUNNING");
+    web["labels"] = json!({"env": "dev"});
+    web["labelFingerprint"] = json!("lf-1");
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/aggregated/instances"),
+        json!({"items": {format!("zones/{ZONE}"): {"instances": [web.clone()]}}}),
+    );
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances/web-1"),
+        web,
+    );
+    api.operation("POST", "instances/web-1/setLabels")
+        .operation("POST", "instances");
+
+    let manifest = format!(
+        "project: {PROJECT}\n\
+         instances:\n\
+         - name: web-1\n  zone: {ZONE}\n  labels: {{env: prod}}\n\
+         - name: web-2\n  zone: {ZONE}\n  machineType: e2-small\n"
+    );
+    api.command()
+        .args(["apply", "-f", "-", "--force"])
+        .write_stdin(manifest)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("+ create web-2"))
+        .stdout(predicate::str::contains(
+            "Plan: 1 to create, 1 to update, 0 to delete.",
+        ))
+        .stdout(predicate::str::contains("Instance web-2 created"));
+
+    let requests = api.requests();
+    let posts: Vec<_> = requests.iter().filter(|r| r.method == "POST").collect();
+    assert_eq!(posts.len(), 2);
+    assert_eq!(
+        posts[0].body,
+        json!({"labels": {"env": "prod"}, "labelFingerprint": "lf-1"})
+    );
+    assert_eq!(posts[1].body["name"], "web-2");
+    assert!(
+        posts[1].body["machineType"]
+            .as_str()
+            .is_some_and(|m| m.ends_with("/e2-small"))
+    );
+    Ok(())
+}
+
+#[test]
+fn quotas_fail_above_threshold() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/regions/us-central1"),
+        json!({"name": "us-central1", "quotas": [
+            {"metric": "CPUS", "limit": 24, "usage": 23},
+            {"metric": "IN_USE_ADDRESSES", "limit": 8, "usage": 1},
+            {"metric": "SSD_TOTAL_GB", "limit": 500, "usage": 0},
+        ]}),
+    );
+    api.command()
+        .args([
+            "quotas",
+            "list",
+            "--project",
+            PROJECT,
+            "--region",
+            "us-central1",
+        ])
+        .args(["--fail-above", "90"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("CPUS"))
+        .stdout(predicate::str::contains("SSD_TOTAL_GB").not())
+        .stderr(predicate::str::contains(
+            "CPUS in us-central1 is at 96% of its limit (23/24)",
+        ))
+        .stderr(predicate::str::contains(
+            "1 quota(s) in us-central1 above 90%: CPUS",
+        ));
+    Ok(())
+}
+
+#[test]
+fn verbose_http_dumps_redact_credentials() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": [instance("web-1", "RUNNING")]}),
+    );
+    api.command()
+        .args([
+            "instances",
+            "list",
+            "--project",
+            PROJECT,
+            "--zone",
+            ZONE,
+            "-vvv",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("gcectl::http"))
+        .stderr(predicate::str::contains(
+            r#"("authorization", "[REDACTED]")"#,
+        ))
+        .stderr(predicate::str::contains("test-token").not());
+    Ok(())
+}
+
+#[test]
+fn timeout_names_the_operation_still_running() -> TestResult {
+    let api = MockApi::start();
+    let running = json!({
+        "name": "operation-slow",
+        "status": "RUNNING",
+        "selfLink": format!("{}/compute/v1/projects/{PROJECT}/zones/{ZONE}/operations/operation-slow", api.url()),
+    });
+    api.compute(
+        "POST",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        running.clone(),
+    )
+    .compute(
+        "POST",
+        &format!("projects/{PROJECT}/zones/{ZONE}/operations/operation-slow/wait"),
+        running,
+    );
+    api.command()
+        .args(["--timeout", "1s", "instances", "create", "web-1"])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .code(124)
+        .stderr(predicate::str::contains(
+            "timed out after 1s waiting for operation operation-slow",
+        ))
+        .stderr(predicate::str::contains("gcectl operations wait"));
+    Ok(())
+}
+
+#[test]
+fn quiet_batches_print_names_and_exit_6_on_partial_failure() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": [instance("web-1", "RUNNING"), instance("web-2", "RUNNING")]}),
+    );
+    api.command()
+        .args([
+            "--quiet",
+            "instances",
+            "list",
+            "--project",
+            PROJECT,
+            "--zone",
+            ZONE,
+        ])
+        .assert()
+        .success()
+        .stdout("web-1\nweb-2\n");
+    api.operation("POST", "instances/web-1/stop");
+    api.route(
+        "POST",
+        &format!("/compute/v1/projects/{PROJECT}/zones/{ZONE}/instances/web-2/stop"),
+        404,
+        json!({"error": {"message": "The resource 'web-2' was not found"}}),
+    );
+    api.command()
+        .args(["-q", "instances", "stop", "web-1", "web-2"])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .code(6)
+        .stdout("web-1\nweb-2\n")
+        .stderr(predicate::str::contains(
+            "failed to stop 1 of 2 instance(s)",
+        ));
+    Ok(())
+}
+
+#[test]
+fn all_projects_lists_every_discovered_project() -> TestResult {
+    let api = MockApi::start();
+    api.route(
+        "GET",
+        "/v1/projects",
+        200,
+        json!({"projects": [{"projectId": "team-b"}, {"projectId": "team-a"}]}),
+    )
+    .compute(
+        "GET",
+        "projects/team-a/zones/asia-northeast1-a/instances",
+        json!({"items": [instance("web-1", "RUNNING")]}),
+    )
+    .route(
+        "GET",
+        "/compute/v1/projects/team-b/zones/asia-northeast1-a/instances",
+        403,
+        json!({"error": {"message": "Compute Engine API has not been used"}}),
+    );
+    api.command()
+        .env(
+            "CLOUDSDK_API_ENDPOINT_OVERRIDES_CLOUDRESOURCEMANAGER",
+            api.url(),
+        )
+        .args([
+            "instances",
+            "list",
+            "--all-projects",
+            "--zone",
+            "asia-northeast1-a",
+        ])
+        .args(["--output", "csv"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Name,Project,Zone"))
+        .stdout(predicate::str::contains("web-1,team-a,"))
+        .stderr(predicate::str::contains("skipping project team-b"));
+    Ok(())
+}
+
+#[test]
+fn os_patch_status_joins_inventories_and_patch_jobs() -> TestResult {
+    let api = MockApi::start();
+    let mut web = instance("web-1", "RUNNING");
+    web["id"] = json!("111");
+    let mut db = instance("db", "RUNNING");
+    db["id"] = json!("222");
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": [web, db]}),
+    );
+    api.route(
+        "GET",
+        &format!("/v1/projects/{PROJECT}/locations/{ZONE}/instances/-/inventories"),
+        200,
+        json!({"inventories": [{
+            "name": format!("projects/1/locations/{ZONE}/instances/111/inventory"),
+            "osInfo": {"longName": "Debian GNU/Linux 12"},
+            "items": {"1": {"type": "AVAILABLE_PACKAGE", "availablePackage": {
+                "aptPackage": {"packageName": "openssl", "version": "3.0.13"}
+            }}}
+        }]}),
+    );
+    api.route(
+        "GET",
+        &format!("/v1/projects/{PROJECT}/patchJobs"),
+        200,
+        json!({"patchJobs": [{
+            "name": format!("projects/{PROJECT}/patchJobs/weekly-1"),
+            "createTime": "2026-10-01T00:00:00Z",
+            "state": "SUCCEEDED"
+        }]}),
+    );
+    api.route(
+        "GET",
+        &format!("/v1/projects/{PROJECT}/patchJobs/weekly-1/instanceDetails"),
+        200,
+        json!({"patchJobInstanceDetails": [{
+            "name": format!("projects/{PROJECT}/zones/{ZONE}/instances/web-1"),
+            "instanceSystemId": "111",
+            "state": "SUCCEEDED_REBOOT_REQUIRED"
+        }]}),
+    );
+    api.command()
+        .env("CLOUDSDK_API_ENDPOINT_OVERRIDES_OSCONFIG", api.url())
+        .args(["os", "patch-status", "--project", PROJECT, "--zone", ZONE])
+        .args(["--output", "csv"])
+        .assert()
+        .success()
+        .stdout(concat!(
+            "Instance,OS,Updates,Last-Patch-Job,State,Failure\n",
+            "web-1,Debian GNU/Linux 12,1,weekly-1,SUCCEEDED_REBOOT_REQUIRED,\n",
+            "db,-,-,-,-,\n",
+        ));
+    Ok(())
+}
+
+#[test]
+fn diagnose_suggests_enabling_a_disabled_compute_api() -> TestResult {
+    let api = MockApi::start();
+    api.route(
+        "GET",
+        &format!("/compute/v1/projects/{PROJECT}"),
+        403,
+        json!({"error": {
+            "message": "Compute Engine API has not been used in project demo",
+            "details": [{"@type": "type.googleapis.com/google.rpc.ErrorInfo", "reason": "SERVICE_DISABLED"}],
+        }}),
+    );
+    api.command()
+        .args(["--output", "csv", "diagnose", "--project", PROJECT])
+        .assert()
+        .code(1)
+        .stdout(
+            predicate::str::contains("credentials,ok,GCE metadata server")
+                .and(predicate::str::contains("token,ok"))
+                .and(predicate::str::contains("compute api,FAIL"))
+                .and(predicate::str::contains(
+                    "gcloud services enable compute.googleapis.com",
+                ))
+                .and(predicate::str::contains("gcloud,warn")),
+        )
+        .stderr(predicate::str::contains("1 failed"));
+    Ok(())
+}
+
+#[test]
+fn injected_access_tokens_can_impersonate_a_service_account() -> TestResult {
+    let api = MockApi::start();
+    let account = "deploy@test-project.iam.gserviceaccount.com";
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": [instance("web-1", "RUNNING")]}),
+    );
+    api.route(
+        "POST",
+        &format!("/v1/projects/-/serviceAccounts/{account}:generateAccessToken"),
+        200,
+        json!({"accessToken": "impersonated", "expireTime": "2099-01-01T00:00:00Z"}),
+    );
+    let list = ["instances", "list", "--project", PROJECT, "--zone", ZONE];
+    api.command()
+        .env("GCECTL_ACCESS_TOKEN", "injected")
+        .args(list)
+        .assert()
+        .success();
+    api.command()
+        .env("GCECTL_ACCESS_TOKEN", "injected")
+        .env("CLOUDSDK_API_ENDPOINT_OVERRIDES_IAMCREDENTIALS", api.url())
+        .args(["--impersonate-service-account", account, "--no-cache"])
+        .args(list)
+        .assert()
+        .success();
+    let tokens: Vec<Option<String>> = api
+        .requests()
+        .into_iter()
+        .map(|r| r.authorization)
+        .collect();
+    assert_eq!(
+        tokens,
+        [
+            Some("Bearer injected".to_string()),
+            Some("Bearer impersonated".to_string())
+        ]
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn run_prefixes_output_and_reports_failed_instances() -> TestResult {
+    use std::os::unix::fs::PermissionsExt;
+
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": [
+            instance("web-1", "RUNNING"),
+            instance("web-22", "RUNNING"),
+            instance("web-3", "TERMINATED"),
+            instance("db", "RUNNING"),
+        ]}),
+    );
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}"),
+        json!({"name": PROJECT}),
+    );
+    // a stand-in ssh that echoes its last two arguments, the destination
+    // and the command, and fails any command but `uptime`
+    let bin = tempfile::tempdir()?;
+    let ssh = bin.path().join("ssh");
+    std::fs::write(
+        &ssh,
+        "#!/bin/sh\nfor arg; do last=$prev; prev=$arg; done\n\
+         echo \"ran $prev on $last\"\n[ \"$prev\" = uptime ] || exit 3\n",
+    )?;
+    std::fs::set_permissions(&ssh, std::fs::Permissions::from_mode(0o755))?;
+    let path = format!("{}:{}", bin.path().display(), std::env::var("PATH")?);
+
+    api.command()
+        .env("PATH", &path)
+        .env("USER", "alice")
+        .args(["run", "web-*", "--project", PROJECT, "--zone", ZONE])
+        .args(["--internal-ip", "--", "uptime"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("web-1  | ran uptime on alice@10.0.0.2")
+                .and(predicate::str::contains("web-22 | ran uptime"))
+                .and(predicate::str::contains("db").not()),
+        )
+        .stderr(predicate::str::contains("skipping web-3: it is TERMINATED"));
+    api.command()
+        .env("PATH", &path)
+        .args([
+            "run",
+            "db",
+            "--user",
+            "bob",
+            "--project",
+            PROJECT,
+            "--zone",
+            ZONE,
+        ])
+        .args(["--internal-ip", "--", "false"])
+        .assert()
+        .code(1)
+        .stderr(
+            predicate::str::contains("db: exit 3")
+                .and(predicate::str::contains("failed on 1 of 1 instance(s)")),
+        );
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn run_takes_os_login_as_disabled_when_the_project_is_unreadable() -> TestResult {
+    use std::os::unix::fs::PermissionsExt;
+
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": [instance("web-1", "RUNNING")]}),
+    );
+    api.route(
+        "GET",
+        &format!("/compute/v1/projects/{PROJECT}"),
+        403,
+        json!({"error": {"code": 403, "message": "Required 'compute.projects.get' permission"}}),
+    );
+    let bin = tempfile::tempdir()?;
+    let ssh = bin.path().join("ssh");
+    std::fs::write(
+        &ssh,
+        "#!/bin/sh
+for arg; do last=$prev; prev=$arg; done
+echo \"on $last\"\n",
+    )?;
+    std::fs::set_permissions(&ssh, std::fs::Permissions::from_mode(0o755))?;
+    let path = format!("{}:{}", bin.path().display(), std::env::var("PATH")?);
+
+    api.command()
+        .env("PATH", &path)
+        .env("USER", "alice")
+        .args(["run", "web-1", "--project", PROJECT, "--zone", ZONE])
+        .args(["--internal-ip", "--", "uptime"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("on alice@10.0.0.2"));
+    Ok(())
+}
+
+#[test]
+fn assign_ip_restores_the_old_config_when_the_new_one_fails() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/regions/us-central1/addresses/web-ip"),
+        json!({"name": "web-ip", "address": "34.1.2.3", "addressType": "EXTERNAL", "networkTier": "STANDARD"}),
+    );
+    let mut web = instance("web-1", "RUNNING");
+    web["networkInterfaces"] = json!([{
+        "name": "nic0",
+        "accessConfigs": [{"name": "External NAT", "natIP": "35.9.9.9", "type": "ONE_TO_ONE_NAT", "networkTier": "STANDARD"}],
+    }]);
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances/web-1"),
+        web,
+    );
+    api.operation("POST", "instances/web-1/deleteAccessConfig");
+    api.route(
+        "POST",
+        &format!("/compute/v1/projects/{PROJECT}/zones/{ZONE}/instances/web-1/addAccessConfig"),
+        400,
+        json!({"error": {"code": 400, "message": "Invalid value for field 'resource.natIP'"}}),
+    );
+
+    api.command()
+        .args(["instances", "assign-ip", "web-1", "--address", "web-ip"])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "instance web-1 was left without access config External NAT (35.9.9.9)",
+        ));
+
+    let adds: Vec<_> = api
+        .requests()
+        .into_iter()
+        .filter(|r| r.path.ends_with("/addAccessConfig"))
+        .collect();
+    assert_eq!(adds.len(), 2, "the assignment, then the restore");
+    assert_eq!(adds[0].body["natIP"], "34.1.2.3");
+    assert_eq!(adds[0].body["networkTier"], "STANDARD");
+    assert_eq!(adds[1].body["natIP"], "35.9.9.9");
+    Ok(())
+}
+
+#[test]
+fn move_snapshots_disks_and_carries_the_static_ip() -> TestResult {
+    let api = MockApi::start();
+    let disk_url = format!(
+        "{}/compute/v1/projects/{PROJECT}/zones/{ZONE}/disks/web-1",
+        api.url()
+    );
+    let mut web = instance("web-1", "RUNNING");
+    web["disks"] = json!([{"source": disk_url, "boot": true, "autoDelete": true}]);
+    web["networkInterfaces"] = json!([{
+        "name": "nic0",
+        "accessConfigs": [{"name": "External NAT", "natIP": "34.1.2.3"}],
+    }]);
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances/web-1"),
+        web,
+    );
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/disks/web-1"),
+        json!({"name": "web-1", "type": "pd-balanced", "sizeGb": "10", "selfLink": disk_url}),
+    );
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/regions/us-central1/addresses"),
+        json!({"items": [{"name": "web-ip", "address": "34.1.2.3", "status": "IN_USE"}]}),
+    );
+    api.operation("POST", "instances/web-1/stop");
+    api.operation("POST", "disks/web-1/createSnapshot");
+    api.operation("POST", "instances/web-1/deleteAccessConfig");
+    api.compute(
+        "POST",
+        &format!("projects/{PROJECT}/zones/us-central1-b/instances"),
+        json!({"name": "operation-2", "status": "DONE"}),
+    );
+    api.command()
+        .args([
+            "instances",
+            "move",
+            "web-1",
+            "--destination-zone",
+            "us-central1-b",
+            "-y",
+        ])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2. snapshot disk web-1 as web-1-"))
+        .stdout(predicate::str::contains(
+            "Instance web-1 moved to us-central1-b",
+        ));
+    let requests = api.requests();
+    let writes: Vec<&str> = requests
+        .iter()
+        .filter(|r| r.method == "POST")
+        .map(|r| r.path.rsplit('/').next().unwrap_or_default())
+        .collect();
+    assert_eq!(
+        writes,
+        ["stop", "createSnapshot", "deleteAccessConfig", "instances"]
+    );
+    let insert = requests.last().ok_or("no insert")?;
+    let params = &insert.body["disks"][0]["initializeParams"];
+    assert!(
+        params["sourceSnapshot"]
+            .as_str()
+            .is_some_and(|s| s.contains("/snapshots/web-1-"))
+    );
+    assert_eq!(
+        insert.body["networkInterfaces"][0]["accessConfigs"][0]["natIP"],
+        "34.1.2.3"
+    );
+    Ok(())
+}
+
+#[test]
+fn cost_estimate_charges_shared_disks_once_and_stopped_instances_for_disks() -> TestResult {
+    let api = MockApi::start();
+    let data =
+        format!("https://www.googleapis.com/compute/v1/projects/{PROJECT}/zones/{ZONE}/disks/data");
+    let mut web = instance("web-1", "RUNNING");
+    let mut db = instance("db", "TERMINATED");
+    for vm in [&mut web, &mut db] {
+        vm["disks"] = json!([{"source": data.clone(), "mode": "READ_ONLY"}]);
+    }
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": [web, db]}),
+    )
+    .compute(
+        "GET",
+        &format!("projects/{PROJECT}/aggregated/disks"),
+        json!({"items": {format!("zones/{ZONE}"): {"disks": [{
+            "name": "data",
+            "selfLink": data,
+            "sizeGb": "10",
+            "type": format!("projects/{PROJECT}/zones/{ZONE}/diskTypes/pd-ssd"),
+        }]}}}),
+    );
+    let assert = api
+        .command()
+        .args(["--output", "json", "cost", "estimate"])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success();
+    let estimates: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(estimates[0]["name"], "web-1");
+    assert!(
+        estimates[0]["disks"]
+            .as_f64()
+            .is_some_and(|d| (d - 1.7).abs() < 0.01)
+    );
+    assert_eq!(estimates[1]["compute"], 0.0);
+    assert_eq!(estimates[1]["disks"], 0.0);
+    Ok(())
+}
+
+#[test]
+fn cost_actual_waits_for_the_billing_query_and_totals_it() -> TestResult {
+    let api = MockApi::start();
+    let queries = "/bigquery/v2/projects/billing-admin/queries";
+    api.route(
+        "POST",
+        queries,
+        200,
+        json!({"jobComplete": false, "jobReference": {"jobId": "job-1", "location": "US"}}),
+    )
+    .route(
+        "GET",
+        &format!("{queries}/job-1"),
+        200,
+        json!({
+            "jobComplete": true,
+            "schema": {"fields": [{"name": "key"}, {"name": "cost"}, {"name": "credits"}, {"name": "currency"}]},
+            "rows": [
+                {"f": [{"v": "web-1"}, {"v": "30.5"}, {"v": "-4.5"}, {"v": "USD"}]},
+                {"f": [{"v": "web-2"}, {"v": "10"}, {"v": "0"}, {"v": "USD"}]},
+            ],
+        }),
+    );
+    api.command()
+        .env("CLOUDSDK_API_ENDPOINT_OVERRIDES_BIGQUERY", api.url())
+        .args(["--output", "csv", "cost", "actual", "--project", PROJECT])
+        .args([
+            "--table",
+            "billing-admin.exports.gcp_billing_export_resource_v1",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("web-1,30.50,-4.50,26.00,USD"))
+        .stderr(predicate::str::contains(
+            "Billed over the last 7d: 36.00 USD net of credits for 2 instance(s)",
+        ));
+    Ok(())
+}
+
+#[test]
+fn dns_register_creates_a_record_in_the_zone_serving_the_domain() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances/web-1"),
+        instance("web-1", "RUNNING"),
+    )
+    .route(
+        "GET",
+        &format!("/dns/v1/projects/{PROJECT}/managedZones"),
+        200,
+        json!({"managedZones": [
+            {"name": "example", "dnsName": "example.com.", "visibility": "private"},
+            {"name": "dev", "dnsName": "dev.example.com.", "visibility": "private"},
+        ]}),
+    )
+    // no record yet, so the rrsets GET falls through to a 404
+    .route(
+        "POST",
+        &format!("/dns/v1/projects/{PROJECT}/managedZones/dev/rrsets"),
+        200,
+        json!({"name": "web-1.dev.example.com.", "type": "A", "ttl": 300, "rrdatas": ["10.0.0.2"]}),
+    );
+    api.command()
+        .env("CLOUDSDK_API_ENDPOINT_OVERRIDES_DNS", api.url())
+        .args(["dns", "register", "web-1", "--domain", "dev.example.com"])
+        .args(["--internal", "--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "web-1.dev.example.com. now points at 10.0.0.2",
+        ));
+    Ok(())
+}
+
+#[test]
+fn logs_prints_the_newest_entries_of_the_instance_oldest_first() -> TestResult {
+    let api = MockApi::start();
+    let mut web = instance("web-1", "RUNNING");
+    web["id"] = json!("4242");
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances/web-1"),
+        web,
+    )
+    .route(
+        "POST",
+        "/v2/entries:list",
+        200,
+        json!({"entries": [
+            {"logName": format!("projects/{PROJECT}/logs/syslog"), "timestamp": "2026-10-14T12:00:02Z",
+             "severity": "ERROR", "textPayload": "out of memory\n"},
+            {"logName": format!("projects/{PROJECT}/logs/syslog"), "timestamp": "2026-10-14T12:00:01Z",
+             "jsonPayload": {"message": "booted"}},
+        ]}),
+    );
+    api.command()
+        .env("CLOUDSDK_API_ENDPOINT_OVERRIDES_LOGGING", api.url())
+        .args(["logs", "web-1", "--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success()
+        .stdout(
+            "2026-10-14T12:00:01Z DEFAULT  syslog: booted\n\
+             2026-10-14T12:00:02Z ERROR    syslog: out of memory\n",
+        );
+    Ok(())
+}
+
+#[test]
+fn bake_images_the_builder_and_deletes_it() -> TestResult {
+    let api = MockApi::start();
+    let dir = tempfile::tempdir()?;
+    let file = dir.path().join("bake.yaml");
+    std::fs::write(
+        &file,
+        format!("zone: {ZONE}\nbuilds:\n  - family: web\n    labels:\n      role: web\n"),
+    )?;
+    api.operation("POST", "instances")
+        .operation("POST", "instances/bake-web-v1/stop")
+        .operation("DELETE", "instances/bake-web-v1")
+        .compute(
+            "POST",
+            &format!("projects/{PROJECT}/global/images"),
+            json!({"name": "operation-2", "status": "DONE"}),
+        );
+    api.command()
+        .args(["bake", "-f"])
+        .arg(&file)
+        .args(["--version", "v1", "--project", PROJECT])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("web-v1"));
+    let requests = api.requests();
+    let paths: Vec<(&str, &str)> = requests
+        .iter()
+        .map(|r| {
+            (
+                r.method.as_str(),
+                r.path.rsplit('/').next().unwrap_or_default(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            ("POST", "instances"),
+            ("POST", "stop"),
+            ("POST", "images"),
+            ("DELETE", "bake-web-v1")
+        ]
+    );
+    let image = &requests[2].body;
+    assert_eq!(image["family"], "web");
+    assert_eq!(image["labels"]["role"], "web");
+    assert_eq!(
+        image["sourceDisk"],
+        format!("projects/{PROJECT}/zones/{ZONE}/disks/bake-web-v1")
+    );
+    Ok(())
+}
+
+#[test]
+fn notify_runs_the_configured_hooks_when_an_operation_ends() -> TestResult {
+    let api = MockApi::start();
+    let dir = tempfile::tempdir()?;
+    let out = dir.path().join("notified");
+    api.config(&format!(
+        "[notify]\ncommand = 'echo \"$GCECTL_NOTIFY_STATUS $GCECTL_NOTIFY_MESSAGE\" > {}'\n\
+         slack_webhook = '{}/slack'\n",
+        out.display(),
+        api.url()
+    ))
+    .route("POST", "/slack", 200, json!({}))
+    .operation("POST", "instances/web-1/stop");
+    api.command()
+        .args(["instances", "stop", "web-1", "--notify"])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("notification not sent").not());
+    let notified = std::fs::read_to_string(&out)?;
+    assert!(notified.starts_with("succeeded `gcectl instances stop web-1 --notify"));
+    assert!(notified.contains("` finished in "));
+    Ok(())
+}
+
+#[test]
+fn notify_names_the_shell_line_and_skips_dry_runs() -> TestResult {
+    let api = MockApi::start();
+    let dir = tempfile::tempdir()?;
+    let out = dir.path().join("notified");
+    api.config(&format!(
+        "[notify]\ncommand = 'echo \"$GCECTL_NOTIFY_COMMAND\" >> {}'\n",
+        out.display()
+    ))
+    .operation("POST", "instances/web-1/stop");
+    api.command()
+        .env("GCECTL_PROJECT", PROJECT)
+        .arg("shell")
+        .write_stdin(format!(
+            "instances stop web-1 --zone {ZONE} --notify --dry-run\n\
+             instances stop web-1 --zone {ZONE} --notify\n"
+        ))
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_to_string(&out)?,
+        format!("gcectl instances stop web-1 --zone {ZONE} --notify\n")
+    );
+    Ok(())
+}
+
+#[test]
+fn shell_runs_each_line_and_survives_failures() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": [instance("web-1", "RUNNING")]}),
+    );
+    api.command()
+        .env("GCECTL_PROJECT", PROJECT)
+        .arg("shell")
+        .write_stdin(format!(
+            "instances lisst\n\
+             instances list --zone {ZONE} --columns name\n\
+             # listings as CSV\n\
+             instances list --zone {ZONE} -o csv\n\
+             exit\n\
+             instances list --zone {ZONE}\n"
+        ))
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Name,Zone,").and(predicate::str::contains("\nweb-1,")))
+        .stderr(predicate::str::contains("unrecognized subcommand 'lisst'"))
+        .stderr(predicate::str::contains(
+            "--columns holds for the whole shell",
+        ));
+    assert_eq!(api.requests().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn update_builds_the_body_from_the_instance_once_stopped() -> TestResult {
+    let api = MockApi::start();
+    let web_1 = format!("/compute/v1/projects/{PROJECT}/zones/{ZONE}/instances/web-1");
+    let mut web = instance("web-1", "RUNNING");
+    web["fingerprint"] = json!("after-stop");
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances/web-1"),
+        web,
+    )
+    .operation("POST", "instances/web-1/stop")
+    .operation("PUT", "instances/web-1")
+    .operation("POST", "instances/web-1/start");
+    api.command()
+        .args([
+            "instances",
+            "update",
+            "web-1",
+            "--shielded-secure-boot",
+            "-y",
+        ])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success();
+    let calls: Vec<String> = api
+        .requests()
+        .iter()
+        .filter(|r| r.path.starts_with(&web_1))
+        .map(|r| format!("{} {}", r.method, &r.path[web_1.len()..]))
+        .collect();
+    assert_eq!(calls, ["GET ", "POST /stop", "GET ", "PUT ", "POST /start"]);
+    let put = api
+        .requests()
+        .into_iter()
+        .find(|r| r.method == "PUT")
+        .expect("a PUT");
+    assert_eq!(put.body["fingerprint"], "after-stop");
+    assert_eq!(put.body["shieldedInstanceConfig"]["enableSecureBoot"], true);
+    Ok(())
+}
+
+#[test]
+fn set_scheduling_changes_maintenance_without_stopping() -> TestResult {
+    let api = MockApi::start();
+    let mut web = instance("web-1", "RUNNING");
+    web["scheduling"] = json!({"onHostMaintenance": "MIGRATE", "automaticRestart": true});
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances/web-1"),
+        web,
+    )
+    .operation("POST", "instances/web-1/setScheduling");
+    api.command()
+        .args(["instances", "set-scheduling", "web-1"])
+        .args([
+            "--maintenance-policy",
+            "TERMINATE",
+            "--no-restart-on-failure",
+        ])
+        .args(["--project", PROJECT, "--zone", ZONE])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "now runs STANDARD, TERMINATE on maintenance, no automatic restart",
+        ));
+    let requests = api.requests();
+    let posts: Vec<_> = requests.iter().filter(|r| r.method == "POST").collect();
+    assert_eq!(posts.len(), 1, "no stop or start");
+    assert_eq!(
+        posts[0].body,
+        json!({"onHostMaintenance": "TERMINATE", "automaticRestart": false})
+    );
+    api.command()
+        .args([
+            "instances",
+            "set-scheduling",
+            "web-1",
+            "--provisioning-model",
+            "spot",
+        ])
+        .args([
+            "--maintenance-policy",
+            "migrate",
+            "--project",
+            PROJECT,
+            "--zone",
+            ZONE,
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot live-migrate"));
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+#[test]
+fn tokens_are_reused_from_the_keyring_across_runs() -> TestResult {
+    use std::os::unix::fs::PermissionsExt;
+
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": []}),
+    )
+    .route(
+        "POST",
+        "/token",
+        200,
+        json!({"access_token": "federated", "expires_in": 3600}),
+    );
+    let dir = tempfile::tempdir()?;
+    // a Secret Service that keeps each secret in a file named after its account
+    let fake = dir.path().join("secret-tool");
+    std::fs::write(
+        &fake,
+        "#!/bin/sh\nstore=\"$(dirname \"$0\")/secrets\"\nmkdir -p \"$store\"\n\
+         eval account=\\${$#}\ncase \"$1\" in\n\
+         store) cat > \"$store/$account\" ;;\n\
+         lookup) cat \"$store/$account\" 2>/dev/null ;;\n\
+         clear) rm -f \"$store/$account\" ;;\nesac\n",
+    )?;
+    std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755))?;
+    std::fs::write(dir.path().join("oidc"), "subject-token")?;
+    let credentials = dir.path().join("credentials.json");
+    std::fs::write(
+        &credentials,
+        json!({
+            "type": "external_account",
+            "audience": "//iam.googleapis.com/projects/1/locations/global/workloadIdentityPools/ci/providers/oidc",
+            "subject_token_type": "urn:ietf:params:oauth:token-type:jwt",
+            "token_url": format!("{}/token", api.url()),
+            "credential_source": {"file": dir.path().join("oidc")},
+        })
+        .to_string(),
+    )?;
+    let path = format!(
+        "{}:{}",
+        dir.path().display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let list = |extra: &[&str]| {
+        api.command()
+            .env("PATH", &path)
+            .env("GOOGLE_APPLICATION_CREDENTIALS", &credentials)
+            .args([
+                "instances",
+                "list",
+                "--no-cache",
+                "--project",
+                PROJECT,
+                "--zone",
+                ZONE,
+            ])
+            .args(extra)
+            .assert()
+            .success();
+    };
+    list(&[]);
+    list(&[]);
+    assert_eq!(
+        api.hits("POST", "/token"),
+        1,
+        "the second run reused the token"
+    );
+    let stored = std::fs::read_dir(dir.path().join("secrets"))?.count();
+    assert_eq!(stored, 1);
+    list(&["--no-keyring"]);
+    assert_eq!(api.hits("POST", "/token"), 2);
+    assert!(
+        api.requests()
+            .iter()
+            .all(|r| r.authorization.as_deref() == Some("Bearer federated"))
+    );
+    Ok(())
+}
+
+#[test]
+fn placement_suggest_ranks_zones_by_offering_and_stockouts() -> TestResult {
+    let api = MockApi::start();
+    let zone = |name: &str| format!("{}/compute/v1/projects/{PROJECT}/zones/{name}", api.url());
+    let machine_type = |name: &str| {
+        json!({
+            "name": "a2-highgpu-1g",
+            "guestCpus": 12,
+            "zone": zone(name),
+            "accelerators": [{"guestAcceleratorType": "nvidia-tesla-a100", "guestAcceleratorCount": 1}],
+        })
+    };
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/regions/us-central1"),
+        json!({
+            "name": "us-central1",
+            "zones": [zone("us-central1-a"), zone("us-central1-b"), zone("us-central1-c")],
+            "quotas": [
+                {"metric": "A2_CPUS", "limit": 48.0, "usage": 12.0},
+                {"metric": "NVIDIA_A100_GPUS", "limit": 16.0, "usage": 0.0},
+            ],
+        }),
+    )
+    .compute(
+        "GET",
+        &format!("projects/{PROJECT}/aggregated/machineTypes"),
+        json!({"items": {
+            "zones/us-central1-a": {"machineTypes": [machine_type("us-central1-a")]},
+            "zones/us-central1-b": {"machineTypes": [machine_type("us-central1-b")]},
+        }}),
+    );
+    let stockout = json!({
+        "time": chrono::Utc::now() - chrono::Duration::hours(3),
+        "event": "operation",
+        "user": "ci",
+        "command": "instances create gpu-1",
+        "action": "insert",
+        "target": format!("projects/{PROJECT}/zones/us-central1-a/instances/gpu-1"),
+        "project": PROJECT,
+        "location": "us-central1-a",
+        "result": "failed: ZONE_RESOURCE_POOL_EXHAUSTED: no capacity",
+    });
+    api.file("config/audit.jsonl", &format!("{stockout}\n"));
+    let output = api
+        .command()
+        .args(["placement", "suggest", "--machine-type", "a2-highgpu-1g"])
+        .args([
+            "--count",
+            "4",
+            "--project",
+            PROJECT,
+            "--region",
+            "us-central1",
+        ])
+        .args(["--output", "json"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("leaves room for 3 instance(s)"))
+        .get_output()
+        .stdout
+        .clone();
+    let placements: serde_json::Value = serde_json::from_slice(&output)?;
+    let ranked: Vec<(&str, &serde_json::Value, u64)> = placements
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| {
+            (
+                p["zone"].as_str().unwrap(),
+                &p["rank"],
+                p["stockouts"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        ranked,
+        [
+            ("us-central1-b", &json!(1), 0),
+            ("us-central1-a", &json!(2), 1),
+            ("us-central1-c", &json!(null), 0),
+        ]
+    );
+    assert_eq!(placements[0]["headroom"], 3);
+    Ok(())
+}
+
+#[test]
+fn replays_a_recorded_run_without_the_api() -> TestResult {
+    let api = MockApi::start();
+    api.compute(
+        "GET",
+        &format!("projects/{PROJECT}/zones/{ZONE}/instances"),
+        json!({"items": [instance("web-1", "RUNNING")]}),
+    );
+    let fixtures = tempfile::tempdir()?;
+    let list = |api: &MockApi, mode: &str| {
+        api.command()
+            .args(["instances", "list", "--project", PROJECT, "--zone", ZONE])
+            .arg(mode)
+            .arg(fixtures.path())
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone()
+    };
+    let recorded = list(&api, "--record");
+    let saved = std::fs::read_dir(fixtures.path())?
+        .map(|entry| std::fs::read_to_string(entry?.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(saved.len(), 1, "the token exchange is left out");
+    assert!(!saved[0].contains("test-token"));
+
+    // nothing answers this one, not even the metadata server
+    let offline = MockApi::start();
+    let replayed = list(&offline, "--replay");
+    assert_eq!(replayed, recorded);
+    assert!(String::from_utf8(replayed)?.contains("web-1"));
+    assert_eq!(offline.hits("GET", "/computeMetadata/v1/"), 0);
+    assert!(offline.requests().is_empty());
+    Ok(())
+}
@@ -0,0 +1,233 @@
+//! A local stand-in for the Compute Engine API and the GCE metadata server.
+//!
+//! [`MockApi`] answers canned JSON for registered `(method, path)` routes,
+//! hands out access tokens the way the metadata server does, and records
+//! every request so tests can assert on what gcectl sent. Point gcectl at it
+//! with [`MockApi::command`].
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use assert_cmd::Command;
+use serde_json::{Value, json};
+use tempfile::TempDir;
+
+pub const PROJECT: &str = "test-project";
+pub const ZONE: &str = "us-central1-a";
+
+/// A request the mock received.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    // path without the query string
+    pub path: String,
+    pub query: String,
+    pub body: Value,
+    pub authorization: Option<String>,
+}
+
+#[derive(Default)]
+struct State {
+    routes: HashMap<(String, String), (u16, Value)>,
+    requests: Vec<Request>,
+}
+
+pub struct MockApi {
+    addr: String,
+    state: Arc<Mutex<State>>,
+    // config, cache, and audit log of the gcectl runs under test
+    home: TempDir,
+}
+
+impl MockApi {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let addr = listener.local_addr().unwrap().to_string();
+        let state = Arc::new(Mutex::new(State::default()));
+        let shared = Arc::clone(&state);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = Arc::clone(&shared);
+                thread::spawn(move || serve(stream, &state));
+            }
+        });
+        Self {
+            addr,
+            state,
+            home: tempfile::tempdir().expect("temp dir"),
+        }
+    }
+
+    /// Answers `method path` with `status` and `body`.
+    pub fn route(&self, method: &str, path: &str, status: u16, body: Value) -> &Self {
+        self.state
+            .lock()
+            .unwrap()
+            .routes
+            .insert((method.to_string(), path.to_string()), (status, body));
+        self
+    }
+
+    /// Answers a Compute Engine `path` below `compute/v1/`.
+    pub fn compute(&self, method: &str, path: &str, body: Value) -> &Self {
+        self.route(method, &format!("/compute/v1/{path}"), 200, body)
+    }
+
+    /// Answers a zonal write on `target` with a finished operation.
+    pub fn operation(&self, method: &str, target: &str) -> &Self {
+        let body = json!({
+            "name": "operation-1",
+            "status": "DONE",
+            "targetLink": format!("{}/compute/v1/projects/{PROJECT}/zones/{ZONE}/{target}", self.url()),
+            "selfLink": format!("{}/compute/v1/projects/{PROJECT}/zones/{ZONE}/operations/operation-1", self.url()),
+        });
+        self.compute(
+            method,
+            &format!("projects/{PROJECT}/zones/{ZONE}/{target}"),
+            body,
+        )
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Writes the config file the `command` gets.
+    pub fn config(&self, toml: &str) -> &Self {
+        self.file("config/config.toml", toml)
+    }
+
+    /// Writes `path`, relative to the home directory of the `command`.
+    pub fn file(&self, path: &str, contents: &str) -> &Self {
+        let path = self.home.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).expect("home subdirectory");
+        std::fs::write(path, contents).expect("home file");
+        self
+    }
+
+    /// Every Compute Engine request received so far, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|r| r.path.starts_with("/compute/"))
+            .cloned()
+            .collect()
+    }
+
+    /// How many `method path` requests arrived, whatever the API.
+    pub fn hits(&self, method: &str, path: &str) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|r| r.method == method && r.path == path)
+            .count()
+    }
+
+    /// The `gcectl` binary wired to this mock, with its own config and
+    /// cache directories and the test project and zone.
+    pub fn command(&self) -> Command {
+        let mut cmd = Command::cargo_bin("gcectl").expect("gcectl binary");
+        let home = self.home.path();
+        cmd.env_clear()
+            .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+            .env("HOME", home)
+            .env("CLOUDSDK_CONFIG", home.join("gcloud"))
+            .env("GCECTL_CONFIG_DIR", home.join("config"))
+            .env("GCECTL_CACHE_DIR", home.join("cache"))
+            .env("GCE_METADATA_HOST", &self.addr)
+            .env("GCECTL_API_ENDPOINT", format!("{}/compute/v1", self.url()))
+            .env("GCECTL_RETRIES", "0");
+        cmd
+    }
+}
+
+/// Instance JSON as the API returns it.
+pub fn instance(name: &str, status: &str) -> Value {
+    json!({
+        "name": name,
+        "status": status,
+        "zone": format!("https://www.googleapis.com/compute/v1/projects/{PROJECT}/zones/{ZONE}"),
+        "machineType": format!("https://www.googleapis.com/compute/v1/projects/{PROJECT}/zones/{ZONE}/machineTypes/e2-medium"),
+        "networkInterfaces": [{"networkIP": "10.0.0.2"}],
+    })
+}
+
+fn serve(stream: TcpStream, state: &Mutex<State>) {
+    let mut reader = BufReader::new(&stream);
+    let mut line = String::new();
+    if reader.read_line(&mut line).is_err() {
+        return;
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+    let mut length = 0;
+    let mut authorization = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).is_err() || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            length = value.trim().parse().unwrap_or(0);
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("authorization")
+        {
+            authorization = Some(value.trim().to_string());
+        }
+    }
+    let mut body = vec![0; length];
+    if reader.read_exact(&mut body).is_err() {
+        return;
+    }
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let request = Request {
+        method: method.clone(),
+        path: path.to_string(),
+        query: query.to_string(),
+        body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+        authorization,
+    };
+
+    let (status, body) = match (method.as_str(), path) {
+        ("GET", "/computeMetadata/v1/") => (200, json!({})),
+        ("GET", "/computeMetadata/v1/instance/service-accounts/default/token") => (
+            200,
+            json!({"access_token": "test-token", "expires_in": 3600}),
+        ),
+        _ => {
+            let state = state.lock().unwrap();
+            state
+                .routes
+                .get(&(method.clone(), path.to_string()))
+                .cloned()
+                .unwrap_or_else(|| {
+                    (
+                        404,
+                        json!({"error": {"message": format!("no mock route for {method} {path}")}}),
+                    )
+                })
+        }
+    };
+    state.lock().unwrap().requests.push(request);
+
+    let body = body.to_string();
+    let _ = write!(
+        &stream,
+        "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\nMetadata-Flavor: Google\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = (&stream).flush();
+}